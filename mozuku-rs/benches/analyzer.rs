@@ -0,0 +1,38 @@
+//! Benchmarks of the morphological analysis
+//!
+//! Run with `cargo bench --bench analyzer`.
+
+mod corpus;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use mozuku_rs::analyzer::MorphologicalAnalyzer;
+use tower_lsp::lsp_types::Position;
+
+fn tokenize(c: &mut Criterion) {
+    let analyzer = MorphologicalAnalyzer::new().expect("failed to load the dictionary");
+    let mut group = c.benchmark_group("analyzer/tokenize");
+    for times in [1, 10, 100] {
+        let text = corpus::essay(times);
+        group.throughput(Throughput::Bytes(text.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(text.len()), &text, |b, text| {
+            b.iter(|| analyzer.tokenize(text))
+        });
+    }
+    group.finish();
+}
+
+fn token_features(c: &mut Criterion) {
+    let analyzer = MorphologicalAnalyzer::new().expect("failed to load the dictionary");
+    let text = corpus::essay(10);
+    let tokens = analyzer.tokenize(&text);
+
+    c.bench_function("analyzer/semantic_tokens", |b| {
+        b.iter(|| analyzer.semantic_tokens(&text, &tokens))
+    });
+    c.bench_function("analyzer/hover_info", |b| {
+        b.iter(|| analyzer.hover_info(&text, &tokens, Position::new(200, 10)))
+    });
+}
+
+criterion_group!(benches, tokenize, token_features);
+criterion_main!(benches);
@@ -0,0 +1,62 @@
+//! Benchmarks of the grammar checker, for all rules together and for each rule
+//!
+//! Tokens are computed once up front so that only the rules are measured.
+//! Run with `cargo bench --bench checker`.
+
+mod corpus;
+
+use std::sync::Arc;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use mozuku_rs::analyzer::MorphologicalAnalyzer;
+use mozuku_rs::checker::GrammarChecker;
+use mozuku_rs::config::CheckerConfig;
+use mozuku_rs::rules::RULES;
+
+/// Configuration enabling a single rule
+fn only(rule_id: &str) -> CheckerConfig {
+    CheckerConfig {
+        rules: RULES
+            .iter()
+            .map(|rule| (rule.id.to_string(), rule.id == rule_id))
+            .collect(),
+        ..CheckerConfig::default()
+    }
+}
+
+fn check(c: &mut Criterion) {
+    let analyzer = Arc::new(MorphologicalAnalyzer::new().expect("failed to load the dictionary"));
+    let checker = GrammarChecker::new(analyzer.clone());
+    let config = CheckerConfig::default();
+
+    let mut group = c.benchmark_group("checker/all");
+    for times in [1, 10, 100] {
+        let text = corpus::essay(times);
+        let tokens = analyzer.tokenize(&text);
+        group.throughput(Throughput::Bytes(text.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(text.len()), &text, |b, text| {
+            b.iter(|| checker.check_with_tokens(text, &tokens, &config))
+        });
+    }
+    group.finish();
+}
+
+fn check_each_rule(c: &mut Criterion) {
+    let analyzer = Arc::new(MorphologicalAnalyzer::new().expect("failed to load the dictionary"));
+    let checker = GrammarChecker::new(analyzer.clone());
+    let text = corpus::essay(10);
+    let tokens = analyzer.tokenize(&text);
+
+    let mut group = c.benchmark_group("checker/rule");
+    group.throughput(Throughput::Bytes(text.len() as u64));
+    for rule in RULES {
+        let config = only(rule.id);
+        group.bench_function(rule.id, |b| {
+            b.iter(|| checker.check_with_tokens(&text, &tokens, &config))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, check, check_each_rule);
+criterion_main!(benches);
@@ -0,0 +1,12 @@
+//! Japanese text shared by the benchmarks
+//!
+//! A technical document of about 2 KB written to exercise every rule:
+//! it contains ら抜き言葉, い抜き言葉, double particles and honorifics,
+//! redundant expressions, incomplete たり, chained の and katakana variants.
+
+const ESSAY: &str = include_str!("essay.txt");
+
+/// The essay repeated the given number of times
+pub fn essay(times: usize) -> String {
+    ESSAY.repeat(times)
+}
@@ -0,0 +1,88 @@
+//! Benchmarks of the text extraction for each supported language
+//!
+//! Each sample wraps the lines of the corpus in the comments and string
+//! literals of the language (paragraphs and headings for Markdown).
+//! Run with `cargo bench --bench extractor`.
+
+mod corpus;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use mozuku_rs::extractor::{FileType, TextExtractor};
+
+/// Source code of a file type embedding the given lines of Japanese text
+fn sample(file_type: FileType, text: &str) -> String {
+    text.lines()
+        .enumerate()
+        .map(|(i, line)| match file_type {
+            FileType::Markdown => format!("## 見出し{i}\n\n{line}\n\n```rust\nlet x = {i};\n```\n\n"),
+            FileType::Rust => {
+                format!("/// {line}\nfn f{i}() -> &'static str {{\n    // {line}\n    \"{line}\"\n}}\n\n")
+            }
+            FileType::Python => format!("def f{i}():\n    \"\"\"{line}\"\"\"\n    # {line}\n    return \"{line}\"\n\n"),
+            FileType::TypeScript | FileType::JavaScript => {
+                format!("/** {line} */\nfunction f{i}() {{\n  // {line}\n  return \"{line}\";\n}}\n\n")
+            }
+            FileType::C | FileType::Cpp => {
+                format!("/* {line} */\nconst char *f{i}(void) {{\n    // {line}\n    return \"{line}\";\n}}\n\n")
+            }
+            FileType::Go => format!("// {line}\nfunc f{i}() string {{\n\treturn \"{line}\"\n}}\n\n"),
+            FileType::LaTeX => format!("\\section{{見出し{i}}}\n{line}\n\n"),
+            FileType::PlainText => format!("{line}\n"),
+        })
+        .collect()
+}
+
+fn extract(c: &mut Criterion) {
+    let extractor = TextExtractor::new();
+    let text = corpus::essay(10);
+    let file_types = [
+        FileType::Markdown,
+        FileType::Rust,
+        FileType::Python,
+        FileType::TypeScript,
+        FileType::JavaScript,
+        FileType::C,
+        FileType::Cpp,
+        FileType::Go,
+        FileType::LaTeX,
+        FileType::PlainText,
+    ];
+
+    let mut group = c.benchmark_group("extractor/extract");
+    for file_type in file_types {
+        let source = sample(file_type, &text);
+        group.throughput(Throughput::Bytes(source.len() as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(format!("{:?}", file_type)),
+            &source,
+            |b, source| b.iter(|| extractor.extract(source, file_type).expect("extraction failed")),
+        );
+    }
+    group.finish();
+}
+
+fn extract_incremental(c: &mut Criterion) {
+    let extractor = TextExtractor::new();
+    let source = sample(FileType::Markdown, &corpus::essay(10));
+    let edited = source.replacen("起きれる", "起きられる", 1);
+
+    c.bench_function("extractor/extract_incremental/Markdown", |b| {
+        b.iter_batched(
+            || {
+                extractor
+                    .extract_incremental(&source, FileType::Markdown, None)
+                    .unwrap()
+                    .1
+            },
+            |previous| {
+                extractor
+                    .extract_incremental(&edited, FileType::Markdown, previous)
+                    .unwrap()
+            },
+            criterion::BatchSize::SmallInput,
+        )
+    });
+}
+
+criterion_group!(benches, extract, extract_incremental);
+criterion_main!(benches);
@@ -0,0 +1,576 @@
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+use lindera::dictionary::{load_dictionary, load_user_dictionary, DictionaryBuilder, Metadata, UserDictionary};
+use lindera::mode::Mode;
+use lindera::segmenter::Segmenter;
+use lindera::tokenizer::Tokenizer;
+use lsp_types::{Position, SemanticToken};
+use serde::Serialize;
+
+use crate::utf16::{utf16_len, LineIndex};
+
+/// Token information from morphological analysis
+#[derive(Debug, Clone, Serialize)]
+pub struct TokenInfo {
+    /// Surface form (表層形)
+    pub surface: String,
+    /// Part of speech (品詞)
+    pub pos: String,
+    /// Part of speech subcategory 1 (品詞細分類1)
+    pub pos_detail1: String,
+    /// Part of speech subcategory 2 (品詞細分類2)
+    pub pos_detail2: String,
+    /// Part of speech subcategory 3 (品詞細分類3)
+    pub pos_detail3: String,
+    /// Conjugation type (活用型)
+    pub conjugation_type: String,
+    /// Conjugation form (活用形)
+    pub conjugation_form: String,
+    /// Base form (基本形)
+    pub base_form: String,
+    /// Reading (読み)
+    pub reading: String,
+    /// Pronunciation (発音)
+    pub pronunciation: String,
+    /// Byte offset in text
+    pub byte_offset: usize,
+    /// Character offset in text
+    pub char_offset: usize,
+    /// Character length
+    pub char_length: usize,
+}
+
+impl TokenInfo {
+    /// Estimated memory used by the token, in bytes
+    pub fn memory_size(&self) -> usize {
+        let strings = [
+            &self.surface,
+            &self.pos,
+            &self.pos_detail1,
+            &self.pos_detail2,
+            &self.pos_detail3,
+            &self.conjugation_type,
+            &self.conjugation_form,
+            &self.base_form,
+            &self.reading,
+            &self.pronunciation,
+        ];
+        std::mem::size_of::<Self>() + strings.iter().map(|s| s.capacity()).sum::<usize>()
+    }
+}
+
+/// Sentence boundary information
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SentenceBoundary {
+    /// Start byte offset in text
+    pub start: usize,
+    /// End byte offset in text (exclusive)
+    pub end: usize,
+    /// Sentence content
+    pub text: String,
+}
+
+/// Split text into sentences at Japanese sentence-ending punctuation and newlines
+pub fn split_sentences(text: &str) -> Vec<SentenceBoundary> {
+    let mut sentences = Vec::new();
+    let mut start = 0;
+
+    for (i, c) in text.char_indices() {
+        if matches!(c, '。' | '！' | '？' | '!' | '?' | '\n') {
+            let end = i + c.len_utf8();
+            push_sentence(text, start, end, &mut sentences);
+            start = end;
+        }
+    }
+    push_sentence(text, start, text.len(), &mut sentences);
+
+    sentences
+}
+
+/// Push a sentence with surrounding whitespace trimmed, skipping empty ones
+fn push_sentence(text: &str, start: usize, end: usize, sentences: &mut Vec<SentenceBoundary>) {
+    let raw = &text[start..end];
+    let trimmed = raw.trim();
+    if trimmed.is_empty() {
+        return;
+    }
+
+    let leading = raw.len() - raw.trim_start().len();
+    sentences.push(SentenceBoundary {
+        start: start + leading,
+        end: start + leading + trimmed.len(),
+        text: trimmed.to_string(),
+    });
+}
+
+/// Split text into paragraphs separated by blank lines, returning byte ranges
+pub fn split_paragraphs(text: &str) -> Vec<Range<usize>> {
+    let mut paragraphs = Vec::new();
+    let mut start: Option<usize> = None;
+    let mut end = 0;
+    let mut offset = 0;
+
+    for line in text.split_inclusive('\n') {
+        if line.trim().is_empty() {
+            // Blank line closes the current paragraph
+            if let Some(start) = start.take() {
+                paragraphs.push(start..end);
+            }
+        } else {
+            if start.is_none() {
+                start = Some(offset);
+            }
+            end = offset + line.trim_end_matches(['\r', '\n']).len();
+        }
+        offset += line.len();
+    }
+
+    if let Some(start) = start {
+        paragraphs.push(start..end);
+    }
+
+    paragraphs
+}
+
+/// Group tokens into bunsetsu (文節), returning token index ranges.
+///
+/// A new bunsetsu starts at each independent word (自立語) unless it continues
+/// a prefix, a compound noun or a サ変 noun + する; function words (助詞, 助動詞,
+/// 記号, etc.) attach to the preceding bunsetsu.
+pub fn split_bunsetsu(tokens: &[TokenInfo]) -> Vec<Range<usize>> {
+    let mut bunsetsu = Vec::new();
+    let mut start = 0;
+
+    for i in 1..tokens.len() {
+        let prev = &tokens[i - 1];
+        let token = &tokens[i];
+
+        let starts_new = is_independent_word(token)
+            && prev.pos != "接頭詞"
+            && !(token.pos == "名詞" && prev.pos == "名詞")
+            && !(token.base_form == "する" && prev.pos_detail1 == "サ変接続");
+
+        if starts_new {
+            bunsetsu.push(start..i);
+            start = i;
+        }
+    }
+
+    if !tokens.is_empty() {
+        bunsetsu.push(start..tokens.len());
+    }
+
+    bunsetsu
+}
+
+/// Check whether a token is an independent word (自立語)
+fn is_independent_word(token: &TokenInfo) -> bool {
+    match token.pos.as_str() {
+        "名詞" | "動詞" | "形容詞" => token.pos_detail1 != "非自立" && token.pos_detail1 != "接尾",
+        "副詞" | "連体詞" | "接続詞" | "感動詞" | "接頭詞" => true,
+        _ => false,
+    }
+}
+
+/// Conjugate a verb given by its base form (基本形) into a conjugation form
+/// (活用形) of IPADIC, such as 食べられる in 連用形 → 食べられ.
+///
+/// Only 一段 verbs are supported, which is what corrections produce: ら抜き
+/// and い抜き words are fixed with the 一段 verbs 〜られる and 〜ている.
+pub fn conjugate(base_form: &str, conjugation_type: &str, conjugation_form: &str) -> Option<String> {
+    if !conjugation_type.starts_with("一段") {
+        return None;
+    }
+    let stem = base_form.strip_suffix('る')?;
+    let ending = match conjugation_form {
+        "基本形" => "る",
+        "未然形" | "連用形" => "",
+        "未然ウ接続" | "命令ｙｏ" => "よ",
+        "仮定形" => "れ",
+        "仮定縮約１" => "りゃ",
+        "命令ｒｏ" => "ろ",
+        "体言接続特殊" => "ん",
+        _ => return None,
+    };
+    Some(format!("{}{}", stem, ending))
+}
+
+/// Morphological analyzer using Lindera
+pub struct MorphologicalAnalyzer {
+    /// `None` in degraded mode, when the dictionary failed to load
+    tokenizer: Option<Tokenizer>,
+}
+
+impl MorphologicalAnalyzer {
+    pub fn new() -> Result<Self> {
+        Self::with_user_dictionary(None)
+    }
+
+    /// Create an analyzer that also knows the words of a user dictionary CSV.
+    ///
+    /// The binary dictionary written by `dict build` next to the CSV is
+    /// loaded instead when it is up to date, which skips compiling the CSV.
+    pub fn with_user_dictionary(path: Option<&Path>) -> Result<Self> {
+        let dictionary = load_dictionary("embedded://ipadic")?;
+        let user_dictionary = match path {
+            Some(path) => Some(Self::load_user_dictionary(path, &dictionary.metadata)?),
+            None => None,
+        };
+        let segmenter = Segmenter::new(Mode::Normal, dictionary, user_dictionary);
+        let tokenizer = Tokenizer::new(segmenter);
+        Ok(Self {
+            tokenizer: Some(tokenizer),
+        })
+    }
+
+    fn load_user_dictionary(path: &Path, metadata: &Metadata) -> Result<UserDictionary> {
+        let binary = compiled_user_dictionary_path(path);
+        let source = if is_up_to_date(&binary, path) {
+            tracing::debug!("Loading compiled user dictionary {}", binary.display());
+            &binary
+        } else {
+            path
+        };
+        let uri = source
+            .to_str()
+            .ok_or_else(|| anyhow!("Invalid user dictionary path: {}", source.display()))?;
+        load_user_dictionary(uri, metadata)
+            .map_err(|e| anyhow!("Failed to load user dictionary {}: {}", source.display(), e))
+    }
+
+    /// Compile a user dictionary CSV to the binary dictionary loaded by
+    /// [`Self::with_user_dictionary`], returning the path written
+    pub fn build_user_dictionary(path: &Path) -> Result<PathBuf> {
+        let dictionary = load_dictionary("embedded://ipadic")?;
+        let output = compiled_user_dictionary_path(path);
+        DictionaryBuilder::new(dictionary.metadata)
+            .build_user_dictionary(path, &output)
+            .map_err(|e| anyhow!("{}", e))
+            .with_context(|| format!("{} をコンパイルできません", path.display()))?;
+        Ok(output)
+    }
+
+    /// Analyzer for degraded mode. It produces no tokens, so only rules that
+    /// do not depend on morphological analysis report diagnostics.
+    pub fn degraded() -> Self {
+        Self { tokenizer: None }
+    }
+
+    /// Check whether morphological analysis is available
+    pub fn is_available(&self) -> bool {
+        self.tokenizer.is_some()
+    }
+
+    /// Tokenize text and return token information
+    pub fn tokenize(&self, text: &str) -> Vec<TokenInfo> {
+        let tokenizer = match &self.tokenizer {
+            Some(tokenizer) => tokenizer,
+            None => return Vec::new(),
+        };
+        let mut tokens = match tokenizer.tokenize(text) {
+            Ok(t) => t,
+            Err(_) => return Vec::new(),
+        };
+
+        let mut result = Vec::new();
+        let mut char_offset = 0;
+
+        for token in tokens.iter_mut() {
+            let surface = token.surface.as_ref().to_string();
+            let char_length = surface.chars().count();
+
+            // Parse token details (IPADIC format)
+            let details = token.details();
+
+            let token_info = TokenInfo {
+                surface: surface.clone(),
+                pos: details.first().map(|s| s.to_string()).unwrap_or_else(|| "*".to_string()),
+                pos_detail1: details.get(1).map(|s| s.to_string()).unwrap_or_else(|| "*".to_string()),
+                pos_detail2: details.get(2).map(|s| s.to_string()).unwrap_or_else(|| "*".to_string()),
+                pos_detail3: details.get(3).map(|s| s.to_string()).unwrap_or_else(|| "*".to_string()),
+                conjugation_type: details.get(4).map(|s| s.to_string()).unwrap_or_else(|| "*".to_string()),
+                conjugation_form: details.get(5).map(|s| s.to_string()).unwrap_or_else(|| "*".to_string()),
+                base_form: details.get(6).map(|s| s.to_string()).unwrap_or_else(|| surface.clone()),
+                reading: details.get(7).map(|s| s.to_string()).unwrap_or_default(),
+                pronunciation: details.get(8).map(|s| s.to_string()).unwrap_or_default(),
+                byte_offset: token.byte_start,
+                char_offset,
+                char_length,
+            };
+
+            result.push(token_info);
+            char_offset += char_length;
+        }
+
+        result
+    }
+
+    /// Get hover information for a position in the text
+    pub fn get_hover_info(&self, text: &str, position: Position) -> Option<String> {
+        self.hover_info(text, &self.tokenize(text), position)
+    }
+
+    /// Get hover information for a position from the tokens of the text
+    pub fn hover_info(&self, text: &str, tokens: &[TokenInfo], position: Position) -> Option<String> {
+        // Convert position to byte offset
+        if position.line as usize >= text.lines().count() {
+            return None;
+        }
+        let offset = LineIndex::new(text).offset(position);
+
+        // Find token at position
+        for token in tokens {
+            let token_end = token.byte_offset + token.surface.len();
+            if token.byte_offset <= offset && offset < token_end {
+                return Some(self.format_token_info(token));
+            }
+        }
+
+        None
+    }
+
+    /// Format token information for hover display
+    fn format_token_info(&self, token: &TokenInfo) -> String {
+        let mut info = format!("## {}\n\n", token.surface);
+        info.push_str(&format!("**品詞**: {}", token.pos));
+
+        if token.pos_detail1 != "*" {
+            info.push_str(&format!("-{}", token.pos_detail1));
+        }
+        if token.pos_detail2 != "*" {
+            info.push_str(&format!("-{}", token.pos_detail2));
+        }
+        if token.pos_detail3 != "*" {
+            info.push_str(&format!("-{}", token.pos_detail3));
+        }
+        info.push('\n');
+
+        if token.base_form != "*" && token.base_form != token.surface {
+            info.push_str(&format!("\n**基本形**: {}\n", token.base_form));
+        }
+
+        if token.conjugation_type != "*" {
+            info.push_str(&format!("**活用型**: {}\n", token.conjugation_type));
+        }
+
+        if token.conjugation_form != "*" {
+            info.push_str(&format!("**活用形**: {}\n", token.conjugation_form));
+        }
+
+        if !token.reading.is_empty() && token.reading != "*" {
+            info.push_str(&format!("\n**読み**: {}\n", token.reading));
+        }
+
+        info
+    }
+
+    /// Get semantic tokens for syntax highlighting
+    pub fn get_semantic_tokens(&self, text: &str) -> Vec<SemanticToken> {
+        self.semantic_tokens(text, &self.tokenize(text))
+    }
+
+    /// Get semantic tokens from the tokens of the text
+    pub fn semantic_tokens(&self, text: &str, tokens: &[TokenInfo]) -> Vec<SemanticToken> {
+        let mut semantic_tokens = Vec::new();
+
+        let index = LineIndex::new(text);
+        let mut prev_line = 0u32;
+        let mut prev_char = 0u32;
+
+        for token in tokens {
+            // Whitespace tokens may span lines and carry no highlighting
+            if token.surface.trim().is_empty() {
+                continue;
+            }
+
+            // Convert byte offset to line/column (UTF-16)
+            let Position { line, character: col } = index.position(token.byte_offset);
+
+            // Calculate delta from previous token
+            let delta_line = line - prev_line;
+            let delta_start = if delta_line == 0 {
+                col - prev_char
+            } else {
+                col
+            };
+
+            let token_type = self.pos_to_token_type(&token.pos);
+
+            semantic_tokens.push(SemanticToken {
+                delta_line,
+                delta_start,
+                length: utf16_len(&token.surface) as u32,
+                token_type,
+                token_modifiers_bitset: 0,
+            });
+
+            prev_line = line;
+            prev_char = col;
+        }
+
+        semantic_tokens
+    }
+
+    /// Map part of speech to semantic token type
+    fn pos_to_token_type(&self, pos: &str) -> u32 {
+        match pos {
+            "名詞" => 0,   // KEYWORD
+            "動詞" => 1,   // FUNCTION
+            "形容詞" => 2, // PROPERTY
+            "副詞" => 3,   // VARIABLE
+            "助詞" => 4,   // OPERATOR
+            "助動詞" => 5, // STRING
+            "接続詞" => 6, // NUMBER
+            _ => 7,        // COMMENT (その他)
+        }
+    }
+}
+
+/// Path of the binary dictionary compiled from a user dictionary CSV:
+/// the same path with the `.bin` extension
+pub fn compiled_user_dictionary_path(path: &Path) -> PathBuf {
+    path.with_extension("bin")
+}
+
+/// Check whether a compiled file exists and is not older than its source
+fn is_up_to_date(compiled: &Path, source: &Path) -> bool {
+    let modified = |path: &Path| std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok();
+    match (modified(compiled), modified(source)) {
+        (Some(compiled), Some(source)) => compiled >= source,
+        (Some(_), None) => true,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conjugate() {
+        assert_eq!(conjugate("食べられる", "一段", "基本形").as_deref(), Some("食べられる"));
+        assert_eq!(conjugate("食べられる", "一段", "連用形").as_deref(), Some("食べられ"));
+        assert_eq!(conjugate("見られる", "一段", "仮定形").as_deref(), Some("見られれ"));
+        assert_eq!(conjugate("ている", "一段", "命令ｒｏ").as_deref(), Some("ていろ"));
+        assert_eq!(conjugate("書く", "五段・カ行イ音便", "連用形"), None);
+        assert_eq!(conjugate("食べられる", "一段", "不明"), None);
+    }
+
+    #[test]
+    fn test_compiled_user_dictionary_is_up_to_date() {
+        let dir = std::env::temp_dir().join(format!("mozuku-userdic-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let csv = dir.join("user.csv");
+        let binary = compiled_user_dictionary_path(&csv);
+
+        assert_eq!(binary, dir.join("user.bin"));
+        std::fs::write(&csv, "東京スカイツリー,カスタム名詞,トウキョウスカイツリー\n").unwrap();
+        assert!(!is_up_to_date(&binary, &csv));
+        std::fs::write(&binary, "").unwrap();
+        let up_to_date = is_up_to_date(&binary, &csv);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(up_to_date);
+    }
+
+    #[test]
+    fn test_user_dictionary() {
+        let dir = std::env::temp_dir().join(format!("mozuku-userdic-load-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let csv = dir.join("user.csv");
+        std::fs::write(&csv, "もずく語,カスタム名詞,モズクゴ\n").unwrap();
+
+        let from_csv = MorphologicalAnalyzer::with_user_dictionary(Some(&csv)).unwrap();
+        let binary = MorphologicalAnalyzer::build_user_dictionary(&csv).unwrap();
+        let from_binary = MorphologicalAnalyzer::with_user_dictionary(Some(&csv)).unwrap();
+        let binary_exists = binary.exists();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(binary_exists);
+        for analyzer in [from_csv, from_binary] {
+            let tokens = analyzer.tokenize("もずく語を話す");
+            assert_eq!(tokens[0].surface, "もずく語");
+            assert_eq!(tokens[0].pos, "カスタム名詞");
+        }
+    }
+
+    #[test]
+    fn test_degraded_analyzer() {
+        let analyzer = MorphologicalAnalyzer::degraded();
+
+        assert!(!analyzer.is_available());
+        assert!(analyzer.tokenize("私は学生です。").is_empty());
+        assert!(analyzer.get_semantic_tokens("私は学生です。").is_empty());
+    }
+
+    #[test]
+    fn test_split_sentences() {
+        let sentences = split_sentences("今日は晴れです。明日は雨です！\n改行後の文");
+
+        let texts: Vec<&str> = sentences.iter().map(|s| s.text.as_str()).collect();
+        assert_eq!(texts, vec!["今日は晴れです。", "明日は雨です！", "改行後の文"]);
+    }
+
+    #[test]
+    fn test_split_sentences_offsets() {
+        let text = "一文目。　二文目。";
+        let sentences = split_sentences(text);
+
+        assert_eq!(sentences.len(), 2);
+        assert_eq!(&text[sentences[1].start..sentences[1].end], "二文目。");
+    }
+
+    #[test]
+    fn test_split_paragraphs() {
+        let text = "一段落目\n続き\n\n\n二段落目\n";
+        let paragraphs = split_paragraphs(text);
+
+        assert_eq!(paragraphs.len(), 2);
+        assert_eq!(&text[paragraphs[0].clone()], "一段落目\n続き");
+        assert_eq!(&text[paragraphs[1].clone()], "二段落目");
+    }
+
+    fn token(surface: &str, pos: &str, pos_detail1: &str) -> TokenInfo {
+        TokenInfo {
+            surface: surface.to_string(),
+            pos: pos.to_string(),
+            pos_detail1: pos_detail1.to_string(),
+            pos_detail2: "*".to_string(),
+            pos_detail3: "*".to_string(),
+            conjugation_type: "*".to_string(),
+            conjugation_form: "*".to_string(),
+            base_form: surface.to_string(),
+            reading: String::new(),
+            pronunciation: String::new(),
+            byte_offset: 0,
+            char_offset: 0,
+            char_length: surface.chars().count(),
+        }
+    }
+
+    #[test]
+    fn test_split_bunsetsu() {
+        // 私の | 日本語学校に | 行っている
+        let tokens = vec![
+            token("私", "名詞", "代名詞"),
+            token("の", "助詞", "連体化"),
+            token("日本語", "名詞", "一般"),
+            token("学校", "名詞", "一般"),
+            token("に", "助詞", "格助詞"),
+            token("行っ", "動詞", "自立"),
+            token("て", "助詞", "接続助詞"),
+            token("いる", "動詞", "非自立"),
+        ];
+
+        assert_eq!(split_bunsetsu(&tokens), vec![0..2, 2..5, 5..8]);
+        assert!(split_bunsetsu(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_split_sentences_empty() {
+        assert!(split_sentences("").is_empty());
+        assert!(split_sentences("\n\n").is_empty());
+    }
+}
@@ -0,0 +1,2461 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use lsp_types::{Diagnostic, DiagnosticRelatedInformation, DiagnosticSeverity, Location, Position, Range, Url};
+use serde::{Deserialize, Serialize};
+
+use crate::analyzer::{conjugate, split_sentences, MorphologicalAnalyzer, TokenInfo};
+use crate::config::CheckerConfig;
+use crate::dictionary::CharKind;
+use crate::pattern::{PatternFiles, PatternRule};
+#[cfg(feature = "plugins")]
+use crate::plugin::PluginHost;
+use crate::rules::{self, RuleCheck};
+use crate::spellcheck::{self, UserDictionaries};
+use crate::textlint::TextContext;
+use crate::utf16::LineIndex;
+
+/// URI used for related information locations.
+///
+/// Like diagnostic ranges, related locations are relative to the checked text;
+/// callers replace this URI with the document URI when mapping positions.
+pub const CHECKED_TEXT_URI: &str = "mozuku:text";
+
+/// A replacement fixing a diagnostic.
+///
+/// Rules attach fix candidates to the diagnostic `data` (see [`fix_data`]);
+/// the range uses the same coordinates as the diagnostic range.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fix {
+    /// Range to replace
+    pub range: Range,
+    /// Replacement text
+    pub new_text: String,
+}
+
+impl Fix {
+    pub fn new(range: Range, new_text: impl Into<String>) -> Self {
+        Self {
+            range,
+            new_text: new_text.into(),
+        }
+    }
+}
+
+/// Structured data attached to diagnostics
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct DiagnosticData {
+    #[serde(default)]
+    fixes: Vec<Fix>,
+}
+
+/// Diagnostic `data` holding fix candidates
+pub fn fix_data(fixes: Vec<Fix>) -> Option<serde_json::Value> {
+    serde_json::to_value(DiagnosticData { fixes }).ok()
+}
+
+/// Fix candidates attached to a diagnostic, best first
+pub fn diagnostic_fixes(diagnostic: &Diagnostic) -> Vec<Fix> {
+    diagnostic
+        .data
+        .as_ref()
+        .and_then(|data| serde_json::from_value::<DiagnosticData>(data.clone()).ok())
+        .map(|data| data.fixes)
+        .unwrap_or_default()
+}
+
+/// The best fix of each diagnostic of a text, in text order, with the
+/// diagnostic it fixes.
+///
+/// Fixes that leave the text unchanged or overlap an earlier one are
+/// skipped; the latter can be applied by fixing the result again. Both
+/// `--fix` and the fix-all code action apply these.
+pub fn fix_all<'d>(text: &str, diagnostics: &'d [Diagnostic]) -> Vec<(&'d Diagnostic, Fix)> {
+    let index = LineIndex::new(text);
+    let mut fixes: Vec<(&Diagnostic, Fix)> = diagnostics
+        .iter()
+        .filter_map(|diag| Some((diag, diagnostic_fixes(diag).into_iter().next()?)))
+        .filter(|(_, fix)| {
+            let (start, end) = (index.offset(fix.range.start), index.offset(fix.range.end));
+            start <= end && text.get(start..end) != Some(fix.new_text.as_str())
+        })
+        .collect();
+    fixes.sort_by_key(|(_, fix)| (fix.range.start, fix.range.end));
+
+    let mut last = Position::default();
+    fixes.retain(|(_, fix)| {
+        if fix.range.start < last {
+            return false;
+        }
+        last = fix.range.end;
+        true
+    });
+    fixes
+}
+
+/// Time spent in each stage of checking, accumulated over checks
+#[derive(Debug, Clone, Default)]
+pub struct CheckProfile {
+    /// Morphological analysis
+    pub tokenize: Duration,
+    /// Each rule, by rule ID
+    pub rules: BTreeMap<&'static str, Duration>,
+}
+
+impl CheckProfile {
+    /// Add the times of another profile (e.g. of a span checked in parallel)
+    pub fn merge(&mut self, other: &CheckProfile) {
+        self.tokenize += other.tokenize;
+        for (rule_id, time) in &other.rules {
+            *self.rules.entry(rule_id).or_default() += *time;
+        }
+    }
+}
+
+/// Grammar checker for Japanese text
+pub struct GrammarChecker {
+    analyzer: Arc<MorphologicalAnalyzer>,
+    /// Rules of `[checker] pattern_files`
+    pattern_files: PatternFiles,
+    /// Words of `[checker] english_dictionary`
+    english_dictionaries: UserDictionaries,
+    /// Runs the WASM plugins of `[checker] plugins`
+    #[cfg(feature = "plugins")]
+    plugins: PluginHost,
+}
+
+impl GrammarChecker {
+    pub fn new(analyzer: Arc<MorphologicalAnalyzer>) -> Self {
+        Self {
+            analyzer,
+            pattern_files: PatternFiles::default(),
+            english_dictionaries: UserDictionaries::default(),
+            #[cfg(feature = "plugins")]
+            plugins: PluginHost::new(),
+        }
+    }
+
+    /// Check text and return diagnostics
+    pub fn check(&self, text: &str) -> Vec<Diagnostic> {
+        self.check_with_config(text, &CheckerConfig::default())
+    }
+
+    /// Check text with only the rules enabled in the configuration
+    pub fn check_with_config(&self, text: &str, config: &CheckerConfig) -> Vec<Diagnostic> {
+        self.run_checks(text, None, config, None)
+    }
+
+    /// Check text like [`Self::check_with_config`], using tokens already
+    /// produced by the analyzer for this text
+    pub fn check_with_tokens(&self, text: &str, tokens: &[TokenInfo], config: &CheckerConfig) -> Vec<Diagnostic> {
+        self.run_checks(text, Some(tokens), config, None)
+    }
+
+    /// Check text like [`Self::check_with_config`], adding the time spent in
+    /// morphological analysis and in each rule to a profile
+    pub fn check_profiled(&self, text: &str, config: &CheckerConfig, profile: &mut CheckProfile) -> Vec<Diagnostic> {
+        self.run_checks(text, None, config, Some(profile))
+    }
+
+    /// Check text like [`Self::check_with_tokens`], adding the time spent in
+    /// each rule to a profile
+    pub fn check_with_tokens_profiled(
+        &self,
+        text: &str,
+        tokens: &[TokenInfo],
+        config: &CheckerConfig,
+        profile: &mut CheckProfile,
+    ) -> Vec<Diagnostic> {
+        self.run_checks(text, Some(tokens), config, Some(profile))
+    }
+
+    fn run_checks(
+        &self,
+        text: &str,
+        tokens: Option<&[TokenInfo]>,
+        config: &CheckerConfig,
+        mut profile: Option<&mut CheckProfile>,
+    ) -> Vec<Diagnostic> {
+        // Only read the clock when profiling: `Instant::now` panics on
+        // wasm32-unknown-unknown
+        let timed = profile.is_some();
+        let tokenized;
+        let tokens = match tokens {
+            Some(tokens) => tokens,
+            None => {
+                let start = timed.then(Instant::now);
+                tokenized = self.analyzer.tokenize(text);
+                if let (Some(profile), Some(start)) = (profile.as_deref_mut(), start) {
+                    profile.tokenize += start.elapsed();
+                }
+                &tokenized
+            }
+        };
+        let index = LineIndex::new(text);
+        let ctx = RuleContext { tokens, index: &index };
+
+        let pattern_files = self.pattern_files.rules(&config.pattern_files);
+        let patterns: Vec<&PatternRule> = config
+            .patterns
+            .iter()
+            .chain(pattern_files.iter().flat_map(|rules| rules.iter()))
+            .collect();
+
+        // Token rules share a single pass over the tokens; text rules run after it
+        let mut token_rules: Vec<(&'static str, Box<dyn TokenRule<'_> + '_>)> = Vec::new();
+        let mut text_rules = Vec::new();
+        for rule in rules::RULES.iter().filter(|rule| config.is_rule_enabled(rule.id)) {
+            match rule.check {
+                RuleCheck::Tokens(new) => token_rules.push((rule.id, new(config))),
+                RuleCheck::Text(check) => text_rules.push((rule.id, check)),
+                RuleCheck::External => {}
+            }
+        }
+        if !patterns.is_empty() {
+            token_rules.push(("patterns", Box::new(Patterns { rules: &patterns })));
+        }
+        let mut active: Vec<ActiveRule> = token_rules
+            .into_iter()
+            .map(|(rule_id, rule)| ActiveRule {
+                rule_id,
+                rule,
+                diagnostics: Vec::new(),
+                elapsed: Duration::ZERO,
+                done: false,
+            })
+            .collect();
+
+        // Drive every rule with a single pass over the tokens, stopping early
+        // once no rule needs further tokens
+        for i in 0..tokens.len() {
+            let mut running = false;
+            for rule in active.iter_mut().filter(|rule| !rule.done) {
+                let start = timed.then(Instant::now);
+                rule.done = rule.rule.step(&ctx, i, &mut rule.diagnostics) == Step::Done;
+                if let Some(start) = start {
+                    rule.elapsed += start.elapsed();
+                }
+                running |= !rule.done;
+            }
+            if !running {
+                break;
+            }
+        }
+
+        let mut diagnostics = Vec::new();
+        for mut rule in active {
+            let start = timed.then(Instant::now);
+            rule.rule.finish(&ctx, &mut rule.diagnostics);
+            if let Some(start) = start {
+                rule.elapsed += start.elapsed();
+            }
+            if let Some(profile) = profile.as_deref_mut() {
+                *profile.rules.entry(rule.rule_id).or_default() += rule.elapsed;
+            }
+            diagnostics.extend(rule.diagnostics);
+        }
+
+        // Text rules look at whole sentences and runs of characters
+        if !text_rules.is_empty() {
+            let mut text_ctx = TextContext::new(text, tokens, &index);
+            if config.is_rule_enabled(spellcheck::RULE_ID) {
+                text_ctx.english_words = self.english_dictionaries.words(&config.english_dictionary);
+            }
+            for (rule_id, rule) in text_rules {
+                let start = timed.then(Instant::now);
+                diagnostics.extend(rule(&text_ctx, config));
+                if let (Some(profile), Some(start)) = (profile.as_deref_mut(), start) {
+                    *profile.rules.entry(rule_id).or_default() += start.elapsed();
+                }
+            }
+        }
+
+        // A failing plugin is logged and skipped so that built-in rules still report
+        #[cfg(feature = "plugins")]
+        for path in &config.plugins {
+            let start = timed.then(Instant::now);
+            match self.plugins.check(path, text, tokens, &index) {
+                Ok(found) => diagnostics.extend(found),
+                Err(e) => tracing::warn!("Plugin check failed: {:#}", e),
+            }
+            if let (Some(profile), Some(start)) = (profile.as_deref_mut(), start) {
+                *profile.rules.entry("plugins").or_default() += start.elapsed();
+            }
+        }
+
+        // Drop diagnostics of pattern and plugin rules disabled by
+        // `[checker.rules]`, and those on words the user chose to ignore, for
+        // all rules or for the rule that reported them
+        if !config.rules.is_empty() || !config.ignore_words.is_empty() || !config.false_positives.is_empty() {
+            diagnostics.retain(|diag| {
+                let word = &text[index.offset(diag.range.start)..index.offset(diag.range.end)];
+                let rule_id = rule_id(diag).unwrap_or_default();
+                config.is_rule_enabled(rule_id)
+                    && !config.ignore_words.iter().any(|w| w == word)
+                    && !config.is_false_positive(rule_id, word)
+            });
+        }
+
+        // Severities are settled before merging, which keeps the most severe
+        for diag in &mut diagnostics {
+            rules::annotate(diag);
+            if let Some(severity) = rule_id(diag).and_then(|rule_id| config.severity_override(rule_id)) {
+                diag.severity = Some(severity);
+            }
+        }
+        if config.merge_overlapping {
+            diagnostics = merge_overlapping(diagnostics);
+        }
+        diagnostics
+    }
+}
+
+/// Rule ID (code) of a diagnostic
+fn rule_id(diagnostic: &Diagnostic) -> Option<&str> {
+    match &diagnostic.code {
+        Some(lsp_types::NumberOrString::String(code)) => Some(code),
+        _ => None,
+    }
+}
+
+/// Merge diagnostics on the same range into one, keeping the order of
+/// their first occurrence.
+///
+/// The diagnostic with the highest priority (most severe, then with fixes,
+/// then of the rule registered first) is kept. The others become its related
+/// information and their fixes follow its own; exact duplicates are dropped.
+fn merge_overlapping(diagnostics: Vec<Diagnostic>) -> Vec<Diagnostic> {
+    let mut groups: Vec<Vec<Diagnostic>> = Vec::new();
+    let mut by_range: BTreeMap<(Position, Position), usize> = BTreeMap::new();
+    for diag in diagnostics {
+        let key = (diag.range.start, diag.range.end);
+        match by_range.get(&key) {
+            Some(&i) => groups[i].push(diag),
+            None => {
+                by_range.insert(key, groups.len());
+                groups.push(vec![diag]);
+            }
+        }
+    }
+    groups.into_iter().map(merge_group).collect()
+}
+
+fn merge_group(mut group: Vec<Diagnostic>) -> Diagnostic {
+    if group.len() == 1 {
+        return group.pop().unwrap();
+    }
+    group.sort_by_cached_key(|diag| {
+        let severity = match diag.severity {
+            Some(DiagnosticSeverity::ERROR) => 0,
+            Some(DiagnosticSeverity::WARNING) | None => 1,
+            Some(DiagnosticSeverity::INFORMATION) => 2,
+            _ => 3,
+        };
+        let rule_id = rules::rule_for_diagnostic(diag).map(|rule| rule.id);
+        let registered = rules::RULES
+            .iter()
+            .position(|rule| Some(rule.id) == rule_id)
+            .unwrap_or(rules::RULES.len());
+        (severity, diagnostic_fixes(diag).is_empty(), registered)
+    });
+
+    let mut group = group.into_iter();
+    let mut primary = group.next().unwrap();
+    let mut fixes = diagnostic_fixes(&primary);
+    let mut fixes_added = false;
+    let mut seen = vec![(primary.code.clone(), primary.message.clone())];
+    for diag in group {
+        let key = (diag.code.clone(), diag.message.clone());
+        if seen.contains(&key) {
+            continue;
+        }
+        seen.push(key);
+
+        let message = match rules::rule_for_diagnostic(&diag) {
+            Some(rule) => format!("{}: {}", rule.name, diag.message),
+            None => diag.message.clone(),
+        };
+        primary
+            .related_information
+            .get_or_insert_with(Vec::new)
+            .push(DiagnosticRelatedInformation {
+                location: Location {
+                    uri: Url::parse(CHECKED_TEXT_URI).unwrap(),
+                    range: diag.range,
+                },
+                message,
+            });
+        for fix in diagnostic_fixes(&diag) {
+            if !fixes.contains(&fix) {
+                fixes.push(fix);
+                fixes_added = true;
+            }
+        }
+    }
+    if fixes_added {
+        primary.data = fix_data(fixes);
+    }
+    primary
+}
+
+/// Whether a rule needs to see further tokens
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Step {
+    Continue,
+    /// The rule cannot report anything more; it only gets [`TokenRule::finish`]
+    Done,
+}
+
+/// A rule driven by the single pass over the tokens in [`GrammarChecker`].
+///
+/// Each step looks at one token and at most a few tokens around it, so a check
+/// stays linear in the number of tokens however many rules are enabled.
+/// Patterns spanning more tokens are tracked in the rule's own state.
+pub(crate) trait TokenRule<'a> {
+    /// Look at the token at index `i`
+    fn step(&mut self, ctx: &RuleContext<'a>, i: usize, diagnostics: &mut Vec<Diagnostic>) -> Step;
+
+    /// Report what depends on the whole text, after the last step
+    fn finish(&mut self, _ctx: &RuleContext<'a>, _diagnostics: &mut Vec<Diagnostic>) {}
+}
+
+/// An enabled rule and its results during a check
+struct ActiveRule<'a> {
+    rule_id: &'static str,
+    rule: Box<dyn TokenRule<'a> + 'a>,
+    diagnostics: Vec<Diagnostic>,
+    elapsed: Duration,
+    done: bool,
+}
+
+/// Tokens of the checked text, shared by the rules
+pub(crate) struct RuleContext<'a> {
+    tokens: &'a [TokenInfo],
+    index: &'a LineIndex,
+}
+
+impl<'a> RuleContext<'a> {
+    /// Convert token position to LSP range
+    fn token_to_range(&self, token: &TokenInfo) -> Range {
+        Range {
+            start: self.index.position(token.byte_offset),
+            end: self.index.position(token.byte_offset + token.surface.len()),
+        }
+    }
+
+    /// Convert multiple tokens to a single range
+    fn tokens_to_range(&self, tokens: &[&TokenInfo]) -> Range {
+        let first = tokens.first().unwrap();
+        let last = tokens.last().unwrap();
+
+        Range {
+            start: self.index.position(first.byte_offset),
+            end: self.index.position(last.byte_offset + last.surface.len()),
+        }
+    }
+
+    /// Build related information pointing into the checked text
+    fn related_information(&self, range: Range, message: &str) -> DiagnosticRelatedInformation {
+        DiagnosticRelatedInformation {
+            location: Location {
+                uri: Url::parse(CHECKED_TEXT_URI).unwrap(),
+                range,
+            },
+            message: message.to_string(),
+        }
+    }
+}
+
+/// Check for ら抜き言葉 (ra-nuki kotoba)
+/// Example: 食べれる → 食べられる, 見れた → 見られた
+pub(crate) struct RaNuki;
+
+impl RaNuki {
+    /// 一段 verbs in れる whose stem reads like that of a ら抜き word
+    const REGULAR: [&'static str; 13] = [
+        "入れる",
+        "いれる",
+        "切れる",
+        "きれる",
+        "知れる",
+        "しれる",
+        "照れる",
+        "紛れる",
+        "痺れる",
+        "捩れる",
+        "淹れる",
+        "煎れる",
+        "焦がれる",
+    ];
+
+    /// Whether a 一段 verb in れる is the ら抜き form of another verb: its
+    /// stem reads like that of a 一段 verb or of 来る (見れる, 起きれる,
+    /// 来れる), unlike 忘れる or 流れる
+    fn is_ra_nuki_verb(token: &TokenInfo) -> bool {
+        let Some(stem) = token.base_form.strip_suffix("れる") else {
+            return false;
+        };
+        let regular = Self::REGULAR.iter().any(|verb| token.base_form.ends_with(verb));
+        if stem.is_empty() || stem.ends_with('ら') || regular {
+            return false;
+        }
+        // The reading is in katakana, one character per kana of the ending
+        let ending = token.surface.chars().count().saturating_sub(stem.chars().count());
+        let Some(last) = token.reading.chars().rev().nth(ending) else {
+            return false;
+        };
+        "イキギシジチヂニヒビピミリエケゲセゼテデネヘベペメレコ".contains(last)
+    }
+
+    fn diagnostic(range: Range, wrong: &str, correct: String) -> Diagnostic {
+        Diagnostic {
+            range,
+            code: Some(lsp_types::NumberOrString::String("ra-nuki".to_string())),
+            source: Some("mozuku".to_string()),
+            message: format!("ら抜き言葉の可能性があります。「{}」→「{}」", wrong, correct),
+            data: fix_data(vec![Fix::new(range, correct)]),
+            ..Default::default()
+        }
+    }
+}
+
+impl<'a> TokenRule<'a> for RaNuki {
+    fn step(&mut self, ctx: &RuleContext<'a>, i: usize, diagnostics: &mut Vec<Diagnostic>) -> Step {
+        let token = &ctx.tokens[i];
+        if token.pos != "動詞" || !token.conjugation_type.starts_with("一段") {
+            return Step::Continue;
+        }
+
+        // A single token in the dictionary: 見れる, 見れ(た)
+        if token.pos_detail1 == "自立" && Self::is_ra_nuki_verb(token) {
+            let base = format!("{}られる", token.base_form.trim_end_matches("れる"));
+            if let Some(correct) = conjugate(&base, &token.conjugation_type, &token.conjugation_form) {
+                diagnostics.push(Self::diagnostic(ctx.token_to_range(token), &token.surface, correct));
+            }
+        }
+
+        // A 一段 verb or 来る followed by れる: 食べ|れる, 食べ|れ(た)
+        if i > 0 && token.base_form == "れる" && token.pos_detail1 == "接尾" {
+            let prev = &ctx.tokens[i - 1];
+            let stem_form = prev.conjugation_form.starts_with("未然形") || prev.conjugation_form.starts_with("連用形");
+            let stem_type = prev.conjugation_type.starts_with("一段") || prev.conjugation_type.starts_with("カ変");
+            if prev.pos == "動詞" && stem_type && stem_form {
+                if let Some(ending) = conjugate("られる", &token.conjugation_type, &token.conjugation_form) {
+                    let range = ctx.tokens_to_range(&[prev, token]);
+                    let wrong = format!("{}{}", prev.surface, token.surface);
+                    diagnostics.push(Self::diagnostic(range, &wrong, format!("{}{}", prev.surface, ending)));
+                }
+            }
+        }
+
+        Step::Continue
+    }
+}
+
+/// Check for い抜き言葉 (i-nuki kotoba)
+/// Example: している → してる, していた → してた
+pub(crate) struct INuki;
+
+impl<'a> TokenRule<'a> for INuki {
+    fn step(&mut self, ctx: &RuleContext<'a>, i: usize, diagnostics: &mut Vec<Diagnostic>) -> Step {
+        let token = &ctx.tokens[i];
+
+        // てる/でる in any conjugation form (should be ている/でいる)
+        let full = match token.base_form.as_str() {
+            "てる" => "ている",
+            "でる" => "でいる",
+            _ => return Step::Continue,
+        };
+        let auxiliary = token.pos == "助動詞" || token.pos == "動詞" && token.pos_detail1 == "非自立";
+        if i == 0 || !auxiliary || ctx.tokens[i - 1].pos != "動詞" {
+            return Step::Continue;
+        }
+        let correct = conjugate(full, &token.conjugation_type, &token.conjugation_form)
+            .or_else(|| (token.surface == token.base_form).then(|| full.to_string()));
+        let Some(correct) = correct else {
+            return Step::Continue;
+        };
+
+        let range = ctx.token_to_range(token);
+        diagnostics.push(Diagnostic {
+            range,
+            code: Some(lsp_types::NumberOrString::String("i-nuki".to_string())),
+            source: Some("mozuku".to_string()),
+            message: format!("い抜き言葉です。「{}」→「{}」（口語では許容）", token.surface, correct),
+            data: fix_data(vec![Fix::new(range, correct)]),
+            ..Default::default()
+        });
+        Step::Continue
+    }
+}
+
+/// Check for double particles (二重助詞)
+/// Example: がが, をを, にに
+pub(crate) struct DoubleParticle;
+
+impl<'a> TokenRule<'a> for DoubleParticle {
+    fn step(&mut self, ctx: &RuleContext<'a>, i: usize, diagnostics: &mut Vec<Diagnostic>) -> Step {
+        // Particles to check for duplication
+        let target_particles = ["が", "を", "に", "へ", "で", "と", "から", "まで", "より"];
+
+        let current = &ctx.tokens[i];
+        let Some(next) = ctx.tokens.get(i + 1) else {
+            return Step::Continue;
+        };
+
+        // Check if both are the same particle
+        if current.pos == "助詞"
+            && next.pos == "助詞"
+            && current.surface == next.surface
+            && target_particles.contains(&current.surface.as_str())
+        {
+            let range = ctx.tokens_to_range(&[current, next]);
+            diagnostics.push(Diagnostic {
+                range,
+                code: Some(lsp_types::NumberOrString::String(
+                    "double-particle".to_string(),
+                )),
+                source: Some("mozuku".to_string()),
+                message: format!(
+                    "助詞「{}」が重複しています。",
+                    current.surface
+                ),
+                data: fix_data(vec![Fix::new(range, current.surface.clone())]),
+                ..Default::default()
+            });
+        }
+
+        Step::Continue
+    }
+}
+
+/// Check for a particle dropped between a noun and a verb
+/// Example: 私行きます → 私が行きます, 資料送ります → 資料を送ります
+pub(crate) struct MissingParticle;
+
+impl MissingParticle {
+    /// Nouns that modify a verb without a particle: 今日行く, 全部食べる,
+    /// 3回読む, or that are not words of their own
+    const ADVERBIAL: [&'static str; 6] = ["副詞可能", "数", "接尾", "非自立", "形容動詞語幹", "ナイ形容詞語幹"];
+
+    /// Verbs making a verb of the noun before them: 勉強する, 利用できる
+    const LIGHT_VERBS: [&'static str; 6] = ["する", "できる", "出来る", "させる", "なさる", "いたす"];
+}
+
+impl<'a> TokenRule<'a> for MissingParticle {
+    fn step(&mut self, ctx: &RuleContext<'a>, i: usize, diagnostics: &mut Vec<Diagnostic>) -> Step {
+        let noun = &ctx.tokens[i];
+        let Some(verb) = ctx.tokens.get(i + 1) else {
+            return Step::Continue;
+        };
+        if noun.pos != "名詞" || Self::ADVERBIAL.contains(&noun.pos_detail1.as_str()) {
+            return Step::Continue;
+        }
+        if verb.pos != "動詞" || verb.pos_detail1 != "自立" {
+            return Step::Continue;
+        }
+        if Self::LIGHT_VERBS.contains(&verb.base_form.as_str()) {
+            return Step::Continue;
+        }
+        // Words apart, as in a list or a heading, are not missing a particle
+        if noun.byte_offset + noun.surface.len() != verb.byte_offset {
+            return Step::Continue;
+        }
+
+        diagnostics.push(Diagnostic {
+            range: ctx.tokens_to_range(&[noun, verb]),
+            code: Some(lsp_types::NumberOrString::String("missing-particle".to_string())),
+            source: Some("mozuku".to_string()),
+            message: format!(
+                "「{}」と「{}」の間の助詞が抜けている可能性があります（口語では許容）。",
+                noun.surface, verb.surface
+            ),
+            ..Default::default()
+        });
+        Step::Continue
+    }
+}
+
+/// Check for redundant な with na-adjectives
+/// Example: 静かなな → 静かな
+pub(crate) struct RedundantNa;
+
+impl<'a> TokenRule<'a> for RedundantNa {
+    fn step(&mut self, ctx: &RuleContext<'a>, i: usize, diagnostics: &mut Vec<Diagnostic>) -> Step {
+        let current = &ctx.tokens[i];
+        let Some(next) = ctx.tokens.get(i + 1) else {
+            return Step::Continue;
+        };
+
+        // Check for な + な pattern
+        if current.surface == "な"
+            && next.surface == "な"
+            && current.pos == "助動詞"
+            && next.pos == "助動詞"
+        {
+            let range = ctx.tokens_to_range(&[current, next]);
+            diagnostics.push(Diagnostic {
+                range,
+                code: Some(lsp_types::NumberOrString::String(
+                    "redundant-na".to_string(),
+                )),
+                source: Some("mozuku".to_string()),
+                message: "「な」が重複しています。".to_string(),
+                data: fix_data(vec![Fix::new(range, "な")]),
+                ..Default::default()
+            });
+        }
+
+        Step::Continue
+    }
+}
+
+/// Check for double honorific (二重敬語)
+/// Example: おっしゃられる → おっしゃる, ご覧になられる → ご覧になる
+pub(crate) struct DoubleHonorific;
+
+impl<'a> TokenRule<'a> for DoubleHonorific {
+    fn step(&mut self, ctx: &RuleContext<'a>, i: usize, diagnostics: &mut Vec<Diagnostic>) -> Step {
+        // Common honorific verb stems that should not be followed by れる/られる
+        // Lindera may split "おっしゃられ" into "おっしゃら" + "れ"
+        // (stem, dictionary form, 連用形 before ます)
+        let honorific_stems = [
+            ("おっしゃ", "おっしゃる", "おっしゃい"),     // おっしゃら + れ
+            ("いらっしゃ", "いらっしゃる", "いらっしゃい"), // いらっしゃら + れ
+            ("なさ", "なさる", "なさい"),                   // なさら + れ
+            ("くださ", "くださる", "ください"),             // くださら + れ
+            ("召し上が", "召し上がる", "召し上がり"),       // 召し上がら + れ
+        ];
+        let tokens = ctx.tokens;
+
+        // Check for stem + れ pattern (e.g., おっしゃら + れ)
+        if let [current, next, ..] = &tokens[i..] {
+            // Check if current token is an honorific stem
+            for (stem, correct, masu_stem) in &honorific_stems {
+                if current.surface.starts_with(stem)
+                    && current.pos == "動詞"
+                    && (next.surface == "れ" || next.surface == "られ")
+                    && next.pos == "動詞"
+                {
+                    let range = ctx.tokens_to_range(&[current, next]);
+                    let fix = honorific_fix(&tokens[i..i + 2], tokens.get(i + 2), correct, masu_stem, ctx.index);
+                    diagnostics.push(Diagnostic {
+                        range,
+                        code: Some(lsp_types::NumberOrString::String(
+                            "double-honorific".to_string(),
+                        )),
+                        source: Some("mozuku".to_string()),
+                        message: format!(
+                            "二重敬語の可能性があります。「{}{}」→「{}」",
+                            current.surface, next.surface, correct
+                        ),
+                        data: fix_data(fix.into_iter().collect()),
+                        ..Default::default()
+                    });
+                    break;
+                }
+            }
+        }
+
+        // Check for ご〜になられる pattern
+        // Lindera splits: ご覧 + に + なら + れ
+        // Pattern: ご〜 + に + なら + れ
+        if let [t0, t1, t2, t3, ..] = &tokens[i..] {
+            if t0.surface.starts_with("ご")
+                && t1.surface == "に"
+                && (t2.surface == "なら" || t2.surface == "なり")
+                && (t3.surface == "れ" || t3.surface == "られ")
+            {
+                let range = ctx.tokens_to_range(&[t0, t1, t2, t3]);
+                let fix = honorific_fix(
+                    &tokens[i..i + 4],
+                    tokens.get(i + 4),
+                    &format!("{}になる", t0.surface),
+                    &format!("{}になり", t0.surface),
+                    ctx.index,
+                );
+                diagnostics.push(Diagnostic {
+                    range,
+                    code: Some(lsp_types::NumberOrString::String(
+                        "double-honorific".to_string(),
+                    )),
+                    source: Some("mozuku".to_string()),
+                    message: format!(
+                        "二重敬語の可能性があります。「{}{}{}{}」→「{}になる」",
+                        t0.surface, t1.surface, t2.surface, t3.surface, t0.surface
+                    ),
+                    data: fix_data(fix.into_iter().collect()),
+                    ..Default::default()
+                });
+            }
+        }
+
+        Step::Continue
+    }
+}
+
+/// Check for redundant expressions (冗長表現)
+/// Example: することができる → できる, ことが可能 → できる
+pub(crate) struct RedundantExpression;
+
+impl<'a> TokenRule<'a> for RedundantExpression {
+    fn step(&mut self, ctx: &RuleContext<'a>, i: usize, diagnostics: &mut Vec<Diagnostic>) -> Step {
+        // Look for patterns like: Verb + こと + が + できる/可能
+        // (followed by at least one more token)
+        let tokens = ctx.tokens;
+        let [t0, t1, t2, t3, ..] = &tokens[i..] else {
+            return Step::Continue;
+        };
+
+        // Pattern: こと + が + できる
+        if t0.surface == "こと" && t1.surface == "が" {
+            if t2.surface == "でき" || t2.base_form == "できる" {
+                let range = ctx.tokens_to_range(&[t0, t1, t2]);
+                // 〜することができる → 〜できる; other verbs need rewording
+                let fix = i
+                    .checked_sub(1)
+                    .map(|p| &tokens[p])
+                    .filter(|prev| prev.surface == "する")
+                    .map(|prev| Fix::new(ctx.tokens_to_range(&[prev, t2]), t2.surface.clone()));
+                diagnostics.push(Diagnostic {
+                    range,
+                    code: Some(lsp_types::NumberOrString::String(
+                        "redundant-expression".to_string(),
+                    )),
+                    source: Some("mozuku".to_string()),
+                    message: "冗長な表現です。「〜ことができる」→「〜できる」".to_string(),
+                    data: fix_data(fix.into_iter().collect()),
+                    ..Default::default()
+                });
+            } else if t2.surface == "可能" {
+                let range = ctx.tokens_to_range(&[t0, t1, t2]);
+                // 〜することが可能です → 〜できます
+                let ending = match t3.surface.as_str() {
+                    "です" => Some("できます"),
+                    "だ" | "な" => Some("できる"),
+                    _ => None,
+                };
+                let fix = i
+                    .checked_sub(1)
+                    .map(|p| &tokens[p])
+                    .filter(|prev| prev.surface == "する")
+                    .zip(ending)
+                    .map(|(prev, ending)| Fix::new(ctx.tokens_to_range(&[prev, t3]), ending));
+                diagnostics.push(Diagnostic {
+                    range,
+                    code: Some(lsp_types::NumberOrString::String(
+                        "redundant-expression".to_string(),
+                    )),
+                    source: Some("mozuku".to_string()),
+                    message: "冗長な表現です。「〜ことが可能」→「〜できる」".to_string(),
+                    data: fix_data(fix.into_iter().collect()),
+                    ..Default::default()
+                });
+            }
+        }
+
+        Step::Continue
+    }
+}
+
+/// Check for incomplete たり parallel (たり〜たり の不完全な並列)
+/// Example: 歩いたり走る → 歩いたり走ったりする
+///
+/// Only a text with exactly one たり and no 〜たり after it is reported, so
+/// the rule is done as soon as a second one appears.
+#[derive(Default)]
+pub(crate) struct TariParallel {
+    /// The first たり
+    tari: Option<usize>,
+    /// The first verb after it
+    verb: Option<usize>,
+}
+
+impl<'a> TokenRule<'a> for TariParallel {
+    fn step(&mut self, ctx: &RuleContext<'a>, i: usize, _diagnostics: &mut Vec<Diagnostic>) -> Step {
+        let token = &ctx.tokens[i];
+        if self.tari.is_none() {
+            if token.surface == "たり" {
+                self.tari = Some(i);
+            }
+            return Step::Continue;
+        }
+
+        // A matching たり after the first one completes the parallel
+        if token.surface.ends_with("たり") {
+            self.tari = None;
+            return Step::Done;
+        }
+        if self.verb.is_none() && token.pos == "動詞" {
+            self.verb = Some(i);
+        }
+        Step::Continue
+    }
+
+    fn finish(&mut self, ctx: &RuleContext<'a>, diagnostics: &mut Vec<Diagnostic>) {
+        let (Some(tari), Some(verb)) = (self.tari, self.verb) else {
+            return;
+        };
+
+        diagnostics.push(Diagnostic {
+            range: ctx.token_to_range(&ctx.tokens[tari]),
+            code: Some(lsp_types::NumberOrString::String(
+                "incomplete-tari".to_string(),
+            )),
+            source: Some("mozuku".to_string()),
+            message: "「たり」を使う場合は「〜たり〜たりする」の形が適切です。".to_string(),
+            related_information: Some(vec![ctx.related_information(
+                ctx.token_to_range(&ctx.tokens[verb]),
+                "対になる「〜たり」がここに必要です",
+            )]),
+            ..Default::default()
+        });
+    }
+}
+
+/// Check for consecutive の particles (「の」の連続使用)
+/// Pattern: 名詞の名詞の名詞の... (3つ以上の「の」は警告)
+#[derive(Default)]
+pub(crate) struct ConsecutiveNo<'a> {
+    /// の particles of the current 名詞 + の sequence
+    no_positions: Vec<&'a TokenInfo>,
+}
+
+impl<'a> ConsecutiveNo<'a> {
+    /// End the current sequence, reporting it when it has 3 or more の
+    fn flush(&mut self, ctx: &RuleContext<'a>, diagnostics: &mut Vec<Diagnostic>) {
+        if self.no_positions.len() >= 3 {
+            let range = ctx.tokens_to_range(&self.no_positions);
+
+            diagnostics.push(Diagnostic {
+                range,
+                code: Some(lsp_types::NumberOrString::String(
+                    "consecutive-no".to_string(),
+                )),
+                source: Some("mozuku".to_string()),
+                message: format!(
+                    "「の」が{}回連続しています。読みやすさのため言い換えを検討してください。",
+                    self.no_positions.len()
+                ),
+                ..Default::default()
+            });
+        }
+        self.no_positions.clear();
+    }
+}
+
+impl<'a> TokenRule<'a> for ConsecutiveNo<'a> {
+    fn step(&mut self, ctx: &RuleContext<'a>, i: usize, diagnostics: &mut Vec<Diagnostic>) -> Step {
+        let tokens = ctx.tokens;
+        let token = &tokens[i];
+
+        if token.surface == "の" && token.pos == "助詞" {
+            // Check if preceded by 名詞
+            if i > 0 && tokens[i - 1].pos == "名詞" {
+                self.no_positions.push(token);
+            } else {
+                // Reset if の is not preceded by 名詞
+                self.flush(ctx, diagnostics);
+            }
+        } else if token.pos != "名詞" && !self.no_positions.is_empty() {
+            // Non-noun token (not の) breaks the sequence
+            self.flush(ctx, diagnostics);
+        }
+
+        Step::Continue
+    }
+
+    fn finish(&mut self, ctx: &RuleContext<'a>, diagnostics: &mut Vec<Diagnostic>) {
+        // Check remaining sequence
+        self.flush(ctx, diagnostics);
+    }
+}
+
+/// Check for notation variance of katakana words (表記ゆれ)
+/// Example: サーバー and サーバ in the same text → サーバー
+#[derive(Default)]
+pub(crate) struct NotationVariance<'a> {
+    /// Indices of katakana nouns, by stem without trailing ー
+    groups: HashMap<&'a str, Vec<usize>>,
+}
+
+impl<'a> NotationVariance<'a> {
+    /// Record the token at `i` if it is a katakana noun
+    fn collect(&mut self, tokens: &'a [TokenInfo], i: usize) {
+        let token = &tokens[i];
+        if token.pos != "名詞" || !token.surface.chars().all(|c| CharKind::of(c) == CharKind::Katakana) {
+            return;
+        }
+        let stem = token.surface.trim_end_matches('ー');
+        if stem.chars().count() >= 2 {
+            self.groups.entry(stem).or_default().push(i);
+        }
+    }
+
+    /// Find the katakana nouns written with and without a trailing long vowel mark.
+    ///
+    /// Returns pairs of (variant token index, preferred token index). The preferred
+    /// form is the more frequent one, or the one with ー on a tie.
+    fn variants(&self, tokens: &[TokenInfo]) -> Vec<(usize, usize)> {
+        let mut variants = Vec::new();
+        for indices in self.groups.values() {
+            let mut counts: HashMap<&str, usize> = HashMap::new();
+            for &i in indices {
+                *counts.entry(tokens[i].surface.as_str()).or_default() += 1;
+            }
+            if counts.len() < 2 {
+                continue;
+            }
+
+            let preferred = counts
+                .iter()
+                .max_by_key(|(surface, count)| (**count, surface.ends_with('ー'), Reverse(surface.len())))
+                .map(|(surface, _)| *surface)
+                .unwrap();
+            let first_preferred = indices
+                .iter()
+                .copied()
+                .find(|&i| tokens[i].surface == preferred)
+                .unwrap();
+
+            variants.extend(
+                indices
+                    .iter()
+                    .filter(|&&i| tokens[i].surface != preferred)
+                    .map(|&i| (i, first_preferred)),
+            );
+        }
+
+        variants.sort_unstable();
+        variants
+    }
+}
+
+impl<'a> TokenRule<'a> for NotationVariance<'a> {
+    fn step(&mut self, ctx: &RuleContext<'a>, i: usize, _diagnostics: &mut Vec<Diagnostic>) -> Step {
+        self.collect(ctx.tokens, i);
+        Step::Continue
+    }
+
+    fn finish(&mut self, ctx: &RuleContext<'a>, diagnostics: &mut Vec<Diagnostic>) {
+        diagnostics.extend(self.variants(ctx.tokens).into_iter().map(|(variant, preferred)| {
+            let variant = &ctx.tokens[variant];
+            let preferred = &ctx.tokens[preferred];
+            Diagnostic {
+                range: ctx.token_to_range(variant),
+                code: Some(lsp_types::NumberOrString::String(
+                    "notation-variance".to_string(),
+                )),
+                source: Some("mozuku".to_string()),
+                message: format!(
+                    "表記ゆれがあります。「{}」→「{}」",
+                    variant.surface, preferred.surface
+                ),
+                data: fix_data(vec![Fix::new(ctx.token_to_range(variant), preferred.surface.clone())]),
+                related_information: Some(vec![ctx.related_information(
+                    ctx.token_to_range(preferred),
+                    &format!("「{}」と表記されている箇所", preferred.surface),
+                )]),
+                ..Default::default()
+            }
+        }));
+    }
+}
+
+/// Check for words read alike but written in different scripts (表記ゆれ)
+/// Example: こども and 子供 in the same text, ケータイ and 携帯
+#[derive(Default)]
+pub(crate) struct ScriptVariance<'a> {
+    /// Indices of nouns, by pronunciation
+    groups: HashMap<&'a str, Vec<usize>>,
+}
+
+impl<'a> ScriptVariance<'a> {
+    /// Pronunciations shorter than this have too many homophones (はし: 橋, 箸, 端)
+    const MIN_LENGTH: usize = 3;
+
+    /// Nouns that are not words of their own or have no spelling to unify
+    const EXCLUDED: [&'static str; 3] = ["非自立", "数", "接尾"];
+
+    /// Record the token at `i` if it is a noun with a known pronunciation
+    fn collect(&mut self, tokens: &'a [TokenInfo], i: usize) {
+        let token = &tokens[i];
+        if token.pos != "名詞" || Self::EXCLUDED.contains(&token.pos_detail1.as_str()) {
+            return;
+        }
+        // Words missing from the dictionary have no pronunciation; katakana
+        // words are read as written
+        let katakana = token.surface.chars().all(|c| CharKind::of(c) == CharKind::Katakana);
+        let pronunciation = match token.pronunciation.as_str() {
+            _ if katakana => token.surface.as_str(),
+            "" | "*" => return,
+            pronunciation => pronunciation,
+        };
+        if pronunciation.chars().count() >= Self::MIN_LENGTH {
+            self.groups.entry(pronunciation).or_default().push(i);
+        }
+    }
+
+    /// Scripts a word is written in: hiragana, katakana and kanji
+    fn scripts(surface: &str) -> [bool; 3] {
+        let has = |kind| surface.chars().any(|c| CharKind::of(c) == kind);
+        [has(CharKind::Hiragana), has(CharKind::Katakana), has(CharKind::Kanji)]
+    }
+
+    /// Find the words read alike and written in different scripts.
+    ///
+    /// Returns pairs of (variant token index, preferred token index). The
+    /// preferred form is the more frequent one, or the first one on a tie.
+    /// Spellings in the same scripts are homophones rather than variants
+    /// (意志 and 意思), so they are not compared.
+    fn variants(&self, tokens: &[TokenInfo]) -> Vec<(usize, usize)> {
+        let mut variants = Vec::new();
+        for indices in self.groups.values() {
+            // Count and first index of each spelling
+            let mut spellings: HashMap<&str, (usize, usize)> = HashMap::new();
+            for &i in indices {
+                spellings.entry(tokens[i].surface.as_str()).or_insert((0, i)).0 += 1;
+            }
+            if spellings.len() < 2 {
+                continue;
+            }
+
+            let (preferred, (_, first_preferred)) = spellings
+                .iter()
+                .max_by_key(|(_, (count, first))| (*count, Reverse(*first)))
+                .map(|(surface, spelling)| (*surface, *spelling))
+                .unwrap();
+            let scripts = Self::scripts(preferred);
+            variants.extend(
+                indices
+                    .iter()
+                    .filter(|&&i| tokens[i].surface != preferred && Self::scripts(&tokens[i].surface) != scripts)
+                    .map(|&i| (i, first_preferred)),
+            );
+        }
+
+        variants.sort_unstable();
+        variants
+    }
+}
+
+impl<'a> TokenRule<'a> for ScriptVariance<'a> {
+    fn step(&mut self, ctx: &RuleContext<'a>, i: usize, _diagnostics: &mut Vec<Diagnostic>) -> Step {
+        self.collect(ctx.tokens, i);
+        Step::Continue
+    }
+
+    fn finish(&mut self, ctx: &RuleContext<'a>, diagnostics: &mut Vec<Diagnostic>) {
+        diagnostics.extend(self.variants(ctx.tokens).into_iter().map(|(variant, preferred)| {
+            let variant = &ctx.tokens[variant];
+            let preferred = &ctx.tokens[preferred];
+            let range = ctx.token_to_range(variant);
+            Diagnostic {
+                range,
+                code: Some(lsp_types::NumberOrString::String("script-variance".to_string())),
+                source: Some("mozuku".to_string()),
+                message: format!(
+                    "同じ語が異なる文字種で書かれています。「{}」→「{}」",
+                    variant.surface, preferred.surface
+                ),
+                data: fix_data(vec![Fix::new(range, preferred.surface.clone())]),
+                related_information: Some(vec![ctx.related_information(
+                    ctx.token_to_range(preferred),
+                    &format!("「{}」と表記されている箇所", preferred.surface),
+                )]),
+                ..Default::default()
+            }
+        }));
+    }
+}
+
+/// Check for quantities written in kanji numerals (漢数字)
+/// Example: 三つ → 3つ, 二十五件 → 25件
+///
+/// Only numerals followed by a counter are reported, so words containing a
+/// numeral (一般, 唯一) and repeated counts (一つ一つ) stay as they are.
+pub(crate) struct KanjiNumerals;
+
+impl KanjiNumerals {
+    fn is_numeral(token: &TokenInfo) -> bool {
+        let kanji = |c: char| KANJI_DIGITS.contains(c) || UNITS.contains(c);
+        token.pos_detail1 == "数" && token.surface.chars().all(kanji)
+    }
+}
+
+/// Kanji digits, in the order of their values
+const KANJI_DIGITS: &str = "〇一二三四五六七八九";
+
+/// Kanji numerals multiplying the digits before them
+const UNITS: &str = "十百千万";
+
+/// Value of a number written in kanji numerals: 二十五, 三千二百 or 二〇二四.
+///
+/// Digits in a row without 十, 百 or 千 are only read as a positional number
+/// when one of them is 〇, since 二三日 means "a few days".
+fn kanji_number(numerals: &str) -> Option<u64> {
+    let digit = |c: char| KANJI_DIGITS.chars().position(|d| d == c).map(|d| d as u64);
+    if !numerals.chars().any(|c| UNITS.contains(c)) {
+        if numerals.chars().count() > 1 && !numerals.contains('〇') {
+            return None;
+        }
+        return numerals.chars().try_fold(0, |value, c| Some(value * 10 + digit(c)?));
+    }
+
+    let (mut total, mut section, mut current) = (0, 0, None);
+    for c in numerals.chars() {
+        match c {
+            '万' => {
+                total += (section + current.unwrap_or(0)).max(1) * 10_000;
+                section = 0;
+                current = None;
+            }
+            '十' | '百' | '千' => {
+                let unit = match c {
+                    '十' => 10,
+                    '百' => 100,
+                    _ => 1000,
+                };
+                section += current.unwrap_or(1) * unit;
+                current = None;
+            }
+            _ if current.is_some() => return None,
+            _ => current = Some(digit(c)?),
+        }
+    }
+    Some(total + section + current.unwrap_or(0))
+}
+
+impl<'a> TokenRule<'a> for KanjiNumerals {
+    fn step(&mut self, ctx: &RuleContext<'a>, i: usize, diagnostics: &mut Vec<Diagnostic>) -> Step {
+        let tokens = ctx.tokens;
+        // Look at each run of numerals from its first token
+        if !Self::is_numeral(&tokens[i]) || i > 0 && tokens[i - 1].pos_detail1 == "数" {
+            return Step::Continue;
+        }
+        let end = i + tokens[i..].iter().take_while(|token| Self::is_numeral(token)).count();
+        let Some(counter) = tokens.get(end).filter(|token| token.pos_detail2 == "助数詞") else {
+            return Step::Continue;
+        };
+
+        let numerals: String = tokens[i..end].iter().map(|token| token.surface.as_str()).collect();
+        let count = format!("{}{}", numerals, counter.surface);
+        // Repeated counts such as 一つ一つ
+        let repeated_after = tokens.get(end + 1).is_some_and(|next| count.starts_with(&next.surface));
+        if repeated_after || i > 0 && tokens[i - 1].surface == counter.surface {
+            return Step::Continue;
+        }
+        let Some(value) = kanji_number(&numerals) else {
+            return Step::Continue;
+        };
+
+        let range = ctx.tokens_to_range(&[&tokens[i], &tokens[end - 1]]);
+        diagnostics.push(Diagnostic {
+            range,
+            code: Some(lsp_types::NumberOrString::String(
+                "kanji-numerals".to_string(),
+            )),
+            source: Some("mozuku".to_string()),
+            message: format!(
+                "数量は算用数字で書きます。「{}」→「{}{}」",
+                count, value, counter.surface
+            ),
+            data: fix_data(vec![Fix::new(range, value.to_string())]),
+            ..Default::default()
+        });
+
+        Step::Continue
+    }
+}
+
+/// Token pattern rules of the configuration (see [`crate::pattern`])
+struct Patterns<'p> {
+    rules: &'p [&'p PatternRule],
+}
+
+impl<'a> TokenRule<'a> for Patterns<'_> {
+    fn step(&mut self, ctx: &RuleContext<'a>, i: usize, diagnostics: &mut Vec<Diagnostic>) -> Step {
+        for rule in self.rules {
+            let Some(matched) = rule.match_at(ctx.tokens, i) else {
+                continue;
+            };
+            let range = ctx.tokens_to_range(&[&matched[0], &matched[matched.len() - 1]]);
+
+            diagnostics.push(Diagnostic {
+                range,
+                severity: Some(rule.severity.into()),
+                code: Some(lsp_types::NumberOrString::String(rule.id.clone())),
+                source: Some("mozuku".to_string()),
+                message: rule.message(matched),
+                data: rule
+                    .replacement(matched)
+                    .and_then(|replacement| fix_data(vec![Fix::new(range, replacement)])),
+                ..Default::default()
+            });
+        }
+
+        Step::Continue
+    }
+}
+
+/// Hedging sentence endings, longest first
+const HEDGE_ENDINGS: [&str; 11] = [
+    "のではないでしょうか",
+    "と考えられます",
+    "と考えられる",
+    "と思われます",
+    "に思われます",
+    "と思われる",
+    "と思います",
+    "に思います",
+    "気がします",
+    "気がする",
+    "と思う",
+];
+
+/// Text rule for hedge-endings
+pub(crate) fn hedge_endings(ctx: &TextContext, config: &CheckerConfig) -> Vec<Diagnostic> {
+    check_hedge_endings(ctx.text, ctx.index, config)
+}
+
+/// Check for overused hedging sentence endings (ヘッジ表現の多用)
+/// Example: 〜と思います。〜と考えられます。 in more than `max_hedge_ratio` of the sentences
+///
+/// A single hedge is never reported, however short the text.
+fn check_hedge_endings(text: &str, index: &LineIndex, config: &CheckerConfig) -> Vec<Diagnostic> {
+    let sentences = split_sentences(text);
+    let closing = |c: char| "。．.！？!?」』）)".contains(c);
+    let hedges: Vec<(usize, &str)> = sentences
+        .iter()
+        .filter_map(|sentence| {
+            let body = sentence.text.trim_end_matches(closing);
+            let hedge = HEDGE_ENDINGS.into_iter().find(|hedge| body.ends_with(hedge))?;
+            Some((sentence.start + body.len() - hedge.len(), hedge))
+        })
+        .collect();
+    if hedges.len() < 2 || hedges.len() as f64 <= config.max_hedge_ratio * sentences.len() as f64 {
+        return Vec::new();
+    }
+
+    hedges
+        .iter()
+        .map(|&(start, hedge)| Diagnostic {
+            range: Range {
+                start: index.position(start),
+                end: index.position(start + hedge.len()),
+            },
+            code: Some(lsp_types::NumberOrString::String("hedge-endings".to_string())),
+            source: Some("mozuku".to_string()),
+            message: format!(
+                "文末の「{}」が多用されています（{}文中{}文）。根拠を示して言い切れる文は断定してください。",
+                hedge,
+                sentences.len(),
+                hedges.len()
+            ),
+            ..Default::default()
+        })
+        .collect()
+}
+
+/// Text rule for consecutive-endings
+pub(crate) fn consecutive_endings(ctx: &TextContext, _config: &CheckerConfig) -> Vec<Diagnostic> {
+    check_consecutive_sentence_endings(ctx.text, ctx.index)
+}
+
+/// Check for consecutive same sentence endings (連続する同じ文末)
+/// Example: です。です。です。
+fn check_consecutive_sentence_endings(text: &str, index: &LineIndex) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    // Split by sentence-ending punctuation and analyze, keeping byte offsets
+    let sentences: Vec<(usize, &str)> = text
+        .split('。')
+        .scan(0, |offset, sentence| {
+            let start = *offset;
+            *offset += sentence.len() + '。'.len_utf8();
+            Some((start, sentence))
+        })
+        .filter(|(_, s)| !s.is_empty())
+        .collect();
+
+    if sentences.len() < 3 {
+        return diagnostics;
+    }
+
+    // Track consecutive endings
+    let mut consecutive_count = 1;
+    let mut last_ending = String::new();
+
+    for &(start, sentence) in &sentences {
+        let trimmed = sentence.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+
+        // Get the last few characters as the ending pattern
+        let ending = if trimmed.ends_with("です") {
+            "です"
+        } else if trimmed.ends_with("ます") {
+            "ます"
+        } else if trimmed.ends_with("である") {
+            "である"
+        } else if trimmed.ends_with("だ") {
+            "だ"
+        } else {
+            ""
+        };
+
+        if ending.is_empty() {
+            consecutive_count = 1;
+            last_ending = String::new();
+            continue;
+        }
+
+        if ending == last_ending {
+            consecutive_count += 1;
+        } else {
+            consecutive_count = 1;
+            last_ending = ending.to_string();
+        }
+
+        // Report if 3 or more consecutive same endings
+        if consecutive_count >= 3 {
+            // Highlight the ending of the current sentence
+            let end = start + sentence.trim_end().len();
+            let range = Range {
+                start: index.position(end - ending.len()),
+                end: index.position(end),
+            };
+
+            diagnostics.push(Diagnostic {
+                range,
+                code: Some(lsp_types::NumberOrString::String(
+                    "consecutive-endings".to_string(),
+                )),
+                source: Some("mozuku".to_string()),
+                message: format!(
+                    "同じ文末「{}」が{}回連続しています。文体の変化を検討してください。",
+                    last_ending, consecutive_count
+                ),
+                ..Default::default()
+            });
+
+            // Reset to avoid multiple warnings
+            consecutive_count = 1;
+        }
+    }
+
+    diagnostics
+}
+
+/// Fix for a double honorific ending in れ/られ, conjugated to match the
+/// following token: る → dictionary form, ます → 連用形, て/た → 促音便.
+/// Returns `None` when the conjugation cannot be determined.
+fn honorific_fix(
+    matched: &[TokenInfo],
+    following: Option<&TokenInfo>,
+    dictionary_form: &str,
+    masu_stem: &str,
+    index: &LineIndex,
+) -> Option<Fix> {
+    let first = matched.first()?;
+    let last = matched.last()?;
+    let following = following?;
+
+    let (new_text, end) = if following.surface == "る" {
+        (dictionary_form.to_string(), following.byte_offset + following.surface.len())
+    } else if following.base_form == "ます" {
+        (masu_stem.to_string(), last.byte_offset + last.surface.len())
+    } else if ["て", "た"].contains(&following.surface.as_str()) {
+        let stem = dictionary_form.strip_suffix('る')?;
+        (format!("{}っ", stem), last.byte_offset + last.surface.len())
+    } else {
+        return None;
+    };
+
+    Some(Fix::new(
+        Range {
+            start: index.position(first.byte_offset),
+            end: index.position(end),
+        },
+        new_text,
+    ))
+}
+
+/// Map a diagnostic from positions relative to a span's text to the document.
+///
+/// Positions are converted through byte offsets, and related information is
+/// pointed at the document URI.
+pub fn map_span_diagnostic(
+    mut diag: Diagnostic,
+    uri: &Url,
+    span_index: &LineIndex,
+    doc_index: &LineIndex,
+    span_start: usize,
+) -> Diagnostic {
+    let map_range = |range: Range| Range {
+        start: doc_index.position(span_start + span_index.offset(range.start)),
+        end: doc_index.position(span_start + span_index.offset(range.end)),
+    };
+
+    diag.range = map_range(diag.range);
+    for info in diag.related_information.iter_mut().flatten() {
+        info.location.uri = uri.clone();
+        info.location.range = map_range(info.location.range);
+    }
+
+    let fixes = diagnostic_fixes(&diag);
+    if !fixes.is_empty() {
+        diag.data = fix_data(
+            fixes
+                .into_iter()
+                .map(|fix| Fix::new(map_range(fix.range), fix.new_text))
+                .collect(),
+        );
+    }
+    diag
+}
+
+/// Warning shown at the top of a document exceeding the size limit
+pub fn large_file_diagnostic(config: &CheckerConfig, truncated: bool) -> Diagnostic {
+    let message = if truncated {
+        format!(
+            "ファイルが大きいため、先頭 {} バイトのみ解析しています（max_file_size）",
+            config.max_file_size
+        )
+    } else {
+        format!(
+            "ファイルが {} バイトを超えているため、解析をスキップしました（max_file_size）",
+            config.max_file_size
+        )
+    };
+
+    Diagnostic {
+        range: Range::default(),
+        severity: Some(DiagnosticSeverity::WARNING),
+        code: Some(lsp_types::NumberOrString::String("file-too-large".to_string())),
+        source: Some("mozuku".to_string()),
+        message,
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup_checker() -> GrammarChecker {
+        let analyzer = Arc::new(MorphologicalAnalyzer::new().unwrap());
+        GrammarChecker::new(analyzer)
+    }
+
+    #[test]
+    fn test_check_profiled() {
+        use crate::terms;
+
+        let checker = setup_checker();
+        let config = CheckerConfig {
+            consecutive_no: false,
+            ..CheckerConfig::default()
+        };
+        let text = "私がが行く";
+        let mut profile = CheckProfile::default();
+
+        let diagnostics = checker.check_profiled(text, &config, &mut profile);
+
+        assert_eq!(diagnostics, checker.check_with_config(text, &config));
+        assert!(profile.rules.contains_key("double-particle"));
+        assert!(!profile.rules.contains_key("consecutive-no"));
+        // Workspace term consistency is checked by the server
+        let enabled = rules::RULES
+            .iter()
+            .filter(|rule| config.is_rule_enabled(rule.id) && rule.id != terms::RULE_ID)
+            .count();
+        assert_eq!(profile.rules.len(), enabled);
+
+        let mut total = profile.clone();
+        total.merge(&profile);
+        assert_eq!(total.tokenize, profile.tokenize * 2);
+        assert_eq!(total.rules["double-particle"], profile.rules["double-particle"] * 2);
+    }
+
+    #[test]
+    fn test_registered_rules_are_implemented() {
+        use crate::config::RuleSetting;
+        use crate::{readability, terms};
+
+        let checker = setup_checker();
+        let config = CheckerConfig {
+            rules: rules::RULES
+                .iter()
+                .map(|rule| (rule.id.to_string(), RuleSetting::Enabled(true)))
+                .collect(),
+            ..CheckerConfig::default()
+        };
+        let mut profile = CheckProfile::default();
+        checker.check_profiled("私は行く。", &config, &mut profile);
+
+        // Workspace term consistency is checked by the server, readability
+        // on whole documents
+        for rule in rules::RULES {
+            let checked = profile.rules.contains_key(rule.id);
+            assert_eq!(
+                checked,
+                rule.id != terms::RULE_ID && rule.id != readability::RULE_ID,
+                "{}",
+                rule.id
+            );
+        }
+    }
+
+    #[test]
+    fn test_double_particle() {
+        let checker = setup_checker();
+        let text = "私がが行く";
+        let diagnostics = checker.check(text);
+
+        assert!(!diagnostics.is_empty());
+        assert!(diagnostics
+            .iter()
+            .any(|d| d.message.contains("助詞") && d.message.contains("重複")));
+    }
+
+    #[test]
+    fn test_check_with_config_skips_disabled_rules() {
+        let checker = setup_checker();
+        let config = CheckerConfig {
+            double_particle: false,
+            ..Default::default()
+        };
+        let diagnostics = checker.check_with_config("私がが行く", &config);
+
+        let code = Some(lsp_types::NumberOrString::String("double-particle".to_string()));
+        assert!(!diagnostics.iter().any(|d| d.code == code));
+    }
+
+    #[test]
+    fn test_no_false_positive() {
+        let checker = setup_checker();
+        let text = "私は本を読む";
+        let diagnostics = checker.check(text);
+
+        // Should have no errors for correct text
+        let errors: Vec<_> = diagnostics
+            .iter()
+            .filter(|d| d.severity == Some(DiagnosticSeverity::ERROR))
+            .collect();
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn test_false_positives() {
+        use crate::config::FalsePositive;
+
+        let checker = setup_checker();
+        let text = "あれも見れる。";
+        let index = LineIndex::new(text);
+        let ra_nuki_words = |config: &CheckerConfig| -> Vec<String> {
+            checker
+                .check_with_config(text, config)
+                .iter()
+                .filter(|d| d.code == Some(lsp_types::NumberOrString::String("ra-nuki".to_string())))
+                .map(|d| text[index.offset(d.range.start)..index.offset(d.range.end)].to_string())
+                .collect()
+        };
+        let words = ra_nuki_words(&CheckerConfig::default());
+        assert!(!words.is_empty());
+
+        let mut config = CheckerConfig::default();
+        config.false_positives = words
+            .iter()
+            .map(|word| FalsePositive {
+                rule: "ra-nuki".to_string(),
+                word: word.clone(),
+            })
+            .collect();
+        assert!(ra_nuki_words(&config).is_empty());
+
+        // Other rules still report the word
+        for fp in &mut config.false_positives {
+            fp.rule = "double-particle".to_string();
+        }
+        assert_eq!(ra_nuki_words(&config), words);
+    }
+
+    // ==========================================
+    // Phase 3: 追加文法ルールのテスト
+    // ==========================================
+
+    #[test]
+    fn test_double_honorific_osshareru() {
+        // おっしゃられる → おっしゃる（二重敬語）
+        let checker = setup_checker();
+        let text = "先生がおっしゃられました";
+        let diagnostics = checker.check(text);
+
+        assert!(
+            diagnostics.iter().any(|d| d.message.contains("二重敬語")),
+            "Should detect double honorific: {:?}",
+            diagnostics
+        );
+    }
+
+    #[test]
+    fn test_double_honorific_goranninaru() {
+        // ご覧になられる → ご覧になる（二重敬語）
+        let checker = setup_checker();
+        let text = "資料をご覧になられてください";
+        let diagnostics = checker.check(text);
+
+        assert!(
+            diagnostics.iter().any(|d| d.message.contains("二重敬語")),
+            "Should detect double honorific: {:?}",
+            diagnostics
+        );
+    }
+
+    #[test]
+    fn test_redundant_suru_koto_ga_dekiru() {
+        // することができる → できる
+        let checker = setup_checker();
+        let text = "私は泳ぐことができます";
+        let diagnostics = checker.check(text);
+
+        assert!(
+            diagnostics.iter().any(|d| d.message.contains("冗長")),
+            "Should detect redundant expression: {:?}",
+            diagnostics
+        );
+    }
+
+    #[test]
+    fn test_redundant_koto_ga_kanou() {
+        // ことが可能 → できる
+        let checker = setup_checker();
+        let text = "参加することが可能です";
+        let diagnostics = checker.check(text);
+
+        let redundant = diagnostics.iter().find(|d| d.message.contains("冗長"));
+        assert!(
+            redundant.is_some(),
+            "Should detect redundant expression: {:?}",
+            diagnostics
+        );
+        assert_eq!(diagnostic_fixes(redundant.unwrap())[0].new_text, "できます");
+    }
+
+    #[test]
+    fn test_degraded_analyzer_keeps_text_rules() {
+        let checker = GrammarChecker::new(Arc::new(MorphologicalAnalyzer::degraded()));
+        let diagnostics = checker.check("私は学生です。彼も学生です。彼女も学生です。見れる");
+
+        let codes: Vec<_> = diagnostics.iter().filter_map(|d| d.code.clone()).collect();
+        assert_eq!(
+            codes,
+            vec![lsp_types::NumberOrString::String("consecutive-endings".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_consecutive_sentence_endings() {
+        // 連続する同じ文末
+        let checker = setup_checker();
+        let text = "私は学生です。彼も学生です。彼女も学生です。";
+        let diagnostics = checker.check(text);
+
+        assert!(
+            diagnostics.iter().any(|d| d.message.contains("文末") || d.message.contains("連続")),
+            "Should detect consecutive same endings: {:?}",
+            diagnostics
+        );
+    }
+
+    #[test]
+    fn test_tari_parallel_incomplete() {
+        // たり〜たり の不完全な並列
+        let checker = setup_checker();
+        let text = "歩いたり走る";
+        let diagnostics = checker.check(text);
+
+        assert!(
+            diagnostics.iter().any(|d| d.message.contains("たり")),
+            "Should detect incomplete tari parallel: {:?}",
+            diagnostics
+        );
+    }
+
+    #[test]
+    fn test_tari_parallel_correct() {
+        // 正しい「たり〜たり」
+        let checker = setup_checker();
+        let text = "歩いたり走ったりする";
+        let diagnostics = checker.check(text);
+
+        // This should NOT trigger tari error
+        let tari_errors: Vec<_> = diagnostics
+            .iter()
+            .filter(|d| d.message.contains("たり"))
+            .collect();
+        assert!(
+            tari_errors.is_empty(),
+            "Should not flag correct tari usage: {:?}",
+            tari_errors
+        );
+    }
+
+    #[test]
+    fn test_hedge_endings() {
+        let config = CheckerConfig::default();
+        let hedged = |text: &str| -> Vec<String> {
+            let index = LineIndex::new(text);
+            check_hedge_endings(text, &index, &config)
+                .iter()
+                .map(|d| text[index.offset(d.range.start)..index.offset(d.range.end)].to_string())
+                .collect()
+        };
+
+        let text = "原因は設定だと思います。修正で直ると考えられます。次に手順を示す。";
+        assert_eq!(hedged(text), ["と思います", "と考えられます"]);
+        // At most 20% of the sentences, or a single hedge
+        assert!(hedged("原因は設定だと思います。手順を示す。結果を示す。考察を示す。まとめる。").is_empty());
+        assert!(hedged("直ると思います。").is_empty());
+
+        let config = CheckerConfig {
+            max_hedge_ratio: 0.8,
+            ..CheckerConfig::default()
+        };
+        let index = LineIndex::new(text);
+        assert!(check_hedge_endings(text, &index, &config).is_empty());
+    }
+
+    #[test]
+    fn test_consecutive_no_particles() {
+        // 「の」の連続使用
+        let checker = setup_checker();
+        let text = "私の友達の本の内容";
+        let diagnostics = checker.check(text);
+
+        assert!(
+            diagnostics.iter().any(|d| d.message.contains("の") && d.message.contains("連続")),
+            "Should detect consecutive no particles: {:?}",
+            diagnostics
+        );
+    }
+
+    #[test]
+    fn test_two_no_particles_ok() {
+        // 2つまでの「の」は許容
+        let checker = setup_checker();
+        let text = "私の本の内容";
+        let diagnostics = checker.check(text);
+
+        // Should not trigger for just 2 consecutive の
+        let no_errors: Vec<_> = diagnostics
+            .iter()
+            .filter(|d| d.message.contains("の") && d.message.contains("連続"))
+            .collect();
+        assert!(
+            no_errors.is_empty(),
+            "Should allow 2 consecutive no: {:?}",
+            no_errors
+        );
+    }
+
+    #[test]
+    fn test_rule_severity() {
+        use crate::config::RuleSetting;
+        use crate::pattern::PatternSeverity;
+
+        let checker = setup_checker();
+        let ra_nuki = |config: &CheckerConfig| {
+            checker
+                .check_with_config("これを食べれる。", config)
+                .into_iter()
+                .find(|d| d.code == Some(lsp_types::NumberOrString::String("ra-nuki".to_string())))
+                .unwrap()
+        };
+
+        // The default severity comes from the registry
+        let mut config = CheckerConfig::default();
+        assert_eq!(ra_nuki(&config).severity, Some(DiagnosticSeverity::WARNING));
+
+        config
+            .rules
+            .insert("ra-nuki".to_string(), RuleSetting::Severity(PatternSeverity::Error));
+        assert_eq!(ra_nuki(&config).severity, Some(DiagnosticSeverity::ERROR));
+    }
+
+    #[test]
+    fn test_pattern_rules() {
+        use crate::config::RuleSetting;
+
+        let checker = setup_checker();
+        let mut config: CheckerConfig = toml::from_str(
+            r#"
+[[patterns]]
+id = "kudasai"
+message = "「$0」はひらがなで書きます"
+severity = "hint"
+pattern = "表層=下さい"
+replacement = "ください"
+"#,
+        )
+        .unwrap();
+        let diagnostics = checker.check_with_config("資料を送って下さい。", &config);
+
+        let pattern: Vec<_> = diagnostics
+            .iter()
+            .filter(|d| d.code == Some(lsp_types::NumberOrString::String("kudasai".to_string())))
+            .collect();
+        assert_eq!(pattern.len(), 1, "{:?}", diagnostics);
+        assert_eq!(pattern[0].message, "「下さい」はひらがなで書きます");
+        assert_eq!(pattern[0].severity, Some(DiagnosticSeverity::HINT));
+        assert_eq!(diagnostic_fixes(pattern[0])[0].new_text, "ください");
+
+        config.rules.insert("kudasai".to_string(), RuleSetting::Enabled(false));
+        let diagnostics = checker.check_with_config("資料を送って下さい。", &config);
+        let kudasai = Some(lsp_types::NumberOrString::String("kudasai".to_string()));
+        assert!(diagnostics.iter().all(|d| d.code != kudasai), "{:?}", diagnostics);
+    }
+
+    #[test]
+    fn test_textlint_rules() {
+        fn code(diagnostic: &Diagnostic) -> Option<&str> {
+            match &diagnostic.code {
+                Some(lsp_types::NumberOrString::String(code)) => Some(code),
+                _ => None,
+            }
+        }
+
+        let checker = setup_checker();
+        let config = CheckerConfig {
+            mix_dearu_desumasu: true,
+            hankaku_kana: true,
+            zenkaku_alphanumerics: true,
+            weak_phrase: true,
+            ..CheckerConfig::default()
+        };
+        let text = "今日は晴れです。明日は雨だ。ﾃﾞｰﾀが消えるかもしれません。ＡＰＩは１０個です。";
+        let diagnostics = checker.check_with_config(text, &config);
+
+        let mix = diagnostics.iter().find(|d| code(d) == Some("no-mix-dearu-desumasu"));
+        assert!(mix.is_some(), "{:?}", diagnostics);
+        assert_eq!(diagnostic_fixes(mix.unwrap())[0].new_text, "です");
+        assert!(diagnostics.iter().any(|d| code(d) == Some("ja-no-weak-phrase")));
+        let hankaku = diagnostics.iter().find(|d| code(d) == Some("no-hankaku-kana")).unwrap();
+        assert_eq!(diagnostic_fixes(hankaku)[0].new_text, "データ");
+        let zenkaku: Vec<_> = diagnostics
+            .iter()
+            .filter(|d| code(d) == Some("no-zenkaku-alphanumerics"))
+            .map(|d| diagnostic_fixes(d)[0].new_text.clone())
+            .collect();
+        assert_eq!(zenkaku, ["API", "10"]);
+        // Off by default
+        assert!(checker.check(text).iter().all(|d| code(d) != Some("no-hankaku-kana")));
+    }
+
+    #[test]
+    fn test_kanji_number() {
+        assert_eq!(kanji_number("三"), Some(3));
+        assert_eq!(kanji_number("二十五"), Some(25));
+        assert_eq!(kanji_number("百五"), Some(105));
+        assert_eq!(kanji_number("三千二百"), Some(3200));
+        assert_eq!(kanji_number("一万二千"), Some(12_000));
+        assert_eq!(kanji_number("二〇二四"), Some(2024));
+        // 二、三日
+        assert_eq!(kanji_number("二三"), None);
+        assert_eq!(kanji_number("五三十"), None);
+    }
+
+    #[test]
+    fn test_kanji_numerals() {
+        let checker = setup_checker();
+        let config = CheckerConfig {
+            kanji_numerals: true,
+            ..CheckerConfig::default()
+        };
+        let kanji_numerals = Some(lsp_types::NumberOrString::String("kanji-numerals".to_string()));
+
+        let diagnostics = checker.check_with_config("申請は二十五件あった。", &config);
+        let numerals: Vec<_> = diagnostics.iter().filter(|d| d.code == kanji_numerals).collect();
+        assert_eq!(numerals.len(), 1, "{:?}", diagnostics);
+        assert_eq!(diagnostic_fixes(numerals[0])[0].new_text, "25");
+
+        for text in ["一般的な方法を唯一の手段とする。", "二三日で届く。"] {
+            let diagnostics = checker.check_with_config(text, &config);
+            let reported = diagnostics.iter().any(|d| d.code == kanji_numerals);
+            assert!(!reported, "{:?}", diagnostics);
+        }
+        // Off by default
+        let diagnostics = checker.check("申請は二十五件あった。");
+        assert!(diagnostics.iter().all(|d| d.code != kanji_numerals));
+    }
+
+    #[test]
+    fn test_missing_particle() {
+        use crate::config::RuleSetting;
+        use crate::pattern::PatternSeverity;
+
+        let checker = setup_checker();
+        let mut config = CheckerConfig {
+            missing_particle: true,
+            ..CheckerConfig::default()
+        };
+        let missing_particle = Some(lsp_types::NumberOrString::String("missing-particle".to_string()));
+
+        let diagnostics = checker.check_with_config("資料送ります。", &config);
+        let missing: Vec<_> = diagnostics.iter().filter(|d| d.code == missing_particle).collect();
+        assert_eq!(missing.len(), 1, "{:?}", diagnostics);
+        assert_eq!(missing[0].severity, Some(DiagnosticSeverity::INFORMATION));
+
+        for text in ["資料を送ります。", "明日行きます。", "毎日勉強します。"] {
+            let diagnostics = checker.check_with_config(text, &config);
+            let reported = diagnostics.iter().any(|d| d.code == missing_particle);
+            assert!(!reported, "{:?}", diagnostics);
+        }
+
+        // Raised for written documents
+        config.rules.insert(
+            "missing-particle".to_string(),
+            RuleSetting::Severity(PatternSeverity::Warning),
+        );
+        let diagnostics = checker.check_with_config("資料送ります。", &config);
+        let missing = diagnostics.iter().find(|d| d.code == missing_particle).unwrap();
+        assert_eq!(missing.severity, Some(DiagnosticSeverity::WARNING));
+    }
+
+    #[test]
+    fn test_english_spelling() {
+        let checker = setup_checker();
+        let config = CheckerConfig {
+            english_spelling: true,
+            ..CheckerConfig::default()
+        };
+        let text = "設定ファイルの cofig を開く。";
+        let diagnostics = checker.check_with_config(text, &config);
+
+        let spelling = diagnostics
+            .iter()
+            .find(|d| d.code == Some(lsp_types::NumberOrString::String("english-spelling".into())))
+            .unwrap();
+        assert_eq!(diagnostic_fixes(spelling)[0].new_text, "config");
+        // Off by default
+        assert!(checker.check(text).iter().all(|d| !d.message.contains("cofig")));
+    }
+
+    fn token(surface: &str, pos: &str) -> TokenInfo {
+        TokenInfo {
+            surface: surface.to_string(),
+            pos: pos.to_string(),
+            pos_detail1: "*".to_string(),
+            pos_detail2: "*".to_string(),
+            pos_detail3: "*".to_string(),
+            conjugation_type: "*".to_string(),
+            conjugation_form: "*".to_string(),
+            base_form: surface.to_string(),
+            reading: String::new(),
+            pronunciation: String::new(),
+            byte_offset: 0,
+            char_offset: 0,
+            char_length: surface.chars().count(),
+        }
+    }
+
+    fn katakana_variants(tokens: &[TokenInfo]) -> Vec<(usize, usize)> {
+        let mut rule = NotationVariance::default();
+        for i in 0..tokens.len() {
+            rule.collect(tokens, i);
+        }
+        rule.variants(tokens)
+    }
+
+    #[test]
+    fn test_tari_parallel_done_after_pair() {
+        let tokens = vec![
+            token("歩い", "動詞"),
+            token("たり", "助詞"),
+            token("走っ", "動詞"),
+            token("たり", "助詞"),
+            token("する", "動詞"),
+        ];
+        let index = LineIndex::new("");
+        let ctx = RuleContext {
+            tokens: &tokens,
+            index: &index,
+        };
+        let mut rule = TariParallel::default();
+        let mut diagnostics = Vec::new();
+
+        let steps: Vec<Step> = (0..4).map(|i| rule.step(&ctx, i, &mut diagnostics)).collect();
+        rule.finish(&ctx, &mut diagnostics);
+
+        assert_eq!(steps, vec![Step::Continue, Step::Continue, Step::Continue, Step::Done]);
+        assert!(diagnostics.is_empty());
+    }
+
+    #[test]
+    fn test_katakana_variants() {
+        let tokens = vec![
+            token("サーバー", "名詞"),
+            token("と", "助詞"),
+            token("サーバ", "名詞"),
+            token("の", "助詞"),
+            token("サーバー", "名詞"),
+        ];
+
+        assert_eq!(katakana_variants(&tokens), vec![(2, 0)]);
+    }
+
+    #[test]
+    fn test_katakana_variants_prefers_long_vowel_on_tie() {
+        let tokens = vec![token("ユーザ", "名詞"), token("ユーザー", "名詞")];
+
+        assert_eq!(katakana_variants(&tokens), vec![(0, 1)]);
+    }
+
+    #[test]
+    fn test_katakana_variants_consistent() {
+        let tokens = vec![token("サーバー", "名詞"), token("データ", "名詞"), token("サーバー", "名詞")];
+
+        assert!(katakana_variants(&tokens).is_empty());
+    }
+
+    fn script_variants(tokens: &[TokenInfo]) -> Vec<(usize, usize)> {
+        let mut rule = ScriptVariance::default();
+        for i in 0..tokens.len() {
+            rule.collect(tokens, i);
+        }
+        rule.variants(tokens)
+    }
+
+    fn noun(surface: &str, pronunciation: &str) -> TokenInfo {
+        let mut token = token(surface, "名詞");
+        token.pronunciation = pronunciation.to_string();
+        token
+    }
+
+    #[test]
+    fn test_script_variants() {
+        let tokens = vec![
+            noun("子供", "コドモ"),
+            noun("こども", "コドモ"),
+            noun("子供", "コドモ"),
+            noun("携帯", "ケータイ"),
+            noun("ケータイ", ""),
+        ];
+
+        assert_eq!(script_variants(&tokens), vec![(1, 0), (4, 3)]);
+    }
+
+    #[test]
+    fn test_script_variants_skip_homophones() {
+        // Kanji spellings of the same reading are different words
+        let tokens = vec![
+            noun("意志", "イシ"),
+            noun("意思", "イシ"),
+            noun("機会", "キカイ"),
+            noun("機械", "キカイ"),
+        ];
+        assert!(script_variants(&tokens).is_empty());
+
+        // Too short to tell apart
+        let tokens = vec![noun("橋", "ハシ"), noun("はし", "ハシ")];
+        assert!(script_variants(&tokens).is_empty());
+    }
+
+    /// Tokens laid out consecutively, as (surface, base form)
+    fn sentence(parts: &[(&str, &str)]) -> (String, Vec<TokenInfo>) {
+        let mut text = String::new();
+        let mut tokens = Vec::new();
+        for (surface, base_form) in parts {
+            let mut t = token(surface, "動詞");
+            t.base_form = base_form.to_string();
+            t.byte_offset = text.len();
+            text.push_str(surface);
+            tokens.push(t);
+        }
+        (text, tokens)
+    }
+
+    fn apply(text: &str, fix: &Fix) -> String {
+        let index = LineIndex::new(text);
+        let (start, end) = (index.offset(fix.range.start), index.offset(fix.range.end));
+        format!("{}{}{}", &text[..start], fix.new_text, &text[end..])
+    }
+
+    #[test]
+    fn test_honorific_fix_conjugates() {
+        let cases = [
+            (vec![("おっしゃら", "おっしゃる"), ("れ", "れる"), ("まし", "ます"), ("た", "た")], "おっしゃいました"),
+            (vec![("おっしゃら", "おっしゃる"), ("れ", "れる"), ("る", "る")], "おっしゃる"),
+            (vec![("おっしゃら", "おっしゃる"), ("れ", "れる"), ("た", "た")], "おっしゃった"),
+        ];
+
+        for (parts, expected) in cases {
+            let (text, tokens) = sentence(&parts);
+            let index = LineIndex::new(&text);
+            let fix = honorific_fix(&tokens[..2], tokens.get(2), "おっしゃる", "おっしゃい", &index).unwrap();
+            assert_eq!(apply(&text, &fix), expected);
+        }
+    }
+
+    /// Text and tokens of 一段 verbs: (surface, 品詞細分類1, 活用形, 基本形, 読み)
+    fn ichidan(parts: &[(&str, &str, &str, &str, &str)]) -> (String, Vec<TokenInfo>) {
+        let surfaces: Vec<_> = parts.iter().map(|(surface, _, _, base, _)| (*surface, *base)).collect();
+        let (text, mut tokens) = sentence(&surfaces);
+        for (token, (_, detail1, form, _, reading)) in tokens.iter_mut().zip(parts) {
+            token.pos_detail1 = detail1.to_string();
+            token.conjugation_type = "一段".to_string();
+            token.conjugation_form = form.to_string();
+            token.reading = reading.to_string();
+        }
+        (text, tokens)
+    }
+
+    /// Texts fixed with each diagnostic of a token rule
+    fn fixed<R: for<'a> TokenRule<'a>>(mut rule: R, text: &str, tokens: &[TokenInfo]) -> Vec<String> {
+        let index = LineIndex::new(text);
+        let ctx = RuleContext { tokens, index: &index };
+        let mut diagnostics = Vec::new();
+        for i in 0..tokens.len() {
+            rule.step(&ctx, i, &mut diagnostics);
+        }
+        diagnostics
+            .iter()
+            .map(|d| apply(text, &diagnostic_fixes(d)[0]))
+            .collect()
+    }
+
+    #[test]
+    fn test_ra_nuki_fix_conjugates() {
+        let cases = [
+            (
+                vec![
+                    ("食べ", "自立", "連用形", "食べる", "タベ"),
+                    ("れ", "接尾", "連用形", "れる", "レ"),
+                    ("た", "*", "*", "た", "タ"),
+                ],
+                "食べられた",
+            ),
+            (
+                vec![
+                    ("食べ", "自立", "未然形", "食べる", "タベ"),
+                    ("れれ", "接尾", "仮定形", "れる", "レレ"),
+                    ("ば", "*", "*", "ば", "バ"),
+                ],
+                "食べられれば",
+            ),
+            (
+                vec![
+                    ("見れ", "自立", "連用形", "見れる", "ミレ"),
+                    ("た", "*", "*", "た", "タ"),
+                ],
+                "見られた",
+            ),
+            (vec![("来れる", "自立", "基本形", "来れる", "コレル")], "来られる"),
+        ];
+        for (parts, expected) in cases {
+            let (text, tokens) = ichidan(&parts);
+            assert_eq!(fixed(RaNuki, &text, &tokens), [expected]);
+        }
+
+        // Regular verbs in れる and the 仮定形 of 食べる
+        for parts in [
+            vec![
+                ("入れ", "自立", "連用形", "入れる", "イレ"),
+                ("た", "*", "*", "た", "タ"),
+            ],
+            vec![
+                ("忘れ", "自立", "連用形", "忘れる", "ワスレ"),
+                ("た", "*", "*", "た", "タ"),
+            ],
+            vec![
+                ("食べれ", "自立", "仮定形", "食べる", "タベレ"),
+                ("ば", "*", "*", "ば", "バ"),
+            ],
+        ] {
+            let (text, tokens) = ichidan(&parts);
+            assert!(fixed(RaNuki, &text, &tokens).is_empty(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn test_i_nuki_fix_conjugates() {
+        let cases = [
+            (
+                vec![
+                    ("し", "自立", "連用形", "する", "シ"),
+                    ("て", "非自立", "連用形", "てる", "テ"),
+                    ("た", "*", "*", "た", "タ"),
+                ],
+                "していた",
+            ),
+            (
+                vec![
+                    ("し", "自立", "連用形", "する", "シ"),
+                    ("てれ", "非自立", "仮定形", "てる", "テレ"),
+                    ("ば", "*", "*", "ば", "バ"),
+                ],
+                "していれば",
+            ),
+            (
+                vec![
+                    ("読ん", "自立", "連用タ接続", "読む", "ヨン"),
+                    ("でる", "非自立", "基本形", "でる", "デル"),
+                ],
+                "読んでいる",
+            ),
+        ];
+        for (parts, expected) in cases {
+            let (text, tokens) = ichidan(&parts);
+            assert_eq!(fixed(INuki, &text, &tokens), [expected]);
+        }
+    }
+
+    #[test]
+    fn test_honorific_fix_unknown_conjugation() {
+        let (text, tokens) = sentence(&[("おっしゃら", "おっしゃる"), ("れ", "れる"), ("ず", "ず")]);
+        let index = LineIndex::new(&text);
+
+        assert_eq!(honorific_fix(&tokens[..2], tokens.get(2), "おっしゃる", "おっしゃい", &index), None);
+        assert_eq!(honorific_fix(&tokens[..2], None, "おっしゃる", "おっしゃい", &index), None);
+    }
+
+    #[test]
+    fn test_diagnostic_fixes_round_trip() {
+        let fix = Fix::new(Range::default(), "られる");
+        let diagnostic = Diagnostic {
+            data: fix_data(vec![fix.clone()]),
+            ..Default::default()
+        };
+
+        assert_eq!(diagnostic_fixes(&diagnostic), vec![fix]);
+        assert!(diagnostic_fixes(&Diagnostic::default()).is_empty());
+    }
+
+    #[test]
+    fn test_fix_all() {
+        let text = "これを見れる。あれも見れる。";
+        let fix = |start: u32, end: u32, new_text: &str| Diagnostic {
+            range: Range::new(Position::new(0, start), Position::new(0, end)),
+            data: fix_data(vec![Fix::new(
+                Range::new(Position::new(0, start), Position::new(0, end)),
+                new_text,
+            )]),
+            ..Default::default()
+        };
+        let diagnostics = vec![
+            fix(10, 13, "見られる"),
+            fix(3, 6, "見られる"),
+            fix(4, 6, "られる"),
+            fix(0, 2, "これ"),
+            Diagnostic::default(),
+        ];
+
+        let fixes = fix_all(text, &diagnostics);
+        let applied: Vec<(u32, &str)> = fixes
+            .iter()
+            .map(|(_, fix)| (fix.range.start.character, fix.new_text.as_str()))
+            .collect();
+        assert_eq!(applied, [(3, "見られる"), (10, "見られる")]);
+    }
+
+    #[test]
+    fn test_merge_overlapping() {
+        fn diagnostic(code: &str, severity: DiagnosticSeverity, start: u32, end: u32, fix: Option<&str>) -> Diagnostic {
+            let range = Range::new(Position::new(0, start), Position::new(0, end));
+            Diagnostic {
+                range,
+                severity: Some(severity),
+                code: Some(lsp_types::NumberOrString::String(code.to_string())),
+                message: format!("{} の指摘", code),
+                data: fix.and_then(|fix| fix_data(vec![Fix::new(range, fix)])),
+                ..Default::default()
+            }
+        }
+
+        let diagnostics = vec![
+            diagnostic("redundant-expression", DiagnosticSeverity::HINT, 0, 4, Some("できる")),
+            diagnostic("ra-nuki", DiagnosticSeverity::WARNING, 0, 4, Some("見られる")),
+            diagnostic("consecutive-no", DiagnosticSeverity::WARNING, 5, 8, None),
+            diagnostic("ra-nuki", DiagnosticSeverity::WARNING, 0, 4, Some("見られる")),
+            diagnostic("my-pattern", DiagnosticSeverity::WARNING, 0, 4, None),
+        ];
+
+        let merged = merge_overlapping(diagnostics);
+
+        assert_eq!(merged.len(), 2);
+        assert_eq!(merged[0].message, "ra-nuki の指摘");
+        let fixes: Vec<String> = diagnostic_fixes(&merged[0])
+            .into_iter()
+            .map(|fix| fix.new_text)
+            .collect();
+        assert_eq!(fixes, ["見られる", "できる"]);
+        let related: Vec<&str> = merged[0]
+            .related_information
+            .iter()
+            .flatten()
+            .map(|info| info.message.as_str())
+            .collect();
+        assert_eq!(related, ["my-pattern の指摘", "冗長表現: redundant-expression の指摘"]);
+        assert_eq!(merged[1].message, "consecutive-no の指摘");
+        assert!(merged[1].related_information.is_none());
+    }
+
+    #[test]
+    fn test_map_span_diagnostic() {
+        let content = "# 見出し\n\n😀サーバとサーバー";
+        let span_text = "😀サーバとサーバー";
+        let span_start = content.find(span_text).unwrap();
+        let uri = Url::parse("file:///test.md").unwrap();
+
+        let diag = Diagnostic {
+            range: Range {
+                start: Position { line: 0, character: 2 },
+                end: Position { line: 0, character: 5 },
+            },
+            related_information: Some(vec![DiagnosticRelatedInformation {
+                location: Location {
+                    uri: Url::parse(crate::checker::CHECKED_TEXT_URI).unwrap(),
+                    range: Range {
+                        start: Position { line: 0, character: 6 },
+                        end: Position { line: 0, character: 10 },
+                    },
+                },
+                message: String::new(),
+            }]),
+            data: fix_data(vec![Fix::new(
+                Range {
+                    start: Position { line: 0, character: 2 },
+                    end: Position { line: 0, character: 5 },
+                },
+                "サーバー",
+            )]),
+            ..Default::default()
+        };
+
+        let mapped = map_span_diagnostic(
+            diag,
+            &uri,
+            &LineIndex::new(span_text),
+            &LineIndex::new(content),
+            span_start,
+        );
+
+        assert_eq!(mapped.range.start, Position { line: 2, character: 2 });
+        assert_eq!(diagnostic_fixes(&mapped)[0].range, mapped.range);
+        let related = &mapped.related_information.unwrap()[0];
+        assert_eq!(related.location.uri, uri);
+        assert_eq!(related.location.range.start, Position { line: 2, character: 6 });
+    }
+
+    #[test]
+    fn test_large_file_diagnostic() {
+        let config = CheckerConfig::default();
+
+        assert!(large_file_diagnostic(&config, true).message.contains("先頭"));
+        assert!(large_file_diagnostic(&config, false).message.contains("スキップ"));
+    }
+}
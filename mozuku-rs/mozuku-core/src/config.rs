@@ -0,0 +1,2270 @@
+//! Configuration management for MoZuku LSP server
+//!
+//! Handles loading and parsing of `mozuku.toml` configuration file.
+
+use anyhow::{anyhow, Result};
+use lsp_types::DiagnosticSeverity;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use toml_edit::{Array, ArrayOfTables, DocumentMut, Item, Table};
+
+use crate::extractor::FileType;
+use crate::pattern::{PatternRule, PatternSeverity};
+use crate::preset;
+use crate::rules;
+use crate::textlint;
+use crate::workspace::Exclusions;
+
+/// Main configuration structure
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct Config {
+    /// Bundled settings preset applied under the settings of the file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preset: Option<Preset>,
+
+    /// LLM provider settings
+    #[serde(default)]
+    pub llm: LlmConfig,
+
+    /// Grammar checker settings
+    #[serde(default)]
+    pub checker: CheckerConfig,
+
+    /// Notation dictionary settings
+    #[serde(default)]
+    pub dictionary: DictionaryConfig,
+
+    /// Glossary settings
+    #[serde(default)]
+    pub glossary: GlossaryConfig,
+
+    /// On-type formatting settings
+    #[serde(default)]
+    pub format: FormatConfig,
+
+    /// Local rule statistics settings
+    #[serde(default)]
+    pub telemetry: TelemetryConfig,
+}
+
+/// LLM provider configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LlmConfig {
+    /// LLM provider: "claude", "openai", "azure", "bedrock", "openai-compatible", "ollama", or "none"
+    #[serde(default = "default_provider")]
+    pub provider: String,
+
+    /// API key (can also be set via environment variable)
+    #[serde(default)]
+    pub api_key: Option<String>,
+
+    /// Model name (e.g., "claude-3-5-sonnet-20241022", "gpt-4o", "llama3.1")
+    #[serde(default)]
+    pub model: Option<String>,
+
+    /// API base URL (for custom endpoints)
+    #[serde(default)]
+    pub base_url: Option<String>,
+
+    /// Maximum tokens for response
+    #[serde(default = "default_max_tokens")]
+    pub max_tokens: u32,
+
+    /// Number of candidates offered as AI code actions
+    #[serde(default = "default_candidates")]
+    pub candidates: usize,
+
+    /// Sampling temperature (provider default when unset)
+    #[serde(default)]
+    pub temperature: Option<f32>,
+
+    /// Nucleus sampling probability (provider default when unset)
+    #[serde(default)]
+    pub top_p: Option<f32>,
+
+    /// System prompt sent with every request
+    #[serde(default)]
+    pub system_prompt: Option<String>,
+
+    /// Sequences that stop the generation
+    #[serde(default)]
+    pub stop: Vec<String>,
+
+    /// Explain diagnostics with the LLM on hover (explanations are cached)
+    #[serde(default)]
+    pub explain_on_hover: bool,
+
+    /// Maximum number of issues fixed in one request by the batch fix action
+    #[serde(default = "default_batch_size")]
+    pub batch_size: usize,
+
+    /// Additional HTTP headers sent with every request
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+
+    /// Azure OpenAI deployment name (defaults to the model name)
+    #[serde(default)]
+    pub deployment: Option<String>,
+
+    /// Azure OpenAI API version
+    #[serde(default)]
+    pub api_version: Option<String>,
+
+    /// AWS region for Bedrock (defaults to `AWS_REGION` / `AWS_DEFAULT_REGION`)
+    #[serde(default)]
+    pub region: Option<String>,
+
+    /// Maximum number of retries for rate-limited, failed or timed-out requests
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+
+    /// Maximum number of requests in flight (defaults depend on the provider)
+    #[serde(default)]
+    pub max_concurrent_requests: Option<usize>,
+
+    /// Requests per minute limit (none by default)
+    #[serde(default)]
+    pub requests_per_minute: Option<u32>,
+
+    /// Tokens per minute limit, estimated from prompt and response length (none by default)
+    #[serde(default)]
+    pub tokens_per_minute: Option<u32>,
+
+    /// Correction examples inserted into the prompt (few-shot)
+    #[serde(default)]
+    pub examples: Vec<FewShotExample>,
+
+    /// Response cache settings
+    #[serde(default)]
+    pub cache: LlmCacheConfig,
+
+    /// AI diagnostics pass settings
+    #[serde(default)]
+    pub diagnostics: AiDiagnosticsConfig,
+
+    /// Usage tracking and monthly budget settings
+    #[serde(default)]
+    pub usage: LlmUsageConfig,
+
+    /// Masking of sensitive text sent to the LLM
+    #[serde(default)]
+    pub masking: MaskingConfig,
+
+    /// HTTP(S) proxy for LLM requests
+    #[serde(default)]
+    pub proxy: LlmProxyConfig,
+
+    /// Request schema-conforming JSON (Claude tool use, OpenAI `response_format`,
+    /// Ollama `format`) instead of relying on the prompt alone
+    #[serde(default = "default_true")]
+    pub structured_output: bool,
+
+    /// Stream responses of whole-document proofreading to show partial results
+    #[serde(default = "default_true")]
+    pub stream: bool,
+
+    /// Request timeout in seconds (defaults depend on the provider)
+    #[serde(default)]
+    pub timeout: Option<u64>,
+}
+
+impl Default for LlmConfig {
+    fn default() -> Self {
+        Self {
+            provider: default_provider(),
+            api_key: None,
+            model: None,
+            base_url: None,
+            max_tokens: default_max_tokens(),
+            candidates: default_candidates(),
+            temperature: None,
+            top_p: None,
+            system_prompt: None,
+            stop: Vec::new(),
+            explain_on_hover: false,
+            batch_size: default_batch_size(),
+            headers: BTreeMap::new(),
+            deployment: None,
+            api_version: None,
+            region: None,
+            max_retries: default_max_retries(),
+            max_concurrent_requests: None,
+            requests_per_minute: None,
+            tokens_per_minute: None,
+            examples: Vec::new(),
+            cache: LlmCacheConfig::default(),
+            diagnostics: AiDiagnosticsConfig::default(),
+            usage: LlmUsageConfig::default(),
+            masking: MaskingConfig::default(),
+            proxy: LlmProxyConfig::default(),
+            structured_output: true,
+            stream: true,
+            timeout: None,
+        }
+    }
+}
+
+/// An example correction that shows the LLM the expected style
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FewShotExample {
+    /// Text before correction
+    pub input: String,
+    /// Text after correction
+    pub output: String,
+    /// Reason for the correction
+    #[serde(default)]
+    pub explanation: Option<String>,
+}
+
+/// Configuration of the AI diagnostics pass, which asks the LLM about issues
+/// rules cannot detect when a document is saved
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AiDiagnosticsConfig {
+    /// Run the pass on save (off by default)
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Sentences shorter than this (in characters) are skipped
+    #[serde(default = "default_ai_min_chars")]
+    pub min_chars: usize,
+
+    /// Maximum number of sentences checked per pass
+    #[serde(default = "default_ai_max_sentences")]
+    pub max_sentences: usize,
+
+    /// Number of sentences sent in one request
+    #[serde(default = "default_ai_batch_size")]
+    pub batch_size: usize,
+}
+
+impl Default for AiDiagnosticsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            min_chars: default_ai_min_chars(),
+            max_sentences: default_ai_max_sentences(),
+            batch_size: default_ai_batch_size(),
+        }
+    }
+}
+
+/// LLM response cache configuration
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct LlmCacheConfig {
+    /// Cache responses for identical requests
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+
+    /// Number of responses kept in memory
+    #[serde(default = "default_cache_memory_entries")]
+    pub memory_entries: usize,
+
+    /// Also keep responses on disk across restarts. Off by default since
+    /// replies contain the text of the documents
+    #[serde(default)]
+    pub disk: bool,
+
+    /// Disk cache directory (defaults to the user cache directory)
+    #[serde(default)]
+    pub dir: Option<PathBuf>,
+
+    /// Time to live of cached responses in seconds
+    #[serde(default = "default_cache_ttl")]
+    pub ttl: u64,
+
+    /// Maximum total size of the disk cache in bytes
+    #[serde(default = "default_cache_max_disk_size")]
+    pub max_disk_size: u64,
+}
+
+impl Default for LlmCacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            memory_entries: default_cache_memory_entries(),
+            disk: false,
+            dir: None,
+            ttl: default_cache_ttl(),
+            max_disk_size: default_cache_max_disk_size(),
+        }
+    }
+}
+
+impl LlmCacheConfig {
+    /// Disk cache directory: the configured one, or `llm` in the user cache directory
+    pub fn cache_dir(&self) -> Option<PathBuf> {
+        self.dir.clone().or_else(|| {
+            directories::ProjectDirs::from("", "", "mozuku").map(|dirs| dirs.cache_dir().join("llm"))
+        })
+    }
+}
+
+/// LLM usage tracking configuration
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LlmUsageConfig {
+    /// Record token counts and estimated costs
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+
+    /// Keep the usage in a file shared across restarts and server instances
+    #[serde(default = "default_true")]
+    pub persist: bool,
+
+    /// Usage file (defaults to `usage.json` in the user data directory)
+    #[serde(default)]
+    pub file: Option<PathBuf>,
+
+    /// Monthly budget in USD; AI features stop once the estimated cost reaches it
+    #[serde(default)]
+    pub monthly_budget: Option<f64>,
+
+    /// Price of input tokens in USD per million (overrides the built-in price of the model)
+    #[serde(default)]
+    pub input_price: Option<f64>,
+
+    /// Price of output tokens in USD per million (overrides the built-in price of the model)
+    #[serde(default)]
+    pub output_price: Option<f64>,
+}
+
+impl Default for LlmUsageConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            persist: true,
+            file: None,
+            monthly_budget: None,
+            input_price: None,
+            output_price: None,
+        }
+    }
+}
+
+impl LlmUsageConfig {
+    /// Usage file: the configured one, or `usage.json` in the user data directory
+    pub fn usage_file(&self) -> Option<PathBuf> {
+        self.file.clone().or_else(|| {
+            directories::ProjectDirs::from("", "", "mozuku").map(|dirs| dirs.data_dir().join("usage.json"))
+        })
+    }
+}
+
+/// Masking of sensitive text sent to the LLM
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct MaskingConfig {
+    /// Mask text before sending it to the LLM
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+
+    /// Mask email addresses
+    #[serde(default = "default_true")]
+    pub email: bool,
+
+    /// Mask phone numbers
+    #[serde(default = "default_true")]
+    pub phone: bool,
+
+    /// Mask strings that look like API keys or tokens
+    #[serde(default = "default_true")]
+    pub api_key: bool,
+
+    /// Additional regular expressions to mask (e.g. project code names)
+    #[serde(default)]
+    pub patterns: Vec<String>,
+}
+
+impl Default for MaskingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            email: true,
+            phone: true,
+            api_key: true,
+            patterns: Vec::new(),
+        }
+    }
+}
+
+/// HTTP(S) proxy configuration
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct LlmProxyConfig {
+    /// Proxy URL (defaults to `HTTPS_PROXY` / `ALL_PROXY`)
+    #[serde(default)]
+    pub url: Option<String>,
+
+    /// User name for Basic authentication
+    #[serde(default)]
+    pub username: Option<String>,
+
+    /// Password for Basic authentication (or `MOZUKU_PROXY_PASSWORD`)
+    #[serde(default)]
+    pub password: Option<String>,
+
+    /// Hosts that bypass the proxy, comma separated (defaults to `NO_PROXY`)
+    #[serde(default)]
+    pub no_proxy: Option<String>,
+}
+
+impl LlmProxyConfig {
+    /// Proxy URL: the configured one, or the proxy from the environment
+    pub fn proxy_url(&self) -> Option<String> {
+        self.url.clone().filter(|url| !url.is_empty()).or_else(|| {
+            ["HTTPS_PROXY", "https_proxy", "ALL_PROXY", "all_proxy"]
+                .iter()
+                .find_map(|name| std::env::var(name).ok().filter(|url| !url.is_empty()))
+        })
+    }
+
+    /// Password for Basic authentication, from the configuration or the environment
+    pub fn password(&self) -> Option<String> {
+        self.password
+            .clone()
+            .or_else(|| std::env::var("MOZUKU_PROXY_PASSWORD").ok())
+    }
+}
+
+/// Grammar checker configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckerConfig {
+    /// Enable ら抜き言葉 detection
+    #[serde(default = "default_true")]
+    pub ra_nuki: bool,
+
+    /// Enable い抜き言葉 detection
+    #[serde(default = "default_true")]
+    pub i_nuki: bool,
+
+    /// Enable double particle detection
+    #[serde(default = "default_true")]
+    pub double_particle: bool,
+
+    /// Enable detection of a particle missing between a noun and a verb
+    /// (私行きます → 私が行きます). Spoken language drops particles, so it is
+    /// reported as information unless `[checker.rules]` raises it
+    #[serde(default)]
+    pub missing_particle: bool,
+
+    /// Enable double honorific detection
+    #[serde(default = "default_true")]
+    pub double_honorific: bool,
+
+    /// Enable redundant expression detection
+    #[serde(default = "default_true")]
+    pub redundant_expression: bool,
+
+    /// Enable consecutive sentence endings detection
+    #[serde(default = "default_true")]
+    pub consecutive_endings: bool,
+
+    /// Enable detection of overused hedging sentence endings (〜と思います)
+    #[serde(default)]
+    pub hedge_endings: bool,
+
+    /// Maximum ratio of sentences ending in a hedge (0.0 - 1.0)
+    #[serde(default = "default_max_hedge_ratio")]
+    pub max_hedge_ratio: f64,
+
+    /// Enable incomplete たり parallel detection
+    #[serde(default = "default_true")]
+    pub tari_parallel: bool,
+
+    /// Enable consecutive の detection
+    #[serde(default = "default_true")]
+    pub consecutive_no: bool,
+
+    /// Enable duplicated な detection
+    #[serde(default = "default_true")]
+    pub redundant_na: bool,
+
+    /// Enable notation variance (表記ゆれ) detection
+    #[serde(default = "default_true")]
+    pub notation_variance: bool,
+
+    /// Enable detection of words read alike but written in different
+    /// scripts (こども / 子供, ケータイ / 携帯)
+    #[serde(default)]
+    pub script_variance: bool,
+
+    /// Enable detection of quantities written in kanji numerals (三つ → 3つ)
+    #[serde(default)]
+    pub kanji_numerals: bool,
+
+    /// Enable detection of numbers with thousands separators or without
+    /// 万/億/兆 (12,000円 → 1万2000円)
+    #[serde(default)]
+    pub number_grouping: bool,
+
+    /// Enable detection of units written in different ways (10GB / 10ＧＢ /
+    /// 10ギガバイト, % / ％)
+    #[serde(default)]
+    pub unit_notation: bool,
+
+    /// Space between a number and a unit symbol checked by `unit_notation`
+    #[serde(default)]
+    pub unit_spacing: UnitSpacing,
+
+    /// Enable detection of half-width commas and periods in Japanese text
+    /// (今日は,晴れ. → 今日は、晴れ。)
+    #[serde(default)]
+    pub hankaku_punctuation: bool,
+
+    /// Enable exclamation and question mark style checks (本当!？,
+    /// すごい！！！, 完了！次へ)
+    #[serde(default)]
+    pub exclamation_question_mark: bool,
+
+    /// Report any exclamation mark, for formal documents
+    #[serde(default)]
+    pub no_exclamation: bool,
+
+    /// Enable detection of parentheses in the wrong width (MoZuku(モズク)
+    /// → MoZuku（モズク）)
+    #[serde(default)]
+    pub bracket_width: bool,
+
+    /// Width of parentheses checked by `bracket_width`
+    #[serde(default)]
+    pub preferred_bracket: BracketWidth,
+
+    /// Enable detection of terms written differently across the files of
+    /// the workspace (language server only)
+    #[serde(default = "default_true")]
+    pub term_consistency: bool,
+
+    /// Synonyms that should not be mixed in a workspace
+    /// (e.g. [["ログイン", "サインイン"]]); the first term wins a tie
+    #[serde(default)]
+    pub term_groups: Vec<Vec<String>>,
+
+    /// Enable the sentence length limit (textlint sentence-length)
+    #[serde(default)]
+    pub sentence_length: bool,
+
+    /// Maximum number of characters in a sentence
+    #[serde(default = "default_max_sentence_length")]
+    pub max_sentence_length: usize,
+
+    /// Enable the limit of 読点 in a sentence (textlint max-ten)
+    #[serde(default)]
+    pub max_ten: bool,
+
+    /// Maximum number of 読点 in a sentence
+    #[serde(default = "default_max_ten_count")]
+    pub max_ten_count: usize,
+
+    /// Enable the limit of consecutive kanji (textlint max-kanji-continuous-len)
+    #[serde(default)]
+    pub max_kanji_continuous_len: bool,
+
+    /// Maximum number of consecutive kanji
+    #[serde(default = "default_max_kanji_length")]
+    pub max_kanji_length: usize,
+
+    /// Enable detection of mixed ですます and である styles
+    /// (textlint no-mix-dearu-desumasu)
+    #[serde(default)]
+    pub mix_dearu_desumasu: bool,
+
+    /// Style the sentences should end in; the majority style if unset
+    #[serde(default)]
+    pub preferred_style: Option<WritingStyle>,
+
+    /// Enable detection of two conjunctive が in a sentence
+    /// (textlint no-doubled-conjunctive-particle-ga)
+    #[serde(default)]
+    pub doubled_conjunctive_particle_ga: bool,
+
+    /// Enable detection of sentences starting with the same conjunction
+    /// (textlint no-doubled-conjunction)
+    #[serde(default)]
+    pub doubled_conjunction: bool,
+
+    /// Enable half-width katakana detection (textlint no-hankaku-kana)
+    #[serde(default)]
+    pub hankaku_kana: bool,
+
+    /// Enable full-width alphanumeric detection (textlint preset-jtf-style
+    /// 2.1.8 and 2.1.9)
+    #[serde(default)]
+    pub zenkaku_alphanumerics: bool,
+
+    /// Enable weak phrase (〜かもしれない) detection (textlint ja-no-weak-phrase)
+    #[serde(default)]
+    pub weak_phrase: bool,
+
+    /// Enable zero-width space detection (textlint no-zero-width-spaces)
+    #[serde(default)]
+    pub zero_width_spaces: bool,
+
+    /// Enable spell checking of English words in Japanese sentences
+    #[serde(default)]
+    pub english_spelling: bool,
+
+    /// User dictionaries of English words (one word per line), relative to
+    /// the directory of `mozuku.toml`
+    #[serde(default)]
+    pub english_dictionary: Vec<PathBuf>,
+
+    /// Enable diagnostics on sections above the readability thresholds
+    #[serde(default)]
+    pub readability: bool,
+
+    /// Maximum estimated school grade of a section (1.0 - 12.0)
+    #[serde(default = "default_max_grade")]
+    pub max_grade: f64,
+
+    /// Maximum average sentence length of a section, in characters
+    #[serde(default = "default_max_average_sentence_length")]
+    pub max_average_sentence_length: usize,
+
+    /// Maximum ratio of kanji in a section (0.0 - 1.0)
+    #[serde(default = "default_max_kanji_ratio")]
+    pub max_kanji_ratio: f64,
+
+    /// Maximum ratio of sentences in the passive voice in a section (0.0 - 1.0)
+    #[serde(default = "default_max_passive_ratio")]
+    pub max_passive_ratio: f64,
+
+    /// Rules enabled or disabled by ID (`[checker.rules]`), taking
+    /// precedence over the settings above, or given a severity in place of
+    /// their default one. Also applies to the IDs of pattern and plugin rules.
+    #[serde(default)]
+    pub rules: BTreeMap<String, RuleSetting>,
+
+    /// Words never reported by any rule
+    #[serde(default)]
+    pub ignore_words: Vec<String>,
+
+    /// Words never reported by one rule, marked as false positives
+    /// (`[[checker.false_positives]]`)
+    #[serde(default)]
+    pub false_positives: Vec<FalsePositive>,
+
+    /// Merge diagnostics of several rules on the same range into one
+    #[serde(default = "default_true")]
+    pub merge_overlapping: bool,
+
+    /// Maximum document size (in bytes) analyzed in full
+    #[serde(default = "default_max_file_size")]
+    pub max_file_size: usize,
+
+    /// What to do with documents larger than `max_file_size`
+    #[serde(default)]
+    pub large_file: LargeFileAction,
+
+    /// File types to check (e.g. ["markdown", "plaintext"]); empty means all
+    #[serde(default)]
+    pub file_types: Vec<FileType>,
+
+    /// Gitignore-style patterns of paths never checked in directory and
+    /// workspace-wide operations (relative to the directory of `mozuku.toml`)
+    #[serde(default)]
+    pub exclude: Vec<String>,
+
+    /// WASM modules providing custom rules (see docs/plugins.md), relative
+    /// to the directory of `mozuku.toml`
+    #[serde(default)]
+    pub plugins: Vec<PathBuf>,
+
+    /// Token pattern rules (`[[checker.patterns]]`, see docs/patterns.md)
+    #[serde(default)]
+    pub patterns: Vec<PatternRule>,
+
+    /// TOML or YAML files of token pattern rules, relative to the directory
+    /// of `mozuku.toml`
+    #[serde(default)]
+    pub pattern_files: Vec<PathBuf>,
+
+    /// textlint rules turned into the settings above (see docs/textlint.md)
+    #[serde(default)]
+    pub textlint: TextlintConfig,
+}
+
+/// A word reported by a rule that is not a mistake in this project
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FalsePositive {
+    /// Rule ID (diagnostic code), e.g. `ra-nuki`
+    pub rule: String,
+    /// Reported text, e.g. `見れる`
+    pub word: String,
+}
+
+/// Sentence ending style
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum WritingStyle {
+    /// ですます調
+    Desumasu,
+    /// である調
+    Dearu,
+}
+
+/// Space between a number and a unit symbol (10GB / 10 GB)
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum UnitSpacing {
+    /// Follow the style used most in the document
+    #[default]
+    Consistent,
+    /// No space (10GB)
+    None,
+    /// A space (10 GB)
+    Space,
+}
+
+/// Width of parentheses
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum BracketWidth {
+    /// Full width in Japanese text, half width in English
+    #[default]
+    Auto,
+    /// Full width everywhere
+    Zenkaku,
+    /// Half width everywhere
+    Hankaku,
+}
+
+/// A rule in `[checker.rules]`
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum RuleSetting {
+    /// `true` or `false`
+    Enabled(bool),
+    /// `"error"`, `"warning"`, `"information"` or `"hint"`. Whether the rule
+    /// is enabled is left to its setting, so that a preset raising a rule
+    /// does not turn it back on when the configuration disables it.
+    Severity(PatternSeverity),
+}
+
+/// textlint configuration carried over from a project using textlint
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct TextlintConfig {
+    /// `.textlintrc` (JSON or YAML) whose `rules` are applied, relative to
+    /// the directory of `mozuku.toml`
+    #[serde(default)]
+    pub textlintrc: Option<PathBuf>,
+
+    /// Rules in the format of the `rules` of `.textlintrc`, applied after
+    /// those of the file
+    #[serde(default)]
+    pub rules: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Handling of documents exceeding the size limit
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LargeFileAction {
+    /// Analyze only the first `max_file_size` bytes
+    #[default]
+    Truncate,
+    /// Skip analysis entirely
+    Skip,
+}
+
+impl Default for CheckerConfig {
+    fn default() -> Self {
+        Self {
+            ra_nuki: true,
+            i_nuki: true,
+            double_particle: true,
+            missing_particle: false,
+            double_honorific: true,
+            redundant_expression: true,
+            consecutive_endings: true,
+            hedge_endings: false,
+            max_hedge_ratio: default_max_hedge_ratio(),
+            tari_parallel: true,
+            consecutive_no: true,
+            redundant_na: true,
+            notation_variance: true,
+            script_variance: false,
+            kanji_numerals: false,
+            number_grouping: false,
+            unit_notation: false,
+            unit_spacing: UnitSpacing::default(),
+            hankaku_punctuation: false,
+            exclamation_question_mark: false,
+            no_exclamation: false,
+            bracket_width: false,
+            preferred_bracket: BracketWidth::default(),
+            term_consistency: true,
+            term_groups: Vec::new(),
+            sentence_length: false,
+            max_sentence_length: default_max_sentence_length(),
+            max_ten: false,
+            max_ten_count: default_max_ten_count(),
+            max_kanji_continuous_len: false,
+            max_kanji_length: default_max_kanji_length(),
+            mix_dearu_desumasu: false,
+            preferred_style: None,
+            doubled_conjunctive_particle_ga: false,
+            doubled_conjunction: false,
+            hankaku_kana: false,
+            zenkaku_alphanumerics: false,
+            weak_phrase: false,
+            zero_width_spaces: false,
+            english_spelling: false,
+            english_dictionary: Vec::new(),
+            readability: false,
+            max_grade: default_max_grade(),
+            max_average_sentence_length: default_max_average_sentence_length(),
+            max_kanji_ratio: default_max_kanji_ratio(),
+            max_passive_ratio: default_max_passive_ratio(),
+            rules: BTreeMap::new(),
+            ignore_words: Vec::new(),
+            false_positives: Vec::new(),
+            merge_overlapping: true,
+            max_file_size: default_max_file_size(),
+            large_file: LargeFileAction::default(),
+            file_types: Vec::new(),
+            exclude: Vec::new(),
+            plugins: Vec::new(),
+            patterns: Vec::new(),
+            pattern_files: Vec::new(),
+            textlint: TextlintConfig::default(),
+        }
+    }
+}
+
+impl CheckerConfig {
+    /// Check whether documents of the given file type are analyzed
+    pub fn is_file_type_enabled(&self, file_type: FileType) -> bool {
+        self.file_types.is_empty() || self.file_types.contains(&file_type)
+    }
+
+    /// The part of a document to analyze under the size limit.
+    /// Returns `None` if the document should be skipped entirely.
+    pub fn analysis_text<'a>(&self, content: &'a str) -> Option<&'a str> {
+        if content.len() <= self.max_file_size {
+            return Some(content);
+        }
+
+        match self.large_file {
+            LargeFileAction::Skip => None,
+            LargeFileAction::Truncate => {
+                let mut end = self.max_file_size;
+                while !content.is_char_boundary(end) {
+                    end -= 1;
+                }
+                Some(&content[..end])
+            }
+        }
+    }
+
+    /// Check whether the rule with the given ID (diagnostic code) is enabled:
+    /// by `[checker.rules]`, then by the setting of a registered rule. Other
+    /// rules are enabled unless `[checker.rules]` disables them.
+    pub fn is_rule_enabled(&self, rule_id: &str) -> bool {
+        if let Some(&RuleSetting::Enabled(enabled)) = self.rules.get(rule_id) {
+            return enabled;
+        }
+        rules::find_rule(rule_id).is_none_or(|rule| (rule.setting_value)(self))
+    }
+
+    /// Severity `[checker.rules]` sets for the rule with the given ID, in
+    /// place of the default one of the rule
+    pub fn severity_override(&self, rule_id: &str) -> Option<DiagnosticSeverity> {
+        match self.rules.get(rule_id) {
+            Some(RuleSetting::Severity(severity)) => Some((*severity).into()),
+            _ => None,
+        }
+    }
+
+    /// Severity of the diagnostics of the rule with the given ID: the one set
+    /// by `[checker.rules]`, or the default one of a registered rule
+    pub fn rule_severity(&self, rule_id: &str) -> Option<DiagnosticSeverity> {
+        self.severity_override(rule_id)
+            .or_else(|| rules::find_rule(rule_id).map(|rule| rule.severity))
+    }
+
+    /// Check whether a word reported by a rule was marked as a false positive
+    pub fn is_false_positive(&self, rule_id: &str, word: &str) -> bool {
+        self.false_positives
+            .iter()
+            .any(|fp| fp.rule == rule_id && fp.word == word)
+    }
+
+    /// Pattern rule files, `.textlintrc` and English dictionaries, resolved
+    /// against the current directory
+    pub fn rule_file_paths(&self) -> Vec<PathBuf> {
+        self.pattern_files
+            .iter()
+            .chain(self.textlint.textlintrc.iter())
+            .chain(self.english_dictionary.iter())
+            .map(|path| std::path::absolute(path).unwrap_or_else(|_| path.clone()))
+            .collect()
+    }
+
+    /// The `[checker]` setting key that toggles the rule with the given ID
+    pub fn rule_setting_key(rule_id: &str) -> Option<&'static str> {
+        rules::find_rule(rule_id).map(|rule| rule.setting)
+    }
+}
+
+/// Notation dictionary configuration
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct DictionaryConfig {
+    /// User dictionary CSV file (Lindera simple format: 表層形,品詞,読み),
+    /// used for morphological analysis and completion
+    #[serde(default)]
+    pub user_dictionary: Option<PathBuf>,
+
+    /// prh-style YAML files mapping variant spellings to canonical terms
+    #[serde(default)]
+    pub prh: Vec<PathBuf>,
+}
+
+impl DictionaryConfig {
+    /// Resolve relative dictionary paths against a base directory
+    pub fn resolve_paths(&mut self, base: &Path) {
+        for path in self.prh.iter_mut().chain(self.user_dictionary.iter_mut()) {
+            *path = base.join(&*path);
+        }
+    }
+
+    /// All dictionary files, resolved against the current directory
+    pub fn paths(&self) -> Vec<PathBuf> {
+        self.prh
+            .iter()
+            .chain(self.user_dictionary.iter())
+            .map(|path| std::path::absolute(path).unwrap_or_else(|_| path.clone()))
+            .collect()
+    }
+}
+
+/// Glossary (用語集) configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GlossaryConfig {
+    /// Glossary file whose headings define terms
+    #[serde(default)]
+    pub path: Option<PathBuf>,
+
+    /// Heading format of a term, with `{term}` as the placeholder
+    #[serde(default = "default_glossary_heading")]
+    pub heading: String,
+}
+
+impl Default for GlossaryConfig {
+    fn default() -> Self {
+        Self {
+            path: None,
+            heading: default_glossary_heading(),
+        }
+    }
+}
+
+/// On-type formatting configuration
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FormatConfig {
+    /// Convert punctuation while typing (off by default)
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Convert "," after Japanese text to "、"
+    #[serde(default = "default_true")]
+    pub comma: bool,
+
+    /// Convert "." after Japanese text to "。"
+    #[serde(default = "default_true")]
+    pub period: bool,
+
+    /// Convert "..." to "…"
+    #[serde(default = "default_true")]
+    pub ellipsis: bool,
+}
+
+impl Default for FormatConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            comma: true,
+            period: true,
+            ellipsis: true,
+        }
+    }
+}
+
+/// Local rule statistics configuration
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TelemetryConfig {
+    /// Record how often each rule fires, how often its fixes are applied and
+    /// the time spent in it (off by default; nothing leaves the machine)
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Statistics file (defaults to `stats.json` in the user data directory)
+    #[serde(default)]
+    pub file: Option<PathBuf>,
+}
+
+impl TelemetryConfig {
+    /// Statistics file: the configured one, or `stats.json` in the user data directory
+    pub fn stats_file(&self) -> Option<PathBuf> {
+        self.file.clone().or_else(|| {
+            directories::ProjectDirs::from("", "", "mozuku").map(|dirs| dirs.data_dir().join("stats.json"))
+        })
+    }
+}
+
+fn default_glossary_heading() -> String {
+    "## {term}".to_string()
+}
+
+/// Azure OpenAI API version used when none is configured
+/// (the first GA version supporting structured outputs)
+const DEFAULT_AZURE_API_VERSION: &str = "2024-10-21";
+
+fn default_provider() -> String {
+    "none".to_string()
+}
+
+fn default_max_tokens() -> u32 {
+    1024
+}
+
+fn default_candidates() -> usize {
+    3
+}
+
+fn default_batch_size() -> usize {
+    20
+}
+
+fn default_max_retries() -> u32 {
+    2
+}
+
+fn default_ai_min_chars() -> usize {
+    15
+}
+
+fn default_ai_max_sentences() -> usize {
+    30
+}
+
+fn default_ai_batch_size() -> usize {
+    10
+}
+
+fn default_cache_memory_entries() -> usize {
+    256
+}
+
+fn default_cache_ttl() -> u64 {
+    7 * 24 * 60 * 60
+}
+
+fn default_cache_max_disk_size() -> u64 {
+    50 * 1024 * 1024
+}
+
+/// Values accepted by `[llm] provider`
+const PROVIDERS: [&str; 7] = [
+    "none",
+    "claude",
+    "openai",
+    "azure",
+    "bedrock",
+    "openai-compatible",
+    "ollama",
+];
+
+/// Severity of a problem found in a configuration file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IssueLevel {
+    /// The setting cannot work as written
+    Error,
+    /// The setting is probably not what was intended
+    Warning,
+}
+
+/// A problem found in a configuration file by `config validate`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigIssue {
+    pub level: IssueLevel,
+    pub message: String,
+}
+
+impl ConfigIssue {
+    fn error(message: impl Into<String>) -> Self {
+        Self {
+            level: IssueLevel::Error,
+            message: message.into(),
+        }
+    }
+
+    fn warning(message: impl Into<String>) -> Self {
+        Self {
+            level: IssueLevel::Warning,
+            message: message.into(),
+        }
+    }
+}
+
+/// Commented configuration written by `init`: the example configuration
+const CONFIG_TEMPLATE: &str = include_str!("../../mozuku.toml.example");
+
+/// Line of the example configuration that does not apply to generated files
+const TEMPLATE_RENAME_NOTE: &str = "# このファイルを mozuku.toml にリネームして使用してください\n";
+
+/// Commented-out `preset` key of the example configuration
+const TEMPLATE_PRESET_LINE: &str = "# preset = \"koyobun\"\n";
+
+/// Settings presets, bundled in `data/presets/` (see [`crate::preset`])
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+pub enum Preset {
+    /// Default settings
+    #[default]
+    Default,
+    /// Technical documents: punctuation conversion on, repeated
+    /// である endings allowed, changelogs and vendored files excluded
+    Technical,
+    /// Official documents (公用文): kana for auxiliary verbs and formal
+    /// nouns, Jōyō kanji and standard okurigana, Arabic numerals
+    Koyobun,
+    /// JTF Japanese style guide for translation: long vowels of katakana
+    /// words, full-width and half-width characters, units
+    Jtf,
+    /// Newspaper style (記者ハンドブック): kana for conjunctions and
+    /// adverbs, numbers with 万/億/兆, units in katakana
+    Shinbun,
+    /// Theses and reports: だ・である style, no spoken language, limited
+    /// first person, no vague expressions
+    Academic,
+}
+
+impl Preset {
+    /// Name of the preset in the `preset` key
+    pub fn id(self) -> &'static str {
+        match self {
+            Preset::Default => "default",
+            Preset::Technical => "technical",
+            Preset::Koyobun => "koyobun",
+            Preset::Jtf => "jtf",
+            Preset::Shinbun => "shinbun",
+            Preset::Academic => "academic",
+        }
+    }
+
+    /// Apply the settings of the preset, keeping the comments of the document
+    fn apply(self, document: &mut DocumentMut) {
+        match self {
+            Preset::Default => {}
+            // Written out, as `init` did before presets could be named in the configuration
+            Preset::Technical => {
+                document["checker"]["consecutive_endings"] = toml_edit::value(false);
+                document["checker"]["exclude"] =
+                    toml_edit::value(Array::from_iter(["node_modules/", "vendor/", "CHANGELOG.md"]));
+                document["format"]["enabled"] = toml_edit::value(true);
+            }
+            Preset::Koyobun | Preset::Jtf | Preset::Shinbun | Preset::Academic => {}
+        }
+    }
+
+    /// Whether `init` names the preset in the `preset` key instead of
+    /// writing out its settings
+    fn is_named(self) -> bool {
+        !matches!(self, Preset::Default | Preset::Technical)
+    }
+}
+
+/// Apply an edit to a configuration file, creating it if needed
+fn update_file(path: &Path, edit: impl FnOnce(&mut DocumentMut) -> Result<()>) -> Result<()> {
+    let content = if path.exists() {
+        std::fs::read_to_string(path)?
+    } else {
+        String::new()
+    };
+
+    let mut document: DocumentMut = content.parse()?;
+    edit(&mut document)?;
+    std::fs::write(path, document.to_string())?;
+    Ok(())
+}
+
+/// Get the `[checker]` table, inserting it if missing
+fn checker_table(document: &mut DocumentMut) -> Result<&mut Table> {
+    document
+        .entry("checker")
+        .or_insert(toml_edit::table())
+        .as_table_mut()
+        .ok_or_else(|| anyhow!("`checker` is not a table"))
+}
+
+fn add_ignore_word(document: &mut DocumentMut, word: &str) -> Result<()> {
+    let words = checker_table(document)?
+        .entry("ignore_words")
+        .or_insert(toml_edit::value(Array::new()))
+        .as_array_mut()
+        .ok_or_else(|| anyhow!("`checker.ignore_words` is not an array"))?;
+
+    if !words.iter().any(|w| w.as_str() == Some(word)) {
+        words.push(word);
+    }
+    Ok(())
+}
+
+fn add_false_positive(document: &mut DocumentMut, rule_id: &str, word: &str) -> Result<()> {
+    let entries = checker_table(document)?
+        .entry("false_positives")
+        .or_insert(Item::ArrayOfTables(ArrayOfTables::new()))
+        .as_array_of_tables_mut()
+        .ok_or_else(|| anyhow!("`checker.false_positives` is not an array of tables"))?;
+
+    let exists = entries.iter().any(|entry| {
+        entry.get("rule").and_then(Item::as_str) == Some(rule_id)
+            && entry.get("word").and_then(Item::as_str) == Some(word)
+    });
+    if !exists {
+        let mut entry = Table::new();
+        entry.insert("rule", toml_edit::value(rule_id));
+        entry.insert("word", toml_edit::value(word));
+        entries.push(entry);
+    }
+    Ok(())
+}
+
+fn disable_rule(document: &mut DocumentMut, rule_id: &str) -> Result<()> {
+    let key = CheckerConfig::rule_setting_key(rule_id).ok_or_else(|| anyhow!("Unknown rule: {}", rule_id))?;
+    let checker = checker_table(document)?;
+    checker.insert(key, toml_edit::value(false));
+    // `[checker.rules]` takes precedence over the setting
+    if let Some(rules) = checker.get_mut("rules").and_then(Item::as_table_like_mut) {
+        if rules.contains_key(rule_id) {
+            rules.insert(rule_id, toml_edit::value(false));
+        }
+    }
+    Ok(())
+}
+
+fn default_max_file_size() -> usize {
+    1024 * 1024
+}
+
+fn default_max_sentence_length() -> usize {
+    100
+}
+
+fn default_max_ten_count() -> usize {
+    3
+}
+
+fn default_max_hedge_ratio() -> f64 {
+    0.2
+}
+
+fn default_max_kanji_length() -> usize {
+    6
+}
+
+fn default_max_grade() -> f64 {
+    10.0
+}
+
+fn default_max_average_sentence_length() -> usize {
+    60
+}
+
+fn default_max_kanji_ratio() -> f64 {
+    0.4
+}
+
+fn default_max_passive_ratio() -> f64 {
+    0.3
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl Config {
+    /// Load configuration from file
+    pub fn load(path: &PathBuf) -> Result<Self> {
+        if path.exists() {
+            let content = std::fs::read_to_string(path)?;
+            Self::parse(&content, path.parent().unwrap_or(Path::new("")))
+        } else {
+            Ok(Config::default())
+        }
+    }
+
+    /// Parse the content of a configuration file, turning the textlint rules
+    /// of `[checker.textlint]` into `[checker]` settings not set explicitly.
+    /// The `.textlintrc` path is resolved against `base`, the directory of the file.
+    pub fn parse(content: &str, base: &Path) -> Result<Self> {
+        let mut table: toml::Table = toml::from_str(content)?;
+        preset::apply(&mut table);
+        textlint::apply(&mut table, base);
+        Ok(toml::Value::Table(table).try_into()?)
+    }
+
+    /// Get default config file path
+    pub fn default_path() -> Option<PathBuf> {
+        directories::ProjectDirs::from("", "", "mozuku").map(|dirs| dirs.config_dir().join("mozuku.toml"))
+    }
+
+    /// Load configuration from default path or workspace
+    pub fn load_from_default() -> Self {
+        // Try workspace path first
+        let workspace_path = PathBuf::from("mozuku.toml");
+        if workspace_path.exists() {
+            if let Ok(config) = Self::load(&workspace_path) {
+                return config;
+            }
+        }
+
+        // Try user config directory
+        if let Some(default_path) = Self::default_path() {
+            if let Ok(config) = Self::load(&default_path) {
+                return config;
+            }
+        }
+
+        Config::default()
+    }
+
+    /// Load configuration for a workspace root.
+    ///
+    /// Uses `mozuku.toml` directly under the root if present, with dictionary,
+    /// plugin, pattern file, `.textlintrc` and English dictionary paths resolved
+    /// against the root. Otherwise falls back to the default lookup.
+    pub fn load_for_root(root: &Path) -> Self {
+        let root_path = root.join("mozuku.toml");
+        if root_path.exists() {
+            if let Ok(mut config) = Self::load(&root_path) {
+                config.dictionary.resolve_paths(root);
+                let paths = config.glossary.path.iter_mut().chain(config.checker.plugins.iter_mut());
+                let paths = paths.chain(config.checker.pattern_files.iter_mut());
+                let paths = paths.chain(config.checker.english_dictionary.iter_mut());
+                for path in paths.chain(config.checker.textlint.textlintrc.iter_mut()) {
+                    *path = root.join(&*path);
+                }
+                return config;
+            }
+        }
+
+        Self::load_from_default()
+    }
+
+    /// Content of a new `mozuku.toml`: the commented example configuration
+    /// with the settings of a preset applied
+    pub fn template(preset: Preset) -> String {
+        let mut template = CONFIG_TEMPLATE.replacen(TEMPLATE_RENAME_NOTE, "", 1);
+        if preset.is_named() {
+            template = template.replacen(TEMPLATE_PRESET_LINE, &format!("preset = \"{}\"\n", preset.id()), 1);
+        }
+        let mut document: DocumentMut = template.parse().expect("mozuku.toml.example is valid TOML");
+        preset.apply(&mut document);
+        document.to_string()
+    }
+
+    /// Validate the content of a configuration file.
+    ///
+    /// Syntax errors and values of the wrong type are errors, as are
+    /// settings that cannot work (unknown providers, missing files, invalid
+    /// patterns). Unknown keys, which loading silently ignores, are warnings.
+    /// Relative paths are resolved against `base`, the directory of the file.
+    /// Returns the configuration unless it could not be parsed.
+    pub fn validate(content: &str, base: &Path) -> (Option<Config>, Vec<ConfigIssue>) {
+        let mut issues = Vec::new();
+        let mut unknown_keys = Vec::new();
+        let deserializer = toml::Deserializer::new(content);
+        let config: Config = match serde_ignored::deserialize(deserializer, |path| unknown_keys.push(path.to_string()))
+        {
+            Ok(config) => config,
+            Err(e) => return (None, vec![ConfigIssue::error(e.to_string().trim_end())]),
+        };
+        issues.extend(
+            unknown_keys
+                .into_iter()
+                .map(|key| ConfigIssue::warning(format!("不明なキー `{}` は無視されます", key))),
+        );
+
+        // Check the settings of the file together with those of its preset
+        let config = match config.preset {
+            Some(_) => toml::from_str::<toml::Table>(content)
+                .ok()
+                .and_then(|mut table| {
+                    preset::apply(&mut table);
+                    toml::Value::Table(table).try_into().ok()
+                })
+                .unwrap_or(config),
+            None => config,
+        };
+
+        let llm = &config.llm;
+        if !PROVIDERS.contains(&llm.provider.as_str()) {
+            issues.push(ConfigIssue::error(format!(
+                "llm.provider `{}` は不明です（{} のいずれか）",
+                llm.provider,
+                PROVIDERS.join(", ")
+            )));
+        } else if llm.provider != "none" {
+            if config.requires_api_key() && config.get_api_key().is_none() {
+                issues.push(ConfigIssue::warning(
+                    "API キーが設定されていません（llm.api_key または環境変数）。LLM 機能は無効になります",
+                ));
+            }
+            match llm.provider.as_str() {
+                "azure" => {
+                    if let Err(e) = config.azure_chat_url() {
+                        issues.push(ConfigIssue::error(e.to_string()));
+                    }
+                }
+                "openai-compatible" if llm.base_url.is_none() || llm.model.is_none() => {
+                    issues.push(ConfigIssue::error(
+                        "openai-compatible には llm.base_url と llm.model が必要です",
+                    ));
+                }
+                "bedrock" if config.aws_region().is_none() => {
+                    issues.push(ConfigIssue::error(
+                        "bedrock には llm.region または環境変数 AWS_REGION が必要です",
+                    ));
+                }
+                _ => {}
+            }
+        }
+        for pattern in &llm.masking.patterns {
+            if let Err(e) = regex::Regex::new(pattern) {
+                issues.push(ConfigIssue::error(format!(
+                    "llm.masking.patterns の正規表現 {:?} が不正です: {}",
+                    pattern, e
+                )));
+            }
+        }
+
+        let ratios = [
+            ("checker.max_kanji_ratio", config.checker.max_kanji_ratio),
+            ("checker.max_passive_ratio", config.checker.max_passive_ratio),
+            ("checker.max_hedge_ratio", config.checker.max_hedge_ratio),
+        ];
+        for (key, value) in ratios {
+            if !(0.0..=1.0).contains(&value) {
+                issues.push(ConfigIssue::error(format!(
+                    "{} は 0.0 から 1.0 の範囲で指定してください（{}）",
+                    key, value
+                )));
+            }
+        }
+
+        if let Err(e) = Exclusions::new(base, &config.checker.exclude) {
+            issues.push(ConfigIssue::error(format!("checker.exclude: {:#}", e)));
+        }
+
+        let files = config
+            .dictionary
+            .user_dictionary
+            .iter()
+            .map(|path| ("dictionary.user_dictionary", path))
+            .chain(config.dictionary.prh.iter().map(|path| ("dictionary.prh", path)))
+            .chain(config.glossary.path.iter().map(|path| ("glossary.path", path)))
+            .chain(config.checker.plugins.iter().map(|path| ("checker.plugins", path)))
+            .chain(
+                config
+                    .checker
+                    .pattern_files
+                    .iter()
+                    .map(|path| ("checker.pattern_files", path)),
+            )
+            .chain(
+                config
+                    .checker
+                    .english_dictionary
+                    .iter()
+                    .map(|path| ("checker.english_dictionary", path)),
+            );
+        for (key, path) in files {
+            if !base.join(path).is_file() {
+                issues.push(ConfigIssue::error(format!(
+                    "{} のファイル {} が見つかりません",
+                    key,
+                    path.display()
+                )));
+            }
+        }
+
+        for path in config.checker.pattern_files.iter().map(|path| base.join(path)) {
+            if path.is_file() {
+                if let Err(e) = crate::pattern::load_file(&path) {
+                    issues.push(ConfigIssue::error(format!("checker.pattern_files: {:#}", e)));
+                }
+            }
+        }
+
+        let textlint = &config.checker.textlint;
+        if textlint.textlintrc.is_some() || !textlint.rules.is_empty() {
+            match textlint::merged_rules(textlint, base) {
+                Ok(rules) => issues.extend(textlint::unsupported_rules(&rules).into_iter().map(|name| {
+                    ConfigIssue::warning(format!(
+                        "textlint のルール `{}` には対応していないため無視されます",
+                        name
+                    ))
+                })),
+                Err(e) => issues.push(ConfigIssue::error(format!("checker.textlint: {:#}", e))),
+            }
+        }
+
+        // IDs of pattern files and plugins are only known once they are loaded
+        if config.checker.pattern_files.is_empty() && config.checker.plugins.is_empty() {
+            for id in config.checker.rules.keys() {
+                if rules::find_rule(id).is_none() && !config.checker.patterns.iter().any(|rule| rule.id == *id) {
+                    issues.push(ConfigIssue::warning(format!(
+                        "checker.rules のルール `{}` はありません（`mozuku-rs rules` で一覧を表示）",
+                        id
+                    )));
+                }
+            }
+        }
+
+        #[cfg(feature = "plugins")]
+        for path in config.checker.plugins.iter().map(|path| base.join(path)) {
+            if path.is_file() {
+                if let Err(e) = crate::plugin::validate(&path) {
+                    issues.push(ConfigIssue::error(format!("checker.plugins: {:#}", e)));
+                }
+            }
+        }
+        #[cfg(not(feature = "plugins"))]
+        if !config.checker.plugins.is_empty() {
+            issues.push(ConfigIssue::warning(
+                "プラグイン機能を含まないビルドのため checker.plugins は無視されます",
+            ));
+        }
+
+        (Some(config), issues)
+    }
+
+    /// Add a word to `[checker] ignore_words` of a configuration file,
+    /// creating the file if it does not exist. Existing formatting is kept.
+    pub fn add_ignore_word(path: &Path, word: &str) -> Result<()> {
+        update_file(path, |document| add_ignore_word(document, word))
+    }
+
+    /// Mark a word reported by a rule as a false positive in
+    /// `[[checker.false_positives]]` of a configuration file, creating the
+    /// file if it does not exist. Existing formatting is kept.
+    pub fn add_false_positive(path: &Path, rule_id: &str, word: &str) -> Result<()> {
+        update_file(path, |document| add_false_positive(document, rule_id, word))
+    }
+
+    /// Disable a rule in the `[checker]` section of a configuration file,
+    /// creating the file if it does not exist. Existing formatting is kept.
+    pub fn disable_rule(path: &Path, rule_id: &str) -> Result<()> {
+        update_file(path, |document| disable_rule(document, rule_id))
+    }
+
+    /// Get the effective API key (from config or environment)
+    pub fn get_api_key(&self) -> Option<String> {
+        // First check config file
+        if let Some(ref key) = self.llm.api_key {
+            if !key.is_empty() {
+                return Some(key.clone());
+            }
+        }
+
+        // Then check environment variables
+        match self.llm.provider.as_str() {
+            "claude" => std::env::var("ANTHROPIC_API_KEY").ok(),
+            "openai" => std::env::var("OPENAI_API_KEY").ok(),
+            "azure" => std::env::var("AZURE_OPENAI_API_KEY").ok(),
+            _ => None,
+        }
+    }
+
+    /// Get the effective model name
+    pub fn get_model(&self) -> String {
+        self.llm
+            .model
+            .clone()
+            .unwrap_or_else(|| match self.llm.provider.as_str() {
+                "claude" => "claude-3-5-sonnet-20241022".to_string(),
+                "openai" => "gpt-4o".to_string(),
+                "ollama" => "llama3.1".to_string(),
+                "bedrock" => "anthropic.claude-3-5-sonnet-20240620-v1:0".to_string(),
+                _ => String::new(),
+            })
+    }
+
+    /// Chat completions URL of the Azure OpenAI deployment
+    pub fn azure_chat_url(&self) -> Result<String> {
+        let base_url = self
+            .llm
+            .base_url
+            .as_deref()
+            .ok_or_else(|| anyhow!("base_url is required for Azure OpenAI (e.g. https://<resource>.openai.azure.com)"))?;
+        let deployment = self
+            .llm
+            .deployment
+            .as_deref()
+            .or(self.llm.model.as_deref())
+            .ok_or_else(|| anyhow!("deployment is required for Azure OpenAI"))?;
+        let api_version = self.llm.api_version.as_deref().unwrap_or(DEFAULT_AZURE_API_VERSION);
+
+        Ok(format!(
+            "{}/openai/deployments/{}/chat/completions?api-version={}",
+            base_url.trim_end_matches('/'),
+            deployment,
+            api_version
+        ))
+    }
+
+    /// AWS region for Bedrock
+    pub fn aws_region(&self) -> Option<String> {
+        self.llm
+            .region
+            .clone()
+            .or_else(|| std::env::var("AWS_REGION").ok())
+            .or_else(|| std::env::var("AWS_DEFAULT_REGION").ok())
+            .filter(|region| !region.is_empty())
+    }
+
+    /// Whether the provider needs an API key.
+    /// Local and OpenAI-compatible servers do not; Bedrock uses AWS credentials instead.
+    pub fn requires_api_key(&self) -> bool {
+        !matches!(self.llm.provider.as_str(), "ollama" | "openai-compatible" | "bedrock")
+    }
+
+    /// Get the effective request timeout.
+    /// Local models can take minutes to load and generate, so they get a longer default.
+    pub fn llm_timeout(&self) -> Duration {
+        let secs = self.llm.timeout.unwrap_or(match self.llm.provider.as_str() {
+            "ollama" => 300,
+            _ => 60,
+        });
+        Duration::from_secs(secs)
+    }
+
+    /// Get the effective limit of concurrent requests.
+    /// A local Ollama server processes one request at a time by default.
+    pub fn llm_max_concurrent_requests(&self) -> usize {
+        self.llm
+            .max_concurrent_requests
+            .unwrap_or(match self.llm.provider.as_str() {
+                "ollama" => 1,
+                _ => 4,
+            })
+            .max(1)
+    }
+
+    /// Check if LLM integration is enabled
+    pub fn is_llm_enabled(&self) -> bool {
+        self.llm.provider != "none" && (!self.requires_api_key() || self.get_api_key().is_some())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_config() {
+        let config = Config::default();
+
+        assert_eq!(config.llm.provider, "none");
+        assert!(config.llm.api_key.is_none());
+        assert_eq!(config.llm.max_tokens, 1024);
+        assert!(config.checker.ra_nuki);
+        assert!(config.checker.double_honorific);
+    }
+
+    #[test]
+    fn test_parse_minimal_toml() {
+        let toml_str = r#"
+[llm]
+provider = "claude"
+"#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+
+        assert_eq!(config.llm.provider, "claude");
+        assert!(config.llm.api_key.is_none());
+        assert!(config.checker.ra_nuki); // defaults to true
+    }
+
+    #[test]
+    fn test_parse_full_toml() {
+        let toml_str = r#"
+[llm]
+provider = "openai"
+api_key = "sk-test-key"
+model = "gpt-4o-mini"
+max_tokens = 2048
+
+[checker]
+ra_nuki = true
+i_nuki = false
+double_particle = true
+double_honorific = true
+redundant_expression = false
+consecutive_endings = true
+tari_parallel = true
+consecutive_no = false
+"#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+
+        assert_eq!(config.llm.provider, "openai");
+        assert_eq!(config.llm.api_key, Some("sk-test-key".to_string()));
+        assert_eq!(config.llm.model, Some("gpt-4o-mini".to_string()));
+        assert_eq!(config.llm.max_tokens, 2048);
+
+        assert!(config.checker.ra_nuki);
+        assert!(!config.checker.i_nuki);
+        assert!(config.checker.double_particle);
+        assert!(!config.checker.redundant_expression);
+        assert!(!config.checker.consecutive_no);
+    }
+
+    #[test]
+    fn test_parse_dictionary_config() {
+        let toml_str = r#"
+[dictionary]
+user_dictionary = "dict/user.csv"
+prh = ["prh.yml", "rules/tech.yml"]
+"#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+
+        assert_eq!(config.dictionary.user_dictionary, Some(PathBuf::from("dict/user.csv")));
+        assert_eq!(config.dictionary.prh.len(), 2);
+        assert!(Config::default().dictionary.prh.is_empty());
+    }
+
+    #[test]
+    fn test_parse_format_config() {
+        let toml_str = r#"
+[format]
+enabled = true
+ellipsis = false
+"#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+
+        assert!(config.format.enabled);
+        assert!(config.format.comma);
+        assert!(!config.format.ellipsis);
+        assert!(!Config::default().format.enabled);
+    }
+
+    #[test]
+    fn test_parse_telemetry_config() {
+        let toml_str = r#"
+[telemetry]
+enabled = true
+file = "/tmp/mozuku-stats.json"
+"#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+
+        assert!(config.telemetry.enabled);
+        assert_eq!(config.telemetry.file, Some(PathBuf::from("/tmp/mozuku-stats.json")));
+        assert!(!Config::default().telemetry.enabled);
+    }
+
+    #[test]
+    fn test_dictionary_paths() {
+        let config = DictionaryConfig {
+            user_dictionary: Some(PathBuf::from("dict/user.csv")),
+            prh: vec![PathBuf::from("prh.yml")],
+        };
+        let paths = config.paths();
+
+        assert_eq!(paths.len(), 2);
+        assert!(paths.iter().all(|p| p.is_absolute()));
+        assert!(paths[0].ends_with("prh.yml"));
+        assert!(paths[1].ends_with("dict/user.csv"));
+        assert!(DictionaryConfig::default().paths().is_empty());
+    }
+
+    #[test]
+    fn test_get_model_defaults() {
+        let mut config = Config::default();
+
+        config.llm.provider = "claude".to_string();
+        assert_eq!(config.get_model(), "claude-3-5-sonnet-20241022");
+
+        config.llm.provider = "openai".to_string();
+        assert_eq!(config.get_model(), "gpt-4o");
+
+        config.llm.provider = "ollama".to_string();
+        assert_eq!(config.get_model(), "llama3.1");
+
+        config.llm.model = Some("custom-model".to_string());
+        assert_eq!(config.get_model(), "custom-model");
+    }
+
+    #[test]
+    fn test_azure_chat_url() {
+        let mut config = Config::default();
+        config.llm.provider = "azure".to_string();
+        assert!(config.azure_chat_url().is_err());
+
+        config.llm.base_url = Some("https://example.openai.azure.com/".to_string());
+        config.llm.deployment = Some("gpt4o-prod".to_string());
+        assert_eq!(
+            config.azure_chat_url().unwrap(),
+            "https://example.openai.azure.com/openai/deployments/gpt4o-prod/chat/completions?api-version=2024-10-21"
+        );
+
+        config.llm.api_version = Some("2024-08-01-preview".to_string());
+        assert!(config.azure_chat_url().unwrap().ends_with("api-version=2024-08-01-preview"));
+    }
+
+    #[test]
+    fn test_parse_openai_compatible() {
+        let toml_str = r#"
+[llm]
+provider = "openai-compatible"
+base_url = "https://openrouter.ai/api/v1"
+model = "anthropic/claude-3.5-sonnet"
+
+[llm.headers]
+HTTP-Referer = "https://example.com"
+"#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+
+        assert!(config.is_llm_enabled());
+        assert_eq!(config.get_model(), "anthropic/claude-3.5-sonnet");
+        assert_eq!(config.llm.headers["HTTP-Referer"], "https://example.com");
+    }
+
+    #[test]
+    fn test_parse_few_shot_examples() {
+        let toml_str = r#"
+[[llm.examples]]
+input = "サーバを再起動して下さい"
+output = "サーバーを再起動してください"
+explanation = "社内表記に統一"
+
+[[llm.examples]]
+input = "確認をお願い致します"
+output = "確認をお願いします"
+"#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+
+        assert_eq!(config.llm.examples.len(), 2);
+        assert_eq!(config.llm.examples[0].explanation.as_deref(), Some("社内表記に統一"));
+        assert_eq!(config.llm.examples[1].explanation, None);
+    }
+
+    #[test]
+    fn test_ollama_needs_no_api_key() {
+        let mut config = Config::default();
+        config.llm.provider = "ollama".to_string();
+
+        assert!(!config.requires_api_key());
+        assert!(config.is_llm_enabled());
+        assert_eq!(config.llm_timeout(), Duration::from_secs(300));
+
+        config.llm.timeout = Some(30);
+        assert_eq!(config.llm_timeout(), Duration::from_secs(30));
+
+        assert_eq!(config.llm_max_concurrent_requests(), 1);
+        config.llm.provider = "claude".to_string();
+        assert_eq!(config.llm_max_concurrent_requests(), 4);
+        config.llm.max_concurrent_requests = Some(0);
+        assert_eq!(config.llm_max_concurrent_requests(), 1);
+    }
+
+    #[test]
+    fn test_is_llm_enabled() {
+        let mut config = Config::default();
+
+        // Default: disabled (provider = "none")
+        assert!(!config.is_llm_enabled());
+
+        // Provider set but no API key
+        config.llm.provider = "claude".to_string();
+        assert!(!config.is_llm_enabled());
+
+        // Provider and API key set
+        config.llm.api_key = Some("test-key".to_string());
+        assert!(config.is_llm_enabled());
+    }
+
+    #[test]
+    fn test_load_nonexistent_file() {
+        let path = PathBuf::from("/nonexistent/path/mozuku.toml");
+        let config = Config::load(&path).unwrap();
+
+        // Should return default config
+        assert_eq!(config.llm.provider, "none");
+    }
+
+    #[test]
+    fn test_checker_config_all_enabled() {
+        let config = CheckerConfig::default();
+
+        assert!(config.ra_nuki);
+        assert!(config.i_nuki);
+        assert!(config.double_particle);
+        assert!(config.double_honorific);
+        assert!(config.redundant_expression);
+        assert!(config.consecutive_endings);
+        assert!(config.tari_parallel);
+        assert!(config.consecutive_no);
+    }
+
+    #[test]
+    fn test_parse_large_file_config() {
+        let toml_str = r#"
+[checker]
+max_file_size = 2048
+large_file = "skip"
+"#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+
+        assert_eq!(config.checker.max_file_size, 2048);
+        assert_eq!(config.checker.large_file, LargeFileAction::Skip);
+        assert_eq!(CheckerConfig::default().large_file, LargeFileAction::Truncate);
+    }
+
+    #[test]
+    fn test_file_types_filter() {
+        let toml_str = r#"
+[checker]
+file_types = ["markdown", "plaintext"]
+"#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+
+        assert!(config.checker.is_file_type_enabled(FileType::Markdown));
+        assert!(config.checker.is_file_type_enabled(FileType::PlainText));
+        assert!(!config.checker.is_file_type_enabled(FileType::Rust));
+        // All file types are checked by default
+        assert!(CheckerConfig::default().is_file_type_enabled(FileType::Rust));
+    }
+
+    #[test]
+    fn test_analysis_text() {
+        let mut config = CheckerConfig {
+            max_file_size: 4,
+            ..Default::default()
+        };
+
+        assert_eq!(config.analysis_text("abc"), Some("abc"));
+        // Truncates at a character boundary
+        assert_eq!(config.analysis_text("あいう"), Some("あ"));
+
+        config.large_file = LargeFileAction::Skip;
+        assert_eq!(config.analysis_text("あいう"), None);
+    }
+
+    #[test]
+    fn test_is_rule_enabled() {
+        let config = CheckerConfig {
+            ra_nuki: false,
+            tari_parallel: false,
+            ..Default::default()
+        };
+
+        assert!(!config.is_rule_enabled("ra-nuki"));
+        assert!(!config.is_rule_enabled("incomplete-tari"));
+        assert!(config.is_rule_enabled("i-nuki"));
+        assert!(config.is_rule_enabled("notation-variance"));
+        assert!(config.is_rule_enabled("my-pattern"));
+    }
+
+    #[test]
+    fn test_parse_rules_table() {
+        let toml_str = r#"
+[checker]
+ra_nuki = false
+max_ten = false
+
+[checker.rules]
+ra-nuki = true
+i-nuki = false
+max-ten = true
+my-pattern = false
+missing-particle = "warning"
+"#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+
+        assert!(config.checker.is_rule_enabled("ra-nuki"));
+        assert!(!config.checker.is_rule_enabled("i-nuki"));
+        assert!(config.checker.is_rule_enabled("max-ten"));
+        assert!(!config.checker.is_rule_enabled("my-pattern"));
+        assert!(config.checker.is_rule_enabled("double-particle"));
+
+        // A severity replaces the default one, leaving the rule disabled by default
+        assert!(!config.checker.is_rule_enabled("missing-particle"));
+        assert_eq!(
+            config.checker.severity_override("missing-particle"),
+            Some(DiagnosticSeverity::WARNING)
+        );
+        assert_eq!(config.checker.severity_override("ra-nuki"), None);
+        assert!(toml::from_str::<Config>("[checker.rules]\nra-nuki = \"fatal\"\n").is_err());
+    }
+
+    #[test]
+    fn test_default_enabled_rules() {
+        let config = CheckerConfig::default();
+        for rule in crate::rules::RULES {
+            assert_eq!(
+                config.is_rule_enabled(rule.id),
+                rule.default_enabled,
+                "default of {} differs from the registry",
+                rule.id
+            );
+        }
+    }
+
+    #[test]
+    fn test_load_for_root() {
+        let root = std::env::temp_dir().join(format!("mozuku-config-test-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(
+            root.join("mozuku.toml"),
+            "[checker]\nra_nuki = false\nplugins = [\"rules.wasm\"]\npattern_files = [\"patterns.yml\"]\nenglish_dictionary = [\"words.txt\"]\n\n[dictionary]\nprh = [\"prh.yml\"]\n\n[glossary]\npath = \"docs/glossary.md\"\n",
+        )
+        .unwrap();
+
+        let config = Config::load_for_root(&root);
+        std::fs::remove_dir_all(&root).unwrap();
+
+        assert!(!config.checker.ra_nuki);
+        assert_eq!(config.dictionary.prh, vec![root.join("prh.yml")]);
+        assert_eq!(config.checker.plugins, vec![root.join("rules.wasm")]);
+        assert_eq!(config.checker.pattern_files, vec![root.join("patterns.yml")]);
+        assert_eq!(config.checker.english_dictionary, vec![root.join("words.txt")]);
+        assert_eq!(config.glossary.path, Some(root.join("docs/glossary.md")));
+        assert_eq!(config.glossary.heading, "## {term}");
+    }
+
+    #[test]
+    fn test_parse_textlint_rules() {
+        let content = r#"
+[checker]
+max_ten = false
+
+[checker.textlint.rules]
+preset-ja-technical-writing = { sentence-length = { max = 80 } }
+max-ten = { max = 2 }
+no-mix-dearu-desumasu = { preferInBody = "である" }
+"#;
+        let config = Config::parse(content, Path::new("")).unwrap();
+
+        assert!(config.checker.sentence_length);
+        assert_eq!(config.checker.max_sentence_length, 80);
+        assert!(config.checker.weak_phrase);
+        assert_eq!(config.checker.preferred_style, Some(WritingStyle::Dearu));
+        // Settings written in [checker] take precedence
+        assert!(!config.checker.max_ten);
+        assert_eq!(config.checker.max_ten_count, 2);
+    }
+
+    #[test]
+    fn test_load_textlintrc() {
+        let dir = std::env::temp_dir().join(format!("mozuku-textlintrc-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join(".textlintrc"),
+            r#"{ "rules": { "no-doubled-joshi": false, "ja-no-weak-phrase": true } }"#,
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("mozuku.toml"),
+            "[checker.textlint]\ntextlintrc = \".textlintrc\"\n",
+        )
+        .unwrap();
+
+        let config = Config::load(&dir.join("mozuku.toml")).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(!config.checker.double_particle);
+        assert!(config.checker.weak_phrase);
+        assert!(!config.checker.sentence_length);
+    }
+
+    #[test]
+    fn test_rule_setting_key_matches_is_rule_enabled() {
+        for rule in crate::rules::RULES {
+            let key = CheckerConfig::rule_setting_key(rule.id).unwrap();
+            let mut document: DocumentMut = format!("[checker]\n{} = false\n", key).parse().unwrap();
+            disable_rule(&mut document, rule.id).unwrap();
+
+            let config: Config = toml::from_str(&document.to_string()).unwrap();
+            assert!(!config.checker.is_rule_enabled(rule.id), "{} should be disabled", rule.id);
+
+            let config: Config = toml::from_str(&format!("[checker]\n{} = true\n", key)).unwrap();
+            assert!(config.checker.is_rule_enabled(rule.id), "{} should be enabled", rule.id);
+        }
+    }
+
+    #[test]
+    fn test_disable_rule_overrides_rules_table() {
+        let mut document: DocumentMut = "[checker.rules]\nra-nuki = true\n".parse().unwrap();
+        disable_rule(&mut document, "ra-nuki").unwrap();
+
+        let config: Config = toml::from_str(&document.to_string()).unwrap();
+        assert!(!config.checker.is_rule_enabled("ra-nuki"));
+        assert_eq!(config.checker.rules.get("ra-nuki"), Some(&RuleSetting::Enabled(false)));
+    }
+
+    #[test]
+    fn test_add_ignore_word_keeps_formatting() {
+        let mut document: DocumentMut = "# 設定\n[checker]\nra_nuki = true # ら抜き\n".parse().unwrap();
+        add_ignore_word(&mut document, "見れる").unwrap();
+        add_ignore_word(&mut document, "見れる").unwrap();
+        let content = document.to_string();
+
+        assert!(content.contains("# 設定"));
+        assert!(content.contains("ra_nuki = true # ら抜き"));
+        let config: Config = toml::from_str(&content).unwrap();
+        assert_eq!(config.checker.ignore_words, vec!["見れる".to_string()]);
+    }
+
+    #[test]
+    fn test_add_false_positive() {
+        let mut document: DocumentMut = "[checker]\nra_nuki = true # ら抜き\n".parse().unwrap();
+        add_false_positive(&mut document, "ra-nuki", "見れる").unwrap();
+        add_false_positive(&mut document, "ra-nuki", "見れる").unwrap();
+        add_false_positive(&mut document, "i-nuki", "見れる").unwrap();
+        let content = document.to_string();
+
+        assert!(content.contains("ra_nuki = true # ら抜き"));
+        assert!(content.contains("[[checker.false_positives]]\nrule = \"ra-nuki\"\nword = \"見れる\"\n"));
+        let config: Config = toml::from_str(&content).unwrap();
+        assert_eq!(config.checker.false_positives.len(), 2);
+        assert!(config.checker.is_false_positive("ra-nuki", "見れる"));
+        assert!(!config.checker.is_false_positive("ra-nuki", "来れる"));
+        assert!(!config.checker.is_false_positive("double-particle", "見れる"));
+    }
+
+    #[test]
+    fn test_disable_rule_creates_file() {
+        let dir = std::env::temp_dir().join(format!("mozuku-disable-rule-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("mozuku.toml");
+
+        Config::disable_rule(&path, "incomplete-tari").unwrap();
+        let config = Config::load(&path).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(!config.checker.tari_parallel);
+        assert!(Config::disable_rule(&path, "unknown-rule").is_err());
+    }
+
+    #[test]
+    fn test_validate() {
+        let content = r#"
+[llm]
+provider = "gemini"
+
+[llm.masking]
+patterns = ["("]
+
+[checker]
+ra_nuki = true
+typo_key = 1
+
+[dictionary]
+prh = ["missing-prh.yml"]
+"#;
+        let (config, issues) = Config::validate(content, &std::env::temp_dir());
+        let messages: Vec<&str> = issues.iter().map(|issue| issue.message.as_str()).collect();
+
+        assert!(config.is_some());
+        assert_eq!(issues.len(), 4, "{:?}", messages);
+        assert_eq!(issues[0].level, IssueLevel::Warning);
+        assert!(messages[0].contains("checker.typo_key"));
+        assert!(messages[1].contains("gemini"));
+        assert!(messages[2].contains("llm.masking.patterns"));
+        assert!(messages[3].contains("missing-prh.yml"));
+    }
+
+    #[test]
+    fn test_validate_invalid_values() {
+        let (config, issues) = Config::validate(
+            "[checker]
+ra_nuki = \"yes\"
+",
+            Path::new("."),
+        );
+        assert!(config.is_none());
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].level, IssueLevel::Error);
+        assert!(issues[0].message.contains("ra_nuki"));
+
+        let (_, issues) = Config::validate(
+            "[llm]
+provider = \"azure\"
+api_key = \"key\"
+",
+            Path::new("."),
+        );
+        assert!(issues[0].message.contains("base_url"));
+
+        let (_, issues) = Config::validate("[checker]\nmax_kanji_ratio = 40.0\n", Path::new("."));
+        assert_eq!(issues.len(), 1);
+        assert!(issues[0].message.contains("max_kanji_ratio"));
+
+        let (_, issues) = Config::validate("[checker.rules]\nra-nuki = false\nra_nuki = false\n", Path::new("."));
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].level, IssueLevel::Warning);
+        assert!(issues[0].message.contains("`ra_nuki`"));
+
+        let (_, issues) = Config::validate(&Config::template(Preset::Technical), Path::new("."));
+        assert!(issues.is_empty(), "{:?}", issues);
+
+        let (config, issues) = Config::validate(
+            "preset = \"koyobun\"\n\n[checker.rules]\nkoyobun-hyogai = false\n",
+            Path::new("."),
+        );
+        assert!(issues.is_empty(), "{:?}", issues);
+        assert!(config.unwrap().checker.kanji_numerals);
+
+        let (config, issues) = Config::validate("preset = \"unknown\"\n", Path::new("."));
+        assert!(config.is_none());
+        assert_eq!(issues[0].level, IssueLevel::Error);
+    }
+
+    #[test]
+    fn test_template() {
+        let template = Config::template(Preset::Default);
+        let config: Config = toml::from_str(&template).unwrap();
+
+        assert!(template.starts_with("# MoZuku 設定ファイル\n"));
+        assert!(!template.contains("リネーム"));
+        assert!(template.contains("# ら抜き言葉の検出"));
+        assert_eq!(config.llm.provider, "none");
+        assert!(config.checker.consecutive_endings);
+        assert!(!config.format.enabled);
+    }
+
+    #[test]
+    fn test_technical_template() {
+        let template = Config::template(Preset::Technical);
+        let config: Config = toml::from_str(&template).unwrap();
+
+        assert!(template.contains("# ら抜き言葉の検出"));
+        assert!(!config.checker.consecutive_endings);
+        assert!(config.checker.exclude.contains(&"CHANGELOG.md".to_string()));
+        assert!(config.format.enabled);
+        assert!(config.checker.ra_nuki);
+    }
+
+    #[test]
+    fn test_koyobun_template() {
+        let template = Config::template(Preset::Koyobun);
+        let config = Config::parse(&template, Path::new(".")).unwrap();
+
+        assert!(template.starts_with("# MoZuku 設定ファイル\n"));
+        assert!(template.contains("\npreset = \"koyobun\"\n"));
+        assert_eq!(config.preset, Some(Preset::Koyobun));
+        assert!(config.checker.kanji_numerals);
+        assert!(config.checker.patterns.iter().any(|rule| rule.id == "koyobun-kana"));
+
+        let (_, issues) = Config::validate(&template, Path::new("."));
+        assert!(issues.is_empty(), "{:?}", issues);
+    }
+
+    #[test]
+    fn test_serialize_config() {
+        let config = Config::default();
+        let toml_str = toml::to_string(&config).unwrap();
+
+        assert!(toml_str.contains("[llm]"));
+        assert!(toml_str.contains("[checker]"));
+    }
+}
@@ -0,0 +1,361 @@
+//! Notation dictionaries (表記ゆれ辞書)
+//!
+//! Loads prh-style YAML rules and user dictionary CSV files that map variant
+//! spellings to their canonical forms, and provides completion candidates.
+
+use anyhow::Result;
+use serde::Deserialize;
+
+use crate::config::DictionaryConfig;
+
+/// A canonical term and the variant spellings that should be replaced by it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TermRule {
+    /// Canonical spelling (正規表記)
+    pub expected: String,
+    /// Variant spellings (表記ゆれ)
+    pub patterns: Vec<String>,
+}
+
+/// A completion candidate from the dictionary
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TermCandidate {
+    /// Term to insert
+    pub term: String,
+    /// Variant spelling that matched the typed text, if any
+    pub variant: Option<String>,
+    /// Dictionary the term comes from
+    pub source: TermSource,
+}
+
+/// Dictionary a completion candidate comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TermSource {
+    /// `expected` of a prh rule
+    Prh,
+    /// Word of the user dictionary
+    UserDictionary,
+}
+
+/// Character class used to find word boundaries
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CharKind {
+    Hiragana,
+    Katakana,
+    Kanji,
+    Alphanumeric,
+    Other,
+}
+
+impl CharKind {
+    /// Classify a character
+    pub fn of(c: char) -> Self {
+        match c {
+            'ぁ'..='ゖ' | 'ゝ' | 'ゞ' => CharKind::Hiragana,
+            'ァ'..='ヺ' | 'ー' | 'ヽ' | 'ヾ' | 'ｦ'..='ﾟ' => CharKind::Katakana,
+            '一'..='鿿' | '㐀'..='䶿' | '々' | '〆' => CharKind::Kanji,
+            c if c.is_alphanumeric() => CharKind::Alphanumeric,
+            _ => CharKind::Other,
+        }
+    }
+}
+
+/// Notation dictionary built from prh rules and user dictionary words
+#[derive(Debug, Clone, Default)]
+pub struct TermDictionary {
+    rules: Vec<TermRule>,
+    words: Vec<String>,
+}
+
+// prh YAML types
+#[derive(Deserialize)]
+struct PrhFile {
+    #[serde(default)]
+    rules: Vec<PrhRule>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PrhRule {
+    Expected(String),
+    Full {
+        expected: String,
+        #[serde(default)]
+        pattern: Option<OneOrMany>,
+        #[serde(default)]
+        patterns: Option<OneOrMany>,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum OneOrMany {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl OneOrMany {
+    fn into_vec(self) -> Vec<String> {
+        match self {
+            OneOrMany::One(s) => vec![s],
+            OneOrMany::Many(v) => v,
+        }
+    }
+}
+
+impl TermDictionary {
+    /// Create a dictionary from rules and words
+    pub fn new(rules: Vec<TermRule>, words: Vec<String>) -> Self {
+        Self { rules, words }
+    }
+
+    /// Load dictionaries listed in the configuration.
+    ///
+    /// Files that cannot be read or parsed are skipped with a warning.
+    pub fn load(config: &DictionaryConfig) -> Self {
+        let mut dictionary = Self::default();
+
+        for path in &config.prh {
+            match std::fs::read_to_string(path)
+                .map_err(anyhow::Error::from)
+                .and_then(|content| Self::parse_prh(&content))
+            {
+                Ok(rules) => dictionary.rules.extend(rules),
+                Err(e) => tracing::warn!("Failed to load prh dictionary {}: {}", path.display(), e),
+            }
+        }
+
+        if let Some(ref path) = config.user_dictionary {
+            match std::fs::read_to_string(path) {
+                Ok(content) => dictionary.words = Self::parse_user_csv(&content),
+                Err(e) => tracing::warn!("Failed to load user dictionary {}: {}", path.display(), e),
+            }
+        }
+
+        dictionary
+    }
+
+    /// Parse prh YAML rules. Regular expression patterns (`/.../`) are ignored.
+    pub fn parse_prh(content: &str) -> Result<Vec<TermRule>> {
+        let file: PrhFile = serde_yaml::from_str(content)?;
+
+        Ok(file
+            .rules
+            .into_iter()
+            .map(|rule| match rule {
+                PrhRule::Expected(expected) => TermRule {
+                    expected,
+                    patterns: vec![],
+                },
+                PrhRule::Full {
+                    expected,
+                    pattern,
+                    patterns,
+                } => {
+                    let patterns = pattern
+                        .into_iter()
+                        .chain(patterns)
+                        .flat_map(OneOrMany::into_vec)
+                        .filter(|p| !p.starts_with('/') && *p != expected)
+                        .collect();
+                    TermRule { expected, patterns }
+                }
+            })
+            .collect())
+    }
+
+    /// Parse words (first column) from a user dictionary CSV
+    pub fn parse_user_csv(content: &str) -> Vec<String> {
+        content
+            .lines()
+            .filter_map(|line| line.split(',').next())
+            .map(str::trim)
+            .filter(|word| !word.is_empty() && !word.starts_with('#'))
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// Canonical term rules
+    pub fn rules(&self) -> &[TermRule] {
+        &self.rules
+    }
+
+    /// User dictionary words
+    pub fn words(&self) -> &[String] {
+        &self.words
+    }
+
+    /// Completion candidates for a typed word
+    pub fn complete(&self, prefix: &str) -> Vec<TermCandidate> {
+        let mut candidates: Vec<TermCandidate> = Vec::new();
+        if prefix.is_empty() {
+            return candidates;
+        }
+
+        for rule in &self.rules {
+            if rule.expected == prefix {
+                continue;
+            }
+            let variant = rule
+                .patterns
+                .iter()
+                .find(|p| p.starts_with(prefix))
+                .cloned();
+            if variant.is_some() || rule.expected.starts_with(prefix) {
+                candidates.push(TermCandidate {
+                    term: rule.expected.clone(),
+                    variant,
+                    source: TermSource::Prh,
+                });
+            }
+        }
+
+        for word in &self.words {
+            if word != prefix && word.starts_with(prefix) && !candidates.iter().any(|c| &c.term == word) {
+                candidates.push(TermCandidate {
+                    term: word.clone(),
+                    variant: None,
+                    source: TermSource::UserDictionary,
+                });
+            }
+        }
+
+        candidates
+    }
+}
+
+/// Get the word being typed at the end of the text.
+///
+/// The word is the trailing run of characters of the same kind (katakana,
+/// hiragana, kanji or alphanumeric) as the last character.
+pub fn trailing_word(text: &str) -> &str {
+    let kind = match text.chars().last() {
+        Some(c) => CharKind::of(c),
+        None => return "",
+    };
+    if kind == CharKind::Other {
+        return "";
+    }
+
+    let start = text
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| CharKind::of(*c) == kind)
+        .last()
+        .map(|(i, _)| i)
+        .unwrap_or(text.len());
+
+    &text[start..]
+}
+
+/// Find occurrences of a variant spelling that should be replaced.
+///
+/// Occurrences followed by a character of the same kind as the variant's last
+/// character are skipped, since they are part of a longer word (e.g. サーバ in
+/// サーバー or サーバント). Returns byte offsets in `text`.
+pub fn find_term(text: &str, term: &str) -> Vec<usize> {
+    let last_kind = match term.chars().last() {
+        Some(c) => CharKind::of(c),
+        None => return vec![],
+    };
+
+    text.match_indices(term)
+        .map(|(i, _)| i)
+        .filter(|&i| {
+            text[i + term.len()..]
+                .chars()
+                .next()
+                .map(|next| last_kind == CharKind::Other || CharKind::of(next) != last_kind)
+                .unwrap_or(true)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_prh() {
+        let yaml = r#"
+version: 1
+rules:
+  - expected: サーバー
+    pattern: サーバ
+  - expected: ユーザー
+    patterns:
+      - ユーザ
+      - /ユーザ(?!ー)/
+  - インターフェース
+"#;
+        let rules = TermDictionary::parse_prh(yaml).unwrap();
+
+        assert_eq!(rules.len(), 3);
+        assert_eq!(rules[0].expected, "サーバー");
+        assert_eq!(rules[0].patterns, vec!["サーバ"]);
+        // Regex patterns are skipped
+        assert_eq!(rules[1].patterns, vec!["ユーザ"]);
+        assert!(rules[2].patterns.is_empty());
+    }
+
+    #[test]
+    fn test_parse_user_csv() {
+        let csv = "東京スカイツリー,カスタム名詞,トウキョウスカイツリー\n# comment\n\nもずく,カスタム名詞,モズク\n";
+        let words = TermDictionary::parse_user_csv(csv);
+
+        assert_eq!(words, vec!["東京スカイツリー", "もずく"]);
+    }
+
+    #[test]
+    fn test_complete_from_variant() {
+        let dictionary = TermDictionary::new(
+            vec![TermRule {
+                expected: "サーバー".to_string(),
+                patterns: vec!["サーバ".to_string()],
+            }],
+            vec![],
+        );
+
+        let candidates = dictionary.complete("サーバ");
+        assert_eq!(
+            candidates,
+            vec![TermCandidate {
+                term: "サーバー".to_string(),
+                variant: Some("サーバ".to_string()),
+                source: TermSource::Prh,
+            }]
+        );
+        // The canonical spelling itself is from prh too
+        assert_eq!(dictionary.complete("サー")[0].source, TermSource::Prh);
+        // Already canonical
+        assert!(dictionary.complete("サーバー").is_empty());
+    }
+
+    #[test]
+    fn test_complete_from_user_words() {
+        let dictionary = TermDictionary::new(vec![], vec!["東京スカイツリー".to_string()]);
+
+        let candidates = dictionary.complete("東京");
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[0].term, "東京スカイツリー");
+        assert!(candidates[0].variant.is_none());
+        assert_eq!(candidates[0].source, TermSource::UserDictionary);
+    }
+
+    #[test]
+    fn test_find_term() {
+        let text = "サーバとWebサーバ、サーバーとサーバント";
+        let offsets = find_term(text, "サーバ");
+
+        assert_eq!(offsets, vec![0, text.find("Webサーバ").unwrap() + 3]);
+        assert!(find_term(text, "").is_empty());
+    }
+
+    #[test]
+    fn test_trailing_word() {
+        assert_eq!(trailing_word("新しいサーバ"), "サーバ");
+        assert_eq!(trailing_word("設定をAPI"), "API");
+        assert_eq!(trailing_word("文末。"), "");
+        assert_eq!(trailing_word(""), "");
+    }
+}
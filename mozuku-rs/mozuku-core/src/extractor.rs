@@ -0,0 +1,1329 @@
+//! Text extraction from various document formats using tree-sitter.
+//!
+//! This module extracts prose text (comments, markdown content, etc.)
+//! from source code and documents for Japanese proofreading.
+//!
+//! Without the `parsers` feature (e.g. for WebAssembly, where the C grammars
+//! do not build), Markdown is scanned line by line and other file types are
+//! checked as plain text.
+
+#[cfg(feature = "parsers")]
+use std::collections::HashMap;
+use std::ops::Range;
+#[cfg(feature = "parsers")]
+use std::sync::Mutex;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "parsers")]
+use tree_sitter::{InputEdit, Parser, Point, Tree};
+
+/// A span of extracted text with its position in the original document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextSpan {
+    /// The extracted text content
+    pub text: String,
+    /// Start byte offset of the text in the original document
+    pub start_byte: usize,
+    /// End byte offset of the text in the original document
+    pub end_byte: usize,
+    /// Start line (0-indexed)
+    pub start_line: usize,
+    /// Start column (0-indexed, in bytes)
+    pub start_col: usize,
+    /// End line (0-indexed)
+    pub end_line: usize,
+    /// End column (0-indexed, in bytes)
+    pub end_col: usize,
+}
+
+impl TextSpan {
+    pub fn new(
+        text: String,
+        start_byte: usize,
+        end_byte: usize,
+        start_line: usize,
+        start_col: usize,
+        end_line: usize,
+        end_col: usize,
+    ) -> Self {
+        Self {
+            text,
+            start_byte,
+            end_byte,
+            start_line,
+            start_col,
+            end_line,
+            end_col,
+        }
+    }
+}
+
+/// A document heading (Markdown heading or LaTeX sectioning command)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Heading {
+    /// Heading title and its position
+    pub span: TextSpan,
+    /// Nesting level (1 = top level)
+    pub level: usize,
+}
+
+/// LaTeX sectioning commands and their nesting levels
+const LATEX_SECTIONS: [(&str, usize); 6] = [
+    ("part", 1),
+    ("chapter", 2),
+    ("section", 3),
+    ("subsection", 4),
+    ("subsubsection", 5),
+    ("paragraph", 6),
+];
+
+/// Plain text longer than this (in bytes) is split into chunks at sentence boundaries
+const PLAIN_TEXT_CHUNK_BYTES: usize = 16 * 1024;
+
+/// Approximate memory used by a node of a tree-sitter syntax tree, in bytes
+#[cfg(feature = "parsers")]
+const SYNTAX_NODE_SIZE: usize = 64;
+
+/// Row and byte column in a document, like tree-sitter's `Point`
+#[cfg(not(feature = "parsers"))]
+#[derive(Debug, Clone, Copy, Default)]
+struct Point {
+    row: usize,
+    column: usize,
+}
+
+/// Supported file types for text extraction
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileType {
+    Markdown,
+    Rust,
+    Python,
+    TypeScript,
+    JavaScript,
+    C,
+    Cpp,
+    Go,
+    LaTeX,
+    PlainText,
+}
+
+impl FileType {
+    /// Detect file type from file extension
+    pub fn from_extension(ext: &str) -> Self {
+        match ext.to_lowercase().as_str() {
+            "md" | "markdown" => FileType::Markdown,
+            "rs" => FileType::Rust,
+            "py" | "pyi" => FileType::Python,
+            "ts" | "tsx" => FileType::TypeScript,
+            "js" | "jsx" | "mjs" | "cjs" => FileType::JavaScript,
+            "c" | "h" => FileType::C,
+            "cpp" | "cc" | "cxx" | "hpp" | "hxx" => FileType::Cpp,
+            "go" => FileType::Go,
+            "tex" | "latex" => FileType::LaTeX,
+            _ => FileType::PlainText,
+        }
+    }
+
+    /// Detect file type from an LSP language identifier.
+    /// Returns `None` for unknown identifiers.
+    pub fn from_language_id(language_id: &str) -> Option<Self> {
+        match language_id {
+            "markdown" => Some(FileType::Markdown),
+            "rust" => Some(FileType::Rust),
+            "python" => Some(FileType::Python),
+            "typescript" | "typescriptreact" => Some(FileType::TypeScript),
+            "javascript" | "javascriptreact" => Some(FileType::JavaScript),
+            "c" => Some(FileType::C),
+            "cpp" => Some(FileType::Cpp),
+            "go" => Some(FileType::Go),
+            "latex" | "tex" => Some(FileType::LaTeX),
+            "plaintext" => Some(FileType::PlainText),
+            _ => None,
+        }
+    }
+}
+
+/// Syntax tree of a document, kept between versions so that the next
+/// version is reparsed incrementally
+#[cfg(feature = "parsers")]
+#[derive(Debug, Clone)]
+pub struct SyntaxTree {
+    file_type: FileType,
+    /// Text the tree was parsed from
+    text: String,
+    tree: Tree,
+}
+
+/// Without the `parsers` feature nothing is parsed, so there is never a
+/// syntax tree to keep
+#[cfg(not(feature = "parsers"))]
+#[derive(Debug, Clone)]
+pub enum SyntaxTree {}
+
+impl SyntaxTree {
+    /// Estimated memory used by the tree and its text, in bytes
+    #[cfg(feature = "parsers")]
+    pub fn memory_size(&self) -> usize {
+        self.text.capacity() + self.tree.root_node().descendant_count() * SYNTAX_NODE_SIZE
+    }
+
+    #[cfg(not(feature = "parsers"))]
+    pub fn memory_size(&self) -> usize {
+        match *self {}
+    }
+}
+
+/// Text extractor that uses tree-sitter to parse documents
+pub struct TextExtractor {
+    /// Idle parsers by grammar name, created on first use. A parser is taken
+    /// out of the pool while parsing, so concurrent extractions never share one.
+    #[cfg(feature = "parsers")]
+    parsers: Mutex<HashMap<&'static str, Vec<Parser>>>,
+}
+
+impl TextExtractor {
+    pub fn new() -> Self {
+        Self {
+            #[cfg(feature = "parsers")]
+            parsers: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Parse a document with a pooled parser for its file type, reusing
+    /// unchanged parts of `old_tree`
+    #[cfg(feature = "parsers")]
+    fn parse(&self, content: &str, file_type: FileType, old_tree: Option<&Tree>) -> Result<Tree> {
+        let (language, name) = grammar(file_type).ok_or_else(|| anyhow::anyhow!("No grammar for {:?}", file_type))?;
+        let pooled = self
+            .parsers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get_mut(name)
+            .and_then(Vec::pop);
+        let mut parser = match pooled {
+            Some(parser) => parser,
+            None => {
+                let mut parser = Parser::new();
+                parser.set_language(&language)?;
+                parser
+            }
+        };
+
+        let tree = parser.parse(content, old_tree);
+        if tree.is_none() {
+            parser.reset();
+        }
+        self.parsers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(name)
+            .or_default()
+            .push(parser);
+        tree.ok_or_else(|| anyhow::anyhow!("Failed to parse {}", name))
+    }
+
+    /// Extract text spans from a document based on its file type
+    pub fn extract(&self, content: &str, file_type: FileType) -> Result<Vec<TextSpan>> {
+        self.extract_incremental(content, file_type, None)
+            .map(|(spans, _)| spans)
+    }
+
+    /// Extract text spans like [`Self::extract`], reusing the syntax tree of
+    /// the previous version of the document.
+    ///
+    /// The edit between the previous and the current text is applied to the
+    /// old tree so that tree-sitter only reparses the changed region. Returns
+    /// the tree of the current text to pass in for the next version (`None`
+    /// for file types that are not parsed).
+    #[cfg(feature = "parsers")]
+    pub fn extract_incremental(
+        &self,
+        content: &str,
+        file_type: FileType,
+        previous: Option<SyntaxTree>,
+    ) -> Result<(Vec<TextSpan>, Option<SyntaxTree>)> {
+        if grammar(file_type).is_none() {
+            return Ok((self.extract_plain_text(content)?, None));
+        }
+
+        let old_tree = previous
+            .filter(|previous| previous.file_type == file_type)
+            .map(|mut previous| {
+                previous.tree.edit(&input_edit(&previous.text, content));
+                previous.tree
+            });
+        let tree = self.parse(content, file_type, old_tree.as_ref())?;
+
+        let root = tree.root_node();
+        let source = content.as_bytes();
+        let mut spans = Vec::new();
+        match file_type {
+            FileType::Markdown => self.collect_markdown_text(root, source, &mut spans),
+            FileType::Rust => self.collect_comments(root, source, &mut spans, &["line_comment", "block_comment"]),
+            FileType::Python => self.collect_comments(root, source, &mut spans, &["comment", "string"]),
+            _ => self.collect_comments(root, source, &mut spans, &["comment"]),
+        }
+
+        let tree = SyntaxTree {
+            file_type,
+            text: content.to_string(),
+            tree,
+        };
+        Ok((spans, Some(tree)))
+    }
+
+    /// Extract text spans like [`Self::extract`]. Without the `parsers`
+    /// feature there is no syntax tree to reuse: Markdown is scanned line by
+    /// line and other file types are extracted as plain text.
+    #[cfg(not(feature = "parsers"))]
+    pub fn extract_incremental(
+        &self,
+        content: &str,
+        file_type: FileType,
+        _previous: Option<SyntaxTree>,
+    ) -> Result<(Vec<TextSpan>, Option<SyntaxTree>)> {
+        match file_type {
+            FileType::Markdown => Ok((spans_at(content, markdown_prose(content)), None)),
+            _ => Ok((self.extract_plain_text(content)?, None)),
+        }
+    }
+
+    /// Extract headings that make up the document outline
+    pub fn extract_headings(&self, content: &str, file_type: FileType) -> Result<Vec<Heading>> {
+        match file_type {
+            FileType::Markdown => self.extract_markdown_headings(content),
+            FileType::LaTeX => Ok(self.extract_latex_headings(content)),
+            _ => Ok(vec![]),
+        }
+    }
+
+    /// Extract ATX and setext headings from Markdown
+    #[cfg(feature = "parsers")]
+    fn extract_markdown_headings(&self, content: &str) -> Result<Vec<Heading>> {
+        let tree = self.parse(content, FileType::Markdown, None)?;
+
+        let mut headings = Vec::new();
+        self.collect_markdown_headings(tree.root_node(), content.as_bytes(), &mut headings);
+        Ok(headings)
+    }
+
+    /// Extract ATX and setext headings from Markdown, line by line
+    #[cfg(not(feature = "parsers"))]
+    fn extract_markdown_headings(&self, content: &str) -> Result<Vec<Heading>> {
+        let (levels, titles): (Vec<usize>, Vec<Range<usize>>) = markdown_line_headings(content).into_iter().unzip();
+        Ok(spans_at(content, titles)
+            .into_iter()
+            .zip(levels)
+            .map(|(span, level)| Heading { span, level })
+            .collect())
+    }
+
+    /// Recursively collect heading nodes from Markdown AST
+    #[cfg(feature = "parsers")]
+    fn collect_markdown_headings(
+        &self,
+        node: tree_sitter::Node,
+        source: &[u8],
+        headings: &mut Vec<Heading>,
+    ) {
+        if node.kind() == "atx_heading" || node.kind() == "setext_heading" {
+            let mut cursor = node.walk();
+            let level = node
+                .children(&mut cursor)
+                .find_map(|child| markdown_heading_level(child.kind()));
+
+            if let (Some(level), Some(title)) = (level, node.child_by_field_name("heading_content")) {
+                if let Ok(text) = title.utf8_text(source) {
+                    // Trim surrounding whitespace while keeping positions in sync
+                    let leading = text.len() - text.trim_start().len();
+                    let text = text.trim();
+                    if !text.is_empty() {
+                        let start = title.start_position();
+                        headings.push(Heading {
+                            span: TextSpan::new(
+                                text.to_string(),
+                                title.start_byte() + leading,
+                                title.start_byte() + leading + text.len(),
+                                start.row,
+                                start.column + leading,
+                                start.row,
+                                start.column + leading + text.len(),
+                            ),
+                            level,
+                        });
+                    }
+                }
+            }
+            return;
+        }
+
+        let mut cursor = node.walk();
+        for child in node.children(&mut cursor) {
+            self.collect_markdown_headings(child, source, headings);
+        }
+    }
+
+    /// Extract sectioning commands (\section{...} etc.) from LaTeX
+    fn extract_latex_headings(&self, content: &str) -> Vec<Heading> {
+        let mut headings = Vec::new();
+        let mut line_start = 0;
+
+        for (line_num, line) in content.split_inclusive('\n').enumerate() {
+            let trimmed = line.trim_start();
+            if let Some(command) = trimmed.strip_prefix('\\') {
+                let indent = line.len() - trimmed.len();
+                if let Some((level, title_start, title)) = parse_latex_section(command) {
+                    // Offset of the title within the line (+1 for the backslash)
+                    let col = indent + 1 + title_start + (title.len() - title.trim_start().len());
+                    let title = title.trim();
+                    if !title.is_empty() {
+                        headings.push(Heading {
+                            span: TextSpan::new(
+                                title.to_string(),
+                                line_start + col,
+                                line_start + col + title.len(),
+                                line_num,
+                                col,
+                                line_num,
+                                col + title.len(),
+                            ),
+                            level,
+                        });
+                    }
+                }
+            }
+            line_start += line.len();
+        }
+
+        headings
+    }
+
+    /// Extract entire content as a single span (for plain text)
+    fn extract_plain_text(&self, content: &str) -> Result<Vec<TextSpan>> {
+        // Long texts are split into chunks of whole sentences, checked and
+        // reported one by one instead of all at once
+        Ok(spans_at(content, sentence_chunks(content, PLAIN_TEXT_CHUNK_BYTES)))
+    }
+
+    /// Recursively collect text nodes from Markdown AST
+    #[cfg(feature = "parsers")]
+    fn collect_markdown_text(
+        &self,
+        node: tree_sitter::Node,
+        source: &[u8],
+        spans: &mut Vec<TextSpan>,
+    ) {
+        // Node types that contain prose text in tree-sitter-md
+        let text_node_types = [
+            "paragraph",
+            "heading_content",
+            "list_item",
+            "atx_heading",
+        ];
+
+        // Skip code blocks and inline code
+        let skip_types = ["code_block", "fenced_code_block", "code_span", "indented_code_block"];
+
+        if skip_types.contains(&node.kind()) {
+            return;
+        }
+
+        if text_node_types.contains(&node.kind()) {
+            if let Ok(text) = node.utf8_text(source) {
+                let trimmed = text.trim();
+                if !trimmed.is_empty() {
+                    spans.push(node_substring_span(node, text, trimmed));
+                }
+            }
+        }
+
+        // Recurse into children
+        let mut cursor = node.walk();
+        for child in node.children(&mut cursor) {
+            self.collect_markdown_text(child, source, spans);
+        }
+    }
+
+    /// Recursively collect comment nodes from AST
+    #[cfg(feature = "parsers")]
+    fn collect_comments(
+        &self,
+        node: tree_sitter::Node,
+        source: &[u8],
+        spans: &mut Vec<TextSpan>,
+        comment_types: &[&str],
+    ) {
+        if comment_types.contains(&node.kind()) {
+            if let Ok(text) = node.utf8_text(source) {
+                // Strip comment markers
+                let cleaned = self.strip_comment_markers(text, node.kind());
+                if !cleaned.trim().is_empty() {
+                    spans.push(node_substring_span(node, text, cleaned));
+                }
+            }
+        }
+
+        // Recurse into children
+        let mut cursor = node.walk();
+        for child in node.children(&mut cursor) {
+            self.collect_comments(child, source, spans, comment_types);
+        }
+    }
+
+    /// Strip comment markers from comment text.
+    ///
+    /// The result is always a substring of `text`, so its position in the
+    /// document can be recovered.
+    #[cfg(feature = "parsers")]
+    fn strip_comment_markers<'a>(&self, text: &'a str, kind: &str) -> &'a str {
+        match kind {
+            "line_comment" => {
+                // Rust // or /// or //!
+                text.trim_start_matches("///")
+                    .trim_start_matches("//!")
+                    .trim_start_matches("//")
+                    .trim()
+            }
+            "block_comment" => {
+                // Rust /* */ or /** */
+                text.trim_start_matches("/**")
+                    .trim_start_matches("/*!")
+                    .trim_start_matches("/*")
+                    .trim_end_matches("*/")
+                    .trim()
+            }
+            "comment" => {
+                // Generic comment (Python #, C/C++ //, etc.)
+                let trimmed = text.trim();
+                if trimmed.starts_with('#') {
+                    trimmed.trim_start_matches('#').trim()
+                } else if trimmed.starts_with("//") {
+                    trimmed.trim_start_matches("//").trim()
+                } else if trimmed.starts_with("/*") {
+                    trimmed
+                        .trim_start_matches("/*")
+                        .trim_end_matches("*/")
+                        .trim()
+                } else {
+                    trimmed
+                }
+            }
+            "string" => {
+                // Python docstring
+                let trimmed = text.trim();
+                if trimmed.starts_with("\"\"\"") || trimmed.starts_with("'''") {
+                    trimmed[3..trimmed.len().saturating_sub(3)].trim()
+                } else {
+                    "" // Not a docstring
+                }
+            }
+            _ => text,
+        }
+    }
+}
+
+/// Tree-sitter grammar used to extract text from a file type, and its name
+#[cfg(feature = "parsers")]
+fn grammar(file_type: FileType) -> Option<(tree_sitter::Language, &'static str)> {
+    match file_type {
+        FileType::Markdown => Some((tree_sitter_md::LANGUAGE.into(), "Markdown")),
+        FileType::Rust => Some((tree_sitter_rust::LANGUAGE.into(), "Rust")),
+        FileType::Python => Some((tree_sitter_python::LANGUAGE.into(), "Python")),
+        FileType::TypeScript | FileType::JavaScript => Some((
+            tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
+            "TypeScript/JavaScript",
+        )),
+        FileType::C | FileType::Cpp => Some((tree_sitter_cpp::LANGUAGE.into(), "C/C++")),
+        FileType::Go => Some((tree_sitter_go::LANGUAGE.into(), "Go")),
+        // TODO: LaTeX support disabled due to linker issues
+        FileType::PlainText | FileType::LaTeX => None,
+    }
+}
+
+/// The edit turning `old` into `new`: the byte range between their common
+/// prefix and common suffix
+#[cfg(feature = "parsers")]
+fn input_edit(old: &str, new: &str) -> InputEdit {
+    let mut start = old.bytes().zip(new.bytes()).take_while(|(a, b)| a == b).count();
+    while !old.is_char_boundary(start) {
+        start -= 1;
+    }
+    let max_suffix = (old.len() - start).min(new.len() - start);
+    let suffix = old
+        .bytes()
+        .rev()
+        .zip(new.bytes().rev())
+        .take(max_suffix)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let (mut old_end, mut new_end) = (old.len() - suffix, new.len() - suffix);
+    while !old.is_char_boundary(old_end) || !new.is_char_boundary(new_end) {
+        old_end += 1;
+        new_end += 1;
+    }
+
+    let origin = Point { row: 0, column: 0 };
+    let start_position = advance_point(origin, &old[..start]);
+    InputEdit {
+        start_byte: start,
+        old_end_byte: old_end,
+        new_end_byte: new_end,
+        start_position,
+        old_end_position: advance_point(start_position, &old[start..old_end]),
+        new_end_position: advance_point(start_position, &new[start..new_end]),
+    }
+}
+
+/// Build a span for `inner`, a substring of `text` (the text of `node`)
+#[cfg(feature = "parsers")]
+fn node_substring_span(node: tree_sitter::Node, text: &str, inner: &str) -> TextSpan {
+    let offset = inner.as_ptr() as usize - text.as_ptr() as usize;
+    let start = advance_point(node.start_position(), &text[..offset]);
+    let end = advance_point(start, inner);
+
+    TextSpan::new(
+        inner.to_string(),
+        node.start_byte() + offset,
+        node.start_byte() + offset + inner.len(),
+        start.row,
+        start.column,
+        end.row,
+        end.column,
+    )
+}
+
+/// Split text into chunks of whole sentences of at most `max_len` bytes.
+///
+/// Sentences end at 。！？!? and line breaks; a sentence longer than
+/// `max_len` becomes a chunk of its own.
+fn sentence_chunks(text: &str, max_len: usize) -> Vec<Range<usize>> {
+    let sentence_ends = text
+        .char_indices()
+        .filter(|(_, c)| matches!(c, '。' | '！' | '？' | '!' | '?' | '\n'))
+        .map(|(i, c)| i + c.len_utf8())
+        .chain([text.len()]);
+
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    let mut end = 0;
+    for sentence_end in sentence_ends {
+        if sentence_end - start > max_len && end > start {
+            chunks.push(start..end);
+            start = end;
+        }
+        end = sentence_end;
+    }
+    if end > start {
+        chunks.push(start..end);
+    }
+    chunks
+}
+
+/// Spans for byte ranges of a document, in document order
+fn spans_at(content: &str, ranges: Vec<Range<usize>>) -> Vec<TextSpan> {
+    let mut point = Point::default();
+    let mut offset = 0;
+    ranges
+        .into_iter()
+        .map(|range| {
+            let start = advance_point(point, &content[offset..range.start]);
+            let end = advance_point(start, &content[range.clone()]);
+            (point, offset) = (end, range.end);
+            TextSpan::new(
+                content[range.clone()].to_string(),
+                range.start,
+                range.end,
+                start.row,
+                start.column,
+                end.row,
+                end.column,
+            )
+        })
+        .collect()
+}
+
+/// Advance a tree-sitter point (row, byte column) over the given text
+fn advance_point(point: Point, text: &str) -> Point {
+    match text.rfind('\n') {
+        Some(i) => Point {
+            row: point.row + text.matches('\n').count(),
+            column: text.len() - i - 1,
+        },
+        None => Point {
+            row: point.row,
+            column: point.column + text.len(),
+        },
+    }
+}
+
+/// Lines of Markdown outside fenced code blocks, with their byte offsets and
+/// without line breaks
+#[cfg(not(feature = "parsers"))]
+fn markdown_lines(content: &str) -> Vec<(usize, &str)> {
+    let mut lines = Vec::new();
+    let mut fence: Option<&str> = None;
+    let mut offset = 0;
+    for line in content.split_inclusive('\n') {
+        let start = offset;
+        offset += line.len();
+        let line = line.trim_end_matches(['\n', '\r']);
+        let trimmed = line.trim_start();
+        match fence {
+            Some(marker) if trimmed.starts_with(marker) => fence = None,
+            Some(_) => {}
+            None => match ["```", "~~~"].into_iter().find(|marker| trimmed.starts_with(marker)) {
+                Some(marker) => fence = Some(marker),
+                None => lines.push((start, line)),
+            },
+        }
+    }
+    lines
+}
+
+/// Byte ranges of the prose of Markdown: paragraphs, and the text of
+/// headings, list items and block quotes
+#[cfg(not(feature = "parsers"))]
+fn markdown_prose(content: &str) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut paragraph: Option<Range<usize>> = None;
+    for (start, line) in markdown_lines(content) {
+        let text = markdown_line_text(line);
+        if text.trim().is_empty() || is_markdown_rule(line) {
+            ranges.extend(paragraph.take());
+            continue;
+        }
+
+        let text_start = start + (text.as_ptr() as usize - line.as_ptr() as usize);
+        let text_end = text_start + text.trim_end().len();
+        if text.len() != line.trim_start().len() {
+            // A heading or list item stands on its own
+            ranges.extend(paragraph.take());
+            ranges.push(text_start..text_end);
+        } else {
+            match &mut paragraph {
+                Some(range) => range.end = text_end,
+                None => paragraph = Some(text_start..text_end),
+            }
+        }
+    }
+    ranges.extend(paragraph);
+    ranges
+}
+
+/// ATX and setext headings of Markdown: their levels and the byte ranges of
+/// their titles
+#[cfg(not(feature = "parsers"))]
+fn markdown_line_headings(content: &str) -> Vec<(usize, Range<usize>)> {
+    let mut headings = Vec::new();
+    let mut previous: Option<(usize, &str)> = None;
+    for (start, line) in markdown_lines(content) {
+        let trimmed = line.trim_start();
+        let hashes = trimmed.len() - trimmed.trim_start_matches('#').len();
+        let after = &trimmed[hashes..];
+        if (1..=6).contains(&hashes) && (after.is_empty() || after.starts_with([' ', '\t'])) {
+            // A closing sequence of #s needs a space before it
+            let title = match after.trim().trim_end_matches('#') {
+                rest if rest.is_empty() || rest.ends_with([' ', '\t']) => rest.trim(),
+                _ => after.trim(),
+            };
+            if !title.is_empty() {
+                let title_start = start + (title.as_ptr() as usize - line.as_ptr() as usize);
+                headings.push((hashes, title_start..title_start + title.len()));
+            }
+            previous = None;
+            continue;
+        }
+
+        let underline = line.trim();
+        let level = match underline.chars().next() {
+            Some('=') if underline.chars().all(|c| c == '=') => Some(1),
+            Some('-') if underline.chars().all(|c| c == '-') => Some(2),
+            _ => None,
+        };
+        match (level, previous) {
+            (Some(level), Some((title_start, title))) => {
+                let leading = title.len() - title.trim_start().len();
+                let title_start = title_start + leading;
+                headings.push((level, title_start..title_start + title.trim().len()));
+                previous = None;
+            }
+            _ => previous = (!underline.is_empty()).then_some((start, line)),
+        }
+    }
+    headings
+}
+
+/// Text of a Markdown line after heading, list and block quote markers
+#[cfg(not(feature = "parsers"))]
+fn markdown_line_text(line: &str) -> &str {
+    let mut text = line.trim_start();
+    loop {
+        let hashes = text.len() - text.trim_start_matches('#').len();
+        let digits = text.len() - text.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        let marker = if (1..=6).contains(&hashes) {
+            hashes
+        } else if text.starts_with(['-', '*', '+', '>']) {
+            1
+        } else if digits > 0 && text[digits..].starts_with(['.', ')']) {
+            digits + 1
+        } else {
+            return text;
+        };
+        let rest = &text[marker..];
+        if !(rest.is_empty() || rest.starts_with([' ', '\t']) || text.starts_with('>')) {
+            return text;
+        }
+        text = rest.trim_start();
+    }
+}
+
+/// Whether a Markdown line is a thematic break or a setext underline
+#[cfg(not(feature = "parsers"))]
+fn is_markdown_rule(line: &str) -> bool {
+    let line = line.trim();
+    line.len() >= 3
+        && ['-', '=', '*', '_']
+            .into_iter()
+            .any(|c| line.chars().all(|x| x == c || x == ' '))
+}
+
+/// Map a Markdown heading marker node to its heading level
+#[cfg(feature = "parsers")]
+fn markdown_heading_level(kind: &str) -> Option<usize> {
+    match kind {
+        "atx_h1_marker" | "setext_h1_underline" => Some(1),
+        "atx_h2_marker" | "setext_h2_underline" => Some(2),
+        "atx_h3_marker" => Some(3),
+        "atx_h4_marker" => Some(4),
+        "atx_h5_marker" => Some(5),
+        "atx_h6_marker" => Some(6),
+        _ => None,
+    }
+}
+
+/// Parse a LaTeX sectioning command (without the leading backslash).
+///
+/// Returns the level, the byte offset of the title within `command`, and the title.
+fn parse_latex_section(command: &str) -> Option<(usize, usize, &str)> {
+    let (name, level) = LATEX_SECTIONS
+        .iter()
+        .find(|(name, _)| {
+            command.starts_with(name)
+                && !command[name.len()..].starts_with(|c: char| c.is_ascii_alphabetic())
+        })
+        .copied()?;
+
+    let mut rest = command[name.len()..].trim_start_matches('*');
+    // Skip optional short title: \section[short]{long}
+    if rest.starts_with('[') {
+        rest = &rest[rest.find(']')? + 1..];
+    }
+    let rest = rest.strip_prefix('{')?;
+
+    // Find the matching closing brace
+    let mut depth = 1;
+    for (i, c) in rest.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    let title_start = command.len() - rest.len();
+                    return Some((level, title_start, &rest[..i]));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+impl Default for TextExtractor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    // ==========================================
+    // TextSpan tests
+    // ==========================================
+
+    #[test]
+    fn test_text_span_creation() {
+        let span = TextSpan::new(
+            "テスト".to_string(),
+            0,
+            9, // 3 chars * 3 bytes
+            0,
+            0,
+            0,
+            3,
+        );
+        assert_eq!(span.text, "テスト");
+        assert_eq!(span.start_byte, 0);
+        assert_eq!(span.end_byte, 9);
+    }
+
+    // ==========================================
+    // FileType detection tests
+    // ==========================================
+
+    #[test]
+    fn test_file_type_from_extension() {
+        assert_eq!(FileType::from_extension("md"), FileType::Markdown);
+        assert_eq!(FileType::from_extension("rs"), FileType::Rust);
+        assert_eq!(FileType::from_extension("py"), FileType::Python);
+        assert_eq!(FileType::from_extension("ts"), FileType::TypeScript);
+        assert_eq!(FileType::from_extension("js"), FileType::JavaScript);
+        assert_eq!(FileType::from_extension("c"), FileType::C);
+        assert_eq!(FileType::from_extension("cpp"), FileType::Cpp);
+        assert_eq!(FileType::from_extension("go"), FileType::Go);
+        assert_eq!(FileType::from_extension("tex"), FileType::LaTeX);
+        assert_eq!(FileType::from_extension("txt"), FileType::PlainText);
+        assert_eq!(FileType::from_extension("unknown"), FileType::PlainText);
+    }
+
+    #[test]
+    fn test_file_type_case_insensitive() {
+        assert_eq!(FileType::from_extension("MD"), FileType::Markdown);
+        assert_eq!(FileType::from_extension("Rs"), FileType::Rust);
+        assert_eq!(FileType::from_extension("PY"), FileType::Python);
+    }
+
+    #[test]
+    fn test_file_type_from_language_id() {
+        assert_eq!(FileType::from_language_id("markdown"), Some(FileType::Markdown));
+        assert_eq!(FileType::from_language_id("typescriptreact"), Some(FileType::TypeScript));
+        assert_eq!(FileType::from_language_id("latex"), Some(FileType::LaTeX));
+        assert_eq!(FileType::from_language_id("plaintext"), Some(FileType::PlainText));
+        assert_eq!(FileType::from_language_id("japanese"), None);
+    }
+
+    // ==========================================
+    // Plain text extraction tests
+    // ==========================================
+
+    #[test]
+    fn test_extract_plain_text() {
+        let extractor = TextExtractor::new();
+        let content = "これはテストです。\n二行目です。";
+        let spans = extractor.extract(content, FileType::PlainText).unwrap();
+
+        assert_eq!(spans.len(), 1);
+        assert_eq!(spans[0].text, content);
+        assert_eq!(spans[0].start_byte, 0);
+        assert_eq!(spans[0].start_line, 0);
+    }
+
+    #[test]
+    fn test_sentence_chunks() {
+        let text = "一文目。二文目！\n三文目";
+        let chunks = sentence_chunks(text, "一文目。二文目！".len());
+
+        assert_eq!(
+            chunks.iter().map(|chunk| &text[chunk.clone()]).collect::<Vec<_>>(),
+            vec!["一文目。二文目！", "\n三文目"]
+        );
+        assert_eq!(sentence_chunks(text, 1).len(), 4);
+        assert_eq!(sentence_chunks(text, text.len()), vec![0..text.len()]);
+        assert!(sentence_chunks("", 10).is_empty());
+    }
+
+    #[test]
+    fn test_extract_long_plain_text_in_chunks() {
+        let extractor = TextExtractor::new();
+        let sentence = "これは長い文書の一文です。\n";
+        let content = sentence.repeat(PLAIN_TEXT_CHUNK_BYTES / sentence.len() * 3);
+        let spans = extractor.extract(&content, FileType::PlainText).unwrap();
+
+        assert!(spans.len() >= 3);
+        assert!(spans.iter().all(|span| span.text.len() <= PLAIN_TEXT_CHUNK_BYTES));
+        assert_eq!(spans.iter().map(|span| span.text.as_str()).collect::<String>(), content);
+        assert!(spans.iter().all(|span| span.start_col == 0 && span.text.starts_with("これは")));
+        assert_eq!(spans[1].start_line, spans[0].end_line);
+        assert_eq!(spans[1].start_byte, spans[0].end_byte);
+    }
+
+    #[test]
+    fn test_extract_empty_plain_text() {
+        let extractor = TextExtractor::new();
+        let spans = extractor.extract("", FileType::PlainText).unwrap();
+        assert!(spans.is_empty());
+    }
+
+    // ==========================================
+    // Markdown extraction tests
+    // ==========================================
+
+    #[test]
+    fn test_extract_markdown_paragraph() {
+        let extractor = TextExtractor::new();
+        let content = "これは段落です。";
+        let spans = extractor.extract(content, FileType::Markdown).unwrap();
+
+        assert!(!spans.is_empty());
+        // Should contain the paragraph text
+        let texts: Vec<&str> = spans.iter().map(|s| s.text.as_str()).collect();
+        assert!(texts.iter().any(|t| t.contains("これは段落です")));
+    }
+
+    #[test]
+    fn test_extract_markdown_heading() {
+        let extractor = TextExtractor::new();
+        let content = "# 見出し\n\n本文です。";
+        let spans = extractor.extract(content, FileType::Markdown).unwrap();
+
+        let texts: Vec<&str> = spans.iter().map(|s| s.text.as_str()).collect();
+        // Should contain heading and paragraph
+        assert!(texts.iter().any(|t| t.contains("見出し")));
+        assert!(texts.iter().any(|t| t.contains("本文です")));
+    }
+
+    #[test]
+    fn test_extract_markdown_skip_code_block() {
+        let extractor = TextExtractor::new();
+        let content = "説明文\n\n```rust\nlet x = 1;\n```\n\n続きの文";
+        let spans = extractor.extract(content, FileType::Markdown).unwrap();
+
+        let all_text: String = spans.iter().map(|s| s.text.as_str()).collect();
+        // Code block content should NOT be included
+        assert!(!all_text.contains("let x = 1"));
+        // But surrounding text should be
+        assert!(all_text.contains("説明文"));
+    }
+
+    // ==========================================
+    // Rust comment extraction tests
+    // ==========================================
+
+    #[test]
+    #[cfg(feature = "parsers")]
+    fn test_extract_rust_line_comment() {
+        let extractor = TextExtractor::new();
+        let content = r#"
+fn main() {
+    // これはコメントです
+    let x = 1;
+}
+"#;
+        let spans = extractor.extract(content, FileType::Rust).unwrap();
+
+        assert!(!spans.is_empty());
+        let texts: Vec<&str> = spans.iter().map(|s| s.text.as_str()).collect();
+        assert!(texts.iter().any(|t| t.contains("これはコメントです")));
+    }
+
+    #[test]
+    #[cfg(feature = "parsers")]
+    fn test_extract_comment_span_points_at_text() {
+        let extractor = TextExtractor::new();
+        let content = "fn main() {\n    // これはコメントです\n}\n";
+        let spans = extractor.extract(content, FileType::Rust).unwrap();
+
+        assert_eq!(spans.len(), 1);
+        let span = &spans[0];
+        assert_eq!(&content[span.start_byte..span.end_byte], span.text);
+        assert_eq!(span.start_line, 1);
+        assert_eq!(span.start_col, 7);
+    }
+
+    #[test]
+    #[cfg(feature = "parsers")]
+    fn test_extract_rust_doc_comment() {
+        let extractor = TextExtractor::new();
+        let content = r#"
+/// ドキュメントコメント
+fn foo() {}
+"#;
+        let spans = extractor.extract(content, FileType::Rust).unwrap();
+
+        let texts: Vec<&str> = spans.iter().map(|s| s.text.as_str()).collect();
+        assert!(texts.iter().any(|t| t.contains("ドキュメントコメント")));
+    }
+
+    #[test]
+    #[cfg(feature = "parsers")]
+    fn test_extract_rust_block_comment() {
+        let extractor = TextExtractor::new();
+        let content = r#"
+/*
+ * ブロックコメント
+ * 複数行
+ */
+fn main() {}
+"#;
+        let spans = extractor.extract(content, FileType::Rust).unwrap();
+
+        let all_text: String = spans.iter().map(|s| &s.text).cloned().collect();
+        assert!(all_text.contains("ブロックコメント"));
+    }
+
+    #[test]
+    #[cfg(feature = "parsers")]
+    fn test_extract_rust_no_code() {
+        let extractor = TextExtractor::new();
+        let content = r#"
+fn main() {
+    // コメント
+    let message = "文字列リテラル";
+}
+"#;
+        let spans = extractor.extract(content, FileType::Rust).unwrap();
+
+        let all_text: String = spans.iter().map(|s| &s.text).cloned().collect();
+        // Should include comment
+        assert!(all_text.contains("コメント"));
+        // Should NOT include string literal or code
+        assert!(!all_text.contains("文字列リテラル"));
+        assert!(!all_text.contains("let message"));
+    }
+
+    // ==========================================
+    // Python comment extraction tests
+    // ==========================================
+
+    #[test]
+    #[cfg(feature = "parsers")]
+    fn test_extract_python_comment() {
+        let extractor = TextExtractor::new();
+        let content = r#"
+# Pythonのコメント
+x = 1
+"#;
+        let spans = extractor.extract(content, FileType::Python).unwrap();
+
+        let texts: Vec<&str> = spans.iter().map(|s| s.text.as_str()).collect();
+        assert!(texts.iter().any(|t| t.contains("Pythonのコメント")));
+    }
+
+    #[test]
+    #[cfg(feature = "parsers")]
+    fn test_extract_python_docstring() {
+        let extractor = TextExtractor::new();
+        let content = r#"
+def foo():
+    """
+    これはdocstringです。
+    関数の説明。
+    """
+    pass
+"#;
+        let spans = extractor.extract(content, FileType::Python).unwrap();
+
+        let all_text: String = spans.iter().map(|s| &s.text).cloned().collect();
+        assert!(all_text.contains("docstring") || all_text.contains("関数の説明"));
+    }
+
+    // ==========================================
+    // JavaScript/TypeScript comment extraction tests
+    // ==========================================
+
+    #[test]
+    #[cfg(feature = "parsers")]
+    fn test_extract_js_line_comment() {
+        let extractor = TextExtractor::new();
+        let content = r#"
+// JSのコメント
+const x = 1;
+"#;
+        let spans = extractor.extract(content, FileType::JavaScript).unwrap();
+
+        let texts: Vec<&str> = spans.iter().map(|s| s.text.as_str()).collect();
+        assert!(texts.iter().any(|t| t.contains("JSのコメント")));
+    }
+
+    #[test]
+    #[cfg(feature = "parsers")]
+    fn test_extract_ts_block_comment() {
+        let extractor = TextExtractor::new();
+        let content = r#"
+/**
+ * TypeScriptのJSDocコメント
+ */
+function foo() {}
+"#;
+        let spans = extractor.extract(content, FileType::TypeScript).unwrap();
+
+        let all_text: String = spans.iter().map(|s| &s.text).cloned().collect();
+        assert!(all_text.contains("TypeScript") || all_text.contains("JSDoc"));
+    }
+
+    // ==========================================
+    // C/C++ comment extraction tests
+    // ==========================================
+
+    #[test]
+    #[cfg(feature = "parsers")]
+    fn test_extract_c_comment() {
+        let extractor = TextExtractor::new();
+        let content = r#"
+// Cのコメント
+int main() { return 0; }
+"#;
+        let spans = extractor.extract(content, FileType::C).unwrap();
+
+        let texts: Vec<&str> = spans.iter().map(|s| s.text.as_str()).collect();
+        assert!(texts.iter().any(|t| t.contains("Cのコメント")));
+    }
+
+    #[test]
+    #[cfg(feature = "parsers")]
+    fn test_extract_cpp_block_comment() {
+        let extractor = TextExtractor::new();
+        let content = r#"
+/* C++のブロックコメント */
+int main() { return 0; }
+"#;
+        let spans = extractor.extract(content, FileType::Cpp).unwrap();
+
+        let texts: Vec<&str> = spans.iter().map(|s| s.text.as_str()).collect();
+        assert!(texts.iter().any(|t| t.contains("C++のブロックコメント")));
+    }
+
+    // ==========================================
+    // Go comment extraction tests
+    // ==========================================
+
+    #[test]
+    #[cfg(feature = "parsers")]
+    fn test_extract_go_comment() {
+        let extractor = TextExtractor::new();
+        let content = r#"
+// Goのコメント
+package main
+func main() {}
+"#;
+        let spans = extractor.extract(content, FileType::Go).unwrap();
+
+        let texts: Vec<&str> = spans.iter().map(|s| s.text.as_str()).collect();
+        assert!(texts.iter().any(|t| t.contains("Goのコメント")));
+    }
+
+    // ==========================================
+    // Integration tests
+    // ==========================================
+
+    #[test]
+    fn test_extractor_default() {
+        let extractor = TextExtractor::default();
+        let spans = extractor.extract("テスト", FileType::PlainText).unwrap();
+        assert_eq!(spans.len(), 1);
+    }
+
+    // ==========================================
+    // Heading extraction tests
+    // ==========================================
+
+    #[test]
+    #[cfg(feature = "parsers")]
+    fn test_input_edit() {
+        let edit = input_edit("// 一行目\n// 二行目\n", "// 一行目\n// 二つ目の行\n");
+
+        assert_eq!(edit.start_byte, "// 一行目\n// 二".len());
+        assert_eq!(edit.old_end_byte, "// 一行目\n// 二行".len());
+        assert_eq!(edit.new_end_byte, "// 一行目\n// 二つ目の行".len());
+        assert_eq!((edit.start_position.row, edit.start_position.column), (1, "// 二".len()));
+        assert_eq!((edit.new_end_position.row, edit.new_end_position.column), (1, "// 二つ目の行".len()));
+
+        let unchanged = input_edit("同じ", "同じ");
+        assert_eq!(unchanged.start_byte, unchanged.old_end_byte);
+        assert_eq!(unchanged.start_byte, unchanged.new_end_byte);
+    }
+
+    #[test]
+    #[cfg(feature = "parsers")]
+    fn test_extract_incremental() {
+        let extractor = TextExtractor::new();
+        let before = "fn main() {\n    // 最初のコメント\n}\n\n// 末尾のコメント\n";
+        let after = "fn main() {\n    // 書き換えたコメント\n    let x = 1;\n}\n\n// 末尾のコメント\n";
+
+        let (_, tree) = extractor.extract_incremental(before, FileType::Rust, None).unwrap();
+        let (spans, tree) = extractor.extract_incremental(after, FileType::Rust, tree).unwrap();
+
+        assert_eq!(spans, extractor.extract(after, FileType::Rust).unwrap());
+        assert!(tree.is_some());
+
+        // A tree of another file type is not reused
+        let text = "本文です。";
+        let (spans, _) = extractor.extract_incremental(text, FileType::Markdown, tree).unwrap();
+        assert_eq!(spans.len(), 1);
+        let (_, tree) = extractor.extract_incremental(text, FileType::PlainText, None).unwrap();
+        assert!(tree.is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "parsers")]
+    fn test_parsers_are_reused() {
+        let extractor = TextExtractor::new();
+        let pooled = |name| extractor.parsers.lock().unwrap().get(name).map_or(0, Vec::len);
+
+        extractor.extract("// コメント", FileType::Rust).unwrap();
+        extractor.extract("// コメント", FileType::Rust).unwrap();
+        extractor.extract("# 見出し", FileType::Markdown).unwrap();
+        extractor.extract_headings("# 見出し", FileType::Markdown).unwrap();
+
+        assert_eq!(pooled("Rust"), 1);
+        assert_eq!(pooled("Markdown"), 1);
+        assert_eq!(pooled("Go"), 0);
+    }
+
+    #[test]
+    fn test_extract_markdown_headings() {
+        let extractor = TextExtractor::new();
+        let content = "# 第一章\n\n本文\n\n## 第一節\n\n本文\n\n設定\n---\n";
+        let headings = extractor.extract_headings(content, FileType::Markdown).unwrap();
+
+        let outline: Vec<(&str, usize)> = headings.iter().map(|h| (h.span.text.as_str(), h.level)).collect();
+        assert_eq!(outline, vec![("第一章", 1), ("第一節", 2), ("設定", 2)]);
+        assert_eq!(&content[headings[0].span.start_byte..headings[0].span.end_byte], "第一章");
+        assert_eq!(headings[1].span.start_line, 4);
+    }
+
+    #[test]
+    fn test_extract_latex_headings() {
+        let extractor = TextExtractor::new();
+        let content = "\\documentclass{article}\n\\section{はじめに}\n本文\n  \\subsection*[短縮]{背景と{目的}}\n";
+        let headings = extractor.extract_headings(content, FileType::LaTeX).unwrap();
+
+        let outline: Vec<(&str, usize)> = headings.iter().map(|h| (h.span.text.as_str(), h.level)).collect();
+        assert_eq!(outline, vec![("はじめに", 3), ("背景と{目的}", 4)]);
+        assert_eq!(&content[headings[1].span.start_byte..headings[1].span.end_byte], "背景と{目的}");
+    }
+
+    #[test]
+    fn test_extract_headings_unsupported_type() {
+        let extractor = TextExtractor::new();
+        let headings = extractor.extract_headings("// # コメント", FileType::Rust).unwrap();
+        assert!(headings.is_empty());
+    }
+
+    #[test]
+    #[cfg(not(feature = "parsers"))]
+    fn test_markdown_prose_without_parsers() {
+        let content = "# 見出し #\n\n一行目と\n二行目の段落。\n\n- 項目です\n> 引用です\n\n```\n# コード\n```\n";
+        let texts: Vec<&str> = markdown_prose(content)
+            .into_iter()
+            .map(|range| &content[range])
+            .collect();
+        assert_eq!(
+            texts,
+            vec!["見出し #", "一行目と\n二行目の段落。", "項目です", "引用です"]
+        );
+
+        let headings: Vec<(usize, &str)> = markdown_line_headings(content)
+            .into_iter()
+            .map(|(level, range)| (level, &content[range]))
+            .collect();
+        assert_eq!(headings, vec![(1, "見出し")]);
+        assert_eq!(markdown_line_headings("C#\n# C#\n")[0].0, 1);
+        assert_eq!(&"# C#\n"[markdown_line_headings("# C#\n")[0].1.clone()], "C#");
+    }
+}
@@ -0,0 +1,236 @@
+//! MoZuku core - Japanese proofreading without a language server
+//!
+//! Morphological analysis, text extraction from documents and grammar checks,
+//! shared by the `mozuku-rs` language server and CLI. Diagnostics use the LSP
+//! data types (`lsp-types`) but nothing here depends on a server runtime, so
+//! the checks can run in build tools such as static site generators:
+//!
+//! ```no_run
+//! use mozuku_core::extractor::FileType;
+//!
+//! let diagnostics = mozuku_core::check_text("これを食べれる。", FileType::PlainText)?;
+//! for diagnostic in &diagnostics {
+//!     println!("{}:{} {}", diagnostic.range.start.line + 1, diagnostic.range.start.character + 1, diagnostic.message);
+//! }
+//! # anyhow::Ok(())
+//! ```
+//!
+//! Use [`Proofreader`] to check with a `mozuku.toml` configuration.
+
+pub mod analyzer;
+pub mod checker;
+pub mod config;
+pub mod dictionary;
+pub mod extractor;
+pub mod notation;
+pub mod pattern;
+#[cfg(feature = "plugins")]
+pub mod plugin;
+pub mod preset;
+pub mod readability;
+pub mod rules;
+pub mod spellcheck;
+pub mod suppression;
+pub mod terms;
+pub mod textlint;
+pub mod utf16;
+pub mod workspace;
+
+use std::sync::{Arc, OnceLock};
+
+use anyhow::{Context, Result};
+use lsp_types::{Diagnostic, Url};
+
+use crate::analyzer::{MorphologicalAnalyzer, TokenInfo};
+use crate::checker::{large_file_diagnostic, map_span_diagnostic, GrammarChecker, CHECKED_TEXT_URI};
+use crate::config::Config;
+use crate::extractor::{FileType, TextExtractor, TextSpan};
+use crate::readability::ReadabilityScore;
+use crate::suppression::Suppressions;
+use crate::utf16::LineIndex;
+
+/// Checks documents with one configuration
+pub struct Proofreader {
+    analyzer: Arc<MorphologicalAnalyzer>,
+    checker: GrammarChecker,
+    extractor: TextExtractor,
+    config: Config,
+}
+
+impl Proofreader {
+    /// Create a proofreader. Unlike the language server, a failed analyzer
+    /// is an error since degraded results would silently report nothing.
+    pub fn new(config: Config) -> Result<Self> {
+        let analyzer = MorphologicalAnalyzer::with_user_dictionary(config.dictionary.user_dictionary.as_deref())
+            .context("形態素解析器の初期化に失敗しました")?;
+        Ok(Self::with_analyzer(config, Arc::new(analyzer)))
+    }
+
+    /// Create a proofreader with an analyzer created by the caller, such as
+    /// [`MorphologicalAnalyzer::degraded`] where no dictionary is embedded,
+    /// or one shared by proofreaders with different configurations
+    pub fn with_analyzer(config: Config, analyzer: Arc<MorphologicalAnalyzer>) -> Self {
+        Self {
+            checker: GrammarChecker::new(analyzer.clone()),
+            analyzer,
+            extractor: TextExtractor::new(),
+            config,
+        }
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    pub fn checker(&self) -> &GrammarChecker {
+        &self.checker
+    }
+
+    pub fn analyzer(&self) -> &MorphologicalAnalyzer {
+        &self.analyzer
+    }
+
+    /// Check the text of a document, as the language server does on open and
+    /// change. Positions are relative to `content`.
+    pub fn check_text(&self, content: &str, file_type: FileType) -> Vec<Diagnostic> {
+        let uri = Url::parse(CHECKED_TEXT_URI).unwrap();
+        self.check_document(&uri, content, file_type).0
+    }
+
+    /// Check the text of a document like [`Self::check_text`], also scoring
+    /// the readability of the checked text. Related information points at `uri`.
+    pub fn check_document(&self, uri: &Url, content: &str, file_type: FileType) -> (Vec<Diagnostic>, ReadabilityScore) {
+        let checker_config = &self.config.checker;
+        if !checker_config.is_file_type_enabled(file_type) {
+            return (Vec::new(), ReadabilityScore::default());
+        }
+
+        let text = checker_config.analysis_text(content);
+        let mut diagnostics = Vec::new();
+        if text.map(str::len) != Some(content.len()) {
+            diagnostics.push(large_file_diagnostic(checker_config, text.is_some()));
+        }
+        let text = text.unwrap_or_default();
+
+        let spans = self.extractor.extract(text, file_type).unwrap_or_else(|e| {
+            tracing::warn!("Failed to extract text from {}: {}", uri, e);
+            self.extractor.extract(text, FileType::PlainText).unwrap_or_default()
+        });
+        let doc_index = LineIndex::new(content);
+        let span_tokens: Vec<Vec<TokenInfo>> = spans.iter().map(|span| self.analyzer.tokenize(&span.text)).collect();
+        for (span, tokens) in spans.iter().zip(&span_tokens) {
+            let span_index = LineIndex::new(&span.text);
+            diagnostics.extend(
+                self.checker
+                    .check_with_tokens(&span.text, tokens, checker_config)
+                    .into_iter()
+                    .map(|diag| map_span_diagnostic(diag, uri, &span_index, &doc_index, span.start_byte)),
+            );
+        }
+
+        let scored: Vec<(&TextSpan, &[TokenInfo])> = spans
+            .iter()
+            .zip(&span_tokens)
+            .map(|(span, tokens)| (span, tokens.as_slice()))
+            .collect();
+        if checker_config.is_rule_enabled(readability::RULE_ID) {
+            let sections = readability::sections(&self.extractor, text, file_type);
+            diagnostics.extend(readability::check_sections(text, &sections, &scored, checker_config));
+        }
+        let score = readability::score_range(0..text.len(), &scored);
+
+        let suppressions = Suppressions::parse(content);
+        diagnostics.retain(|diag| !suppressions.is_suppressed(diag));
+        diagnostics.sort_by_key(|diag| diag.range.start);
+        (diagnostics, score)
+    }
+
+    /// Readability of the text of a document, without checking it
+    pub fn readability(&self, content: &str, file_type: FileType) -> ReadabilityScore {
+        let text = self.config.checker.analysis_text(content).unwrap_or_default();
+        let spans = self.extract(text, file_type);
+        let span_tokens: Vec<Vec<TokenInfo>> = spans.iter().map(|span| self.analyzer.tokenize(&span.text)).collect();
+        let scored: Vec<(&TextSpan, &[TokenInfo])> = spans
+            .iter()
+            .zip(&span_tokens)
+            .map(|(span, tokens)| (span, tokens.as_slice()))
+            .collect();
+        readability::score_range(0..text.len(), &scored)
+    }
+
+    /// Japanese text of a document, falling back to plain text when the
+    /// document cannot be parsed
+    pub fn extract(&self, content: &str, file_type: FileType) -> Vec<TextSpan> {
+        self.extractor
+            .extract(content, file_type)
+            .or_else(|_| self.extractor.extract(content, FileType::PlainText))
+            .unwrap_or_default()
+    }
+
+    /// Morphemes of text, with the user dictionary of the configuration
+    pub fn tokenize(&self, text: &str) -> Vec<TokenInfo> {
+        self.analyzer.tokenize(text)
+    }
+}
+
+/// Proofreader with the default configuration, created on first use
+fn default_proofreader() -> Result<&'static Proofreader> {
+    static DEFAULT: OnceLock<Proofreader> = OnceLock::new();
+    if let Some(proofreader) = DEFAULT.get() {
+        return Ok(proofreader);
+    }
+    let proofreader = Proofreader::new(Config::default())?;
+    Ok(DEFAULT.get_or_init(|| proofreader))
+}
+
+/// Check the text of a document with the default configuration
+pub fn check_text(content: &str, file_type: FileType) -> Result<Vec<Diagnostic>> {
+    Ok(default_proofreader()?.check_text(content, file_type))
+}
+
+/// Japanese text of a document: comments and string literals of source
+/// code, paragraphs of Markdown, or the whole of plain text
+pub fn extract(content: &str, file_type: FileType) -> Result<Vec<TextSpan>> {
+    TextExtractor::new().extract(content, file_type)
+}
+
+/// Morphemes of text, with the built-in IPADIC dictionary
+pub fn tokenize(text: &str) -> Result<Vec<TokenInfo>> {
+    Ok(default_proofreader()?.tokenize(text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_text() {
+        let diagnostics = check_text("# 見出し\n\nこれを食べれる。\n", FileType::Markdown).unwrap();
+        let ra_nuki = diagnostics
+            .iter()
+            .find(|d| d.code == Some(lsp_types::NumberOrString::String("ra-nuki".to_string())))
+            .unwrap();
+        assert_eq!(ra_nuki.range.start.line, 2);
+        let diagnostics = check_text("これを食べられる。", FileType::PlainText).unwrap();
+        assert!(diagnostics.is_empty());
+    }
+
+    #[test]
+    fn test_extract_and_tokenize() {
+        let spans = extract("// 日本語のコメント\nfn main() {}\n", FileType::Rust).unwrap();
+        assert_eq!(spans.len(), 1);
+        assert!(spans[0].text.contains("日本語のコメント"));
+
+        let tokens = tokenize("日本語のコメント").unwrap();
+        assert_eq!(tokens[0].surface, "日本語");
+    }
+
+    #[test]
+    fn test_proofreader_config() {
+        let mut config = Config::default();
+        config.checker.ra_nuki = false;
+        let proofreader = Proofreader::new(config).unwrap();
+        let diagnostics = proofreader.check_text("これを食べれる。", FileType::PlainText);
+        assert!(diagnostics.is_empty());
+    }
+}
@@ -0,0 +1,621 @@
+//! Notation rules over runs of characters
+//!
+//! Rules on how numbers and symbols are written. Like the rules ported from
+//! textlint they look at the text rather than at tokens, so they keep
+//! working without morphological analysis.
+
+use std::ops::Range;
+
+use lsp_types::{Diagnostic, DiagnosticSeverity};
+
+use crate::checker::{fix_data, Fix};
+use crate::config::{BracketWidth, CheckerConfig, UnitSpacing};
+use crate::dictionary::CharKind;
+use crate::textlint::{char_runs, TextContext};
+
+/// Units of the groups of four digits
+const UNITS: [&str; 5] = ["", "万", "億", "兆", "京"];
+
+/// Numbers in ASCII digits counting something (12,000円, 120000人) written
+/// with thousands separators or without 万/億/兆. Digits that belong to an
+/// identifier, a version or a decimal (v1,000, 3.14, 2,000.5円) are skipped.
+pub(crate) fn number_grouping(ctx: &TextContext, _config: &CheckerConfig) -> Vec<Diagnostic> {
+    char_runs(ctx.text, |c| c.is_ascii_digit() || c == ',')
+        .into_iter()
+        .filter_map(|run| {
+            // Commas around the number are punctuation
+            let number = ctx.text[run.clone()].trim_matches(',');
+            let start = run.start + ctx.text[run.clone()].find(number)?;
+            let end = start + number.len();
+
+            let before = ctx.text[..start].chars().next_back();
+            if before.is_some_and(|c| c.is_ascii_alphanumeric() || "._-/#:+¥$".contains(c)) {
+                return None;
+            }
+            // Only numbers followed by Japanese, such as a counter or a particle
+            let after = ctx.text[end..].chars().next()?;
+            if matches!(CharKind::of(after), CharKind::Alphanumeric | CharKind::Other) {
+                return None;
+            }
+            if number.starts_with('0') || !is_grouped(number) {
+                return None;
+            }
+            let value: u64 = number.replace(',', "").parse().ok()?;
+            // 12,000万円 is left to the writer
+            if value >= 10_000 && matches!(after, '万' | '億' | '兆') {
+                return None;
+            }
+            let grouped = group_number(value)?;
+            if grouped == number {
+                return None;
+            }
+
+            let message = if value >= 10_000 {
+                format!("万以上の数は万・億・兆を使って書きます。「{}」→「{}」", number, grouped)
+            } else {
+                format!("4桁の数には位取りのコンマを付けません。「{}」→「{}」", number, grouped)
+            };
+            let mut diagnostic = ctx.diagnostic("number-grouping", start..end, message);
+            diagnostic.data = fix_data(vec![Fix::new(diagnostic.range, grouped)]);
+            Some(diagnostic)
+        })
+        .collect()
+}
+
+/// Whether the commas of a number, if any, separate groups of three digits
+fn is_grouped(number: &str) -> bool {
+    let mut groups = number.split(',');
+    let first = groups.next().unwrap_or_default();
+    !number.contains(',') || ((1..=3).contains(&first.len()) && groups.all(|group| group.len() == 3))
+}
+
+/// A number written with 万/億/兆/京 for each group of four digits
+/// (12000 → 1万2000, 120000 → 12万), or `None` when it is too large
+fn group_number(value: u64) -> Option<String> {
+    if value == 0 {
+        return Some("0".to_string());
+    }
+    let mut groups = Vec::new();
+    let mut rest = value;
+    while rest > 0 {
+        groups.push(rest % 10_000);
+        rest /= 10_000;
+    }
+    if groups.len() > UNITS.len() {
+        return None;
+    }
+    Some(
+        groups
+            .iter()
+            .zip(UNITS)
+            .rev()
+            .filter(|(group, _)| **group > 0)
+            .map(|(group, unit)| format!("{}{}", group, unit))
+            .collect(),
+    )
+}
+
+/// Spellings of the units checked by `unit-notation`: the half-width
+/// symbol, the full-width symbol and the name in katakana
+const UNIT_SPELLINGS: &[&[&str]] = &[
+    &["%", "％", "パーセント"],
+    &["KB", "ＫＢ", "キロバイト"],
+    &["MB", "ＭＢ", "メガバイト"],
+    &["GB", "ＧＢ", "ギガバイト"],
+    &["TB", "ＴＢ", "テラバイト"],
+    &["MHz", "ＭＨｚ", "メガヘルツ"],
+    &["GHz", "ＧＨｚ", "ギガヘルツ"],
+    &["ms", "ｍｓ", "ミリ秒"],
+    &["mm", "ｍｍ", "ミリメートル"],
+    &["cm", "ｃｍ", "センチメートル"],
+    &["m", "ｍ", "メートル"],
+    &["km", "ｋｍ", "キロメートル"],
+    &["mg", "ｍｇ", "ミリグラム"],
+    &["g", "ｇ", "グラム"],
+    &["kg", "ｋｇ", "キログラム"],
+];
+
+/// A number followed by a unit
+struct UnitUse {
+    /// Byte range of the digits
+    number: Range<usize>,
+    /// Byte range of the unit, after the space if any
+    unit: Range<usize>,
+    /// Index in `UNIT_SPELLINGS`
+    kind: usize,
+    /// Index in the spellings of the unit
+    spelling: usize,
+}
+
+impl UnitUse {
+    fn spelling(&self) -> &'static str {
+        UNIT_SPELLINGS[self.kind][self.spelling]
+    }
+
+    fn spaced(&self) -> bool {
+        self.number.end < self.unit.start
+    }
+}
+
+/// Units written in different ways in the document (10GB, 10ＧＢ,
+/// 10ギガバイト), and spaces between numbers and unit symbols (10 GB)
+/// against `unit_spacing`. Only symbols in ASCII letters are checked for
+/// spaces: 10%, 10ＧＢ and 10ギガバイト are written without one either way.
+pub(crate) fn unit_notation(ctx: &TextContext, config: &CheckerConfig) -> Vec<Diagnostic> {
+    let uses = unit_uses(ctx.text);
+    let mut diagnostics = Vec::new();
+
+    for (kind, spellings) in UNIT_SPELLINGS.iter().enumerate() {
+        let of_kind: Vec<&UnitUse> = uses.iter().filter(|unit| unit.kind == kind).collect();
+        let Some(preferred) = most_used(&of_kind, |unit| unit.spelling) else {
+            continue;
+        };
+        let expected = spellings[preferred];
+        for unit in of_kind.iter().filter(|unit| unit.spelling != preferred) {
+            let message = format!("単位の表記が揺れています。「{}」→「{}」", unit.spelling(), expected);
+            let mut diagnostic = ctx.diagnostic("unit-notation", unit.unit.clone(), message);
+            diagnostic.data = fix_data(vec![Fix::new(diagnostic.range, expected.to_string())]);
+            diagnostics.push(diagnostic);
+        }
+    }
+
+    let symbols: Vec<&UnitUse> = uses
+        .iter()
+        .filter(|unit| unit.spelling().chars().all(|c| c.is_ascii_alphabetic()))
+        .collect();
+    let spaced = match config.unit_spacing {
+        UnitSpacing::None => false,
+        UnitSpacing::Space => true,
+        UnitSpacing::Consistent => match most_used(&symbols, |unit| unit.spaced()) {
+            Some(spaced) => spaced,
+            None => return diagnostics,
+        },
+    };
+    let message = match (config.unit_spacing, spaced) {
+        (UnitSpacing::Consistent, _) => "数値と単位記号の間のスペースの有無が揃っていません。",
+        (_, true) => "数値と単位記号の間にスペースを入れます。",
+        (_, false) => "数値と単位記号の間にスペースを入れません。",
+    };
+    for unit in symbols.iter().filter(|unit| unit.spaced() != spaced) {
+        let (range, fixed) = if spaced {
+            (unit.unit.clone(), format!(" {}", unit.spelling()))
+        } else {
+            (unit.number.end..unit.unit.end, unit.spelling().to_string())
+        };
+        let number = &ctx.text[unit.number.clone()];
+        let written = &ctx.text[unit.number.start..unit.unit.end];
+        let message = format!("{}「{}」→「{}{}」", message, written, number, fixed);
+        let mut diagnostic = ctx.diagnostic("unit-notation", range, message);
+        diagnostic.data = fix_data(vec![Fix::new(diagnostic.range, fixed)]);
+        diagnostics.push(diagnostic);
+    }
+    diagnostics
+}
+
+/// Numbers (10, 3.5, １０) followed by one of `UNIT_SPELLINGS`, with at most
+/// one space in between. Digits after a letter (H2O, mp3) are not numbers.
+fn unit_uses(text: &str) -> Vec<UnitUse> {
+    let digit = |c: char| c.is_ascii_digit() || ('０'..='９').contains(&c);
+    char_runs(text, |c| digit(c) || ".,．".contains(c))
+        .into_iter()
+        .filter_map(|run| {
+            // Separators around the number are punctuation
+            let number = text[run.clone()].trim_matches(|c: char| !digit(c));
+            let start = run.start + text[run.clone()].find(number)?;
+            let number = start..start + number.len();
+            let before = text[..number.start].chars().next_back();
+            if before.is_some_and(|c| c.is_ascii_alphabetic() || c == '_') {
+                return None;
+            }
+            let rest = &text[number.end..];
+            let space = rest.starts_with([' ', '\u{3000}']);
+            let start = number.end + rest.chars().next().filter(|_| space).map_or(0, char::len_utf8);
+            let rest = &text[start..];
+            let (kind, spelling, unit) = UNIT_SPELLINGS
+                .iter()
+                .enumerate()
+                .flat_map(|(kind, spellings)| spellings.iter().enumerate().map(move |(i, unit)| (kind, i, *unit)))
+                .filter(|(_, _, unit)| rest.starts_with(unit) && ends_word(unit, &rest[unit.len()..]))
+                .max_by_key(|(_, _, unit)| unit.len())?;
+            Some(UnitUse {
+                number,
+                unit: start..start + unit.len(),
+                kind,
+                spelling,
+            })
+        })
+        .collect()
+}
+
+/// Whether a unit is not the start of a longer word (10GBit, 10メートル法)
+fn ends_word(unit: &str, after: &str) -> bool {
+    let (Some(last), Some(next)) = (unit.chars().next_back(), after.chars().next()) else {
+        return true;
+    };
+    let kind = CharKind::of(last);
+    !matches!(kind, CharKind::Alphanumeric | CharKind::Katakana) || CharKind::of(next) != kind
+}
+
+/// The key used most often, the earliest one on a tie
+fn most_used<T, K: PartialEq + Copy>(items: &[T], key: impl Fn(&T) -> K) -> Option<K> {
+    let mut counts: Vec<(K, usize)> = Vec::new();
+    for item in items {
+        let key = key(item);
+        match counts.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((key, 1)),
+        }
+    }
+    // max_by_key returns the last of equal maxima
+    counts
+        .into_iter()
+        .rev()
+        .max_by_key(|(_, count)| *count)
+        .map(|(key, _)| key)
+}
+
+/// Half-width commas and periods after Japanese text (今日は,晴れ.), which
+/// should be 、 and 。, or ， and ． in documents using those more. Numbers
+/// (1,000, 3.14), file names (設定.json), ellipses, URLs and code in
+/// backquotes are skipped.
+pub(crate) fn hankaku_punctuation(ctx: &TextContext, _config: &CheckerConfig) -> Vec<Diagnostic> {
+    let text = ctx.text;
+    let prefer = |western: char, japanese: char| {
+        let western_used = text.matches(western).count() > text.matches(japanese).count();
+        if western_used {
+            western
+        } else {
+            japanese
+        }
+    };
+    let (comma, period) = (prefer('，', '、'), prefer('．', '。'));
+    let skipped = code_and_urls(text);
+
+    text.char_indices()
+        .filter(|(_, c)| matches!(c, ',' | '.'))
+        .filter_map(|(i, c)| {
+            let before = text[..i].chars().next_back()?;
+            let after = text[i + 1..].chars().next();
+            if !is_japanese(before) || after.is_some_and(|c| c.is_ascii_alphanumeric() || c == '.') {
+                return None;
+            }
+            if skipped.iter().any(|range| range.contains(&i)) {
+                return None;
+            }
+            let fixed = if c == ',' { comma } else { period };
+            let message = format!(
+                "和文中に半角の「{}」が使われています。「{}」を使ってください。",
+                c, fixed
+            );
+            let mut diagnostic = ctx.diagnostic("hankaku-punctuation", i..i + 1, message);
+            diagnostic.data = fix_data(vec![Fix::new(diagnostic.range, fixed.to_string())]);
+            Some(diagnostic)
+        })
+        .collect()
+}
+
+/// Whether punctuation after a character belongs to Japanese text
+fn is_japanese(c: char) -> bool {
+    matches!(
+        CharKind::of(c),
+        CharKind::Hiragana | CharKind::Katakana | CharKind::Kanji
+    ) || "」』）】".contains(c)
+}
+
+/// Byte ranges of code in backquotes and of URLs. A URL ends at a space,
+/// hiragana or Japanese punctuation, so it may contain kanji and katakana.
+fn code_and_urls(text: &str) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        let quotes: Vec<usize> = line.match_indices('`').map(|(i, _)| offset + i).collect();
+        ranges.extend(quotes.chunks_exact(2).map(|pair| pair[0]..pair[1]));
+        offset += line.len();
+    }
+
+    let url_end =
+        |c: char| c.is_whitespace() || CharKind::of(c) == CharKind::Hiragana || "、。「」『』（）<>\"'".contains(c);
+    for (start, _) in text.match_indices("http") {
+        let url = &text[start..];
+        if url.starts_with("http://") || url.starts_with("https://") {
+            ranges.push(start..start + url.find(url_end).unwrap_or(url.len()));
+        }
+    }
+    ranges
+}
+
+/// Exclamation and question marks after Japanese text: half-width and
+/// full-width marks mixed in the document, repeated marks (すごい！！！) and
+/// full-width marks followed by a sentence without a full-width space. With
+/// `no_exclamation`, any exclamation mark is reported instead.
+pub(crate) fn exclamation_question_mark(ctx: &TextContext, config: &CheckerConfig) -> Vec<Diagnostic> {
+    let text = ctx.text;
+    let runs: Vec<Range<usize>> = char_runs(text, |c| "！？!?".contains(c))
+        .into_iter()
+        .filter(|run| text[..run.start].chars().next_back().is_some_and(is_japanese))
+        // Images in Markdown: 図![alt](path)
+        .filter(|run| !text[run.end..].starts_with('['))
+        .collect();
+    let all_marks = || runs.iter().flat_map(|run| text[run.clone()].chars());
+    // Full-width marks unless half-width ones are used more
+    let hankaku = all_marks().filter(char::is_ascii).count() * 2 > all_marks().count();
+
+    let mut diagnostics = Vec::new();
+    for run in runs {
+        let written = &text[run.clone()];
+        if config.no_exclamation && written.contains(['！', '!']) {
+            let message = "フォーマルな文書では感嘆符を使いません。".to_string();
+            let mut diagnostic = ctx.diagnostic("exclamation-question-mark", run, message);
+            diagnostic.severity = Some(DiagnosticSeverity::WARNING);
+            diagnostics.push(diagnostic);
+            continue;
+        }
+
+        let mut marks = String::new();
+        for c in written.chars().map(|c| mark_width(c, hankaku)) {
+            if !marks.ends_with(c) {
+                marks.push(c);
+            }
+        }
+        let after = &text[run.end..];
+        let spaced = !hankaku && needs_space(after);
+        let message = if marks.chars().count() < written.chars().count() {
+            format!("感嘆符・疑問符を重ねて使っています。「{}」→「{}」", written, marks)
+        } else if marks != written {
+            format!(
+                "感嘆符・疑問符の全角と半角が混在しています。「{}」→「{}」",
+                written, marks
+            )
+        } else if spaced {
+            format!("文が続く「{}」の後には全角スペースを入れます。", written)
+        } else {
+            continue;
+        };
+
+        let mut range = run;
+        if spaced {
+            if after.starts_with(' ') {
+                range.end += 1;
+            }
+            marks.push('\u{3000}');
+        }
+        let mut diagnostic = ctx.diagnostic("exclamation-question-mark", range, message);
+        diagnostic.data = fix_data(vec![Fix::new(diagnostic.range, marks)]);
+        diagnostics.push(diagnostic);
+    }
+    diagnostics
+}
+
+/// An exclamation or question mark in half or full width
+fn mark_width(c: char, hankaku: bool) -> char {
+    match (c, hankaku) {
+        ('！', true) => '!',
+        ('？', true) => '?',
+        ('!', false) => '！',
+        ('?', false) => '？',
+        _ => c,
+    }
+}
+
+/// Whether the text after a full-width mark goes on with a sentence not
+/// separated by a full-width space. Marks at the end of a quotation, of a
+/// line or before a quoting と/って (本当？と聞く) need none.
+fn needs_space(after: &str) -> bool {
+    match after.chars().next() {
+        Some(' ') => true,
+        Some(c) => !c.is_whitespace() && !"」』）)】。、と".contains(c) && !after.starts_with("って"),
+        None => false,
+    }
+}
+
+/// Parentheses in the width against `preferred_bracket`: by default
+/// full-width in Japanese text and half-width in English, where a pair is
+/// Japanese when it encloses or follows Japanese text. Both parentheses of
+/// a pair are checked, so that （図1) is reported at the closing one.
+/// Markdown links, URLs and code in backquotes are skipped.
+pub(crate) fn bracket_width(ctx: &TextContext, config: &CheckerConfig) -> Vec<Diagnostic> {
+    let text = ctx.text;
+    let skipped = code_and_urls(text);
+    let mut opening = Vec::new();
+    let mut pairs = Vec::new();
+    let mut previous = None;
+    for (i, c) in text.char_indices() {
+        match c {
+            '(' | '（' => opening.push(i),
+            ')' | '）' => pairs.extend(opening.pop().map(|start| (start, i))),
+            // Parentheses do not span paragraphs
+            '\n' if previous == Some('\n') => opening.clear(),
+            // CRLF line breaks count like LF ones
+            '\r' => continue,
+            _ => {}
+        }
+        previous = Some(c);
+    }
+
+    let mut diagnostics = Vec::new();
+    for (start, end) in pairs {
+        let in_skipped = skipped
+            .iter()
+            .any(|range| range.contains(&start) || range.contains(&end));
+        if in_skipped || text[..start].ends_with(']') {
+            continue;
+        }
+        let zenkaku = match config.preferred_bracket {
+            BracketWidth::Zenkaku => true,
+            BracketWidth::Hankaku => false,
+            BracketWidth::Auto => {
+                text[start..end].chars().skip(1).any(is_japanese)
+                    || text[..start].chars().next_back().is_some_and(is_japanese)
+            }
+        };
+        let message = match (config.preferred_bracket, zenkaku) {
+            (BracketWidth::Auto, true) => "和文中の括弧は全角で書きます。",
+            (BracketWidth::Auto, false) => "英文中の括弧は半角で書きます。",
+            (_, true) => "括弧は全角で書きます。",
+            (_, false) => "括弧は半角で書きます。",
+        };
+        for i in [start, end] {
+            let Some(bracket) = text[i..].chars().next() else {
+                continue;
+            };
+            let fixed = bracket_in_width(bracket, zenkaku);
+            if fixed == bracket {
+                continue;
+            }
+            let message = format!("{}「{}」→「{}」", message, bracket, fixed);
+            let mut diagnostic = ctx.diagnostic("bracket-width", i..i + bracket.len_utf8(), message);
+            diagnostic.data = fix_data(vec![Fix::new(diagnostic.range, fixed.to_string())]);
+            diagnostics.push(diagnostic);
+        }
+    }
+    diagnostics.sort_by_key(|diagnostic| diagnostic.range.start);
+    diagnostics
+}
+
+/// A parenthesis in full or half width
+fn bracket_in_width(c: char, zenkaku: bool) -> char {
+    match (c, zenkaku) {
+        ('(', true) => '（',
+        (')', true) => '）',
+        ('（', false) => '(',
+        ('）', false) => ')',
+        _ => c,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checker::diagnostic_fixes;
+    use crate::textlint::TextRule;
+    use crate::utf16::LineIndex;
+
+    fn fixes(text: &str) -> Vec<String> {
+        fixes_of(number_grouping, &CheckerConfig::default(), text)
+    }
+
+    fn fixes_of(rule: TextRule, config: &CheckerConfig, text: &str) -> Vec<String> {
+        let index = LineIndex::new(text);
+        let ctx = TextContext::new(text, &[], &index);
+        rule(&ctx, config)
+            .iter()
+            .map(|diagnostic| diagnostic_fixes(diagnostic)[0].new_text.clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_group_number() {
+        assert_eq!(group_number(0).as_deref(), Some("0"));
+        assert_eq!(group_number(2000).as_deref(), Some("2000"));
+        assert_eq!(group_number(12_000).as_deref(), Some("1万2000"));
+        assert_eq!(group_number(120_000).as_deref(), Some("12万"));
+        assert_eq!(group_number(300_000_050).as_deref(), Some("3億50"));
+    }
+
+    #[test]
+    fn test_number_grouping() {
+        assert_eq!(fixes("予算は12,000円です。"), ["1万2000"]);
+        assert_eq!(fixes("参加者は120000人、定員は2,000人。"), ["12万", "2000"]);
+        // Already grouped, or not a count
+        assert!(fixes("2000人が1万2000円を払った。").is_empty());
+        assert!(fixes("v1,000版、3.14倍、1,00円、12,000万円、12,000 items").is_empty());
+    }
+
+    fn unit_fixes(text: &str, unit_spacing: UnitSpacing) -> Vec<String> {
+        let config = CheckerConfig {
+            unit_spacing,
+            ..CheckerConfig::default()
+        };
+        fixes_of(unit_notation, &config, text)
+    }
+
+    #[test]
+    fn test_unit_notation() {
+        let consistent = UnitSpacing::Consistent;
+        assert_eq!(
+            unit_fixes("容量は10GB、予備は5ＧＢ、計15ギガバイト。", consistent),
+            ["GB", "GB"]
+        );
+        assert_eq!(unit_fixes("成長率は5%、前年は3％、目標は10％。", consistent), ["％"]);
+        // Not units, or one of a kind
+        assert!(unit_fixes("H2Oと10GBitの回線、5ＧＢの容量。", consistent).is_empty());
+        assert_eq!(unit_fixes("10ミリ秒と10msは同じ。", consistent), ["ミリ秒"]);
+    }
+
+    #[test]
+    fn test_unit_spacing() {
+        assert_eq!(unit_fixes("3.5 GBと2GBと1GB。", UnitSpacing::Consistent), ["GB"]);
+        assert_eq!(unit_fixes("10GBと20 GB、50%。", UnitSpacing::Space), [" GB"]);
+        assert_eq!(unit_fixes("10 GBと20 kg、50 %。", UnitSpacing::None), ["GB", "kg"]);
+
+        let text = "3.5 GBと2GBと1GB。";
+        let index = LineIndex::new(text);
+        let ctx = TextContext::new(text, &[], &index);
+        let diagnostics = unit_notation(&ctx, &CheckerConfig::default());
+        assert!(diagnostics[0].message.ends_with("「3.5 GB」→「3.5GB」"));
+    }
+
+    #[test]
+    fn test_hankaku_punctuation() {
+        let config = CheckerConfig::default();
+        let fixes = |text| fixes_of(hankaku_punctuation, &config, text);
+        assert_eq!(fixes("今日は晴れ,明日は「雨」."), ["、", "。"]);
+        // The document uses ， and ．
+        assert_eq!(fixes("今日は，晴れ．明日は雨."), ["．"]);
+        // Numbers, file names, ellipses, URLs and code
+        assert!(fixes("1,000円の3.14倍。設定.jsonを見て、えっと...").is_empty());
+        assert!(fixes("https://ja.wikipedia.org/wiki/東京,大阪 と `値.設定` を参照。").is_empty());
+    }
+
+    #[test]
+    fn test_exclamation_question_mark() {
+        let config = CheckerConfig::default();
+        let fixes = |text| fixes_of(exclamation_question_mark, &config, text);
+        assert_eq!(fixes("すごい！！！本当に？"), ["！\u{3000}"]);
+        assert_eq!(fixes("本当ですか？\u{3000}はい!\u{3000}いいえ？"), ["！"]);
+        assert_eq!(fixes("本当？ はい。"), ["？\u{3000}"]);
+        assert!(fixes("本当？と聞いた。「何？」\n次へ！").is_empty());
+        // Half-width marks used throughout, or not in Japanese text
+        assert!(fixes("完了!次へ?").is_empty());
+        assert!(fixes("Hello!! 図![alt](a.png)").is_empty());
+    }
+
+    #[test]
+    fn test_no_exclamation() {
+        let config = CheckerConfig {
+            no_exclamation: true,
+            ..CheckerConfig::default()
+        };
+        let text = "完了！！次へ？\u{3000}はい。";
+        let index = LineIndex::new(text);
+        let ctx = TextContext::new(text, &[], &index);
+        let diagnostics = exclamation_question_mark(&ctx, &config);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Some(DiagnosticSeverity::WARNING));
+    }
+
+    fn bracket_fixes(text: &str, preferred_bracket: BracketWidth) -> Vec<String> {
+        let config = CheckerConfig {
+            preferred_bracket,
+            ..CheckerConfig::default()
+        };
+        fixes_of(bracket_width, &config, text)
+    }
+
+    #[test]
+    fn test_bracket_width() {
+        let auto = BracketWidth::Auto;
+        assert_eq!(bracket_fixes("MoZuku(モズク)は関数foo(x)を使う。", auto), ["（", "）"]);
+        assert_eq!(
+            bracket_fixes("結果（図1)を見る。See （Figure 1）.", auto),
+            ["）", "(", ")"]
+        );
+        assert!(bracket_fixes("第3版（2024）、[資料](https://example.com/a(b))、`f（x）`", auto).is_empty());
+        assert_eq!(bracket_fixes("関数（引数）", BracketWidth::Hankaku), ["(", ")"]);
+        assert_eq!(bracket_fixes("API (Application)", BracketWidth::Zenkaku), ["（", "）"]);
+
+        // Parentheses are not paired across paragraphs, whatever the line breaks
+        assert!(bracket_fixes("注（1\n\nSee Figure 1)", auto).is_empty());
+        assert!(bracket_fixes("注（1\r\n\r\nSee Figure 1)", auto).is_empty());
+    }
+}
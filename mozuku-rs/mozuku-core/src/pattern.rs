@@ -0,0 +1,390 @@
+//! Declarative token pattern rules
+//!
+//! A pattern rule reports sequences of tokens matching a pattern such as
+//! `品詞=名詞 → 表層=の → 品詞=名詞`, so that projects can add their own rules
+//! in `mozuku.toml` (`[[checker.patterns]]`) or in TOML/YAML rule files
+//! (`[checker] pattern_files`) without writing Rust.
+//!
+//! Pattern syntax:
+//! - tokens are separated by `→` (or `->`), `*` matches any token
+//! - each token has conditions separated by `,`, all of which must hold
+//! - a condition is `key=value` or `key!=value`; alternatives are separated by
+//!   `|` and a trailing `*` matches a prefix (`活用形=連用*`)
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use anyhow::{anyhow, bail, Context, Result};
+use lsp_types::DiagnosticSeverity;
+use serde::{Deserialize, Serialize};
+
+use crate::analyzer::TokenInfo;
+
+/// A rule reporting token sequences that match a pattern
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PatternRule {
+    /// Rule ID, used as the diagnostic code
+    pub id: String,
+    /// Message; `$0` is replaced by the matched text and `$1`, `$2`, ... by
+    /// the surfaces of the matched tokens
+    pub message: String,
+    /// Tokens to match
+    pub pattern: TokenPattern,
+    #[serde(default)]
+    pub severity: PatternSeverity,
+    /// Replacement offered as a fix, with the same placeholders as the message
+    #[serde(default)]
+    pub replacement: Option<String>,
+}
+
+/// Severity of the diagnostics of a pattern rule
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PatternSeverity {
+    Error,
+    #[default]
+    Warning,
+    Information,
+    Hint,
+}
+
+impl From<PatternSeverity> for DiagnosticSeverity {
+    fn from(severity: PatternSeverity) -> Self {
+        match severity {
+            PatternSeverity::Error => DiagnosticSeverity::ERROR,
+            PatternSeverity::Warning => DiagnosticSeverity::WARNING,
+            PatternSeverity::Information => DiagnosticSeverity::INFORMATION,
+            PatternSeverity::Hint => DiagnosticSeverity::HINT,
+        }
+    }
+}
+
+impl PatternRule {
+    /// The tokens matching the pattern from index `i`, if any
+    pub fn match_at<'t>(&self, tokens: &'t [TokenInfo], i: usize) -> Option<&'t [TokenInfo]> {
+        let matched = tokens.get(i..i + self.pattern.tokens.len())?;
+        matched
+            .iter()
+            .zip(&self.pattern.tokens)
+            .all(|(token, matcher)| matcher.matches(token))
+            .then_some(matched)
+    }
+
+    /// The message for matched tokens
+    pub fn message(&self, matched: &[TokenInfo]) -> String {
+        expand(&self.message, matched)
+    }
+
+    /// The replacement text for matched tokens
+    pub fn replacement(&self, matched: &[TokenInfo]) -> Option<String> {
+        self.replacement.as_deref().map(|template| expand(template, matched))
+    }
+}
+
+/// Replace `$0` by the matched text and `$N` by the surface of the Nth token
+fn expand(template: &str, matched: &[TokenInfo]) -> String {
+    let mut result = String::new();
+    let mut rest = template;
+    while let Some(dollar) = rest.find('$') {
+        result.push_str(&rest[..dollar]);
+        let after = &rest[dollar + 1..];
+        let digits = after.find(|c: char| !c.is_ascii_digit()).unwrap_or(after.len());
+        match after[..digits].parse::<usize>() {
+            Ok(0) => matched.iter().for_each(|token| result.push_str(&token.surface)),
+            Ok(n) if n <= matched.len() => result.push_str(&matched[n - 1].surface),
+            _ => result.push_str(&rest[dollar..dollar + 1 + digits]),
+        }
+        rest = &after[digits..];
+    }
+    result.push_str(rest);
+    result
+}
+
+/// A sequence of token matchers, written as `品詞=名詞 → 表層=の → 品詞=名詞`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct TokenPattern {
+    tokens: Vec<TokenMatcher>,
+    source: String,
+}
+
+impl TryFrom<String> for TokenPattern {
+    type Error = String;
+
+    fn try_from(source: String) -> Result<Self, String> {
+        let tokens = source
+            .split('→')
+            .flat_map(|part| part.split("->"))
+            .map(TokenMatcher::parse)
+            .collect::<Result<Vec<_>>>()
+            .map_err(|e| format!("パターン {:?} が不正です: {:#}", source, e))?;
+        Ok(Self { tokens, source })
+    }
+}
+
+impl From<TokenPattern> for String {
+    fn from(pattern: TokenPattern) -> Self {
+        pattern.source
+    }
+}
+
+impl fmt::Display for TokenPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+/// Conditions on a single token; empty for `*`
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct TokenMatcher {
+    conditions: Vec<Condition>,
+}
+
+impl TokenMatcher {
+    fn parse(spec: &str) -> Result<Self> {
+        let spec = spec.trim();
+        if spec.is_empty() {
+            bail!("空のトークン指定があります");
+        }
+        if spec == "*" {
+            return Ok(Self { conditions: Vec::new() });
+        }
+
+        let conditions = spec.split(',').map(Condition::parse).collect::<Result<_>>()?;
+        Ok(Self { conditions })
+    }
+
+    fn matches(&self, token: &TokenInfo) -> bool {
+        self.conditions.iter().all(|condition| condition.matches(token))
+    }
+}
+
+/// `key=value` or `key!=value`, with `|`-separated alternatives
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Condition {
+    field: Field,
+    negated: bool,
+    values: Vec<String>,
+}
+
+impl Condition {
+    fn parse(spec: &str) -> Result<Self> {
+        let (key, values) = spec
+            .split_once('=')
+            .ok_or_else(|| anyhow!("条件 {:?} に `=` がありません", spec.trim()))?;
+        let (key, negated) = match key.strip_suffix('!') {
+            Some(key) => (key, true),
+            None => (key, false),
+        };
+        let field = Field::parse(key.trim())?;
+        let values = values.split('|').map(|value| value.trim().to_string()).collect();
+        Ok(Self { field, negated, values })
+    }
+
+    fn matches(&self, token: &TokenInfo) -> bool {
+        let actual = self.field.value(token);
+        let found = self.values.iter().any(|value| match value.strip_suffix('*') {
+            Some(prefix) => actual.starts_with(prefix),
+            None => actual == value,
+        });
+        found != self.negated
+    }
+}
+
+/// Token attribute compared by a condition
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Surface,
+    Pos,
+    PosDetail1,
+    PosDetail2,
+    PosDetail3,
+    ConjugationType,
+    ConjugationForm,
+    BaseForm,
+    Reading,
+    Pronunciation,
+}
+
+impl Field {
+    fn parse(key: &str) -> Result<Self> {
+        Ok(match key {
+            "表層" | "表層形" | "surface" => Self::Surface,
+            "品詞" | "pos" => Self::Pos,
+            "品詞細分類1" | "pos_detail1" => Self::PosDetail1,
+            "品詞細分類2" | "pos_detail2" => Self::PosDetail2,
+            "品詞細分類3" | "pos_detail3" => Self::PosDetail3,
+            "活用型" | "conjugation_type" => Self::ConjugationType,
+            "活用形" | "conjugation_form" => Self::ConjugationForm,
+            "基本形" | "原形" | "base_form" => Self::BaseForm,
+            "読み" | "reading" => Self::Reading,
+            "発音" | "pronunciation" => Self::Pronunciation,
+            _ => bail!("不明なキー {:?}", key),
+        })
+    }
+
+    fn value(self, token: &TokenInfo) -> &str {
+        match self {
+            Self::Surface => &token.surface,
+            Self::Pos => &token.pos,
+            Self::PosDetail1 => &token.pos_detail1,
+            Self::PosDetail2 => &token.pos_detail2,
+            Self::PosDetail3 => &token.pos_detail3,
+            Self::ConjugationType => &token.conjugation_type,
+            Self::ConjugationForm => &token.conjugation_form,
+            Self::BaseForm => &token.base_form,
+            Self::Reading => &token.reading,
+            Self::Pronunciation => &token.pronunciation,
+        }
+    }
+}
+
+/// Content of a pattern rule file
+#[derive(Debug, Deserialize)]
+struct PatternFile {
+    #[serde(default)]
+    patterns: Vec<PatternRule>,
+}
+
+/// Load the pattern rules of a file: YAML for `.yml`/`.yaml`, TOML otherwise
+pub fn load_file(path: &Path) -> Result<Vec<PatternRule>> {
+    let content = std::fs::read_to_string(path).with_context(|| format!("{} を読み込めません", path.display()))?;
+    let is_yaml = matches!(path.extension().and_then(|ext| ext.to_str()), Some("yml" | "yaml"));
+    let file: PatternFile = if is_yaml {
+        serde_yaml::from_str(&content).with_context(|| format!("{} の解析に失敗しました", path.display()))?
+    } else {
+        toml::from_str(&content).with_context(|| format!("{} の解析に失敗しました", path.display()))?
+    };
+    Ok(file.patterns)
+}
+
+/// Pattern rule files loaded on first use and reloaded when they change
+#[derive(Default)]
+pub struct PatternFiles {
+    files: Mutex<HashMap<PathBuf, CachedFile>>,
+}
+
+struct CachedFile {
+    modified: Option<SystemTime>,
+    rules: Arc<Vec<PatternRule>>,
+}
+
+impl PatternFiles {
+    /// The rules of the given files. A file that cannot be loaded is logged
+    /// once per modification and contributes no rules.
+    pub fn rules(&self, paths: &[PathBuf]) -> Vec<Arc<Vec<PatternRule>>> {
+        let mut files = self.files.lock().unwrap();
+        paths
+            .iter()
+            .map(|path| {
+                let modified = std::fs::metadata(path).and_then(|meta| meta.modified()).ok();
+                if let Some(cached) = files.get(path) {
+                    if cached.modified == modified {
+                        return cached.rules.clone();
+                    }
+                }
+
+                let rules = Arc::new(load_file(path).unwrap_or_else(|e| {
+                    tracing::warn!("Failed to load pattern rules: {:#}", e);
+                    Vec::new()
+                }));
+                files.insert(
+                    path.clone(),
+                    CachedFile {
+                        modified,
+                        rules: rules.clone(),
+                    },
+                );
+                rules
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token(surface: &str, pos: &str, conjugation_form: &str) -> TokenInfo {
+        TokenInfo {
+            surface: surface.to_string(),
+            pos: pos.to_string(),
+            pos_detail1: "*".to_string(),
+            pos_detail2: "*".to_string(),
+            pos_detail3: "*".to_string(),
+            conjugation_type: "*".to_string(),
+            conjugation_form: conjugation_form.to_string(),
+            base_form: surface.to_string(),
+            reading: String::new(),
+            pronunciation: String::new(),
+            byte_offset: 0,
+            char_offset: 0,
+            char_length: surface.chars().count(),
+        }
+    }
+
+    fn rule(pattern: &str) -> PatternRule {
+        toml::from_str(&format!(
+            "id = \"test\"\nmessage = \"「$0」→「$1$3」\"\npattern = {:?}\nreplacement = \"$1$3\"",
+            pattern
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_match_at() {
+        let tokens = vec![
+            token("会社", "名詞", "*"),
+            token("の", "助詞", "*"),
+            token("規定", "名詞", "*"),
+            token("を", "助詞", "*"),
+        ];
+        let rule = rule("品詞=名詞 → 表層=の → 品詞=名詞");
+
+        let matched = rule.match_at(&tokens, 0).unwrap();
+        assert_eq!(matched.len(), 3);
+        assert_eq!(rule.message(matched), "「会社の規定」→「会社規定」");
+        assert_eq!(rule.replacement(matched).as_deref(), Some("会社規定"));
+        assert!(rule.match_at(&tokens, 1).is_none());
+        assert!(rule.match_at(&tokens, 2).is_none());
+    }
+
+    #[test]
+    fn test_conditions() {
+        let tokens = vec![token("行き", "動詞", "連用形"), token("ます", "助動詞", "基本形")];
+
+        assert!(rule("活用形=連用* -> *").match_at(&tokens, 0).is_some());
+        assert!(rule("品詞=動詞,表層!=行き → *").match_at(&tokens, 0).is_none());
+        assert!(rule("品詞=名詞|動詞 → 品詞!=助詞").match_at(&tokens, 0).is_some());
+    }
+
+    #[test]
+    fn test_invalid_patterns() {
+        for pattern in ["品詞=名詞 → ", "品詞", "色=赤"] {
+            let result: Result<PatternRule, _> =
+                toml::from_str(&format!("id = \"test\"\nmessage = \"m\"\npattern = {:?}", pattern));
+            assert!(result.is_err(), "{}", pattern);
+        }
+    }
+
+    #[test]
+    fn test_load_yaml_file() {
+        let path = std::env::temp_dir().join(format!("mozuku-patterns-{}.yml", std::process::id()));
+        std::fs::write(
+            &path,
+            "patterns:\n  - id: no-kudasai\n    message: 「下さい」はひらがなで書く\n    severity: hint\n    pattern: 表層=下さい\n    replacement: ください\n",
+        )
+        .unwrap();
+
+        let rules = load_file(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        let rules = rules.unwrap();
+        assert_eq!(rules.len(), 1);
+        assert_eq!(rules[0].severity, PatternSeverity::Hint);
+        assert_eq!(rules[0].pattern.to_string(), "表層=下さい");
+    }
+}
@@ -0,0 +1,384 @@
+//! WASM plugins providing custom rules
+//!
+//! A plugin is a WebAssembly module listed in `[checker] plugins`. For each
+//! checked text the host serializes the text and its tokens to JSON, passes
+//! them to the plugin and converts the returned diagnostics (byte ranges,
+//! messages and fix candidates) to LSP diagnostics. The ABI is described in
+//! docs/plugins.md.
+//!
+//! Every check runs in a fresh instance with limited fuel and memory, so a
+//! faulty plugin can neither keep state between texts nor hang the server.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::SystemTime;
+
+use anyhow::{anyhow, bail, Context, Result};
+use lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString, Range};
+use serde::{Deserialize, Serialize};
+use wasmtime::{Caller, Engine, Linker, Module, Store, StoreLimits, StoreLimitsBuilder};
+
+use crate::analyzer::TokenInfo;
+use crate::checker::{fix_data, Fix};
+use crate::utf16::LineIndex;
+
+/// Instructions a plugin may execute for one text
+const PLUGIN_FUEL: u64 = 1_000_000_000;
+
+/// Maximum linear memory of a plugin instance, in bytes
+const PLUGIN_MEMORY_LIMIT: usize = 64 * 1024 * 1024;
+
+/// Module namespace of the functions imported by plugins
+const HOST_MODULE: &str = "mozuku";
+
+/// Input passed to `mozuku_check`
+#[derive(Debug, Serialize)]
+struct PluginInput<'a> {
+    text: &'a str,
+    tokens: Vec<PluginToken<'a>>,
+}
+
+/// A token as seen by plugins; offsets are bytes in the text
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PluginToken<'a> {
+    surface: &'a str,
+    pos: &'a str,
+    pos_detail1: &'a str,
+    pos_detail2: &'a str,
+    pos_detail3: &'a str,
+    conjugation_type: &'a str,
+    conjugation_form: &'a str,
+    base_form: &'a str,
+    reading: &'a str,
+    pronunciation: &'a str,
+    start: usize,
+    end: usize,
+}
+
+impl<'a> From<&'a TokenInfo> for PluginToken<'a> {
+    fn from(token: &'a TokenInfo) -> Self {
+        Self {
+            surface: &token.surface,
+            pos: &token.pos,
+            pos_detail1: &token.pos_detail1,
+            pos_detail2: &token.pos_detail2,
+            pos_detail3: &token.pos_detail3,
+            conjugation_type: &token.conjugation_type,
+            conjugation_form: &token.conjugation_form,
+            base_form: &token.base_form,
+            reading: &token.reading,
+            pronunciation: &token.pronunciation,
+            start: token.byte_offset,
+            end: token.byte_offset + token.surface.len(),
+        }
+    }
+}
+
+/// Output returned by `mozuku_check`
+#[derive(Debug, Default, Deserialize)]
+struct PluginOutput {
+    #[serde(default)]
+    diagnostics: Vec<PluginDiagnostic>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PluginDiagnostic {
+    start: usize,
+    end: usize,
+    message: String,
+    /// Diagnostic code; defaults to the file name of the plugin
+    #[serde(default)]
+    code: Option<String>,
+    /// "error", "warning", "information" or "hint"; defaults to "warning"
+    #[serde(default)]
+    severity: Option<String>,
+    #[serde(default)]
+    fixes: Vec<PluginFix>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PluginFix {
+    start: usize,
+    end: usize,
+    new_text: String,
+}
+
+/// State of a plugin instance
+struct PluginState {
+    name: String,
+    limits: StoreLimits,
+}
+
+/// A compiled plugin and the modification time of its file
+struct CachedModule {
+    modified: Option<SystemTime>,
+    module: Module,
+}
+
+/// Loads plugins and runs them on checked texts.
+///
+/// Modules are compiled on first use and recompiled when their file changes.
+#[derive(Default)]
+pub struct PluginHost {
+    engine: OnceLock<Engine>,
+    modules: Mutex<HashMap<PathBuf, CachedModule>>,
+}
+
+impl PluginHost {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run a plugin on a text and its tokens
+    pub fn check(&self, path: &Path, text: &str, tokens: &[TokenInfo], index: &LineIndex) -> Result<Vec<Diagnostic>> {
+        let name = plugin_name(path);
+        let module = self.module(path)?;
+        let input = serde_json::to_vec(&PluginInput {
+            text,
+            tokens: tokens.iter().map(PluginToken::from).collect(),
+        })?;
+
+        let output = self
+            .run(&module, &name, &input)
+            .with_context(|| format!("プラグイン {} の実行に失敗しました", path.display()))?;
+        Ok(to_diagnostics(output, &name, text, index))
+    }
+
+    fn engine(&self) -> &Engine {
+        self.engine.get_or_init(|| {
+            let mut config = wasmtime::Config::new();
+            config.consume_fuel(true);
+            Engine::new(&config).expect("valid wasmtime configuration")
+        })
+    }
+
+    /// The compiled module of a plugin file
+    fn module(&self, path: &Path) -> Result<Module> {
+        let modified = std::fs::metadata(path)
+            .with_context(|| format!("プラグイン {} が見つかりません", path.display()))?
+            .modified()
+            .ok();
+
+        let mut modules = self.modules.lock().unwrap();
+        if let Some(cached) = modules.get(path) {
+            if cached.modified == modified {
+                return Ok(cached.module.clone());
+            }
+        }
+
+        tracing::info!("Compiling plugin {}", path.display());
+        let module = Module::from_file(self.engine(), path)
+            .with_context(|| format!("プラグイン {} を読み込めません", path.display()))?;
+        modules.insert(
+            path.to_path_buf(),
+            CachedModule {
+                modified,
+                module: module.clone(),
+            },
+        );
+        Ok(module)
+    }
+
+    /// Instantiate a module and call `mozuku_check` with the JSON input
+    fn run(&self, module: &Module, name: &str, input: &[u8]) -> Result<PluginOutput> {
+        let engine = self.engine();
+        let state = PluginState {
+            name: name.to_string(),
+            limits: StoreLimitsBuilder::new().memory_size(PLUGIN_MEMORY_LIMIT).build(),
+        };
+        let mut store = Store::new(engine, state);
+        store.limiter(|state| &mut state.limits);
+        store.set_fuel(PLUGIN_FUEL)?;
+
+        let mut linker = Linker::new(engine);
+        linker.func_wrap(HOST_MODULE, "log", log)?;
+        let instance = linker.instantiate(&mut store, module)?;
+
+        let memory = instance
+            .get_memory(&mut store, "memory")
+            .ok_or_else(|| anyhow!("`memory` がエクスポートされていません"))?;
+        let alloc = instance.get_typed_func::<i32, i32>(&mut store, "mozuku_alloc")?;
+        let check = instance.get_typed_func::<(i32, i32), i64>(&mut store, "mozuku_check")?;
+
+        let len = i32::try_from(input.len()).context("入力が大きすぎます")?;
+        let ptr = alloc.call(&mut store, len)?;
+        memory.write(&mut store, ptr as u32 as usize, input)?;
+
+        let packed = check.call(&mut store, (ptr, len))? as u64;
+        let (out_ptr, out_len) = ((packed >> 32) as usize, (packed & 0xffff_ffff) as usize);
+        // The length comes from the plugin: check it before reading
+        let out_end = out_ptr
+            .checked_add(out_len)
+            .filter(|&end| end <= memory.data_size(&store))
+            .ok_or_else(|| anyhow!("プラグインの出力（{} バイト）がメモリの範囲外です", out_len))?;
+        let output = &memory.data(&store)[out_ptr..out_end];
+
+        serde_json::from_slice(output).context("プラグインの出力が不正な JSON です")
+    }
+}
+
+/// `mozuku.log(ptr, len)`: write a UTF-8 message of the plugin to the log
+fn log(mut caller: Caller<'_, PluginState>, ptr: i32, len: i32) {
+    let Some(memory) = caller.get_export("memory").and_then(|export| export.into_memory()) else {
+        return;
+    };
+    let start = ptr as u32 as usize;
+    let end = start.saturating_add(len as u32 as usize);
+    if let Some(bytes) = memory.data(&caller).get(start..end) {
+        tracing::debug!("Plugin {}: {}", caller.data().name, String::from_utf8_lossy(bytes));
+    }
+}
+
+/// Name of a plugin: the file name without extension
+fn plugin_name(path: &Path) -> String {
+    path.file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "plugin".to_string())
+}
+
+/// Convert the diagnostics of a plugin, dropping those with invalid ranges
+fn to_diagnostics(output: PluginOutput, name: &str, text: &str, index: &LineIndex) -> Vec<Diagnostic> {
+    let valid = |start: usize, end: usize| {
+        start <= end && end <= text.len() && text.is_char_boundary(start) && text.is_char_boundary(end)
+    };
+
+    output
+        .diagnostics
+        .into_iter()
+        .filter_map(|diag| {
+            if !valid(diag.start, diag.end) {
+                tracing::warn!("Plugin {} returned an invalid range {}..{}", name, diag.start, diag.end);
+                return None;
+            }
+            let fixes: Vec<Fix> = diag
+                .fixes
+                .into_iter()
+                .filter(|fix| valid(fix.start, fix.end))
+                .map(|fix| {
+                    let range = Range::new(index.position(fix.start), index.position(fix.end));
+                    Fix::new(range, fix.new_text)
+                })
+                .collect();
+
+            Some(Diagnostic {
+                range: Range::new(index.position(diag.start), index.position(diag.end)),
+                severity: Some(parse_severity(diag.severity.as_deref())),
+                code: Some(NumberOrString::String(diag.code.unwrap_or_else(|| name.to_string()))),
+                source: Some("mozuku".to_string()),
+                message: diag.message,
+                data: if fixes.is_empty() { None } else { fix_data(fixes) },
+                ..Default::default()
+            })
+        })
+        .collect()
+}
+
+fn parse_severity(severity: Option<&str>) -> DiagnosticSeverity {
+    match severity {
+        Some("error") => DiagnosticSeverity::ERROR,
+        Some("information") => DiagnosticSeverity::INFORMATION,
+        Some("hint") => DiagnosticSeverity::HINT,
+        _ => DiagnosticSeverity::WARNING,
+    }
+}
+
+/// Check that a file is a plugin the host can run
+pub fn validate(path: &Path) -> Result<()> {
+    let host = PluginHost::new();
+    let module = host.module(path)?;
+    for (export, kind) in [("memory", "memory"), ("mozuku_alloc", "func"), ("mozuku_check", "func")] {
+        if module.get_export(export).is_none() {
+            bail!("{} は {} `{}` をエクスポートしていません", path.display(), kind, export);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A plugin returning a fixed output, whatever the input
+    fn fixed_plugin(host: &PluginHost, output: &str) -> Module {
+        let wat = format!(
+            r#"(module
+                (import "mozuku" "log" (func $log (param i32 i32)))
+                (memory (export "memory") 1)
+                (data (i32.const 0) "{output}")
+                (func (export "mozuku_alloc") (param i32) (result i32) i32.const 4096)
+                (func (export "mozuku_check") (param i32 i32) (result i64)
+                    (call $log (i32.const 0) (i32.const 1))
+                    i64.const {len}))"#,
+            output = output.replace('"', "\\\""),
+            len = output.len(),
+        );
+        Module::new(host.engine(), wat).unwrap()
+    }
+
+    #[test]
+    fn test_run_plugin() {
+        let host = PluginHost::new();
+        let module = fixed_plugin(
+            &host,
+            r#"{"diagnostics":[{"start":0,"end":6,"message":"m","fixes":[{"start":0,"end":6,"newText":"x"}]}]}"#,
+        );
+        let text = "見れる";
+        let index = LineIndex::new(text);
+
+        let output = host.run(&module, "sample", b"{}").unwrap();
+        let diagnostics = to_diagnostics(output, "sample", text, &index);
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code, Some(NumberOrString::String("sample".to_string())));
+        assert_eq!(diagnostics[0].range.end.character, 2);
+        assert_eq!(crate::checker::diagnostic_fixes(&diagnostics[0])[0].new_text, "x");
+    }
+
+    #[test]
+    fn test_invalid_ranges_dropped() {
+        let text = "見れる";
+        let index = LineIndex::new(text);
+        let output: PluginOutput = serde_json::from_str(
+            r#"{"diagnostics":[
+                {"start":0,"end":1,"message":"inside a character"},
+                {"start":0,"end":100,"message":"out of bounds"},
+                {"start":3,"end":9,"message":"ok","code":"my-rule","severity":"error"}
+            ]}"#,
+        )
+        .unwrap();
+
+        let diagnostics = to_diagnostics(output, "sample", text, &index);
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code, Some(NumberOrString::String("my-rule".to_string())));
+        assert_eq!(diagnostics[0].severity, Some(DiagnosticSeverity::ERROR));
+    }
+
+    #[test]
+    fn test_output_out_of_memory() {
+        let host = PluginHost::new();
+        // Output of 4 GiB at address 0, beyond the single 64 KiB page
+        let module = Module::new(
+            host.engine(),
+            r#"(module
+                (memory (export "memory") 1)
+                (func (export "mozuku_alloc") (param i32) (result i32) i32.const 0)
+                (func (export "mozuku_check") (param i32 i32) (result i64) i64.const 0xffffffff))"#,
+        )
+        .unwrap();
+
+        let error = host.run(&module, "huge", b"{}").unwrap_err();
+        assert!(error.to_string().contains("範囲外"));
+    }
+
+    #[test]
+    fn test_missing_exports() {
+        let host = PluginHost::new();
+        let module = Module::new(host.engine(), "(module (memory (export \"memory\") 1))").unwrap();
+
+        assert!(host.run(&module, "empty", b"{}").is_err());
+    }
+}
@@ -0,0 +1,180 @@
+//! Bundled settings presets
+//!
+//! `preset = "koyobun"` at the top of `mozuku.toml` applies a bundled set of
+//! settings and pattern rules for a kind of document. Presets are partial
+//! `mozuku.toml` files in `data/presets/`, merged into the configuration when
+//! it is parsed: settings written in the configuration win, and the pattern
+//! rules of the preset are added after those of the configuration unless one
+//! with the same ID is defined there.
+
+use crate::config::Preset;
+
+/// Bundled settings of a preset, as a partial `mozuku.toml`
+fn content(preset: Preset) -> &'static str {
+    match preset {
+        Preset::Default => "",
+        Preset::Technical => include_str!("../data/presets/technical.toml"),
+        Preset::Koyobun => include_str!("../data/presets/koyobun.toml"),
+        Preset::Jtf => include_str!("../data/presets/jtf.toml"),
+        Preset::Shinbun => include_str!("../data/presets/shinbun.toml"),
+        Preset::Academic => include_str!("../data/presets/academic.toml"),
+    }
+}
+
+/// Settings of a preset
+pub fn settings(preset: Preset) -> toml::Table {
+    content(preset).parse().expect("bundled presets are valid TOML")
+}
+
+/// Merge the settings of the preset named by the `preset` key into a
+/// configuration, keeping the settings written explicitly
+pub fn apply(table: &mut toml::Table) {
+    let Some(preset) = table.get("preset") else {
+        return;
+    };
+    let Ok(preset) = preset.clone().try_into::<Preset>() else {
+        // Reported when deserializing the whole configuration
+        return;
+    };
+    merge(table, settings(preset));
+}
+
+/// Add the settings of `preset` missing from `table`
+fn merge(table: &mut toml::Table, preset: toml::Table) {
+    for (key, value) in preset {
+        let Some(current) = table.get_mut(&key) else {
+            table.insert(key, value);
+            continue;
+        };
+        match (current, value) {
+            (toml::Value::Table(table), toml::Value::Table(preset)) => merge(table, preset),
+            (toml::Value::Array(rules), toml::Value::Array(preset)) if key == "patterns" => {
+                let ids: Vec<toml::Value> = rules.iter().filter_map(|rule| rule.get("id").cloned()).collect();
+                rules.extend(
+                    preset
+                        .into_iter()
+                        .filter(|rule| rule.get("id").is_none_or(|id| !ids.contains(id))),
+                );
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Config, WritingStyle};
+    use lsp_types::DiagnosticSeverity;
+    use std::path::Path;
+
+    #[test]
+    fn test_bundled_presets() {
+        for preset in [
+            Preset::Default,
+            Preset::Technical,
+            Preset::Koyobun,
+            Preset::Jtf,
+            Preset::Shinbun,
+            Preset::Academic,
+        ] {
+            let mut table = toml::Table::new();
+            table.insert("preset".to_string(), toml::Value::try_from(preset).unwrap());
+            apply(&mut table);
+            let config: Config = toml::Value::Table(table).try_into().unwrap();
+            assert_eq!(config.preset, Some(preset));
+            // Pattern rules are named after their preset
+            let prefix = format!("{}-", preset.id());
+            for rule in &config.checker.patterns {
+                assert!(rule.id.starts_with(&prefix), "{}", rule.id);
+            }
+        }
+    }
+
+    #[test]
+    fn test_explicit_settings_win() {
+        let config = Config::parse(
+            r#"
+preset = "koyobun"
+
+[checker]
+max_sentence_length = 80
+missing_particle = false
+
+[[checker.patterns]]
+id = "koyobun-okurigana"
+message = "独自の送り仮名ルール"
+pattern = "表層=行ない"
+
+[[checker.patterns]]
+id = "my-rule"
+message = "独自のルール"
+pattern = "表層=弊社"
+"#,
+            Path::new("."),
+        )
+        .unwrap();
+
+        assert!(config.checker.kanji_numerals);
+        assert!(config.checker.sentence_length);
+        assert_eq!(config.checker.max_sentence_length, 80);
+        assert!(config.checker.ra_nuki);
+        // Raising the severity in the preset does not turn the rule back on
+        assert!(!config.checker.is_rule_enabled("missing-particle"));
+
+        let patterns = &config.checker.patterns;
+        assert_eq!(patterns[0].message, "独自の送り仮名ルール");
+        assert_eq!(patterns[1].id, "my-rule");
+        assert!(patterns.iter().any(|rule| rule.id == "koyobun-kana"));
+        assert_eq!(patterns.iter().filter(|rule| rule.id == "koyobun-okurigana").count(), 1);
+    }
+
+    #[test]
+    fn test_jtf_preset() {
+        let config = Config::parse("preset = \"jtf\"\n", Path::new(".")).unwrap();
+
+        assert_eq!(config.checker.preferred_style, Some(WritingStyle::Desumasu));
+        assert!(config.checker.zenkaku_alphanumerics);
+        assert!(config.checker.hankaku_kana);
+        assert!(config.checker.bracket_width);
+        let ids: Vec<&str> = config.checker.patterns.iter().map(|rule| rule.id.as_str()).collect();
+        for id in ["jtf-long-vowel", "jtf-counter", "jtf-symbol", "jtf-unit"] {
+            assert!(ids.contains(&id), "{}", id);
+        }
+    }
+
+    #[test]
+    fn test_shinbun_preset() {
+        let config = Config::parse("preset = \"shinbun\"\n", Path::new(".")).unwrap();
+
+        assert!(config.checker.number_grouping);
+        // Newspapers keep kanji numerals in 一つ, 二人
+        assert!(!config.checker.kanji_numerals);
+        let ids: Vec<&str> = config.checker.patterns.iter().map(|rule| rule.id.as_str()).collect();
+        assert!(ids.contains(&"shinbun-kana"));
+        assert!(ids.contains(&"shinbun-unit"));
+    }
+
+    #[test]
+    fn test_academic_preset() {
+        let config = Config::parse("preset = \"academic\"\n", Path::new(".")).unwrap();
+
+        assert_eq!(config.checker.preferred_style, Some(WritingStyle::Dearu));
+        assert!(config.checker.weak_phrase);
+        assert!(config.checker.is_rule_enabled("missing-particle"));
+        assert_eq!(
+            config.checker.severity_override("missing-particle"),
+            Some(DiagnosticSeverity::WARNING)
+        );
+        assert!(config.checker.no_exclamation);
+        let ids: Vec<&str> = config.checker.patterns.iter().map(|rule| rule.id.as_str()).collect();
+        for id in ["academic-colloquial", "academic-first-person", "academic-vague"] {
+            assert!(ids.contains(&id), "{}", id);
+        }
+    }
+
+    #[test]
+    fn test_unknown_preset() {
+        assert!(Config::parse("preset = \"unknown\"\n", Path::new(".")).is_err());
+    }
+}
@@ -0,0 +1,487 @@
+//! Readability scoring
+//!
+//! Estimates the school grade a Japanese text suits from its sentence
+//! lengths, kanji ratio and vocabulary difficulty, and measures the share of
+//! sentences in the passive voice. Documents are scored by section (see
+//! [`sections`]). Scores are shown by the `readability` subcommand, in code
+//! lenses and in the reports of `check`; sections above the thresholds of
+//! `[checker]` get a `readability` diagnostic.
+
+use std::ops::Range;
+
+use lsp_types::{Diagnostic, NumberOrString};
+use serde::Serialize;
+
+use crate::analyzer::{split_paragraphs, split_sentences, TokenInfo};
+use crate::config::CheckerConfig;
+use crate::dictionary::CharKind;
+use crate::extractor::{FileType, TextExtractor, TextSpan};
+use crate::rules;
+use crate::utf16::LineIndex;
+
+/// Rule ID of diagnostics on sections above the readability thresholds
+pub const RULE_ID: &str = "readability";
+
+/// Upper bounds (in characters) of the sentence length buckets;
+/// longer sentences fall into a last bucket
+pub const SENTENCE_LENGTH_BUCKETS: [usize; 3] = [20, 40, 60];
+
+/// Kanji compounds of this many characters or more count as difficult words
+const DIFFICULT_COMPOUND_LENGTH: usize = 4;
+
+/// Grades range from the first year of elementary school to the last year of high school
+const MIN_GRADE: f64 = 1.0;
+const MAX_GRADE: f64 = 12.0;
+
+/// Sections with fewer sentences are too short to be reported
+const MIN_SECTION_SENTENCES: usize = 3;
+
+/// Readability of a piece of text
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadabilityScore {
+    /// Number of sentences
+    pub sentence_count: usize,
+    /// Average sentence length in characters (excluding whitespace)
+    pub average_sentence_length: f64,
+    /// Number of sentences in each length bucket (see [`SENTENCE_LENGTH_BUCKETS`])
+    pub sentence_lengths: [usize; 4],
+    /// Ratio of kanji to all characters (0.0 - 1.0)
+    pub kanji_ratio: f64,
+    /// Average length of kanji compounds (runs of consecutive kanji)
+    pub average_compound_length: f64,
+    /// Ratio of kanji compounds of four or more characters (0.0 - 1.0)
+    pub difficult_word_ratio: f64,
+    /// Ratio of sentences in the passive voice (0.0 - 1.0); 0 when scored
+    /// without tokens
+    pub passive_ratio: f64,
+    /// Estimated school grade, from 1.0 (first year of elementary school)
+    /// to 12.0 (last year of high school)
+    pub grade: f64,
+}
+
+impl ReadabilityScore {
+    /// Score the given text.
+    ///
+    /// The grade is a weighted mean of three estimates, each placing typical
+    /// texts for first graders at 0 and newspaper articles at about 10:
+    /// the average sentence length (10 to 46 characters), the kanji ratio
+    /// (10% to 40%) and the average kanji compound length (1.2 to 2.64).
+    pub fn from_text(text: &str) -> Self {
+        let mut sentence_lengths = [0; 4];
+        let lengths: Vec<usize> = split_sentences(text)
+            .iter()
+            .map(|s| s.text.chars().filter(|c| !c.is_whitespace()).count())
+            .collect();
+        for &length in &lengths {
+            let bucket = SENTENCE_LENGTH_BUCKETS
+                .iter()
+                .position(|&bound| length <= bound)
+                .unwrap_or(SENTENCE_LENGTH_BUCKETS.len());
+            sentence_lengths[bucket] += 1;
+        }
+
+        let char_count = text.chars().filter(|c| !c.is_whitespace()).count();
+        let kanji_count = text.chars().filter(|&c| CharKind::of(c) == CharKind::Kanji).count();
+        let compounds = kanji_compound_lengths(text);
+        let difficult = compounds
+            .iter()
+            .filter(|&&length| length >= DIFFICULT_COMPOUND_LENGTH)
+            .count();
+
+        let average_sentence_length = ratio(char_count, lengths.len());
+        let kanji_ratio = ratio(kanji_count, char_count);
+        let average_compound_length = ratio(kanji_count, compounds.len());
+
+        let by_sentences = (average_sentence_length - 10.0) / 3.0;
+        let by_kanji = (kanji_ratio - 0.1) / 0.025;
+        let by_vocabulary = (average_compound_length - 1.2) / 0.12;
+        let grade = if char_count == 0 {
+            MIN_GRADE
+        } else {
+            (0.4 * by_sentences + 0.35 * by_kanji + 0.25 * by_vocabulary).clamp(MIN_GRADE, MAX_GRADE)
+        };
+
+        Self {
+            sentence_count: lengths.len(),
+            average_sentence_length,
+            sentence_lengths,
+            kanji_ratio,
+            average_compound_length,
+            difficult_word_ratio: ratio(difficult, compounds.len()),
+            passive_ratio: 0.0,
+            grade,
+        }
+    }
+
+    /// Score the given text like [`Self::from_text`], counting the sentences
+    /// in the passive voice with the tokens of the text
+    pub fn from_tokens(text: &str, tokens: &[TokenInfo]) -> Self {
+        let passive: Vec<usize> = tokens
+            .iter()
+            .filter(|token| is_passive(token))
+            .map(|token| token.byte_offset)
+            .collect();
+        let sentences = split_sentences(text);
+        let passive_sentences = sentences
+            .iter()
+            .filter(|sentence| {
+                passive
+                    .iter()
+                    .any(|&offset| (sentence.start..sentence.end).contains(&offset))
+            })
+            .count();
+
+        Self {
+            passive_ratio: ratio(passive_sentences, sentences.len()),
+            ..Self::from_text(text)
+        }
+    }
+
+    /// Metrics above the thresholds of `[checker]`, described for a
+    /// diagnostic message
+    pub fn exceeded(&self, config: &CheckerConfig) -> Vec<String> {
+        let mut exceeded = Vec::new();
+        if self.grade > config.max_grade {
+            exceeded.push(format!(
+                "{}相当（{:.1}、上限 {:.1}）",
+                self.grade_label(),
+                self.grade,
+                config.max_grade
+            ));
+        }
+        if self.average_sentence_length > config.max_average_sentence_length as f64 {
+            exceeded.push(format!(
+                "平均文長 {:.1}文字（上限 {}文字）",
+                self.average_sentence_length, config.max_average_sentence_length
+            ));
+        }
+        if self.kanji_ratio > config.max_kanji_ratio {
+            exceeded.push(format!(
+                "漢字率 {:.1}%（上限 {:.1}%）",
+                self.kanji_ratio * 100.0,
+                config.max_kanji_ratio * 100.0
+            ));
+        }
+        if self.passive_ratio > config.max_passive_ratio {
+            exceeded.push(format!(
+                "受動態率 {:.1}%（上限 {:.1}%）",
+                self.passive_ratio * 100.0,
+                config.max_passive_ratio * 100.0
+            ));
+        }
+        exceeded
+    }
+
+    /// School year of the grade, e.g. 小学6年 or 中学2年
+    pub fn grade_label(&self) -> String {
+        let grade = self.grade.round() as u32;
+        match grade {
+            0..=6 => format!("小学{}年", grade.max(1)),
+            7..=9 => format!("中学{}年", grade - 6),
+            _ => format!("高校{}年", grade.min(12) - 9),
+        }
+    }
+
+    /// Multi-line summary for the command line
+    pub fn to_text(&self) -> String {
+        let [short, medium, long, very_long] = self.sentence_lengths;
+        format!(
+            "{}相当（{:.1}）\n\
+             文数 {} / 平均文長 {:.1}文字（〜20文字: {} / 〜40文字: {} / 〜60文字: {} / 61文字〜: {}）\n\
+             漢字率 {:.1}% / 漢字語の平均長 {:.2}文字 / 難語率 {:.1}% / 受動態率 {:.1}%\n",
+            self.grade_label(),
+            self.grade,
+            self.sentence_count,
+            self.average_sentence_length,
+            short,
+            medium,
+            long,
+            very_long,
+            self.kanji_ratio * 100.0,
+            self.average_compound_length,
+            self.difficult_word_ratio * 100.0,
+            self.passive_ratio * 100.0
+        )
+    }
+}
+
+/// Passive auxiliary verb (書か*れる*, 見*られる*). Potential and honorific
+/// uses of られる are counted too, since they cannot be told apart by tokens.
+fn is_passive(token: &TokenInfo) -> bool {
+    token.pos == "動詞" && token.pos_detail1 == "接尾" && matches!(token.base_form.as_str(), "れる" | "られる")
+}
+
+/// Byte ranges of the sections of a document scored separately.
+///
+/// Documents with headings are split at each heading; prose documents
+/// without headings are split into paragraphs. Source code has no sections.
+pub fn sections(extractor: &TextExtractor, content: &str, file_type: FileType) -> Vec<Range<usize>> {
+    let headings = extractor.extract_headings(content, file_type).unwrap_or_default();
+
+    if !headings.is_empty() {
+        let starts: Vec<usize> = headings.iter().map(|h| h.span.start_byte - h.span.start_col).collect();
+        return starts
+            .iter()
+            .enumerate()
+            .map(|(i, &start)| {
+                let end = starts.get(i + 1).copied().unwrap_or(content.len());
+                start..start + content[start..end].trim_end().len()
+            })
+            .collect();
+    }
+
+    match file_type {
+        FileType::PlainText | FileType::Markdown | FileType::LaTeX => split_paragraphs(content),
+        _ => vec![],
+    }
+}
+
+/// Score the part of a document in a byte range on the text of the
+/// extracted spans inside it, each span with its tokens. Spans are joined
+/// with line breaks, so markup and code are left out.
+pub fn score_range(range: Range<usize>, spans: &[(&TextSpan, &[TokenInfo])]) -> ReadabilityScore {
+    let mut text = String::new();
+    let mut tokens = Vec::new();
+    for (span, span_tokens) in spans {
+        let start = range.start.max(span.start_byte);
+        let end = range.end.min(span.end_byte);
+        if start >= end {
+            continue;
+        }
+        // Spans whose text is not a slice of the document are taken whole
+        let (from, to) = if span.text.len() == span.end_byte - span.start_byte {
+            (start - span.start_byte, end - span.start_byte)
+        } else if range.contains(&span.start_byte) {
+            (0, span.text.len())
+        } else {
+            continue;
+        };
+        if !span.text.is_char_boundary(from) || !span.text.is_char_boundary(to) {
+            continue;
+        }
+
+        if !text.is_empty() {
+            text.push('\n');
+        }
+        let offset = text.len();
+        text.push_str(&span.text[from..to]);
+        tokens.extend(
+            span_tokens
+                .iter()
+                .filter(|token| (from..to).contains(&token.byte_offset))
+                .map(|token| TokenInfo {
+                    byte_offset: token.byte_offset - from + offset,
+                    ..token.clone()
+                }),
+        );
+    }
+    ReadabilityScore::from_tokens(&text, &tokens)
+}
+
+/// Diagnostics on the first line of the sections whose score exceeds the
+/// thresholds of `[checker]`
+pub fn check_sections(
+    content: &str,
+    sections: &[Range<usize>],
+    spans: &[(&TextSpan, &[TokenInfo])],
+    config: &CheckerConfig,
+) -> Vec<Diagnostic> {
+    let index = LineIndex::new(content);
+    sections
+        .iter()
+        .filter_map(|section| {
+            let score = score_range(section.clone(), spans);
+            if score.sentence_count < MIN_SECTION_SENTENCES {
+                return None;
+            }
+            let exceeded = score.exceeded(config);
+            if exceeded.is_empty() {
+                return None;
+            }
+
+            let line_end = content[section.clone()]
+                .find('\n')
+                .map_or(section.end, |i| section.start + i);
+            let mut diagnostic = Diagnostic {
+                range: lsp_types::Range::new(index.position(section.start), index.position(line_end)),
+                severity: config.rule_severity(RULE_ID),
+                code: Some(NumberOrString::String(RULE_ID.to_string())),
+                source: Some("mozuku".to_string()),
+                message: format!(
+                    "この節は読みにくくなっています（{}）。文を分ける、漢語を言い換えるなどしてください。",
+                    exceeded.join(" / ")
+                ),
+                ..Default::default()
+            };
+            rules::annotate(&mut diagnostic);
+            Some(diagnostic)
+        })
+        .collect()
+}
+
+/// Lengths of the runs of consecutive kanji in a text
+fn kanji_compound_lengths(text: &str) -> Vec<usize> {
+    let mut lengths = Vec::new();
+    let mut run = 0;
+    for c in text.chars() {
+        if CharKind::of(c) == CharKind::Kanji {
+            run += 1;
+        } else if run > 0 {
+            lengths.push(run);
+            run = 0;
+        }
+    }
+    if run > 0 {
+        lengths.push(run);
+    }
+    lengths
+}
+
+fn ratio(count: usize, total: usize) -> f64 {
+    if total > 0 {
+        count as f64 / total as f64
+    } else {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kanji_compound_lengths() {
+        assert_eq!(kanji_compound_lengths("情報処理の基本を学ぶ"), vec![4, 2, 1]);
+        assert!(kanji_compound_lengths("ひらがな").is_empty());
+    }
+
+    #[test]
+    fn test_sentence_lengths() {
+        let long = "あ".repeat(45);
+        let score = ReadabilityScore::from_text(&format!("短い文。\n{}。", long));
+
+        assert_eq!(score.sentence_count, 2);
+        assert_eq!(score.sentence_lengths, [1, 0, 1, 0]);
+    }
+
+    #[test]
+    fn test_grade() {
+        let easy = ReadabilityScore::from_text("いぬが はしる。ねこも はしる。みんなで あそぶ。");
+        let hard = ReadabilityScore::from_text(
+            "国際情勢の急激な変化に伴い、政府は経済安全保障推進法に基づく重要物資の安定供給確保に関する基本指針を閣議決定し、\
+             関係省庁間の情報共有体制の抜本的強化を図る方針を示した。",
+        );
+
+        assert_eq!(easy.grade, 1.0);
+        assert_eq!(easy.grade_label(), "小学1年");
+        assert!(hard.grade > 9.0, "grade: {}", hard.grade);
+        assert!(hard.difficult_word_ratio > 0.0);
+        assert_eq!(ReadabilityScore::from_text("").grade, 1.0);
+    }
+
+    #[test]
+    fn test_grade_label() {
+        let score = |grade| ReadabilityScore {
+            grade,
+            ..Default::default()
+        };
+
+        assert_eq!(score(5.6).grade_label(), "小学6年");
+        assert_eq!(score(7.8).grade_label(), "中学2年");
+        assert_eq!(score(12.0).grade_label(), "高校3年");
+    }
+
+    fn token(surface: &str, pos: &str, pos_detail1: &str, byte_offset: usize) -> TokenInfo {
+        TokenInfo {
+            surface: surface.to_string(),
+            pos: pos.to_string(),
+            pos_detail1: pos_detail1.to_string(),
+            pos_detail2: "*".to_string(),
+            pos_detail3: "*".to_string(),
+            conjugation_type: "*".to_string(),
+            conjugation_form: "*".to_string(),
+            base_form: surface.to_string(),
+            reading: String::new(),
+            pronunciation: String::new(),
+            byte_offset,
+            char_offset: 0,
+            char_length: surface.chars().count(),
+        }
+    }
+
+    /// Tokens of 書かれる at the given byte offset
+    fn passive_tokens(offset: usize) -> Vec<TokenInfo> {
+        vec![
+            token("書か", "動詞", "自立", offset),
+            token("れる", "動詞", "接尾", offset + "書か".len()),
+        ]
+    }
+
+    #[test]
+    fn test_passive_ratio() {
+        let text = "本が書かれる。本を書く。";
+        let score = ReadabilityScore::from_tokens(text, &passive_tokens(6));
+
+        assert_eq!(score.sentence_count, 2);
+        assert_eq!(score.passive_ratio, 0.5);
+        assert_eq!(ReadabilityScore::from_text(text).passive_ratio, 0.0);
+    }
+
+    #[test]
+    fn test_exceeded() {
+        let config = CheckerConfig::default();
+        let score = ReadabilityScore {
+            grade: 11.0,
+            kanji_ratio: 0.2,
+            passive_ratio: 0.5,
+            ..Default::default()
+        };
+
+        let exceeded = score.exceeded(&config);
+        assert_eq!(exceeded.len(), 2, "{:?}", exceeded);
+        assert!(exceeded[0].starts_with("高校2年相当"));
+        assert!(exceeded[1].starts_with("受動態率 50.0%"));
+        assert!(ReadabilityScore::default().exceeded(&config).is_empty());
+    }
+
+    #[test]
+    fn test_score_range_clips_spans() {
+        let content = "本が書かれる。\n\n本を書く。";
+        let span = TextSpan::new(content.to_string(), 0, content.len(), 0, 0, 2, 15);
+        let tokens = passive_tokens(6);
+        let spans = [(&span, tokens.as_slice())];
+
+        let first = score_range(0..21, &spans);
+        let second = score_range(23..content.len(), &spans);
+        assert_eq!((first.sentence_count, first.passive_ratio), (1, 1.0));
+        assert_eq!((second.sentence_count, second.passive_ratio), (1, 0.0));
+    }
+
+    #[test]
+    fn test_check_sections() {
+        let content = "# 見出し\n本が書かれる。本が読まれる。本が売られる。\n";
+        let end = content.len() - 1;
+        let span = TextSpan::new(content[12..end].to_string(), 12, end, 1, 0, 1, 63);
+        let tokens: Vec<TokenInfo> = [6, 27, 48].into_iter().flat_map(passive_tokens).collect();
+        let config = CheckerConfig::default();
+
+        let diagnostics = check_sections(content, &[0..end], &[(&span, tokens.as_slice())], &config);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].range.end.line, 0);
+        assert!(diagnostics[0].message.contains("受動態率 100.0%"));
+
+        // Sections of a single sentence are too short to be reported
+        let short = TextSpan::new(content[12..33].to_string(), 12, 33, 1, 0, 1, 21);
+        assert!(check_sections(content, &[0..33], &[(&short, &tokens[..2])], &config).is_empty());
+    }
+
+    #[test]
+    fn test_to_text() {
+        let text = ReadabilityScore::from_text("今日は晴れ。").to_text();
+
+        assert!(text.starts_with("小学"));
+        assert!(text.contains("〜20文字: 1"));
+        assert!(text.contains("漢字率 50.0%"));
+    }
+}
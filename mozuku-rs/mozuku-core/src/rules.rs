@@ -0,0 +1,847 @@
+//! Rule registry
+//!
+//! Maps rule IDs (diagnostic codes) to their names, descriptions, categories,
+//! default severities, `[checker]` settings, examples and documentation
+//! pages, and how the checker runs them. The checker instantiates the
+//! enabled rules from the registry and reports with their severities, and
+//! the configuration, `rules` listing, reports and diagnostic links all look
+//! rules up here.
+
+use lsp_types::{CodeDescription, Diagnostic, DiagnosticSeverity, DiagnosticTag, NumberOrString, Url};
+
+use crate::checker::{
+    self, ConsecutiveNo, DoubleHonorific, DoubleParticle, INuki, KanjiNumerals, MissingParticle, NotationVariance,
+    RaNuki, RedundantExpression, RedundantNa, ScriptVariance, TariParallel, TokenRule,
+};
+use crate::config::CheckerConfig;
+use crate::notation;
+use crate::spellcheck;
+use crate::textlint::{self, TextRule};
+
+/// Base URL of the rule documentation
+pub const RULE_DOCS_URL: &str = "https://github.com/clearclown/MoZukuRust/blob/main/mozuku-rs/docs/rules.md";
+
+/// Rule documentation, embedded for hover explanations
+const RULE_DOCS: &str = include_str!("../../docs/rules.md");
+
+/// Metadata for a checker rule
+#[derive(Debug, Clone, Copy)]
+pub struct RuleInfo {
+    /// Rule ID, used as the diagnostic code
+    pub id: &'static str,
+    /// Display name
+    pub name: &'static str,
+    /// Short description
+    pub description: &'static str,
+    /// Kind of problem the rule finds
+    pub category: RuleCategory,
+    /// Default severity of the rule's diagnostics, which `[checker.rules]` can replace
+    pub severity: DiagnosticSeverity,
+    /// `[checker]` setting key that toggles the rule
+    pub setting: &'static str,
+    /// Value of the setting in a configuration
+    pub setting_value: fn(&CheckerConfig) -> bool,
+    /// Whether the rule is enabled without configuration
+    pub default_enabled: bool,
+    /// Example sentences flagged by the rule and their corrections
+    pub examples: &'static [RuleExample],
+    /// Whether the flagged text can simply be removed (rendered faded)
+    pub unnecessary: bool,
+    /// How the checker runs the rule
+    pub(crate) check: RuleCheck,
+}
+
+impl PartialEq for RuleInfo {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl Eq for RuleInfo {}
+
+/// How the checker runs a rule
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum RuleCheck {
+    /// Created from the configuration for each check, and driven by the
+    /// single pass over the tokens
+    Tokens(for<'a> fn(&'a CheckerConfig) -> Box<dyn TokenRule<'a> + 'a>),
+    /// Run on the whole text, with its sentences
+    Text(TextRule),
+    /// Run outside [`GrammarChecker`](crate::checker::GrammarChecker): on the
+    /// sections of a document or across workspace files
+    External,
+}
+
+/// Kind of problem a rule finds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+pub enum RuleCategory {
+    /// Grammatical mistakes (ら抜き言葉, duplicated particles)
+    Grammar,
+    /// Wording and style (redundant expressions, mixed endings)
+    Style,
+    /// Notation (表記ゆれ, half-width katakana)
+    Notation,
+    /// Readability (long sentences, many 読点)
+    Readability,
+}
+
+impl RuleCategory {
+    /// Identifier used in reports and on the command line
+    pub fn id(&self) -> &'static str {
+        match self {
+            RuleCategory::Grammar => "grammar",
+            RuleCategory::Style => "style",
+            RuleCategory::Notation => "notation",
+            RuleCategory::Readability => "readability",
+        }
+    }
+
+    /// Display name
+    pub fn label(&self) -> &'static str {
+        match self {
+            RuleCategory::Grammar => "文法",
+            RuleCategory::Style => "文体・表現",
+            RuleCategory::Notation => "表記",
+            RuleCategory::Readability => "読みやすさ",
+        }
+    }
+}
+
+/// A sentence flagged by a rule and its correction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RuleExample {
+    pub incorrect: &'static str,
+    pub correct: &'static str,
+}
+
+impl RuleInfo {
+    /// URL of the rule's documentation section
+    pub fn doc_url(&self) -> Url {
+        Url::parse(&format!("{}#{}", RULE_DOCS_URL, self.id)).unwrap()
+    }
+
+    /// Markdown explanation of the rule: its section of docs/rules.md
+    /// without the heading, or the short description if it has none
+    pub fn explanation(&self) -> &'static str {
+        let heading = format!("## {}\n", self.id);
+        let Some(start) = RULE_DOCS.find(&heading) else {
+            return self.description;
+        };
+
+        let section = &RULE_DOCS[start + heading.len()..];
+        let end = section.find("\n## ").unwrap_or(section.len());
+        section[..end].trim()
+    }
+
+    /// Markdown shown when hovering a diagnostic of the rule
+    pub fn hover_markdown(&self) -> String {
+        format!(
+            "{}\n\n[ルールの解説]({}) (`{}`)",
+            self.explanation(),
+            self.doc_url(),
+            self.id
+        )
+    }
+}
+
+/// All built-in rules
+pub const RULES: &[RuleInfo] = &[
+    RuleInfo {
+        id: "ra-nuki",
+        name: "ら抜き言葉",
+        description: "可能の「られる」から「ら」が抜けた表現（食べれる → 食べられる）",
+        category: RuleCategory::Grammar,
+        severity: DiagnosticSeverity::WARNING,
+        setting: "ra_nuki",
+        setting_value: |config| config.ra_nuki,
+        default_enabled: true,
+        examples: &[
+            RuleExample {
+                incorrect: "食べれる",
+                correct: "食べられる",
+            },
+            RuleExample {
+                incorrect: "見れる",
+                correct: "見られる",
+            },
+        ],
+        unnecessary: false,
+        check: RuleCheck::Tokens(|_| Box::new(RaNuki)),
+    },
+    RuleInfo {
+        id: "i-nuki",
+        name: "い抜き言葉",
+        description: "「ている」から「い」が抜けた表現（食べてる → 食べている）",
+        category: RuleCategory::Grammar,
+        severity: DiagnosticSeverity::HINT,
+        setting: "i_nuki",
+        setting_value: |config| config.i_nuki,
+        default_enabled: true,
+        examples: &[
+            RuleExample {
+                incorrect: "食べてる",
+                correct: "食べている",
+            },
+            RuleExample {
+                incorrect: "読んでる",
+                correct: "読んでいる",
+            },
+        ],
+        unnecessary: false,
+        check: RuleCheck::Tokens(|_| Box::new(INuki)),
+    },
+    RuleInfo {
+        id: "double-particle",
+        name: "助詞の重複",
+        description: "同じ助詞が連続している（私はは → 私は）",
+        category: RuleCategory::Grammar,
+        severity: DiagnosticSeverity::ERROR,
+        setting: "double_particle",
+        setting_value: |config| config.double_particle,
+        default_enabled: true,
+        examples: &[RuleExample {
+            incorrect: "私はは学生です",
+            correct: "私は学生です",
+        }],
+        unnecessary: false,
+        check: RuleCheck::Tokens(|_| Box::new(DoubleParticle)),
+    },
+    RuleInfo {
+        id: "missing-particle",
+        name: "助詞の脱落",
+        description: "名詞の直後に動詞が続き、助詞が抜けている（私行きます → 私が行きます）",
+        category: RuleCategory::Grammar,
+        severity: DiagnosticSeverity::INFORMATION,
+        setting: "missing_particle",
+        setting_value: |config| config.missing_particle,
+        default_enabled: false,
+        examples: &[
+            RuleExample {
+                incorrect: "私行きます",
+                correct: "私が行きます",
+            },
+            RuleExample {
+                incorrect: "資料送ります",
+                correct: "資料を送ります",
+            },
+        ],
+        unnecessary: false,
+        check: RuleCheck::Tokens(|_| Box::new(MissingParticle)),
+    },
+    RuleInfo {
+        id: "redundant-na",
+        name: "「な」の重複",
+        description: "形容動詞の語尾「な」が重複している（静かなな → 静かな）",
+        category: RuleCategory::Grammar,
+        severity: DiagnosticSeverity::ERROR,
+        setting: "redundant_na",
+        setting_value: |config| config.redundant_na,
+        default_enabled: true,
+        examples: &[RuleExample {
+            incorrect: "静かなな部屋",
+            correct: "静かな部屋",
+        }],
+        unnecessary: true,
+        check: RuleCheck::Tokens(|_| Box::new(RedundantNa)),
+    },
+    RuleInfo {
+        id: "double-honorific",
+        name: "二重敬語",
+        description: "敬語が重ねて使われている（おっしゃられる → おっしゃる）",
+        category: RuleCategory::Grammar,
+        severity: DiagnosticSeverity::WARNING,
+        setting: "double_honorific",
+        setting_value: |config| config.double_honorific,
+        default_enabled: true,
+        examples: &[
+            RuleExample {
+                incorrect: "おっしゃられる",
+                correct: "おっしゃる",
+            },
+            RuleExample {
+                incorrect: "ご覧になられる",
+                correct: "ご覧になる",
+            },
+        ],
+        unnecessary: false,
+        check: RuleCheck::Tokens(|_| Box::new(DoubleHonorific)),
+    },
+    RuleInfo {
+        id: "redundant-expression",
+        name: "冗長表現",
+        description: "より簡潔に言い換えられる表現（することができる → できる）",
+        category: RuleCategory::Style,
+        severity: DiagnosticSeverity::HINT,
+        setting: "redundant_expression",
+        setting_value: |config| config.redundant_expression,
+        default_enabled: true,
+        examples: &[
+            RuleExample {
+                incorrect: "使用することができる",
+                correct: "使用できる",
+            },
+            RuleExample {
+                incorrect: "実行することが可能",
+                correct: "実行できる",
+            },
+        ],
+        unnecessary: true,
+        check: RuleCheck::Tokens(|_| Box::new(RedundantExpression)),
+    },
+    RuleInfo {
+        id: "consecutive-endings",
+        name: "連続文末",
+        description: "同じ文末表現が3回以上続いている",
+        category: RuleCategory::Style,
+        severity: DiagnosticSeverity::HINT,
+        setting: "consecutive_endings",
+        setting_value: |config| config.consecutive_endings,
+        default_enabled: true,
+        examples: &[RuleExample {
+            incorrect: "晴れです。暑いです。夏です。",
+            correct: "晴れて暑い。夏です。",
+        }],
+        unnecessary: false,
+        check: RuleCheck::Text(checker::consecutive_endings),
+    },
+    RuleInfo {
+        id: "hedge-endings",
+        name: "ヘッジ表現の多用",
+        description: "「と思います」「と考えられます」などで終わる文が多すぎる",
+        category: RuleCategory::Style,
+        severity: DiagnosticSeverity::INFORMATION,
+        setting: "hedge_endings",
+        setting_value: |config| config.hedge_endings,
+        default_enabled: false,
+        examples: &[RuleExample {
+            incorrect: "原因は設定だと思います。修正で直ると思います。",
+            correct: "原因は設定である。修正で直ることを確認した。",
+        }],
+        unnecessary: false,
+        check: RuleCheck::Text(checker::hedge_endings),
+    },
+    RuleInfo {
+        id: "incomplete-tari",
+        name: "たり並列",
+        description: "「〜たり〜たりする」の並列が不完全（歩いたり走る → 歩いたり走ったりする）",
+        category: RuleCategory::Grammar,
+        severity: DiagnosticSeverity::WARNING,
+        setting: "tari_parallel",
+        setting_value: |config| config.tari_parallel,
+        default_enabled: true,
+        examples: &[RuleExample {
+            incorrect: "歩いたり走る",
+            correct: "歩いたり走ったりする",
+        }],
+        unnecessary: false,
+        check: RuleCheck::Tokens(|_| Box::<TariParallel>::default()),
+    },
+    RuleInfo {
+        id: "consecutive-no",
+        name: "の連続",
+        description: "助詞「の」が3回以上続いている（私の友達の本の内容）",
+        category: RuleCategory::Style,
+        severity: DiagnosticSeverity::HINT,
+        setting: "consecutive_no",
+        setting_value: |config| config.consecutive_no,
+        default_enabled: true,
+        examples: &[RuleExample {
+            incorrect: "私の友達の本の内容",
+            correct: "友達から借りた本の内容",
+        }],
+        unnecessary: false,
+        check: RuleCheck::Tokens(|_| Box::<ConsecutiveNo>::default()),
+    },
+    RuleInfo {
+        id: "notation-variance",
+        name: "表記ゆれ",
+        description: "同じ語が異なる表記で書かれている（サーバ / サーバー）",
+        category: RuleCategory::Notation,
+        severity: DiagnosticSeverity::INFORMATION,
+        setting: "notation_variance",
+        setting_value: |config| config.notation_variance,
+        default_enabled: true,
+        examples: &[RuleExample {
+            incorrect: "サーバーとサーバ",
+            correct: "サーバーとサーバー",
+        }],
+        unnecessary: false,
+        check: RuleCheck::Tokens(|_| Box::<NotationVariance>::default()),
+    },
+    RuleInfo {
+        id: "script-variance",
+        name: "文字種の表記ゆれ",
+        description: "同じ読みの語がひらがな・カタカナ・漢字の異なる表記で書かれている（こども / 子供）",
+        category: RuleCategory::Notation,
+        severity: DiagnosticSeverity::INFORMATION,
+        setting: "script_variance",
+        setting_value: |config| config.script_variance,
+        default_enabled: false,
+        examples: &[
+            RuleExample {
+                incorrect: "子供の数とこどもの数",
+                correct: "子供の数と子供の数",
+            },
+            RuleExample {
+                incorrect: "携帯とケータイ",
+                correct: "携帯と携帯",
+            },
+        ],
+        unnecessary: false,
+        check: RuleCheck::Tokens(|_| Box::<ScriptVariance>::default()),
+    },
+    RuleInfo {
+        id: "kanji-numerals",
+        name: "漢数字",
+        description: "数量を漢数字で書いている（三つ → 3つ、二十件 → 20件）",
+        category: RuleCategory::Notation,
+        severity: DiagnosticSeverity::INFORMATION,
+        setting: "kanji_numerals",
+        setting_value: |config| config.kanji_numerals,
+        default_enabled: false,
+        examples: &[
+            RuleExample {
+                incorrect: "三つの方法",
+                correct: "3つの方法",
+            },
+            RuleExample {
+                incorrect: "二十五件の申請",
+                correct: "25件の申請",
+            },
+        ],
+        unnecessary: false,
+        check: RuleCheck::Tokens(|_| Box::new(KanjiNumerals)),
+    },
+    RuleInfo {
+        id: "number-grouping",
+        name: "数の位取り",
+        description: "数に位取りのコンマを付けている、または万以上の数を万・億・兆で区切っていない（12,000円）",
+        category: RuleCategory::Notation,
+        severity: DiagnosticSeverity::INFORMATION,
+        setting: "number_grouping",
+        setting_value: |config| config.number_grouping,
+        default_enabled: false,
+        examples: &[
+            RuleExample {
+                incorrect: "予算は12,000円",
+                correct: "予算は1万2000円",
+            },
+            RuleExample {
+                incorrect: "2,000人が参加",
+                correct: "2000人が参加",
+            },
+        ],
+        unnecessary: false,
+        check: RuleCheck::Text(notation::number_grouping),
+    },
+    RuleInfo {
+        id: "unit-notation",
+        name: "単位の表記",
+        description: "同じ単位の表記（10GB / 10ＧＢ / 10ギガバイト）や数値と単位の間のスペースが揃っていない",
+        category: RuleCategory::Notation,
+        severity: DiagnosticSeverity::INFORMATION,
+        setting: "unit_notation",
+        setting_value: |config| config.unit_notation,
+        default_enabled: false,
+        examples: &[
+            RuleExample {
+                incorrect: "容量は10GB、予備は5ＧＢ",
+                correct: "容量は10GB、予備は5GB",
+            },
+            RuleExample {
+                incorrect: "10GBと20 GB",
+                correct: "10GBと20GB",
+            },
+        ],
+        unnecessary: false,
+        check: RuleCheck::Text(notation::unit_notation),
+    },
+    RuleInfo {
+        id: "hankaku-punctuation",
+        name: "和文中の半角の句読点",
+        description: "和文中に半角のカンマやピリオドが使われている（今日は,晴れ.）",
+        category: RuleCategory::Notation,
+        severity: DiagnosticSeverity::WARNING,
+        setting: "hankaku_punctuation",
+        setting_value: |config| config.hankaku_punctuation,
+        default_enabled: false,
+        examples: &[RuleExample {
+            incorrect: "今日は,晴れです.",
+            correct: "今日は、晴れです。",
+        }],
+        unnecessary: false,
+        check: RuleCheck::Text(notation::hankaku_punctuation),
+    },
+    RuleInfo {
+        id: "exclamation-question-mark",
+        name: "感嘆符・疑問符",
+        description: "感嘆符・疑問符の全角と半角が混在している、重ねている、後に全角スペースがない（すごい！！！）",
+        category: RuleCategory::Notation,
+        severity: DiagnosticSeverity::INFORMATION,
+        setting: "exclamation_question_mark",
+        setting_value: |config| config.exclamation_question_mark,
+        default_enabled: false,
+        examples: &[
+            RuleExample {
+                incorrect: "すごい！！！",
+                correct: "すごい！",
+            },
+            RuleExample {
+                incorrect: "本当？はい。",
+                correct: "本当？　はい。",
+            },
+        ],
+        unnecessary: false,
+        check: RuleCheck::Text(notation::exclamation_question_mark),
+    },
+    RuleInfo {
+        id: "bracket-width",
+        name: "括弧の全角・半角",
+        description: "和文中に半角の括弧、英文中に全角の括弧が使われている（MoZuku(モズク)）",
+        category: RuleCategory::Notation,
+        severity: DiagnosticSeverity::INFORMATION,
+        setting: "bracket_width",
+        setting_value: |config| config.bracket_width,
+        default_enabled: false,
+        examples: &[
+            RuleExample {
+                incorrect: "MoZuku(モズク)",
+                correct: "MoZuku（モズク）",
+            },
+            RuleExample {
+                incorrect: "結果（図1)",
+                correct: "結果（図1）",
+            },
+        ],
+        unnecessary: false,
+        check: RuleCheck::Text(notation::bracket_width),
+    },
+    RuleInfo {
+        id: "term-consistency",
+        name: "用語の統一",
+        description: "ワークスペース内のファイル間で同じ用語の表記が揺れている（ログイン / サインイン）",
+        category: RuleCategory::Notation,
+        severity: DiagnosticSeverity::INFORMATION,
+        setting: "term_consistency",
+        setting_value: |config| config.term_consistency,
+        default_enabled: true,
+        examples: &[RuleExample {
+            incorrect: "（他のファイルは「ログイン」）サインインする",
+            correct: "ログインする",
+        }],
+        unnecessary: false,
+        check: RuleCheck::External,
+    },
+    RuleInfo {
+        id: "sentence-length",
+        name: "文の長さ",
+        description: "一文が長すぎる（既定は100文字まで）",
+        category: RuleCategory::Readability,
+        severity: DiagnosticSeverity::WARNING,
+        setting: "sentence_length",
+        setting_value: |config| config.sentence_length,
+        default_enabled: false,
+        examples: &[RuleExample {
+            incorrect: "（120文字を超える一文）",
+            correct: "（二つ以上の文に分けた文章）",
+        }],
+        unnecessary: false,
+        check: RuleCheck::Text(textlint::sentence_length),
+    },
+    RuleInfo {
+        id: "max-ten",
+        name: "読点の数",
+        description: "一文に使われている読点が多すぎる（既定は3個まで）",
+        category: RuleCategory::Readability,
+        severity: DiagnosticSeverity::WARNING,
+        setting: "max_ten",
+        setting_value: |config| config.max_ten,
+        default_enabled: false,
+        examples: &[RuleExample {
+            incorrect: "まず、設定を開き、項目を選び、値を変え、保存する。",
+            correct: "まず設定を開き、項目を選ぶ。値を変えて保存する。",
+        }],
+        unnecessary: false,
+        check: RuleCheck::Text(textlint::max_ten),
+    },
+    RuleInfo {
+        id: "max-kanji-continuous-len",
+        name: "漢字の連続",
+        description: "漢字が長く連続している（既定は6文字まで）",
+        category: RuleCategory::Readability,
+        severity: DiagnosticSeverity::WARNING,
+        setting: "max_kanji_continuous_len",
+        setting_value: |config| config.max_kanji_continuous_len,
+        default_enabled: false,
+        examples: &[RuleExample {
+            incorrect: "情報処理技術者試験対策",
+            correct: "情報処理技術者試験の対策",
+        }],
+        unnecessary: false,
+        check: RuleCheck::Text(textlint::max_kanji_continuous_len),
+    },
+    RuleInfo {
+        id: "no-mix-dearu-desumasu",
+        name: "文体の混在",
+        description: "「ですます」調と「である」調が混在している",
+        category: RuleCategory::Style,
+        severity: DiagnosticSeverity::WARNING,
+        setting: "mix_dearu_desumasu",
+        setting_value: |config| config.mix_dearu_desumasu,
+        default_enabled: false,
+        examples: &[RuleExample {
+            incorrect: "晴れです。暑い日だ。",
+            correct: "晴れです。暑い日です。",
+        }],
+        unnecessary: false,
+        check: RuleCheck::Text(textlint::mix_dearu_desumasu),
+    },
+    RuleInfo {
+        id: "no-doubled-conjunctive-particle-ga",
+        name: "逆接の「が」の重複",
+        description: "一文に逆接の接続助詞「が」が2回以上使われている",
+        category: RuleCategory::Style,
+        severity: DiagnosticSeverity::WARNING,
+        setting: "doubled_conjunctive_particle_ga",
+        setting_value: |config| config.doubled_conjunctive_particle_ga,
+        default_enabled: false,
+        examples: &[RuleExample {
+            incorrect: "今日は晴れたが、風は強いが、暖かい。",
+            correct: "今日は晴れたが、風は強い。それでも暖かい。",
+        }],
+        unnecessary: false,
+        check: RuleCheck::Text(textlint::doubled_conjunctive_particle_ga),
+    },
+    RuleInfo {
+        id: "no-doubled-conjunction",
+        name: "接続詞の連続",
+        description: "同じ接続詞で始まる文が続いている",
+        category: RuleCategory::Style,
+        severity: DiagnosticSeverity::WARNING,
+        setting: "doubled_conjunction",
+        setting_value: |config| config.doubled_conjunction,
+        default_enabled: false,
+        examples: &[RuleExample {
+            incorrect: "しかし、雨だ。しかし、出かける。",
+            correct: "しかし、雨だ。それでも出かける。",
+        }],
+        unnecessary: false,
+        check: RuleCheck::Text(textlint::doubled_conjunction),
+    },
+    RuleInfo {
+        id: "no-hankaku-kana",
+        name: "半角カナ",
+        description: "半角カタカナが使われている（ｶﾀｶﾅ → カタカナ）",
+        category: RuleCategory::Notation,
+        severity: DiagnosticSeverity::WARNING,
+        setting: "hankaku_kana",
+        setting_value: |config| config.hankaku_kana,
+        default_enabled: false,
+        examples: &[RuleExample {
+            incorrect: "ﾃﾞｰﾀを保存する",
+            correct: "データを保存する",
+        }],
+        unnecessary: false,
+        check: RuleCheck::Text(textlint::hankaku_kana),
+    },
+    RuleInfo {
+        id: "no-zenkaku-alphanumerics",
+        name: "全角英数字",
+        description: "英字・数字が全角で書かれている（ＡＰＩ → API、１０ → 10）",
+        category: RuleCategory::Notation,
+        severity: DiagnosticSeverity::WARNING,
+        setting: "zenkaku_alphanumerics",
+        setting_value: |config| config.zenkaku_alphanumerics,
+        default_enabled: false,
+        examples: &[RuleExample {
+            incorrect: "ＡＰＩの応答は１０秒以内",
+            correct: "APIの応答は10秒以内",
+        }],
+        unnecessary: false,
+        check: RuleCheck::Text(textlint::zenkaku_alphanumerics),
+    },
+    RuleInfo {
+        id: "ja-no-weak-phrase",
+        name: "弱い表現",
+        description: "「かもしれない」などの弱い表現が使われている",
+        category: RuleCategory::Style,
+        severity: DiagnosticSeverity::INFORMATION,
+        setting: "weak_phrase",
+        setting_value: |config| config.weak_phrase,
+        default_enabled: false,
+        examples: &[RuleExample {
+            incorrect: "動作するかもしれません",
+            correct: "動作します",
+        }],
+        unnecessary: false,
+        check: RuleCheck::Text(textlint::weak_phrase),
+    },
+    RuleInfo {
+        id: "no-zero-width-spaces",
+        name: "ゼロ幅スペース",
+        description: "見えないゼロ幅スペース（U+200B）が含まれている",
+        category: RuleCategory::Notation,
+        severity: DiagnosticSeverity::WARNING,
+        setting: "zero_width_spaces",
+        setting_value: |config| config.zero_width_spaces,
+        default_enabled: false,
+        examples: &[RuleExample {
+            incorrect: "設定\u{200B}ファイル",
+            correct: "設定ファイル",
+        }],
+        unnecessary: true,
+        check: RuleCheck::Text(textlint::zero_width_spaces),
+    },
+    RuleInfo {
+        id: "english-spelling",
+        name: "英単語のスペル",
+        description: "和文中の英単語のスペルが誤っている",
+        category: RuleCategory::Notation,
+        severity: DiagnosticSeverity::INFORMATION,
+        setting: "english_spelling",
+        setting_value: |config| config.english_spelling,
+        default_enabled: false,
+        examples: &[RuleExample {
+            incorrect: "設定ファイルの cofig を開く",
+            correct: "設定ファイルの config を開く",
+        }],
+        unnecessary: false,
+        check: RuleCheck::Text(spellcheck::english_spelling),
+    },
+    RuleInfo {
+        id: "readability",
+        name: "読みやすさ",
+        description: "節の学年レベル・平均文長・漢字率・受動態率が閾値を超えている",
+        category: RuleCategory::Readability,
+        severity: DiagnosticSeverity::INFORMATION,
+        setting: "readability",
+        setting_value: |config| config.readability,
+        default_enabled: false,
+        examples: &[RuleExample {
+            incorrect: "本件は関係部署により検討された。結論は会議で報告された。対応は後日決定された。",
+            correct: "関係部署が本件を検討した。結論は会議で報告した。対応は後日決める。",
+        }],
+        unnecessary: false,
+        check: RuleCheck::External,
+    },
+];
+
+/// Look up a rule by ID
+pub fn find_rule(id: &str) -> Option<&'static RuleInfo> {
+    RULES.iter().find(|rule| rule.id == id)
+}
+
+/// Look up the rule that produced a diagnostic
+pub fn rule_for_diagnostic(diagnostic: &Diagnostic) -> Option<&'static RuleInfo> {
+    match &diagnostic.code {
+        Some(NumberOrString::String(code)) => find_rule(code),
+        _ => None,
+    }
+}
+
+/// Attach the default severity, documentation link and tags of the
+/// diagnostic's rule. A severity the rule gave the diagnostic itself is kept.
+pub fn annotate(diagnostic: &mut Diagnostic) {
+    let Some(rule) = rule_for_diagnostic(diagnostic) else {
+        return;
+    };
+
+    diagnostic.severity.get_or_insert(rule.severity);
+    diagnostic.code_description = Some(CodeDescription { href: rule.doc_url() });
+    if rule.unnecessary {
+        diagnostic
+            .tags
+            .get_or_insert_with(Vec::new)
+            .push(DiagnosticTag::UNNECESSARY);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rule_ids_unique() {
+        for (i, rule) in RULES.iter().enumerate() {
+            assert!(
+                RULES[i + 1..].iter().all(|other| other.id != rule.id),
+                "Duplicate rule ID: {}",
+                rule.id
+            );
+            assert!(
+                RULES[i + 1..].iter().all(|other| other.setting != rule.setting),
+                "Duplicate setting: {}",
+                rule.setting
+            );
+        }
+    }
+
+    #[test]
+    fn test_rules_documented() {
+        for rule in RULES {
+            assert!(
+                RULE_DOCS.contains(&format!("## {}\n", rule.id)),
+                "Rule {} is missing from docs/rules.md",
+                rule.id
+            );
+        }
+    }
+
+    #[test]
+    fn test_rules_have_examples() {
+        for rule in RULES {
+            assert!(!rule.examples.is_empty(), "Rule {} has no examples", rule.id);
+        }
+        assert_eq!(find_rule("i-nuki").unwrap().severity, DiagnosticSeverity::HINT);
+    }
+
+    #[test]
+    fn test_explanation() {
+        let explanation = find_rule("ra-nuki").unwrap().explanation();
+
+        assert!(explanation.starts_with("**ら抜き言葉**"));
+        assert!(explanation.contains("**直し方**"));
+        assert!(!explanation.contains("## "));
+        assert!(!explanation.contains("い抜き言葉"));
+    }
+
+    #[test]
+    fn test_explanation_of_last_rule() {
+        let last = RULES.last().unwrap();
+
+        assert!(last.explanation().starts_with(&format!("**{}**", last.name)));
+        assert!(last
+            .hover_markdown()
+            .contains(&format!("({}#{})", RULE_DOCS_URL, last.id)));
+    }
+
+    #[test]
+    fn test_annotate() {
+        let mut diagnostic = Diagnostic {
+            code: Some(NumberOrString::String("redundant-expression".to_string())),
+            ..Default::default()
+        };
+        annotate(&mut diagnostic);
+
+        assert_eq!(
+            diagnostic.code_description.unwrap().href.as_str(),
+            format!("{}#redundant-expression", RULE_DOCS_URL)
+        );
+        assert_eq!(diagnostic.tags, Some(vec![DiagnosticTag::UNNECESSARY]));
+        assert_eq!(diagnostic.severity, Some(DiagnosticSeverity::HINT));
+    }
+
+    #[test]
+    fn test_annotate_unknown_rule() {
+        let mut diagnostic = Diagnostic {
+            code: Some(NumberOrString::String("unknown".to_string())),
+            ..Default::default()
+        };
+        annotate(&mut diagnostic);
+
+        assert!(diagnostic.code_description.is_none());
+        assert!(diagnostic.tags.is_none());
+        assert!(diagnostic.severity.is_none());
+    }
+}
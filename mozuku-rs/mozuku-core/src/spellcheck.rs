@@ -0,0 +1,475 @@
+//! English spelling in Japanese text
+//!
+//! Finds misspelled English words written among Japanese sentences
+//! (「設定ファイルの cofig を開く」). Words are looked up in a built-in
+//! frequency dictionary (`data/english.txt`) and the user dictionaries of
+//! `[checker] english_dictionary`; regular inflections and common prefixes
+//! are accepted. Identifiers are left alone: words with capital letters
+//! (API, GitHub, camelCase), digits, underscores or other symbols, and inline
+//! code between backticks. Sentences without Japanese are not checked.
+
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::SystemTime;
+
+use anyhow::{Context, Result};
+use lsp_types::{Diagnostic, NumberOrString};
+
+use crate::analyzer::split_sentences;
+use crate::checker::{fix_data, Fix};
+use crate::config::CheckerConfig;
+use crate::dictionary::CharKind;
+use crate::rules;
+use crate::textlint::TextContext;
+use crate::utf16::LineIndex;
+
+/// Rule ID of spelling diagnostics
+pub const RULE_ID: &str = "english-spelling";
+
+/// Built-in dictionary, most frequent words first
+const ENGLISH_WORDS: &str = include_str!("../data/english.txt");
+
+/// Words shorter than this are not checked
+const MIN_WORD_LEN: usize = 3;
+
+/// Number of suggestions offered as fixes
+const MAX_SUGGESTIONS: usize = 3;
+
+/// Suffixes of regularly inflected and derived forms, longest first
+const SUFFIXES: &[&str] = &[
+    "ness", "ment", "iest", "ing", "ies", "ied", "ier", "ily", "est", "ed", "es", "er", "ly", "s",
+];
+
+/// Prefixes accepted before a known word (reinstall, unsupported)
+const PREFIXES: &[&str] = &[
+    "inter", "multi", "under", "auto", "over", "pre", "non", "sub", "dis", "un", "re", "de",
+];
+
+/// Suffixes re-attached to dictionary words when looking for suggestions
+const SUGGESTION_SUFFIXES: &[&str] = &["ing", "ed", "es", "s"];
+
+/// Words of the built-in dictionary, in frequency order
+fn builtin_words() -> &'static [&'static str] {
+    static WORDS: OnceLock<Vec<&str>> = OnceLock::new();
+    WORDS.get_or_init(|| {
+        ENGLISH_WORDS
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .collect()
+    })
+}
+
+fn builtin_set() -> &'static HashSet<&'static str> {
+    static SET: OnceLock<HashSet<&str>> = OnceLock::new();
+    SET.get_or_init(|| builtin_words().iter().copied().collect())
+}
+
+/// Words known to the checker: the built-in dictionary and user dictionaries
+pub struct Lexicon<'a> {
+    user: &'a [Arc<HashSet<String>>],
+}
+
+impl<'a> Lexicon<'a> {
+    pub fn new(user: &'a [Arc<HashSet<String>>]) -> Self {
+        Self { user }
+    }
+
+    fn contains(&self, word: &str) -> bool {
+        builtin_set().contains(word) || self.user.iter().any(|words| words.contains(word))
+    }
+
+    /// Whether a lowercase word is in a dictionary, possibly inflected or
+    /// with a prefix
+    pub fn is_known(&self, word: &str) -> bool {
+        if base_forms(word).iter().any(|form| self.contains(form)) {
+            return true;
+        }
+        PREFIXES.iter().any(|prefix| {
+            word.strip_prefix(prefix)
+                .filter(|rest| rest.len() >= MIN_WORD_LEN)
+                .is_some_and(|rest| base_forms(rest).iter().any(|form| self.contains(form)))
+        })
+    }
+
+    /// Dictionary words closest to a misspelled word, best first: fewest
+    /// edits, then words as listed over inflected forms, then most frequent
+    pub fn suggestions(&self, word: &str) -> Vec<String> {
+        let max_distance = if word.len() <= 4 { 1 } else { 2 };
+        let suffixes: Vec<&str> = std::iter::once("")
+            .chain(
+                SUGGESTION_SUFFIXES
+                    .iter()
+                    .copied()
+                    .filter(|suffix| word.ends_with(suffix)),
+            )
+            .collect();
+
+        let user_words = self.user.iter().flat_map(|words| words.iter().map(String::as_str));
+        let mut found: Vec<(usize, bool, usize, String)> = Vec::new();
+        for (rank, base) in builtin_words().iter().copied().chain(user_words).enumerate() {
+            for suffix in &suffixes {
+                let candidate = inflect(base, suffix);
+                if candidate.len().abs_diff(word.len()) > max_distance {
+                    continue;
+                }
+                let distance = edit_distance(word, &candidate);
+                if distance <= max_distance {
+                    found.push((distance, !suffix.is_empty(), rank, candidate));
+                }
+            }
+        }
+        found.sort();
+
+        let mut seen = HashSet::new();
+        found
+            .into_iter()
+            .map(|(_, _, _, candidate)| candidate)
+            .filter(|candidate| seen.insert(candidate.clone()))
+            .take(MAX_SUGGESTIONS)
+            .collect()
+    }
+}
+
+/// The word and the base forms it may be a regular inflection of
+fn base_forms(word: &str) -> Vec<String> {
+    let mut forms = vec![word.to_string()];
+    for suffix in SUFFIXES {
+        let Some(stem) = word.strip_suffix(suffix) else {
+            continue;
+        };
+        if stem.len() < 2 {
+            continue;
+        }
+        if suffix.starts_with('i') && *suffix != "ing" {
+            // copies → copy, easily → easy
+            forms.push(format!("{}y", stem));
+            continue;
+        }
+        forms.push(stem.to_string());
+        // making → make
+        forms.push(format!("{}e", stem));
+        // stopped → stop
+        let bytes = stem.as_bytes();
+        if bytes.len() >= 3 && bytes[bytes.len() - 1] == bytes[bytes.len() - 2] {
+            forms.push(stem[..stem.len() - 1].to_string());
+        }
+    }
+    forms
+}
+
+/// A dictionary word with a suffix attached (make + ing → making)
+fn inflect(base: &str, suffix: &str) -> String {
+    if suffix.is_empty() {
+        return base.to_string();
+    }
+    if let Some(stem) = base.strip_suffix('y') {
+        let consonant = stem.chars().last().is_some_and(|c| !"aeiou".contains(c));
+        if consonant && suffix != "ing" {
+            // copy + s → copies, easy + ly → easily
+            let suffix = if suffix == "s" { "es" } else { suffix };
+            return format!("{}i{}", stem, suffix);
+        }
+    }
+    if let Some(stem) = base.strip_suffix('e') {
+        if suffix.starts_with(['e', 'i']) {
+            return format!("{}{}", stem, suffix);
+        }
+    }
+    format!("{}{}", base, suffix)
+}
+
+/// Edit distance counting insertions, deletions, substitutions and
+/// transpositions of adjacent characters
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut rows = vec![(0..=b.len()).collect::<Vec<usize>>()];
+    for i in 1..=a.len() {
+        let mut row = vec![i; b.len() + 1];
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            row[j] = (rows[i - 1][j] + 1).min(row[j - 1] + 1).min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                row[j] = row[j].min(rows[i - 2][j - 2] + 1);
+            }
+        }
+        rows.push(row);
+    }
+    rows[a.len()][b.len()]
+}
+
+fn is_japanese(c: char) -> bool {
+    matches!(
+        CharKind::of(c),
+        CharKind::Hiragana | CharKind::Katakana | CharKind::Kanji
+    )
+}
+
+/// Byte ranges of inline code between backticks on a line
+fn code_spans(text: &str) -> Vec<Range<usize>> {
+    let mut spans = Vec::new();
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        let ticks: Vec<usize> = line.match_indices('`').map(|(i, _)| offset + i).collect();
+        spans.extend(ticks.chunks_exact(2).map(|pair| pair[0]..pair[1] + 1));
+        offset += line.len();
+    }
+    spans
+}
+
+/// Byte ranges of the lowercase English words to check: words in sentences
+/// containing Japanese, outside inline code, and not part of an identifier,
+/// path or URL
+pub fn english_words(text: &str) -> Vec<Range<usize>> {
+    let code = code_spans(text);
+    let mut words = Vec::new();
+    for sentence in split_sentences(text) {
+        if !sentence.text.chars().any(is_japanese) {
+            continue;
+        }
+
+        // Runs of ASCII characters without spaces, e.g. `cofig`, `e.g.` or `src/main.rs`
+        let mut chunks = Vec::new();
+        let mut start = None;
+        for (i, c) in sentence.text.char_indices().chain([(sentence.text.len(), ' ')]) {
+            match (c.is_ascii_graphic(), start) {
+                (true, None) => start = Some(i),
+                (false, Some(s)) => {
+                    chunks.push(sentence.start + s..sentence.start + i);
+                    start = None;
+                }
+                _ => {}
+            }
+        }
+
+        for chunk in chunks {
+            if code.iter().any(|span| span.start < chunk.end && chunk.start < span.end) {
+                continue;
+            }
+            let raw = &text[chunk.clone()];
+            let word = raw.trim_start_matches(|c| "\"'([{<".contains(c));
+            let leading = raw.len() - word.len();
+            let word = word.trim_end_matches(|c| "\"'.,:;!?)]}>".contains(c));
+            let valid = word
+                .split('-')
+                .all(|part| !part.is_empty() && part.bytes().all(|b| b.is_ascii_alphabetic()));
+            if !valid {
+                continue;
+            }
+
+            let mut offset = chunk.start + leading;
+            for part in word.split('-') {
+                if part.len() >= MIN_WORD_LEN && part.bytes().all(|b| b.is_ascii_lowercase()) {
+                    words.push(offset..offset + part.len());
+                }
+                offset += part.len() + 1;
+            }
+        }
+    }
+    words
+}
+
+/// Text rule for english-spelling, with the user dictionaries loaded by the checker
+pub(crate) fn english_spelling(ctx: &TextContext, _config: &CheckerConfig) -> Vec<Diagnostic> {
+    check(ctx.text, ctx.index, &Lexicon::new(&ctx.english_words))
+}
+
+/// Misspelled English words, with the closest dictionary words as fixes
+pub fn check(text: &str, index: &LineIndex, lexicon: &Lexicon) -> Vec<Diagnostic> {
+    let mut suggestions: HashMap<&str, Option<Vec<String>>> = HashMap::new();
+    let mut diagnostics = Vec::new();
+    for range in english_words(text) {
+        let word = &text[range.clone()];
+        let found = suggestions
+            .entry(word)
+            .or_insert_with(|| (!lexicon.is_known(word)).then(|| lexicon.suggestions(word)));
+        let Some(found) = found else {
+            continue;
+        };
+
+        let message = match found.first() {
+            Some(best) => format!(
+                "英単語「{}」は辞書にありません。「{}」の誤りではありませんか？",
+                word, best
+            ),
+            None => format!("英単語「{}」は辞書にありません。スペルを確認してください。", word),
+        };
+        let range = lsp_types::Range::new(index.position(range.start), index.position(range.end));
+        diagnostics.push(Diagnostic {
+            range,
+            severity: rules::find_rule(RULE_ID).map(|rule| rule.severity),
+            code: Some(NumberOrString::String(RULE_ID.to_string())),
+            source: Some("mozuku".to_string()),
+            message,
+            data: fix_data(found.iter().map(|word| Fix::new(range, word.clone())).collect()),
+            ..Default::default()
+        });
+    }
+    diagnostics
+}
+
+/// Load a user dictionary: one word per line, `#` starts a comment line
+pub fn load_file(path: &Path) -> Result<HashSet<String>> {
+    let content = std::fs::read_to_string(path).with_context(|| format!("{} を読み込めません", path.display()))?;
+    Ok(content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_lowercase)
+        .collect())
+}
+
+/// User dictionaries loaded on first use and reloaded when they change
+#[derive(Default)]
+pub struct UserDictionaries {
+    files: Mutex<HashMap<PathBuf, CachedFile>>,
+}
+
+struct CachedFile {
+    modified: Option<SystemTime>,
+    words: Arc<HashSet<String>>,
+}
+
+impl UserDictionaries {
+    /// The words of the given files. A file that cannot be loaded is logged
+    /// once per modification and contributes no words.
+    pub fn words(&self, paths: &[PathBuf]) -> Vec<Arc<HashSet<String>>> {
+        let mut files = self.files.lock().unwrap();
+        paths
+            .iter()
+            .map(|path| {
+                let modified = std::fs::metadata(path).and_then(|meta| meta.modified()).ok();
+                if let Some(cached) = files.get(path) {
+                    if cached.modified == modified {
+                        return cached.words.clone();
+                    }
+                }
+
+                let words = Arc::new(load_file(path).unwrap_or_else(|e| {
+                    tracing::warn!("Failed to load English dictionary: {:#}", e);
+                    HashSet::new()
+                }));
+                files.insert(
+                    path.clone(),
+                    CachedFile {
+                        modified,
+                        words: words.clone(),
+                    },
+                );
+                words
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(text: &str) -> Vec<&str> {
+        english_words(text).into_iter().map(|range| &text[range]).collect()
+    }
+
+    fn check_text(text: &str) -> Vec<Diagnostic> {
+        check(text, &LineIndex::new(text), &Lexicon::new(&[]))
+    }
+
+    #[test]
+    fn test_english_words_in_japanese_sentences() {
+        assert_eq!(words("設定ファイルの cofig を開く。"), vec!["cofig"]);
+        assert_eq!(words("well-known な方法。"), vec!["well", "known"]);
+        assert_eq!(words("これは（sample）です。"), vec!["sample"]);
+        // English sentences are not checked
+        assert!(words("This is an exmple.").is_empty());
+    }
+
+    #[test]
+    fn test_english_words_skip_identifiers() {
+        assert!(words("GitHub の API と getValue を使う。").is_empty());
+        assert!(words("user_id と utf8 と src/main.rs と --verbose を指定する。").is_empty());
+        assert!(words("`cofig` を https://example.com に置く。").is_empty());
+        assert!(words("e.g. や don't は対象外。").is_empty());
+    }
+
+    #[test]
+    fn test_is_known_inflections() {
+        let lexicon = Lexicon::new(&[]);
+        for word in [
+            "tests",
+            "testing",
+            "used",
+            "making",
+            "stopped",
+            "copies",
+            "easily",
+            "reinstall",
+            "unsupported",
+        ] {
+            assert!(lexicon.is_known(word), "{}", word);
+        }
+        assert!(!lexicon.is_known("cofig"));
+        assert!(!lexicon.is_known("teh"));
+    }
+
+    #[test]
+    fn test_suggestions() {
+        let lexicon = Lexicon::new(&[]);
+        assert_eq!(lexicon.suggestions("teh").first().map(String::as_str), Some("the"));
+        assert_eq!(
+            lexicon.suggestions("recieve").first().map(String::as_str),
+            Some("receive")
+        );
+        assert_eq!(
+            lexicon.suggestions("recieved").first().map(String::as_str),
+            Some("received")
+        );
+        assert_eq!(lexicon.suggestions("cofig").first().map(String::as_str), Some("config"));
+        assert!(lexicon.suggestions("xqzjvkw").is_empty());
+    }
+
+    #[test]
+    fn test_inflect() {
+        assert_eq!(inflect("make", "ing"), "making");
+        assert_eq!(inflect("copy", "s"), "copies");
+        assert_eq!(inflect("copy", "ed"), "copied");
+        assert_eq!(inflect("copy", "ing"), "copying");
+        assert_eq!(inflect("play", "ed"), "played");
+        assert_eq!(inflect("test", "ed"), "tested");
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("teh", "the"), 1);
+        assert_eq!(edit_distance("cofig", "config"), 1);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+    }
+
+    #[test]
+    fn test_check_with_fixes() {
+        let diagnostics = check_text("設定ファイルの cofig を開く。");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].range.start.character, 8);
+        assert!(diagnostics[0].message.contains("config"));
+        let fixes = crate::checker::diagnostic_fixes(&diagnostics[0]);
+        assert_eq!(fixes[0].new_text, "config");
+
+        assert!(check_text("テストの結果を review する。").is_empty());
+    }
+
+    #[test]
+    fn test_user_dictionary() {
+        let path = std::env::temp_dir().join(format!("mozuku-english-{}.txt", std::process::id()));
+        std::fs::write(&path, "# 社内用語\nkubectl\n").unwrap();
+
+        let dictionaries = UserDictionaries::default();
+        let user = dictionaries.words(&[path]);
+        let text = "kubectl で確認する。";
+        assert!(check(text, &LineIndex::new(text), &Lexicon::new(&user)).is_empty());
+        assert_eq!(check_text(text).len(), 1);
+
+        std::fs::remove_file(&path).ok();
+    }
+}
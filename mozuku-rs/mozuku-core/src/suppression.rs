@@ -0,0 +1,231 @@
+//! Inline suppression directives
+//!
+//! Directives are written in comments of the checked file:
+//!
+//! - `mozuku-disable-next-line [rule-id ...]` suppresses the following line
+//! - `mozuku-disable-line [rule-id ...]` suppresses the line itself
+//! - `mozuku-disable [rule-id ...]` … `mozuku-enable` suppresses a block
+//!
+//! Without rule IDs, all rules are suppressed. textlint directives
+//! (`textlint-disable` etc.) are accepted too, with textlint rule names mapped
+//! to MoZuku rule IDs.
+
+use std::ops::Range;
+
+use lsp_types::{Diagnostic, NumberOrString};
+
+use crate::extractor::FileType;
+use crate::textlint;
+
+/// Directive suppressing the line after the comment
+pub const DISABLE_NEXT_LINE: &str = "mozuku-disable-next-line";
+
+const DIRECTIVE_PREFIX: &str = "mozuku-";
+
+const TEXTLINT_DIRECTIVE_PREFIX: &str = "textlint-";
+
+/// A range of lines where some (or all) rules are suppressed
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Suppression {
+    /// Suppressed lines (0-based, end exclusive)
+    lines: Range<u32>,
+    /// Suppressed rule IDs; empty means all rules
+    rules: Vec<String>,
+}
+
+impl Suppression {
+    fn covers(&self, line: u32, rule_id: Option<&str>) -> bool {
+        self.lines.contains(&line)
+            && (self.rules.is_empty() || rule_id.is_some_and(|id| self.rules.iter().any(|r| r == id)))
+    }
+}
+
+/// Suppression directives found in a document
+#[derive(Debug, Default)]
+pub struct Suppressions {
+    suppressions: Vec<Suppression>,
+}
+
+impl Suppressions {
+    /// Collect the directives of a document
+    pub fn parse(text: &str) -> Self {
+        let mut suppressions = Vec::new();
+        let mut open_block: Option<(u32, Vec<String>)> = None;
+
+        for (line, content) in (0u32..).zip(text.lines()) {
+            let Some((directive, rules)) = parse_directive(content) else {
+                continue;
+            };
+
+            match directive {
+                "disable-next-line" => suppressions.push(Suppression {
+                    lines: line + 1..line + 2,
+                    rules,
+                }),
+                "disable-line" => suppressions.push(Suppression {
+                    lines: line..line + 1,
+                    rules,
+                }),
+                "disable" => {
+                    if let Some((start, rules)) = open_block.take() {
+                        suppressions.push(Suppression { lines: start..line, rules });
+                    }
+                    open_block = Some((line, rules));
+                }
+                "enable" => {
+                    if let Some((start, rules)) = open_block.take() {
+                        suppressions.push(Suppression { lines: start..line, rules });
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // An unterminated block runs to the end of the document
+        if let Some((start, rules)) = open_block {
+            suppressions.push(Suppression {
+                lines: start..u32::MAX,
+                rules,
+            });
+        }
+
+        Self { suppressions }
+    }
+
+    /// Check whether a diagnostic is suppressed by a directive
+    pub fn is_suppressed(&self, diagnostic: &Diagnostic) -> bool {
+        let rule_id = match &diagnostic.code {
+            Some(NumberOrString::String(code)) => Some(code.as_str()),
+            _ => None,
+        };
+        self.suppressions
+            .iter()
+            .any(|s| s.covers(diagnostic.range.start.line, rule_id))
+    }
+}
+
+/// Parse a directive on a line into its name (without prefix) and rule IDs
+fn parse_directive(line: &str) -> Option<(&str, Vec<String>)> {
+    let (start, is_textlint) = [(DIRECTIVE_PREFIX, false), (TEXTLINT_DIRECTIVE_PREFIX, true)]
+        .into_iter()
+        .filter_map(|(prefix, is_textlint)| line.find(prefix).map(|i| (i + prefix.len(), is_textlint)))
+        .min()?;
+    let mut words = line[start..]
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|w| !w.is_empty());
+
+    let directive = words.next()?;
+    let rules = words
+        .take_while(|w| w.chars().all(|c| c.is_ascii_alphanumeric() || "-_/@".contains(c)))
+        .map(|w| match is_textlint {
+            true => textlint::rule_id(w).unwrap_or(w),
+            false => w,
+        })
+        .map(str::to_string)
+        .collect();
+
+    Some((directive, rules))
+}
+
+/// Wrap a directive in the line comment syntax of a file type.
+/// Returns `None` for file types without comments.
+pub fn directive_comment(file_type: FileType, directive: &str) -> Option<String> {
+    match file_type {
+        FileType::Markdown => Some(format!("<!-- {} -->", directive)),
+        FileType::Rust
+        | FileType::TypeScript
+        | FileType::JavaScript
+        | FileType::C
+        | FileType::Cpp
+        | FileType::Go => Some(format!("// {}", directive)),
+        FileType::Python => Some(format!("# {}", directive)),
+        FileType::LaTeX => Some(format!("% {}", directive)),
+        FileType::PlainText => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lsp_types::{Position, Range as LspRange};
+
+    fn diagnostic(line: u32, code: &str) -> Diagnostic {
+        Diagnostic {
+            range: LspRange {
+                start: Position { line, character: 0 },
+                end: Position { line, character: 1 },
+            },
+            code: Some(NumberOrString::String(code.to_string())),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_disable_next_line() {
+        let suppressions = Suppressions::parse("<!-- mozuku-disable-next-line -->\n見れる\n見れる\n");
+
+        assert!(suppressions.is_suppressed(&diagnostic(1, "ra-nuki")));
+        assert!(!suppressions.is_suppressed(&diagnostic(2, "ra-nuki")));
+    }
+
+    #[test]
+    fn test_disable_next_line_with_rules() {
+        let suppressions = Suppressions::parse("// mozuku-disable-next-line ra-nuki, i-nuki\n// 見れる\n");
+
+        assert!(suppressions.is_suppressed(&diagnostic(1, "ra-nuki")));
+        assert!(suppressions.is_suppressed(&diagnostic(1, "i-nuki")));
+        assert!(!suppressions.is_suppressed(&diagnostic(1, "double-particle")));
+    }
+
+    #[test]
+    fn test_disable_line_in_markdown_comment() {
+        let suppressions = Suppressions::parse("見れる <!-- mozuku-disable-line ra-nuki -->\n");
+
+        assert!(suppressions.is_suppressed(&diagnostic(0, "ra-nuki")));
+        assert!(!suppressions.is_suppressed(&diagnostic(0, "i-nuki")));
+    }
+
+    #[test]
+    fn test_disable_enable_block() {
+        let text = "a\n# mozuku-disable\nb\nc\n# mozuku-enable\nd\n% mozuku-disable consecutive-no\ne\n";
+        let suppressions = Suppressions::parse(text);
+
+        assert!(!suppressions.is_suppressed(&diagnostic(0, "ra-nuki")));
+        assert!(suppressions.is_suppressed(&diagnostic(2, "ra-nuki")));
+        assert!(suppressions.is_suppressed(&diagnostic(3, "ra-nuki")));
+        assert!(!suppressions.is_suppressed(&diagnostic(5, "ra-nuki")));
+        assert!(suppressions.is_suppressed(&diagnostic(7, "consecutive-no")));
+        assert!(!suppressions.is_suppressed(&diagnostic(7, "ra-nuki")));
+    }
+
+    #[test]
+    fn test_textlint_directives() {
+        let text = "<!-- textlint-disable ja-technical-writing/no-doubled-joshi -->\n私はは\n<!-- textlint-enable -->\n私はは\n";
+        let suppressions = Suppressions::parse(text);
+
+        assert!(suppressions.is_suppressed(&diagnostic(1, "double-particle")));
+        assert!(!suppressions.is_suppressed(&diagnostic(1, "ra-nuki")));
+        assert!(!suppressions.is_suppressed(&diagnostic(3, "double-particle")));
+    }
+
+    #[test]
+    fn test_directive_comment() {
+        assert_eq!(
+            directive_comment(FileType::Markdown, DISABLE_NEXT_LINE).as_deref(),
+            Some("<!-- mozuku-disable-next-line -->")
+        );
+        assert_eq!(
+            directive_comment(FileType::Python, "mozuku-disable-next-line ra-nuki").as_deref(),
+            Some("# mozuku-disable-next-line ra-nuki")
+        );
+        assert_eq!(directive_comment(FileType::PlainText, DISABLE_NEXT_LINE), None);
+    }
+
+    #[test]
+    fn test_directive_comment_is_parsed() {
+        let comment = directive_comment(FileType::Markdown, &format!("{} ra-nuki", DISABLE_NEXT_LINE)).unwrap();
+        let suppressions = Suppressions::parse(&format!("{}\n見れる", comment));
+
+        assert!(suppressions.is_suppressed(&diagnostic(1, "ra-nuki")));
+    }
+}
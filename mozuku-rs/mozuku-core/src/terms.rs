@@ -0,0 +1,349 @@
+//! Workspace-wide term consistency
+//!
+//! Collects the terms used in each file of the workspace and finds files
+//! writing a term differently from the rest of the workspace: katakana nouns
+//! with and without a trailing long vowel mark (サーバ / サーバー) and the
+//! synonyms grouped in `[checker] term_groups` (ログイン / サインイン).
+//! The server keeps a [`TermIndex`] of all files, updated as documents change.
+
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Range as ByteRange;
+use std::path::Path;
+
+use lsp_types::{Diagnostic, DiagnosticRelatedInformation, Location, NumberOrString, Range, Url};
+
+use crate::analyzer::{MorphologicalAnalyzer, TokenInfo};
+use crate::checker::{fix_data, Fix};
+use crate::config::CheckerConfig;
+use crate::dictionary::{find_term, CharKind};
+use crate::extractor::{FileType, TextExtractor};
+use crate::utf16::LineIndex;
+
+/// Rule ID of workspace term consistency diagnostics
+pub const RULE_ID: &str = "term-consistency";
+
+/// Terms that should be written the same way across the workspace
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum TermGroup {
+    /// Katakana nouns sharing a stem without trailing ー
+    Katakana(String),
+    /// A group of `[checker] term_groups`, by its first term
+    Synonyms(String),
+}
+
+/// A term found in a text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TermOccurrence {
+    pub group: TermGroup,
+    pub term: String,
+    /// Byte range in the text
+    pub range: ByteRange<usize>,
+}
+
+impl TermOccurrence {
+    /// Move the occurrence by `offset` bytes, from a text span to its document
+    pub fn offset(self, offset: usize) -> Self {
+        Self {
+            range: self.range.start + offset..self.range.end + offset,
+            ..self
+        }
+    }
+}
+
+/// Collect the terms of an analyzed text
+pub fn collect_terms(text: &str, tokens: &[TokenInfo], term_groups: &[Vec<String>]) -> Vec<TermOccurrence> {
+    let mut occurrences: Vec<TermOccurrence> = tokens
+        .iter()
+        .filter(|token| token.pos == "名詞" && token.surface.chars().all(|c| CharKind::of(c) == CharKind::Katakana))
+        .filter_map(|token| {
+            let stem = token.surface.trim_end_matches('ー');
+            (stem.chars().count() >= 2).then(|| TermOccurrence {
+                group: TermGroup::Katakana(stem.to_string()),
+                term: token.surface.clone(),
+                range: token.byte_offset..token.byte_offset + token.surface.len(),
+            })
+        })
+        .collect();
+
+    for group in term_groups.iter().filter(|group| group.len() >= 2) {
+        for term in group {
+            occurrences.extend(find_term(text, term).into_iter().map(|offset| TermOccurrence {
+                group: TermGroup::Synonyms(group[0].clone()),
+                term: term.clone(),
+                range: offset..offset + term.len(),
+            }));
+        }
+    }
+    occurrences
+}
+
+/// Collect the terms of a file on disk, extracting its prose. Returns `None`
+/// if the file cannot be read or is not checked under the configuration.
+pub fn read_file_terms(
+    path: &Path,
+    config: &CheckerConfig,
+    extractor: &TextExtractor,
+    analyzer: &MorphologicalAnalyzer,
+) -> Option<FileTerms> {
+    let file_type = FileType::from_extension(path.extension()?.to_str()?);
+    if !config.is_file_type_enabled(file_type) {
+        return None;
+    }
+    let content = std::fs::read_to_string(path).ok()?;
+    let text = config.analysis_text(&content)?;
+
+    let spans = extractor.extract(text, file_type).unwrap_or_default();
+    let occurrences = spans
+        .iter()
+        .flat_map(|span| {
+            let tokens = analyzer.tokenize(&span.text);
+            collect_terms(&span.text, &tokens, &config.term_groups)
+                .into_iter()
+                .map(|occurrence| occurrence.offset(span.start_byte))
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    Some(FileTerms::new(&content, occurrences))
+}
+
+/// A term found in a file of the workspace
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct IndexedTerm {
+    group: TermGroup,
+    term: String,
+    range: Range,
+}
+
+/// Terms used in a file
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FileTerms {
+    terms: Vec<IndexedTerm>,
+}
+
+impl FileTerms {
+    /// Terms found in a document, with byte ranges in `text`
+    pub fn new(text: &str, mut occurrences: Vec<TermOccurrence>) -> Self {
+        // Spans may nest, so the same occurrence can be found twice
+        occurrences.sort_by_key(|occurrence| (occurrence.range.start, occurrence.range.end));
+        occurrences.dedup();
+
+        let index = LineIndex::new(text);
+        let terms = occurrences
+            .into_iter()
+            .map(|occurrence| IndexedTerm {
+                range: Range::new(
+                    index.position(occurrence.range.start),
+                    index.position(occurrence.range.end),
+                ),
+                group: occurrence.group,
+                term: occurrence.term,
+            })
+            .collect();
+        Self { terms }
+    }
+
+    /// The distinct forms used, in order
+    fn forms(&self) -> Vec<(&TermGroup, &str)> {
+        let mut forms: Vec<_> = self
+            .terms
+            .iter()
+            .map(|term| (&term.group, term.term.as_str()))
+            .collect();
+        forms.sort();
+        forms.dedup();
+        forms
+    }
+}
+
+/// Terms used in the files of the workspace
+#[derive(Debug, Default)]
+pub struct TermIndex {
+    files: HashMap<Url, FileTerms>,
+}
+
+impl TermIndex {
+    /// Replace the terms of a file. Returns whether the forms used in the
+    /// file changed, which may change the diagnostics of other files.
+    pub fn update(&mut self, uri: &Url, terms: FileTerms) -> bool {
+        let changed = self.files.get(uri).map_or(Vec::new(), FileTerms::forms) != terms.forms();
+        if terms.terms.is_empty() {
+            self.files.remove(uri);
+        } else {
+            self.files.insert(uri.clone(), terms);
+        }
+        changed
+    }
+
+    /// Forget a file. Returns whether it used any terms.
+    pub fn remove(&mut self, uri: &Url) -> bool {
+        self.files.remove(uri).is_some()
+    }
+
+    /// Keep only the files for which `keep` returns true
+    pub fn retain(&mut self, mut keep: impl FnMut(&Url) -> bool) {
+        self.files.retain(|uri, _| keep(uri));
+    }
+
+    /// The form of each term used by the workspace: the one used in the most
+    /// files, then the most often. Ties go to the first term of a synonym
+    /// group and to katakana nouns with ー.
+    fn preferred_terms(&self) -> HashMap<&TermGroup, (&str, usize)> {
+        // Number of files and occurrences of each form
+        let mut counts: BTreeMap<(&TermGroup, &str), (usize, usize)> = BTreeMap::new();
+        for file in self.files.values() {
+            for term in &file.terms {
+                counts.entry((&term.group, term.term.as_str())).or_default().1 += 1;
+            }
+            for form in file.forms() {
+                counts.entry(form).or_default().0 += 1;
+            }
+        }
+
+        let mut preferred: HashMap<&TermGroup, (&str, (usize, usize))> = HashMap::new();
+        let mut forms: HashMap<&TermGroup, usize> = HashMap::new();
+        for ((group, term), count) in counts {
+            *forms.entry(group).or_default() += 1;
+            let rank = |term: &str, count: (usize, usize)| {
+                let is_head = matches!(group, TermGroup::Synonyms(head) if head == term);
+                (count, is_head, term.ends_with('ー'), Reverse(term.to_string()))
+            };
+            match preferred.get(group) {
+                Some(&(best, best_count)) if rank(best, best_count) >= rank(term, count) => {}
+                _ => {
+                    preferred.insert(group, (term, count));
+                }
+            }
+        }
+
+        // Only terms written in more than one way need to be unified
+        preferred
+            .into_iter()
+            .filter(|(group, _)| forms[group] > 1)
+            .map(|(group, (term, (files, _)))| (group, (term, files)))
+            .collect()
+    }
+
+    /// Diagnostics on the terms of a file written differently from the rest
+    /// of the workspace. Katakana nouns already written both ways within the
+    /// file are left to the notation-variance rule.
+    pub fn diagnostics(&self, uri: &Url, config: &CheckerConfig) -> Vec<Diagnostic> {
+        let Some(terms) = self.files.get(uri).map(|file| &file.terms) else {
+            return Vec::new();
+        };
+        let preferred = self.preferred_terms();
+
+        terms
+            .iter()
+            .filter_map(|term| {
+                let &(preferred_term, files) = preferred.get(&term.group)?;
+                if term.term == preferred_term {
+                    return None;
+                }
+                if matches!(term.group, TermGroup::Katakana(_))
+                    && terms.iter().any(|t| t.group == term.group && t.term == preferred_term)
+                {
+                    return None;
+                }
+                let (other_uri, other) = self.first_use(&term.group, preferred_term, uri)?;
+
+                Some(Diagnostic {
+                    range: term.range,
+                    severity: config.rule_severity(RULE_ID),
+                    code: Some(NumberOrString::String(RULE_ID.to_string())),
+                    source: Some("mozuku".to_string()),
+                    message: format!(
+                        "ワークスペース内で用語の表記が統一されていません。「{}」→「{}」（{}ファイルで使用）",
+                        term.term, preferred_term, files
+                    ),
+                    related_information: Some(vec![DiagnosticRelatedInformation {
+                        location: Location {
+                            uri: other_uri.clone(),
+                            range: other.range,
+                        },
+                        message: format!("「{}」の使用箇所", preferred_term),
+                    }]),
+                    data: fix_data(vec![Fix::new(term.range, preferred_term)]),
+                    ..Default::default()
+                })
+            })
+            .collect()
+    }
+
+    /// First use of a term in a file other than `uri`, in URI order
+    fn first_use(&self, group: &TermGroup, term: &str, uri: &Url) -> Option<(&Url, &IndexedTerm)> {
+        self.files
+            .iter()
+            .filter(|(file, _)| *file != uri)
+            .filter_map(|(file, terms)| {
+                terms
+                    .terms
+                    .iter()
+                    .find(|t| t.group == *group && t.term == term)
+                    .map(|t| (file, t))
+            })
+            .min_by(|(a, _), (b, _)| a.as_str().cmp(b.as_str()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file_terms(text: &str, terms: &[(&str, &str)]) -> FileTerms {
+        let occurrences = terms
+            .iter()
+            .map(|(group, term)| {
+                let start = text.find(term).unwrap();
+                TermOccurrence {
+                    group: TermGroup::Synonyms(group.to_string()),
+                    term: term.to_string(),
+                    range: start..start + term.len(),
+                }
+            })
+            .collect();
+        FileTerms::new(text, occurrences)
+    }
+
+    fn url(name: &str) -> Url {
+        Url::parse(&format!("file:///work/{}", name)).unwrap()
+    }
+
+    #[test]
+    fn test_collect_synonyms() {
+        let groups = vec![vec!["ログイン".to_string(), "サインイン".to_string()]];
+        let found = collect_terms("サインインした後にログインする", &[], &groups);
+
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].term, "ログイン");
+        assert_eq!(found[1].group, TermGroup::Synonyms("ログイン".to_string()));
+        assert_eq!(found[1].range, 0.."サインイン".len());
+    }
+
+    #[test]
+    fn test_diagnostics_across_files() {
+        let mut index = TermIndex::default();
+        let a = "ログインする";
+        let b = "ログインできない";
+        let c = "サインインする";
+        assert!(index.update(&url("a.md"), file_terms(a, &[("ログイン", "ログイン")])));
+        index.update(&url("b.md"), file_terms(b, &[("ログイン", "ログイン")]));
+        index.update(&url("c.md"), file_terms(c, &[("ログイン", "サインイン")]));
+
+        assert!(index.diagnostics(&url("a.md"), &CheckerConfig::default()).is_empty());
+        let diagnostics = index.diagnostics(&url("c.md"), &CheckerConfig::default());
+        assert_eq!(diagnostics.len(), 1);
+        assert!(diagnostics[0]
+            .message
+            .contains("「サインイン」→「ログイン」（2ファイルで使用）"));
+        let related = &diagnostics[0].related_information.as_ref().unwrap()[0];
+        assert_eq!(related.location.uri, url("a.md"));
+
+        // The same terms again do not change other files
+        assert!(!index.update(&url("a.md"), file_terms(a, &[("ログイン", "ログイン")])));
+        assert!(index.remove(&url("b.md")));
+        // On a tie, the first term of the group is preferred
+        assert!(index.diagnostics(&url("a.md"), &CheckerConfig::default()).is_empty());
+        assert_eq!(index.diagnostics(&url("c.md"), &CheckerConfig::default()).len(), 1);
+    }
+}
@@ -0,0 +1,636 @@
+//! textlint compatibility
+//!
+//! Projects moving from textlint keep their configuration and suppression
+//! comments: rule names of textlint-rule-preset-ja-technical-writing map to
+//! MoZuku rules, the `rules` of `.textlintrc` become `[checker]` settings and
+//! `textlint-disable` comments suppress the corresponding rules (see
+//! [`crate::suppression`]). Rules of the preset without a MoZuku counterpart
+//! are implemented here under their textlint names.
+
+use std::collections::{BTreeMap, HashSet};
+use std::ops::Range;
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use lsp_types::{Diagnostic, NumberOrString};
+use serde_json::{Map, Value};
+
+use crate::analyzer::{split_sentences, SentenceBoundary, TokenInfo};
+use crate::checker::{fix_data, Fix};
+use crate::config::{CheckerConfig, TextlintConfig, WritingStyle};
+use crate::dictionary::CharKind;
+use crate::rules;
+use crate::utf16::LineIndex;
+
+/// Names of textlint-rule-preset-ja-technical-writing in `.textlintrc`
+const PRESET_NAMES: &[&str] = &["preset-ja-technical-writing", "ja-technical-writing"];
+
+/// Rules of the preset supported by MoZuku: textlint name and MoZuku rule ID
+const PRESET_RULES: &[(&str, &str)] = &[
+    ("sentence-length", "sentence-length"),
+    ("max-ten", "max-ten"),
+    ("max-kanji-continuous-len", "max-kanji-continuous-len"),
+    ("no-mix-dearu-desumasu", "no-mix-dearu-desumasu"),
+    ("no-dropping-the-ra", "ra-nuki"),
+    (
+        "no-doubled-conjunctive-particle-ga",
+        "no-doubled-conjunctive-particle-ga",
+    ),
+    ("no-doubled-conjunction", "no-doubled-conjunction"),
+    ("no-doubled-joshi", "double-particle"),
+    ("no-hankaku-kana", "no-hankaku-kana"),
+    ("ja-no-weak-phrase", "ja-no-weak-phrase"),
+    ("ja-no-redundant-expression", "redundant-expression"),
+    ("no-zero-width-spaces", "no-zero-width-spaces"),
+];
+
+/// Rule options stored in `[checker]` settings: textlint name, option, setting key
+const RULE_OPTIONS: &[(&str, &str, &str)] = &[
+    ("sentence-length", "max", "max_sentence_length"),
+    ("max-ten", "max", "max_ten_count"),
+    ("max-kanji-continuous-len", "max", "max_kanji_length"),
+    ("no-mix-dearu-desumasu", "preferInBody", "preferred_style"),
+];
+
+/// Rule name without the package prefix (`textlint-rule-`) and preset
+/// (`ja-technical-writing/`)
+fn normalize(name: &str) -> &str {
+    let name = name.rsplit('/').next().unwrap_or(name);
+    name.strip_prefix("textlint-rule-").unwrap_or(name)
+}
+
+fn is_preset(name: &str) -> bool {
+    let name = name.strip_prefix("textlint-rule-").unwrap_or(name);
+    PRESET_NAMES.contains(&name)
+}
+
+/// The MoZuku rule implementing a textlint rule
+pub fn rule_id(name: &str) -> Option<&'static str> {
+    let name = normalize(name);
+    PRESET_RULES
+        .iter()
+        .find(|(textlint, _)| *textlint == name)
+        .map(|(_, rule_id)| *rule_id)
+}
+
+/// Rules of `[checker.textlint]`: those of `.textlintrc`, then the inline ones
+pub fn merged_rules(config: &TextlintConfig, base: &Path) -> Result<Map<String, Value>> {
+    let mut rules = match &config.textlintrc {
+        Some(path) => load_textlintrc(&base.join(path))?,
+        None => Map::new(),
+    };
+    rules.extend(config.rules.clone());
+    Ok(rules)
+}
+
+/// The `rules` of a `.textlintrc` file (JSON or YAML)
+fn load_textlintrc(path: &Path) -> Result<Map<String, Value>> {
+    let content = std::fs::read_to_string(path).with_context(|| format!("{} を読み込めません", path.display()))?;
+    let textlintrc: Value =
+        serde_yaml::from_str(&content).with_context(|| format!("{} の解析に失敗しました", path.display()))?;
+    match textlintrc.get("rules") {
+        Some(Value::Object(rules)) => Ok(rules.clone()),
+        _ => Ok(Map::new()),
+    }
+}
+
+/// Configured textlint rules MoZuku does not implement
+pub fn unsupported_rules(rules: &Map<String, Value>) -> Vec<String> {
+    let preset_overrides = rules
+        .iter()
+        .filter(|(name, _)| is_preset(name))
+        .filter_map(|(_, value)| value.as_object())
+        .flat_map(|overrides| overrides.keys());
+    rules
+        .keys()
+        .filter(|name| !is_preset(name))
+        .chain(preset_overrides)
+        .filter(|name| rule_id(name).is_none())
+        .cloned()
+        .collect()
+}
+
+/// `[checker]` settings equivalent to textlint rules. Rules enabled by the
+/// preset come first so that rules configured one by one take precedence.
+fn settings(rules: &Map<String, Value>) -> BTreeMap<&'static str, toml::Value> {
+    let mut settings = BTreeMap::new();
+    for (_, value) in rules.iter().filter(|(name, _)| is_preset(name)) {
+        if value == &Value::Bool(false) {
+            continue;
+        }
+        for (name, _) in PRESET_RULES {
+            add_setting(&mut settings, name, &Value::Bool(true));
+        }
+        for (name, value) in value.as_object().into_iter().flatten() {
+            add_setting(&mut settings, name, value);
+        }
+    }
+    for (name, value) in rules.iter().filter(|(name, _)| !is_preset(name)) {
+        add_setting(&mut settings, name, value);
+    }
+    settings
+}
+
+/// Add the settings of a rule configured as `true`, `false` or an options object
+fn add_setting(settings: &mut BTreeMap<&'static str, toml::Value>, name: &str, value: &Value) {
+    let Some(key) = rule_id(name).and_then(CheckerConfig::rule_setting_key) else {
+        return;
+    };
+    settings.insert(key, toml::Value::Boolean(value != &Value::Bool(false)));
+
+    let Some(options) = value.as_object() else {
+        return;
+    };
+    let name = normalize(name);
+    for &(_, option, key) in RULE_OPTIONS.iter().filter(|(rule, _, _)| *rule == name) {
+        let setting = match (key, options.get(option)) {
+            ("preferred_style", Some(Value::String(style))) => match style.as_str() {
+                "ですます" => Some(toml::Value::String("desumasu".to_string())),
+                "である" => Some(toml::Value::String("dearu".to_string())),
+                _ => None,
+            },
+            (_, Some(value)) => toml::Value::try_from(value).ok(),
+            (_, None) => None,
+        };
+        if let Some(setting) = setting {
+            settings.insert(key, setting);
+        }
+    }
+}
+
+/// Turn the textlint rules of `[checker.textlint]` into `[checker]` settings,
+/// keeping the settings written explicitly
+pub fn apply(table: &mut toml::Table, base: &Path) {
+    let Some(toml::Value::Table(checker)) = table.get_mut("checker") else {
+        return;
+    };
+    let Some(textlint) = checker.get("textlint") else {
+        return;
+    };
+    let Ok(config) = textlint.clone().try_into::<TextlintConfig>() else {
+        // Reported when deserializing the whole configuration
+        return;
+    };
+    let rules = merged_rules(&config, base).unwrap_or_else(|e| {
+        tracing::warn!("Failed to load textlint rules: {:#}", e);
+        config.rules.clone()
+    });
+
+    for (key, value) in settings(&rules) {
+        checker.entry(key).or_insert(value);
+    }
+}
+
+/// Text checked by the text rules: those ported from textlint, and the
+/// notation, sentence ending and spelling rules
+pub(crate) struct TextContext<'a> {
+    pub text: &'a str,
+    pub tokens: &'a [TokenInfo],
+    pub index: &'a LineIndex<'a>,
+    pub sentences: Vec<SentenceBoundary>,
+    /// Words of `[checker] english_dictionary`, when spelling is checked
+    pub english_words: Vec<Arc<HashSet<String>>>,
+}
+
+impl<'a> TextContext<'a> {
+    pub fn new(text: &'a str, tokens: &'a [TokenInfo], index: &'a LineIndex<'a>) -> Self {
+        Self {
+            text,
+            tokens,
+            index,
+            sentences: split_sentences(text),
+            english_words: Vec::new(),
+        }
+    }
+
+    /// Tokens within a sentence
+    fn sentence_tokens(&self, sentence: &SentenceBoundary) -> &'a [TokenInfo] {
+        let start = self.tokens.partition_point(|token| token.byte_offset < sentence.start);
+        let end = self.tokens.partition_point(|token| token.byte_offset < sentence.end);
+        &self.tokens[start..end]
+    }
+
+    pub(crate) fn diagnostic(&self, rule_id: &str, range: Range<usize>, message: String) -> Diagnostic {
+        Diagnostic {
+            range: lsp_types::Range::new(self.index.position(range.start), self.index.position(range.end)),
+            severity: rules::find_rule(rule_id).map(|rule| rule.severity),
+            code: Some(NumberOrString::String(rule_id.to_string())),
+            source: Some("mozuku".to_string()),
+            message,
+            ..Default::default()
+        }
+    }
+
+    fn token_range(token: &TokenInfo) -> Range<usize> {
+        token.byte_offset..token.byte_offset + token.surface.len()
+    }
+}
+
+/// A rule run on the whole text, registered in [`rules::RULES`]
+pub(crate) type TextRule = fn(&TextContext, &CheckerConfig) -> Vec<Diagnostic>;
+
+/// Sentences longer than `max_sentence_length` characters
+pub(crate) fn sentence_length(ctx: &TextContext, config: &CheckerConfig) -> Vec<Diagnostic> {
+    let max = config.max_sentence_length;
+    ctx.sentences
+        .iter()
+        .filter_map(|sentence| {
+            let length = sentence.text.chars().count();
+            (length > max).then(|| {
+                ctx.diagnostic(
+                    "sentence-length",
+                    sentence.start..sentence.end,
+                    format!(
+                        "一文が長すぎます（{}文字、上限 {}文字）。文を分けてください。",
+                        length, max
+                    ),
+                )
+            })
+        })
+        .collect()
+}
+
+/// Sentences with more than `max_ten_count` 読点, reported at the first one too many
+pub(crate) fn max_ten(ctx: &TextContext, config: &CheckerConfig) -> Vec<Diagnostic> {
+    let max = config.max_ten_count;
+    ctx.sentences
+        .iter()
+        .filter_map(|sentence| {
+            let ten: Vec<usize> = sentence.text.match_indices(['、', '，']).map(|(i, _)| i).collect();
+            let excess = *ten.get(max)?;
+            let start = sentence.start + excess;
+            Some(ctx.diagnostic(
+                "max-ten",
+                start..start + '、'.len_utf8(),
+                format!(
+                    "一文に読点が{}個あります（上限 {}個）。文を分けてください。",
+                    ten.len(),
+                    max
+                ),
+            ))
+        })
+        .collect()
+}
+
+/// Runs of more than `max_kanji_length` kanji
+pub(crate) fn max_kanji_continuous_len(ctx: &TextContext, config: &CheckerConfig) -> Vec<Diagnostic> {
+    let max = config.max_kanji_length;
+    char_runs(ctx.text, |c| CharKind::of(c) == CharKind::Kanji)
+        .into_iter()
+        .filter_map(|run| {
+            let length = ctx.text[run.clone()].chars().count();
+            (length > max).then(|| {
+                ctx.diagnostic(
+                    "max-kanji-continuous-len",
+                    run,
+                    format!(
+                        "漢字が{}文字連続しています（上限 {}文字）。ひらがなを交えて読みやすくしてください。",
+                        length, max
+                    ),
+                )
+            })
+        })
+        .collect()
+}
+
+/// Byte ranges of the maximal runs of characters matching a predicate
+pub(crate) fn char_runs(text: &str, matches: impl Fn(char) -> bool) -> Vec<Range<usize>> {
+    let mut runs = Vec::new();
+    let mut start = None;
+    for (i, c) in text.char_indices() {
+        match (matches(c), start) {
+            (true, None) => start = Some(i),
+            (false, Some(s)) => {
+                runs.push(s..i);
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    runs.extend(start.map(|s| s..text.len()));
+    runs
+}
+
+/// The style of a sentence, the byte range of its ending (the chain of
+/// 助動詞 at the end of the sentence, before any symbols) and whether the
+/// ending follows a noun
+fn sentence_style(tokens: &[TokenInfo]) -> Option<(WritingStyle, Range<usize>, bool)> {
+    let content = tokens
+        .iter()
+        .rposition(|token| token.pos != "記号")
+        .map_or(&tokens[..0], |last| &tokens[..=last]);
+    let chain_start = content
+        .iter()
+        .rposition(|token| token.pos != "助動詞")
+        .map_or(0, |i| i + 1);
+    let chain = &content[chain_start..];
+
+    let style = if chain
+        .iter()
+        .any(|token| matches!(token.base_form.as_str(), "です" | "ます"))
+    {
+        WritingStyle::Desumasu
+    } else if chain
+        .iter()
+        .any(|token| matches!(token.base_form.as_str(), "だ" | "ある"))
+    {
+        WritingStyle::Dearu
+    } else {
+        return None;
+    };
+    let range = TextContext::token_range(chain.first()?).start..TextContext::token_range(chain.last()?).end;
+    let after_noun = chain_start > 0 && content[chain_start - 1].pos == "名詞";
+    Some((style, range, after_noun))
+}
+
+/// Copula ending of the given style replacing one of the other style, e.g.
+/// 「である」→「です」; endings after verbs would need conjugating
+fn copula_ending(ending: &str, style: WritingStyle) -> Option<&'static str> {
+    match (style, ending) {
+        (WritingStyle::Desumasu, "だ" | "である") => Some("です"),
+        (WritingStyle::Desumasu, "だった" | "であった") => Some("でした"),
+        (WritingStyle::Dearu, "です") => Some("である"),
+        (WritingStyle::Dearu, "でした") => Some("であった"),
+        _ => None,
+    }
+}
+
+/// Sentences not ending in the preferred style, or the majority style
+pub(crate) fn mix_dearu_desumasu(ctx: &TextContext, config: &CheckerConfig) -> Vec<Diagnostic> {
+    let endings: Vec<(WritingStyle, Range<usize>, bool)> = ctx
+        .sentences
+        .iter()
+        .filter_map(|sentence| sentence_style(ctx.sentence_tokens(sentence)))
+        .collect();
+
+    let preferred = config.preferred_style.unwrap_or_else(|| {
+        let dearu = endings
+            .iter()
+            .filter(|(style, _, _)| *style == WritingStyle::Dearu)
+            .count();
+        if dearu * 2 > endings.len() {
+            WritingStyle::Dearu
+        } else {
+            WritingStyle::Desumasu
+        }
+    });
+    let (expected, found) = match preferred {
+        WritingStyle::Desumasu => ("ですます", "である"),
+        WritingStyle::Dearu => ("である", "ですます"),
+    };
+
+    endings
+        .into_iter()
+        .filter(|(style, _, _)| *style != preferred)
+        .map(|(_, range, after_noun)| {
+            let replacement = after_noun
+                .then(|| copula_ending(&ctx.text[range.clone()], preferred))
+                .flatten();
+            let mut diagnostic = ctx.diagnostic(
+                "no-mix-dearu-desumasu",
+                range,
+                format!(
+                    "「{}」調の文末が混在しています。「{}」調に統一してください。",
+                    found, expected
+                ),
+            );
+            diagnostic.data = replacement.and_then(|ending| fix_data(vec![Fix::new(diagnostic.range, ending)]));
+            diagnostic
+        })
+        .collect()
+}
+
+/// Sentences with the conjunctive particle が used twice, reported at the second
+pub(crate) fn doubled_conjunctive_particle_ga(ctx: &TextContext, _config: &CheckerConfig) -> Vec<Diagnostic> {
+    ctx.sentences
+        .iter()
+        .filter_map(|sentence| {
+            let second = ctx
+                .sentence_tokens(sentence)
+                .iter()
+                .filter(|token| token.surface == "が" && token.pos == "助詞" && token.pos_detail1 == "接続助詞")
+                .nth(1)?;
+            Some(ctx.diagnostic(
+                "no-doubled-conjunctive-particle-ga",
+                TextContext::token_range(second),
+                "一文に逆接の接続助詞「が」が2回以上使われています。文を分けてください。".to_string(),
+            ))
+        })
+        .collect()
+}
+
+/// Consecutive sentences starting with the same conjunction, reported at the second
+pub(crate) fn doubled_conjunction(ctx: &TextContext, _config: &CheckerConfig) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut previous: Option<&str> = None;
+    for sentence in &ctx.sentences {
+        let conjunction = ctx
+            .sentence_tokens(sentence)
+            .iter()
+            .find(|token| token.pos != "記号")
+            .filter(|token| token.pos == "接続詞");
+        if let Some(token) = conjunction {
+            if previous == Some(token.surface.as_str()) {
+                diagnostics.push(ctx.diagnostic(
+                    "no-doubled-conjunction",
+                    TextContext::token_range(token),
+                    format!("同じ接続詞「{}」が連続して使われています。", token.surface),
+                ));
+            }
+        }
+        previous = conjunction.map(|token| token.surface.as_str());
+    }
+    diagnostics
+}
+
+/// Half-width katakana (U+FF66..=U+FF9D) and their full-width forms
+const HANKAKU: &str = "ｦｧｨｩｪｫｬｭｮｯｰｱｲｳｴｵｶｷｸｹｺｻｼｽｾｿﾀﾁﾂﾃﾄﾅﾆﾇﾈﾉﾊﾋﾌﾍﾎﾏﾐﾑﾒﾓﾔﾕﾖﾗﾘﾙﾚﾛﾜﾝ";
+const ZENKAKU: &str =
+    "ヲァィゥェォャュョッーアイウエオカキクケコサシスセソタチツテトナニヌネノハヒフヘホマミムメモヤユヨラリルレロワン";
+
+fn is_hankaku_kana(c: char) -> bool {
+    ('\u{FF65}'..='\u{FF9F}').contains(&c)
+}
+
+/// Full-width form of half-width katakana, combining voiced sound marks
+fn to_zenkaku_kana(text: &str) -> String {
+    let mut result = String::new();
+    for c in text.chars() {
+        let combined = match (c, result.chars().last()) {
+            ('ﾞ', Some('ウ')) => Some('ヴ'),
+            ('ﾞ', Some(prev @ ('カ'..='ト' | 'ハ'..='ホ'))) => char::from_u32(prev as u32 + 1),
+            ('ﾟ', Some(prev @ 'ハ'..='ホ')) => char::from_u32(prev as u32 + 2),
+            _ => None,
+        };
+        if let Some(combined) = combined {
+            result.pop();
+            result.push(combined);
+            continue;
+        }
+        match c {
+            '･' => result.push('・'),
+            'ﾞ' => result.push('゛'),
+            'ﾟ' => result.push('゜'),
+            _ => match HANKAKU.chars().position(|h| h == c) {
+                Some(i) => result.extend(ZENKAKU.chars().nth(i)),
+                None => result.push(c),
+            },
+        }
+    }
+    result
+}
+
+/// Half-width katakana, with the full-width form as the fix
+pub(crate) fn hankaku_kana(ctx: &TextContext, _config: &CheckerConfig) -> Vec<Diagnostic> {
+    char_runs(ctx.text, is_hankaku_kana)
+        .into_iter()
+        .map(|run| {
+            let zenkaku = to_zenkaku_kana(&ctx.text[run.clone()]);
+            let mut diagnostic = ctx.diagnostic(
+                "no-hankaku-kana",
+                run.clone(),
+                format!(
+                    "半角カナ「{}」が使われています。全角の「{}」を使ってください。",
+                    &ctx.text[run], zenkaku
+                ),
+            );
+            diagnostic.data = fix_data(vec![Fix::new(diagnostic.range, zenkaku)]);
+            diagnostic
+        })
+        .collect()
+}
+
+fn is_zenkaku_alphanumeric(c: char) -> bool {
+    matches!(c, '０'..='９' | 'Ａ'..='Ｚ' | 'ａ'..='ｚ')
+}
+
+/// Full-width Latin letters and digits, with the half-width form as the fix
+/// (2.1.8 and 2.1.9 of preset-jtf-style)
+pub(crate) fn zenkaku_alphanumerics(ctx: &TextContext, _config: &CheckerConfig) -> Vec<Diagnostic> {
+    char_runs(ctx.text, is_zenkaku_alphanumeric)
+        .into_iter()
+        .map(|run| {
+            // The full-width forms are those of ASCII shifted by U+FEE0
+            let hankaku: String = ctx.text[run.clone()]
+                .chars()
+                .filter_map(|c| char::from_u32(c as u32 - 0xFEE0))
+                .collect();
+            let mut diagnostic = ctx.diagnostic(
+                "no-zenkaku-alphanumerics",
+                run.clone(),
+                format!(
+                    "全角の英数字「{}」が使われています。半角の「{}」を使ってください。",
+                    &ctx.text[run], hankaku
+                ),
+            );
+            diagnostic.data = fix_data(vec![Fix::new(diagnostic.range, hankaku)]);
+            diagnostic
+        })
+        .collect()
+}
+
+/// 〜かもしれない and its polite forms
+pub(crate) fn weak_phrase(ctx: &TextContext, _config: &CheckerConfig) -> Vec<Diagnostic> {
+    ctx.text
+        .match_indices("かもしれ")
+        .map(|(start, phrase)| {
+            let rest = &ctx.text[start + phrase.len()..];
+            let ending = ["ない", "ません"]
+                .into_iter()
+                .find(|ending| rest.starts_with(ending))
+                .unwrap_or("");
+            let end = start + phrase.len() + ending.len();
+            ctx.diagnostic(
+                "ja-no-weak-phrase",
+                start..end,
+                format!(
+                    "弱い表現「{}」が使われています。言い切れる内容は断定してください。",
+                    &ctx.text[start..end]
+                ),
+            )
+        })
+        .collect()
+}
+
+/// Zero-width spaces, removed by the fix
+pub(crate) fn zero_width_spaces(ctx: &TextContext, _config: &CheckerConfig) -> Vec<Diagnostic> {
+    char_runs(ctx.text, |c| c == '\u{200B}')
+        .into_iter()
+        .map(|run| {
+            let mut diagnostic = ctx.diagnostic(
+                "no-zero-width-spaces",
+                run,
+                "ゼロ幅スペースが含まれています。".to_string(),
+            );
+            diagnostic.data = fix_data(vec![Fix::new(diagnostic.range, "")]);
+            diagnostic
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(json: &str) -> Map<String, Value> {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn test_rule_id() {
+        assert_eq!(rule_id("no-doubled-joshi"), Some("double-particle"));
+        assert_eq!(rule_id("ja-technical-writing/no-dropping-the-ra"), Some("ra-nuki"));
+        assert_eq!(rule_id("textlint-rule-sentence-length"), Some("sentence-length"));
+        assert_eq!(rule_id("no-exclamation-question-mark"), None);
+    }
+
+    #[test]
+    fn test_settings() {
+        let settings = settings(&rules(
+            r#"{
+                "preset-ja-technical-writing": {
+                    "sentence-length": { "max": 120 },
+                    "no-doubled-joshi": false
+                },
+                "no-mix-dearu-desumasu": { "preferInBody": "である" },
+                "no-hankaku-kana": false
+            }"#,
+        ));
+
+        assert_eq!(settings["sentence_length"], toml::Value::Boolean(true));
+        assert_eq!(settings["max_sentence_length"], toml::Value::Integer(120));
+        assert_eq!(settings["double_particle"], toml::Value::Boolean(false));
+        assert_eq!(settings["ra_nuki"], toml::Value::Boolean(true));
+        assert_eq!(settings["preferred_style"], toml::Value::String("dearu".to_string()));
+        assert_eq!(settings["hankaku_kana"], toml::Value::Boolean(false));
+    }
+
+    #[test]
+    fn test_unsupported_rules() {
+        let mut unsupported = unsupported_rules(&rules(
+            r#"{
+                "preset-ja-technical-writing": { "no-exclamation-question-mark": false },
+                "max-ten": true,
+                "spellcheck-tech-word": true
+            }"#,
+        ));
+
+        unsupported.sort();
+        assert_eq!(unsupported, ["no-exclamation-question-mark", "spellcheck-tech-word"]);
+    }
+
+    #[test]
+    fn test_char_runs() {
+        assert_eq!(
+            char_runs("ab漢字cd字", |c| CharKind::of(c) == CharKind::Kanji),
+            vec![2..8, 10..13]
+        );
+    }
+
+    #[test]
+    fn test_to_zenkaku_kana() {
+        assert_eq!(to_zenkaku_kana("ｶﾞｲﾄﾞﾌﾞｯｸ"), "ガイドブック");
+        assert_eq!(to_zenkaku_kana("ﾊﾟｿｺﾝ･ｳﾞｧ"), "パソコン・ヴァ");
+    }
+}
@@ -0,0 +1,157 @@
+//! UTF-16 position handling
+//!
+//! LSP positions count columns in UTF-16 code units. Characters outside the
+//! BMP (emoji, 𩸽, etc.) take two code units, so columns cannot be computed
+//! from character counts. All conversions go through byte offsets.
+
+use lsp_types::Position;
+
+/// Number of UTF-16 code units in a string
+pub fn utf16_len(text: &str) -> usize {
+    text.chars().map(char::len_utf16).sum()
+}
+
+/// Line start table for converting between byte offsets and LSP positions
+pub struct LineIndex<'a> {
+    text: &'a str,
+    /// Byte offset of the start of each line
+    line_starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    pub fn new(text: &'a str) -> Self {
+        let mut line_starts = vec![0];
+        line_starts.extend(text.match_indices('\n').map(|(i, _)| i + 1));
+        Self { text, line_starts }
+    }
+
+    /// Convert a byte offset to an LSP position
+    pub fn position(&self, offset: usize) -> Position {
+        let mut offset = offset.min(self.text.len());
+        while !self.text.is_char_boundary(offset) {
+            offset -= 1;
+        }
+
+        let line = self.line_starts.partition_point(|&start| start <= offset) - 1;
+        let line_start = self.line_starts[line];
+
+        Position {
+            line: line as u32,
+            character: utf16_len(&self.text[line_start..offset]) as u32,
+        }
+    }
+
+    /// Convert an LSP position to a byte offset.
+    ///
+    /// Positions past the end of a line clamp to the line end, and positions
+    /// past the last line clamp to the end of the text.
+    pub fn offset(&self, position: Position) -> usize {
+        let line = position.line as usize;
+        let start = match self.line_starts.get(line) {
+            Some(&start) => start,
+            None => return self.text.len(),
+        };
+        let end = self
+            .line_starts
+            .get(line + 1)
+            .map(|&next| next - 1)
+            .unwrap_or(self.text.len());
+        let line_text = self.text[start..end].trim_end_matches('\r');
+
+        let mut units = 0;
+        for (i, c) in line_text.char_indices() {
+            if units >= position.character as usize {
+                return start + i;
+            }
+            units += c.len_utf16();
+        }
+
+        start + line_text.len()
+    }
+}
+
+/// Convert a byte offset in the text to an LSP position
+pub fn byte_offset_to_position(text: &str, offset: usize) -> Position {
+    LineIndex::new(text).position(offset)
+}
+
+/// Convert an LSP position to a byte offset in the text
+pub fn position_to_byte_offset(text: &str, position: Position) -> usize {
+    LineIndex::new(text).offset(position)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_utf16_len() {
+        assert_eq!(utf16_len("abc"), 3);
+        assert_eq!(utf16_len("日本語"), 3);
+        assert_eq!(utf16_len("𩸽を食べる"), 6);
+        assert_eq!(utf16_len("😀"), 2);
+    }
+
+    #[test]
+    fn test_byte_offset_to_position() {
+        let content = "一行目\n二行目です";
+        let offset = content.find("です").unwrap();
+
+        assert_eq!(
+            byte_offset_to_position(content, offset),
+            Position { line: 1, character: 3 }
+        );
+        assert_eq!(byte_offset_to_position(content, 0), Position { line: 0, character: 0 });
+    }
+
+    #[test]
+    fn test_byte_offset_to_position_surrogate_pair() {
+        let content = "😀絵文字の後の𩸽です";
+        let offset = content.find("です").unwrap();
+
+        // 😀 and 𩸽 count as two UTF-16 code units each
+        assert_eq!(
+            byte_offset_to_position(content, offset),
+            Position { line: 0, character: 10 }
+        );
+    }
+
+    #[test]
+    fn test_position_to_byte_offset() {
+        let content = "一行目\r\n二行目です";
+
+        assert_eq!(
+            position_to_byte_offset(content, Position { line: 1, character: 3 }),
+            content.find("です").unwrap()
+        );
+        // Positions past the end of a line clamp to the line end
+        assert_eq!(
+            position_to_byte_offset(content, Position { line: 0, character: 99 }),
+            "一行目".len()
+        );
+        assert_eq!(
+            position_to_byte_offset(content, Position { line: 5, character: 0 }),
+            content.len()
+        );
+    }
+
+    #[test]
+    fn test_position_to_byte_offset_surrogate_pair() {
+        let content = "𩸽です";
+
+        assert_eq!(
+            position_to_byte_offset(content, Position { line: 0, character: 2 }),
+            "𩸽".len()
+        );
+    }
+
+    #[test]
+    fn test_line_index_round_trip() {
+        let content = "a😀b\n\nc𩸽d";
+        let index = LineIndex::new(content);
+
+        for (offset, _) in content.char_indices() {
+            assert_eq!(index.offset(index.position(offset)), offset);
+        }
+    }
+}
@@ -0,0 +1,189 @@
+//! Workspace file discovery
+//!
+//! Enumerates documents under workspace roots for workspace-wide operations.
+//! `.gitignore` files and the `[checker] exclude` patterns of `mozuku.toml`
+//! are respected.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::WalkBuilder;
+
+/// Directories never descended into when collecting files
+const SKIPPED_DIRS: [&str; 4] = ["node_modules", "target", "dist", "build"];
+
+/// Extensions of files collected from the workspace
+pub const EXTENSIONS: [&str; 22] = [
+    "md", "markdown", "txt", "rs", "py", "pyi", "ts", "tsx", "js", "jsx", "mjs", "cjs", "c", "h", "cpp", "cc", "cxx",
+    "hpp", "hxx", "go", "tex", "latex",
+];
+
+/// Paths excluded by gitignore-style patterns.
+///
+/// Patterns are relative to a base directory (the directory of `mozuku.toml`):
+/// `drafts/` excludes the `drafts` directory at any depth, `/docs/old` only the
+/// one directly under the base, and `!` re-includes a path.
+#[derive(Debug, Clone)]
+pub struct Exclusions {
+    base: PathBuf,
+    patterns: Gitignore,
+}
+
+impl Exclusions {
+    /// Compile exclusion patterns relative to a base directory
+    pub fn new(base: &Path, patterns: &[String]) -> Result<Self> {
+        let base = std::path::absolute(base).unwrap_or_else(|_| base.to_path_buf());
+        let mut builder = GitignoreBuilder::new(&base);
+        for pattern in patterns {
+            builder
+                .add_line(None, pattern)
+                .with_context(|| format!("除外パターン {} が不正です", pattern))?;
+        }
+        let patterns = builder.build().context("除外パターンを読み込めません")?;
+        Ok(Self { base, patterns })
+    }
+
+    /// Check whether a path or one of its parent directories is excluded.
+    /// Paths outside the base directory are never excluded.
+    pub fn is_excluded(&self, path: &Path, is_dir: bool) -> bool {
+        let Ok(path) = std::path::absolute(path) else {
+            return false;
+        };
+        match path.strip_prefix(&self.base) {
+            Ok(relative) if !relative.as_os_str().is_empty() => {
+                self.patterns.matched_path_or_any_parents(relative, is_dir).is_ignore()
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Default for Exclusions {
+    fn default() -> Self {
+        Self {
+            base: PathBuf::new(),
+            patterns: Gitignore::empty(),
+        }
+    }
+}
+
+/// Recursively collect checkable files under a directory.
+///
+/// Hidden entries, common build/dependency directories, paths ignored by
+/// `.gitignore` (also outside git repositories) and excluded paths are
+/// skipped. Symbolic links are not followed, so a link to a parent
+/// directory cannot make the walk loop. Paths are returned in sorted order.
+pub fn collect_files(root: &Path, exclusions: &Exclusions) -> Vec<PathBuf> {
+    let exclusions = exclusions.clone();
+    let walker = WalkBuilder::new(root)
+        .require_git(false)
+        .follow_links(false)
+        .filter_entry(move |entry| {
+            let is_dir = entry.file_type().is_some_and(|file_type| file_type.is_dir());
+            let name = entry.file_name().to_string_lossy();
+            !(is_dir && SKIPPED_DIRS.contains(&name.as_ref())) && !exclusions.is_excluded(entry.path(), is_dir)
+        })
+        .build();
+
+    let mut files: Vec<PathBuf> = walker
+        .filter_map(|entry| match entry {
+            Ok(entry) => Some(entry),
+            Err(e) => {
+                tracing::debug!("Failed to read entry under {}: {}", root.display(), e);
+                None
+            }
+        })
+        .filter(|entry| entry.file_type().is_some_and(|file_type| file_type.is_file()))
+        .map(|entry| entry.into_path())
+        .filter(|path| is_target_file(path))
+        .collect();
+    files.sort();
+    files
+}
+
+/// Check whether a file has one of the supported extensions
+pub fn is_target_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_target_file() {
+        assert!(is_target_file(Path::new("docs/README.md")));
+        assert!(is_target_file(Path::new("src/main.RS")));
+        assert!(!is_target_file(Path::new("image.png")));
+        assert!(!is_target_file(Path::new("Makefile")));
+    }
+
+    #[test]
+    fn test_collect_files() {
+        let root = std::env::temp_dir().join(format!("mozuku-workspace-test-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("docs")).unwrap();
+        std::fs::create_dir_all(root.join("node_modules/pkg")).unwrap();
+        std::fs::create_dir_all(root.join(".git")).unwrap();
+        std::fs::write(root.join("docs/guide.md"), "ガイド").unwrap();
+        std::fs::write(root.join("notes.txt"), "メモ").unwrap();
+        std::fs::write(root.join("logo.png"), "").unwrap();
+        std::fs::write(root.join("node_modules/pkg/README.md"), "").unwrap();
+        std::fs::write(root.join(".git/HEAD.md"), "").unwrap();
+
+        let files = collect_files(&root, &Exclusions::default());
+        std::fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(files, vec![root.join("docs/guide.md"), root.join("notes.txt")]);
+    }
+
+    #[test]
+    fn test_collect_files_respects_gitignore_and_exclusions() {
+        let root = std::env::temp_dir().join(format!("mozuku-workspace-ignore-test-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("docs/drafts")).unwrap();
+        std::fs::create_dir_all(root.join("vendor")).unwrap();
+        std::fs::write(root.join(".gitignore"), "vendor/\n*.log.md\n").unwrap();
+        std::fs::write(root.join("docs/guide.md"), "ガイド").unwrap();
+        std::fs::write(root.join("docs/drafts/wip.md"), "").unwrap();
+        std::fs::write(root.join("docs/CHANGELOG.md"), "").unwrap();
+        std::fs::write(root.join("vendor/README.md"), "").unwrap();
+        std::fs::write(root.join("build.log.md"), "").unwrap();
+
+        let exclusions = Exclusions::new(&root, &["drafts/".to_string(), "/docs/CHANGELOG.md".to_string()]).unwrap();
+        let files = collect_files(&root, &exclusions);
+        std::fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(files, vec![root.join("docs/guide.md")]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_collect_files_skips_symlink_cycles() {
+        let root = std::env::temp_dir().join(format!("mozuku-workspace-symlink-test-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("docs")).unwrap();
+        std::fs::write(root.join("docs/guide.md"), "ガイド").unwrap();
+        std::os::unix::fs::symlink(&root, root.join("docs/loop")).unwrap();
+
+        let files = collect_files(&root, &Exclusions::default());
+        std::fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(files, vec![root.join("docs/guide.md")]);
+    }
+
+    #[test]
+    fn test_exclusions() {
+        let exclusions = Exclusions::new(
+            Path::new("/work"),
+            &["*.gen.md".to_string(), "!keep.gen.md".to_string()],
+        )
+        .unwrap();
+
+        assert!(exclusions.is_excluded(Path::new("/work/docs/api.gen.md"), false));
+        assert!(!exclusions.is_excluded(Path::new("/work/docs/keep.gen.md"), false));
+        assert!(!exclusions.is_excluded(Path::new("/other/api.gen.md"), false));
+        assert!(Exclusions::new(Path::new("/work"), &["[".to_string()]).is_err());
+    }
+}
@@ -0,0 +1,170 @@
+//! Rule regression corpus
+//!
+//! Each YAML file in `tests/corpus/` holds input texts and the diagnostics
+//! expected from all the rules enabled by its `checker` settings (on top of
+//! the default `[checker]` settings):
+//!
+//! ```yaml
+//! description: 形態素解析を使わないルール
+//! checker:
+//!   hankaku_kana: true
+//! cases:
+//!   - name: 半角カナ
+//!     text: これはﾃｽﾄです。
+//!     expected:
+//!       - rule: no-hankaku-kana
+//!         line: 1
+//!         column: 4
+//!         text: ﾃｽﾄ
+//! ```
+//!
+//! Lines and columns are 1-based, columns counted in characters. Cases are
+//! checked as plain text unless they set `file_type` (e.g. `markdown`).
+//!
+//! After changing a rule, review the differences and accept them with
+//! `MOZUKU_UPDATE_CORPUS=1 cargo test -p mozuku-core --test corpus`, which
+//! rewrites the `expected` lists of the failing cases. New cases can be added
+//! without `expected` and filled in the same way. Rewritten files lose their
+//! YAML comments, so describe cases with `description` and `name` instead.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use lsp_types::NumberOrString;
+use mozuku_core::analyzer::MorphologicalAnalyzer;
+use mozuku_core::config::{CheckerConfig, Config};
+use mozuku_core::extractor::FileType;
+use mozuku_core::utf16::LineIndex;
+use mozuku_core::Proofreader;
+use pretty_assertions::Comparison;
+use serde::{Deserialize, Serialize};
+
+/// Environment variable rewriting the expected diagnostics
+const UPDATE_VAR: &str = "MOZUKU_UPDATE_CORPUS";
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CorpusFile {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    /// `[checker]` settings of all the cases
+    #[serde(default, skip_serializing_if = "serde_yaml::Mapping::is_empty")]
+    checker: serde_yaml::Mapping,
+    cases: Vec<Case>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Case {
+    name: String,
+    #[serde(default = "plain_text", skip_serializing_if = "is_plain_text")]
+    file_type: FileType,
+    text: String,
+    #[serde(default)]
+    expected: Vec<Expected>,
+}
+
+/// A diagnostic as written in the corpus
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Expected {
+    rule: String,
+    line: u32,
+    column: usize,
+    /// Text of the diagnostic range
+    text: String,
+}
+
+fn plain_text() -> FileType {
+    FileType::PlainText
+}
+
+fn is_plain_text(file_type: &FileType) -> bool {
+    *file_type == FileType::PlainText
+}
+
+/// Diagnostics of a case, in the order of their positions
+fn check(proofreader: &Proofreader, case: &Case) -> Vec<Expected> {
+    let text = &case.text;
+    let index = LineIndex::new(text);
+    let mut found: Vec<Expected> = proofreader
+        .check_text(text, case.file_type)
+        .iter()
+        .map(|diag| {
+            let start = index.offset(diag.range.start);
+            let end = index.offset(diag.range.end);
+            let line_start = text[..start].rfind('\n').map_or(0, |i| i + 1);
+            Expected {
+                rule: match &diag.code {
+                    Some(NumberOrString::String(code)) => code.clone(),
+                    _ => String::new(),
+                },
+                line: diag.range.start.line + 1,
+                column: text[line_start..start].chars().count() + 1,
+                text: text[start..end].to_string(),
+            }
+        })
+        .collect();
+    found.sort_by(|a, b| (a.line, a.column, &a.rule, &a.text).cmp(&(b.line, b.column, &b.rule, &b.text)));
+    found
+}
+
+fn corpus_files() -> Vec<PathBuf> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/corpus");
+    let mut files: Vec<PathBuf> = std::fs::read_dir(&dir)
+        .unwrap_or_else(|e| panic!("{}: {}", dir.display(), e))
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| matches!(path.extension().and_then(|ext| ext.to_str()), Some("yml" | "yaml")))
+        .collect();
+    files.sort();
+    files
+}
+
+#[test]
+fn corpus() {
+    let update = std::env::var_os(UPDATE_VAR).is_some();
+    let analyzer = Arc::new(MorphologicalAnalyzer::new().unwrap());
+    let mut failures = Vec::new();
+
+    let files = corpus_files();
+    assert!(!files.is_empty(), "no corpus files");
+    for path in files {
+        let content = std::fs::read_to_string(&path).unwrap();
+        let mut corpus: CorpusFile =
+            serde_yaml::from_str(&content).unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+        let checker: CheckerConfig = serde_yaml::from_value(serde_yaml::Value::Mapping(corpus.checker.clone()))
+            .unwrap_or_else(|e| panic!("{}: checker: {}", path.display(), e));
+        let config = Config {
+            checker,
+            ..Config::default()
+        };
+        let proofreader = Proofreader::with_analyzer(config, analyzer.clone());
+
+        let mut changed = false;
+        for case in &mut corpus.cases {
+            let found = check(&proofreader, case);
+            if found == case.expected {
+                continue;
+            }
+            if update {
+                case.expected = found;
+                changed = true;
+            } else {
+                failures.push(format!(
+                    "{}: {}\n{}",
+                    path.display(),
+                    case.name,
+                    Comparison::new(&case.expected, &found)
+                ));
+            }
+        }
+        if changed {
+            std::fs::write(&path, serde_yaml::to_string(&corpus).unwrap()).unwrap();
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "{} corpus case(s) differ; accept the new results with {}=1\n\n{}",
+        failures.len(),
+        UPDATE_VAR,
+        failures.join("\n\n")
+    );
+}
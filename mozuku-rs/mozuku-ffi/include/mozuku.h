@@ -0,0 +1,62 @@
+/*
+ * MoZuku - Japanese proofreading, C API
+ *
+ * Link with libmozuku (built by `cargo build --release -p mozuku-ffi`).
+ *
+ * Ownership:
+ *   - Strings passed in are NUL-terminated UTF-8 and stay owned by the caller.
+ *   - Strings returned are owned by the caller; release them with
+ *     mozuku_string_free(). Release proofreaders with mozuku_proofreader_free().
+ *
+ * Errors:
+ *   - Functions returning a pointer return NULL on failure.
+ *     mozuku_last_error() then describes the failure of the last call on the
+ *     same thread.
+ *
+ * Threads:
+ *   - A proofreader may be used from several threads at once.
+ */
+
+#ifndef MOZUKU_H
+#define MOZUKU_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct MozukuProofreader MozukuProofreader;
+
+/*
+ * Create a proofreader from the content of a mozuku.toml, or the default
+ * configuration when config is NULL. Loading the dictionary takes a while,
+ * so create one proofreader and reuse it.
+ */
+MozukuProofreader *mozuku_proofreader_new(const char *config);
+
+/* Release a proofreader. NULL is ignored. */
+void mozuku_proofreader_free(MozukuProofreader *proofreader);
+
+/*
+ * Check text, returning a JSON array of LSP diagnostics. language is an LSP
+ * language identifier such as "markdown"; NULL or an unknown one checks the
+ * text as plain text. Positions are 0-based lines and UTF-16 columns.
+ */
+char *mozuku_check_text(const MozukuProofreader *proofreader, const char *text, const char *language);
+
+/* Morphemes of text, returning a JSON array of tokens. */
+char *mozuku_tokenize(const MozukuProofreader *proofreader, const char *text);
+
+/* Release a string returned by this library. NULL is ignored. */
+void mozuku_string_free(char *s);
+
+/*
+ * Description of the last failure on the calling thread, or NULL. Owned by
+ * the library and valid until the next call on the same thread.
+ */
+const char *mozuku_last_error(void);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* MOZUKU_H */
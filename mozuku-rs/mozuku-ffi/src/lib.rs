@@ -0,0 +1,238 @@
+//! C ABI for MoZuku, declared in `include/mozuku.h`
+//!
+//! Conventions shared by every function:
+//!
+//! - Strings passed in are NUL-terminated UTF-8 and stay owned by the caller.
+//! - Strings returned are owned by the caller and must be released with
+//!   [`mozuku_string_free`]; proofreaders with [`mozuku_proofreader_free`].
+//! - Failures return `NULL`. [`mozuku_last_error`] then describes the
+//!   failure of the last call on the same thread.
+//! - Diagnostics and tokens are returned as JSON arrays. Diagnostics have
+//!   the fields of LSP diagnostics, tokens those of `TokenInfo`.
+//! - Panics do not cross the boundary; they are reported as failures.
+
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::Path;
+use std::ptr;
+
+use mozuku_core::config::Config;
+use mozuku_core::extractor::FileType;
+use mozuku_core::Proofreader;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Opaque handle to a proofreader
+pub struct MozukuProofreader(Proofreader);
+
+/// Create a proofreader from the content of a `mozuku.toml`, or the default
+/// configuration when `config` is `NULL`. Relative paths in the configuration
+/// are resolved against the current directory.
+///
+/// # Safety
+///
+/// `config` must be `NULL` or a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn mozuku_proofreader_new(config: *const c_char) -> *mut MozukuProofreader {
+    guard(|| {
+        let config = match unsafe { optional_str(config, "config")? } {
+            Some(content) => Config::parse(content, Path::new(""))
+                .map_err(|e| format!("設定ファイルの形式が正しくありません: {:#}", e))?,
+            None => Config::default(),
+        };
+        let proofreader = Proofreader::new(config).map_err(|e| format!("{:#}", e))?;
+        Ok(Box::into_raw(Box::new(MozukuProofreader(proofreader))))
+    })
+    .unwrap_or(ptr::null_mut())
+}
+
+/// Release a proofreader. `NULL` is ignored.
+///
+/// # Safety
+///
+/// `proofreader` must be `NULL` or returned by [`mozuku_proofreader_new`]
+/// and not released yet.
+#[no_mangle]
+pub unsafe extern "C" fn mozuku_proofreader_free(proofreader: *mut MozukuProofreader) {
+    if !proofreader.is_null() {
+        drop(unsafe { Box::from_raw(proofreader) });
+    }
+}
+
+/// Check text, returning a JSON array of LSP diagnostics. `language` is an
+/// LSP language identifier such as `markdown`; `NULL` or an unknown one
+/// checks the text as plain text. Positions are 0-based lines and UTF-16
+/// columns.
+///
+/// # Safety
+///
+/// `proofreader` must be a live proofreader, `text` a NUL-terminated string
+/// and `language` `NULL` or a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn mozuku_check_text(
+    proofreader: *const MozukuProofreader,
+    text: *const c_char,
+    language: *const c_char,
+) -> *mut c_char {
+    guard(|| {
+        let proofreader = unsafe { proofreader.as_ref() }.ok_or("proofreader が NULL です")?;
+        let text = unsafe { required_str(text, "text")? };
+        let file_type = unsafe { optional_str(language, "language")? }
+            .and_then(FileType::from_language_id)
+            .unwrap_or(FileType::PlainText);
+        to_json(&proofreader.0.check_text(text, file_type))
+    })
+    .unwrap_or(ptr::null_mut())
+}
+
+/// Morphemes of text, returning a JSON array of tokens
+///
+/// # Safety
+///
+/// `proofreader` must be a live proofreader and `text` a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn mozuku_tokenize(proofreader: *const MozukuProofreader, text: *const c_char) -> *mut c_char {
+    guard(|| {
+        let proofreader = unsafe { proofreader.as_ref() }.ok_or("proofreader が NULL です")?;
+        let text = unsafe { required_str(text, "text")? };
+        to_json(&proofreader.0.tokenize(text))
+    })
+    .unwrap_or(ptr::null_mut())
+}
+
+/// Release a string returned by this library. `NULL` is ignored.
+///
+/// # Safety
+///
+/// `s` must be `NULL` or returned by this library and not released yet.
+#[no_mangle]
+pub unsafe extern "C" fn mozuku_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(unsafe { CString::from_raw(s) });
+    }
+}
+
+/// Description of the last failure on the calling thread, or `NULL` if
+/// there was none. The string is owned by the library and stays valid until
+/// the next call on the same thread; do not release it.
+#[no_mangle]
+pub extern "C" fn mozuku_last_error() -> *const c_char {
+    LAST_ERROR.with(|error| error.borrow().as_ref().map_or(ptr::null(), |e| e.as_ptr()))
+}
+
+/// Run the body of an exported function, recording its failure or panic as
+/// the last error
+fn guard<T>(f: impl FnOnce() -> Result<T, String>) -> Option<T> {
+    set_last_error(None);
+    let message = match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(value)) => return Some(value),
+        Ok(Err(message)) => message,
+        Err(_) => "内部エラーが発生しました".to_string(),
+    };
+    set_last_error(Some(message));
+    None
+}
+
+fn set_last_error(message: Option<String>) {
+    let message = message.map(|m| CString::new(m.replace('\0', "")).unwrap_or_default());
+    LAST_ERROR.with(|error| *error.borrow_mut() = message);
+}
+
+unsafe fn optional_str<'a>(s: *const c_char, name: &str) -> Result<Option<&'a str>, String> {
+    if s.is_null() {
+        return Ok(None);
+    }
+    unsafe { CStr::from_ptr(s) }
+        .to_str()
+        .map(Some)
+        .map_err(|_| format!("{} が UTF-8 ではありません", name))
+}
+
+unsafe fn required_str<'a>(s: *const c_char, name: &str) -> Result<&'a str, String> {
+    unsafe { optional_str(s, name)? }.ok_or_else(|| format!("{} が NULL です", name))
+}
+
+fn to_json<T: serde::Serialize + ?Sized>(value: &T) -> Result<*mut c_char, String> {
+    let json = serde_json::to_string(value).map_err(|e| e.to_string())?;
+    // JSON escapes control characters, so it never contains a NUL
+    Ok(CString::new(json).map_err(|e| e.to_string())?.into_raw())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn last_error() -> Option<String> {
+        let error = mozuku_last_error();
+        (!error.is_null()).then(|| unsafe { CStr::from_ptr(error) }.to_string_lossy().into_owned())
+    }
+
+    unsafe fn take_json(s: *mut c_char) -> serde_json::Value {
+        assert!(!s.is_null(), "{:?}", last_error());
+        let value = serde_json::from_str(unsafe { CStr::from_ptr(s) }.to_str().unwrap()).unwrap();
+        unsafe { mozuku_string_free(s) };
+        value
+    }
+
+    #[test]
+    fn test_check_text_and_tokenize() {
+        let source = "これを食べれる。";
+        let text = CString::new(source).unwrap();
+        let language = CString::new("markdown").unwrap();
+        unsafe {
+            let proofreader = mozuku_proofreader_new(ptr::null());
+            assert!(!proofreader.is_null(), "{:?}", last_error());
+
+            let diagnostics = take_json(mozuku_check_text(proofreader, text.as_ptr(), language.as_ptr()));
+            let ra_nuki = diagnostics
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|d| d["code"] == "ra-nuki")
+                .unwrap();
+            // The range starts at the flagged words, in UTF-16 code units
+            let message = ra_nuki["message"].as_str().unwrap();
+            let wrong = message.split('「').nth(1).and_then(|s| s.split('」').next()).unwrap();
+            let column = source[..source.find(wrong).unwrap()].encode_utf16().count();
+            assert_eq!(ra_nuki["range"]["start"]["line"], 0);
+            assert_eq!(ra_nuki["range"]["start"]["character"], column);
+
+            let tokens = take_json(mozuku_tokenize(proofreader, text.as_ptr()));
+            assert_eq!(tokens[0]["surface"], "これ");
+            assert!(last_error().is_none());
+
+            mozuku_proofreader_free(proofreader);
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        let config = CString::new("[checker\n").unwrap();
+        unsafe {
+            assert!(mozuku_proofreader_new(config.as_ptr()).is_null());
+            assert!(last_error().unwrap().contains("設定ファイル"));
+
+            let config = CString::new("[checker]\nra_nuki = false\n").unwrap();
+            let proofreader = mozuku_proofreader_new(config.as_ptr());
+            assert!(last_error().is_none());
+            assert!(mozuku_check_text(proofreader, ptr::null(), ptr::null()).is_null());
+            assert_eq!(last_error().unwrap(), "text が NULL です");
+
+            let invalid = [0xffu8 as c_char, 0];
+            assert!(mozuku_tokenize(proofreader, invalid.as_ptr()).is_null());
+            assert_eq!(last_error().unwrap(), "text が UTF-8 ではありません");
+
+            let text = CString::new("これを食べれる。").unwrap();
+            let diagnostics = take_json(mozuku_check_text(proofreader, text.as_ptr(), ptr::null()));
+            assert_eq!(diagnostics, serde_json::json!([]));
+
+            assert!(mozuku_check_text(ptr::null(), text.as_ptr(), ptr::null()).is_null());
+            mozuku_proofreader_free(proofreader);
+            mozuku_proofreader_free(ptr::null_mut());
+            mozuku_string_free(ptr::null_mut());
+        }
+    }
+}
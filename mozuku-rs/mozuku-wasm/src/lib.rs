@@ -0,0 +1,90 @@
+//! MoZuku for the browser - offline proofreading in web editors
+//!
+//! Built with `wasm-pack build mozuku-wasm --target web`:
+//!
+//! ```js
+//! import init, { Proofreader } from "./pkg/mozuku_wasm.js";
+//!
+//! await init();
+//! const proofreader = new Proofreader("[checker]\nra_nuki = true\n");
+//! for (const diagnostic of proofreader.check("これを食べれる。", "plaintext")) {
+//!   console.log(diagnostic.range.start.line, diagnostic.message);
+//! }
+//! ```
+//!
+//! Diagnostics are LSP diagnostics as plain objects. Markdown is scanned line
+//! by line and source code is checked as plain text, since the tree-sitter
+//! grammars are not built for WebAssembly.
+
+use std::path::Path;
+use std::sync::Arc;
+
+use mozuku_core::analyzer::MorphologicalAnalyzer;
+use mozuku_core::config::Config;
+use mozuku_core::extractor::FileType;
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+/// Checks text with one configuration
+#[wasm_bindgen]
+pub struct Proofreader {
+    inner: mozuku_core::Proofreader,
+}
+
+#[wasm_bindgen]
+impl Proofreader {
+    /// Create a proofreader from the content of a `mozuku.toml`, or the
+    /// default configuration. Settings that refer to files, such as the user
+    /// dictionary, have no effect since the browser has no file system.
+    #[wasm_bindgen(constructor)]
+    pub fn new(config: Option<String>) -> Result<Proofreader, JsError> {
+        let config = match config {
+            Some(content) => Config::parse(&content, Path::new(""))
+                .map_err(|e| JsError::new(&format!("設定ファイルの形式が正しくありません: {:#}", e)))?,
+            None => Config::default(),
+        };
+        Ok(Self {
+            inner: mozuku_core::Proofreader::with_analyzer(config, Arc::new(analyzer())),
+        })
+    }
+
+    /// Whether morphological analysis is available. It is not in builds
+    /// without the embedded dictionary, where only the rules that do not
+    /// need it report diagnostics.
+    #[wasm_bindgen(js_name = isAnalyzerAvailable)]
+    pub fn is_analyzer_available(&self) -> bool {
+        self.inner.analyzer().is_available()
+    }
+
+    /// Check text, returning an array of LSP diagnostics. `language` is an
+    /// LSP language identifier such as `markdown`; unknown ones are checked
+    /// as plain text.
+    pub fn check(&self, text: &str, language: Option<String>) -> Result<JsValue, JsError> {
+        let file_type = language
+            .as_deref()
+            .and_then(FileType::from_language_id)
+            .unwrap_or(FileType::PlainText);
+        to_js(&self.inner.check_text(text, file_type))
+    }
+
+    /// Morphemes of text, as an array of token objects
+    pub fn tokenize(&self, text: &str) -> Result<JsValue, JsError> {
+        to_js(&self.inner.tokenize(text))
+    }
+}
+
+/// Analyzer with the embedded dictionary, or degraded without it
+fn analyzer() -> MorphologicalAnalyzer {
+    if cfg!(feature = "embedded-ipadic") {
+        MorphologicalAnalyzer::new().unwrap_or_else(|_| MorphologicalAnalyzer::degraded())
+    } else {
+        MorphologicalAnalyzer::degraded()
+    }
+}
+
+/// Convert to a plain JavaScript value, with maps as objects
+fn to_js<T: Serialize + ?Sized>(value: &T) -> Result<JsValue, JsError> {
+    value
+        .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+        .map_err(|e| JsError::new(&e.to_string()))
+}
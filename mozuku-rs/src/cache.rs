@@ -0,0 +1,264 @@
+//! Cache of LLM responses
+//!
+//! Responses are kept in an in-memory LRU cache and, optionally, as files in
+//! the user cache directory so that they survive restarts. Entries expire
+//! after a TTL, and the oldest files are removed when the disk cache grows
+//! beyond its size limit.
+
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use ring::digest;
+use serde::{Deserialize, Serialize};
+
+use crate::config::LlmCacheConfig;
+use crate::lru::LruMap;
+
+/// A cached response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    /// Creation time (seconds since the Unix epoch)
+    created: u64,
+    response: String,
+}
+
+impl CacheEntry {
+    fn is_expired(&self, now: u64, ttl: Duration) -> bool {
+        now.saturating_sub(self.created) >= ttl.as_secs()
+    }
+}
+
+/// Two-level (memory and disk) cache of LLM responses
+#[derive(Debug)]
+pub struct ResponseCache {
+    enabled: bool,
+    ttl: Duration,
+    /// Disk cache directory; `None` disables the disk cache
+    dir: Option<PathBuf>,
+    max_disk_size: u64,
+    /// Entries of size 1, bounded by the number of memory entries
+    memory: Mutex<LruMap<String, CacheEntry>>,
+}
+
+impl ResponseCache {
+    /// Create a cache from the configuration
+    pub fn new(config: &LlmCacheConfig) -> Self {
+        Self {
+            enabled: config.enabled,
+            ttl: Duration::from_secs(config.ttl),
+            dir: config.disk.then(|| config.cache_dir()).flatten(),
+            max_disk_size: config.max_disk_size,
+            memory: Mutex::new(LruMap::new(config.memory_entries)),
+        }
+    }
+
+    /// Cache key of a request, from everything that affects the reply
+    pub fn key(parts: &[&str]) -> String {
+        let mut context = digest::Context::new(&digest::SHA256);
+        for part in parts {
+            context.update(part.as_bytes());
+            context.update(&[0]);
+        }
+        context.finish().as_ref().iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    /// Look up a response, from memory first and then from disk
+    pub fn get(&self, key: &str) -> Option<String> {
+        if !self.enabled {
+            return None;
+        }
+        let now = unix_time();
+
+        {
+            let mut memory = self.memory.lock().unwrap_or_else(|e| e.into_inner());
+            match memory.get(key) {
+                Some(entry) if entry.is_expired(now, self.ttl) => {
+                    memory.remove(key);
+                }
+                Some(entry) => return Some(entry.response.clone()),
+                None => {}
+            }
+        }
+
+        let path = self.entry_path(key)?;
+        let entry: CacheEntry = serde_json::from_str(&std::fs::read_to_string(&path).ok()?).ok()?;
+        if entry.is_expired(now, self.ttl) {
+            let _ = std::fs::remove_file(&path);
+            return None;
+        }
+
+        let response = entry.response.clone();
+        self.insert_memory(key, entry);
+        Some(response)
+    }
+
+    /// Store a response in memory and on disk
+    pub fn put(&self, key: &str, response: &str) {
+        if !self.enabled {
+            return;
+        }
+        let entry = CacheEntry {
+            created: unix_time(),
+            response: response.to_string(),
+        };
+
+        if let (Some(dir), Some(path)) = (&self.dir, self.entry_path(key)) {
+            let written = std::fs::create_dir_all(dir)
+                .and_then(|_| std::fs::write(&path, serde_json::to_string(&entry).unwrap_or_default()));
+            match written {
+                Ok(()) => prune_dir(dir, self.max_disk_size),
+                Err(e) => tracing::warn!("Failed to write LLM cache {}: {}", path.display(), e),
+            }
+        }
+
+        self.insert_memory(key, entry);
+    }
+
+    fn insert_memory(&self, key: &str, entry: CacheEntry) {
+        let mut memory = self.memory.lock().unwrap_or_else(|e| e.into_inner());
+        memory.insert(key.to_string(), entry, 1);
+    }
+
+    fn entry_path(&self, key: &str) -> Option<PathBuf> {
+        Some(self.dir.as_ref()?.join(format!("{}.json", key)))
+    }
+}
+
+/// Remove the oldest cache files until the directory fits in `max_size` bytes
+fn prune_dir(dir: &Path, max_size: u64) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let mut files: Vec<(PathBuf, u64, SystemTime)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            metadata
+                .is_file()
+                .then(|| (entry.path(), metadata.len(), metadata.modified().unwrap_or(UNIX_EPOCH)))
+        })
+        .collect();
+
+    let mut total: u64 = files.iter().map(|(_, size, _)| size).sum();
+    files.sort_by_key(|(_, _, modified)| *modified);
+    for (path, size, _) in files {
+        if total <= max_size {
+            break;
+        }
+        if std::fs::remove_file(&path).is_ok() {
+            total -= size;
+        }
+    }
+}
+
+fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn memory_only(memory_entries: usize) -> ResponseCache {
+        ResponseCache::new(&LlmCacheConfig {
+            memory_entries,
+            disk: false,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_key_depends_on_all_parts() {
+        let key = ResponseCache::key(&["claude", "model", "prompt"]);
+
+        assert_eq!(key.len(), 64);
+        assert_eq!(key, ResponseCache::key(&["claude", "model", "prompt"]));
+        assert_ne!(key, ResponseCache::key(&["openai", "model", "prompt"]));
+        assert_ne!(
+            ResponseCache::key(&["a", "bc", "d"]),
+            ResponseCache::key(&["ab", "c", "d"])
+        );
+    }
+
+    #[test]
+    fn test_disk_cache_is_opt_in() {
+        let cache = ResponseCache::new(&LlmCacheConfig::default());
+
+        assert!(cache.dir.is_none());
+    }
+
+    #[test]
+    fn test_memory_lru() {
+        let cache = memory_only(2);
+
+        cache.put("a", "A");
+        cache.put("b", "B");
+        assert_eq!(cache.get("a").as_deref(), Some("A"));
+        cache.put("c", "C");
+
+        // "b" was the least recently used
+        assert_eq!(cache.get("b"), None);
+        assert_eq!(cache.get("a").as_deref(), Some("A"));
+        assert_eq!(cache.get("c").as_deref(), Some("C"));
+    }
+
+    #[test]
+    fn test_disabled() {
+        let cache = ResponseCache::new(&LlmCacheConfig {
+            enabled: false,
+            disk: false,
+            ..Default::default()
+        });
+
+        cache.put("a", "A");
+        assert_eq!(cache.get("a"), None);
+    }
+
+    #[test]
+    fn test_expired_entries_are_ignored() {
+        let cache = ResponseCache::new(&LlmCacheConfig {
+            ttl: 0,
+            disk: false,
+            ..Default::default()
+        });
+
+        cache.put("a", "A");
+        assert_eq!(cache.get("a"), None);
+    }
+
+    #[test]
+    fn test_disk_cache_survives_restart() {
+        let dir = std::env::temp_dir().join(format!("mozuku-llm-cache-test-{}", std::process::id()));
+        let config = LlmCacheConfig {
+            disk: true,
+            dir: Some(dir.clone()),
+            ..Default::default()
+        };
+
+        ResponseCache::new(&config).put("key", "応答");
+        let restored = ResponseCache::new(&config).get("key");
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(restored.as_deref(), Some("応答"));
+    }
+
+    #[test]
+    fn test_prune_dir_removes_oldest() {
+        let dir = std::env::temp_dir().join(format!("mozuku-llm-cache-prune-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("old.json"), "0123456789").unwrap();
+        let old = std::fs::File::options().write(true).open(dir.join("old.json")).unwrap();
+        old.set_modified(UNIX_EPOCH + Duration::from_secs(1)).unwrap();
+        std::fs::write(dir.join("new.json"), "0123456789").unwrap();
+
+        prune_dir(&dir, 15);
+        let remaining: Vec<bool> = ["old.json", "new.json"].iter().map(|f| dir.join(f).exists()).collect();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(remaining, vec![false, true]);
+    }
+}
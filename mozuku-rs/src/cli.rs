@@ -0,0 +1,1177 @@
+//! Command-line batch checking
+//!
+//! `mozuku-rs check` checks files and directories without an editor and
+//! prints the diagnostics to standard output, so that documents can be
+//! checked in CI. The same extractor and checker as the language server are
+//! used, so the results match what the editor shows.
+//!
+//! With `--fix`, the fixes attached to diagnostics (full-width alphanumerics,
+//! punctuation style, ra-nuki and so on) are written back to the files;
+//! `--dry-run` only prints them as a diff. `--watch` keeps running and
+//! re-checks the files that change. Files are checked in parallel, with
+//! `--jobs` limiting the number of threads.
+//!
+//! `mozuku-rs readability` prints the readability score of each file,
+//! `mozuku-rs rules` lists the rules or explains one of them,
+//! `mozuku-rs init` writes a commented `mozuku.toml`,
+//! `mozuku-rs config validate` checks one, `mozuku-rs config false-positive`
+//! adds a reported word to its false positives, `mozuku-rs dict build`
+//! precompiles the user dictionary, `mozuku-rs bench` measures the time
+//! spent in each stage of checking and `mozuku-rs stats` prints the rule
+//! statistics recorded by the language server.
+
+use std::collections::{BTreeMap, HashSet};
+use std::io::IsTerminal;
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Context, Result};
+use clap::{Args, Subcommand, ValueEnum};
+use mozuku_core::Proofreader;
+use rayon::prelude::*;
+use tower_lsp::lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString, Position, Url};
+
+use crate::analyzer::MorphologicalAnalyzer;
+use crate::checker::{fix_all, CheckProfile};
+use crate::config::{Config, IssueLevel, Preset};
+use crate::extractor::FileType;
+use crate::git::ChangedLines;
+use crate::llm::LlmClient;
+use crate::readability::ReadabilityScore;
+use crate::report::{self, OutputFormat, ReportFormat};
+use crate::rules::{self, RuleCategory, RuleInfo, RULES};
+use crate::telemetry;
+use crate::utf16::{position_to_byte_offset, LineIndex};
+use crate::workspace::{collect_files, Exclusions};
+
+/// Arguments of `check`
+#[derive(Debug, Clone, Args)]
+pub struct CheckArgs {
+    /// Files or directories to check (directories are searched recursively)
+    #[arg(required = true, value_name = "PATH")]
+    pub paths: Vec<PathBuf>,
+
+    /// Configuration file (defaults to mozuku.toml in the current directory)
+    #[arg(long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    /// Apply the fixes of the diagnostics to the files
+    #[arg(long)]
+    pub fix: bool,
+
+    /// Print the changes `--fix` would make as a diff without writing the files
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Output format of the diagnostics
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub format: OutputFormat,
+
+    /// Only report diagnostics on lines added or modified since a git revision (e.g. origin/main)
+    #[arg(long, value_name = "REV")]
+    pub diff: Option<String>,
+
+    /// Keep running and re-check files whenever they change
+    #[arg(long, conflicts_with_all = ["fix", "dry_run", "diff", "report"])]
+    pub watch: bool,
+
+    /// Number of files checked in parallel (defaults to the number of CPUs)
+    #[arg(short, long, value_name = "N")]
+    pub jobs: Option<NonZeroUsize>,
+
+    /// Also write a report for sharing the results to the `--output` file
+    #[arg(long, value_enum, value_name = "FORMAT", requires = "output")]
+    pub report: Option<ReportFormat>,
+
+    /// File the `--report` is written to
+    #[arg(short, long, value_name = "FILE", requires = "report")]
+    pub output: Option<PathBuf>,
+
+    /// Exit with a failure if there are diagnostics of this severity or higher
+    #[arg(long, value_enum, value_name = "SEVERITY", default_value_t = FailOn::Hint)]
+    pub fail_on: FailOn,
+}
+
+/// Arguments of `readability`
+#[derive(Debug, Clone, Args)]
+pub struct ReadabilityArgs {
+    /// Files or directories to score (directories are searched recursively)
+    #[arg(required = true, value_name = "PATH")]
+    pub paths: Vec<PathBuf>,
+
+    /// Configuration file (defaults to mozuku.toml in the current directory)
+    #[arg(long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+}
+
+/// Arguments of `bench`
+#[derive(Debug, Clone, Args)]
+pub struct BenchArgs {
+    /// Files or directories to check (directories are searched recursively)
+    #[arg(required = true, value_name = "PATH")]
+    pub paths: Vec<PathBuf>,
+
+    /// Configuration file (defaults to mozuku.toml in the current directory)
+    #[arg(long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    /// Number of times the files are checked; times are averaged
+    #[arg(short = 'n', long, value_name = "N", default_value = "3")]
+    pub iterations: NonZeroUsize,
+}
+
+/// Arguments of `stats`
+#[derive(Debug, Clone, Args)]
+pub struct StatsArgs {
+    /// Configuration file (defaults to mozuku.toml in the current directory)
+    #[arg(long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    /// Print the statistics as JSON
+    #[arg(long)]
+    pub json: bool,
+
+    /// Delete the recorded statistics
+    #[arg(long, conflicts_with = "json")]
+    pub reset: bool,
+}
+
+/// Arguments of `rules`
+#[derive(Debug, Clone, Args)]
+pub struct RulesArgs {
+    /// Rule ID to explain in detail (lists all rules if omitted)
+    #[arg(value_name = "RULE")]
+    pub rule: Option<String>,
+
+    /// List only the rules of this category
+    #[arg(long, value_enum, conflicts_with = "rule")]
+    pub category: Option<RuleCategory>,
+}
+
+/// Arguments of `init`
+#[derive(Debug, Clone, Args)]
+pub struct InitArgs {
+    /// Settings preset to start from
+    #[arg(long, value_enum, default_value_t = Preset::Default)]
+    pub preset: Preset,
+
+    /// Overwrite an existing mozuku.toml
+    #[arg(long)]
+    pub force: bool,
+}
+
+/// Arguments of `config`
+#[derive(Debug, Clone, Args)]
+pub struct ConfigArgs {
+    #[command(subcommand)]
+    pub command: ConfigCommand,
+}
+
+/// Subcommands of `config`
+#[derive(Debug, Clone, Subcommand)]
+pub enum ConfigCommand {
+    /// Report syntax errors, unknown keys and invalid values in a mozuku.toml
+    Validate(ValidateArgs),
+    /// Mark a word reported by a rule as a false positive, so that the rule
+    /// no longer reports it
+    FalsePositive(FalsePositiveArgs),
+}
+
+/// Arguments of `config validate`
+#[derive(Debug, Clone, Args)]
+pub struct ValidateArgs {
+    /// Configuration file to validate
+    #[arg(value_name = "FILE", default_value = "mozuku.toml")]
+    pub path: PathBuf,
+
+    /// Also send a short request to the configured LLM provider
+    #[arg(long)]
+    pub check_llm: bool,
+}
+
+/// Arguments of `config false-positive`
+#[derive(Debug, Clone, Args)]
+pub struct FalsePositiveArgs {
+    /// Rule ID of the diagnostic (e.g. ra-nuki)
+    #[arg(value_name = "RULE")]
+    pub rule: String,
+
+    /// Reported text (e.g. 見れる)
+    #[arg(value_name = "WORD")]
+    pub word: String,
+
+    /// Configuration file to update (created if missing)
+    #[arg(long, value_name = "FILE", default_value = "mozuku.toml")]
+    pub config: PathBuf,
+}
+
+/// Arguments of `dict`
+#[derive(Debug, Clone, Args)]
+pub struct DictArgs {
+    #[command(subcommand)]
+    pub command: DictCommand,
+}
+
+/// Subcommands of `dict`
+#[derive(Debug, Clone, Subcommand)]
+pub enum DictCommand {
+    /// Compile a user dictionary CSV to a binary dictionary next to it
+    Build(DictBuildArgs),
+}
+
+/// Arguments of `dict build`
+#[derive(Debug, Clone, Args)]
+pub struct DictBuildArgs {
+    /// User dictionary CSV (defaults to `[dictionary] user_dictionary`)
+    #[arg(value_name = "FILE")]
+    pub path: Option<PathBuf>,
+
+    /// Configuration file (defaults to mozuku.toml in the current directory)
+    #[arg(long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+}
+
+/// Lowest severity of diagnostics that makes `check` fail
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum FailOn {
+    Error,
+    Warning,
+    Info,
+    /// Any diagnostic
+    #[default]
+    Hint,
+    /// Only report diagnostics, never fail because of them
+    Never,
+}
+
+impl FailOn {
+    /// Check whether a diagnostic of the given severity makes `check` fail.
+    /// Diagnostics without a severity count as warnings, as in the output.
+    pub fn fails(self, severity: Option<DiagnosticSeverity>) -> bool {
+        let threshold = match self {
+            FailOn::Error => 1,
+            FailOn::Warning => 2,
+            FailOn::Info => 3,
+            FailOn::Hint => 4,
+            FailOn::Never => return false,
+        };
+        severity_rank(severity) <= threshold
+    }
+}
+
+/// Severity as a number, 1 being the most severe (as in LSP)
+fn severity_rank(severity: Option<DiagnosticSeverity>) -> u8 {
+    match severity {
+        Some(DiagnosticSeverity::ERROR) => 1,
+        Some(DiagnosticSeverity::INFORMATION) => 3,
+        Some(DiagnosticSeverity::HINT) => 4,
+        _ => 2,
+    }
+}
+
+/// Checks documents outside the language server
+pub struct Linter {
+    proofreader: Proofreader,
+}
+
+/// Diagnostics of a checked file
+#[derive(Debug, Clone)]
+pub struct FileReport {
+    pub path: PathBuf,
+    pub content: String,
+    pub diagnostics: Vec<Diagnostic>,
+    /// Readability of the text of the file
+    pub readability: ReadabilityScore,
+}
+
+impl Linter {
+    /// Create a linter. Unlike the server, a failed analyzer is an error
+    /// since degraded results would make CI pass silently.
+    pub fn new(config: Config) -> Result<Self> {
+        Ok(Self {
+            proofreader: Proofreader::new(config)?,
+        })
+    }
+
+    /// Check the text of a document, as the server does on open and change
+    pub fn check(&self, uri: &Url, content: &str, file_type: FileType) -> Vec<Diagnostic> {
+        self.proofreader.check_document(uri, content, file_type).0
+    }
+
+    /// Readability of the text of a document, without checking it
+    pub fn readability(&self, content: &str, file_type: FileType) -> ReadabilityScore {
+        self.proofreader.readability(content, file_type)
+    }
+
+    /// Read and check a file
+    pub fn check_file(&self, path: &Path) -> Result<FileReport> {
+        let content = std::fs::read_to_string(path).with_context(|| format!("{} を読み込めません", path.display()))?;
+        Ok(self.check_content(path, content))
+    }
+
+    /// Check the content of a file, which may differ from the file on disk
+    pub fn check_content(&self, path: &Path, content: String) -> FileReport {
+        let file_type = file_type_of(path);
+        let uri = file_uri(path);
+        let (diagnostics, readability) = self.proofreader.check_document(&uri, &content, file_type);
+        FileReport {
+            path: path.to_path_buf(),
+            content,
+            diagnostics,
+            readability,
+        }
+    }
+
+    /// Check the text of a document like [`Self::check`], adding the time
+    /// spent in each stage to a benchmark
+    fn bench(&self, content: &str, file_type: FileType, bench: &mut Benchmark) {
+        let checker_config = &self.proofreader.config().checker;
+        if !checker_config.is_file_type_enabled(file_type) {
+            return;
+        }
+        let text = checker_config.analysis_text(content).unwrap_or_default();
+
+        let start = Instant::now();
+        let spans = self.proofreader.extract(text, file_type);
+        bench.extract += start.elapsed();
+
+        for span in &spans {
+            self.proofreader
+                .checker()
+                .check_profiled(&span.text, checker_config, &mut bench.check);
+        }
+    }
+
+    /// Apply the fixes of a report, writing the file unless `dry_run` is set.
+    /// Returns the report of the fixed content, the number of applied fixes
+    /// and, for a dry run, the diff of the changes.
+    fn fix(&self, report: FileReport, dry_run: bool) -> Result<(FileReport, usize, Option<String>)> {
+        let (fixed, applied) = apply_fixes(&report.content, &report.diagnostics);
+        if applied == 0 {
+            return Ok((report, 0, None));
+        }
+
+        let diff = if dry_run {
+            Some(unified_diff(&report.path, &report.content, &fixed))
+        } else {
+            std::fs::write(&report.path, &fixed)
+                .with_context(|| format!("{} に書き込めません", report.path.display()))?;
+            None
+        };
+        Ok((self.check_content(&report.path, fixed), applied, diff))
+    }
+}
+
+/// Run `check`: print the diagnostics of all files and fail if any at or
+/// above the `--fail-on` severity were found, or if a file could not be checked
+pub fn check(args: &CheckArgs) -> Result<ExitCode> {
+    let (config, exclusions) = load_config(args.config.as_ref())?;
+    let linter = Linter::new(config)?;
+    if args.watch {
+        return watch(&linter, args, &exclusions);
+    }
+
+    let changes = args.diff.as_deref().map(ChangedLines::since).transpose()?;
+    let mut files = collect_targets(&args.paths, &exclusions)?;
+    if let Some(changes) = &changes {
+        files.retain(|path| changes.contains_file(path));
+    }
+    let only_changed = |mut report: FileReport| {
+        if let Some(changes) = &changes {
+            report.diagnostics.retain(|diag| changes.touches(&report.path, diag));
+        }
+        report
+    };
+
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(args.jobs.map_or(0, NonZeroUsize::get))
+        .build()
+        .context("スレッドプールを作成できません")?;
+    // Results are collected in the order of the files, so the output does not
+    // depend on which thread finishes first
+    let results: Vec<_> = pool.install(|| {
+        files
+            .par_iter()
+            .map(|path| -> Result<(FileReport, usize, Option<String>)> {
+                let report = linter.check_file(path).map(only_changed)?;
+                if args.fix || args.dry_run {
+                    let (report, applied, diff) = linter.fix(report, args.dry_run)?;
+                    Ok((only_changed(report), applied, diff))
+                } else {
+                    Ok((report, 0, None))
+                }
+            })
+            .collect()
+    });
+
+    let mut reports = Vec::new();
+    let mut fixed = 0;
+    let mut failed = false;
+    for result in results {
+        match result {
+            Ok((report, applied, diff)) => {
+                if let Some(diff) = diff {
+                    print!("{}", diff);
+                }
+                fixed += applied;
+                reports.push(report);
+            }
+            Err(e) => {
+                eprintln!("{:#}", e);
+                failed = true;
+            }
+        }
+    }
+
+    // The diff is the output of a dry run
+    if !args.dry_run {
+        print!("{}", report::render(args.format, &reports));
+    }
+    let total: usize = reports.iter().map(|report| report.diagnostics.len()).sum();
+
+    if let (Some(format), Some(output)) = (args.report, &args.output) {
+        std::fs::write(output, report::render_report(format, &reports))
+            .with_context(|| format!("{} に書き込めません", output.display()))?;
+        eprintln!("レポートを {} に出力しました", output.display());
+    }
+    if args.dry_run {
+        eprintln!("{}件の修正が可能（ファイルは変更していません）", fixed);
+    } else if args.fix {
+        eprintln!("{}件を修正", fixed);
+    }
+    eprintln!("{}ファイル中 {}件の指摘", files.len(), total);
+    let failing = reports
+        .iter()
+        .flat_map(|report| &report.diagnostics)
+        .any(|diag| args.fail_on.fails(diag.severity));
+    Ok(if failing || failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    })
+}
+
+/// Run `readability`: print the readability score of the text of each file
+pub fn readability(args: &ReadabilityArgs) -> Result<ExitCode> {
+    let (config, exclusions) = load_config(args.config.as_ref())?;
+    let linter = Linter::new(config)?;
+
+    let mut failed = false;
+    for path in collect_targets(&args.paths, &exclusions)? {
+        let content = match std::fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) => {
+                eprintln!("{} を読み込めません: {}", path.display(), e);
+                failed = true;
+                continue;
+            }
+        };
+        let file_type = file_type_of(&path);
+        if !linter.proofreader.config().checker.is_file_type_enabled(file_type) {
+            continue;
+        }
+
+        let score = linter.readability(&content, file_type);
+        print!("{}: {}", path.display(), score.to_text());
+    }
+    Ok(if failed { ExitCode::FAILURE } else { ExitCode::SUCCESS })
+}
+
+/// Time spent checking files, accumulated over all iterations
+#[derive(Debug, Clone, Default)]
+struct Benchmark {
+    extract: Duration,
+    check: CheckProfile,
+}
+
+impl Benchmark {
+    /// Table of the average time per iteration of each stage, with the rules
+    /// from the slowest
+    fn to_text(&self, iterations: usize) -> String {
+        let line = |duration: Duration, label: &str| {
+            let average = duration.as_secs_f64() * 1000.0 / iterations as f64;
+            format!("{:>10.2} ms  {}\n", average, label)
+        };
+        let mut rules: Vec<(&str, Duration)> = self.check.rules.iter().map(|(id, time)| (*id, *time)).collect();
+        rules.sort_by_key(|(_, time)| std::cmp::Reverse(*time));
+        let rules_total: Duration = rules.iter().map(|(_, time)| *time).sum();
+
+        let mut text = line(self.extract, "抽出");
+        text.push_str(&line(self.check.tokenize, "形態素解析"));
+        text.push_str(&line(rules_total, "ルール"));
+        for (rule_id, time) in rules {
+            text.push_str(&line(time, &format!("  {}", rule_id)));
+        }
+        text.push_str(&line(self.extract + self.check.tokenize + rules_total, "合計"));
+        text
+    }
+}
+
+/// Run `bench`: check the files repeatedly and print the average time spent
+/// in extraction, morphological analysis and each rule
+pub fn bench(args: &BenchArgs) -> Result<ExitCode> {
+    let (config, exclusions) = load_config(args.config.as_ref())?;
+    let linter = Linter::new(config)?;
+
+    let mut documents = Vec::new();
+    for path in collect_targets(&args.paths, &exclusions)? {
+        let content = std::fs::read_to_string(&path).with_context(|| format!("{} を読み込めません", path.display()))?;
+        documents.push((file_type_of(&path), content));
+    }
+
+    let iterations = args.iterations.get();
+    let mut benchmark = Benchmark::default();
+    for _ in 0..iterations {
+        for (file_type, content) in &documents {
+            linter.bench(content, *file_type, &mut benchmark);
+        }
+    }
+
+    let bytes: usize = documents.iter().map(|(_, content)| content.len()).sum();
+    println!(
+        "{} ファイル（{:.1} KB）を {} 回チェックした平均:",
+        documents.len(),
+        bytes as f64 / 1024.0,
+        iterations
+    );
+    print!("{}", benchmark.to_text(iterations));
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Run `stats`: print the rule statistics recorded with `[telemetry]`, or
+/// delete them
+pub fn stats(args: &StatsArgs) -> Result<ExitCode> {
+    let (config, _) = load_config(args.config.as_ref())?;
+    let path = config
+        .telemetry
+        .stats_file()
+        .ok_or_else(|| anyhow!("利用統計の保存先が見つかりません（[telemetry] file を指定してください）"))?;
+
+    if args.reset {
+        match std::fs::remove_file(&path) {
+            Ok(()) => println!("{} を削除しました", path.display()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => println!("利用統計は記録されていません"),
+            Err(e) => return Err(e).with_context(|| format!("{} を削除できません", path.display())),
+        }
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    let stats = telemetry::load(&path);
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&stats)?);
+    } else {
+        println!("{}", stats.message());
+    }
+    if !config.telemetry.enabled {
+        eprintln!("利用統計の記録は無効です（[telemetry] enabled = true で記録を始めます）");
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Run `rules`: list all rules, or explain the given one
+pub fn rules(args: &RulesArgs) -> Result<ExitCode> {
+    match &args.rule {
+        Some(id) => {
+            let rule =
+                rules::find_rule(id).ok_or_else(|| anyhow!("ルール {} はありません（`rules` で一覧を表示）", id))?;
+            print!("{}", describe_rule(rule));
+        }
+        None => print!("{}", list_rules(args.category)),
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Rules of the category, or all rules, with their ID, default severity,
+/// category, name, description and first example
+pub fn list_rules(category: Option<RuleCategory>) -> String {
+    let width = RULES.iter().map(|rule| rule.id.len()).max().unwrap_or(0);
+    let mut list = String::new();
+    let selected = RULES.iter().filter(|rule| category.is_none_or(|c| rule.category == c));
+    for rule in selected {
+        let disabled = if rule.default_enabled {
+            ""
+        } else {
+            "（既定で無効）"
+        };
+        list.push_str(&format!(
+            "{:<width$}  {:<7}  {:<11}  {}: {}{}\n",
+            rule.id,
+            severity_label(Some(rule.severity)),
+            rule.category.id(),
+            rule.name,
+            rule.description,
+            disabled,
+            width = width
+        ));
+        if let Some(example) = rule.examples.first() {
+            list.push_str(&format!(
+                "{:<indent$}例: {} → {}\n",
+                "",
+                example.incorrect,
+                example.correct,
+                indent = width + 24
+            ));
+        }
+    }
+    list
+}
+
+/// Detailed explanation of a rule: its metadata, all examples and its
+/// section of the rule documentation
+pub fn describe_rule(rule: &RuleInfo) -> String {
+    let mut description = format!(
+        "{} ({})\n重大度: {}\n分類: {}\n設定: [checker] {}（既定値 {}）\n{}\n\n例:\n",
+        rule.name,
+        rule.id,
+        severity_label(Some(rule.severity)),
+        rule.category.label(),
+        rule.setting,
+        rule.default_enabled,
+        rule.description
+    );
+    for example in rule.examples {
+        description.push_str(&format!("  ✗ {}\n  ✓ {}\n", example.incorrect, example.correct));
+    }
+    description.push_str(&format!("\n{}\n\n詳細: {}\n", rule.explanation(), rule.doc_url()));
+    description
+}
+
+/// Run `init`: write a commented `mozuku.toml` to the current directory
+pub fn init(args: &InitArgs) -> Result<ExitCode> {
+    let path = Path::new("mozuku.toml");
+    if path.exists() && !args.force {
+        return Err(anyhow!("{} は既に存在します（上書きするには --force）", path.display()));
+    }
+    std::fs::write(path, Config::template(args.preset))
+        .with_context(|| format!("{} に書き込めません", path.display()))?;
+    eprintln!("{} を作成しました", path.display());
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Run `config`
+pub async fn config(args: &ConfigArgs) -> Result<ExitCode> {
+    match &args.command {
+        ConfigCommand::Validate(args) => validate_config(args).await,
+        ConfigCommand::FalsePositive(args) => mark_false_positive(args),
+    }
+}
+
+/// Run `config false-positive`: add the rule and word to
+/// `[[checker.false_positives]]` of the configuration file
+fn mark_false_positive(args: &FalsePositiveArgs) -> Result<ExitCode> {
+    if args.word.is_empty() {
+        return Err(anyhow!("誤検出とする語を指定してください"));
+    }
+    // Pattern rules and plugins have IDs of their own, so unknown IDs are kept
+    if rules::find_rule(&args.rule).is_none() {
+        eprintln!(
+            "warning: {} は組み込みのルールではありません（パターンルールやプラグインの ID として追加します）",
+            args.rule
+        );
+    }
+    Config::add_false_positive(&args.config, &args.rule, &args.word)
+        .with_context(|| format!("{} を更新できません", args.config.display()))?;
+    eprintln!(
+        "「{}」を {} の誤検出として {} に追加しました",
+        args.word,
+        args.rule,
+        args.config.display()
+    );
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Run `config validate`: print the problems found in a configuration file
+/// and fail if any of them is an error
+async fn validate_config(args: &ValidateArgs) -> Result<ExitCode> {
+    let path = &args.path;
+    let content = std::fs::read_to_string(path).with_context(|| format!("{} を読み込めません", path.display()))?;
+    let base = path
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let (config, issues) = Config::validate(&content, base);
+
+    for issue in &issues {
+        let level = match issue.level {
+            IssueLevel::Error => "error",
+            IssueLevel::Warning => "warning",
+        };
+        println!("{}: {}: {}", path.display(), level, issue.message);
+    }
+    let mut failed = issues.iter().any(|issue| issue.level == IssueLevel::Error);
+
+    if args.check_llm {
+        match config {
+            Some(config) if !failed => match LlmClient::new(config).check_connection().await {
+                Ok(()) => println!("{}: LLM プロバイダに接続できました", path.display()),
+                Err(e) => {
+                    println!("{}: error: LLM プロバイダに接続できません: {:#}", path.display(), e);
+                    failed = true;
+                }
+            },
+            _ => eprintln!("設定にエラーがあるため LLM の接続確認を省略しました"),
+        }
+    }
+
+    if failed {
+        Ok(ExitCode::FAILURE)
+    } else {
+        if issues.is_empty() {
+            println!("{}: 問題は見つかりませんでした", path.display());
+        }
+        Ok(ExitCode::SUCCESS)
+    }
+}
+
+/// Run `dict`
+pub fn dict(args: &DictArgs) -> Result<ExitCode> {
+    match &args.command {
+        DictCommand::Build(args) => build_dictionary(args),
+    }
+}
+
+/// Run `dict build`: compile the user dictionary CSV so that the analyzer
+/// loads the binary dictionary instead of compiling the CSV on startup
+fn build_dictionary(args: &DictBuildArgs) -> Result<ExitCode> {
+    let path = match &args.path {
+        Some(path) => path.clone(),
+        None => load_config(args.config.as_ref())?
+            .0
+            .dictionary
+            .user_dictionary
+            .ok_or_else(|| anyhow!("ユーザー辞書が指定されていません（引数または [dictionary] user_dictionary）"))?,
+    };
+    if !path.is_file() {
+        return Err(anyhow!("ユーザー辞書 {} が見つかりません", path.display()));
+    }
+    let output = MorphologicalAnalyzer::build_user_dictionary(&path)?;
+    eprintln!("{} を {} にコンパイルしました", path.display(), output.display());
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Load the configuration given with `--config`, or the default one, and
+/// its exclude patterns, which are relative to the directory of the file
+fn load_config(path: Option<&PathBuf>) -> Result<(Config, Exclusions)> {
+    let config = match path {
+        Some(path) if !path.exists() => return Err(anyhow!("設定ファイル {} が見つかりません", path.display())),
+        Some(path) => Config::load(path).with_context(|| format!("{} を読み込めません", path.display()))?,
+        None => Config::load_from_default(),
+    };
+    let config_dir = path
+        .and_then(|path| path.parent())
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let exclusions = Exclusions::new(config_dir, &config.checker.exclude)?;
+    Ok((config, exclusions))
+}
+
+/// Wait for file changes after the first check and re-check only the changed
+/// files, reprinting the diagnostics of all files each time
+fn watch(linter: &Linter, args: &CheckArgs, exclusions: &Exclusions) -> Result<ExitCode> {
+    use notify::{RecursiveMode, Watcher};
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx).context("ファイル監視を開始できません")?;
+    for path in &args.paths {
+        watcher
+            .watch(path, RecursiveMode::Recursive)
+            .with_context(|| format!("{} を監視できません", path.display()))?;
+    }
+
+    let mut reports: BTreeMap<PathBuf, FileReport> = BTreeMap::new();
+    let mut changed: HashSet<PathBuf> = HashSet::new();
+    let mut first = true;
+    loop {
+        let files = collect_targets(&args.paths, exclusions).unwrap_or_default();
+        let targets: HashSet<&PathBuf> = files.iter().collect();
+        let before = reports.len();
+        reports.retain(|path, _| targets.contains(path));
+        let mut updated = first || reports.len() != before;
+
+        for path in &files {
+            let modified = canonical(path).is_some_and(|path| changed.contains(&path));
+            if reports.contains_key(path) && !modified {
+                continue;
+            }
+            match linter.check_file(path) {
+                Ok(report) => {
+                    reports.insert(path.clone(), report);
+                }
+                Err(e) => {
+                    tracing::debug!("{:#}", e);
+                    reports.remove(path);
+                }
+            }
+            updated = true;
+        }
+
+        if updated {
+            print_watch_results(args.format, &reports);
+        }
+        first = false;
+
+        // Wait for a change, then collect the burst of events an editor save produces
+        changed.clear();
+        let event = rx.recv().map_err(|_| anyhow!("ファイル監視が終了しました"))?;
+        let mut events = vec![event];
+        while let Ok(event) = rx.recv_timeout(WATCH_DEBOUNCE) {
+            events.push(event);
+        }
+        for event in events {
+            match event {
+                // Reading the files while checking them produces access events
+                Ok(event) if event.kind.is_access() => {}
+                Ok(event) => changed.extend(event.paths.iter().filter_map(|path| canonical(path))),
+                Err(e) => tracing::warn!("File watch error: {}", e),
+            }
+        }
+    }
+}
+
+/// How long to wait for further events after a file change
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(200);
+
+fn print_watch_results(format: OutputFormat, reports: &BTreeMap<PathBuf, FileReport>) {
+    let reports: Vec<FileReport> = reports.values().cloned().collect();
+    if std::io::stdout().is_terminal() {
+        // Clear the screen so that only the current results are shown
+        print!("\x1b[2J\x1b[H");
+    }
+    print!("{}", report::render(format, &reports));
+    let total: usize = reports.iter().map(|report| report.diagnostics.len()).sum();
+    eprintln!(
+        "{}ファイル中 {}件の指摘（変更を監視中。Ctrl+C で終了）",
+        reports.len(),
+        total
+    );
+}
+
+fn canonical(path: &Path) -> Option<PathBuf> {
+    std::fs::canonicalize(path).ok()
+}
+
+/// Files to check: directories are expanded to the checkable files under them
+/// that are neither ignored by `.gitignore` nor excluded, files are checked as given
+pub fn collect_targets(paths: &[PathBuf], exclusions: &Exclusions) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            files.extend(collect_files(path, exclusions));
+        } else if path.is_file() {
+            files.push(path.clone());
+        } else {
+            return Err(anyhow!("{} が見つかりません", path.display()));
+        }
+    }
+    files.dedup();
+    Ok(files)
+}
+
+/// Apply the best fix of each diagnostic to a text (see [`fix_all`]).
+///
+/// Returns the fixed text and the number of applied fixes.
+pub fn apply_fixes(content: &str, diagnostics: &[Diagnostic]) -> (String, usize) {
+    let index = LineIndex::new(content);
+    let fixes = fix_all(content, diagnostics);
+
+    let mut fixed = String::with_capacity(content.len());
+    let mut last = 0;
+    for (_, fix) in &fixes {
+        let start = index.offset(fix.range.start);
+        fixed.push_str(&content[last..start]);
+        fixed.push_str(&fix.new_text);
+        last = index.offset(fix.range.end);
+    }
+    fixed.push_str(&content[last..]);
+    (fixed, fixes.len())
+}
+
+/// Unified diff of a fixed file, with one hunk per run of changed lines
+pub fn unified_diff(path: &Path, old: &str, new: &str) -> String {
+    let old_lines: Vec<&str> = old.split_inclusive('\n').collect();
+    let new_lines: Vec<&str> = new.split_inclusive('\n').collect();
+
+    // Lines shared at the start and end are unchanged; fixes do not move text
+    // across lines, so whatever lies between is compared line by line when the
+    // line counts match, and replaced as a whole otherwise
+    let mut hunks: Vec<(std::ops::Range<usize>, std::ops::Range<usize>)> = Vec::new();
+    if old_lines.len() == new_lines.len() {
+        let mut i = 0;
+        while i < old_lines.len() {
+            if old_lines[i] == new_lines[i] {
+                i += 1;
+                continue;
+            }
+            let start = i;
+            while i < old_lines.len() && old_lines[i] != new_lines[i] {
+                i += 1;
+            }
+            hunks.push((start..i, start..i));
+        }
+    } else {
+        let prefix = old_lines.iter().zip(&new_lines).take_while(|(a, b)| a == b).count();
+        let suffix = old_lines[prefix..]
+            .iter()
+            .rev()
+            .zip(new_lines[prefix..].iter().rev())
+            .take_while(|(a, b)| a == b)
+            .count();
+        hunks.push((prefix..old_lines.len() - suffix, prefix..new_lines.len() - suffix));
+    }
+
+    let mut diff = format!("--- a/{0}\n+++ b/{0}\n", path.display());
+    for (old_range, new_range) in hunks {
+        diff.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            old_range.start + 1,
+            old_range.len(),
+            new_range.start + 1,
+            new_range.len()
+        ));
+        for line in &old_lines[old_range] {
+            diff.push_str(&format!("-{}", line));
+            if !line.ends_with('\n') {
+                diff.push_str("\n\\ No newline at end of file\n");
+            }
+        }
+        for line in &new_lines[new_range] {
+            diff.push_str(&format!("+{}", line));
+            if !line.ends_with('\n') {
+                diff.push_str("\n\\ No newline at end of file\n");
+            }
+        }
+    }
+    diff
+}
+
+/// One diagnostic as `path:line:column: severity: message [code]`,
+/// with a 1-based line and column counted in characters
+pub fn format_diagnostic(path: &Path, content: &str, diagnostic: &Diagnostic) -> String {
+    let (line, column) = display_position(content, diagnostic.range.start);
+    let mut formatted = format!(
+        "{}:{}:{}: {}: {}",
+        path.display(),
+        line,
+        column,
+        severity_label(diagnostic.severity),
+        diagnostic.message
+    );
+    if let Some(code) = diagnostic_code(diagnostic) {
+        formatted.push_str(&format!(" [{}]", code));
+    }
+    formatted
+}
+
+/// Diagnostic code (rule ID) as a string
+pub fn diagnostic_code(diagnostic: &Diagnostic) -> Option<String> {
+    diagnostic.code.as_ref().map(|code| match code {
+        NumberOrString::Number(n) => n.to_string(),
+        NumberOrString::String(s) => s.clone(),
+    })
+}
+
+/// 1-based line and character column of an LSP position
+pub fn display_position(content: &str, position: Position) -> (usize, usize) {
+    let offset = position_to_byte_offset(content, position);
+    let line_start = content[..offset].rfind('\n').map_or(0, |i| i + 1);
+    (
+        position.line as usize + 1,
+        content[line_start..offset].chars().count() + 1,
+    )
+}
+
+/// Lower-case severity name, as used by compilers
+pub fn severity_label(severity: Option<DiagnosticSeverity>) -> &'static str {
+    match severity {
+        Some(DiagnosticSeverity::ERROR) => "error",
+        Some(DiagnosticSeverity::INFORMATION) => "info",
+        Some(DiagnosticSeverity::HINT) => "hint",
+        _ => "warning",
+    }
+}
+
+/// File type of a path from its extension, plain text if unknown
+fn file_type_of(path: &Path) -> FileType {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(FileType::from_extension)
+        .unwrap_or(FileType::PlainText)
+}
+
+/// `file:` URI of a path, relative paths being resolved against the current directory
+fn file_uri(path: &Path) -> Url {
+    std::path::absolute(path)
+        .ok()
+        .and_then(|path| Url::from_file_path(path).ok())
+        .unwrap_or_else(|| Url::parse("mozuku:text").expect("valid URI"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checker::{fix_data, Fix};
+    use tower_lsp::lsp_types::Range;
+
+    fn diagnostic(start: Position, severity: DiagnosticSeverity, code: &str) -> Diagnostic {
+        Diagnostic {
+            range: Range { start, end: start },
+            severity: Some(severity),
+            code: Some(NumberOrString::String(code.to_string())),
+            message: "ら抜き言葉です".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_display_position() {
+        let content = "一行目\n𠮷野家で見れる";
+
+        // "𠮷" is two UTF-16 code units but one character
+        assert_eq!(display_position(content, Position::new(1, 5)), (2, 5));
+        assert_eq!(display_position(content, Position::new(0, 0)), (1, 1));
+    }
+
+    #[test]
+    fn test_format_diagnostic() {
+        let content = "今日は\n景色が見れる。";
+        let diag = diagnostic(Position::new(1, 3), DiagnosticSeverity::WARNING, "ra-nuki");
+
+        assert_eq!(
+            format_diagnostic(Path::new("docs/a.md"), content, &diag),
+            "docs/a.md:2:4: warning: ら抜き言葉です [ra-nuki]"
+        );
+    }
+
+    fn fixable(range: Range, new_text: &str) -> Diagnostic {
+        Diagnostic {
+            range,
+            data: fix_data(vec![Fix::new(range, new_text)]),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_apply_fixes() {
+        let content = "ＡＢＣを見れる\n次の行,です";
+        let diagnostics = vec![
+            fixable(Range::new(Position::new(1, 3), Position::new(1, 4)), "、"),
+            fixable(Range::new(Position::new(0, 0), Position::new(0, 3)), "ABC"),
+            fixable(Range::new(Position::new(0, 4), Position::new(0, 7)), "見られる"),
+            // Overlaps the fix above
+            fixable(Range::new(Position::new(0, 5), Position::new(0, 6)), "ら"),
+            // Has no fix
+            diagnostic(Position::new(1, 0), DiagnosticSeverity::WARNING, "x"),
+        ];
+
+        let (fixed, applied) = apply_fixes(content, &diagnostics);
+        assert_eq!(fixed, "ABCを見られる\n次の行、です");
+        assert_eq!(applied, 3);
+        assert_eq!(apply_fixes(content, &[]), (content.to_string(), 0));
+    }
+
+    #[test]
+    fn test_unified_diff() {
+        let diff = unified_diff(Path::new("a.md"), "一\nＡＢ\n三\n四", "一\nAB\n三\n四。");
+
+        assert_eq!(
+            diff,
+            "--- a/a.md\n+++ b/a.md\n@@ -2,1 +2,1 @@\n-ＡＢ\n+AB\n\
+             @@ -4,1 +4,1 @@\n-四\n\\ No newline at end of file\n+四。\n\\ No newline at end of file\n"
+        );
+    }
+
+    #[test]
+    fn test_severity_label() {
+        assert_eq!(severity_label(Some(DiagnosticSeverity::ERROR)), "error");
+        assert_eq!(severity_label(Some(DiagnosticSeverity::HINT)), "hint");
+        assert_eq!(severity_label(None), "warning");
+    }
+
+    #[test]
+    fn test_benchmark_to_text() {
+        let mut benchmark = Benchmark {
+            extract: Duration::from_millis(2),
+            ..Default::default()
+        };
+        benchmark.check.tokenize = Duration::from_millis(10);
+        let rules = &mut benchmark.check.rules;
+        rules.insert("ra-nuki", Duration::from_millis(1));
+        rules.insert("notation-variance", Duration::from_millis(3));
+
+        let text = benchmark.to_text(2);
+        let lines: Vec<&str> = text.lines().collect();
+
+        assert_eq!(lines.len(), 6);
+        assert_eq!(lines[0], "      1.00 ms  抽出");
+        assert_eq!(lines[2], "      2.00 ms  ルール");
+        assert_eq!(lines[3], "      1.50 ms    notation-variance");
+        assert_eq!(lines[4], "      0.50 ms    ra-nuki");
+        assert_eq!(lines[5], "      8.00 ms  合計");
+    }
+
+    #[test]
+    fn test_list_rules() {
+        let list = list_rules(None);
+
+        assert!(list.starts_with("ra-nuki               warning  grammar      ら抜き言葉: "));
+        assert!(list.contains("例: 食べれる → 食べられる\n"));
+        assert!(list.contains("（既定で無効）\n"));
+        assert_eq!(list.lines().count(), RULES.len() * 2);
+
+        let notation = list_rules(Some(RuleCategory::Notation));
+        assert!(notation.starts_with("notation-variance"));
+        assert!(!notation.contains("ra-nuki"));
+    }
+
+    #[test]
+    fn test_describe_rule() {
+        let description = describe_rule(rules::find_rule("i-nuki").unwrap());
+
+        assert!(description.starts_with("い抜き言葉 (i-nuki)\n重大度: hint\n分類: 文法\n"));
+        assert!(description.contains("設定: [checker] i_nuki（既定値 true）\n"));
+        assert!(description.contains("  ✗ 読んでる\n  ✓ 読んでいる\n"));
+        assert!(description.contains("**直し方**"));
+        assert!(description.ends_with("rules.md#i-nuki\n"));
+    }
+
+    #[test]
+    fn test_fail_on() {
+        assert!(FailOn::Hint.fails(Some(DiagnosticSeverity::HINT)));
+        assert!(FailOn::Warning.fails(Some(DiagnosticSeverity::ERROR)));
+        assert!(FailOn::Warning.fails(Some(DiagnosticSeverity::WARNING)));
+        assert!(FailOn::Warning.fails(None));
+        assert!(!FailOn::Warning.fails(Some(DiagnosticSeverity::INFORMATION)));
+        assert!(!FailOn::Error.fails(Some(DiagnosticSeverity::WARNING)));
+        assert!(!FailOn::Never.fails(Some(DiagnosticSeverity::ERROR)));
+    }
+
+    #[test]
+    fn test_collect_targets() {
+        let root = std::env::temp_dir().join(format!("mozuku-cli-test-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("docs")).unwrap();
+        std::fs::write(root.join("docs/guide.md"), "ガイド").unwrap();
+        std::fs::write(root.join("notes.txt"), "メモ").unwrap();
+
+        std::fs::write(root.join("docs/draft.md"), "下書き").unwrap();
+        std::fs::write(root.join("draft.txt"), "下書き").unwrap();
+
+        let exclusions = Exclusions::new(&root, &["draft.*".to_string()]).unwrap();
+        let targets = [root.join("docs"), root.join("notes.txt"), root.join("draft.txt")];
+        let files = collect_targets(&targets, &exclusions).unwrap();
+        // Excluded files are skipped in directories but checked when given explicitly
+        assert_eq!(
+            files,
+            vec![
+                root.join("docs/guide.md"),
+                root.join("notes.txt"),
+                root.join("draft.txt")
+            ]
+        );
+        assert!(collect_targets(&[root.join("missing")], &exclusions).is_err());
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
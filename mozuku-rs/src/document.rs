@@ -0,0 +1,145 @@
+//! Open document state
+//!
+//! The text of an open document is kept in a rope so that incremental
+//! `didChange` edits are applied in place and LSP positions are converted
+//! through the rope's line index instead of scanning the whole text.
+
+use ropey::Rope;
+use tower_lsp::lsp_types::{Position, TextDocumentContentChangeEvent};
+
+use crate::extractor::FileType;
+
+/// Document state stored for each open file
+#[derive(Debug, Clone)]
+pub struct DocumentState {
+    /// Text of the document, edited in place by incremental changes
+    pub rope: Rope,
+    /// Text of the current version, rebuilt from the rope after each change
+    /// for the analyses that need contiguous text
+    pub content: String,
+    pub version: i32,
+    pub file_type: FileType,
+}
+
+impl DocumentState {
+    pub fn new(content: String, version: i32, file_type: FileType) -> Self {
+        Self {
+            rope: Rope::from_str(&content),
+            content,
+            version,
+            file_type,
+        }
+    }
+
+    /// Apply the content changes of a `didChange` notification in order.
+    /// Changes with a range edit the rope; changes without one replace the text.
+    pub fn apply_changes(&mut self, changes: Vec<TextDocumentContentChangeEvent>, version: i32) {
+        for change in changes {
+            match change.range {
+                Some(range) => {
+                    let start = self.rope.byte_to_char(self.offset_at(range.start));
+                    let end = self.rope.byte_to_char(self.offset_at(range.end)).max(start);
+                    self.rope.remove(start..end);
+                    self.rope.insert(start, &change.text);
+                }
+                None => self.rope = Rope::from_str(&change.text),
+            }
+        }
+        self.content = self.rope.to_string();
+        self.version = version;
+    }
+
+    /// Convert an LSP position to a byte offset, clamping like
+    /// [`crate::utf16::LineIndex::offset`]
+    pub fn offset_at(&self, position: Position) -> usize {
+        let line = position.line as usize;
+        if line >= self.rope.len_lines() {
+            return self.rope.len_bytes();
+        }
+        let start = self.rope.line_to_byte(line);
+        let line_text = std::borrow::Cow::<str>::from(self.rope.line(line));
+        let line_text = line_text.trim_end_matches('\n').trim_end_matches('\r');
+
+        let mut units = 0;
+        for (i, c) in line_text.char_indices() {
+            if units >= position.character as usize {
+                return start + i;
+            }
+            units += c.len_utf16();
+        }
+        start + line_text.len()
+    }
+
+    /// Convert a byte offset to an LSP position
+    pub fn position_at(&self, offset: usize) -> Position {
+        let char_index = self.rope.byte_to_char(offset.min(self.rope.len_bytes()));
+        let line = self.rope.char_to_line(char_index);
+        let line_start = self.rope.line_to_char(line);
+        Position {
+            line: line as u32,
+            character: (self.rope.char_to_utf16_cu(char_index) - self.rope.char_to_utf16_cu(line_start)) as u32,
+        }
+    }
+
+    /// Byte offset of the start of the line containing a byte offset
+    pub fn line_start(&self, offset: usize) -> usize {
+        let line = self.rope.byte_to_line(offset.min(self.rope.len_bytes()));
+        self.rope.line_to_byte(line)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utf16::LineIndex;
+    use tower_lsp::lsp_types::Range;
+
+    fn change(range: Option<((u32, u32), (u32, u32))>, text: &str) -> TextDocumentContentChangeEvent {
+        TextDocumentContentChangeEvent {
+            range: range.map(|((start_line, start_char), (end_line, end_char))| {
+                Range::new(Position::new(start_line, start_char), Position::new(end_line, end_char))
+            }),
+            range_length: None,
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_apply_changes() {
+        let mut doc = DocumentState::new("一行目\n二行目です\n".to_string(), 1, FileType::PlainText);
+
+        doc.apply_changes(
+            vec![
+                change(Some(((1, 3), (1, 5)), "でした")),
+                change(Some(((0, 0), (0, 0)), "😀")),
+                change(Some(((0, 5), (1, 0)), "")),
+            ],
+            2,
+        );
+
+        assert_eq!(doc.content, "😀一行目二行目でした\n");
+        assert_eq!(doc.rope.to_string(), doc.content);
+        assert_eq!(doc.version, 2);
+
+        doc.apply_changes(vec![change(None, "置き換え")], 3);
+        assert_eq!(doc.content, "置き換え");
+    }
+
+    #[test]
+    fn test_positions_match_line_index() {
+        let text = "😀絵文字\r\n二行目の𩸽です\n\n末尾";
+        let doc = DocumentState::new(text.to_string(), 1, FileType::PlainText);
+        let index = LineIndex::new(text);
+
+        for offset in 0..=text.len() + 1 {
+            assert_eq!(doc.position_at(offset), index.position(offset), "offset {}", offset);
+        }
+        for line in 0..6 {
+            for character in 0..12 {
+                let position = Position::new(line, character);
+                assert_eq!(doc.offset_at(position), index.offset(position), "{:?}", position);
+            }
+        }
+        assert_eq!(doc.line_start(text.find("です").unwrap()), text.find("二").unwrap());
+    }
+}
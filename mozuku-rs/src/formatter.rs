@@ -0,0 +1,116 @@
+//! On-type formatting of Japanese punctuation
+//!
+//! Converts "," and "." typed after Japanese text to "、" and "。", and "..."
+//! to "…".
+
+use crate::config::FormatConfig;
+use crate::dictionary::CharKind;
+
+/// Characters that trigger on-type formatting
+pub const TRIGGER_CHARACTERS: [&str; 2] = [",", "."];
+
+/// A replacement of the text just before the cursor
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Replacement {
+    /// Number of bytes before the cursor to replace
+    pub len: usize,
+    /// Replacement text
+    pub new_text: String,
+}
+
+/// Compute the replacement for a typed character.
+///
+/// `before` is the text of the line up to the cursor, including the typed
+/// character.
+pub fn format_on_type(before: &str, ch: char, config: &FormatConfig) -> Option<Replacement> {
+    if !config.enabled {
+        return None;
+    }
+    let preceding = before.strip_suffix(ch)?;
+
+    if ch == '.' && config.ellipsis {
+        // The first dot may already have been converted to "。"
+        let dots = [("..", true), ("。.", config.period)];
+        if let Some((prefix, _)) = dots.iter().find(|(prefix, on)| *on && preceding.ends_with(prefix)) {
+            return Some(Replacement {
+                len: prefix.len() + ch.len_utf8(),
+                new_text: "…".to_string(),
+            });
+        }
+    }
+
+    let new_text = match ch {
+        ',' if config.comma => "、",
+        '.' if config.period => "。",
+        _ => return None,
+    };
+    preceding.chars().next_back().filter(|&c| is_japanese(c))?;
+
+    Some(Replacement {
+        len: ch.len_utf8(),
+        new_text: new_text.to_string(),
+    })
+}
+
+/// Whether punctuation after a character should be Japanese
+fn is_japanese(c: char) -> bool {
+    matches!(CharKind::of(c), CharKind::Hiragana | CharKind::Katakana | CharKind::Kanji)
+        || matches!(c, '」' | '』' | '）' | '】')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn enabled() -> FormatConfig {
+        FormatConfig {
+            enabled: true,
+            ..Default::default()
+        }
+    }
+
+    fn format(before: &str, ch: char, config: &FormatConfig) -> Option<(usize, String)> {
+        format_on_type(before, ch, config).map(|r| (r.len, r.new_text))
+    }
+
+    #[test]
+    fn test_punctuation_after_japanese() {
+        let config = enabled();
+
+        assert_eq!(format("今日は,", ',', &config), Some((1, "、".to_string())));
+        assert_eq!(format("晴れ」.", '.', &config), Some((1, "。".to_string())));
+    }
+
+    #[test]
+    fn test_punctuation_after_ascii_is_kept() {
+        let config = enabled();
+
+        assert_eq!(format("1,", ',', &config), None);
+        assert_eq!(format("v1.", '.', &config), None);
+        assert_eq!(format(",", ',', &config), None);
+    }
+
+    #[test]
+    fn test_ellipsis() {
+        let config = enabled();
+
+        assert_eq!(format("えっと...", '.', &config), Some((3, "…".to_string())));
+        // The first dot was converted to 。 as it was typed
+        assert_eq!(format("えっと。.", '.', &config), None);
+        assert_eq!(format("えっと。..", '.', &config), Some((5, "…".to_string())));
+    }
+
+    #[test]
+    fn test_rules_can_be_disabled() {
+        let config = FormatConfig {
+            period: false,
+            ellipsis: false,
+            ..enabled()
+        };
+
+        assert_eq!(format("今日は,", ',', &config), Some((1, "、".to_string())));
+        assert_eq!(format("今日は.", '.', &config), None);
+        assert_eq!(format("今日は...", '.', &config), None);
+        assert_eq!(format("今日は,", ',', &FormatConfig::default()), None);
+    }
+}
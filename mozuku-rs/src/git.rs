@@ -0,0 +1,285 @@
+//! Lines changed since a git revision
+//!
+//! Used by `check --diff` to report only the diagnostics on added or
+//! modified lines, so that new changes can be reviewed without the existing
+//! diagnostics of the documents.
+
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::{Component, Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{anyhow, Context, Result};
+use tower_lsp::lsp_types::Diagnostic;
+
+/// Added or modified lines (0-based, end-exclusive ranges) of each changed file,
+/// with paths relative to the current directory
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChangedLines {
+    files: HashMap<PathBuf, Vec<Range<u32>>>,
+    /// Directory the paths are relative to, for looking up absolute paths
+    root: PathBuf,
+}
+
+impl ChangedLines {
+    /// Lines changed in the working tree since a revision (`git diff <revision>`)
+    pub fn since(revision: &str) -> Result<Self> {
+        let root = std::env::current_dir().context("カレントディレクトリを取得できません")?;
+        // Non-ASCII paths are written as is instead of as octal escapes
+        let output = Command::new("git")
+            .args(["-c", "core.quotePath=false"])
+            .args(["diff", "--relative", "--no-prefix", "--no-color"])
+            .args(["--no-ext-diff", "--unified=0"])
+            .arg(revision)
+            .arg("--")
+            .output()
+            .context("git を実行できません")?;
+        if !output.status.success() {
+            return Err(anyhow!(
+                "git diff {} に失敗しました: {}",
+                revision,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Ok(Self {
+            root,
+            ..Self::parse(&String::from_utf8_lossy(&output.stdout))
+        })
+    }
+
+    /// Parse the output of `git diff --no-prefix --unified=0`
+    pub fn parse(diff: &str) -> Self {
+        let mut files: HashMap<PathBuf, Vec<Range<u32>>> = HashMap::new();
+        let mut current: Option<PathBuf> = None;
+
+        for line in diff.lines() {
+            if let Some(path) = line.strip_prefix("+++ ") {
+                // git ends the line with a tab when the path contains spaces
+                let path = path.strip_suffix('\t').unwrap_or(path);
+                // Deleted files have no lines left to check
+                current = (path != "/dev/null").then(|| normalize(Path::new(&unquote(path))));
+            } else if let (Some(path), Some(hunk)) = (&current, line.strip_prefix("@@ ")) {
+                if let Some(lines) = hunk_new_lines(hunk) {
+                    if !lines.is_empty() {
+                        files.entry(path.clone()).or_default().push(lines);
+                    }
+                }
+            }
+        }
+
+        Self {
+            files,
+            root: PathBuf::new(),
+        }
+    }
+
+    /// Check whether a file has changed lines
+    pub fn contains_file(&self, path: &Path) -> bool {
+        self.files.contains_key(&self.relative(path))
+    }
+
+    /// Check whether a diagnostic touches a changed line of a file
+    pub fn touches(&self, path: &Path, diagnostic: &Diagnostic) -> bool {
+        let Some(lines) = self.files.get(&self.relative(path)) else {
+            return false;
+        };
+        let (start, end) = (diagnostic.range.start.line, diagnostic.range.end.line);
+        lines.iter().any(|lines| lines.start <= end && start < lines.end)
+    }
+
+    /// Path given on the command line relative to the directory of the diff.
+    /// Absolute paths are also resolved through symbolic links.
+    fn relative(&self, path: &Path) -> PathBuf {
+        if path.is_absolute() {
+            if let Ok(relative) = path.strip_prefix(&self.root) {
+                return normalize(relative);
+            }
+            if let Some(relative) = path
+                .canonicalize()
+                .ok()
+                .and_then(|path| path.strip_prefix(&self.root).ok().map(Path::to_path_buf))
+            {
+                return normalize(&relative);
+            }
+        }
+        normalize(path)
+    }
+}
+
+/// Lines of the new file in a hunk header (`-a,b +c,d @@ ...`)
+fn hunk_new_lines(hunk: &str) -> Option<Range<u32>> {
+    let new = hunk.split_whitespace().find_map(|part| part.strip_prefix('+'))?;
+    let (start, count) = match new.split_once(',') {
+        Some((start, count)) => (start.parse::<u32>().ok()?, count.parse::<u32>().ok()?),
+        None => (new.parse::<u32>().ok()?, 1),
+    };
+    // Lines are 1-based; a hunk without new lines has count 0
+    let start = start.saturating_sub(1);
+    Some(start..start + count)
+}
+
+/// Decode a path git quoted because of special characters, written as a
+/// C string literal with octal escapes for non-ASCII bytes
+fn unquote(path: &str) -> String {
+    let Some(quoted) = path.strip_prefix('"').and_then(|path| path.strip_suffix('"')) else {
+        return path.to_string();
+    };
+
+    let mut bytes = Vec::with_capacity(quoted.len());
+    let mut rest = quoted.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        rest = tail;
+        if byte != b'\\' {
+            bytes.push(byte);
+            continue;
+        }
+        let Some((&escape, tail)) = rest.split_first() else {
+            bytes.push(byte);
+            break;
+        };
+        rest = tail;
+        match escape {
+            b'0'..=b'7' => {
+                let digits = rest
+                    .iter()
+                    .take(2)
+                    .take_while(|digit| matches!(digit, b'0'..=b'7'))
+                    .count();
+                let octal = std::iter::once(escape).chain(rest[..digits].iter().copied());
+                bytes.push(octal.fold(0u8, |value, digit| value.wrapping_mul(8).wrapping_add(digit - b'0')));
+                rest = &rest[digits..];
+            }
+            b'a' => bytes.push(0x07),
+            b'b' => bytes.push(0x08),
+            b't' => bytes.push(b'\t'),
+            b'n' => bytes.push(b'\n'),
+            b'v' => bytes.push(0x0b),
+            b'f' => bytes.push(0x0c),
+            b'r' => bytes.push(b'\r'),
+            other => bytes.push(other),
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+/// Path without `.` components, for comparing paths given on the command line
+/// with paths in the diff
+fn normalize(path: &Path) -> PathBuf {
+    path.components()
+        .filter(|component| !matches!(component, Component::CurDir))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tower_lsp::lsp_types::{Position, Range as LspRange};
+
+    const DIFF: &str = "\
+diff --git docs/guide.md docs/guide.md
+index 1111111..2222222 100644
+--- docs/guide.md
++++ docs/guide.md
+@@ -3 +3 @@ 見出し
+-古い行
++新しい行
+@@ -10,0 +11,2 @@
++追加1
++追加2
+@@ -20,2 +21,0 @@
+-削除
+-削除
+diff --git old.md old.md
+deleted file mode 100644
+--- old.md
++++ /dev/null
+@@ -1 +0,0 @@
+-消えた
+";
+
+    fn on_lines(start: u32, end: u32) -> Diagnostic {
+        Diagnostic {
+            range: LspRange::new(Position::new(start, 0), Position::new(end, 1)),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let changes = ChangedLines::parse(DIFF);
+
+        assert_eq!(changes.files[Path::new("docs/guide.md")], vec![2..3, 10..12]);
+        assert!(!changes.contains_file(Path::new("old.md")));
+        assert!(changes.contains_file(Path::new("./docs/guide.md")));
+    }
+
+    #[test]
+    fn test_touches() {
+        let changes = ChangedLines::parse(DIFF);
+        let path = Path::new("./docs/guide.md");
+
+        assert!(changes.touches(path, &on_lines(2, 2)));
+        assert!(changes.touches(path, &on_lines(11, 11)));
+        assert!(changes.touches(path, &on_lines(8, 10)));
+        assert!(!changes.touches(path, &on_lines(3, 3)));
+        assert!(!changes.touches(path, &on_lines(12, 12)));
+        assert!(!changes.touches(Path::new("other.md"), &on_lines(2, 2)));
+    }
+
+    #[test]
+    fn test_parse_quoted_paths() {
+        let diff = "\
+diff --git \"docs/\\343\\202\\254\\343\\202\\244\\343\\203\\211.md\" \"docs/\\343\\202\\254\\343\\202\\244\\343\\203\\211.md\"
+--- \"docs/\\343\\202\\254\\343\\202\\244\\343\\203\\211.md\"
++++ \"docs/\\343\\202\\254\\343\\202\\244\\343\\203\\211.md\"
+@@ -1 +1 @@
+-古い行
++新しい行
+diff --git 使い方.md 使い方.md
+--- 使い方.md
++++ 使い方.md
+@@ -2 +2 @@
+-古い行
++新しい行
+diff --git my notes.md my notes.md
+--- my notes.md\t
++++ my notes.md\t
+@@ -3 +3 @@
+-古い行
++新しい行
+";
+        let changes = ChangedLines::parse(diff);
+
+        assert!(changes.contains_file(Path::new("docs/ガイド.md")));
+        assert!(changes.contains_file(Path::new("使い方.md")));
+        assert!(changes.contains_file(Path::new("my notes.md")));
+    }
+
+    #[test]
+    fn test_unquote() {
+        assert_eq!(unquote("plain.md"), "plain.md");
+        assert_eq!(unquote(r#""\344\275\277.md""#), "使.md");
+        assert_eq!(unquote(r#""a\"b\\c\td.md""#), "a\"b\\c\td.md");
+    }
+
+    #[test]
+    fn test_absolute_paths() {
+        let root = std::env::current_dir().unwrap();
+        let changes = ChangedLines {
+            root: root.clone(),
+            ..ChangedLines::parse(DIFF)
+        };
+
+        assert!(changes.contains_file(&root.join("docs/guide.md")));
+        assert!(changes.touches(&root.join("docs").join("guide.md"), &on_lines(2, 2)));
+        assert!(!changes.contains_file(&root.join("old.md")));
+    }
+
+    #[test]
+    fn test_hunk_new_lines() {
+        assert_eq!(hunk_new_lines("-1,2 +5,3 @@"), Some(4..7));
+        assert_eq!(hunk_new_lines("-1 +1 @@ heading"), Some(0..1));
+        assert_eq!(hunk_new_lines("-4,2 +3,0 @@"), Some(2..2));
+        assert_eq!(hunk_new_lines("garbage"), None);
+    }
+}
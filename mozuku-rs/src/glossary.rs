@@ -0,0 +1,129 @@
+//! Glossary (用語集) documents
+//!
+//! Collects terms from the headings of a glossary file so that occurrences in
+//! other documents can link to their definitions.
+
+use std::ops::Range;
+
+use crate::dictionary::find_term;
+
+/// Placeholder for the term in a heading format
+pub const TERM_PLACEHOLDER: &str = "{term}";
+
+/// A term defined in the glossary
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GlossaryEntry {
+    /// Term as written in the heading
+    pub term: String,
+    /// Line of the heading (0-based)
+    pub line: u32,
+}
+
+/// Terms defined in a glossary document
+#[derive(Debug, Clone, Default)]
+pub struct Glossary {
+    entries: Vec<GlossaryEntry>,
+}
+
+impl Glossary {
+    /// Parse a glossary, taking terms from lines that match the heading format
+    /// (e.g. `## {term}`)
+    pub fn parse(content: &str, heading_format: &str) -> Self {
+        let (prefix, suffix) = heading_format
+            .split_once(TERM_PLACEHOLDER)
+            .unwrap_or((heading_format, ""));
+
+        let entries = (0u32..)
+            .zip(content.lines())
+            .filter_map(|(line, text)| {
+                let term = text.trim_end().strip_prefix(prefix)?.strip_suffix(suffix)?.trim();
+                (!term.is_empty()).then(|| GlossaryEntry {
+                    term: term.to_string(),
+                    line,
+                })
+            })
+            .collect();
+
+        Self { entries }
+    }
+
+    /// Defined terms
+    pub fn entries(&self) -> &[GlossaryEntry] {
+        &self.entries
+    }
+
+    /// Find glossary terms in a text, preferring longer terms where they overlap.
+    /// Returns byte ranges sorted by position.
+    pub fn find_terms(&self, text: &str) -> Vec<(Range<usize>, &GlossaryEntry)> {
+        let mut entries: Vec<&GlossaryEntry> = self.entries.iter().collect();
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.term.len()));
+
+        let mut matches: Vec<(Range<usize>, &GlossaryEntry)> = Vec::new();
+        for entry in entries {
+            for start in find_term(text, &entry.term) {
+                let range = start..start + entry.term.len();
+                if matches.iter().all(|(m, _)| range.end <= m.start || m.end <= range.start) {
+                    matches.push((range, entry));
+                }
+            }
+        }
+
+        matches.sort_by_key(|(range, _)| range.start);
+        matches
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GLOSSARY: &str = "# 用語集\n\n## サーバー\n\n要求を処理する。\n\n## 認証サーバー\n\n## API\n";
+
+    #[test]
+    fn test_parse() {
+        let glossary = Glossary::parse(GLOSSARY, "## {term}");
+
+        assert_eq!(
+            glossary.entries(),
+            &[
+                GlossaryEntry {
+                    term: "サーバー".to_string(),
+                    line: 2
+                },
+                GlossaryEntry {
+                    term: "認証サーバー".to_string(),
+                    line: 6
+                },
+                GlossaryEntry {
+                    term: "API".to_string(),
+                    line: 8
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_custom_format() {
+        let glossary = Glossary::parse("【サーバー】\n要求を処理する。\n【 】\n", "【{term}】");
+
+        let terms: Vec<&str> = glossary.entries().iter().map(|e| e.term.as_str()).collect();
+        assert_eq!(terms, vec!["サーバー"]);
+    }
+
+    #[test]
+    fn test_find_terms_prefers_longer() {
+        let glossary = Glossary::parse(GLOSSARY, "## {term}");
+        let text = "認証サーバーとサーバーのAPIとAPIs";
+
+        let found: Vec<(&str, &str)> = glossary
+            .find_terms(text)
+            .into_iter()
+            .map(|(range, entry)| (&text[range], entry.term.as_str()))
+            .collect();
+
+        assert_eq!(
+            found,
+            vec![("認証サーバー", "認証サーバー"), ("サーバー", "サーバー"), ("API", "API")]
+        );
+    }
+}
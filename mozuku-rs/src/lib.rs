@@ -1,10 +1,30 @@
 //! MoZuku - Japanese proofreading Language Server
 //!
 //! A language server for Japanese text proofreading with morphological analysis.
+//! Analysis and checking live in the `mozuku-core` crate, re-exported here.
 
-pub mod analyzer;
-pub mod checker;
-pub mod config;
-pub mod extractor;
+#[cfg(feature = "plugins")]
+pub use mozuku_core::plugin;
+pub use mozuku_core::{
+    analyzer, checker, config, dictionary, extractor, notation, pattern, preset, readability, rules, spellcheck,
+    suppression, terms, textlint, utf16, workspace,
+};
+
+pub mod cache;
+pub mod cli;
+pub mod document;
+pub mod formatter;
+pub mod git;
+pub mod glossary;
 pub mod llm;
+pub mod lru;
+pub mod masking;
+pub mod protocol;
+pub mod rate_limit;
+pub mod report;
 pub mod server;
+pub mod sigv4;
+pub mod statistics;
+pub mod telemetry;
+pub mod transport;
+pub mod usage;
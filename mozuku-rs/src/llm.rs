@@ -1,17 +1,58 @@
 //! LLM client for Japanese text proofreading suggestions
 //!
-//! Supports Claude (Anthropic) and OpenAI APIs.
+//! Supports Claude (Anthropic, or via Amazon Bedrock), OpenAI and Azure OpenAI
+//! APIs, OpenAI-compatible servers (LM Studio, OpenRouter, vLLM, ...), and local
+//! models via Ollama.
 
 use anyhow::{anyhow, Result};
-use reqwest::Client;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, RETRY_AFTER};
+use reqwest::{Client, NoProxy, Proxy, RequestBuilder, Response, StatusCode, Url};
 use serde::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime};
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::sync::Semaphore;
 
-use crate::config::Config;
+use crate::analyzer::{split_paragraphs, split_sentences};
+use crate::cache::ResponseCache;
+use crate::config::{Config, LlmProxyConfig};
+use crate::masking::Masker;
+use crate::rate_limit::RateLimiter;
+use crate::sigv4::{sign, uri_encode, AwsCredentials, SigningParams};
+use crate::usage::{TokenUsage, UsageSummary, UsageTracker};
+
+/// Maximum number of characters sent to the LLM in a single proofreading request
+pub const MAX_CHUNK_CHARS: usize = 400;
+
+/// Timeout for establishing a connection to the API
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Hosts that bypass an explicitly configured proxy when `NO_PROXY` is not set
+/// (local servers such as Ollama)
+const DEFAULT_NO_PROXY: &str = "localhost,127.0.0.1,::1";
+
+/// Delay before the first retry
+const INITIAL_RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// Upper bound for the delay between retries
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
 
 /// LLM client for making API requests
 pub struct LlmClient {
     client: Client,
     config: Config,
+    /// Limits the number of requests in flight
+    semaphore: Semaphore,
+    rate_limiter: RateLimiter,
+    cache: ResponseCache,
+    usage: UsageTracker,
+    masker: Masker,
+}
+
+/// Reply of a provider
+struct Completion {
+    text: String,
+    /// Token counts reported by the provider
+    usage: Option<TokenUsage>,
 }
 
 /// Request for proofreading suggestions
@@ -23,11 +64,13 @@ pub struct ProofreadRequest {
     pub context: Option<String>,
     /// Specific issue to address (optional)
     pub issue: Option<String>,
+    /// Number of correction candidates to ask for
+    pub candidates: usize,
 }
 
-/// Response from proofreading
-#[derive(Debug, Clone)]
-pub struct ProofreadResponse {
+/// A single correction candidate
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ProofreadCandidate {
     /// Suggested correction
     pub suggestion: String,
     /// Explanation of the correction
@@ -36,12 +79,185 @@ pub struct ProofreadResponse {
     pub confidence: f32,
 }
 
+/// Response from proofreading
+#[derive(Debug, Clone)]
+pub struct ProofreadResponse {
+    /// Correction candidates, most confident first
+    pub candidates: Vec<ProofreadCandidate>,
+}
+
+impl ProofreadResponse {
+    /// The most confident candidate
+    pub fn best(&self) -> Option<&ProofreadCandidate> {
+        self.candidates.first()
+    }
+}
+
+/// An issue found by the AI diagnostics pass
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct SentenceIssue {
+    /// Index of the sentence in the request
+    pub index: usize,
+    /// Description of the problem
+    pub message: String,
+    /// Corrected sentence, if proposed
+    #[serde(default)]
+    pub suggestion: Option<String>,
+}
+
+/// An issue to fix in a batch request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchItem {
+    /// Text at the issue
+    pub text: String,
+    /// Description of the issue
+    pub issue: String,
+}
+
+/// Correction of one item of a batch request
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct BatchFix {
+    /// Index of the item in the request
+    pub index: usize,
+    /// Corrected text
+    pub suggestion: String,
+}
+
+#[derive(Deserialize)]
+struct BatchResponse {
+    #[serde(default)]
+    fixes: Vec<BatchFix>,
+}
+
+#[derive(Deserialize)]
+struct DiagnosisResponse {
+    #[serde(default)]
+    issues: Vec<SentenceIssue>,
+}
+
+/// Sentence-ending style of Japanese prose
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum WritingStyle {
+    /// 敬体（です・ます調）
+    #[serde(rename = "desu-masu")]
+    Polite,
+    /// 常体（だ・である調）
+    #[serde(rename = "da-dearu")]
+    Plain,
+}
+
+impl WritingStyle {
+    /// Japanese name of the style
+    pub fn label(self) -> &'static str {
+        match self {
+            WritingStyle::Polite => "です・ます調",
+            WritingStyle::Plain => "だ・である調",
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ExplanationResponse {
+    explanation: String,
+}
+
+#[derive(Deserialize)]
+struct ConversionResponse {
+    text: String,
+}
+
+/// A unit of text sent to the LLM during whole-document proofreading
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextChunk {
+    /// Chunk content
+    pub text: String,
+    /// Start byte offset in the source text
+    pub start_byte: usize,
+    /// End byte offset in the source text (exclusive)
+    pub end_byte: usize,
+}
+
+/// JSON schema the reply must follow when structured output is enabled
+struct OutputSchema {
+    /// Name of the tool (Claude) or schema (OpenAI)
+    name: &'static str,
+    description: &'static str,
+    schema: serde_json::Value,
+}
+
+impl OutputSchema {
+    /// Claude tool whose input is the reply
+    fn claude_tool(&self) -> ClaudeTool {
+        ClaudeTool {
+            name: self.name.to_string(),
+            description: self.description.to_string(),
+            input_schema: self.schema.clone(),
+        }
+    }
+
+    /// Forces Claude to answer by calling the tool
+    fn claude_tool_choice(&self) -> ClaudeToolChoice {
+        ClaudeToolChoice {
+            kind: "tool".to_string(),
+            name: self.name.to_string(),
+        }
+    }
+
+    /// OpenAI `response_format` with a strict JSON schema
+    fn openai_response_format(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "json_schema",
+            "json_schema": {
+                "name": self.name,
+                "description": self.description,
+                "strict": true,
+                "schema": self.schema,
+            }
+        })
+    }
+}
+
 // Claude API types
 #[derive(Serialize)]
 struct ClaudeRequest {
     model: String,
     max_tokens: u32,
+    #[serde(flatten)]
+    params: ClaudeParams,
     messages: Vec<ClaudeMessage>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tools: Vec<ClaudeTool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_choice: Option<ClaudeToolChoice>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    stream: bool,
+}
+
+/// Generation parameters of the Anthropic Messages API
+#[derive(Serialize)]
+struct ClaudeParams {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    system: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    stop_sequences: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct ClaudeTool {
+    name: String,
+    description: String,
+    input_schema: serde_json::Value,
+}
+
+#[derive(Serialize)]
+struct ClaudeToolChoice {
+    #[serde(rename = "type")]
+    kind: String,
+    name: String,
 }
 
 #[derive(Serialize)]
@@ -53,11 +269,68 @@ struct ClaudeMessage {
 #[derive(Deserialize)]
 struct ClaudeResponse {
     content: Vec<ClaudeContent>,
+    #[serde(default)]
+    usage: Option<ClaudeUsage>,
 }
 
 #[derive(Deserialize)]
-struct ClaudeContent {
-    text: String,
+struct ClaudeUsage {
+    #[serde(default)]
+    input_tokens: u64,
+    #[serde(default)]
+    output_tokens: u64,
+}
+
+impl From<ClaudeUsage> for TokenUsage {
+    fn from(usage: ClaudeUsage) -> Self {
+        TokenUsage {
+            input_tokens: usage.input_tokens,
+            output_tokens: usage.output_tokens,
+        }
+    }
+}
+
+impl ClaudeResponse {
+    /// Reply text: the tool input when the tool was called, otherwise the text
+    fn output(&self) -> Option<String> {
+        let tool_input = self.content.iter().find_map(|c| match c {
+            ClaudeContent::ToolUse { input } => Some(input.to_string()),
+            _ => None,
+        });
+        tool_input.or_else(|| {
+            self.content.iter().find_map(|c| match c {
+                ClaudeContent::Text { text } => Some(text.clone()),
+                _ => None,
+            })
+        })
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ClaudeContent {
+    Text {
+        text: String,
+    },
+    ToolUse {
+        input: serde_json::Value,
+    },
+    #[serde(other)]
+    Other,
+}
+
+// Bedrock API types (Anthropic Messages API; the response is a ClaudeResponse)
+#[derive(Serialize)]
+struct BedrockRequest {
+    anthropic_version: String,
+    max_tokens: u32,
+    #[serde(flatten)]
+    params: ClaudeParams,
+    messages: Vec<ClaudeMessage>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tools: Vec<ClaudeTool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_choice: Option<ClaudeToolChoice>,
 }
 
 // OpenAI API types
@@ -66,6 +339,19 @@ struct OpenAiRequest {
     model: String,
     messages: Vec<OpenAiMessage>,
     max_tokens: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    stop: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_format: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    stream: bool,
+    /// Asks for token counts in the last streamed chunk
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream_options: Option<serde_json::Value>,
 }
 
 #[derive(Serialize)]
@@ -77,6 +363,25 @@ struct OpenAiMessage {
 #[derive(Deserialize)]
 struct OpenAiResponse {
     choices: Vec<OpenAiChoice>,
+    #[serde(default)]
+    usage: Option<OpenAiUsage>,
+}
+
+#[derive(Deserialize)]
+struct OpenAiUsage {
+    #[serde(default)]
+    prompt_tokens: u64,
+    #[serde(default)]
+    completion_tokens: u64,
+}
+
+impl From<OpenAiUsage> for TokenUsage {
+    fn from(usage: OpenAiUsage) -> Self {
+        TokenUsage {
+            input_tokens: usage.prompt_tokens,
+            output_tokens: usage.completion_tokens,
+        }
+    }
 }
 
 #[derive(Deserialize)]
@@ -89,42 +394,373 @@ struct OpenAiMessageResponse {
     content: String,
 }
 
-// Parsed suggestion from LLM response
+// Ollama API types
+#[derive(Serialize)]
+struct OllamaRequest {
+    model: String,
+    messages: Vec<OpenAiMessage>,
+    stream: bool,
+    /// `"json"`, or a JSON schema for structured output
+    format: serde_json::Value,
+    options: OllamaOptions,
+}
+
+#[derive(Serialize)]
+struct OllamaOptions {
+    num_predict: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    stop: Vec<String>,
+}
+
 #[derive(Deserialize)]
-struct ParsedSuggestion {
-    suggestion: String,
-    explanation: String,
-    confidence: f32,
+struct OllamaResponse {
+    message: OpenAiMessageResponse,
+    #[serde(default)]
+    prompt_eval_count: Option<u64>,
+    #[serde(default)]
+    eval_count: Option<u64>,
+}
+
+impl OllamaResponse {
+    fn usage(&self) -> Option<TokenUsage> {
+        Some(TokenUsage {
+            input_tokens: self.prompt_eval_count?,
+            output_tokens: self.eval_count.unwrap_or(0),
+        })
+    }
+}
+
+/// Wire format of a streamed response
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StreamFormat {
+    /// Server-sent events with `content_block_delta` events
+    Claude,
+    /// Server-sent events with `choices[].delta` chunks, ending with `[DONE]`
+    OpenAi,
+    /// Newline-delimited JSON messages
+    Ollama,
+}
+
+impl StreamFormat {
+    /// Text delta carried by one line of the stream
+    fn delta(self, line: &str) -> Option<String> {
+        let json = match self {
+            StreamFormat::Claude | StreamFormat::OpenAi => line.strip_prefix("data:")?.trim(),
+            StreamFormat::Ollama => line,
+        };
+        if json.is_empty() || json == "[DONE]" {
+            return None;
+        }
+
+        let value: serde_json::Value = serde_json::from_str(json).ok()?;
+        let text = match self {
+            // Text, or the tool input JSON when the reply is a tool call
+            StreamFormat::Claude if value["type"] == "content_block_delta" => match &value["delta"]["text"] {
+                serde_json::Value::Null => &value["delta"]["partial_json"],
+                text => text,
+            },
+            StreamFormat::Claude => return None,
+            StreamFormat::OpenAi => &value["choices"][0]["delta"]["content"],
+            StreamFormat::Ollama => &value["message"]["content"],
+        };
+        text.as_str().map(str::to_string)
+    }
+
+    /// Token counts carried by one line of the stream (possibly only input or output)
+    fn usage(self, line: &str) -> Option<TokenUsage> {
+        let json = match self {
+            StreamFormat::Claude | StreamFormat::OpenAi => line.strip_prefix("data:")?.trim(),
+            StreamFormat::Ollama => line,
+        };
+        let value: serde_json::Value = serde_json::from_str(json).ok()?;
+        let count = |value: &serde_json::Value| value.as_u64().unwrap_or(0);
+
+        let usage = match self {
+            // message_start carries the input tokens, message_delta the output tokens
+            StreamFormat::Claude => {
+                let usage = if value["type"] == "message_start" {
+                    &value["message"]["usage"]
+                } else {
+                    &value["usage"]
+                };
+                usage.is_object().then(|| TokenUsage {
+                    input_tokens: count(&usage["input_tokens"]),
+                    output_tokens: count(&usage["output_tokens"]),
+                })?
+            }
+            StreamFormat::OpenAi => {
+                let usage = &value["usage"];
+                usage.is_object().then(|| TokenUsage {
+                    input_tokens: count(&usage["prompt_tokens"]),
+                    output_tokens: count(&usage["completion_tokens"]),
+                })?
+            }
+            StreamFormat::Ollama if value["done"] == true => TokenUsage {
+                input_tokens: count(&value["prompt_eval_count"]),
+                output_tokens: count(&value["eval_count"]),
+            },
+            StreamFormat::Ollama => return None,
+        };
+        Some(usage)
+    }
+}
+
+// Parsed suggestions from LLM response (a single object is accepted as one candidate)
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ParsedResponse {
+    Candidates { candidates: Vec<ProofreadCandidate> },
+    Single(ProofreadCandidate),
 }
 
 impl LlmClient {
     /// Create a new LLM client with the given configuration
     pub fn new(config: Config) -> Self {
+        let mut builder = Client::builder()
+            .timeout(config.llm_timeout())
+            .connect_timeout(CONNECT_TIMEOUT)
+            .default_headers(extra_headers(&config));
+        match proxy(&config.llm.proxy) {
+            Ok(Some(proxy)) => builder = builder.proxy(proxy),
+            // Without settings, reqwest uses the proxy environment variables itself
+            Ok(None) => {}
+            Err(e) => tracing::warn!("Ignoring invalid proxy settings: {}", e),
+        }
+        let client = builder.build().unwrap_or_default();
+        let semaphore = Semaphore::new(config.llm_max_concurrent_requests());
+        let rate_limiter = RateLimiter::new(config.llm.requests_per_minute, config.llm.tokens_per_minute);
+        let cache = ResponseCache::new(&config.llm.cache);
+        let usage = UsageTracker::new(&config.llm.usage);
+        if usage.budget_unenforceable(&config.llm.provider, &config.get_model()) {
+            tracing::warn!(
+                "The price of {} is unknown, so it cannot count towards the monthly budget; \
+                 set llm.usage.input_price and output_price to use it",
+                config.get_model()
+            );
+        }
+        let masker = Masker::new(&config.llm.masking);
         Self {
-            client: Client::new(),
+            client,
             config,
+            semaphore,
+            rate_limiter,
+            cache,
+            usage,
+            masker,
         }
     }
 
-    /// Check if LLM integration is available
+    /// Check if LLM integration is available (configured, and within the monthly budget)
     pub fn is_available(&self) -> bool {
-        self.config.is_llm_enabled()
+        self.config.is_llm_enabled() && !self.budget_exceeded() && !self.budget_unenforceable()
+    }
+
+    /// Check whether AI features are stopped because the monthly budget cannot apply to the
+    /// model, its price being unknown
+    pub fn budget_unenforceable(&self) -> bool {
+        self.usage
+            .budget_unenforceable(&self.config.llm.provider, &self.config.get_model())
+    }
+
+    /// Check whether AI features are stopped because the monthly budget is used up
+    pub fn budget_exceeded(&self) -> bool {
+        self.usage.budget_exceeded()
+    }
+
+    /// Token counts and estimated cost of the current month
+    pub fn usage_summary(&self) -> UsageSummary {
+        self.usage.summary()
+    }
+
+    /// Number of correction candidates to offer as code actions
+    pub fn candidates(&self) -> usize {
+        self.config.llm.candidates.max(1)
     }
 
     /// Get proofreading suggestion for the given text
     pub async fn proofread(&self, request: ProofreadRequest) -> Result<ProofreadResponse> {
-        if !self.is_available() {
+        self.request_proofreading(request, None).await
+    }
+
+    /// Get proofreading suggestion for the given text, streaming the response.
+    ///
+    /// The first suggestion received so far is sent to `partial` as it grows.
+    /// Without streaming (disabled in the configuration, or unsupported by the
+    /// provider), only the final response is returned.
+    pub async fn proofread_streaming(
+        &self,
+        request: ProofreadRequest,
+        partial: &UnboundedSender<String>,
+    ) -> Result<ProofreadResponse> {
+        let partial = self.config.llm.stream.then_some(partial);
+        self.request_proofreading(request, partial).await
+    }
+
+    async fn request_proofreading(
+        &self,
+        request: ProofreadRequest,
+        partial: Option<&UnboundedSender<String>>,
+    ) -> Result<ProofreadResponse> {
+        let prompt = self.build_prompt(&request);
+        self.complete(&prompt, &proofread_schema(), partial, |response| {
+            self.parse_response(response)
+        })
+        .await
+    }
+
+    /// Get corrections for several issues at once, splitting them into requests
+    /// of at most `batch_size` items. Items without a correction are left out.
+    pub async fn proofread_batch(&self, items: &[BatchItem]) -> Result<Vec<BatchFix>> {
+        let mut fixes = Vec::new();
+        for (i, batch) in items.chunks(self.config.llm.batch_size.max(1)).enumerate() {
+            let offset = i * self.config.llm.batch_size.max(1);
+            let prompt = self.build_batch_prompt(batch);
+            let batch_fixes = self
+                .complete(&prompt, &batch_schema(), None, |response| {
+                    self.parse_batch(response, batch)
+                })
+                .await?;
+            fixes.extend(batch_fixes.into_iter().map(|fix| BatchFix {
+                index: offset + fix.index,
+                ..fix
+            }));
+        }
+        Ok(fixes)
+    }
+
+    /// Find issues that rules cannot detect (word order, flow between
+    /// sentences, tone) in a batch of sentences
+    pub async fn diagnose(&self, sentences: &[String]) -> Result<Vec<SentenceIssue>> {
+        let prompt = self.build_diagnosis_prompt(sentences);
+        self.complete(&prompt, &diagnosis_schema(), None, |response| {
+            self.parse_diagnosis(response, sentences.len())
+        })
+        .await
+    }
+
+    /// Explain why the text at an issue is a problem, with the line it appears in as context
+    pub async fn explain(&self, text: &str, issue: &str, context: &str) -> Result<String> {
+        let prompt = self.build_explanation_prompt(text, issue, context);
+        self.complete(&prompt, &explanation_schema(), None, |response| {
+            self.parse_explanation(response)
+        })
+        .await
+    }
+
+    /// Rewrite a paragraph in the given writing style, keeping its content
+    pub async fn convert_style(&self, text: &str, style: WritingStyle) -> Result<String> {
+        let prompt = self.build_conversion_prompt(text, style);
+        self.complete(&prompt, &conversion_schema(), None, |response| {
+            self.parse_conversion(response)
+        })
+        .await
+    }
+
+    /// Send a prompt to the configured provider and parse the reply, using the
+    /// cache and respecting the concurrency and rate limits.
+    /// The reply is constrained to `schema` unless structured output is disabled.
+    /// Sensitive text is masked in the request and restored in the reply.
+    /// Only replies that parse are cached.
+    async fn complete<T>(
+        &self,
+        prompt: &str,
+        schema: &OutputSchema,
+        partial: Option<&UnboundedSender<String>>,
+        parse: impl Fn(&str) -> Result<T>,
+    ) -> Result<T> {
+        if !self.config.is_llm_enabled() {
             return Err(anyhow!("LLM integration is not configured"));
         }
+        if self.budget_exceeded() {
+            return Err(anyhow!("Monthly LLM budget exceeded"));
+        }
+        if self.budget_unenforceable() {
+            return Err(anyhow!(
+                "Monthly LLM budget is set but the price of {} is unknown",
+                self.config.get_model()
+            ));
+        }
 
-        let prompt = self.build_prompt(&request);
-        let response = match self.config.llm.provider.as_str() {
-            "claude" => self.call_claude(&prompt).await?,
-            "openai" => self.call_openai(&prompt).await?,
-            _ => return Err(anyhow!("Unknown LLM provider: {}", self.config.llm.provider)),
-        };
+        // Replies depend on the system prompt and the sampling settings as
+        // much as on the prompt itself
+        let llm = &self.config.llm;
+        let parameters = serde_json::json!({
+            "system_prompt": llm.system_prompt,
+            "max_tokens": llm.max_tokens,
+            "temperature": llm.temperature,
+            "top_p": llm.top_p,
+            "stop": llm.stop,
+            "structured_output": llm.structured_output,
+        })
+        .to_string();
+        let cache_key = ResponseCache::key(&[&llm.provider, &self.config.get_model(), &parameters, prompt]);
+        if let Some(cached) = self.cache.get(&cache_key) {
+            match parse(&cached) {
+                Ok(parsed) => return Ok(parsed),
+                Err(e) => tracing::debug!("Ignoring unusable cached LLM response: {}", e),
+            }
+        }
+
+        let _permit = self.semaphore.acquire().await?;
+        self.rate_limiter.acquire(self.estimate_tokens(prompt)).await;
+
+        let schema = self.config.llm.structured_output.then_some(schema);
+        let masked = self.masker.mask(prompt);
+        let request = masked.prompt();
+        let response = self.call_provider(&request, schema, partial).await?;
+
+        // Estimate the counts when the provider does not report them
+        let usage = response.usage.unwrap_or_else(|| TokenUsage {
+            input_tokens: request.chars().count() as u64,
+            output_tokens: response.text.chars().count() as u64,
+        });
+        self.usage
+            .record(&self.config.llm.provider, &self.config.get_model(), usage);
+
+        let text = masked.unmask_json(&response.text);
+        let parsed = parse(&text)?;
+        self.cache.put(&cache_key, &text);
+        Ok(parsed)
+    }
+
+    /// Send a prompt to the configured provider
+    async fn call_provider(
+        &self,
+        prompt: &str,
+        schema: Option<&OutputSchema>,
+        partial: Option<&UnboundedSender<String>>,
+    ) -> Result<Completion> {
+        match self.config.llm.provider.as_str() {
+            "claude" => self.call_claude(prompt, schema, partial).await,
+            // Bedrock streams in the AWS event stream format, which is not supported
+            "bedrock" => self.call_bedrock(prompt, schema).await,
+            "openai" => self.call_openai(prompt, schema, partial).await,
+            "azure" => self.call_azure(prompt, schema, partial).await,
+            "openai-compatible" => self.call_openai_compatible(prompt, schema, partial).await,
+            "ollama" => self.call_ollama(prompt, schema, partial).await,
+            _ => Err(anyhow!("Unknown LLM provider: {}", self.config.llm.provider)),
+        }
+    }
+
+    /// Send a minimal request to check the provider settings and credentials.
+    /// The cache, rate limits and usage tracking are bypassed.
+    pub async fn check_connection(&self) -> Result<()> {
+        if !self.config.is_llm_enabled() {
+            return Err(anyhow!("LLM integration is not configured"));
+        }
+        self.call_provider("「OK」とだけ返してください。", None, None).await?;
+        Ok(())
+    }
 
-        self.parse_response(&response)
+    /// Rough token count of a request: about one token per character of Japanese
+    /// prompt, plus the maximum response length
+    fn estimate_tokens(&self, prompt: &str) -> u32 {
+        let prompt_tokens = u32::try_from(prompt.chars().count()).unwrap_or(u32::MAX);
+        prompt_tokens.saturating_add(self.config.llm.max_tokens)
     }
 
     /// Build the prompt for proofreading
@@ -133,6 +769,17 @@ impl LlmClient {
             "あなたは日本語校正の専門家です。以下のテキストを校正し、修正案を提示してください。\n\n",
         );
 
+        if !self.config.llm.examples.is_empty() {
+            prompt.push_str("【修正例】\n以下の例と同じ方針・文体で修正してください。\n\n");
+            for example in &self.config.llm.examples {
+                prompt.push_str(&format!("修正前: {}\n修正後: {}\n", example.input, example.output));
+                if let Some(ref explanation) = example.explanation {
+                    prompt.push_str(&format!("理由: {}\n", explanation));
+                }
+                prompt.push('\n');
+            }
+        }
+
         if let Some(ref context) = request.context {
             prompt.push_str(&format!("【文脈】\n{}\n\n", context));
         }
@@ -143,12 +790,24 @@ impl LlmClient {
             prompt.push_str(&format!("【検出された問題】\n{}\n\n", issue));
         }
 
+        let candidates = request.candidates.max(1);
+        if candidates > 1 {
+            prompt.push_str(&format!(
+                "互いに異なる修正案を{}案、確信度の高い順に提示してください。\n\n",
+                candidates
+            ));
+        }
+
         prompt.push_str(
             r#"以下のJSON形式で回答してください：
 {
-  "suggestion": "修正後のテキスト",
-  "explanation": "修正理由の説明",
-  "confidence": 0.0〜1.0の確信度
+  "candidates": [
+    {
+      "suggestion": "修正後のテキスト",
+      "explanation": "修正理由の説明",
+      "confidence": 0.0〜1.0の確信度
+    }
+  ]
 }
 
 JSONのみを出力し、それ以外のテキストは含めないでください。"#,
@@ -158,7 +817,12 @@ JSONのみを出力し、それ以外のテキストは含めないでくださ
     }
 
     /// Call Claude API
-    async fn call_claude(&self, prompt: &str) -> Result<String> {
+    async fn call_claude(
+        &self,
+        prompt: &str,
+        schema: Option<&OutputSchema>,
+        partial: Option<&UnboundedSender<String>>,
+    ) -> Result<Completion> {
         let api_key = self
             .config
             .get_api_key()
@@ -174,38 +838,111 @@ JSONのみを出力し、それ以外のテキストは含めないでくださ
         let request = ClaudeRequest {
             model: self.config.get_model(),
             max_tokens: self.config.llm.max_tokens,
+            params: self.claude_params(),
             messages: vec![ClaudeMessage {
                 role: "user".to_string(),
                 content: prompt.to_string(),
             }],
+            tools: schema.map(OutputSchema::claude_tool).into_iter().collect(),
+            tool_choice: schema.map(OutputSchema::claude_tool_choice),
+            stream: partial.is_some(),
         };
 
-        let response = self
+        let request = self
             .client
             .post(format!("{}/v1/messages", base_url))
             .header("x-api-key", api_key)
             .header("anthropic-version", "2023-06-01")
             .header("content-type", "application/json")
-            .json(&request)
-            .send()
-            .await?;
+            .json(&request);
+        let response = self.send(request).await?;
 
         if !response.status().is_success() {
             let status = response.status();
             let body = response.text().await.unwrap_or_default();
             return Err(anyhow!("Claude API error: {} - {}", status, body));
         }
+        if let Some(partial) = partial {
+            return read_stream(response, StreamFormat::Claude, partial).await;
+        }
 
         let claude_response: ClaudeResponse = response.json().await?;
-        claude_response
-            .content
-            .first()
-            .map(|c| c.text.clone())
-            .ok_or_else(|| anyhow!("Empty response from Claude"))
+        let text = claude_response
+            .output()
+            .ok_or_else(|| anyhow!("Empty response from Claude"))?;
+        Ok(Completion {
+            text,
+            usage: claude_response.usage.map(TokenUsage::from),
+        })
+    }
+
+    /// Call Claude on Amazon Bedrock, signing the request with AWS credentials
+    async fn call_bedrock(&self, prompt: &str, schema: Option<&OutputSchema>) -> Result<Completion> {
+        let credentials = AwsCredentials::from_env()?;
+        let region = self
+            .config
+            .aws_region()
+            .ok_or_else(|| anyhow!("AWS region not set (llm.region or AWS_REGION)"))?;
+        let base_url = self
+            .config
+            .llm
+            .base_url
+            .clone()
+            .unwrap_or_else(|| format!("https://bedrock-runtime.{}.amazonaws.com", region));
+        let url = Url::parse(&format!(
+            "{}/model/{}/invoke",
+            base_url.trim_end_matches('/'),
+            uri_encode(&self.config.get_model())
+        ))?;
+
+        let body = serde_json::to_vec(&BedrockRequest {
+            anthropic_version: "bedrock-2023-05-31".to_string(),
+            max_tokens: self.config.llm.max_tokens,
+            params: self.claude_params(),
+            messages: vec![ClaudeMessage {
+                role: "user".to_string(),
+                content: prompt.to_string(),
+            }],
+            tools: schema.map(OutputSchema::claude_tool).into_iter().collect(),
+            tool_choice: schema.map(OutputSchema::claude_tool_choice),
+        })?;
+
+        let params = SigningParams {
+            credentials: &credentials,
+            region: &region,
+            service: "bedrock",
+            time: SystemTime::now(),
+        };
+        let mut request = self.client.post(url.clone()).header("content-type", "application/json");
+        for (name, value) in sign("POST", &url, &body, &params)? {
+            request = request.header(name, value);
+        }
+
+        let response = self.send(request.body(body)).await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(anyhow!("Bedrock API error: {} - {}", status, body));
+        }
+
+        let claude_response: ClaudeResponse = response.json().await?;
+        let text = claude_response
+            .output()
+            .ok_or_else(|| anyhow!("Empty response from Bedrock"))?;
+        Ok(Completion {
+            text,
+            usage: claude_response.usage.map(TokenUsage::from),
+        })
     }
 
     /// Call OpenAI API
-    async fn call_openai(&self, prompt: &str) -> Result<String> {
+    async fn call_openai(
+        &self,
+        prompt: &str,
+        schema: Option<&OutputSchema>,
+        partial: Option<&UnboundedSender<String>>,
+    ) -> Result<Completion> {
         let api_key = self
             .config
             .get_api_key()
@@ -218,36 +955,375 @@ JSONのみを出力し、それ以外のテキストは含めないでくださ
             .clone()
             .unwrap_or_else(|| "https://api.openai.com".to_string());
 
-        let request = OpenAiRequest {
+        let request = self
+            .client
+            .post(format!("{}/v1/chat/completions", base_url))
+            .header("Authorization", format!("Bearer {}", api_key));
+
+        self.send_chat_completion(request, prompt, schema, "OpenAI", partial).await
+    }
+
+    /// Call Azure OpenAI API (deployment-based URL and `api-key` header)
+    async fn call_azure(
+        &self,
+        prompt: &str,
+        schema: Option<&OutputSchema>,
+        partial: Option<&UnboundedSender<String>>,
+    ) -> Result<Completion> {
+        let api_key = self
+            .config
+            .get_api_key()
+            .ok_or_else(|| anyhow!("Azure OpenAI API key not found"))?;
+        let url = self.config.azure_chat_url()?;
+
+        let request = self.client.post(url).header("api-key", api_key);
+
+        self.send_chat_completion(request, prompt, schema, "Azure OpenAI", partial)
+            .await
+    }
+
+    /// Call an OpenAI-compatible server; `base_url` includes the API version path (e.g. `/v1`)
+    async fn call_openai_compatible(
+        &self,
+        prompt: &str,
+        schema: Option<&OutputSchema>,
+        partial: Option<&UnboundedSender<String>>,
+    ) -> Result<Completion> {
+        let base_url = self
+            .config
+            .llm
+            .base_url
+            .as_deref()
+            .ok_or_else(|| anyhow!("base_url is required for OpenAI-compatible providers"))?;
+        if self.config.get_model().is_empty() {
+            return Err(anyhow!("model is required for OpenAI-compatible providers"));
+        }
+
+        let mut request = self
+            .client
+            .post(format!("{}/chat/completions", base_url.trim_end_matches('/')));
+        // Only the configured key: environment keys are meant for the official APIs
+        if let Some(api_key) = self.config.llm.api_key.as_deref().filter(|key| !key.is_empty()) {
+            request = request.header("Authorization", format!("Bearer {}", api_key));
+        }
+
+        self.send_chat_completion(request, prompt, schema, "OpenAI-compatible", partial)
+            .await
+    }
+
+    /// Send a chat completion request in the OpenAI format and return the reply
+    async fn send_chat_completion(
+        &self,
+        request: RequestBuilder,
+        prompt: &str,
+        schema: Option<&OutputSchema>,
+        provider: &str,
+        partial: Option<&UnboundedSender<String>>,
+    ) -> Result<Completion> {
+        let body = OpenAiRequest {
             model: self.config.get_model(),
             max_tokens: self.config.llm.max_tokens,
-            messages: vec![OpenAiMessage {
-                role: "user".to_string(),
-                content: prompt.to_string(),
-            }],
+            messages: self.openai_messages(prompt),
+            temperature: self.config.llm.temperature,
+            top_p: self.config.llm.top_p,
+            stop: self.config.llm.stop.clone(),
+            response_format: schema.map(OutputSchema::openai_response_format),
+            stream: partial.is_some(),
+            stream_options: partial.map(|_| serde_json::json!({ "include_usage": true })),
         };
 
         let response = self
+            .send(request.header("content-type", "application/json").json(&body))
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(anyhow!("{} API error: {} - {}", provider, status, body));
+        }
+        if let Some(partial) = partial {
+            return read_stream(response, StreamFormat::OpenAi, partial).await;
+        }
+
+        let openai_response: OpenAiResponse = response.json().await?;
+        let text = openai_response
+            .choices
+            .first()
+            .map(|c| c.message.content.clone())
+            .ok_or_else(|| anyhow!("Empty response from {}", provider))?;
+        Ok(Completion {
+            text,
+            usage: openai_response.usage.map(TokenUsage::from),
+        })
+    }
+
+    /// Call a local Ollama server (no API key needed)
+    async fn call_ollama(
+        &self,
+        prompt: &str,
+        schema: Option<&OutputSchema>,
+        partial: Option<&UnboundedSender<String>>,
+    ) -> Result<Completion> {
+        let base_url = self
+            .config
+            .llm
+            .base_url
+            .clone()
+            .unwrap_or_else(|| "http://localhost:11434".to_string());
+
+        let request = OllamaRequest {
+            model: self.config.get_model(),
+            messages: self.openai_messages(prompt),
+            stream: partial.is_some(),
+            // Constrain the output to JSON; small local models often add prose otherwise
+            format: schema.map_or_else(|| "json".into(), |schema| schema.schema.clone()),
+            options: OllamaOptions {
+                num_predict: self.config.llm.max_tokens,
+                temperature: self.config.llm.temperature,
+                top_p: self.config.llm.top_p,
+                stop: self.config.llm.stop.clone(),
+            },
+        };
+
+        let request = self
             .client
-            .post(format!("{}/v1/chat/completions", base_url))
-            .header("Authorization", format!("Bearer {}", api_key))
+            .post(format!("{}/api/chat", base_url))
             .header("content-type", "application/json")
-            .json(&request)
-            .send()
-            .await?;
+            .json(&request);
+        let response = self
+            .send(request)
+            .await
+            .map_err(|e| anyhow!("Failed to connect to Ollama at {}: {}", base_url, e))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(anyhow!("Ollama API error: {} - {}", status, body));
+        }
+        if let Some(partial) = partial {
+            return read_stream(response, StreamFormat::Ollama, partial).await;
+        }
+
+        let ollama_response: OllamaResponse = response.json().await?;
+        Ok(Completion {
+            usage: ollama_response.usage(),
+            text: ollama_response.message.content,
+        })
+    }
+
+    /// Configured generation parameters in the Anthropic format
+    fn claude_params(&self) -> ClaudeParams {
+        ClaudeParams {
+            system: self.config.llm.system_prompt.clone(),
+            temperature: self.config.llm.temperature,
+            top_p: self.config.llm.top_p,
+            stop_sequences: self.config.llm.stop.clone(),
+        }
+    }
+
+    /// Chat messages in the OpenAI format, with the configured system prompt
+    fn openai_messages(&self, prompt: &str) -> Vec<OpenAiMessage> {
+        let system = self.config.llm.system_prompt.iter().map(|system| OpenAiMessage {
+            role: "system".to_string(),
+            content: system.clone(),
+        });
+        let user = OpenAiMessage {
+            role: "user".to_string(),
+            content: prompt.to_string(),
+        };
+        system.chain(std::iter::once(user)).collect()
+    }
+
+    /// Send a request, retrying rate-limited (429) and server error (5xx) responses,
+    /// timeouts, and connection failures with exponential backoff
+    async fn send(&self, request: RequestBuilder) -> Result<Response> {
+        let max_retries = self.config.llm.max_retries;
+        let mut attempt = 0;
+
+        loop {
+            // Bodies are always in memory, so requests can be cloned
+            let Some(this_try) = request.try_clone() else {
+                return Ok(request.send().await?);
+            };
+
+            let retry_after = match this_try.send().await {
+                Ok(response) if is_retryable_status(response.status()) && attempt < max_retries => {
+                    tracing::warn!("LLM request failed with {}, retrying", response.status());
+                    retry_after(&response)
+                }
+                Err(e) if (e.is_timeout() || e.is_connect()) && attempt < max_retries => {
+                    tracing::warn!("LLM request failed: {}, retrying", e);
+                    None
+                }
+                result => return Ok(result?),
+            };
+
+            tokio::time::sleep(retry_delay(attempt, retry_after)).await;
+            attempt += 1;
+        }
+    }
+
+    /// Build the prompt for fixing several issues at once
+    fn build_batch_prompt(&self, items: &[BatchItem]) -> String {
+        let mut prompt = String::from(
+            "あなたは日本語校正の専門家です。以下の各箇所について、指摘された問題を修正したテキストを提示してください。\n\n",
+        );
+
+        prompt.push_str("【修正対象】\n");
+        for (i, item) in items.iter().enumerate() {
+            prompt.push_str(&format!("[{}] テキスト: {}\n    問題: {}\n", i, item.text, item.issue));
+        }
+
+        prompt.push_str(
+            r#"
+以下のJSON形式で回答してください。修正後のテキストは各箇所をそのまま置き換えられる形にしてください：
+{
+  "fixes": [
+    {
+      "index": 箇所の番号,
+      "suggestion": "修正後のテキスト"
+    }
+  ]
+}
+
+JSONのみを出力し、それ以外のテキストは含めないでください。"#,
+        );
+
+        prompt
+    }
+
+    /// Parse the response of a batch request, dropping fixes that do not refer
+    /// to one of the items or do not change the text
+    fn parse_batch(&self, response: &str, items: &[BatchItem]) -> Result<Vec<BatchFix>> {
+        let json_str = self.extract_json(response)?;
+        let parsed: BatchResponse = serde_json::from_str(&json_str)
+            .map_err(|e| anyhow!("Failed to parse LLM response: {} - Response: {}", e, json_str))?;
+
+        let mut fixes: Vec<BatchFix> = parsed
+            .fixes
+            .into_iter()
+            .filter(|fix| items.get(fix.index).is_some_and(|item| item.text != fix.suggestion))
+            .collect();
+        fixes.sort_by_key(|fix| fix.index);
+        fixes.dedup_by_key(|fix| fix.index);
+        Ok(fixes)
+    }
+
+    /// Build the prompt for the AI diagnostics pass
+    fn build_diagnosis_prompt(&self, sentences: &[String]) -> String {
+        let mut prompt = String::from(
+            "あなたは日本語校正の専門家です。以下の各文について、語順の不自然さ、前後の文とのつながりの悪さ、\
+             文体やトーンの不統一など、機械的なルールでは検出しにくい問題を指摘してください。\
+             誤字や文法の明らかな誤りは別途検出しているため、指摘は不要です。\n\n",
+        );
+
+        prompt.push_str("【対象の文】\n");
+        for (i, sentence) in sentences.iter().enumerate() {
+            prompt.push_str(&format!("[{}] {}\n", i, sentence));
+        }
+
+        prompt.push_str(
+            r#"
+以下のJSON形式で回答してください。問題のない文は含めず、問題がなければ "issues" を空の配列にしてください：
+{
+  "issues": [
+    {
+      "index": 文の番号,
+      "message": "問題の説明",
+      "suggestion": "修正後の文"
+    }
+  ]
+}
+
+JSONのみを出力し、それ以外のテキストは含めないでください。"#,
+        );
+
+        prompt
+    }
+
+    /// Build the prompt for explaining an issue
+    fn build_explanation_prompt(&self, text: &str, issue: &str, context: &str) -> String {
+        let mut prompt = String::from(
+            "あなたは日本語校正の専門家です。以下の指摘について、なぜこの表現が問題なのかを、\
+             書き手が納得できるように2〜4文で簡潔に解説してください。必要なら望ましい書き方の例も示してください。\n\n",
+        );
+
+        prompt.push_str(&format!("【文脈】\n{}\n\n", context));
+        prompt.push_str(&format!("【指摘箇所】\n{}\n\n", text));
+        prompt.push_str(&format!("【指摘内容】\n{}\n\n", issue));
+
+        prompt.push_str(
+            r#"以下のJSON形式で回答してください：
+{
+  "explanation": "解説"
+}
+
+JSONのみを出力し、それ以外のテキストは含めないでください。"#,
+        );
+
+        prompt
+    }
+
+    /// Parse the response of an explanation request
+    fn parse_explanation(&self, response: &str) -> Result<String> {
+        let json_str = self.extract_json(response)?;
+        let parsed: ExplanationResponse = serde_json::from_str(&json_str)
+            .map_err(|e| anyhow!("Failed to parse LLM response: {} - Response: {}", e, json_str))?;
+
+        let explanation = parsed.explanation.trim();
+        if explanation.is_empty() {
+            return Err(anyhow!("Empty explanation in LLM response: {}", json_str));
+        }
+        Ok(explanation.to_string())
+    }
+
+    /// Build the prompt for converting a paragraph to another writing style
+    fn build_conversion_prompt(&self, text: &str, style: WritingStyle) -> String {
+        let mut prompt = format!(
+            "あなたは日本語校正の専門家です。以下のテキストの文末を{}に統一してください。\
+             文の内容・語順・改行・記号はできるだけ変えず、文体の変換に必要な箇所だけを書き換えてください。\
+             引用やコードはそのまま残してください。\n\n",
+            style.label()
+        );
+
+        prompt.push_str(&format!("【変換対象テキスト】\n{}\n\n", text));
+
+        prompt.push_str(
+            r#"以下のJSON形式で回答してください：
+{
+  "text": "変換後のテキスト"
+}
+
+JSONのみを出力し、それ以外のテキストは含めないでください。"#,
+        );
+
+        prompt
+    }
+
+    /// Parse the response of a writing style conversion
+    fn parse_conversion(&self, response: &str) -> Result<String> {
+        let json_str = self.extract_json(response)?;
+        let parsed: ConversionResponse = serde_json::from_str(&json_str)
+            .map_err(|e| anyhow!("Failed to parse LLM response: {} - Response: {}", e, json_str))?;
 
-        if !response.status().is_success() {
-            let status = response.status();
-            let body = response.text().await.unwrap_or_default();
-            return Err(anyhow!("OpenAI API error: {} - {}", status, body));
+        if parsed.text.trim().is_empty() {
+            return Err(anyhow!("Empty conversion in LLM response: {}", json_str));
         }
+        Ok(parsed.text)
+    }
 
-        let openai_response: OpenAiResponse = response.json().await?;
-        openai_response
-            .choices
-            .first()
-            .map(|c| c.message.content.clone())
-            .ok_or_else(|| anyhow!("Empty response from OpenAI"))
+    /// Parse the response of the AI diagnostics pass, dropping issues that do
+    /// not refer to one of the `count` sentences
+    fn parse_diagnosis(&self, response: &str, count: usize) -> Result<Vec<SentenceIssue>> {
+        let json_str = self.extract_json(response)?;
+        let parsed: DiagnosisResponse = serde_json::from_str(&json_str)
+            .map_err(|e| anyhow!("Failed to parse LLM response: {} - Response: {}", e, json_str))?;
+
+        Ok(parsed
+            .issues
+            .into_iter()
+            .filter(|issue| issue.index < count && !issue.message.trim().is_empty())
+            .collect())
     }
 
     /// Parse the LLM response into a ProofreadResponse
@@ -255,14 +1331,23 @@ JSONのみを出力し、それ以外のテキストは含めないでくださ
         // Try to extract JSON from the response
         let json_str = self.extract_json(response)?;
 
-        let parsed: ParsedSuggestion = serde_json::from_str(&json_str)
+        let parsed: ParsedResponse = serde_json::from_str(&json_str)
             .map_err(|e| anyhow!("Failed to parse LLM response: {} - Response: {}", e, json_str))?;
 
-        Ok(ProofreadResponse {
-            suggestion: parsed.suggestion,
-            explanation: parsed.explanation,
-            confidence: parsed.confidence.clamp(0.0, 1.0),
-        })
+        let mut candidates = match parsed {
+            ParsedResponse::Candidates { candidates } => candidates,
+            ParsedResponse::Single(candidate) => vec![candidate],
+        };
+        if candidates.is_empty() {
+            return Err(anyhow!("No candidates in LLM response: {}", json_str));
+        }
+
+        for candidate in &mut candidates {
+            candidate.confidence = candidate.confidence.clamp(0.0, 1.0);
+        }
+        candidates.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+
+        Ok(ProofreadResponse { candidates })
     }
 
     /// Extract JSON from potentially wrapped response
@@ -311,10 +1396,311 @@ JSONのみを出力し、それ以外のテキストは含めないでくださ
     }
 }
 
+/// Whether a response status is worth retrying
+fn is_retryable_status(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// Delay requested by a `Retry-After` header (in seconds)
+fn retry_after(response: &Response) -> Option<Duration> {
+    let value = response.headers().get(RETRY_AFTER)?.to_str().ok()?;
+    value.trim().parse::<u64>().ok().map(Duration::from_secs)
+}
+
+/// Delay before a retry: the server's `Retry-After` if given, otherwise
+/// exponential backoff, capped at `MAX_RETRY_DELAY`
+fn retry_delay(attempt: u32, retry_after: Option<Duration>) -> Duration {
+    retry_after
+        .unwrap_or_else(|| INITIAL_RETRY_DELAY.saturating_mul(2u32.saturating_pow(attempt)))
+        .min(MAX_RETRY_DELAY)
+}
+
+/// Read a streamed response line by line, returning the full text.
+/// The partial suggestion is sent whenever it grows.
+async fn read_stream(
+    mut response: reqwest::Response,
+    format: StreamFormat,
+    partial: &UnboundedSender<String>,
+) -> Result<Completion> {
+    let mut buffer: Vec<u8> = Vec::new();
+    let mut text = String::new();
+    let mut usage: Option<TokenUsage> = None;
+    let mut sent_len = 0;
+
+    loop {
+        let chunk = response.chunk().await?;
+        if let Some(bytes) = &chunk {
+            buffer.extend_from_slice(bytes);
+        }
+
+        // Lines are complete once a newline arrives, or at the end of the stream
+        let end = if chunk.is_some() {
+            buffer.iter().rposition(|&b| b == b'\n').map(|i| i + 1).unwrap_or(0)
+        } else {
+            buffer.len()
+        };
+        let lines: Vec<u8> = buffer.drain(..end).collect();
+        for line in String::from_utf8_lossy(&lines).lines() {
+            if let Some(delta) = format.delta(line.trim()) {
+                text.push_str(&delta);
+            }
+            if let Some(counts) = format.usage(line.trim()) {
+                usage.get_or_insert_with(TokenUsage::default).merge(counts);
+            }
+        }
+
+        if let Some(suggestion) = partial_suggestion(&text) {
+            if suggestion.len() > sent_len {
+                sent_len = suggestion.len();
+                // The receiver may have stopped listening; the response is still needed
+                let _ = partial.send(suggestion);
+            }
+        }
+
+        if chunk.is_none() {
+            return Ok(Completion { text, usage });
+        }
+    }
+}
+
+/// Extract the (possibly incomplete) first `suggestion` string from partial JSON
+pub fn partial_suggestion(json: &str) -> Option<String> {
+    let key = json.find("\"suggestion\"")? + "\"suggestion\"".len();
+    let rest = json[key..].trim_start().strip_prefix(':')?.trim_start().strip_prefix('"')?;
+
+    let mut suggestion = String::new();
+    let mut chars = rest.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => break,
+            '\\' => match chars.next() {
+                Some('n') => suggestion.push('\n'),
+                Some('t') => suggestion.push('\t'),
+                Some('r') => suggestion.push('\r'),
+                Some('u') => {
+                    let code: String = chars.by_ref().take(4).collect();
+                    match u32::from_str_radix(&code, 16).ok().and_then(char::from_u32) {
+                        Some(c) => suggestion.push(c),
+                        None => break,
+                    }
+                }
+                Some(c) => suggestion.push(c),
+                None => break,
+            },
+            c => suggestion.push(c),
+        }
+    }
+    Some(suggestion)
+}
+
+/// Proxy with Basic authentication, when a proxy URL or credentials are configured
+fn proxy(config: &LlmProxyConfig) -> Result<Option<Proxy>> {
+    if config.url.is_none() && config.username.is_none() {
+        return Ok(None);
+    }
+    let Some(url) = config.proxy_url() else {
+        return Err(anyhow!("proxy credentials are set but no proxy URL is configured"));
+    };
+
+    let mut proxy = Proxy::all(url.as_str())?;
+    if let Some(username) = &config.username {
+        proxy = proxy.basic_auth(username, &config.password().unwrap_or_default());
+    }
+    let no_proxy = match config.no_proxy.as_deref() {
+        Some(hosts) => NoProxy::from_string(hosts),
+        None => NoProxy::from_env().or_else(|| NoProxy::from_string(DEFAULT_NO_PROXY)),
+    };
+    Ok(Some(proxy.no_proxy(no_proxy)))
+}
+
+/// Additional headers from the configuration, skipping invalid ones
+fn extra_headers(config: &Config) -> HeaderMap {
+    config
+        .llm
+        .headers
+        .iter()
+        .filter_map(|(name, value)| {
+            match (HeaderName::try_from(name.as_str()), HeaderValue::try_from(value.as_str())) {
+                (Ok(name), Ok(value)) => Some((name, value)),
+                _ => {
+                    tracing::warn!("Ignoring invalid LLM request header: {}", name);
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+/// Schema of proofreading replies
+fn proofread_schema() -> OutputSchema {
+    OutputSchema {
+        name: "proofread",
+        description: "校正結果の修正案を返す",
+        schema: serde_json::json!({
+            "type": "object",
+            "properties": {
+                "candidates": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "suggestion": { "type": "string" },
+                            "explanation": { "type": "string" },
+                            "confidence": { "type": "number" }
+                        },
+                        "required": ["suggestion", "explanation", "confidence"],
+                        "additionalProperties": false
+                    }
+                }
+            },
+            "required": ["candidates"],
+            "additionalProperties": false
+        }),
+    }
+}
+
+/// Schema of batch fix replies
+fn batch_schema() -> OutputSchema {
+    OutputSchema {
+        name: "fix_issues",
+        description: "各箇所の修正後のテキストを返す",
+        schema: serde_json::json!({
+            "type": "object",
+            "properties": {
+                "fixes": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "index": { "type": "integer" },
+                            "suggestion": { "type": "string" }
+                        },
+                        "required": ["index", "suggestion"],
+                        "additionalProperties": false
+                    }
+                }
+            },
+            "required": ["fixes"],
+            "additionalProperties": false
+        }),
+    }
+}
+
+/// Schema of AI diagnostics replies
+fn diagnosis_schema() -> OutputSchema {
+    OutputSchema {
+        name: "report_issues",
+        description: "各文の問題点を返す",
+        schema: serde_json::json!({
+            "type": "object",
+            "properties": {
+                "issues": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "index": { "type": "integer" },
+                            "message": { "type": "string" },
+                            "suggestion": { "type": ["string", "null"] }
+                        },
+                        "required": ["index", "message", "suggestion"],
+                        "additionalProperties": false
+                    }
+                }
+            },
+            "required": ["issues"],
+            "additionalProperties": false
+        }),
+    }
+}
+
+/// Schema of explanation replies
+fn explanation_schema() -> OutputSchema {
+    OutputSchema {
+        name: "explain_issue",
+        description: "指摘の解説を返す",
+        schema: serde_json::json!({
+            "type": "object",
+            "properties": {
+                "explanation": { "type": "string" }
+            },
+            "required": ["explanation"],
+            "additionalProperties": false
+        }),
+    }
+}
+
+/// Schema of writing style conversion replies
+fn conversion_schema() -> OutputSchema {
+    OutputSchema {
+        name: "converted_text",
+        description: "変換後のテキストを返す",
+        schema: serde_json::json!({
+            "type": "object",
+            "properties": {
+                "text": { "type": "string" }
+            },
+            "required": ["text"],
+            "additionalProperties": false
+        }),
+    }
+}
+
+/// Split text into paragraph chunks, breaking long paragraphs at sentence boundaries
+pub fn split_into_chunks(text: &str, max_chars: usize) -> Vec<TextChunk> {
+    let mut chunks = Vec::new();
+    for paragraph in split_paragraphs(text) {
+        push_paragraph(text, paragraph.start, paragraph.end, max_chars, &mut chunks);
+    }
+    chunks
+}
+
+/// Push a paragraph as one chunk, or as several sentence groups if it is too long
+fn push_paragraph(text: &str, start: usize, end: usize, max_chars: usize, chunks: &mut Vec<TextChunk>) {
+    let paragraph = &text[start..end];
+    if paragraph.chars().count() <= max_chars {
+        chunks.push(TextChunk {
+            text: paragraph.to_string(),
+            start_byte: start,
+            end_byte: end,
+        });
+        return;
+    }
+
+    let mut chunk_start = start;
+    let mut chunk_end = start;
+    let mut chunk_chars = 0;
+
+    for sentence in split_sentences(paragraph) {
+        let sentence_chars = sentence.text.chars().count();
+        if chunk_chars > 0 && chunk_chars + sentence_chars > max_chars {
+            chunks.push(TextChunk {
+                text: text[chunk_start..chunk_end].to_string(),
+                start_byte: chunk_start,
+                end_byte: chunk_end,
+            });
+            chunk_chars = 0;
+        }
+        if chunk_chars == 0 {
+            chunk_start = start + sentence.start;
+        }
+        chunk_end = start + sentence.end;
+        chunk_chars += sentence_chars;
+    }
+
+    if chunk_chars > 0 {
+        chunks.push(TextChunk {
+            text: text[chunk_start..chunk_end].to_string(),
+            start_byte: chunk_start,
+            end_byte: chunk_end,
+        });
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::config::LlmConfig;
+    use crate::config::{FewShotExample, LlmConfig};
 
     fn create_test_config(provider: &str) -> Config {
         Config {
@@ -324,6 +1710,7 @@ mod tests {
                 model: None,
                 base_url: None,
                 max_tokens: 1024,
+                ..Default::default()
             },
             ..Default::default()
         }
@@ -336,6 +1723,233 @@ mod tests {
         assert!(client.is_available());
     }
 
+    #[test]
+    fn test_ollama_client_available_without_key() {
+        let mut config = create_test_config("ollama");
+        config.llm.api_key = None;
+        let client = LlmClient::new(config);
+        assert!(client.is_available());
+    }
+
+    #[test]
+    fn test_ollama_response_format() {
+        let body = r#"{"model":"llama3.1","message":{"role":"assistant","content":"{\"candidates\": []}"},"done":true}"#;
+        let response: OllamaResponse = serde_json::from_str(body).unwrap();
+        assert_eq!(response.message.content, r#"{"candidates": []}"#);
+    }
+
+    #[test]
+    fn test_generation_params() {
+        let mut config = create_test_config("claude");
+        config.llm.temperature = Some(0.2);
+        config.llm.system_prompt = Some("社内の表記ルールに従うこと".to_string());
+        config.llm.stop = vec!["\n\n\n".to_string()];
+        let client = LlmClient::new(config);
+
+        let params = serde_json::to_value(client.claude_params()).unwrap();
+        assert_eq!(params["system"], "社内の表記ルールに従うこと");
+        assert_eq!(params["stop_sequences"][0], "\n\n\n");
+        assert!((params["temperature"].as_f64().unwrap() - 0.2).abs() < 1e-6);
+        assert!(params.get("top_p").is_none());
+
+        let messages = client.openai_messages("本文");
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].role, "system");
+        assert_eq!(messages[1].content, "本文");
+
+        let client = LlmClient::new(create_test_config("openai"));
+        assert_eq!(client.openai_messages("本文").len(), 1);
+        assert_eq!(serde_json::to_value(client.claude_params()).unwrap(), serde_json::json!({}));
+    }
+
+    #[test]
+    fn test_proxy() {
+        assert!(proxy(&LlmProxyConfig::default()).unwrap().is_none());
+
+        let config = LlmProxyConfig {
+            url: Some("http://proxy.example.com:8080".to_string()),
+            username: Some("user".to_string()),
+            password: Some("pass".to_string()),
+            no_proxy: Some("localhost,.internal".to_string()),
+        };
+        assert!(proxy(&config).unwrap().is_some());
+        assert!(LlmClient::new(Config {
+            llm: LlmConfig {
+                proxy: config,
+                ..create_test_config("claude").llm
+            },
+            ..Default::default()
+        })
+        .is_available());
+
+        let invalid = LlmProxyConfig {
+            url: Some("http://[invalid".to_string()),
+            ..Default::default()
+        };
+        assert!(proxy(&invalid).is_err());
+    }
+
+    #[test]
+    fn test_extra_headers_skip_invalid() {
+        let mut config = create_test_config("openai-compatible");
+        config.llm.headers.insert("X-Title".to_string(), "MoZuku".to_string());
+        config.llm.headers.insert("bad header".to_string(), "value".to_string());
+
+        let headers = extra_headers(&config);
+        assert_eq!(headers.len(), 1);
+        assert_eq!(headers["x-title"], "MoZuku");
+    }
+
+    #[test]
+    fn test_stream_format_delta() {
+        let claude = r#"data: {"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text":"{\"sug"}}"#;
+        assert_eq!(StreamFormat::Claude.delta(claude).as_deref(), Some("{\"sug"));
+        assert_eq!(StreamFormat::Claude.delta("event: content_block_delta"), None);
+        assert_eq!(StreamFormat::Claude.delta(r#"data: {"type":"message_stop"}"#), None);
+
+        let openai = r#"data: {"choices":[{"index":0,"delta":{"content":"修正"}}]}"#;
+        assert_eq!(StreamFormat::OpenAi.delta(openai).as_deref(), Some("修正"));
+        assert_eq!(StreamFormat::OpenAi.delta("data: [DONE]"), None);
+
+        let ollama = r#"{"message":{"role":"assistant","content":"後"},"done":false}"#;
+        assert_eq!(StreamFormat::Ollama.delta(ollama).as_deref(), Some("後"));
+    }
+
+    #[test]
+    fn test_stream_format_usage() {
+        let start = r#"data: {"type":"message_start","message":{"usage":{"input_tokens":120,"output_tokens":1}}}"#;
+        let delta = r#"data: {"type":"message_delta","delta":{"stop_reason":"end_turn"},"usage":{"output_tokens":45}}"#;
+        let mut usage = StreamFormat::Claude.usage(start).unwrap();
+        usage.merge(StreamFormat::Claude.usage(delta).unwrap());
+        assert_eq!(
+            usage,
+            TokenUsage {
+                input_tokens: 120,
+                output_tokens: 45
+            }
+        );
+        assert_eq!(StreamFormat::Claude.usage(r#"data: {"type":"ping"}"#), None);
+
+        let openai = r#"data: {"choices":[],"usage":{"prompt_tokens":10,"completion_tokens":5}}"#;
+        assert_eq!(StreamFormat::OpenAi.usage(openai).map(|u| u.output_tokens), Some(5));
+        assert_eq!(StreamFormat::OpenAi.usage("data: [DONE]"), None);
+
+        let ollama = r#"{"message":{"content":""},"done":true,"prompt_eval_count":26,"eval_count":290}"#;
+        assert_eq!(StreamFormat::Ollama.usage(ollama).map(|u| u.input_tokens), Some(26));
+    }
+
+    #[test]
+    fn test_response_usage() {
+        let claude = r#"{"content":[{"type":"text","text":"{}"}],"usage":{"input_tokens":12,"output_tokens":3}}"#;
+        let response: ClaudeResponse = serde_json::from_str(claude).unwrap();
+        assert_eq!(response.usage.map(TokenUsage::from).map(|u| u.input_tokens), Some(12));
+
+        let openai = r#"{"choices":[{"message":{"content":"{}"}}],"usage":{"prompt_tokens":7,"completion_tokens":2}}"#;
+        let response: OpenAiResponse = serde_json::from_str(openai).unwrap();
+        assert_eq!(response.usage.map(TokenUsage::from).map(|u| u.output_tokens), Some(2));
+    }
+
+    #[test]
+    fn test_client_not_available_over_budget() {
+        let mut config = create_test_config("claude");
+        config.llm.usage.persist = false;
+        config.llm.usage.monthly_budget = Some(0.0);
+        let client = LlmClient::new(config);
+
+        assert!(client.budget_exceeded());
+        assert!(!client.is_available());
+    }
+
+    #[test]
+    fn test_client_not_available_with_unpriced_model_under_budget() {
+        let mut config = create_test_config("openai-compatible");
+        config.llm.model = Some("my-local-finetune".to_string());
+        config.llm.usage.persist = false;
+        config.llm.usage.monthly_budget = Some(10.0);
+        let client = LlmClient::new(config.clone());
+        assert!(client.budget_unenforceable());
+        assert!(!client.is_available());
+
+        config.llm.usage.input_price = Some(1.0);
+        config.llm.usage.output_price = Some(2.0);
+        assert!(LlmClient::new(config).is_available());
+    }
+
+    #[test]
+    fn test_stream_format_delta_tool_input() {
+        let claude = r#"data: {"type":"content_block_delta","index":0,"delta":{"type":"input_json_delta","partial_json":"{\"text\": \"猫"}}"#;
+        assert_eq!(StreamFormat::Claude.delta(claude).as_deref(), Some(r#"{"text": "猫"#));
+    }
+
+    #[test]
+    fn test_claude_response_output() {
+        let tool_use = r#"{"content":[
+            {"type":"text","text":"校正します。"},
+            {"type":"tool_use","id":"toolu_1","name":"converted_text","input":{"text":"猫です。"}}
+        ]}"#;
+        let response: ClaudeResponse = serde_json::from_str(tool_use).unwrap();
+        assert_eq!(response.output().as_deref(), Some(r#"{"text":"猫です。"}"#));
+
+        let text = r#"{"content":[{"type":"text","text":"{\"text\": \"猫\"}"}]}"#;
+        let response: ClaudeResponse = serde_json::from_str(text).unwrap();
+        assert_eq!(response.output().as_deref(), Some(r#"{"text": "猫"}"#));
+    }
+
+    #[test]
+    fn test_output_schemas() {
+        let schema = proofread_schema();
+        let tool = serde_json::to_value(schema.claude_tool()).unwrap();
+        assert_eq!(tool["name"], "proofread");
+        assert_eq!(tool["input_schema"]["required"][0], "candidates");
+        assert_eq!(
+            serde_json::to_value(schema.claude_tool_choice()).unwrap(),
+            serde_json::json!({ "type": "tool", "name": "proofread" })
+        );
+
+        let format = diagnosis_schema().openai_response_format();
+        assert_eq!(format["type"], "json_schema");
+        assert_eq!(format["json_schema"]["strict"], true);
+
+        // Replies that follow the schemas are accepted by the parsers
+        let client = LlmClient::new(create_test_config("openai"));
+        let issues = client
+            .parse_diagnosis(r#"{"issues":[{"index":0,"message":"冗長","suggestion":null}]}"#, 1)
+            .unwrap();
+        assert_eq!(issues[0].suggestion, None);
+    }
+
+    #[test]
+    fn test_partial_suggestion() {
+        assert_eq!(partial_suggestion(r#"{"candidates": [{"sugg"#), None);
+        assert_eq!(partial_suggestion(r#"{"candidates": [{"suggestion": ""#).as_deref(), Some(""));
+        assert_eq!(
+            partial_suggestion(r#"{"candidates": [{"suggestion": "食べられ"#).as_deref(),
+            Some("食べられ")
+        );
+        assert_eq!(
+            partial_suggestion(r#"{"suggestion":"一行目\n\"引用\"", "explanation": "理由"#).as_deref(),
+            Some("一行目\n\"引用\"")
+        );
+    }
+
+    #[test]
+    fn test_retryable_status() {
+        assert!(is_retryable_status(StatusCode::TOO_MANY_REQUESTS));
+        assert!(is_retryable_status(StatusCode::SERVICE_UNAVAILABLE));
+        assert!(!is_retryable_status(StatusCode::BAD_REQUEST));
+        assert!(!is_retryable_status(StatusCode::UNAUTHORIZED));
+    }
+
+    #[test]
+    fn test_retry_delay() {
+        assert_eq!(retry_delay(0, None), Duration::from_millis(500));
+        assert_eq!(retry_delay(1, None), Duration::from_secs(1));
+        assert_eq!(retry_delay(3, None), Duration::from_secs(4));
+        assert_eq!(retry_delay(20, None), MAX_RETRY_DELAY);
+        assert_eq!(retry_delay(0, Some(Duration::from_secs(7))), Duration::from_secs(7));
+        assert_eq!(retry_delay(0, Some(Duration::from_secs(600))), MAX_RETRY_DELAY);
+    }
+
     #[test]
     fn test_client_not_available_when_disabled() {
         let config = Config::default(); // provider = "none"
@@ -352,11 +1966,13 @@ mod tests {
             text: "テスト文章".to_string(),
             context: None,
             issue: None,
+            candidates: 1,
         };
 
         let prompt = client.build_prompt(&request);
         assert!(prompt.contains("テスト文章"));
         assert!(prompt.contains("校正対象テキスト"));
+        assert!(!prompt.contains("案、確信度の高い順"));
     }
 
     #[test]
@@ -368,6 +1984,7 @@ mod tests {
             text: "テスト文章".to_string(),
             context: Some("前の文章".to_string()),
             issue: Some("ら抜き言葉".to_string()),
+            candidates: 1,
         };
 
         let prompt = client.build_prompt(&request);
@@ -376,6 +1993,184 @@ mod tests {
         assert!(prompt.contains("ら抜き言葉"));
     }
 
+    #[test]
+    fn test_build_prompt_multiple_candidates() {
+        let config = create_test_config("claude");
+        let client = LlmClient::new(config);
+
+        let request = ProofreadRequest {
+            text: "テスト文章".to_string(),
+            context: None,
+            issue: None,
+            candidates: 3,
+        };
+
+        let prompt = client.build_prompt(&request);
+        assert!(prompt.contains("修正案を3案"));
+    }
+
+    #[test]
+    fn test_build_prompt_with_examples() {
+        let mut config = create_test_config("claude");
+        config.llm.examples = vec![FewShotExample {
+            input: "サーバを再起動して下さい".to_string(),
+            output: "サーバーを再起動してください".to_string(),
+            explanation: Some("社内表記に統一".to_string()),
+        }];
+        let client = LlmClient::new(config);
+
+        let request = ProofreadRequest {
+            text: "テスト文章".to_string(),
+            context: None,
+            issue: None,
+            candidates: 1,
+        };
+
+        let prompt = client.build_prompt(&request);
+        assert!(prompt.contains("【修正例】"));
+        assert!(prompt.contains("修正前: サーバを再起動して下さい\n修正後: サーバーを再起動してください\n理由: 社内表記に統一"));
+        assert!(prompt.find("【修正例】") < prompt.find("【校正対象テキスト】"));
+        assert!(!LlmClient::new(create_test_config("claude")).build_prompt(&request).contains("【修正例】"));
+    }
+
+    #[test]
+    fn test_build_diagnosis_prompt() {
+        let client = LlmClient::new(create_test_config("claude"));
+        let sentences = vec!["一文目です。".to_string(), "二文目です。".to_string()];
+
+        let prompt = client.build_diagnosis_prompt(&sentences);
+        assert!(prompt.contains("[0] 一文目です。\n[1] 二文目です。\n"));
+        assert!(prompt.contains("\"issues\""));
+    }
+
+    #[test]
+    fn test_parse_diagnosis() {
+        let client = LlmClient::new(create_test_config("claude"));
+
+        let response = r#"{"issues": [
+            {"index": 1, "message": "語順が不自然", "suggestion": "修正文"},
+            {"index": 5, "message": "範囲外"},
+            {"index": 0, "message": " "}
+        ]}"#;
+        let issues = client.parse_diagnosis(response, 2).unwrap();
+
+        assert_eq!(
+            issues,
+            vec![SentenceIssue {
+                index: 1,
+                message: "語順が不自然".to_string(),
+                suggestion: Some("修正文".to_string()),
+            }]
+        );
+        assert!(client.parse_diagnosis(r#"{"issues": []}"#, 2).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_build_batch_prompt() {
+        let client = LlmClient::new(create_test_config("claude"));
+        let items = vec![
+            BatchItem {
+                text: "見れる".to_string(),
+                issue: "ら抜き言葉".to_string(),
+            },
+            BatchItem {
+                text: "私はは".to_string(),
+                issue: "助詞の重複".to_string(),
+            },
+        ];
+
+        let prompt = client.build_batch_prompt(&items);
+        assert!(prompt.contains("[0] テキスト: 見れる\n    問題: ら抜き言葉\n"));
+        assert!(prompt.contains("[1] テキスト: 私はは\n"));
+        assert!(prompt.contains("\"fixes\""));
+    }
+
+    #[test]
+    fn test_parse_batch() {
+        let client = LlmClient::new(create_test_config("claude"));
+        let items = vec![
+            BatchItem {
+                text: "見れる".to_string(),
+                issue: "ら抜き言葉".to_string(),
+            },
+            BatchItem {
+                text: "私はは".to_string(),
+                issue: "助詞の重複".to_string(),
+            },
+        ];
+
+        let response = r#"{"fixes": [
+            {"index": 1, "suggestion": "私は"},
+            {"index": 0, "suggestion": "見られる"},
+            {"index": 0, "suggestion": "見ることができる"},
+            {"index": 2, "suggestion": "範囲外"}
+        ]}"#;
+        let fixes = client.parse_batch(response, &items).unwrap();
+
+        assert_eq!(
+            fixes,
+            vec![
+                BatchFix {
+                    index: 0,
+                    suggestion: "見られる".to_string()
+                },
+                BatchFix {
+                    index: 1,
+                    suggestion: "私は".to_string()
+                },
+            ]
+        );
+
+        let unchanged = r#"{"fixes": [{"index": 0, "suggestion": "見れる"}]}"#;
+        assert!(client.parse_batch(unchanged, &items).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_build_explanation_prompt() {
+        let client = LlmClient::new(create_test_config("claude"));
+
+        let prompt = client.build_explanation_prompt("見れる", "ら抜き言葉", "山が見れる。");
+        assert!(prompt.contains("【文脈】\n山が見れる。\n"));
+        assert!(prompt.contains("【指摘箇所】\n見れる\n"));
+        assert!(prompt.contains("【指摘内容】\nら抜き言葉\n"));
+    }
+
+    #[test]
+    fn test_parse_explanation() {
+        let client = LlmClient::new(create_test_config("claude"));
+
+        let explanation = client
+            .parse_explanation(r#"{"explanation": " 「見られる」が本来の形です。 "}"#)
+            .unwrap();
+        assert_eq!(explanation, "「見られる」が本来の形です。");
+        assert!(client.parse_explanation(r#"{"explanation": ""}"#).is_err());
+    }
+
+    #[test]
+    fn test_build_conversion_prompt() {
+        let client = LlmClient::new(create_test_config("claude"));
+
+        let prompt = client.build_conversion_prompt("猫である。", WritingStyle::Polite);
+        assert!(prompt.contains("です・ます調に統一"));
+        assert!(prompt.contains("【変換対象テキスト】\n猫である。\n"));
+    }
+
+    #[test]
+    fn test_parse_conversion() {
+        let client = LlmClient::new(create_test_config("claude"));
+
+        let text = client.parse_conversion(r#"{"text": "猫です。"}"#).unwrap();
+        assert_eq!(text, "猫です。");
+        assert!(client.parse_conversion(r#"{"text": ""}"#).is_err());
+    }
+
+    #[test]
+    fn test_writing_style_from_argument() {
+        let style: WritingStyle = serde_json::from_value(serde_json::json!("da-dearu")).unwrap();
+        assert_eq!(style, WritingStyle::Plain);
+        assert!(serde_json::from_value::<WritingStyle>(serde_json::json!("keigo")).is_err());
+    }
+
     #[test]
     fn test_extract_json_direct() {
         let config = create_test_config("claude");
@@ -418,10 +2213,35 @@ Hope this helps!"#;
 
         let response = r#"{"suggestion": "修正後", "explanation": "理由", "confidence": 0.9}"#;
         let result = client.parse_response(response).unwrap();
+        let best = result.best().unwrap();
+
+        assert_eq!(best.suggestion, "修正後");
+        assert_eq!(best.explanation, "理由");
+        assert!((best.confidence - 0.9).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_parse_response_multiple_candidates() {
+        let config = create_test_config("claude");
+        let client = LlmClient::new(config);
+
+        let response = r#"{"candidates": [
+            {"suggestion": "案A", "explanation": "理由A", "confidence": 0.6},
+            {"suggestion": "案B", "explanation": "理由B", "confidence": 0.8},
+            {"suggestion": "案C", "explanation": "理由C", "confidence": 0.4}
+        ]}"#;
+        let result = client.parse_response(response).unwrap();
 
-        assert_eq!(result.suggestion, "修正後");
-        assert_eq!(result.explanation, "理由");
-        assert!((result.confidence - 0.9).abs() < 0.01);
+        let suggestions: Vec<&str> = result.candidates.iter().map(|c| c.suggestion.as_str()).collect();
+        assert_eq!(suggestions, vec!["案B", "案A", "案C"]);
+    }
+
+    #[test]
+    fn test_parse_response_no_candidates() {
+        let config = create_test_config("claude");
+        let client = LlmClient::new(config);
+
+        assert!(client.parse_response(r#"{"candidates": []}"#).is_err());
     }
 
     #[test]
@@ -431,11 +2251,11 @@ Hope this helps!"#;
 
         let response = r#"{"suggestion": "test", "explanation": "test", "confidence": 1.5}"#;
         let result = client.parse_response(response).unwrap();
-        assert_eq!(result.confidence, 1.0);
+        assert_eq!(result.best().unwrap().confidence, 1.0);
 
         let response = r#"{"suggestion": "test", "explanation": "test", "confidence": -0.5}"#;
         let result = client.parse_response(response).unwrap();
-        assert_eq!(result.confidence, 0.0);
+        assert_eq!(result.best().unwrap().confidence, 0.0);
     }
 
     #[test]
@@ -453,9 +2273,36 @@ Hope this helps!"#;
             text: "食べれる".to_string(),
             context: Some("彼は魚を".to_string()),
             issue: Some("ら抜き言葉の可能性".to_string()),
+            candidates: 1,
         };
 
         assert_eq!(request.text, "食べれる");
         assert_eq!(request.context, Some("彼は魚を".to_string()));
     }
+
+    #[test]
+    fn test_split_into_chunks_paragraphs() {
+        let text = "一段落目です。\n続きです。\n\n二段落目です。\n";
+        let chunks = split_into_chunks(text, MAX_CHUNK_CHARS);
+
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0].text, "一段落目です。\n続きです。");
+        assert_eq!(chunks[1].text, "二段落目です。");
+        assert_eq!(&text[chunks[1].start_byte..chunks[1].end_byte], "二段落目です。");
+    }
+
+    #[test]
+    fn test_split_into_chunks_long_paragraph() {
+        let text = "一文目です。二文目です。三文目です。";
+        let chunks = split_into_chunks(text, 12);
+
+        let texts: Vec<&str> = chunks.iter().map(|c| c.text.as_str()).collect();
+        assert_eq!(texts, vec!["一文目です。二文目です。", "三文目です。"]);
+    }
+
+    #[test]
+    fn test_split_into_chunks_empty() {
+        assert!(split_into_chunks("", MAX_CHUNK_CHARS).is_empty());
+        assert!(split_into_chunks("\n\n", MAX_CHUNK_CHARS).is_empty());
+    }
 }
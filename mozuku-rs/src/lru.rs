@@ -0,0 +1,187 @@
+//! Size-bounded LRU map
+//!
+//! Each entry is inserted with a size (a number of entries, bytes, ...);
+//! the least recently used entries are evicted once the total size exceeds
+//! the capacity. Used by the in-memory caches so that a long-running server
+//! does not keep growing.
+
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+#[derive(Debug)]
+struct Entry<V> {
+    value: V,
+    size: usize,
+    /// Tick of the last use
+    last_used: u64,
+}
+
+/// Map evicting the least recently used entries beyond a total size
+#[derive(Debug)]
+pub struct LruMap<K, V> {
+    entries: HashMap<K, Entry<V>>,
+    tick: u64,
+    size: usize,
+    capacity: usize,
+}
+
+impl<K: Eq + Hash + Clone, V> LruMap<K, V> {
+    /// Create an empty map holding entries up to a total size
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            tick: 0,
+            size: 0,
+            capacity,
+        }
+    }
+
+    /// Look up an entry, marking it as used
+    pub fn get<Q>(&mut self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.get_mut(key).map(|value| &*value)
+    }
+
+    /// Look up an entry for modification, marking it as used. The size of
+    /// the entry is kept; insert it again when the modification changes it.
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.tick += 1;
+        let entry = self.entries.get_mut(key)?;
+        entry.last_used = self.tick;
+        Some(&mut entry.value)
+    }
+
+    /// Insert or replace an entry, then evict the least recently used entries
+    /// until the total size fits the capacity. An entry larger than the whole
+    /// capacity is not kept, and evicts nothing else.
+    pub fn insert(&mut self, key: K, value: V, size: usize) {
+        if size > self.capacity {
+            self.remove(&key);
+            return;
+        }
+        self.tick += 1;
+        let entry = Entry {
+            value,
+            size,
+            last_used: self.tick,
+        };
+        self.size += size;
+        if let Some(old) = self.entries.insert(key, entry) {
+            self.size -= old.size;
+        }
+        self.evict();
+    }
+
+    /// Remove an entry
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        let entry = self.entries.remove(key)?;
+        self.size -= entry.size;
+        Some(entry.value)
+    }
+
+    /// Keep only the entries for which the predicate returns true
+    pub fn retain(&mut self, mut keep: impl FnMut(&K, &V) -> bool) {
+        let mut size = 0;
+        self.entries.retain(|key, entry| {
+            let kept = keep(key, &entry.value);
+            if kept {
+                size += entry.size;
+            }
+            kept
+        });
+        self.size = size;
+    }
+
+    /// Remove all entries
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.size = 0;
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Total size of the entries
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    fn evict(&mut self) {
+        while self.size > self.capacity {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone());
+            match oldest {
+                Some(key) => {
+                    self.remove(&key);
+                }
+                None => break,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        let mut map = LruMap::new(2);
+
+        map.insert("a", 1, 1);
+        map.insert("b", 2, 1);
+        assert_eq!(map.get("a"), Some(&1));
+        map.insert("c", 3, 1);
+
+        assert_eq!(map.get("b"), None);
+        assert_eq!(map.get("a"), Some(&1));
+        assert_eq!(map.get("c"), Some(&3));
+        assert_eq!(map.len(), 2);
+    }
+
+    #[test]
+    fn test_sizes() {
+        let mut map = LruMap::new(100);
+
+        map.insert("a", "a", 60);
+        map.insert("b", "b", 30);
+        map.insert("a", "a", 40);
+        assert_eq!(map.size(), 70);
+
+        // Evicts "b", the least recently used
+        map.insert("c", "c", 50);
+        assert_eq!(map.get("b"), None);
+        assert_eq!(map.get("a"), Some(&"a"));
+        assert_eq!(map.size(), 90);
+
+        map.insert("d", "d", 200);
+        assert_eq!(map.get("d"), None);
+        assert_eq!(map.size(), 90);
+
+        map.retain(|key, _| *key == "c");
+        assert_eq!(map.size(), 50);
+        assert_eq!(map.remove("c"), Some("c"));
+        assert!(map.is_empty());
+        assert_eq!(map.size(), 0);
+    }
+}
@@ -1,24 +1,98 @@
+use std::net::SocketAddr;
+use std::process::ExitCode;
+
 use anyhow::Result;
-use tower_lsp::{LspService, Server};
+use clap::{Parser, Subcommand};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
-use mozuku_rs::server::MozukuServer;
+use mozuku_rs::cli::{
+    self, BenchArgs, CheckArgs, ConfigArgs, DictArgs, InitArgs, ReadabilityArgs, RulesArgs, StatsArgs,
+};
+use mozuku_rs::transport::{self, Transport};
+
+/// Japanese proofreading Language Server
+#[derive(Debug, Parser)]
+#[command(name = "mozuku-rs", version, about)]
+struct Cli {
+    /// Communicate over stdio (default)
+    #[arg(long, conflicts_with_all = ["tcp", "websocket"])]
+    stdio: bool,
+
+    /// Listen for TCP connections on the given address (e.g. 127.0.0.1:9257)
+    #[arg(long, value_name = "ADDR", conflicts_with = "websocket")]
+    tcp: Option<SocketAddr>,
+
+    /// Listen for WebSocket connections on the given address (e.g. 127.0.0.1:9257)
+    #[arg(long, value_name = "ADDR")]
+    websocket: Option<SocketAddr>,
+
+    /// Accept WebSocket connections from browser pages with this origin
+    /// (e.g. http://localhost:3000); other origins are rejected
+    #[arg(long, value_name = "ORIGIN", requires = "websocket")]
+    allow_origin: Vec<String>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+/// Commands run instead of the language server
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Check files and directories and print the diagnostics
+    Check(CheckArgs),
+    /// Print the readability score (school grade) of files and directories
+    Readability(ReadabilityArgs),
+    /// List the rules, or explain one of them
+    Rules(RulesArgs),
+    /// Write a commented mozuku.toml to the current directory
+    Init(InitArgs),
+    /// Validate mozuku.toml
+    Config(ConfigArgs),
+    /// Manage the user dictionary
+    Dict(DictArgs),
+    /// Measure the time spent extracting, tokenizing and in each rule
+    Bench(BenchArgs),
+    /// Print the rule statistics recorded with [telemetry] enabled
+    Stats(StatsArgs),
+}
+
+impl Cli {
+    fn transport(&self) -> Transport {
+        match (self.tcp, self.websocket) {
+            (Some(addr), _) => Transport::Tcp(addr),
+            (_, Some(addr)) => Transport::WebSocket {
+                addr,
+                allowed_origins: self.allow_origin.clone(),
+            },
+            _ => Transport::Stdio,
+        }
+    }
+}
 
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() -> Result<ExitCode> {
+    let cli = Cli::parse();
+
     // Initialize logging
     tracing_subscriber::registry()
         .with(tracing_subscriber::fmt::layer().with_writer(std::io::stderr))
         .with(tracing_subscriber::EnvFilter::from_default_env())
         .init();
 
-    tracing::info!("Starting MoZuku Language Server...");
-
-    let stdin = tokio::io::stdin();
-    let stdout = tokio::io::stdout();
+    match &cli.command {
+        Some(Command::Check(args)) => return cli::check(args),
+        Some(Command::Readability(args)) => return cli::readability(args),
+        Some(Command::Rules(args)) => return cli::rules(args),
+        Some(Command::Init(args)) => return cli::init(args),
+        Some(Command::Config(args)) => return cli::config(args).await,
+        Some(Command::Dict(args)) => return cli::dict(args),
+        Some(Command::Bench(args)) => return cli::bench(args),
+        Some(Command::Stats(args)) => return cli::stats(args),
+        None => {}
+    }
 
-    let (service, socket) = LspService::new(MozukuServer::new);
-    Server::new(stdin, stdout, socket).serve(service).await;
+    tracing::info!("Starting MoZuku Language Server...");
 
-    Ok(())
+    transport::serve(cli.transport()).await?;
+    Ok(ExitCode::SUCCESS)
 }
@@ -0,0 +1,212 @@
+//! Masking of sensitive text before it is sent to an LLM
+//!
+//! Email addresses, phone numbers, strings that look like API keys, and words
+//! matching configured patterns are replaced with placeholders such as
+//! `⟦MASK1⟧`. The placeholders in the reply are restored to the original text
+//! before the reply is used.
+
+use regex::Regex;
+
+use crate::config::MaskingConfig;
+
+/// Email addresses
+const EMAIL_PATTERN: &str = r"[A-Za-z0-9._%+-]+@[A-Za-z0-9-]+(?:\.[A-Za-z0-9-]+)*\.[A-Za-z]{2,}";
+
+/// Japanese and international phone numbers written with separators, and mobile numbers without.
+/// Digits and word boundaries are ASCII-only (`(?-u)`), so numbers next to kana or kanji still match.
+const PHONE_PATTERN: &str = r"(?-u)(?:\+\d{1,3}[- ]?|\b0)\d{1,4}-\d{1,4}-\d{3,4}\b|\b0[5789]0\d{8}\b";
+
+/// Well-known API key and token formats, and long random-looking alphanumeric strings
+/// (with ASCII word boundaries, like the phone numbers)
+const API_KEY_PATTERN: &str = r"(?-u)\b(?:sk-[A-Za-z0-9_-]{16,}|AKIA[0-9A-Z]{16}|gh[pousr]_[A-Za-z0-9]{36,}|xox[abpr]-[A-Za-z0-9-]{10,}|AIza[0-9A-Za-z_-]{35}|[A-Za-z0-9]{32,})\b";
+
+/// Instruction appended to prompts containing placeholders
+const PLACEHOLDER_INSTRUCTION: &str = "\n\n⟦MASK1⟧ のような ⟦ ⟧ で囲まれたプレースホルダーは、変更せずにそのまま出力してください。";
+
+/// Replaces sensitive text with placeholders
+#[derive(Debug, Default)]
+pub struct Masker {
+    patterns: Vec<Regex>,
+}
+
+/// A masked text with the originals of its placeholders
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Masked {
+    /// Text with placeholders
+    pub text: String,
+    /// Placeholders and the text they replace
+    replacements: Vec<(String, String)>,
+}
+
+impl Masker {
+    /// Create a masker from the configuration. Invalid patterns are skipped with a warning.
+    pub fn new(config: &MaskingConfig) -> Self {
+        if !config.enabled {
+            return Self::default();
+        }
+
+        let builtin = [
+            (config.email, EMAIL_PATTERN),
+            (config.phone, PHONE_PATTERN),
+            (config.api_key, API_KEY_PATTERN),
+        ];
+        let patterns = builtin
+            .into_iter()
+            .filter(|(enabled, _)| *enabled)
+            .map(|(_, pattern)| pattern)
+            .chain(config.patterns.iter().map(String::as_str))
+            .filter_map(|pattern| match Regex::new(pattern) {
+                Ok(regex) => Some(regex),
+                Err(e) => {
+                    tracing::warn!("Ignoring invalid masking pattern {:?}: {}", pattern, e);
+                    None
+                }
+            })
+            .collect();
+
+        Self { patterns }
+    }
+
+    /// Replace the matches of all patterns with placeholders.
+    /// The same text always gets the same placeholder.
+    pub fn mask(&self, text: &str) -> Masked {
+        let mut masked = Masked {
+            text: text.to_string(),
+            replacements: Vec::new(),
+        };
+
+        for pattern in &self.patterns {
+            let text = std::mem::take(&mut masked.text);
+            let mut last = 0;
+            for found in pattern.find_iter(&text) {
+                // Placeholders of earlier patterns are kept as they are
+                if found.as_str().is_empty() || found.as_str().contains('⟦') {
+                    continue;
+                }
+                let placeholder = masked.placeholder(found.as_str());
+                masked.text.push_str(&text[last..found.start()]);
+                masked.text.push_str(&placeholder);
+                last = found.end();
+            }
+            masked.text.push_str(&text[last..]);
+        }
+
+        masked
+    }
+}
+
+impl Masked {
+    /// Check whether anything was masked
+    pub fn is_masked(&self) -> bool {
+        !self.replacements.is_empty()
+    }
+
+    /// Masked text as a prompt, telling the LLM to keep the placeholders
+    pub fn prompt(&self) -> String {
+        if self.is_masked() {
+            format!("{}{}", self.text, PLACEHOLDER_INSTRUCTION)
+        } else {
+            self.text.clone()
+        }
+    }
+
+    /// Restore the placeholders in a JSON reply, escaping the originals as JSON string content
+    pub fn unmask_json(&self, reply: &str) -> String {
+        let mut restored = reply.to_string();
+        for (placeholder, original) in &self.replacements {
+            let escaped = serde_json::to_string(original).unwrap_or_default();
+            restored = restored.replace(placeholder, &escaped[1..escaped.len() - 1]);
+        }
+        restored
+    }
+
+    fn placeholder(&mut self, original: &str) -> String {
+        if let Some((placeholder, _)) = self.replacements.iter().find(|(_, o)| o == original) {
+            return placeholder.clone();
+        }
+        let placeholder = format!("⟦MASK{}⟧", self.replacements.len() + 1);
+        self.replacements.push((placeholder.clone(), original.to_string()));
+        placeholder
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn masker() -> Masker {
+        Masker::new(&MaskingConfig::default())
+    }
+
+    #[test]
+    fn test_mask_builtin() {
+        let text = "連絡先は taro.yamada@example.co.jp または 03-1234-5678、09012345678 です。キーは sk-abcdefghijklmnop1234 です。";
+        let masked = masker().mask(text);
+
+        assert_eq!(
+            masked.text,
+            "連絡先は ⟦MASK1⟧ または ⟦MASK2⟧、⟦MASK3⟧ です。キーは ⟦MASK4⟧ です。"
+        );
+        assert!(masked.is_masked());
+        assert!(masked.prompt().ends_with(PLACEHOLDER_INSTRUCTION));
+    }
+
+    #[test]
+    fn test_mask_builtin_next_to_japanese() {
+        let text = "電話は03-1234-5678まで、携帯は09012345678です。キーはsk-abcdefghijklmnop1234です。";
+        let masked = masker().mask(text);
+
+        assert_eq!(masked.text, "電話は⟦MASK1⟧まで、携帯は⟦MASK2⟧です。キーは⟦MASK3⟧です。");
+    }
+
+    #[test]
+    fn test_mask_same_text_same_placeholder() {
+        let masked = masker().mask("a@example.com と a@example.com");
+
+        assert_eq!(masked.text, "⟦MASK1⟧ と ⟦MASK1⟧");
+    }
+
+    #[test]
+    fn test_mask_custom_patterns() {
+        let masker = Masker::new(&MaskingConfig {
+            patterns: vec!["プロジェクト[A-Z]+".to_string(), "(".to_string()],
+            ..Default::default()
+        });
+        let masked = masker.mask("プロジェクトXYZの進捗を共有する。");
+
+        assert_eq!(masked.text, "⟦MASK1⟧の進捗を共有する。");
+    }
+
+    #[test]
+    fn test_mask_disabled() {
+        let masker = Masker::new(&MaskingConfig {
+            enabled: false,
+            ..Default::default()
+        });
+        let masked = masker.mask("a@example.com");
+
+        assert!(!masked.is_masked());
+        assert_eq!(masked.prompt(), "a@example.com");
+    }
+
+    #[test]
+    fn test_unmask_json() {
+        let masker = Masker::new(&MaskingConfig {
+            patterns: vec![r#"社外秘"[^"]*""#.to_string()],
+            ..Default::default()
+        });
+        let masked = masker.mask(r#"社外秘"計画" は a@example.com へ"#);
+        let reply = r#"{"text": "⟦MASK2⟧は⟦MASK1⟧へ送る"}"#;
+
+        let restored = masked.unmask_json(reply);
+        let value: serde_json::Value = serde_json::from_str(&restored).unwrap();
+        assert_eq!(value["text"], r#"社外秘"計画"はa@example.comへ送る"#);
+    }
+
+    #[test]
+    fn test_plain_text_not_masked() {
+        let masked = masker().mask("これは普通の文章です。version 1.2.3 と 2024-06-01 の予定。");
+
+        assert!(!masked.is_masked());
+    }
+}
@@ -0,0 +1,151 @@
+//! Custom LSP extensions
+//!
+//! Notifications the server sends and requests it answers in addition to
+//! the standard protocol.
+
+use serde::{Deserialize, Serialize};
+use tower_lsp::lsp_types::notification::Notification;
+use tower_lsp::lsp_types::request::Request;
+use tower_lsp::lsp_types::{Diagnostic, DiagnosticSeverity, Range, Url};
+
+use crate::statistics::{Readability, TextStatistics};
+use crate::telemetry::Stats;
+
+/// `mozuku/documentStatus`: sent whenever analysis of a document completes,
+/// e.g. for showing "指摘 12 件 / 読みやすさ B" in a status bar
+pub enum DocumentStatus {}
+
+impl Notification for DocumentStatus {
+    type Params = DocumentStatusParams;
+    const METHOD: &'static str = "mozuku/documentStatus";
+}
+
+/// Parameters of `mozuku/documentStatus`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentStatusParams {
+    /// Analyzed document
+    pub uri: Url,
+    /// Version of the document that was analyzed
+    pub version: i32,
+    /// Total number of diagnostics
+    pub diagnostic_count: usize,
+    /// Number of diagnostics per severity
+    pub severity_counts: SeverityCounts,
+    /// Number of characters (excluding whitespace)
+    pub char_count: usize,
+    /// Number of sentences
+    pub sentence_count: usize,
+    /// Readability grade ("A" to "D")
+    pub readability: Readability,
+    /// Whether only rules without morphological analysis are running
+    pub degraded: bool,
+}
+
+/// Number of diagnostics per severity
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SeverityCounts {
+    pub error: usize,
+    pub warning: usize,
+    pub information: usize,
+    pub hint: usize,
+}
+
+impl SeverityCounts {
+    /// Count diagnostics by severity; diagnostics without one count as errors
+    pub fn from_diagnostics(diagnostics: &[Diagnostic]) -> Self {
+        let mut counts = Self::default();
+        for diag in diagnostics {
+            match diag.severity {
+                Some(DiagnosticSeverity::WARNING) => counts.warning += 1,
+                Some(DiagnosticSeverity::INFORMATION) => counts.information += 1,
+                Some(DiagnosticSeverity::HINT) => counts.hint += 1,
+                _ => counts.error += 1,
+            }
+        }
+        counts
+    }
+}
+
+impl DocumentStatusParams {
+    /// Build the status of an analyzed document
+    pub fn new(uri: Url, version: i32, content: &str, diagnostics: &[Diagnostic], degraded: bool) -> Self {
+        let stats = TextStatistics::from_text(content);
+        Self {
+            uri,
+            version,
+            diagnostic_count: diagnostics.len(),
+            severity_counts: SeverityCounts::from_diagnostics(diagnostics),
+            char_count: stats.char_count,
+            sentence_count: stats.sentence_count,
+            readability: stats.readability(),
+            degraded,
+        }
+    }
+}
+
+/// `mozuku/proofreadProgress`: partial results of whole-document AI proofreading,
+/// sent while the response for each chunk streams in
+pub enum ProofreadProgress {}
+
+impl Notification for ProofreadProgress {
+    type Params = ProofreadProgressParams;
+    const METHOD: &'static str = "mozuku/proofreadProgress";
+}
+
+/// Parameters of `mozuku/proofreadProgress`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProofreadProgressParams {
+    /// Proofread document
+    pub uri: Url,
+    /// Range of the chunk being proofread
+    pub range: Range,
+    /// Index of the chunk (0-based)
+    pub chunk: usize,
+    /// Total number of chunks
+    pub chunks: usize,
+    /// Suggestion received so far
+    pub text: String,
+    /// Whether the suggestion for the chunk is complete
+    pub done: bool,
+}
+
+/// `mozuku/stats`: rule statistics recorded with `[telemetry] enabled = true`,
+/// including those of previous sessions (empty when disabled)
+pub enum RuleStatistics {}
+
+impl Request for RuleStatistics {
+    type Params = ();
+    type Result = Stats;
+    const METHOD: &'static str = "mozuku/stats";
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_document_status_params() {
+        let diagnostic = |severity| Diagnostic {
+            severity: Some(severity),
+            ..Default::default()
+        };
+        let diagnostics = [
+            diagnostic(DiagnosticSeverity::WARNING),
+            diagnostic(DiagnosticSeverity::WARNING),
+            diagnostic(DiagnosticSeverity::HINT),
+        ];
+        let uri = Url::parse("file:///test.md").unwrap();
+
+        let params = DocumentStatusParams::new(uri, 3, "これはペンです。", &diagnostics, false);
+        let value = serde_json::to_value(&params).unwrap();
+
+        assert_eq!(value["uri"], "file:///test.md");
+        assert_eq!(value["diagnosticCount"], 3);
+        assert_eq!(value["severityCounts"]["warning"], 2);
+        assert_eq!(value["severityCounts"]["hint"], 1);
+        assert_eq!(value["readability"], "A");
+        assert_eq!(value["degraded"], false);
+    }
+}
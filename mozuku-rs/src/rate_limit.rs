@@ -0,0 +1,113 @@
+//! Client-side rate limiting of API requests
+//!
+//! Keeps requests within requests-per-minute (RPM) and tokens-per-minute (TPM)
+//! limits using a sliding one-minute window.
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+const WINDOW: Duration = Duration::from_secs(60);
+
+/// Sliding-window limiter for requests and tokens per minute
+#[derive(Debug, Default)]
+pub struct RateLimiter {
+    requests_per_minute: Option<u32>,
+    tokens_per_minute: Option<u32>,
+    /// Start time and token count of requests in the current window
+    window: Mutex<VecDeque<(Instant, u32)>>,
+}
+
+impl RateLimiter {
+    /// Create a limiter; `None` means no limit
+    pub fn new(requests_per_minute: Option<u32>, tokens_per_minute: Option<u32>) -> Self {
+        Self {
+            requests_per_minute,
+            tokens_per_minute,
+            window: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Wait until a request using `tokens` fits in the limits, then record it
+    pub async fn acquire(&self, tokens: u32) {
+        if self.requests_per_minute.is_none() && self.tokens_per_minute.is_none() {
+            return;
+        }
+        while let Some(wait) = self.try_acquire(Instant::now(), tokens) {
+            tracing::debug!("Rate limit reached, waiting {:?}", wait);
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Record the request if it fits, or return how long to wait before retrying
+    fn try_acquire(&self, now: Instant, tokens: u32) -> Option<Duration> {
+        let mut window = self.window.lock().unwrap_or_else(|e| e.into_inner());
+        while window.front().is_some_and(|(start, _)| now.duration_since(*start) >= WINDOW) {
+            window.pop_front();
+        }
+
+        let over_requests = self
+            .requests_per_minute
+            .is_some_and(|limit| window.len() >= limit.max(1) as usize);
+        // A single request larger than the limit is let through once the window is empty
+        let used_tokens: u32 = window.iter().map(|(_, tokens)| tokens).sum();
+        let over_tokens = self
+            .tokens_per_minute
+            .is_some_and(|limit| !window.is_empty() && used_tokens.saturating_add(tokens) > limit);
+
+        if over_requests || over_tokens {
+            let (oldest, _) = window.front()?;
+            return Some(WINDOW.saturating_sub(now.duration_since(*oldest)));
+        }
+
+        window.push_back((now, tokens));
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unlimited() {
+        let limiter = RateLimiter::new(None, None);
+        let now = Instant::now();
+
+        for _ in 0..100 {
+            assert_eq!(limiter.try_acquire(now, 10_000), None);
+        }
+    }
+
+    #[test]
+    fn test_requests_per_minute() {
+        let limiter = RateLimiter::new(Some(2), None);
+        let now = Instant::now();
+
+        assert_eq!(limiter.try_acquire(now, 1), None);
+        assert_eq!(limiter.try_acquire(now + Duration::from_secs(10), 1), None);
+        assert_eq!(
+            limiter.try_acquire(now + Duration::from_secs(20), 1),
+            Some(Duration::from_secs(40))
+        );
+        // The first request has left the window
+        assert_eq!(limiter.try_acquire(now + Duration::from_secs(60), 1), None);
+    }
+
+    #[test]
+    fn test_tokens_per_minute() {
+        let limiter = RateLimiter::new(None, Some(1_000));
+        let now = Instant::now();
+
+        assert_eq!(limiter.try_acquire(now, 600), None);
+        assert_eq!(limiter.try_acquire(now, 600), Some(WINDOW));
+        assert_eq!(limiter.try_acquire(now, 400), None);
+    }
+
+    #[test]
+    fn test_oversized_request_is_not_blocked_forever() {
+        let limiter = RateLimiter::new(None, Some(100));
+
+        assert_eq!(limiter.try_acquire(Instant::now(), 500), None);
+    }
+}
@@ -0,0 +1,757 @@
+//! Output formats of the `check` subcommand
+//!
+//! Diagnostics of all checked files are rendered at once, either as one line
+//! per diagnostic or in a machine-readable format for other tools. Reports
+//! for people (`--report html`) are written to a file alongside.
+
+use std::path::Path;
+
+use clap::ValueEnum;
+use serde::Serialize;
+use serde_json::{json, Value};
+use tower_lsp::lsp_types::{Diagnostic, DiagnosticSeverity, Range, Url};
+
+use crate::checker::diagnostic_fixes;
+use crate::cli::{diagnostic_code, display_position, format_diagnostic, severity_label, FileReport};
+use crate::readability::ReadabilityScore;
+use crate::rules::{self, RULES};
+use crate::statistics::{rule_counts, TextStatistics};
+use crate::utf16::{position_to_byte_offset, LineIndex};
+
+/// SARIF schema the output conforms to
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// Output format of diagnostics
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// One line per diagnostic (`path:line:column: severity: message [rule]`)
+    #[default]
+    Text,
+    /// SARIF 2.1.0, for GitHub Code Scanning
+    Sarif,
+    /// JSON with the files, positions, rules, messages and fixes
+    Json,
+    /// GitHub Actions workflow commands, annotating the lines of pull requests
+    Github,
+    /// reviewdog Diagnostic JSON (rdjson)
+    Rdjson,
+}
+
+/// Format of a report file written with `--report`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ReportFormat {
+    /// Static HTML page with the statistics, diagnostics and highlighted text of each file
+    Html,
+}
+
+/// JSON output: all checked files, including those without diagnostics
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonReport {
+    pub files: Vec<JsonFile>,
+    /// Number of diagnostics in all files
+    pub total: usize,
+}
+
+/// Diagnostics of a file in the JSON output
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonFile {
+    pub path: String,
+    pub diagnostics: Vec<JsonDiagnostic>,
+    pub readability: ReadabilityScore,
+}
+
+/// A diagnostic in the JSON output
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonDiagnostic {
+    /// Rule ID
+    pub rule: Option<String>,
+    /// `error`, `warning`, `info` or `hint`
+    pub severity: &'static str,
+    pub message: String,
+    pub start: JsonPosition,
+    pub end: JsonPosition,
+    /// Fix candidates, best first
+    pub fixes: Vec<JsonFix>,
+}
+
+/// 1-based line and column (in characters)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct JsonPosition {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// A replacement fixing a diagnostic in the JSON output
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonFix {
+    pub start: JsonPosition,
+    pub end: JsonPosition,
+    pub text: String,
+}
+
+/// Render the diagnostics of all files
+pub fn render(format: OutputFormat, reports: &[FileReport]) -> String {
+    match format {
+        OutputFormat::Text => lines(reports, |report, diag| {
+            format_diagnostic(&report.path, &report.content, diag)
+        }),
+        OutputFormat::Sarif => format!("{:#}\n", sarif(reports)),
+        OutputFormat::Github => lines(reports, github_annotation),
+        OutputFormat::Rdjson => format!("{:#}\n", rdjson(reports)),
+        OutputFormat::Json => format!(
+            "{}\n",
+            serde_json::to_string_pretty(&json_report(reports)).unwrap_or_default()
+        ),
+    }
+}
+
+/// Render a report file of all files
+pub fn render_report(format: ReportFormat, reports: &[FileReport]) -> String {
+    match format {
+        ReportFormat::Html => html(reports),
+    }
+}
+
+/// One line per diagnostic
+fn lines(reports: &[FileReport], line: impl Fn(&FileReport, &Diagnostic) -> String) -> String {
+    reports
+        .iter()
+        .flat_map(|report| report.diagnostics.iter().map(move |diag| (report, diag)))
+        .map(|(report, diag)| format!("{}\n", line(report, diag)))
+        .collect()
+}
+
+/// JSON output of the checked files
+pub fn json_report(reports: &[FileReport]) -> JsonReport {
+    let position = |content: &str, position| {
+        let (line, column) = display_position(content, position);
+        JsonPosition { line, column }
+    };
+
+    let files: Vec<JsonFile> = reports
+        .iter()
+        .map(|report| JsonFile {
+            path: report.path.display().to_string(),
+            diagnostics: report
+                .diagnostics
+                .iter()
+                .map(|diag| JsonDiagnostic {
+                    rule: diagnostic_code(diag),
+                    severity: severity_label(diag.severity),
+                    message: diag.message.clone(),
+                    start: position(&report.content, diag.range.start),
+                    end: position(&report.content, diag.range.end),
+                    fixes: diagnostic_fixes(diag)
+                        .into_iter()
+                        .map(|fix| JsonFix {
+                            start: position(&report.content, fix.range.start),
+                            end: position(&report.content, fix.range.end),
+                            text: fix.new_text,
+                        })
+                        .collect(),
+                })
+                .collect(),
+            readability: report.readability.clone(),
+        })
+        .collect();
+
+    JsonReport {
+        total: files.iter().map(|file| file.diagnostics.len()).sum(),
+        files,
+    }
+}
+
+/// GitHub Actions annotation of a diagnostic
+/// (`::warning file=...,line=...,col=...,endLine=...,endColumn=...,title=...::message`)
+pub fn github_annotation(report: &FileReport, diagnostic: &Diagnostic) -> String {
+    let level = match diagnostic.severity {
+        Some(DiagnosticSeverity::ERROR) => "error",
+        Some(DiagnosticSeverity::INFORMATION) | Some(DiagnosticSeverity::HINT) => "notice",
+        _ => "warning",
+    };
+    let (line, column) = display_position(&report.content, diagnostic.range.start);
+    let (end_line, end_column) = display_position(&report.content, diagnostic.range.end);
+
+    let mut properties = format!(
+        "file={},line={},col={},endLine={},endColumn={}",
+        escape_property(&artifact_uri(&report.path)),
+        line,
+        column,
+        end_line,
+        end_column
+    );
+    if let Some(code) = diagnostic_code(diagnostic) {
+        let title = match rules::find_rule(&code) {
+            Some(rule) => format!("{} ({})", rule.name, code),
+            None => code,
+        };
+        properties.push_str(&format!(",title={}", escape_property(&title)));
+    }
+    format!("::{} {}::{}", level, properties, escape_data(&diagnostic.message))
+}
+
+/// Escape the message of a workflow command
+fn escape_data(text: &str) -> String {
+    text.replace('%', "%25").replace('\r', "%0D").replace('\n', "%0A")
+}
+
+/// Escape a property value of a workflow command
+fn escape_property(text: &str) -> String {
+    escape_data(text).replace(':', "%3A").replace(',', "%2C")
+}
+
+/// reviewdog Diagnostic JSON (`DiagnosticResult`) of all files.
+///
+/// Columns are 1-based UTF-8 byte counts as rdjson specifies, and fix
+/// candidates become suggestions.
+pub fn rdjson(reports: &[FileReport]) -> Value {
+    let diagnostics: Vec<Value> = reports
+        .iter()
+        .flat_map(|report| {
+            report
+                .diagnostics
+                .iter()
+                .map(move |diag| rdjson_diagnostic(report, diag))
+        })
+        .collect();
+
+    json!({
+        "source": { "name": "mozuku", "url": env!("CARGO_PKG_REPOSITORY") },
+        "diagnostics": diagnostics,
+    })
+}
+
+fn rdjson_diagnostic(report: &FileReport, diagnostic: &Diagnostic) -> Value {
+    let severity = match diagnostic.severity {
+        Some(DiagnosticSeverity::ERROR) => "ERROR",
+        Some(DiagnosticSeverity::INFORMATION) | Some(DiagnosticSeverity::HINT) => "INFO",
+        _ => "WARNING",
+    };
+    let mut value = json!({
+        "message": diagnostic.message,
+        "location": {
+            "path": report.path.display().to_string(),
+            "range": rdjson_range(&report.content, &diagnostic.range),
+        },
+        "severity": severity,
+    });
+
+    if let Some(code) = diagnostic_code(diagnostic) {
+        value["code"] = match rules::find_rule(&code) {
+            Some(rule) => json!({ "value": code, "url": rule.doc_url().as_str() }),
+            None => json!({ "value": code }),
+        };
+    }
+
+    let suggestions: Vec<Value> = diagnostic_fixes(diagnostic)
+        .into_iter()
+        .map(|fix| json!({ "range": rdjson_range(&report.content, &fix.range), "text": fix.new_text }))
+        .collect();
+    if !suggestions.is_empty() {
+        value["suggestions"] = json!(suggestions);
+    }
+    value
+}
+
+/// rdjson range of an LSP range (1-based lines, 1-based UTF-8 byte columns)
+fn rdjson_range(content: &str, range: &Range) -> Value {
+    let position = |position| {
+        let offset = position_to_byte_offset(content, position);
+        let line_start = content[..offset].rfind('\n').map_or(0, |i| i + 1);
+        json!({ "line": position.line + 1, "column": offset - line_start + 1 })
+    };
+    json!({ "start": position(range.start), "end": position(range.end) })
+}
+
+/// SARIF log with one run.
+///
+/// All built-in rules are listed in the driver; results refer to them by ID
+/// and index. Columns are counted in Unicode code points, and the fix
+/// candidates of a diagnostic become SARIF fixes.
+pub fn sarif(reports: &[FileReport]) -> Value {
+    let rules: Vec<Value> = RULES
+        .iter()
+        .map(|rule| {
+            json!({
+                "id": rule.id,
+                "name": rule.name,
+                "shortDescription": { "text": rule.description },
+                "fullDescription": { "markdown": rule.explanation(), "text": rule.description },
+                "helpUri": rule.doc_url().as_str(),
+                "defaultConfiguration": {
+                    "level": sarif_level(Some(rule.severity)),
+                    "enabled": rule.default_enabled,
+                },
+                "properties": { "tags": [rule.category.id()] },
+            })
+        })
+        .collect();
+
+    let results: Vec<Value> = reports
+        .iter()
+        .flat_map(|report| report.diagnostics.iter().map(move |diag| sarif_result(report, diag)))
+        .collect();
+
+    json!({
+        "$schema": SARIF_SCHEMA,
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "MoZuku",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": env!("CARGO_PKG_REPOSITORY"),
+                    "rules": rules,
+                }
+            },
+            "columnKind": "unicodeCodePoints",
+            "results": results,
+        }]
+    })
+}
+
+fn sarif_result(report: &FileReport, diagnostic: &Diagnostic) -> Value {
+    let uri = artifact_uri(&report.path);
+    let mut result = json!({
+        "level": sarif_level(diagnostic.severity),
+        "message": { "text": diagnostic.message },
+        "locations": [{
+            "physicalLocation": {
+                "artifactLocation": { "uri": uri },
+                "region": sarif_region(&report.content, &diagnostic.range),
+            }
+        }],
+    });
+
+    if let Some(code) = diagnostic_code(diagnostic) {
+        if let Some(index) = RULES.iter().position(|rule| rule.id == code) {
+            result["ruleIndex"] = json!(index);
+        }
+        result["ruleId"] = json!(code);
+    }
+
+    let fixes: Vec<Value> = diagnostic_fixes(diagnostic)
+        .into_iter()
+        .map(|fix| {
+            json!({
+                "description": { "text": format!("「{}」に修正", fix.new_text) },
+                "artifactChanges": [{
+                    "artifactLocation": { "uri": uri },
+                    "replacements": [{
+                        "deletedRegion": sarif_region(&report.content, &fix.range),
+                        "insertedContent": { "text": fix.new_text },
+                    }],
+                }],
+            })
+        })
+        .collect();
+    if !fixes.is_empty() {
+        result["fixes"] = json!(fixes);
+    }
+    if let Some(rule) = rules::rule_for_diagnostic(diagnostic) {
+        result["properties"] = json!({ "ruleName": rule.name });
+    }
+    result
+}
+
+/// SARIF region of an LSP range (1-based lines, code point columns, exclusive end)
+fn sarif_region(content: &str, range: &Range) -> Value {
+    let (start_line, start_column) = display_position(content, range.start);
+    let (end_line, end_column) = display_position(content, range.end);
+    json!({
+        "startLine": start_line,
+        "startColumn": start_column,
+        "endLine": end_line,
+        "endColumn": end_column,
+    })
+}
+
+/// SARIF level of a diagnostic severity
+fn sarif_level(severity: Option<DiagnosticSeverity>) -> &'static str {
+    match severity {
+        Some(DiagnosticSeverity::ERROR) => "error",
+        Some(DiagnosticSeverity::INFORMATION) | Some(DiagnosticSeverity::HINT) => "note",
+        _ => "warning",
+    }
+}
+
+/// Stylesheet embedded in the HTML report, so the file can be shared on its own
+const HTML_STYLE: &str = "\
+body { font-family: sans-serif; margin: 2em auto; max-width: 60em; padding: 0 1em; color: #222; }
+table { border-collapse: collapse; margin: 1em 0; }
+th, td { border: 1px solid #ccc; padding: 0.3em 0.6em; text-align: left; vertical-align: top; }
+th { background: #f4f4f4; }
+pre { white-space: pre-wrap; background: #fafafa; border: 1px solid #ddd; padding: 1em; line-height: 1.7; }
+mark { background: #fff3b0; border-bottom: 2px solid #e0a800; }
+mark.error { background: #ffd6d6; border-color: #d33; }
+mark.info, mark.hint { background: #dcecff; border-color: #3b82f6; }
+td.error { color: #d33; }
+td.warning { color: #b07800; }
+.stats { color: #555; }
+";
+
+/// Static HTML report for sharing the results with reviewers.
+///
+/// The summary lists the diagnostics per rule and all checked files; each
+/// file with diagnostics gets its statistics, a table of the diagnostics and
+/// its text with the diagnostics highlighted.
+pub fn html(reports: &[FileReport]) -> String {
+    let diagnostics: Vec<&Diagnostic> = reports.iter().flat_map(|report| &report.diagnostics).collect();
+
+    let mut html = String::from("<!DOCTYPE html>\n<html lang=\"ja\">\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str("<title>MoZuku レポート</title>\n");
+    html.push_str(&format!("<style>\n{}</style>\n</head>\n<body>\n", HTML_STYLE));
+    html.push_str("<h1>MoZuku レポート</h1>\n");
+    html.push_str(&format!(
+        "<p>{}ファイル中 {}件の指摘</p>\n",
+        reports.len(),
+        diagnostics.len()
+    ));
+
+    if !diagnostics.is_empty() {
+        html.push_str("<h2>ルール別指摘数</h2>\n<table>\n<tr><th>ルール</th><th>ID</th><th>件数</th></tr>\n");
+        for rule in rule_counts(&diagnostics) {
+            html.push_str(&format!(
+                "<tr><td>{}</td><td><code>{}</code></td><td>{}</td></tr>\n",
+                escape_html(&rule.name),
+                escape_html(&rule.rule_id),
+                rule.count
+            ));
+        }
+        html.push_str("</table>\n");
+    }
+
+    html.push_str("<h2>ファイル</h2>\n<table>\n<tr><th>ファイル</th><th>指摘数</th></tr>\n");
+    for (index, report) in reports.iter().enumerate() {
+        let path = escape_html(&report.path.display().to_string());
+        let path = if report.diagnostics.is_empty() {
+            path
+        } else {
+            format!("<a href=\"#file-{}\">{}</a>", index, path)
+        };
+        html.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td></tr>\n",
+            path,
+            report.diagnostics.len()
+        ));
+    }
+    html.push_str("</table>\n");
+
+    for (index, report) in reports.iter().enumerate() {
+        if !report.diagnostics.is_empty() {
+            html.push_str(&html_file(index, report));
+        }
+    }
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+/// Section of a file in the HTML report
+fn html_file(index: usize, report: &FileReport) -> String {
+    let stats = TextStatistics::from_text(&report.content);
+    let mut html = format!(
+        "<section id=\"file-{}\">\n<h2>{}</h2>\n",
+        index,
+        escape_html(&report.path.display().to_string())
+    );
+    html.push_str(&format!(
+        "<p class=\"stats\">文字数 {} / 文数 {} / 平均文長 {:.1}文字 / 最長文 {}文字 / 漢字率 {:.1}% / 読みやすさ {:?}</p>\n",
+        stats.char_count,
+        stats.sentence_count,
+        stats.average_sentence_length,
+        stats.max_sentence_length,
+        stats.kanji_ratio * 100.0,
+        stats.readability()
+    ));
+    let score = &report.readability;
+    html.push_str(&format!(
+        "<p class=\"stats\">{}相当（{:.1}）/ 漢字語の平均長 {:.2}文字 / 難語率 {:.1}% / 受動態率 {:.1}%</p>\n",
+        score.grade_label(),
+        score.grade,
+        score.average_compound_length,
+        score.difficult_word_ratio * 100.0,
+        score.passive_ratio * 100.0
+    ));
+
+    html.push_str(
+        "<table>\n<tr><th>位置</th><th>重大度</th><th>ルール</th><th>メッセージ</th><th>修正候補</th></tr>\n",
+    );
+    for (n, diagnostic) in report.diagnostics.iter().enumerate() {
+        let (line, column) = display_position(&report.content, diagnostic.range.start);
+        let severity = severity_label(diagnostic.severity);
+        let rule = match diagnostic_code(diagnostic) {
+            Some(code) => match rules::find_rule(&code) {
+                Some(rule) => format!(
+                    "<a href=\"{}\">{}</a>",
+                    escape_html(rule.doc_url().as_str()),
+                    escape_html(rule.name)
+                ),
+                None => escape_html(&code),
+            },
+            None => String::new(),
+        };
+        let fixes: Vec<String> = diagnostic_fixes(diagnostic)
+            .iter()
+            .map(|fix| escape_html(&fix.new_text))
+            .collect();
+        html.push_str(&format!(
+            "<tr><td><a href=\"#d{}-{}\">{}:{}</a></td><td class=\"{}\">{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+            index,
+            n,
+            line,
+            column,
+            severity,
+            severity,
+            rule,
+            escape_html(&diagnostic.message),
+            fixes.join(" / ")
+        ));
+    }
+    html.push_str("</table>\n");
+
+    html.push_str(&format!("<pre>{}</pre>\n</section>\n", highlighted_text(index, report)));
+    html
+}
+
+/// Text of a file with the ranges of its diagnostics marked.
+///
+/// A diagnostic overlapping an earlier marked one is not marked.
+fn highlighted_text(index: usize, report: &FileReport) -> String {
+    let content = &report.content;
+    let line_index = LineIndex::new(content);
+    let mut ranges: Vec<(usize, usize, usize)> = report
+        .diagnostics
+        .iter()
+        .enumerate()
+        .map(|(n, diag)| {
+            (
+                line_index.offset(diag.range.start),
+                line_index.offset(diag.range.end),
+                n,
+            )
+        })
+        .collect();
+    ranges.sort();
+
+    let mut html = String::with_capacity(content.len());
+    let mut last = 0;
+    for (start, end, n) in ranges {
+        if start < last {
+            continue;
+        }
+        let end = end.max(start);
+        let diagnostic = &report.diagnostics[n];
+        html.push_str(&escape_html(&content[last..start]));
+        html.push_str(&format!(
+            "<mark id=\"d{}-{}\" class=\"{}\" title=\"{}\">{}</mark>",
+            index,
+            n,
+            severity_label(diagnostic.severity),
+            escape_html(&diagnostic.message),
+            escape_html(&content[start..end])
+        ));
+        last = end;
+    }
+    html.push_str(&escape_html(&content[last..]));
+    html
+}
+
+/// Escape text for HTML content and attribute values
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// URI of a checked file: relative paths stay relative (to the repository root
+/// in CI), with `/` separators
+fn artifact_uri(path: &Path) -> String {
+    if path.is_absolute() {
+        if let Ok(uri) = Url::from_file_path(path) {
+            return uri.to_string();
+        }
+    }
+    let path = path.to_string_lossy().replace('\\', "/");
+    path.strip_prefix("./").unwrap_or(&path).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checker::{fix_data, Fix};
+    use std::path::PathBuf;
+    use tower_lsp::lsp_types::{NumberOrString, Position};
+
+    fn report() -> FileReport {
+        let range = Range::new(Position::new(1, 3), Position::new(1, 6));
+        FileReport {
+            path: PathBuf::from("./docs/guide.md"),
+            content: "# 景色\n景色が見れる。".to_string(),
+            diagnostics: vec![Diagnostic {
+                range,
+                severity: Some(DiagnosticSeverity::WARNING),
+                code: Some(NumberOrString::String("ra-nuki".to_string())),
+                message: "ら抜き言葉です".to_string(),
+                data: fix_data(vec![Fix::new(range, "見られる")]),
+                ..Default::default()
+            }],
+            readability: ReadabilityScore::from_text("景色が見れる。"),
+        }
+    }
+
+    #[test]
+    fn test_render_text() {
+        assert_eq!(
+            render(OutputFormat::Text, &[report()]),
+            "./docs/guide.md:2:4: warning: ら抜き言葉です [ra-nuki]\n"
+        );
+    }
+
+    #[test]
+    fn test_sarif() {
+        let log = sarif(&[report()]);
+        let run = &log["runs"][0];
+        assert_eq!(log["version"], "2.1.0");
+        assert_eq!(run["tool"]["driver"]["rules"].as_array().unwrap().len(), RULES.len());
+        assert_eq!(
+            run["tool"]["driver"]["rules"][0]["defaultConfiguration"]["level"],
+            "warning"
+        );
+        assert_eq!(run["tool"]["driver"]["rules"][0]["properties"]["tags"][0], "grammar");
+
+        let result = &run["results"][0];
+        assert_eq!(result["ruleId"], "ra-nuki");
+        assert_eq!(RULES[result["ruleIndex"].as_u64().unwrap() as usize].id, "ra-nuki");
+        assert_eq!(result["level"], "warning");
+
+        let location = &result["locations"][0]["physicalLocation"];
+        assert_eq!(location["artifactLocation"]["uri"], "docs/guide.md");
+        assert_eq!(
+            location["region"],
+            json!({ "startLine": 2, "startColumn": 4, "endLine": 2, "endColumn": 7 })
+        );
+
+        let replacement = &result["fixes"][0]["artifactChanges"][0]["replacements"][0];
+        assert_eq!(replacement["insertedContent"]["text"], "見られる");
+        assert_eq!(replacement["deletedRegion"]["startColumn"], 4);
+    }
+
+    #[test]
+    fn test_json_report() {
+        let clean = FileReport {
+            path: PathBuf::from("README.md"),
+            content: String::new(),
+            diagnostics: Vec::new(),
+            readability: ReadabilityScore::default(),
+        };
+        let value = serde_json::to_value(json_report(&[report(), clean])).unwrap();
+
+        assert_eq!(value["total"], 1);
+        assert_eq!(
+            value["files"][1],
+            json!({
+                "path": "README.md",
+                "diagnostics": [],
+                "readability": serde_json::to_value(ReadabilityScore::default()).unwrap(),
+            })
+        );
+        assert_eq!(
+            value["files"][0]["diagnostics"][0],
+            json!({
+                "rule": "ra-nuki",
+                "severity": "warning",
+                "message": "ら抜き言葉です",
+                "start": { "line": 2, "column": 4 },
+                "end": { "line": 2, "column": 7 },
+                "fixes": [{
+                    "start": { "line": 2, "column": 4 },
+                    "end": { "line": 2, "column": 7 },
+                    "text": "見られる",
+                }],
+            })
+        );
+    }
+
+    #[test]
+    fn test_github_annotation() {
+        let report = report();
+
+        assert_eq!(
+            github_annotation(&report, &report.diagnostics[0]),
+            "::warning file=docs/guide.md,line=2,col=4,endLine=2,endColumn=7,title=ら抜き言葉 (ra-nuki)::ら抜き言葉です"
+        );
+        assert_eq!(escape_data("50%\n次"), "50%25%0A次");
+        assert_eq!(escape_property("a:b,c"), "a%3Ab%2Cc");
+    }
+
+    #[test]
+    fn test_rdjson() {
+        let value = rdjson(&[report()]);
+        assert_eq!(value["source"]["name"], "mozuku");
+
+        let diagnostic = &value["diagnostics"][0];
+        assert_eq!(diagnostic["severity"], "WARNING");
+        assert_eq!(diagnostic["code"]["value"], "ra-nuki");
+        assert_eq!(diagnostic["location"]["path"], "./docs/guide.md");
+        // "景色が" is 9 bytes in UTF-8
+        assert_eq!(
+            diagnostic["location"]["range"],
+            json!({ "start": { "line": 2, "column": 10 }, "end": { "line": 2, "column": 19 } })
+        );
+        assert_eq!(diagnostic["suggestions"][0]["text"], "見られる");
+    }
+
+    #[test]
+    fn test_html() {
+        let mut report = report();
+        report.content = "# <景色>\n景色が見れる。".to_string();
+        let clean = FileReport {
+            path: PathBuf::from("README.md"),
+            content: String::new(),
+            diagnostics: Vec::new(),
+            readability: ReadabilityScore::default(),
+        };
+        let html = html(&[report, clean]);
+
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<p>2ファイル中 1件の指摘</p>"));
+        assert!(html.contains("<tr><td>README.md</td><td>0</td></tr>"));
+        assert!(html.contains("<a href=\"#file-0\">./docs/guide.md</a>"));
+        assert!(html.contains("<td><a href=\"#d0-0\">2:4</a></td><td class=\"warning\">warning</td>"));
+        assert!(html.contains("<td>見られる</td>"));
+        assert!(html.contains("受動態率 0.0%</p>"));
+        assert!(html.contains(
+            "<pre># &lt;景色&gt;\n景色が<mark id=\"d0-0\" class=\"warning\" title=\"ら抜き言葉です\">見れる</mark>。</pre>"
+        ));
+        // Files without diagnostics get no section
+        assert!(!html.contains("id=\"file-1\""));
+    }
+
+    #[test]
+    fn test_escape_html() {
+        assert_eq!(
+            escape_html("<a href=\"x\">&'</a>"),
+            "&lt;a href=&quot;x&quot;&gt;&amp;&#39;&lt;/a&gt;"
+        );
+    }
+
+    #[test]
+    fn test_sarif_level() {
+        assert_eq!(sarif_level(Some(DiagnosticSeverity::ERROR)), "error");
+        assert_eq!(sarif_level(Some(DiagnosticSeverity::HINT)), "note");
+        assert_eq!(sarif_level(None), "warning");
+    }
+}
@@ -1,128 +1,1737 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 use std::sync::Arc;
+use std::time::Instant;
 
-use tokio::sync::RwLock;
-use tower_lsp::jsonrpc::Result;
+use rayon::prelude::*;
+use tokio::sync::{mpsc, RwLock};
+use tower_lsp::jsonrpc::{Error, Result};
 use tower_lsp::lsp_types::*;
 use tower_lsp::{Client, LanguageServer};
 
-use crate::analyzer::MorphologicalAnalyzer;
-use crate::checker::GrammarChecker;
-use crate::config::Config;
-use crate::extractor::{FileType, TextExtractor};
-use crate::llm::{LlmClient, ProofreadRequest};
-
-/// Document state stored for each open file
-#[derive(Debug, Clone)]
-pub struct DocumentState {
-    pub content: String,
-    pub version: i32,
-    pub file_type: FileType,
+use crate::analyzer::{split_bunsetsu, split_paragraphs, split_sentences, MorphologicalAnalyzer, TokenInfo};
+use crate::checker::{
+    diagnostic_fixes, fix_all, fix_data, large_file_diagnostic, map_span_diagnostic, CheckProfile, Fix, GrammarChecker,
+};
+use crate::config::{AiDiagnosticsConfig, Config};
+use crate::dictionary::{find_term, trailing_word, TermDictionary, TermSource};
+use crate::document::DocumentState;
+use crate::extractor::{FileType, Heading, SyntaxTree, TextExtractor, TextSpan};
+use crate::formatter::{format_on_type, TRIGGER_CHARACTERS};
+use crate::glossary::Glossary;
+use crate::llm::{
+    split_into_chunks, BatchItem, LlmClient, ProofreadCandidate, ProofreadRequest, ProofreadResponse, SentenceIssue,
+    WritingStyle, MAX_CHUNK_CHARS,
+};
+use crate::lru::LruMap;
+use crate::protocol::{DocumentStatus, DocumentStatusParams, ProofreadProgress, ProofreadProgressParams};
+use crate::readability;
+use crate::rules;
+use crate::statistics::{rule_counts, StatisticsReport, TextStatistics};
+use crate::suppression::{directive_comment, Suppressions, DISABLE_NEXT_LINE};
+use crate::telemetry::{Stats, Telemetry};
+use crate::terms::{self, FileTerms, TermIndex};
+use crate::utf16::{byte_offset_to_position, position_to_byte_offset, LineIndex};
+use crate::workspace::{collect_files, is_target_file, Exclusions, EXTENSIONS};
+
+/// Command: proofread the whole document (or a range) with the LLM
+pub const PROOFREAD_DOCUMENT_COMMAND: &str = "mozuku.proofreadDocument";
+
+/// Command: show statistics for the whole document (or a range)
+pub const SHOW_STATISTICS_COMMAND: &str = "mozuku.showStatistics";
+
+/// Command: replace a term with its canonical spelling across the workspace
+pub const REPLACE_TERM_IN_WORKSPACE_COMMAND: &str = "mozuku.replaceTermInWorkspace";
+
+/// Command: add a word to `ignore_words` of the project configuration
+pub const IGNORE_WORD_COMMAND: &str = "mozuku.ignoreWord";
+
+/// Command: mark a word reported by a rule as a false positive in the
+/// project configuration
+pub const MARK_FALSE_POSITIVE_COMMAND: &str = "mozuku.markFalsePositive";
+
+/// Command: disable a rule in the project configuration
+pub const DISABLE_RULE_COMMAND: &str = "mozuku.disableRule";
+
+/// Command converting a document (or selection) to です・ます調 or だ・である調
+pub const CONVERT_STYLE_COMMAND: &str = "mozuku.convertStyle";
+
+/// Command showing LLM token usage and estimated cost of the current month
+pub const SHOW_LLM_USAGE_COMMAND: &str = "mozuku.showLlmUsage";
+
+/// Command run by quick fixes to count the fix in the rule statistics
+pub const RECORD_FIX_COMMAND: &str = "mozuku.recordFix";
+
+/// Registration ID for the configuration and dictionary file watchers
+const WATCHED_FILES_REGISTRATION_ID: &str = "mozuku-watched-files";
+
+/// Documents at least this large (in bytes) report analysis progress
+const ANALYSIS_PROGRESS_MIN_BYTES: usize = 20_000;
+
+/// Maximum number of cached AI responses; the least recently used are evicted beyond it
+const AI_CANDIDATE_CACHE_SIZE: usize = 64;
+
+/// Memory (in bytes) available to the syntax trees and tokens cached for all
+/// documents; the least recently used documents are evicted beyond it
+const DOCUMENT_CACHE_MEMORY_LIMIT: usize = 256 * 1024 * 1024;
+
+/// Code action kind applying the fixes of all rule diagnostics
+const FIX_ALL_KIND: &str = "source.fixAll.mozuku";
+
+/// Code action kind of the batch AI fix
+const AI_FIX_ALL_KIND: &str = "source.fixAll.mozuku-ai";
+
+/// Diagnostics together with the document version they were computed for
+type VersionedDiagnostics = (i32, Vec<Diagnostic>);
+
+/// Source of diagnostics from the AI diagnostics pass
+pub const AI_DIAGNOSTIC_SOURCE: &str = "mozuku-ai";
+
+/// Diagnostic code of issues found by the AI diagnostics pass
+const AI_DIAGNOSTIC_CODE: &str = "ai-style";
+
+/// Tokens of the texts analyzed in one version of a document, shared by
+/// diagnostics, hover and semantic tokens
+struct DocumentTokens {
+    version: i32,
+    /// Tokens by analyzed text (the whole document or an extracted span)
+    tokens: HashMap<String, Arc<Vec<TokenInfo>>>,
+    /// Estimated memory used by the texts and tokens, in bytes
+    memory_size: usize,
+}
+
+/// Analysis results kept for a document between analyses
+#[derive(Default)]
+struct DocumentCache {
+    /// Syntax tree of the last analyzed version, reused to reparse the next
+    /// version incrementally
+    syntax_tree: Option<SyntaxTree>,
+    /// Tokens of the latest analyzed version
+    tokens: Option<DocumentTokens>,
+}
+
+impl DocumentCache {
+    /// Estimated memory used, in bytes
+    fn memory_size(&self) -> usize {
+        self.syntax_tree.as_ref().map_or(0, SyntaxTree::memory_size)
+            + self.tokens.as_ref().map_or(0, |tokens| tokens.memory_size)
+    }
+}
+
+/// A workspace root with the configuration and dictionary loaded for it
+#[derive(Clone)]
+struct WorkspaceRoot {
+    path: PathBuf,
+    config: Arc<Config>,
+    dictionary: Arc<TermDictionary>,
+}
+
+impl WorkspaceRoot {
+    fn load(path: PathBuf) -> Self {
+        let config = Config::load_for_root(&path);
+        let dictionary = TermDictionary::load(&config.dictionary);
+        Self {
+            path,
+            config: Arc::new(config),
+            dictionary: Arc::new(dictionary),
+        }
+    }
 }
 
 /// MoZuku Language Server implementation
 pub struct MozukuServer {
     client: Client,
     documents: Arc<RwLock<HashMap<Url, DocumentState>>>,
+    /// Workspace roots, each with its own configuration
+    workspace_roots: Arc<RwLock<Vec<WorkspaceRoot>>>,
+    /// Diagnostics most recently published for each document
+    diagnostics: Arc<RwLock<HashMap<Url, Vec<Diagnostic>>>>,
+    /// Diagnostics of the last AI diagnostics pass, with the analyzed version
+    ai_diagnostics: Arc<RwLock<HashMap<Url, VersionedDiagnostics>>>,
     analyzer: Arc<MorphologicalAnalyzer>,
+    /// Why the analyzer failed to initialize, if it runs in degraded mode
+    analyzer_error: Option<String>,
     checker: Arc<GrammarChecker>,
     extractor: Arc<TextExtractor>,
-    /// Configuration (stored for future use with dynamic checker settings)
-    #[allow(dead_code)]
-    config: Arc<Config>,
-    llm_client: Arc<LlmClient>,
+    /// Syntax trees and tokens of the last analyzed version of each document,
+    /// bounded by [`DOCUMENT_CACHE_MEMORY_LIMIT`]
+    document_cache: RwLock<LruMap<Url, DocumentCache>>,
+    /// Terms used in the files of the workspace, for the term-consistency rule.
+    /// Open documents are indexed as they are analyzed, other files from disk.
+    term_index: RwLock<TermIndex>,
+    /// Notation dictionary for documents outside any workspace root
+    dictionary: RwLock<Arc<TermDictionary>>,
+    /// Configuration for documents outside any workspace root, reloaded when
+    /// mozuku.toml or a dictionary changes
+    config: RwLock<Arc<Config>>,
+    llm_client: RwLock<Arc<LlmClient>>,
+    /// Rule statistics, recorded when `[telemetry]` enables them
+    telemetry: RwLock<Arc<Telemetry>>,
+    /// AI correction candidates by (text, issue), offered directly once fetched
+    ai_candidates: RwLock<LruMap<(String, String), Arc<ProofreadResponse>>>,
+    /// AI explanations shown on hover by (text, issue)
+    ai_explanations: RwLock<LruMap<(String, String), String>>,
+    /// Counter for generating unique work-done progress tokens
+    progress_counter: AtomicU32,
+    /// Whether the client supports server-initiated work-done progress
+    work_done_progress: AtomicBool,
+    /// Whether the client supports dynamic registration of file watchers
+    watch_registration: AtomicBool,
+    /// Whether file watchers are currently registered
+    watchers_registered: AtomicBool,
 }
 
 impl MozukuServer {
     pub fn new(client: Client) -> Self {
         let config = Config::load_from_default();
-        let analyzer = Arc::new(MorphologicalAnalyzer::new().expect("Failed to initialize analyzer"));
+        let user_dictionary = config.dictionary.user_dictionary.as_deref();
+        let analyzer = MorphologicalAnalyzer::with_user_dictionary(user_dictionary).or_else(|e| {
+            if user_dictionary.is_none() {
+                return Err(e);
+            }
+            tracing::warn!("{}; continuing without the user dictionary", e);
+            MorphologicalAnalyzer::new()
+        });
+        let (analyzer, analyzer_error) = match analyzer {
+            Ok(analyzer) => (analyzer, None),
+            Err(e) => {
+                tracing::error!("Failed to initialize morphological analyzer: {}", e);
+                (MorphologicalAnalyzer::degraded(), Some(e.to_string()))
+            }
+        };
+        let analyzer = Arc::new(analyzer);
         let checker = Arc::new(GrammarChecker::new(analyzer.clone()));
         let extractor = Arc::new(TextExtractor::new());
+        let dictionary = Arc::new(TermDictionary::load(&config.dictionary));
         let llm_client = Arc::new(LlmClient::new(config.clone()));
+        let telemetry = Arc::new(Telemetry::new(&config.telemetry));
 
         Self {
             client,
             documents: Arc::new(RwLock::new(HashMap::new())),
+            workspace_roots: Arc::new(RwLock::new(Vec::new())),
+            diagnostics: Arc::new(RwLock::new(HashMap::new())),
+            ai_diagnostics: Arc::new(RwLock::new(HashMap::new())),
             analyzer,
+            analyzer_error,
             checker,
             extractor,
-            config: Arc::new(config),
-            llm_client,
+            document_cache: RwLock::new(LruMap::new(DOCUMENT_CACHE_MEMORY_LIMIT)),
+            term_index: RwLock::new(TermIndex::default()),
+            dictionary: RwLock::new(dictionary),
+            config: RwLock::new(Arc::new(config)),
+            llm_client: RwLock::new(llm_client),
+            telemetry: RwLock::new(telemetry),
+            ai_candidates: RwLock::new(LruMap::new(AI_CANDIDATE_CACHE_SIZE)),
+            ai_explanations: RwLock::new(LruMap::new(AI_CANDIDATE_CACHE_SIZE)),
+            progress_counter: AtomicU32::new(0),
+            work_done_progress: AtomicBool::new(false),
+            watch_registration: AtomicBool::new(false),
+            watchers_registered: AtomicBool::new(false),
+        }
+    }
+
+    /// Detect file type from URI
+    fn detect_file_type(uri: &Url) -> FileType {
+        uri.path()
+            .rsplit('.')
+            .next()
+            .map(FileType::from_extension)
+            .unwrap_or(FileType::PlainText)
+    }
+
+    /// Detect file type of an opened document.
+    ///
+    /// The language ID takes precedence so that documents without a usable
+    /// extension (untitled documents, notebook cells) are still recognized.
+    fn detect_document_file_type(uri: &Url, language_id: &str) -> FileType {
+        FileType::from_language_id(language_id).unwrap_or_else(|| Self::detect_file_type(uri))
+    }
+
+    /// Analyze document and publish diagnostics
+    async fn analyze_document(&self, uri: &Url) {
+        // Snapshot the document so the lock is not held while reporting progress
+        let doc = match self.documents.read().await.get(uri) {
+            Some(doc) => doc.clone(),
+            None => return,
+        };
+
+        // Skip file types excluded by the configuration, clearing stale diagnostics
+        let config = self.config_for(uri).await;
+        if !config.checker.is_file_type_enabled(doc.file_type) {
+            if self.diagnostics.write().await.remove(uri).is_some() {
+                self.client.publish_diagnostics(uri.clone(), Vec::new(), Some(doc.version)).await;
+            }
+            if self.term_index.write().await.remove(uri) {
+                self.refresh_term_diagnostics(Some(uri)).await;
+            }
+            return;
+        }
+
+        // Diagnostics count in the rule statistics when they first appear
+        let telemetry = self.telemetry.read().await.clone();
+        let started = telemetry.is_enabled().then(Instant::now);
+        let previous_diagnostics = match started {
+            Some(_) => self.diagnostics.read().await.get(uri).cloned().unwrap_or_default(),
+            None => Vec::new(),
+        };
+
+        // Limit the analyzed text for very large documents
+        let text = config.checker.analysis_text(&doc.content);
+        let mut all_diagnostics = Vec::new();
+        if text.map(str::len) != Some(doc.content.len()) {
+            all_diagnostics.push(large_file_diagnostic(&config.checker, text.is_some()));
+        }
+        let text = text.unwrap_or_default();
+
+        let progress = if text.len() >= ANALYSIS_PROGRESS_MIN_BYTES {
+            self.begin_progress("MoZuku: 解析").await
+        } else {
+            None
+        };
+        if let Some(token) = &progress {
+            self.report_progress(token, "テキスト抽出中".to_string(), 0).await;
+        }
+
+        // Extract text spans based on file type, reparsing incrementally
+        let previous = self.update_document_cache(uri, |cache| cache.syntax_tree.take()).await;
+        let spans = match self.extractor.extract_incremental(text, doc.file_type, previous) {
+            Ok((spans, tree)) => {
+                if let Some(tree) = tree {
+                    self.update_document_cache(uri, |cache| cache.syntax_tree = Some(tree))
+                        .await;
+                }
+                spans
+            }
+            Err(_) => self.extract_spans(uri, text, doc.file_type),
+        };
+
+        // AI diagnostics stay valid until the document changes
+        let ai_diagnostics = match self.ai_diagnostics.read().await.get(uri) {
+            Some((version, diagnostics)) if *version == doc.version => diagnostics.clone(),
+            _ => Vec::new(),
+        };
+        let suppressions = Suppressions::parse(&doc.content);
+        let doc_index = LineIndex::new(&doc.content);
+
+        // Check the spans in parallel on the blocking thread pool, so that
+        // large documents are checked faster without blocking the runtime
+        let spans = Arc::new(spans);
+        let total = spans.len();
+        let mut cached = Vec::with_capacity(total);
+        for span in spans.iter() {
+            cached.push(self.cached_tokens(uri, doc.version, &span.text).await);
+        }
+        let (done_tx, mut done_rx) = mpsc::unbounded_channel();
+        let analyzer = self.analyzer.clone();
+        let checker = self.checker.clone();
+        let checker_config = config.clone();
+        let collect_terms = config.checker.is_rule_enabled(terms::RULE_ID);
+        let profiled = started.is_some();
+        let task_spans = spans.clone();
+        let task = tokio::task::spawn_blocking(move || {
+            task_spans
+                .par_iter()
+                .zip(cached)
+                .enumerate()
+                .map(|(i, (span, cached))| {
+                    let (tokens, is_new) = match cached {
+                        Some(tokens) => (tokens, false),
+                        None => (Arc::new(analyzer.tokenize(&span.text)), true),
+                    };
+                    let mut profile = CheckProfile::default();
+                    let diagnostics = if profiled {
+                        checker.check_with_tokens_profiled(&span.text, &tokens, &checker_config.checker, &mut profile)
+                    } else {
+                        checker.check_with_tokens(&span.text, &tokens, &checker_config.checker)
+                    };
+                    let terms = if collect_terms {
+                        terms::collect_terms(&span.text, &tokens, &checker_config.checker.term_groups)
+                    } else {
+                        Vec::new()
+                    };
+                    let _ = done_tx.send((i, diagnostics, terms, profile));
+                    (tokens, is_new)
+                })
+                .collect::<Vec<(Arc<Vec<TokenInfo>>, bool)>>()
+        });
+
+        // Collect the diagnostics of each span as soon as it is checked. Long
+        // documents (such as plain text split into chunks) publish the spans
+        // checked so far along with the progress.
+        let streaming = total > 1 && text.len() >= ANALYSIS_PROGRESS_MIN_BYTES;
+        let mut span_diagnostics: Vec<Vec<Diagnostic>> = vec![Vec::new(); total];
+        let mut term_occurrences = Vec::new();
+        let mut profile = CheckProfile::default();
+        let mut done = 0;
+        let mut last_percentage = 0;
+        while let Some((i, diagnostics, terms, span_profile)) = done_rx.recv().await {
+            let span = &spans[i];
+            let span_index = LineIndex::new(&span.text);
+            span_diagnostics[i] = diagnostics
+                .into_iter()
+                .map(|diag| map_span_diagnostic(diag, uri, &span_index, &doc_index, span.start_byte))
+                .collect();
+            term_occurrences.extend(terms.into_iter().map(|term| term.offset(span.start_byte)));
+            profile.merge(&span_profile);
+
+            done += 1;
+            let percentage = progress_percentage(done, total);
+            if done > 1 && percentage <= last_percentage {
+                continue;
+            }
+            last_percentage = percentage;
+            if let Some(token) = &progress {
+                self.report_progress(token, format!("解析中 ({}/{} スパン)", done, total), percentage)
+                    .await;
+            }
+            if streaming && done < total {
+                let partial = all_diagnostics
+                    .iter()
+                    .chain(span_diagnostics.iter().flatten())
+                    .chain(&ai_diagnostics)
+                    .filter(|diag| !suppressions.is_suppressed(diag))
+                    .cloned()
+                    .collect();
+                self.publish_partial_diagnostics(uri, doc.version, partial).await;
+            }
+        }
+        let span_tokens = task.await.unwrap_or_else(|e| {
+            tracing::error!("Failed to check {}: {}", uri, e);
+            Vec::new()
+        });
+
+        for (span, (tokens, is_new)) in spans.iter().zip(&span_tokens) {
+            if *is_new {
+                self.cache_tokens(uri, doc.version, &span.text, tokens.clone()).await;
+            }
+        }
+        all_diagnostics.extend(span_diagnostics.into_iter().flatten());
+        all_diagnostics.extend(ai_diagnostics);
+
+        // Readability is scored by section, over the spans of each section
+        if config.checker.is_rule_enabled(readability::RULE_ID) && span_tokens.len() == spans.len() {
+            let sections = readability::sections(&self.extractor, text, doc.file_type);
+            let scored: Vec<(&TextSpan, &[TokenInfo])> = spans
+                .iter()
+                .zip(&span_tokens)
+                .map(|(span, (tokens, _))| (span, tokens.as_slice()))
+                .collect();
+            all_diagnostics.extend(readability::check_sections(text, &sections, &scored, &config.checker));
+        }
+
+        // Terms written differently from the rest of the workspace. Other open
+        // documents are updated when this one changes the terms it uses.
+        let terms_changed = {
+            let mut term_index = self.term_index.write().await;
+            let changed = if config.checker.is_rule_enabled(terms::RULE_ID) {
+                term_index.update(uri, FileTerms::new(&doc.content, term_occurrences))
+            } else {
+                term_index.remove(uri)
+            };
+            all_diagnostics.extend(term_index.diagnostics(uri, &config.checker));
+            changed
+        };
+        if terms_changed {
+            self.refresh_term_diagnostics(Some(uri)).await;
+        }
+
+        // Honor inline suppression directives
+        all_diagnostics.retain(|diag| !suppressions.is_suppressed(diag));
+
+        if let Some(token) = progress {
+            self.end_progress(token, format!("{}件の指摘", all_diagnostics.len())).await;
+        }
+
+        // Discard the results if the document changed or was closed during analysis
+        if !is_current_version(&*self.documents.read().await, uri, doc.version) {
+            tracing::debug!("Discarding diagnostics for outdated version {} of {}", doc.version, uri);
+            return;
+        }
+
+        if let Some(started) = started {
+            telemetry.record_analysis(&previous_diagnostics, &all_diagnostics, &profile, started.elapsed());
+        }
+
+        let status = DocumentStatusParams::new(
+            uri.clone(),
+            doc.version,
+            &doc.content,
+            &all_diagnostics,
+            self.analyzer_error.is_some(),
+        );
+
+        // Re-publishing identical diagnostics makes them flicker in some editors
+        let changed = store_if_changed(&mut *self.diagnostics.write().await, uri, &all_diagnostics);
+        if changed {
+            self.client
+                .publish_diagnostics(uri.clone(), all_diagnostics, Some(doc.version))
+                .await;
+        }
+        self.client.send_notification::<DocumentStatus>(status).await;
+    }
+
+    /// Publish the diagnostics found so far while a long document is still
+    /// being checked, unless the document changed in the meantime
+    async fn publish_partial_diagnostics(&self, uri: &Url, version: i32, diagnostics: Vec<Diagnostic>) {
+        if !is_current_version(&*self.documents.read().await, uri, version) {
+            return;
+        }
+        if store_if_changed(&mut *self.diagnostics.write().await, uri, &diagnostics) {
+            self.client
+                .publish_diagnostics(uri.clone(), diagnostics, Some(version))
+                .await;
+        }
+    }
+
+    /// Run the AI diagnostics pass over a document and publish its results
+    /// together with the rule diagnostics
+    async fn run_ai_diagnostics(&self, uri: &Url) {
+        let config = self.config_for(uri).await;
+        let llm_client = self.llm_client.read().await.clone();
+        if !config.llm.diagnostics.enabled || !llm_client.is_available() {
+            return;
+        }
+
+        let doc = match self.documents.read().await.get(uri) {
+            Some(doc) => doc.clone(),
+            None => return,
+        };
+        if !config.checker.is_file_type_enabled(doc.file_type) {
+            return;
+        }
+
+        // Skip sentences that rules have already flagged
+        let flagged: Vec<std::ops::Range<usize>> = self
+            .diagnostics
+            .read()
+            .await
+            .get(uri)
+            .into_iter()
+            .flatten()
+            .filter(|diag| diag.source.as_deref() != Some(AI_DIAGNOSTIC_SOURCE))
+            .map(|diag| doc.offset_at(diag.range.start)..doc.offset_at(diag.range.end))
+            .collect();
+        let text = config.checker.analysis_text(&doc.content).unwrap_or_default();
+        let spans = self.extract_spans(uri, text, doc.file_type);
+        let targets = ai_diagnosis_targets(&spans, &flagged, &config.llm.diagnostics);
+        if targets.is_empty() {
+            return;
+        }
+
+        let batches: Vec<_> = targets.chunks(config.llm.diagnostics.batch_size.max(1)).collect();
+        let progress = self.begin_progress("MoZuku: AI診断").await;
+        let doc_index = LineIndex::new(&doc.content);
+        let mut diagnostics = Vec::new();
+
+        for (i, batch) in batches.iter().enumerate() {
+            if let Some(token) = &progress {
+                let message = format!("診断中 ({}/{} バッチ)", i + 1, batches.len());
+                self.report_progress(token, message, progress_percentage(i, batches.len()))
+                    .await;
+            }
+
+            let sentences: Vec<String> = batch.iter().map(|(_, sentence)| sentence.clone()).collect();
+            match llm_client.diagnose(&sentences).await {
+                Ok(issues) => diagnostics.extend(issues.iter().map(|issue| {
+                    let (range, sentence) = &batch[issue.index];
+                    let range = Range {
+                        start: doc_index.position(range.start),
+                        end: doc_index.position(range.end),
+                    };
+                    ai_diagnostic(range, sentence, issue)
+                })),
+                Err(e) => tracing::warn!("AI diagnostics failed for batch {}: {}", i + 1, e),
+            }
+        }
+
+        if let Some(token) = progress {
+            self.end_progress(token, format!("{}件の指摘", diagnostics.len())).await;
+        }
+
+        if !is_current_version(&*self.documents.read().await, uri, doc.version) {
+            return;
+        }
+        self.ai_diagnostics
+            .write()
+            .await
+            .insert(uri.clone(), (doc.version, diagnostics));
+        self.analyze_document(uri).await;
+    }
+
+    /// Tokens of a text of a document version, tokenizing it only on the
+    /// first request
+    async fn document_tokens(&self, uri: &Url, version: i32, text: &str) -> Arc<Vec<TokenInfo>> {
+        if let Some(tokens) = self.cached_tokens(uri, version, text).await {
+            return tokens;
+        }
+        let tokens = Arc::new(self.analyzer.tokenize(text));
+        self.cache_tokens(uri, version, text, tokens.clone()).await;
+        tokens
+    }
+
+    /// Cached tokens of a text of a document version
+    async fn cached_tokens(&self, uri: &Url, version: i32, text: &str) -> Option<Arc<Vec<TokenInfo>>> {
+        let mut cache = self.document_cache.write().await;
+        let cached = cache
+            .get(uri)?
+            .tokens
+            .as_ref()
+            .filter(|cached| cached.version == version)?;
+        cached.tokens.get(text).cloned()
+    }
+
+    /// Cache the tokens of a text of a document version. Tokens of older
+    /// versions are dropped, and tokens of outdated versions are not cached.
+    async fn cache_tokens(&self, uri: &Url, version: i32, text: &str, tokens: Arc<Vec<TokenInfo>>) {
+        self.update_document_cache(uri, |cache| {
+            let cached = cache.tokens.get_or_insert_with(|| DocumentTokens {
+                version,
+                tokens: HashMap::new(),
+                memory_size: 0,
+            });
+            if cached.version < version {
+                *cached = DocumentTokens {
+                    version,
+                    tokens: HashMap::new(),
+                    memory_size: 0,
+                };
+            }
+            if cached.version == version {
+                cached.memory_size += text.len() + tokens.iter().map(TokenInfo::memory_size).sum::<usize>();
+                if let Some(replaced) = cached.tokens.insert(text.to_string(), tokens) {
+                    cached.memory_size -= text.len() + replaced.iter().map(TokenInfo::memory_size).sum::<usize>();
+                }
+            }
+        })
+        .await;
+    }
+
+    /// Update the analysis results cached for a document. The least recently
+    /// used documents are evicted when the cache exceeds its memory limit.
+    async fn update_document_cache<R>(&self, uri: &Url, update: impl FnOnce(&mut DocumentCache) -> R) -> R {
+        let mut cache = self.document_cache.write().await;
+        let mut cached = cache.remove(uri).unwrap_or_default();
+        let result = update(&mut cached);
+        let size = cached.memory_size();
+        cache.insert(uri.clone(), cached, size);
+        result
+    }
+
+    /// Extract the text spans to check, falling back to the whole text
+    fn extract_spans(&self, uri: &Url, text: &str, file_type: FileType) -> Vec<TextSpan> {
+        match self.extractor.extract(text, file_type) {
+            Ok(spans) => spans,
+            Err(e) => {
+                tracing::warn!("Failed to extract text from {}: {}", uri, e);
+                self.extractor
+                    .extract(text, FileType::PlainText)
+                    .unwrap_or_default()
+            }
+        }
+    }
+
+    /// Links from glossary terms in a document to their definitions
+    async fn glossary_links(&self, uri: &Url) -> Option<Vec<DocumentLink>> {
+        let config = self.config_for(uri).await;
+        let path = std::path::absolute(config.glossary.path.as_ref()?).ok()?;
+        let glossary_uri = Url::from_file_path(&path).ok()?;
+        if glossary_uri == *uri {
+            return None;
+        }
+
+        let doc = self.target_document(uri).await?;
+        let text = config.checker.analysis_text(&doc.content)?;
+
+        // Prefer the open glossary document over the file on disk
+        let open_content = self
+            .documents
+            .read()
+            .await
+            .get(&glossary_uri)
+            .map(|glossary| glossary.content.clone());
+        let content = match open_content {
+            Some(content) => content,
+            None => std::fs::read_to_string(&path).ok()?,
+        };
+        let glossary = Glossary::parse(&content, &config.glossary.heading);
+
+        let index = LineIndex::new(&doc.content);
+        let mut links = Vec::new();
+        for span in self.extract_spans(uri, text, doc.file_type) {
+            for (range, entry) in glossary.find_terms(&span.text) {
+                let mut target = glossary_uri.clone();
+                target.set_fragment(Some(&format!("L{}", entry.line + 1)));
+                links.push(DocumentLink {
+                    range: Range {
+                        start: index.position(span.start_byte + range.start),
+                        end: index.position(span.start_byte + range.end),
+                    },
+                    target: Some(target),
+                    tooltip: Some(format!("用語集: {}", entry.term)),
+                    data: None,
+                });
+            }
+        }
+        Some(links)
+    }
+
+    /// Re-publish the diagnostics of open documents with their term-consistency
+    /// diagnostics recomputed, after the terms of another file changed
+    async fn refresh_term_diagnostics(&self, changed: Option<&Url>) {
+        let documents: Vec<(Url, i32, String)> = self
+            .documents
+            .read()
+            .await
+            .iter()
+            .filter(|(uri, _)| Some(*uri) != changed)
+            .map(|(uri, doc)| (uri.clone(), doc.version, doc.content.clone()))
+            .collect();
+
+        for (uri, version, content) in documents {
+            let Some(previous) = self.diagnostics.read().await.get(&uri).cloned() else {
+                continue;
+            };
+            let suppressions = Suppressions::parse(&content);
+            let config = self.config_for(&uri).await;
+            let mut diagnostics: Vec<Diagnostic> = previous
+                .into_iter()
+                .filter(|diag| rules::rule_for_diagnostic(diag).map(|rule| rule.id) != Some(terms::RULE_ID))
+                .collect();
+            diagnostics.extend(
+                self.term_index
+                    .read()
+                    .await
+                    .diagnostics(&uri, &config.checker)
+                    .into_iter()
+                    .filter(|diag| !suppressions.is_suppressed(diag)),
+            );
+
+            if store_if_changed(&mut *self.diagnostics.write().await, &uri, &diagnostics) {
+                self.client.publish_diagnostics(uri, diagnostics, Some(version)).await;
+            }
+        }
+    }
+
+    /// Rebuild the term index from the files of the workspace roots, then
+    /// update the diagnostics of open documents. Open documents keep the terms
+    /// of their current content.
+    async fn index_workspace_terms(&self) {
+        // Files belong to the innermost root containing them
+        let mut roots: Vec<WorkspaceRoot> = self.workspace_roots.read().await.clone();
+        roots.sort_by_key(|root| root.path.components().count());
+        let mut files: BTreeMap<PathBuf, Option<Arc<Config>>> = BTreeMap::new();
+        for root in &roots {
+            let exclusions = Exclusions::new(&root.path, &root.config.checker.exclude).unwrap_or_else(|e| {
+                tracing::warn!("Ignoring exclude patterns of {}: {:#}", root.path.display(), e);
+                Exclusions::default()
+            });
+            let collect_terms = root.config.checker.is_rule_enabled(terms::RULE_ID);
+            let config = collect_terms.then(|| root.config.clone());
+            for path in collect_files(&root.path, &exclusions) {
+                files.insert(path, config.clone());
+            }
+        }
+        let files: Vec<(PathBuf, Arc<Config>)> = files
+            .into_iter()
+            .filter_map(|(path, config)| Some((path, config?)))
+            .collect();
+
+        let progress = if files.is_empty() {
+            None
+        } else {
+            self.begin_progress("MoZuku: 用語の索引").await
+        };
+        let analyzer = self.analyzer.clone();
+        let extractor = self.extractor.clone();
+        let indexed = tokio::task::spawn_blocking(move || {
+            files
+                .par_iter()
+                .filter_map(|(path, config)| {
+                    let uri = Url::from_file_path(path).ok()?;
+                    let terms = terms::read_file_terms(path, &config.checker, &extractor, &analyzer)?;
+                    Some((uri, terms))
+                })
+                .collect::<Vec<(Url, FileTerms)>>()
+        })
+        .await
+        .unwrap_or_else(|e| {
+            tracing::error!("Failed to index workspace terms: {}", e);
+            Vec::new()
+        });
+
+        let file_count = indexed.len();
+        {
+            let open: HashSet<Url> = self.documents.read().await.keys().cloned().collect();
+            let mut term_index = self.term_index.write().await;
+            term_index.retain(|uri| open.contains(uri));
+            for (uri, terms) in indexed.into_iter().filter(|(uri, _)| !open.contains(uri)) {
+                term_index.update(&uri, terms);
+            }
+        }
+        if let Some(token) = progress {
+            self.end_progress(token, format!("{}ファイル", file_count)).await;
+        }
+
+        self.refresh_term_diagnostics(None).await;
+    }
+
+    /// Index files changed on disk again, skipping open documents.
+    /// Returns whether the terms used by any file changed.
+    async fn index_files(&self, uris: &[Url]) -> bool {
+        let mut targets = Vec::new();
+        for uri in uris {
+            if self.documents.read().await.contains_key(uri) {
+                continue;
+            }
+            // Files outside the roots, excluded or deleted are dropped from the index
+            let target = match (uri.to_file_path(), self.root_for(uri).await) {
+                (Ok(path), Some(root))
+                    if root.config.checker.is_rule_enabled(terms::RULE_ID)
+                        && !Exclusions::new(&root.path, &root.config.checker.exclude)
+                            .is_ok_and(|exclusions| exclusions.is_excluded(&path, false)) =>
+                {
+                    Some((path, root.config))
+                }
+                _ => None,
+            };
+            targets.push((uri.clone(), target));
+        }
+        if targets.is_empty() {
+            return false;
+        }
+
+        let analyzer = self.analyzer.clone();
+        let extractor = self.extractor.clone();
+        let indexed = tokio::task::spawn_blocking(move || {
+            targets
+                .into_iter()
+                .map(|(uri, target)| {
+                    let terms = target.and_then(|(path, config)| {
+                        terms::read_file_terms(&path, &config.checker, &extractor, &analyzer)
+                    });
+                    (uri, terms)
+                })
+                .collect::<Vec<_>>()
+        })
+        .await
+        .unwrap_or_default();
+
+        let mut term_index = self.term_index.write().await;
+        let mut changed = false;
+        for (uri, terms) in indexed {
+            changed |= match terms {
+                Some(terms) => term_index.update(&uri, terms),
+                None => term_index.remove(&uri),
+            };
+        }
+        changed
+    }
+
+    /// Re-analyze all open documents
+    async fn analyze_all_documents(&self) {
+        let uris: Vec<Url> = self.documents.read().await.keys().cloned().collect();
+        for uri in &uris {
+            self.analyze_document(uri).await;
+        }
+    }
+
+    /// Show statistics for a document (or the given range).
+    ///
+    /// Arguments: `[uri, range?]`.
+    async fn show_statistics(&self, arguments: &[serde_json::Value]) -> Result<Option<serde_json::Value>> {
+        let uri = match arguments
+            .first()
+            .and_then(|v| v.as_str())
+            .and_then(|s| Url::parse(s).ok())
+        {
+            Some(uri) => uri,
+            None => return Err(Error::invalid_params("Expected a document URI as the first argument")),
+        };
+        let range: Option<Range> = arguments
+            .get(1)
+            .and_then(|v| serde_json::from_value(v.clone()).ok());
+
+        let report = {
+            let documents = self.documents.read().await;
+            let doc = match documents.get(&uri) {
+                Some(doc) => doc,
+                None => return Ok(None),
+            };
+            let range = range.unwrap_or(Range {
+                start: Position::default(),
+                end: doc.position_at(doc.content.len()),
+            });
+
+            let start = doc.offset_at(range.start);
+            let end = doc.offset_at(range.end);
+            let stats = TextStatistics::from_text(&doc.content[start..end.max(start)]);
+
+            let diagnostics = self.diagnostics.read().await;
+            let diagnostics_in_range: Vec<&Diagnostic> = diagnostics
+                .get(&uri)
+                .into_iter()
+                .flatten()
+                .filter(|d| starts_in_range(d, &range))
+                .collect();
+
+            StatisticsReport {
+                statistics: stats,
+                diagnostic_count: diagnostics_in_range.len(),
+                rule_counts: rule_counts(&diagnostics_in_range),
+            }
+        };
+
+        let stats = &report.statistics;
+        self.client
+            .show_message(
+                MessageType::INFO,
+                format!(
+                    "文字数: {} / 文数: {} / 平均文長: {:.1}文字 / 漢字率: {:.1}% / 指摘数: {}件",
+                    stats.char_count,
+                    stats.sentence_count,
+                    stats.average_sentence_length,
+                    stats.kanji_ratio * 100.0,
+                    report.diagnostic_count
+                ),
+            )
+            .await;
+
+        // The Markdown rendering lets clients show the report as a virtual document
+        let title = uri.path_segments().and_then(|mut s| s.next_back()).unwrap_or(uri.as_str());
+        let mut value = serde_json::to_value(&report).unwrap_or_default();
+        value["markdown"] = serde_json::json!(report.to_markdown(title));
+        Ok(Some(value))
+    }
+
+    /// Add a word to `ignore_words` of the document's project configuration
+    async fn ignore_word(&self, arguments: &[serde_json::Value]) -> Result<Option<serde_json::Value>> {
+        let (uri, word) = match uri_and_string_arguments(arguments) {
+            Some(args) => args,
+            None => return Err(Error::invalid_params("Expected [uri, word] as arguments")),
+        };
+
+        let path = self.project_config_path(&uri).await;
+        Config::add_ignore_word(&path, &word).map_err(|e| Error::invalid_params(e.to_string()))?;
+        tracing::info!("Added \"{}\" to ignore_words in {}", word, path.display());
+
+        self.reload_config().await;
+        Ok(None)
+    }
+
+    /// Add a rule and the word it reported to `false_positives` of the
+    /// document's project configuration
+    async fn mark_false_positive(&self, arguments: &[serde_json::Value]) -> Result<Option<serde_json::Value>> {
+        let word = arguments.get(2).and_then(|v| v.as_str()).unwrap_or_default();
+        let (uri, rule_id) = match uri_and_string_arguments(arguments) {
+            Some(args) if !word.is_empty() => args,
+            _ => return Err(Error::invalid_params("Expected [uri, ruleId, word] as arguments")),
+        };
+
+        let path = self.project_config_path(&uri).await;
+        Config::add_false_positive(&path, &rule_id, word).map_err(|e| Error::invalid_params(e.to_string()))?;
+        tracing::info!(
+            "Marked \"{}\" ({}) as a false positive in {}",
+            word,
+            rule_id,
+            path.display()
+        );
+
+        self.reload_config().await;
+        Ok(None)
+    }
+
+    /// Disable a rule in the document's project configuration
+    async fn disable_rule(&self, arguments: &[serde_json::Value]) -> Result<Option<serde_json::Value>> {
+        let (uri, rule_id) = match uri_and_string_arguments(arguments) {
+            Some(args) => args,
+            None => return Err(Error::invalid_params("Expected [uri, ruleId] as arguments")),
+        };
+
+        let path = self.project_config_path(&uri).await;
+        Config::disable_rule(&path, &rule_id).map_err(|e| Error::invalid_params(e.to_string()))?;
+        tracing::info!("Disabled rule {} in {}", rule_id, path.display());
+
+        self.reload_config().await;
+        Ok(None)
+    }
+
+    /// AI correction candidates for a text and its issue.
+    ///
+    /// Responses are cached so that later code action requests for the same
+    /// diagnostic can offer each candidate directly.
+    async fn fetch_ai_candidates(&self, text: &str, issue: &str) -> anyhow::Result<Arc<ProofreadResponse>> {
+        let key = (text.to_string(), issue.to_string());
+        if let Some(response) = self.ai_candidates.write().await.get(&key) {
+            return Ok(response.clone());
+        }
+
+        let llm_client = self.llm_client.read().await.clone();
+        let response = Arc::new(
+            llm_client
+                .proofread(ProofreadRequest {
+                    text: text.to_string(),
+                    context: None,
+                    issue: Some(issue.to_string()),
+                    candidates: llm_client.candidates(),
+                })
+                .await?,
+        );
+
+        self.ai_candidates.write().await.insert(key, response.clone(), 1);
+        Ok(response)
+    }
+
+    /// Edit applying the fixes of all diagnostics of a document, with the
+    /// rule IDs of the applied fixes
+    async fn fix_all_edit(&self, uri: Url) -> Option<(WorkspaceEdit, Vec<String>)> {
+        let doc = self.documents.read().await.get(&uri)?.clone();
+        let diagnostics = self.diagnostics.read().await.get(&uri)?.clone();
+        let fixes = fix_all(&doc.content, &diagnostics);
+        if fixes.is_empty() {
+            return None;
+        }
+
+        let rule_ids = fixes
+            .iter()
+            .filter_map(|(diag, _)| match &diag.code {
+                Some(NumberOrString::String(code)) => Some(code.clone()),
+                _ => None,
+            })
+            .collect();
+        let edits = fixes
+            .into_iter()
+            .map(|(_, fix)| {
+                OneOf::Left(TextEdit {
+                    range: fix.range,
+                    new_text: fix.new_text,
+                })
+            })
+            .collect();
+        // Pin the edit to the analyzed version so stale results are rejected
+        let edit = WorkspaceEdit {
+            document_changes: Some(DocumentChanges::Edits(vec![TextDocumentEdit {
+                text_document: OptionalVersionedTextDocumentIdentifier {
+                    uri,
+                    version: Some(doc.version),
+                },
+                edits,
+            }])),
+            ..Default::default()
+        };
+        Some((edit, rule_ids))
+    }
+
+    /// Edit fixing all issues of a document, with corrections from batched LLM requests
+    async fn batch_fix_edit(&self, uri: Url) -> Option<WorkspaceEdit> {
+        let doc = self.documents.read().await.get(&uri)?.clone();
+        let diagnostics = self.diagnostics.read().await.get(&uri)?.clone();
+        let targets = batch_fix_items(&doc.content, &diagnostics);
+        let items: Vec<BatchItem> = targets.iter().map(|(_, item)| item.clone()).collect();
+
+        let llm_client = self.llm_client.read().await.clone();
+        let fixes = match llm_client.proofread_batch(&items).await {
+            Ok(fixes) => fixes,
+            Err(e) => {
+                tracing::warn!("LLM batch request failed: {}", e);
+                return None;
+            }
+        };
+
+        let edits: Vec<OneOf<TextEdit, AnnotatedTextEdit>> = fixes
+            .into_iter()
+            .map(|fix| {
+                OneOf::Left(TextEdit {
+                    range: targets[fix.index].0,
+                    new_text: fix.suggestion,
+                })
+            })
+            .collect();
+        if edits.is_empty() {
+            return None;
+        }
+
+        // Pin the edit to the analyzed version so stale results are rejected
+        Some(WorkspaceEdit {
+            document_changes: Some(DocumentChanges::Edits(vec![TextDocumentEdit {
+                text_document: OptionalVersionedTextDocumentIdentifier {
+                    uri,
+                    version: Some(doc.version),
+                },
+                edits,
+            }])),
+            ..Default::default()
+        })
+    }
+
+    /// AI explanation of a diagnostic, cached by its text and message
+    async fn fetch_ai_explanation(&self, content: &str, diagnostic: &Diagnostic) -> anyhow::Result<String> {
+        let text = self.get_text_at_range(content, &diagnostic.range);
+        let key = (text.clone(), diagnostic.message.clone());
+        if let Some(explanation) = self.ai_explanations.write().await.get(&key) {
+            return Ok(explanation.clone());
+        }
+
+        let line = content
+            .lines()
+            .nth(diagnostic.range.start.line as usize)
+            .unwrap_or_default();
+        let llm_client = self.llm_client.read().await.clone();
+        let explanation = llm_client.explain(&text, &diagnostic.message, line.trim()).await?;
+
+        self.ai_explanations.write().await.insert(key, explanation.clone(), 1);
+        Ok(explanation)
+    }
+
+    /// `mozuku.toml` of the workspace root containing the document
+    async fn project_config_path(&self, uri: &Url) -> PathBuf {
+        match self.root_for(uri).await {
+            Some(root) => root.path.join("mozuku.toml"),
+            None => PathBuf::from("mozuku.toml"),
+        }
+    }
+
+    /// Replace a term across all workspace files (and open documents).
+    ///
+    /// Arguments: `[from, to, dryRun?]`. With `dryRun` the generated
+    /// WorkspaceEdit is returned for preview instead of being applied.
+    async fn replace_term_in_workspace(&self, arguments: &[serde_json::Value]) -> Result<Option<serde_json::Value>> {
+        let from = arguments.first().and_then(|v| v.as_str()).unwrap_or_default();
+        let to = match arguments.get(1).and_then(|v| v.as_str()) {
+            Some(to) if !from.is_empty() => to,
+            _ => return Err(Error::invalid_params("Expected [from, to] terms as arguments")),
+        };
+        let dry_run = arguments.get(2).and_then(|v| v.as_bool()).unwrap_or(false);
+
+        // Open documents take precedence over the files on disk
+        let mut contents: HashMap<Url, String> = self
+            .documents
+            .read()
+            .await
+            .iter()
+            .map(|(uri, doc)| (uri.clone(), doc.content.clone()))
+            .collect();
+
+        let roots = self.workspace_roots.read().await.clone();
+        let paths: Vec<PathBuf> = roots
+            .iter()
+            .flat_map(|root| {
+                let exclusions = Exclusions::new(&root.path, &root.config.checker.exclude).unwrap_or_else(|e| {
+                    tracing::warn!("Ignoring exclude patterns of {}: {:#}", root.path.display(), e);
+                    Exclusions::default()
+                });
+                collect_files(&root.path, &exclusions)
+            })
+            .collect();
+
+        let progress = self.begin_progress("MoZuku: 用語の置換").await;
+        let mut last_percentage = 0;
+        for (i, path) in paths.iter().enumerate() {
+            if let Some(token) = &progress {
+                let percentage = progress_percentage(i, paths.len());
+                if percentage > last_percentage {
+                    self.report_progress(token, format!("ファイル読み込み中 ({}/{})", i + 1, paths.len()), percentage)
+                        .await;
+                    last_percentage = percentage;
+                }
+            }
+
+            if let Ok(uri) = Url::from_file_path(path) {
+                if let std::collections::hash_map::Entry::Vacant(entry) = contents.entry(uri) {
+                    if let Ok(content) = std::fs::read_to_string(path) {
+                        entry.insert(content);
+                    }
+                }
+            }
+        }
+
+        let mut changes = HashMap::new();
+        let mut replacement_count = 0;
+        for (uri, content) in &contents {
+            let edits = self.term_replacement_edits(content, Self::detect_file_type(uri), from, to);
+            if !edits.is_empty() {
+                replacement_count += edits.len();
+                changes.insert(uri.clone(), edits);
+            }
+        }
+
+        let file_count = changes.len();
+        if let Some(token) = progress {
+            self.end_progress(token, format!("{}ファイル・{}箇所", file_count, replacement_count)).await;
+        }
+
+        let edit = WorkspaceEdit {
+            changes: Some(changes),
+            ..Default::default()
+        };
+
+        if dry_run {
+            return Ok(Some(serde_json::to_value(&edit).unwrap_or_default()));
+        }
+
+        if file_count > 0 {
+            if let Err(e) = self.client.apply_edit(edit).await {
+                tracing::warn!("Failed to apply term replacement: {}", e);
+            }
+        }
+
+        Ok(Some(serde_json::json!({
+            "files": file_count,
+            "replacements": replacement_count,
+        })))
+    }
+
+    /// Build edits replacing `from` with `to` inside the prose of a document
+    fn term_replacement_edits(&self, content: &str, file_type: FileType, from: &str, to: &str) -> Vec<TextEdit> {
+        let spans = self
+            .extractor
+            .extract(content, file_type)
+            .unwrap_or_else(|_| vec![]);
+
+        // Spans may nest (e.g. list items containing paragraphs), so dedupe offsets
+        let offsets: BTreeSet<usize> = spans
+            .iter()
+            .flat_map(|span| {
+                find_term(&content[span.start_byte..span.end_byte], from)
+                    .into_iter()
+                    .map(move |offset| span.start_byte + offset)
+            })
+            .collect();
+
+        offsets
+            .into_iter()
+            .map(|offset| TextEdit {
+                range: Range {
+                    start: byte_offset_to_position(content, offset),
+                    end: byte_offset_to_position(content, offset + from.len()),
+                },
+                new_text: to.to_string(),
+            })
+            .collect()
+    }
+
+    /// Proofread a document (or the given range) with the LLM and apply the result.
+    ///
+    /// Arguments: `[uri, range?]`. The text is split into paragraph/sentence
+    /// chunks which are sent to the LLM one by one while reporting progress.
+    async fn proofread_document(&self, arguments: &[serde_json::Value]) -> Result<Option<serde_json::Value>> {
+        let uri = match arguments
+            .first()
+            .and_then(|v| v.as_str())
+            .and_then(|s| Url::parse(s).ok())
+        {
+            Some(uri) => uri,
+            None => return Err(Error::invalid_params("Expected a document URI as the first argument")),
+        };
+        let range: Option<Range> = arguments
+            .get(1)
+            .and_then(|v| serde_json::from_value(v.clone()).ok());
+
+        let llm_client = self.llm_client.read().await.clone();
+        if !llm_client.is_available() {
+            self.client
+                .show_message(
+                    MessageType::WARNING,
+                    format!("{}、AI校正を実行できません", llm_unavailable_reason(&llm_client)),
+                )
+                .await;
+            return Ok(None);
+        }
+
+        // Copy the content so that the lock is not held during LLM requests
+        let (content, version) = {
+            let documents = self.documents.read().await;
+            match documents.get(&uri) {
+                Some(doc) => (doc.content.clone(), doc.version),
+                None => return Ok(None),
+            }
+        };
+
+        let (start, end) = match range {
+            Some(range) => (
+                position_to_byte_offset(&content, range.start),
+                position_to_byte_offset(&content, range.end),
+            ),
+            None => (0, content.len()),
+        };
+        if start >= end {
+            return Ok(None);
         }
-    }
 
-    /// Detect file type from URI
-    fn detect_file_type(uri: &Url) -> FileType {
-        uri.path()
-            .rsplit('.')
-            .next()
-            .map(FileType::from_extension)
-            .unwrap_or(FileType::PlainText)
-    }
+        let chunks = split_into_chunks(&content[start..end], MAX_CHUNK_CHARS);
+        let progress = self.begin_progress("MoZuku: AI校正").await;
+
+        let mut edits = Vec::new();
+        let mut context: Option<String> = None;
+        for (i, chunk) in chunks.iter().enumerate() {
+            if let Some(ref token) = progress {
+                self.report_progress(
+                    token,
+                    format!("校正中 ({}/{} チャンク)", i + 1, chunks.len()),
+                    progress_percentage(i, chunks.len()),
+                )
+                .await;
+            }
 
-    /// Analyze document and publish diagnostics
-    async fn analyze_document(&self, uri: &Url) {
-        let documents = self.documents.read().await;
-        if let Some(doc) = documents.get(uri) {
-            // Extract text spans based on file type
-            let spans = match self.extractor.extract(&doc.content, doc.file_type) {
-                Ok(spans) => spans,
-                Err(e) => {
-                    tracing::warn!("Failed to extract text from {}: {}", uri, e);
-                    // Fall back to full document analysis
-                    let diagnostics = self.checker.check(&doc.content);
-                    self.client
-                        .publish_diagnostics(uri.clone(), diagnostics, Some(doc.version))
-                        .await;
-                    return;
+            let request = ProofreadRequest {
+                text: chunk.text.clone(),
+                context: context.take(),
+                issue: None,
+                candidates: 1,
+            };
+            let chunk_range = Range {
+                start: byte_offset_to_position(&content, start + chunk.start_byte),
+                end: byte_offset_to_position(&content, start + chunk.end_byte),
+            };
+            let progress_params = |text: String, done: bool| ProofreadProgressParams {
+                uri: uri.clone(),
+                range: chunk_range,
+                chunk: i,
+                chunks: chunks.len(),
+                text,
+                done,
+            };
+
+            // Forward partial suggestions to the client while the response streams in
+            let (partial_tx, mut partial_rx) = mpsc::unbounded_channel();
+            let proofread = llm_client.proofread_streaming(request, &partial_tx);
+            tokio::pin!(proofread);
+            let result = loop {
+                tokio::select! {
+                    result = &mut proofread => break result,
+                    Some(text) = partial_rx.recv() => {
+                        self.client
+                            .send_notification::<ProofreadProgress>(progress_params(text, false))
+                            .await;
+                    }
                 }
             };
 
-            // Check each extracted text span
-            let mut all_diagnostics = Vec::new();
-            for span in spans {
-                let span_diagnostics = self.checker.check(&span.text);
+            match result {
+                Ok(response) => {
+                    if let Some(best) = response.best() {
+                        self.client
+                            .send_notification::<ProofreadProgress>(progress_params(best.suggestion.clone(), true))
+                            .await;
+                        if !best.suggestion.is_empty() && best.suggestion != chunk.text {
+                            edits.push(OneOf::Left(TextEdit {
+                                range: chunk_range,
+                                new_text: best.suggestion.clone(),
+                            }));
+                        }
+                    }
+                }
+                Err(e) => tracing::warn!("LLM request failed for chunk {}: {}", i + 1, e),
+            }
 
-                // Adjust diagnostic positions based on span offset
-                for mut diag in span_diagnostics {
-                    // Store original line values before modification
-                    let orig_start_line = diag.range.start.line;
-                    let orig_end_line = diag.range.end.line;
+            // The previous chunk gives the LLM context for the next one
+            context = Some(chunk.text.clone());
+        }
 
-                    diag.range.start.line += span.start_line as u32;
-                    diag.range.end.line += span.start_line as u32;
+        let edit_count = edits.len();
+        if let Some(token) = progress {
+            self.end_progress(token, format!("{}件の修正", edit_count)).await;
+        }
 
-                    // If on the first line of the span, add column offset
-                    if orig_start_line == 0 {
-                        diag.range.start.character += span.start_col as u32;
-                    }
-                    if orig_end_line == 0 {
-                        diag.range.end.character += span.start_col as u32;
-                    }
+        if edit_count > 0 {
+            // Pin the edit to the proofread version so stale results are rejected
+            let edit = WorkspaceEdit {
+                document_changes: Some(DocumentChanges::Edits(vec![TextDocumentEdit {
+                    text_document: OptionalVersionedTextDocumentIdentifier {
+                        uri,
+                        version: Some(version),
+                    },
+                    edits,
+                }])),
+                ..Default::default()
+            };
+            if let Err(e) = self.client.apply_edit(edit).await {
+                tracing::warn!("Failed to apply proofreading edits: {}", e);
+            }
+        }
 
-                    all_diagnostics.push(diag);
-                }
+        Ok(Some(serde_json::json!({ "edits": edit_count })))
+    }
+
+    /// Convert a document (or the selected range) to another writing style,
+    /// one paragraph per LLM request, and apply the result as a workspace edit.
+    ///
+    /// Arguments: document URI, style (`"desu-masu"` or `"da-dearu"`), optional range.
+    async fn convert_style(&self, arguments: &[serde_json::Value]) -> Result<Option<serde_json::Value>> {
+        let uri = match arguments
+            .first()
+            .and_then(|v| v.as_str())
+            .and_then(|s| Url::parse(s).ok())
+        {
+            Some(uri) => uri,
+            None => return Err(Error::invalid_params("Expected a document URI as the first argument")),
+        };
+        let style: WritingStyle = match arguments.get(1).and_then(|v| serde_json::from_value(v.clone()).ok()) {
+            Some(style) => style,
+            None => {
+                return Err(Error::invalid_params(
+                    "Expected \"desu-masu\" or \"da-dearu\" as the second argument",
+                ))
             }
+        };
+        let range: Option<Range> = arguments
+            .get(2)
+            .and_then(|v| serde_json::from_value(v.clone()).ok());
 
+        let llm_client = self.llm_client.read().await.clone();
+        if !llm_client.is_available() {
             self.client
-                .publish_diagnostics(uri.clone(), all_diagnostics, Some(doc.version))
+                .show_message(
+                    MessageType::WARNING,
+                    format!("{}、文体を変換できません", llm_unavailable_reason(&llm_client)),
+                )
+                .await;
+            return Ok(None);
+        }
+
+        let (content, version) = {
+            let documents = self.documents.read().await;
+            match documents.get(&uri) {
+                Some(doc) => (doc.content.clone(), doc.version),
+                None => return Ok(None),
+            }
+        };
+
+        let (start, end) = match range {
+            Some(range) => (
+                position_to_byte_offset(&content, range.start),
+                position_to_byte_offset(&content, range.end),
+            ),
+            None => (0, content.len()),
+        };
+        if start >= end {
+            return Ok(None);
+        }
+
+        let paragraphs = split_into_chunks(&content[start..end], MAX_CHUNK_CHARS);
+        let progress = self.begin_progress(&format!("MoZuku: {}に変換", style.label())).await;
+
+        let mut edits = Vec::new();
+        for (i, paragraph) in paragraphs.iter().enumerate() {
+            if let Some(ref token) = progress {
+                self.report_progress(
+                    token,
+                    format!("変換中 ({}/{} 段落)", i + 1, paragraphs.len()),
+                    progress_percentage(i, paragraphs.len()),
+                )
                 .await;
+            }
+
+            match llm_client.convert_style(&paragraph.text, style).await {
+                Ok(converted) if converted != paragraph.text => edits.push(OneOf::Left(TextEdit {
+                    range: Range {
+                        start: byte_offset_to_position(&content, start + paragraph.start_byte),
+                        end: byte_offset_to_position(&content, start + paragraph.end_byte),
+                    },
+                    new_text: converted,
+                })),
+                Ok(_) => {}
+                Err(e) => tracing::warn!("Style conversion failed for paragraph {}: {}", i + 1, e),
+            }
+        }
+
+        let edit_count = edits.len();
+        if let Some(token) = progress {
+            self.end_progress(token, format!("{}段落を変換", edit_count)).await;
+        }
+
+        if edit_count > 0 {
+            let edit = WorkspaceEdit {
+                document_changes: Some(DocumentChanges::Edits(vec![TextDocumentEdit {
+                    text_document: OptionalVersionedTextDocumentIdentifier {
+                        uri,
+                        version: Some(version),
+                    },
+                    edits,
+                }])),
+                ..Default::default()
+            };
+            if let Err(e) = self.client.apply_edit(edit).await {
+                tracing::warn!("Failed to apply style conversion edits: {}", e);
+            }
+        }
+
+        Ok(Some(serde_json::json!({ "edits": edit_count })))
+    }
+
+    /// Show the LLM usage of the current month and return it as JSON
+    async fn show_llm_usage(&self) -> Result<Option<serde_json::Value>> {
+        let summary = self.llm_client.read().await.usage_summary();
+        self.client.show_message(MessageType::INFO, summary.message()).await;
+        Ok(serde_json::to_value(&summary).ok())
+    }
+
+    /// Count quick fixes applied from diagnostics of the given rules
+    async fn record_fix(&self, arguments: &[serde_json::Value]) -> Result<Option<serde_json::Value>> {
+        let rule_ids: Option<Vec<&str>> = arguments.iter().map(|v| v.as_str()).collect();
+        let rule_ids = match rule_ids {
+            Some(rule_ids) if !rule_ids.is_empty() => rule_ids,
+            _ => return Err(Error::invalid_params("Expected [ruleId, ...] as arguments")),
+        };
+        let telemetry = self.telemetry.read().await;
+        for rule_id in rule_ids {
+            telemetry.record_fix(rule_id);
+        }
+        Ok(None)
+    }
+
+    /// Handle `mozuku/stats`: the recorded rule statistics
+    pub async fn rule_statistics(&self) -> Result<Stats> {
+        Ok(self.telemetry.read().await.stats())
+    }
+
+    /// The configured dictionaries, pattern rule files, `.textlintrc` and
+    /// English dictionaries of the global and workspace configurations
+    async fn config_file_paths(&self) -> BTreeSet<PathBuf> {
+        let mut paths: BTreeSet<PathBuf> = BTreeSet::new();
+        {
+            let config = self.config.read().await;
+            paths.extend(config.dictionary.paths());
+            paths.extend(config.checker.rule_file_paths());
+        }
+        for root in self.workspace_roots.read().await.iter() {
+            paths.extend(root.config.dictionary.paths());
+            paths.extend(root.config.checker.rule_file_paths());
+        }
+        paths
+    }
+
+    /// Register file watchers for mozuku.toml and the files of
+    /// [`Self::config_file_paths`]
+    async fn register_file_watchers(&self) {
+        if !self.watch_registration.load(Ordering::Relaxed) {
+            return;
+        }
+
+        let watched_paths = self.config_file_paths().await;
+
+        let mut patterns = vec!["**/mozuku.toml".to_string()];
+        patterns.extend(Config::default_path().map(|path| path.display().to_string()));
+        patterns.extend(watched_paths.iter().map(|path| path.display().to_string()));
+        // Documents on disk keep the term index up to date
+        let roots = self.workspace_roots.read().await;
+        let collect_terms = roots
+            .iter()
+            .any(|root| root.config.checker.is_rule_enabled(terms::RULE_ID));
+        if collect_terms {
+            patterns.push(format!("**/*.{{{}}}", EXTENSIONS.join(",")));
+        }
+        drop(roots);
+
+        let options = DidChangeWatchedFilesRegistrationOptions {
+            watchers: patterns
+                .into_iter()
+                .map(|pattern| FileSystemWatcher {
+                    glob_pattern: GlobPattern::String(pattern),
+                    kind: None,
+                })
+                .collect(),
+        };
+
+        // Replace any previous registration, since watched paths may have changed
+        if self.watchers_registered.swap(false, Ordering::Relaxed) {
+            let unregistration = Unregistration {
+                id: WATCHED_FILES_REGISTRATION_ID.to_string(),
+                method: "workspace/didChangeWatchedFiles".to_string(),
+            };
+            if let Err(e) = self.client.unregister_capability(vec![unregistration]).await {
+                tracing::warn!("Failed to unregister file watchers: {}", e);
+            }
+        }
+
+        let registration = Registration {
+            id: WATCHED_FILES_REGISTRATION_ID.to_string(),
+            method: "workspace/didChangeWatchedFiles".to_string(),
+            register_options: serde_json::to_value(options).ok(),
+        };
+        match self.client.register_capability(vec![registration]).await {
+            Ok(()) => self.watchers_registered.store(true, Ordering::Relaxed),
+            Err(e) => tracing::warn!("Failed to register file watchers: {}", e),
+        }
+    }
+
+    /// Reload configuration and dictionaries, then re-diagnose all open documents
+    async fn reload_config(&self) {
+        let config = Config::load_from_default();
+        let mut watches_changed = self.config.read().await.dictionary != config.dictionary;
+
+        *self.dictionary.write().await = Arc::new(TermDictionary::load(&config.dictionary));
+        *self.llm_client.write().await = Arc::new(LlmClient::new(config.clone()));
+        let telemetry = Arc::new(Telemetry::new(&config.telemetry));
+        std::mem::replace(&mut *self.telemetry.write().await, telemetry).flush();
+        self.ai_candidates.write().await.clear();
+        self.ai_explanations.write().await.clear();
+        *self.config.write().await = Arc::new(config);
+
+        {
+            let mut roots = self.workspace_roots.write().await;
+            for root in roots.iter_mut() {
+                let reloaded = WorkspaceRoot::load(root.path.clone());
+                let terms_enabled = |config: &Config| config.checker.is_rule_enabled(terms::RULE_ID);
+                watches_changed |= reloaded.config.dictionary != root.config.dictionary
+                    || terms_enabled(&reloaded.config) != terms_enabled(&root.config);
+                *root = reloaded;
+            }
+        }
+
+        if watches_changed {
+            self.register_file_watchers().await;
+        }
+
+        self.client
+            .log_message(MessageType::INFO, "MoZuku configuration reloaded")
+            .await;
+
+        // Term groups and exclusions may have changed
+        self.index_workspace_terms().await;
+        self.analyze_all_documents().await;
+    }
+
+    /// Find the innermost workspace root containing the document
+    async fn root_for(&self, uri: &Url) -> Option<WorkspaceRoot> {
+        let path = uri.to_file_path().ok()?;
+        self.workspace_roots
+            .read()
+            .await
+            .iter()
+            .filter(|root| path.starts_with(&root.path))
+            .max_by_key(|root| root.path.components().count())
+            .cloned()
+    }
+
+    /// Configuration applying to the document
+    async fn config_for(&self, uri: &Url) -> Arc<Config> {
+        match self.root_for(uri).await {
+            Some(root) => root.config,
+            None => self.config.read().await.clone(),
+        }
+    }
+
+    /// Snapshot of an open document, if its file type is checked under its configuration
+    async fn target_document(&self, uri: &Url) -> Option<DocumentState> {
+        let config = self.config_for(uri).await;
+        self.documents
+            .read()
+            .await
+            .get(uri)
+            .filter(|doc| config.checker.is_file_type_enabled(doc.file_type))
+            .cloned()
+    }
+
+    /// Notation dictionary applying to the document
+    async fn dictionary_for(&self, uri: &Url) -> Arc<TermDictionary> {
+        match self.root_for(uri).await {
+            Some(root) => root.dictionary,
+            None => self.dictionary.read().await.clone(),
+        }
+    }
+
+    /// Begin a work-done progress report, returning its token if the client accepted it
+    async fn begin_progress(&self, title: &str) -> Option<ProgressToken> {
+        if !self.work_done_progress.load(Ordering::Relaxed) {
+            return None;
         }
+
+        let token = NumberOrString::String(format!(
+            "mozuku/{}",
+            self.progress_counter.fetch_add(1, Ordering::Relaxed)
+        ));
+
+        self.client
+            .send_request::<request::WorkDoneProgressCreate>(WorkDoneProgressCreateParams {
+                token: token.clone(),
+            })
+            .await
+            .ok()?;
+
+        self.client
+            .send_notification::<notification::Progress>(ProgressParams {
+                token: token.clone(),
+                value: ProgressParamsValue::WorkDone(WorkDoneProgress::Begin(WorkDoneProgressBegin {
+                    title: title.to_string(),
+                    cancellable: Some(false),
+                    message: None,
+                    percentage: Some(0),
+                })),
+            })
+            .await;
+
+        Some(token)
+    }
+
+    /// Report intermediate progress
+    async fn report_progress(&self, token: &ProgressToken, message: String, percentage: u32) {
+        self.client
+            .send_notification::<notification::Progress>(ProgressParams {
+                token: token.clone(),
+                value: ProgressParamsValue::WorkDone(WorkDoneProgress::Report(WorkDoneProgressReport {
+                    cancellable: Some(false),
+                    message: Some(message),
+                    percentage: Some(percentage),
+                })),
+            })
+            .await;
+    }
+
+    /// Finish a progress report
+    async fn end_progress(&self, token: ProgressToken, message: String) {
+        self.client
+            .send_notification::<notification::Progress>(ProgressParams {
+                token,
+                value: ProgressParamsValue::WorkDone(WorkDoneProgress::End(WorkDoneProgressEnd {
+                    message: Some(message),
+                })),
+            })
+            .await;
     }
 }
 
 #[tower_lsp::async_trait]
 impl LanguageServer for MozukuServer {
-    async fn initialize(&self, _: InitializeParams) -> Result<InitializeResult> {
+    async fn initialize(&self, params: InitializeParams) -> Result<InitializeResult> {
         tracing::info!("MoZuku server initializing...");
 
+        // Remember workspace roots for workspace-wide commands
+        #[allow(deprecated)]
+        let root_uris: Vec<Url> = match params.workspace_folders {
+            Some(folders) => folders.into_iter().map(|f| f.uri).collect(),
+            None => params.root_uri.into_iter().collect(),
+        };
+        *self.workspace_roots.write().await = root_uris
+            .iter()
+            .filter_map(|uri| uri.to_file_path().ok())
+            .map(WorkspaceRoot::load)
+            .collect();
+
+        let work_done_progress = params
+            .capabilities
+            .window
+            .as_ref()
+            .and_then(|window| window.work_done_progress)
+            .unwrap_or(false);
+        self.work_done_progress.store(work_done_progress, Ordering::Relaxed);
+
+        let watch_registration = params
+            .capabilities
+            .workspace
+            .as_ref()
+            .and_then(|workspace| workspace.did_change_watched_files.as_ref())
+            .and_then(|watched| watched.dynamic_registration)
+            .unwrap_or(false);
+        self.watch_registration.store(watch_registration, Ordering::Relaxed);
+
         Ok(InitializeResult {
             capabilities: ServerCapabilities {
                 text_document_sync: Some(TextDocumentSyncCapability::Options(
                     TextDocumentSyncOptions {
                         open_close: Some(true),
-                        change: Some(TextDocumentSyncKind::FULL),
+                        change: Some(TextDocumentSyncKind::INCREMENTAL),
                         save: Some(TextDocumentSyncSaveOptions::SaveOptions(SaveOptions {
                             include_text: Some(true),
                         })),
@@ -160,11 +1769,55 @@ impl LanguageServer for MozukuServer {
                         code_action_kinds: Some(vec![
                             CodeActionKind::QUICKFIX,
                             CodeActionKind::REFACTOR_REWRITE,
+                            CodeActionKind::new(FIX_ALL_KIND),
+                            CodeActionKind::new(AI_FIX_ALL_KIND),
                         ]),
                         resolve_provider: Some(true),
                         ..Default::default()
                     },
                 )),
+                // Document outline from headings
+                document_symbol_provider: Some(OneOf::Left(true)),
+                document_link_provider: Some(DocumentLinkOptions {
+                    resolve_provider: Some(false),
+                    work_done_progress_options: Default::default(),
+                }),
+                // Expand selection: 形態素 → 文節 → 文 → 段落
+                selection_range_provider: Some(SelectionRangeProviderCapability::Simple(true)),
+                // Canonical term completion from notation dictionaries
+                completion_provider: Some(CompletionOptions::default()),
+                // Punctuation conversion while typing (enabled via [format])
+                document_on_type_formatting_provider: Some(DocumentOnTypeFormattingOptions {
+                    first_trigger_character: TRIGGER_CHARACTERS[0].to_string(),
+                    more_trigger_character: Some(TRIGGER_CHARACTERS[1..].iter().map(|c| c.to_string()).collect()),
+                }),
+                // Section statistics
+                code_lens_provider: Some(CodeLensOptions {
+                    resolve_provider: Some(false),
+                }),
+                // Track workspace folders for per-root configuration
+                workspace: Some(WorkspaceServerCapabilities {
+                    workspace_folders: Some(WorkspaceFoldersServerCapabilities {
+                        supported: Some(true),
+                        change_notifications: Some(OneOf::Left(true)),
+                    }),
+                    file_operations: None,
+                }),
+                // Commands for document-wide operations
+                execute_command_provider: Some(ExecuteCommandOptions {
+                    commands: vec![
+                        PROOFREAD_DOCUMENT_COMMAND.to_string(),
+                        SHOW_STATISTICS_COMMAND.to_string(),
+                        REPLACE_TERM_IN_WORKSPACE_COMMAND.to_string(),
+                        IGNORE_WORD_COMMAND.to_string(),
+                        MARK_FALSE_POSITIVE_COMMAND.to_string(),
+                        DISABLE_RULE_COMMAND.to_string(),
+                        CONVERT_STYLE_COMMAND.to_string(),
+                        SHOW_LLM_USAGE_COMMAND.to_string(),
+                        RECORD_FIX_COMMAND.to_string(),
+                    ],
+                    ..Default::default()
+                }),
                 ..Default::default()
             },
             server_info: Some(ServerInfo {
@@ -179,10 +1832,24 @@ impl LanguageServer for MozukuServer {
         self.client
             .log_message(MessageType::INFO, "MoZuku Language Server started")
             .await;
+        if let Some(error) = &self.analyzer_error {
+            self.client
+                .show_message(
+                    MessageType::WARNING,
+                    format!(
+                        "MoZuku: 形態素解析辞書の初期化に失敗しました（{}）。形態素解析に依存しないルールのみで動作します。",
+                        error
+                    ),
+                )
+                .await;
+        }
+        self.register_file_watchers().await;
+        self.index_workspace_terms().await;
     }
 
     async fn shutdown(&self) -> Result<()> {
         tracing::info!("MoZuku server shutting down...");
+        self.telemetry.read().await.flush();
         Ok(())
     }
 
@@ -190,13 +1857,13 @@ impl LanguageServer for MozukuServer {
         let uri = params.text_document.uri;
         let content = params.text_document.text;
         let version = params.text_document.version;
-        let file_type = Self::detect_file_type(&uri);
+        let file_type = Self::detect_document_file_type(&uri, &params.text_document.language_id);
 
         tracing::debug!("Document opened: {} (type: {:?})", uri, file_type);
 
         {
             let mut documents = self.documents.write().await;
-            documents.insert(uri.clone(), DocumentState { content, version, file_type });
+            documents.insert(uri.clone(), DocumentState::new(content, version, file_type));
         }
 
         self.analyze_document(&uri).await;
@@ -205,70 +1872,382 @@ impl LanguageServer for MozukuServer {
     async fn did_change(&self, params: DidChangeTextDocumentParams) {
         let uri = params.text_document.uri;
         let version = params.text_document.version;
-        let file_type = Self::detect_file_type(&uri);
 
-        if let Some(change) = params.content_changes.into_iter().last() {
-            let content = change.text;
+        if params.content_changes.is_empty() {
+            return;
+        }
 
-            {
-                let mut documents = self.documents.write().await;
-                documents.insert(uri.clone(), DocumentState { content, version, file_type });
+        {
+            // Keep the file type detected when the document was opened
+            let mut documents = self.documents.write().await;
+            let doc = documents
+                .entry(uri.clone())
+                .or_insert_with(|| DocumentState::new(String::new(), version, Self::detect_file_type(&uri)));
+            doc.apply_changes(params.content_changes, version);
+        }
+
+        self.analyze_document(&uri).await;
+    }
+
+    async fn did_save(&self, params: DidSaveTextDocumentParams) {
+        let uri = params.text_document.uri;
+        tracing::debug!("Document saved: {}", uri);
+        self.analyze_document(&uri).await;
+        self.run_ai_diagnostics(&uri).await;
+    }
+
+    async fn did_change_watched_files(&self, params: DidChangeWatchedFilesParams) {
+        // Documents only update the term index; the other watched files are
+        // configuration, dictionaries and rules. A dictionary may itself have
+        // a document extension (`words.txt`)
+        let config_paths = self.config_file_paths().await;
+        let (documents, others): (Vec<&FileEvent>, Vec<&FileEvent>) = params.changes.iter().partition(|change| {
+            change
+                .uri
+                .to_file_path()
+                .is_ok_and(|path| is_target_file(&path) && !config_paths.contains(&path))
+        });
+
+        if !documents.is_empty() {
+            tracing::debug!("{} documents changed on disk", documents.len());
+            let uris: Vec<Url> = documents.iter().map(|change| change.uri.clone()).collect();
+            if self.index_files(&uris).await {
+                self.refresh_term_diagnostics(None).await;
+            }
+        }
+
+        for change in &others {
+            tracing::info!("Watched file changed: {} ({:?})", change.uri, change.typ);
+        }
+        if !others.is_empty() {
+            self.reload_config().await;
+        }
+    }
+
+    async fn did_change_workspace_folders(&self, params: DidChangeWorkspaceFoldersParams) {
+        {
+            let mut roots = self.workspace_roots.write().await;
+            let removed: Vec<PathBuf> = params
+                .event
+                .removed
+                .iter()
+                .filter_map(|folder| folder.uri.to_file_path().ok())
+                .collect();
+            roots.retain(|root| !removed.contains(&root.path));
+            roots.extend(
+                params
+                    .event
+                    .added
+                    .iter()
+                    .filter_map(|folder| folder.uri.to_file_path().ok())
+                    .map(WorkspaceRoot::load),
+            );
+        }
+
+        self.register_file_watchers().await;
+        self.index_workspace_terms().await;
+
+        // Documents may now belong to a different root
+        self.analyze_all_documents().await;
+    }
+
+    async fn did_close(&self, params: DidCloseTextDocumentParams) {
+        let uri = params.text_document.uri;
+        tracing::debug!("Document closed: {}", uri);
+
+        self.documents.write().await.remove(&uri);
+        self.document_cache.write().await.remove(&uri);
+        self.diagnostics.write().await.remove(&uri);
+        self.ai_diagnostics.write().await.remove(&uri);
+
+        // Unsaved changes are discarded, so index the file on disk again
+        if self.index_files(std::slice::from_ref(&uri)).await {
+            self.refresh_term_diagnostics(None).await;
+        }
+
+        // AI responses are cached by text; keep only those for text still open
+        let documents = self.documents.read().await;
+        let is_open = |text: &str| documents.values().any(|doc| doc.content.contains(text));
+        self.ai_candidates.write().await.retain(|(text, _), _| is_open(text));
+        self.ai_explanations.write().await.retain(|(text, _), _| is_open(text));
+    }
+
+    async fn hover(&self, params: HoverParams) -> Result<Option<Hover>> {
+        let uri = &params.text_document_position_params.text_document.uri;
+        let position = params.text_document_position_params.position;
+
+        // Explanations of the rules whose diagnostics cover the position
+        let (mut sections, hovered): (Vec<String>, Vec<Diagnostic>) = {
+            let diagnostics = self.diagnostics.read().await;
+            let hovered: Vec<Diagnostic> = diagnostics
+                .get(uri)
+                .into_iter()
+                .flatten()
+                .filter(|diag| range_contains(&diag.range, position))
+                .cloned()
+                .collect();
+            let mut hovered_rules: Vec<&rules::RuleInfo> = Vec::new();
+            for diag in &hovered {
+                if let Some(rule) = rules::rule_for_diagnostic(diag) {
+                    if !hovered_rules.contains(&rule) {
+                        hovered_rules.push(rule);
+                    }
+                }
+            }
+            (hovered_rules.iter().map(|rule| rule.hover_markdown()).collect(), hovered)
+        };
+
+        let config = self.config_for(uri).await;
+        let doc = self.target_document(uri).await;
+
+        // On-demand AI explanation of the first hovered diagnostic, only when enabled
+        if config.llm.explain_on_hover && self.llm_client.read().await.is_available() {
+            if let (Some(doc), Some(diag)) = (doc.as_ref(), hovered.first()) {
+                match self.fetch_ai_explanation(&doc.content, diag).await {
+                    Ok(explanation) => sections.push(format!("🤖 **AIによる解説**\n\n{}", explanation)),
+                    Err(e) => tracing::warn!("LLM explanation failed: {}", e),
+                }
+            }
+        }
+
+        if let Some(doc) = &doc {
+            if let Some(text) = config.checker.analysis_text(&doc.content) {
+                let tokens = self.document_tokens(uri, doc.version, text).await;
+                if let Some(hover_info) = self.analyzer.hover_info(text, &tokens, position) {
+                    sections.push(hover_info);
+                }
             }
+        }
+
+        if sections.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: sections.join("\n\n---\n\n"),
+            }),
+            range: None,
+        }))
+    }
+
+    async fn semantic_tokens_full(
+        &self,
+        params: SemanticTokensParams,
+    ) -> Result<Option<SemanticTokensResult>> {
+        let uri = &params.text_document.uri;
+
+        let config = self.config_for(uri).await;
+        let doc = self.target_document(uri).await;
+        if let Some(doc) = &doc {
+            if let Some(text) = config.checker.analysis_text(&doc.content) {
+                let tokens = self.document_tokens(uri, doc.version, text).await;
+                return Ok(Some(SemanticTokensResult::Tokens(SemanticTokens {
+                    result_id: None,
+                    data: self.analyzer.semantic_tokens(text, &tokens),
+                })));
+            }
+        }
 
-            self.analyze_document(&uri).await;
+        Ok(None)
+    }
+
+    async fn document_symbol(
+        &self,
+        params: DocumentSymbolParams,
+    ) -> Result<Option<DocumentSymbolResponse>> {
+        let doc = match self.target_document(&params.text_document.uri).await {
+            Some(doc) => doc,
+            None => return Ok(None),
+        };
+
+        match self.extractor.extract_headings(&doc.content, doc.file_type) {
+            Ok(headings) => Ok(Some(DocumentSymbolResponse::Nested(build_symbol_tree(
+                &doc.content,
+                &headings,
+                doc.content.len(),
+            )))),
+            Err(e) => {
+                tracing::warn!("Failed to extract headings from {}: {}", params.text_document.uri, e);
+                Ok(None)
+            }
         }
     }
 
-    async fn did_save(&self, params: DidSaveTextDocumentParams) {
-        let uri = params.text_document.uri;
-        tracing::debug!("Document saved: {}", uri);
-        self.analyze_document(&uri).await;
+    async fn document_link(&self, params: DocumentLinkParams) -> Result<Option<Vec<DocumentLink>>> {
+        Ok(self.glossary_links(&params.text_document.uri).await)
+    }
+
+    async fn selection_range(
+        &self,
+        params: SelectionRangeParams,
+    ) -> Result<Option<Vec<SelectionRange>>> {
+        let doc = match self.target_document(&params.text_document.uri).await {
+            Some(doc) => doc,
+            None => return Ok(None),
+        };
+
+        Ok(Some(
+            params
+                .positions
+                .iter()
+                .map(|position| self.selection_range_at(&doc.content, *position))
+                .collect(),
+        ))
+    }
+
+    async fn completion(&self, params: CompletionParams) -> Result<Option<CompletionResponse>> {
+        let uri = &params.text_document_position.text_document.uri;
+        let position = params.text_document_position.position;
+
+        let doc = match self.target_document(uri).await {
+            Some(doc) => doc,
+            None => return Ok(None),
+        };
+
+        let offset = doc.offset_at(position);
+        let line_start = doc.line_start(offset);
+        let word = trailing_word(&doc.content[line_start..offset]);
+        if word.is_empty() {
+            return Ok(None);
+        }
+
+        let range = Range {
+            start: doc.position_at(offset - word.len()),
+            end: position,
+        };
+        let items: Vec<CompletionItem> = self
+            .dictionary_for(uri)
+            .await
+            .complete(word)
+            .into_iter()
+            .map(|candidate| CompletionItem {
+                label: candidate.term.clone(),
+                kind: Some(CompletionItemKind::TEXT),
+                detail: Some(match (candidate.source, candidate.variant) {
+                    (TermSource::Prh, Some(variant)) => format!("正規表記（{} → {}）", variant, candidate.term),
+                    (TermSource::Prh, None) => "正規表記（表記ゆれ辞書）".to_string(),
+                    (TermSource::UserDictionary, _) => "ユーザー辞書".to_string(),
+                }),
+                // Match against the typed text so variants like 鯖 → サーバー are not filtered out
+                filter_text: Some(word.to_string()),
+                text_edit: Some(CompletionTextEdit::Edit(TextEdit {
+                    range,
+                    new_text: candidate.term,
+                })),
+                ..Default::default()
+            })
+            .collect();
+
+        if items.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(CompletionResponse::Array(items)))
     }
 
-    async fn did_close(&self, params: DidCloseTextDocumentParams) {
-        let uri = params.text_document.uri;
-        tracing::debug!("Document closed: {}", uri);
+    async fn on_type_formatting(&self, params: DocumentOnTypeFormattingParams) -> Result<Option<Vec<TextEdit>>> {
+        let uri = &params.text_document_position.text_document.uri;
+        let position = params.text_document_position.position;
+        let Some(ch) = params.ch.chars().next() else {
+            return Ok(None);
+        };
 
-        let mut documents = self.documents.write().await;
-        documents.remove(&uri);
-    }
+        let config = self.config_for(uri).await;
+        if !config.format.enabled {
+            return Ok(None);
+        }
+        let doc = match self.target_document(uri).await {
+            Some(doc) => doc,
+            None => return Ok(None),
+        };
 
-    async fn hover(&self, params: HoverParams) -> Result<Option<Hover>> {
-        let uri = &params.text_document_position_params.text_document.uri;
-        let position = params.text_document_position_params.position;
+        let offset = doc.offset_at(position);
+        let line_start = doc.line_start(offset);
+        let replacement = match format_on_type(&doc.content[line_start..offset], ch, &config.format) {
+            Some(replacement) => replacement,
+            None => return Ok(None),
+        };
 
-        let documents = self.documents.read().await;
-        if let Some(doc) = documents.get(uri) {
-            if let Some(hover_info) = self.analyzer.get_hover_info(&doc.content, position) {
-                return Ok(Some(Hover {
-                    contents: HoverContents::Markup(MarkupContent {
-                        kind: MarkupKind::Markdown,
-                        value: hover_info,
-                    }),
-                    range: None,
-                }));
-            }
+        // Only convert in prose, not in code or code blocks
+        let typed = offset - ch.len_utf8();
+        let in_text = self
+            .extract_spans(uri, &doc.content, doc.file_type)
+            .iter()
+            .any(|span| span.start_byte <= typed && typed < span.end_byte);
+        if !in_text {
+            return Ok(None);
         }
 
-        Ok(None)
+        Ok(Some(vec![TextEdit {
+            range: Range {
+                start: doc.position_at(offset - replacement.len),
+                end: position,
+            },
+            new_text: replacement.new_text,
+        }]))
     }
 
-    async fn semantic_tokens_full(
-        &self,
-        params: SemanticTokensParams,
-    ) -> Result<Option<SemanticTokensResult>> {
-        let uri = &params.text_document.uri;
+    async fn code_lens(&self, params: CodeLensParams) -> Result<Option<Vec<CodeLens>>> {
+        let uri = params.text_document.uri;
 
-        let documents = self.documents.read().await;
-        if let Some(doc) = documents.get(uri) {
-            let tokens = self.analyzer.get_semantic_tokens(&doc.content);
-            return Ok(Some(SemanticTokensResult::Tokens(SemanticTokens {
-                result_id: None,
-                data: tokens,
-            })));
+        let doc = match self.target_document(&uri).await {
+            Some(doc) => doc,
+            None => return Ok(None),
+        };
+        // Score sections on the extracted text, as readability diagnostics do
+        let spans = self.extract_spans(&uri, &doc.content, doc.file_type);
+        let mut span_tokens = Vec::with_capacity(spans.len());
+        for span in &spans {
+            let tokens = match self.cached_tokens(&uri, doc.version, &span.text).await {
+                Some(tokens) => tokens,
+                None => Arc::new(self.analyzer.tokenize(&span.text)),
+            };
+            span_tokens.push(tokens);
         }
+        let scored: Vec<(&TextSpan, &[TokenInfo])> = spans
+            .iter()
+            .zip(&span_tokens)
+            .map(|(span, tokens)| (span, tokens.as_slice()))
+            .collect();
 
-        Ok(None)
+        let diagnostics = self.diagnostics.read().await;
+        let doc_diagnostics = diagnostics.get(&uri).map(|d| d.as_slice()).unwrap_or(&[]);
+
+        let lenses = readability::sections(&self.extractor, &doc.content, doc.file_type)
+            .into_iter()
+            .map(|section| {
+                let stats = TextStatistics::from_text(&doc.content[section.clone()]);
+                let score = readability::score_range(section.clone(), &scored);
+                let range = Range {
+                    start: doc.position_at(section.start),
+                    end: doc.position_at(section.end),
+                };
+                let diagnostic_count = count_diagnostics_in_range(doc_diagnostics, &range);
+
+                CodeLens {
+                    range: Range {
+                        start: range.start,
+                        end: range.start,
+                    },
+                    command: Some(Command {
+                        title: format!(
+                            "{}文字 / {}文 / 平均{:.1}文字 / {}相当 / 指摘{}件",
+                            stats.char_count,
+                            stats.sentence_count,
+                            stats.average_sentence_length,
+                            score.grade_label(),
+                            diagnostic_count
+                        ),
+                        command: SHOW_STATISTICS_COMMAND.to_string(),
+                        arguments: Some(vec![serde_json::json!(uri), serde_json::json!(range)]),
+                    }),
+                    data: None,
+                }
+            })
+            .collect();
+
+        Ok(Some(lenses))
     }
 
     async fn code_action(&self, params: CodeActionParams) -> Result<Option<CodeActionResponse>> {
@@ -289,50 +2268,221 @@ impl LanguageServer for MozukuServer {
             .filter(|d| ranges_overlap(&d.range, &range))
             .collect();
 
-        if diagnostics_in_range.is_empty() {
-            return Ok(None);
-        }
-
+        let llm_client = self.llm_client.read().await.clone();
         let mut actions = Vec::new();
 
-        for diag in diagnostics_in_range {
-            // Get the text at the diagnostic range
-            let text = self.get_text_at_range(&doc.content, &diag.range);
-
-            // Create quick fix action
-            let quick_fix = CodeAction {
-                title: format!("修正: {}", diag.message),
-                kind: Some(CodeActionKind::QUICKFIX),
-                diagnostics: Some(vec![diag.clone()]),
-                is_preferred: Some(true),
+        // Apply the fixes of all diagnostics of the document; offered for a
+        // single fix only when requested, e.g. by fix-all on save
+        let fix_count = self
+            .diagnostics
+            .read()
+            .await
+            .get(uri)
+            .map_or(0, |diagnostics| fix_all(&doc.content, diagnostics).len());
+        let fix_all_requested = params
+            .context
+            .only
+            .iter()
+            .flatten()
+            .any(|kind| FIX_ALL_KIND.starts_with(kind.as_str()));
+        if fix_count > 1 || (fix_count == 1 && fix_all_requested) {
+            actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+                title: format!("{}件の指摘をすべて修正", fix_count),
+                kind: Some(CodeActionKind::new(FIX_ALL_KIND)),
                 data: Some(serde_json::json!({
                     "uri": uri.to_string(),
-                    "range": diag.range,
-                    "text": text,
-                    "message": diag.message,
-                    "type": "quickfix"
+                    "type": "fix_all"
                 })),
                 ..Default::default()
-            };
-            actions.push(CodeActionOrCommand::CodeAction(quick_fix));
+            }));
+        }
 
-            // If LLM is available, add AI suggestion action
-            if self.llm_client.is_available() {
-                let ai_action = CodeAction {
-                    title: format!("🤖 AIによる修正提案: {}", diag.message),
-                    kind: Some(CodeActionKind::REFACTOR_REWRITE),
+        // Fix all issues of the document with one LLM request
+        if llm_client.is_available() {
+            let count = self
+                .diagnostics
+                .read()
+                .await
+                .get(uri)
+                .map_or(0, |diagnostics| batch_fix_items(&doc.content, diagnostics).len());
+            if count > 1 {
+                actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+                    title: format!("🤖 AIで{}件の指摘をまとめて修正", count),
+                    kind: Some(CodeActionKind::new(AI_FIX_ALL_KIND)),
+                    data: Some(serde_json::json!({
+                        "uri": uri.to_string(),
+                        "type": "ai_fix_all"
+                    })),
+                    ..Default::default()
+                }));
+            }
+        }
+
+        if diagnostics_in_range.is_empty() {
+            return Ok((!actions.is_empty()).then_some(actions));
+        }
+
+        for diag in diagnostics_in_range {
+            // Get the text at the diagnostic range
+            let text = self.get_text_at_range(&doc.content, &diag.range);
+
+            // Create quick fix actions from the fix candidates of the rule
+            for (i, fix) in diagnostic_fixes(diag).into_iter().enumerate() {
+                let quick_fix = CodeAction {
+                    title: format!(
+                        "修正: 「{}」→「{}」",
+                        self.get_text_at_range(&doc.content, &fix.range),
+                        fix.new_text
+                    ),
+                    kind: Some(CodeActionKind::QUICKFIX),
                     diagnostics: Some(vec![diag.clone()]),
-                    is_preferred: Some(false),
+                    is_preferred: Some(i == 0),
                     data: Some(serde_json::json!({
                         "uri": uri.to_string(),
-                        "range": diag.range,
-                        "text": text,
-                        "message": diag.message,
-                        "type": "ai_suggestion"
+                        "fix": fix,
+                        "rule": diag.code,
+                        "type": "quickfix"
                     })),
                     ..Default::default()
                 };
-                actions.push(CodeActionOrCommand::CodeAction(ai_action));
+                actions.push(CodeActionOrCommand::CodeAction(quick_fix));
+            }
+
+            // Terms inconsistent across the workspace can be replaced in every file
+            if rules::rule_for_diagnostic(diag).is_some_and(|rule| rule.id == terms::RULE_ID) {
+                if let Some(fix) = diagnostic_fixes(diag).into_iter().next() {
+                    let title = format!("ワークスペース全体で「{}」→「{}」に置換", text, fix.new_text);
+                    actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+                        title: title.clone(),
+                        kind: Some(CodeActionKind::QUICKFIX),
+                        diagnostics: Some(vec![diag.clone()]),
+                        command: Some(Command::new(
+                            title,
+                            REPLACE_TERM_IN_WORKSPACE_COMMAND.to_string(),
+                            Some(vec![serde_json::json!(text), serde_json::json!(fix.new_text)]),
+                        )),
+                        ..Default::default()
+                    }));
+                }
+            }
+
+            // If LLM is available, add one AI suggestion action per candidate
+            if llm_client.is_available() {
+                let cached = self
+                    .ai_candidates
+                    .write()
+                    .await
+                    .get(&(text.clone(), diag.message.clone()))
+                    .cloned();
+
+                match cached {
+                    // Candidates already fetched: offer each with its edit
+                    Some(response) => {
+                        for candidate in &response.candidates {
+                            let ai_action = CodeAction {
+                                title: ai_candidate_title(candidate),
+                                kind: Some(CodeActionKind::REFACTOR_REWRITE),
+                                diagnostics: Some(vec![diag.clone()]),
+                                is_preferred: Some(false),
+                                edit: Some(WorkspaceEdit {
+                                    changes: Some(HashMap::from([(
+                                        uri.clone(),
+                                        vec![TextEdit {
+                                            range: diag.range,
+                                            new_text: candidate.suggestion.clone(),
+                                        }],
+                                    )])),
+                                    ..Default::default()
+                                }),
+                                ..Default::default()
+                            };
+                            actions.push(CodeActionOrCommand::CodeAction(ai_action));
+                        }
+                    }
+                    // Otherwise the candidates are fetched when an action is resolved
+                    None => {
+                        let count = llm_client.candidates();
+                        for i in 0..count {
+                            let ai_action = CodeAction {
+                                title: format!("🤖 AIによる修正提案 ({}/{}): {}", i + 1, count, diag.message),
+                                kind: Some(CodeActionKind::REFACTOR_REWRITE),
+                                diagnostics: Some(vec![diag.clone()]),
+                                is_preferred: Some(false),
+                                data: Some(serde_json::json!({
+                                    "uri": uri.to_string(),
+                                    "range": diag.range,
+                                    "text": text,
+                                    "message": diag.message,
+                                    "candidate": i,
+                                    "type": "ai_suggestion"
+                                })),
+                                ..Default::default()
+                            };
+                            actions.push(CodeActionOrCommand::CodeAction(ai_action));
+                        }
+                    }
+                }
+            }
+
+            // Project configuration actions for MoZuku's own rules
+            if let Some(rule) = rules::rule_for_diagnostic(diag) {
+                if !text.is_empty() {
+                    actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+                        title: format!("「{}」を無視リスト (ignore_words) に追加", text),
+                        kind: Some(CodeActionKind::QUICKFIX),
+                        diagnostics: Some(vec![diag.clone()]),
+                        command: Some(Command {
+                            title: "無視リストに追加".to_string(),
+                            command: IGNORE_WORD_COMMAND.to_string(),
+                            arguments: Some(vec![serde_json::json!(uri), serde_json::json!(text)]),
+                        }),
+                        ..Default::default()
+                    }));
+                    actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+                        title: format!("誤検出として報告（「{}」を「{}」の対象外にする）", text, rule.name),
+                        kind: Some(CodeActionKind::QUICKFIX),
+                        diagnostics: Some(vec![diag.clone()]),
+                        command: Some(Command {
+                            title: "誤検出として報告".to_string(),
+                            command: MARK_FALSE_POSITIVE_COMMAND.to_string(),
+                            arguments: Some(vec![
+                                serde_json::json!(uri),
+                                serde_json::json!(rule.id),
+                                serde_json::json!(text),
+                            ]),
+                        }),
+                        ..Default::default()
+                    }));
+                }
+                actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+                    title: format!("ルール「{}」をプロジェクト設定で無効化", rule.name),
+                    kind: Some(CodeActionKind::QUICKFIX),
+                    diagnostics: Some(vec![diag.clone()]),
+                    command: Some(Command {
+                        title: "ルールを無効化".to_string(),
+                        command: DISABLE_RULE_COMMAND.to_string(),
+                        arguments: Some(vec![serde_json::json!(uri), serde_json::json!(rule.id)]),
+                    }),
+                    ..Default::default()
+                }));
+
+                // Suppress this diagnostic with a comment on the previous line
+                let directive = format!("{} {}", DISABLE_NEXT_LINE, rule.id);
+                if let Some(comment) = directive_comment(doc.file_type, &directive) {
+                    actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+                        title: format!("この行の「{}」を無効化するコメントを挿入", rule.name),
+                        kind: Some(CodeActionKind::QUICKFIX),
+                        diagnostics: Some(vec![diag.clone()]),
+                        edit: Some(WorkspaceEdit {
+                            changes: Some(HashMap::from([(
+                                uri.clone(),
+                                vec![suppression_comment_edit(&doc.content, diag.range.start.line, &comment)],
+                            )])),
+                            ..Default::default()
+                        }),
+                        ..Default::default()
+                    }));
+                }
             }
         }
 
@@ -357,26 +2507,37 @@ impl LanguageServer for MozukuServer {
             Err(_) => return Ok(action),
         };
 
+        if action_type == "ai_fix_all" {
+            action.edit = self.batch_fix_edit(uri).await;
+            return Ok(action);
+        }
+        if action_type == "fix_all" {
+            if let Some((edit, rule_ids)) = self.fix_all_edit(uri).await {
+                action.edit = Some(edit);
+                if self.telemetry.read().await.is_enabled() {
+                    action.command = Some(Command::new(
+                        action.title.clone(),
+                        RECORD_FIX_COMMAND.to_string(),
+                        Some(rule_ids.into_iter().map(serde_json::Value::String).collect()),
+                    ));
+                }
+            }
+            return Ok(action);
+        }
+
         // Generate the edit based on action type
-        let new_text = if action_type == "ai_suggestion" {
-            // Use LLM to generate suggestion
-            match self
-                .llm_client
-                .proofread(ProofreadRequest {
-                    text: text.to_string(),
-                    context: None,
-                    issue: Some(message.to_string()),
-                })
-                .await
-            {
+        let (range, new_text) = if action_type == "ai_suggestion" {
+            // Use LLM to generate suggestions and pick the action's candidate
+            let index = data.get("candidate").and_then(|v| v.as_u64()).unwrap_or(0) as usize;
+            match self.fetch_ai_candidates(text, message).await {
                 Ok(response) => {
+                    let candidate = match response.candidates.get(index) {
+                        Some(candidate) => candidate,
+                        None => return Ok(action),
+                    };
                     // Update action title with explanation
-                    action.title = format!(
-                        "🤖 {} (確信度: {:.0}%)",
-                        response.explanation,
-                        response.confidence * 100.0
-                    );
-                    response.suggestion
+                    action.title = ai_candidate_title(candidate);
+                    (range, candidate.suggestion.clone())
                 }
                 Err(e) => {
                     tracing::warn!("LLM request failed: {}", e);
@@ -384,10 +2545,24 @@ impl LanguageServer for MozukuServer {
                 }
             }
         } else {
-            // For quickfix, extract suggestion from message
-            self.extract_suggestion_from_message(text, message)
+            // For quickfix, use the fix candidate the rule attached to the diagnostic
+            match data.get("fix").and_then(|v| serde_json::from_value::<Fix>(v.clone()).ok()) {
+                Some(fix) => (fix.range, fix.new_text),
+                None => return Ok(action),
+            }
         };
 
+        // Count applied quick fixes in the rule statistics
+        if let Some(rule_id) = data.get("rule").and_then(|v| v.as_str()) {
+            if action_type == "quickfix" && self.telemetry.read().await.is_enabled() {
+                action.command = Some(Command::new(
+                    action.title.clone(),
+                    RECORD_FIX_COMMAND.to_string(),
+                    Some(vec![serde_json::json!(rule_id)]),
+                ));
+            }
+        }
+
         // Create the workspace edit
         let edit = WorkspaceEdit {
             changes: Some(HashMap::from([(
@@ -403,88 +2578,311 @@ impl LanguageServer for MozukuServer {
         action.edit = Some(edit);
         Ok(action)
     }
+
+    async fn execute_command(&self, params: ExecuteCommandParams) -> Result<Option<serde_json::Value>> {
+        match params.command.as_str() {
+            PROOFREAD_DOCUMENT_COMMAND => self.proofread_document(&params.arguments).await,
+            SHOW_STATISTICS_COMMAND => self.show_statistics(&params.arguments).await,
+            REPLACE_TERM_IN_WORKSPACE_COMMAND => self.replace_term_in_workspace(&params.arguments).await,
+            IGNORE_WORD_COMMAND => self.ignore_word(&params.arguments).await,
+            MARK_FALSE_POSITIVE_COMMAND => self.mark_false_positive(&params.arguments).await,
+            DISABLE_RULE_COMMAND => self.disable_rule(&params.arguments).await,
+            CONVERT_STYLE_COMMAND => self.convert_style(&params.arguments).await,
+            SHOW_LLM_USAGE_COMMAND => self.show_llm_usage().await,
+            RECORD_FIX_COMMAND => self.record_fix(&params.arguments).await,
+            _ => Err(Error::invalid_params(format!("Unknown command: {}", params.command))),
+        }
+    }
 }
 
 impl MozukuServer {
-    /// Get text at a specific range
-    fn get_text_at_range(&self, content: &str, range: &Range) -> String {
-        let lines: Vec<&str> = content.lines().collect();
+    /// Build the selection hierarchy 形態素 → 文節 → 文 → 段落 around a position
+    fn selection_range_at(&self, content: &str, position: Position) -> SelectionRange {
+        let offset = position_to_byte_offset(content, position);
 
-        let start_line = range.start.line as usize;
-        let end_line = range.end.line as usize;
+        // Byte ranges from outermost to innermost
+        let mut ranges: Vec<std::ops::Range<usize>> = Vec::new();
 
-        if start_line >= lines.len() {
-            return String::new();
-        }
+        if let Some(paragraph) = split_paragraphs(content)
+            .into_iter()
+            .find(|p| p.start <= offset && offset <= p.end)
+        {
+            ranges.push(paragraph.clone());
 
-        if start_line == end_line {
-            // Single line
-            let line = lines[start_line];
-            let start_char = range.start.character as usize;
-            let end_char = range.end.character as usize;
-            line.chars()
-                .skip(start_char)
-                .take(end_char - start_char)
-                .collect()
-        } else {
-            // Multiple lines
-            let mut result = String::new();
-
-            // First line
-            if let Some(line) = lines.get(start_line) {
-                result.push_str(
-                    &line
-                        .chars()
-                        .skip(range.start.character as usize)
-                        .collect::<String>(),
-                );
-            }
-
-            // Middle lines
-            for i in start_line + 1..end_line {
-                if let Some(line) = lines.get(i) {
-                    result.push('\n');
-                    result.push_str(line);
+            let relative = offset - paragraph.start;
+            if let Some(sentence) = split_sentences(&content[paragraph.clone()])
+                .into_iter()
+                .find(|s| s.start <= relative && relative <= s.end)
+            {
+                let sentence_start = paragraph.start + sentence.start;
+                ranges.push(sentence_start..paragraph.start + sentence.end);
+
+                let tokens = self.analyzer.tokenize(&sentence.text);
+                let relative = offset - sentence_start;
+                if let Some(index) = tokens
+                    .iter()
+                    .position(|t| t.byte_offset <= relative && relative < t.byte_offset + t.surface.len())
+                {
+                    if let Some(bunsetsu) = split_bunsetsu(&tokens).into_iter().find(|b| b.contains(&index)) {
+                        let first = &tokens[bunsetsu.start];
+                        let last = &tokens[bunsetsu.end - 1];
+                        ranges.push(
+                            sentence_start + first.byte_offset
+                                ..sentence_start + last.byte_offset + last.surface.len(),
+                        );
+                    }
+
+                    let token = &tokens[index];
+                    ranges.push(
+                        sentence_start + token.byte_offset
+                            ..sentence_start + token.byte_offset + token.surface.len(),
+                    );
                 }
             }
+        }
 
-            // Last line
-            if let Some(line) = lines.get(end_line) {
-                result.push('\n');
-                result.push_str(
-                    &line
-                        .chars()
-                        .take(range.end.character as usize)
-                        .collect::<String>(),
-                );
+        // Nest the ranges, skipping levels identical to their parent
+        let mut current: Option<SelectionRange> = None;
+        let mut previous: Option<std::ops::Range<usize>> = None;
+        for range in ranges {
+            if previous.as_ref() == Some(&range) {
+                continue;
             }
-
-            result
+            current = Some(SelectionRange {
+                range: Range {
+                    start: byte_offset_to_position(content, range.start),
+                    end: byte_offset_to_position(content, range.end),
+                },
+                parent: current.map(Box::new),
+            });
+            previous = Some(range);
         }
+
+        current.unwrap_or(SelectionRange {
+            range: Range {
+                start: position,
+                end: position,
+            },
+            parent: None,
+        })
     }
 
-    /// Extract suggestion from diagnostic message
-    fn extract_suggestion_from_message(&self, original: &str, message: &str) -> String {
-        // Common patterns: 「X」→「Y」 or X→Y
-        if let Some(arrow_idx) = message.find('→') {
-            let after = &message[arrow_idx + '→'.len_utf8()..];
+    /// Get text at a specific range
+    fn get_text_at_range(&self, content: &str, range: &Range) -> String {
+        let start = position_to_byte_offset(content, range.start);
+        let end = position_to_byte_offset(content, range.end);
+        content[start..end.max(start)].to_string()
+    }
+}
 
-            // Extract text between 「」
-            if let Some(start) = after.find('「') {
-                if let Some(end) = after[start + '「'.len_utf8()..].find('」') {
-                    return after[start + '「'.len_utf8()..start + '「'.len_utf8() + end].to_string();
-                }
-            }
+/// Build a nested symbol tree from headings.
+///
+/// Each section extends from its heading line to the next heading of the same
+/// or higher level (or `end`), and contains the lower-level headings in between.
+fn build_symbol_tree(content: &str, headings: &[Heading], end: usize) -> Vec<DocumentSymbol> {
+    let mut symbols = Vec::new();
+    let mut i = 0;
+
+    while i < headings.len() {
+        let heading = &headings[i];
+        let next = headings[i + 1..]
+            .iter()
+            .position(|h| h.level <= heading.level)
+            .map(|p| i + 1 + p)
+            .unwrap_or(headings.len());
+
+        let section_start = heading.span.start_byte - heading.span.start_col;
+        let section_end = headings
+            .get(next)
+            .map(|h| h.span.start_byte - h.span.start_col)
+            .unwrap_or(end);
+
+        #[allow(deprecated)]
+        symbols.push(DocumentSymbol {
+            name: heading.span.text.clone(),
+            detail: None,
+            kind: SymbolKind::STRING,
+            tags: None,
+            deprecated: None,
+            range: Range {
+                start: byte_offset_to_position(content, section_start),
+                end: byte_offset_to_position(content, section_end),
+            },
+            selection_range: Range {
+                start: byte_offset_to_position(content, heading.span.start_byte),
+                end: byte_offset_to_position(content, heading.span.end_byte),
+            },
+            children: Some(build_symbol_tree(content, &headings[i + 1..next], section_end)),
+        });
+
+        i = next;
+    }
+
+    symbols
+}
+
+/// Sentences for the AI diagnostics pass, with their byte ranges in the document.
+///
+/// To keep the cost down, sentences that are short or already flagged by a
+/// rule are skipped, repeated sentences are checked once, and the number of
+/// sentences is capped.
+fn ai_diagnosis_targets(
+    spans: &[TextSpan],
+    flagged: &[std::ops::Range<usize>],
+    config: &AiDiagnosticsConfig,
+) -> Vec<(std::ops::Range<usize>, String)> {
+    let mut seen = BTreeSet::new();
+    spans
+        .iter()
+        .flat_map(|span| {
+            split_sentences(&span.text).into_iter().map(move |sentence| {
+                (span.start_byte + sentence.start..span.start_byte + sentence.end, sentence.text)
+            })
+        })
+        .filter(|(range, sentence)| {
+            sentence.chars().count() >= config.min_chars
+                && !flagged.iter().any(|f| f.start < range.end && range.start < f.end)
+                && seen.insert(sentence.clone())
+        })
+        .take(config.max_sentences)
+        .collect()
+}
+
+/// Diagnostic for an issue found by the AI diagnostics pass
+fn ai_diagnostic(range: Range, sentence: &str, issue: &SentenceIssue) -> Diagnostic {
+    let fixes: Vec<Fix> = issue
+        .suggestion
+        .iter()
+        .filter(|suggestion| !suggestion.is_empty() && suggestion.as_str() != sentence)
+        .map(|suggestion| Fix::new(range, suggestion.clone()))
+        .collect();
+
+    Diagnostic {
+        range,
+        severity: Some(DiagnosticSeverity::INFORMATION),
+        code: Some(NumberOrString::String(AI_DIAGNOSTIC_CODE.to_string())),
+        source: Some(AI_DIAGNOSTIC_SOURCE.to_string()),
+        message: issue.message.clone(),
+        data: if fixes.is_empty() { None } else { fix_data(fixes) },
+        ..Default::default()
+    }
+}
 
-            // Or just return trimmed text after arrow
-            return after.trim().to_string();
+/// Issues of a document to fix in a batch, with their ranges.
+///
+/// Diagnostics with empty text or overlapping an earlier one are skipped so
+/// that the resulting edits do not overlap.
+fn batch_fix_items(content: &str, diagnostics: &[Diagnostic]) -> Vec<(Range, BatchItem)> {
+    let mut sorted: Vec<&Diagnostic> = diagnostics.iter().collect();
+    sorted.sort_by_key(|d| (d.range.start, d.range.end));
+
+    let mut items: Vec<(Range, BatchItem)> = Vec::new();
+    for diag in sorted {
+        if items.last().is_some_and(|(last, _)| diag.range.start < last.end) {
+            continue;
         }
+        let start = position_to_byte_offset(content, diag.range.start);
+        let end = position_to_byte_offset(content, diag.range.end);
+        if start >= end {
+            continue;
+        }
+        items.push((
+            diag.range,
+            BatchItem {
+                text: content[start..end].to_string(),
+                issue: diag.message.clone(),
+            },
+        ));
+    }
+    items
+}
 
-        // If no suggestion found, return original
-        original.to_string()
+/// Why the LLM cannot be used, for messages to the user
+fn llm_unavailable_reason(llm_client: &LlmClient) -> &'static str {
+    if llm_client.budget_exceeded() {
+        "今月のLLM利用額が上限に達したため"
+    } else if llm_client.budget_unenforceable() {
+        "利用額の上限が設定されているが、モデルの単価が不明なため（llm.usage の input_price / output_price を設定してください）"
+    } else {
+        "LLM連携が設定されていないため"
     }
 }
 
+/// Check whether a position lies inside a range
+fn range_contains(range: &Range, position: Position) -> bool {
+    range.start <= position && position <= range.end
+}
+
+/// Check whether an open document is still at the given version
+fn is_current_version(documents: &HashMap<Url, DocumentState>, uri: &Url, version: i32) -> bool {
+    documents.get(uri).is_some_and(|doc| doc.version == version)
+}
+
+/// Store the diagnostics of a document, returning whether they differ from
+/// the previously stored ones
+fn store_if_changed(store: &mut HashMap<Url, Vec<Diagnostic>>, uri: &Url, diagnostics: &[Diagnostic]) -> bool {
+    if store.get(uri).is_some_and(|previous| previous == diagnostics) {
+        return false;
+    }
+    store.insert(uri.clone(), diagnostics.to_vec());
+    true
+}
+
+/// Percentage of completed work items, for progress reports
+fn progress_percentage(done: usize, total: usize) -> u32 {
+    (done * 100).checked_div(total).unwrap_or(100) as u32
+}
+
+/// Code action title for an AI correction candidate
+fn ai_candidate_title(candidate: &ProofreadCandidate) -> String {
+    format!(
+        "🤖 「{}」: {} (確信度: {:.0}%)",
+        candidate.suggestion,
+        candidate.explanation,
+        candidate.confidence * 100.0
+    )
+}
+
+/// Edit inserting a comment line above a line, with the same indentation
+fn suppression_comment_edit(content: &str, line: u32, comment: &str) -> TextEdit {
+    let indent: String = content
+        .lines()
+        .nth(line as usize)
+        .unwrap_or_default()
+        .chars()
+        .take_while(|c| *c == ' ' || *c == '\t')
+        .collect();
+    let position = Position { line, character: 0 };
+
+    TextEdit {
+        range: Range {
+            start: position,
+            end: position,
+        },
+        new_text: format!("{}{}\n", indent, comment),
+    }
+}
+
+/// Parse `[uri, string]` command arguments
+fn uri_and_string_arguments(arguments: &[serde_json::Value]) -> Option<(Url, String)> {
+    let uri = Url::parse(arguments.first()?.as_str()?).ok()?;
+    let value = arguments.get(1)?.as_str()?;
+    if value.is_empty() {
+        return None;
+    }
+    Some((uri, value.to_string()))
+}
+
+/// Count diagnostics starting inside a range
+fn count_diagnostics_in_range(diagnostics: &[Diagnostic], range: &Range) -> usize {
+    diagnostics.iter().filter(|d| starts_in_range(d, range)).count()
+}
+
+/// Check whether a diagnostic starts inside a range
+fn starts_in_range(diagnostic: &Diagnostic, range: &Range) -> bool {
+    range.start <= diagnostic.range.start && diagnostic.range.start <= range.end
+}
+
 /// Check if two ranges overlap
 fn ranges_overlap(r1: &Range, r2: &Range) -> bool {
     !(r1.end.line < r2.start.line
@@ -503,6 +2901,28 @@ mod tests {
         assert_eq!(MozukuServer::detect_file_type(&uri), FileType::Markdown);
     }
 
+    #[test]
+    fn test_detect_document_file_type_untitled() {
+        let uri = Url::parse("untitled:Untitled-1").unwrap();
+        assert_eq!(MozukuServer::detect_document_file_type(&uri, "markdown"), FileType::Markdown);
+        assert_eq!(MozukuServer::detect_document_file_type(&uri, "unknown"), FileType::PlainText);
+    }
+
+    #[test]
+    fn test_detect_document_file_type_notebook_cell() {
+        let uri = Url::parse("vscode-notebook-cell:/path/to/notebook.ipynb#W0sZmlsZQ%3D%3D").unwrap();
+        assert_eq!(MozukuServer::detect_document_file_type(&uri, "python"), FileType::Python);
+    }
+
+    #[test]
+    fn test_detect_document_file_type_prefers_language_id() {
+        let uri = Url::parse("file:///path/to/notes.txt").unwrap();
+        assert_eq!(MozukuServer::detect_document_file_type(&uri, "markdown"), FileType::Markdown);
+
+        let uri = Url::parse("file:///path/to/main.rs").unwrap();
+        assert_eq!(MozukuServer::detect_document_file_type(&uri, "custom"), FileType::Rust);
+    }
+
     #[test]
     fn test_detect_file_type_rust() {
         let uri = Url::parse("file:///path/to/main.rs").unwrap();
@@ -533,6 +2953,163 @@ mod tests {
         assert_eq!(MozukuServer::detect_file_type(&uri), FileType::PlainText);
     }
 
+    #[test]
+    fn test_range_contains() {
+        let range = Range {
+            start: Position { line: 1, character: 4 },
+            end: Position { line: 2, character: 1 },
+        };
+
+        assert!(range_contains(&range, Position { line: 1, character: 4 }));
+        assert!(range_contains(&range, Position { line: 1, character: 99 }));
+        assert!(range_contains(&range, Position { line: 2, character: 1 }));
+        assert!(!range_contains(&range, Position { line: 1, character: 3 }));
+        assert!(!range_contains(&range, Position { line: 2, character: 2 }));
+    }
+
+    #[test]
+    fn test_is_current_version() {
+        let uri = Url::parse("file:///test.md").unwrap();
+        let mut documents = HashMap::new();
+        assert!(!is_current_version(&documents, &uri, 1));
+
+        documents.insert(uri.clone(), DocumentState::new(String::new(), 2, FileType::Markdown));
+        assert!(is_current_version(&documents, &uri, 2));
+        assert!(!is_current_version(&documents, &uri, 1));
+    }
+
+    #[test]
+    fn test_ai_diagnosis_targets() {
+        let text = "短い文。これは十分に長さのある一つ目の文です。これは十分に長さのある一つ目の文です。ここはルールで指摘済みの長い文です。";
+        let spans = vec![TextSpan::new(text.to_string(), 10, 10 + text.len(), 0, 10, 0, 10 + text.len())];
+        let flagged_start = 10 + text.find("ここは").unwrap();
+        let flagged: Vec<std::ops::Range<usize>> = std::iter::once(flagged_start..flagged_start + 6).collect();
+        let config = AiDiagnosticsConfig {
+            min_chars: 10,
+            ..Default::default()
+        };
+
+        let targets = ai_diagnosis_targets(&spans, &flagged, &config);
+
+        assert_eq!(targets.len(), 1);
+        let (range, sentence) = &targets[0];
+        assert_eq!(sentence, "これは十分に長さのある一つ目の文です。");
+        assert_eq!(&text[range.start - 10..range.end - 10], sentence);
+
+        let capped = ai_diagnosis_targets(&spans, &[], &AiDiagnosticsConfig { max_sentences: 1, ..config });
+        assert_eq!(capped.len(), 1);
+    }
+
+    #[test]
+    fn test_batch_fix_items() {
+        let content = "見れる。私はは行く。\n";
+        let diagnostic = |start: u32, end: u32, message: &str| Diagnostic {
+            range: Range {
+                start: Position { line: 0, character: start },
+                end: Position { line: 0, character: end },
+            },
+            message: message.to_string(),
+            ..Default::default()
+        };
+        let diagnostics = vec![
+            diagnostic(4, 7, "助詞の重複"),
+            diagnostic(0, 3, "ら抜き言葉"),
+            diagnostic(1, 2, "重なる指摘"),
+            diagnostic(8, 8, "空の範囲"),
+        ];
+
+        let items = batch_fix_items(content, &diagnostics);
+        let texts: Vec<(&str, &str)> = items
+            .iter()
+            .map(|(_, item)| (item.text.as_str(), item.issue.as_str()))
+            .collect();
+        assert_eq!(texts, vec![("見れる", "ら抜き言葉"), ("私はは", "助詞の重複")]);
+    }
+
+    #[test]
+    fn test_ai_diagnostic() {
+        let range = Range {
+            start: Position { line: 0, character: 0 },
+            end: Position { line: 0, character: 5 },
+        };
+        let issue = SentenceIssue {
+            index: 0,
+            message: "語順が不自然です".to_string(),
+            suggestion: Some("修正した文。".to_string()),
+        };
+
+        let diagnostic = ai_diagnostic(range, "元の文。", &issue);
+        assert_eq!(diagnostic.source.as_deref(), Some(AI_DIAGNOSTIC_SOURCE));
+        assert_eq!(diagnostic_fixes(&diagnostic), vec![Fix::new(range, "修正した文。".to_string())]);
+
+        let unchanged = SentenceIssue {
+            suggestion: Some("元の文。".to_string()),
+            ..issue
+        };
+        assert!(ai_diagnostic(range, "元の文。", &unchanged).data.is_none());
+    }
+
+    #[test]
+    fn test_store_if_changed() {
+        let uri = Url::parse("file:///test.md").unwrap();
+        let diagnostic = Diagnostic {
+            message: "「な」が重複しています。".to_string(),
+            ..Default::default()
+        };
+        let mut store = HashMap::new();
+
+        assert!(store_if_changed(&mut store, &uri, &[]));
+        assert!(!store_if_changed(&mut store, &uri, &[]));
+        assert!(store_if_changed(&mut store, &uri, std::slice::from_ref(&diagnostic)));
+        assert!(!store_if_changed(&mut store, &uri, std::slice::from_ref(&diagnostic)));
+        assert!(store_if_changed(&mut store, &uri, &[]));
+    }
+
+    #[test]
+    fn test_progress_percentage() {
+        assert_eq!(progress_percentage(0, 4), 0);
+        assert_eq!(progress_percentage(3, 4), 75);
+        assert_eq!(progress_percentage(0, 0), 100);
+    }
+
+    #[test]
+    fn test_suppression_comment_edit() {
+        let edit = suppression_comment_edit("# 見出し\n    見れる\n", 1, "// mozuku-disable-next-line ra-nuki");
+
+        assert_eq!(edit.range.start, Position { line: 1, character: 0 });
+        assert_eq!(edit.range.end, edit.range.start);
+        assert_eq!(edit.new_text, "    // mozuku-disable-next-line ra-nuki\n");
+    }
+
+    #[test]
+    fn test_uri_and_string_arguments() {
+        let args = vec![serde_json::json!("file:///tmp/a.md"), serde_json::json!("見れる")];
+        let (uri, word) = uri_and_string_arguments(&args).unwrap();
+        assert_eq!(uri.as_str(), "file:///tmp/a.md");
+        assert_eq!(word, "見れる");
+
+        assert!(uri_and_string_arguments(&args[..1]).is_none());
+        assert!(uri_and_string_arguments(&[serde_json::json!("file:///tmp/a.md"), serde_json::json!("")]).is_none());
+    }
+
+    #[test]
+    fn test_count_diagnostics_in_range() {
+        let diagnostic = |line: u32| Diagnostic {
+            range: Range {
+                start: Position { line, character: 0 },
+                end: Position { line, character: 2 },
+            },
+            ..Default::default()
+        };
+        let diagnostics = vec![diagnostic(0), diagnostic(2), diagnostic(5)];
+        let range = Range {
+            start: Position { line: 1, character: 0 },
+            end: Position { line: 3, character: 0 },
+        };
+
+        assert_eq!(count_diagnostics_in_range(&diagnostics, &range), 1);
+    }
+
     #[test]
     fn test_ranges_overlap_same_line() {
         let r1 = Range {
@@ -584,4 +3161,30 @@ mod tests {
         };
         assert!(ranges_overlap(&r1, &r2));
     }
+
+    #[test]
+    fn test_build_symbol_tree_nesting() {
+        let content = "# 章A\n## 節1\n## 節2\n# 章B\n";
+        let headings = TextExtractor::new()
+            .extract_headings(content, FileType::Markdown)
+            .unwrap();
+        let symbols = build_symbol_tree(content, &headings, content.len());
+
+        assert_eq!(symbols.len(), 2);
+        assert_eq!(symbols[0].name, "章A");
+        let children = symbols[0].children.as_ref().unwrap();
+        assert_eq!(children.len(), 2);
+        assert_eq!(children[1].name, "節2");
+
+        // Section range ends where the next same-level heading starts
+        assert_eq!(symbols[0].range.end, Position { line: 3, character: 0 });
+        assert_eq!(children[1].range.end, Position { line: 3, character: 0 });
+        assert_eq!(
+            symbols[1].selection_range,
+            Range {
+                start: Position { line: 3, character: 2 },
+                end: Position { line: 3, character: 4 },
+            }
+        );
+    }
 }
@@ -0,0 +1,254 @@
+//! AWS Signature Version 4 request signing
+//!
+//! Used to call Amazon Bedrock without depending on the AWS SDK.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, Result};
+use reqwest::Url;
+use ring::{digest, hmac};
+
+const ALGORITHM: &str = "AWS4-HMAC-SHA256";
+
+/// AWS credentials
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AwsCredentials {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    /// Session token for temporary credentials
+    pub session_token: Option<String>,
+}
+
+impl AwsCredentials {
+    /// Read credentials from the standard AWS environment variables
+    pub fn from_env() -> Result<Self> {
+        let var = |name: &str| std::env::var(name).ok().filter(|v| !v.is_empty());
+        match (var("AWS_ACCESS_KEY_ID"), var("AWS_SECRET_ACCESS_KEY")) {
+            (Some(access_key_id), Some(secret_access_key)) => Ok(Self {
+                access_key_id,
+                secret_access_key,
+                session_token: var("AWS_SESSION_TOKEN"),
+            }),
+            _ => Err(anyhow!("AWS_ACCESS_KEY_ID and AWS_SECRET_ACCESS_KEY must be set")),
+        }
+    }
+}
+
+/// Scope of a signature
+#[derive(Debug, Clone, Copy)]
+pub struct SigningParams<'a> {
+    pub credentials: &'a AwsCredentials,
+    pub region: &'a str,
+    pub service: &'a str,
+    pub time: SystemTime,
+}
+
+/// Sign a request and return the headers to add to it
+/// (`x-amz-date`, `x-amz-security-token` if any, and `authorization`).
+///
+/// Only `host` and the added `x-amz-*` headers are signed.
+pub fn sign(method: &str, url: &Url, body: &[u8], params: &SigningParams) -> Result<Vec<(&'static str, String)>> {
+    let (date, amz_date) = format_time(params.time)?;
+    let host = match (url.host_str(), url.port()) {
+        (Some(host), Some(port)) => format!("{}:{}", host, port),
+        (Some(host), None) => host.to_string(),
+        (None, _) => return Err(anyhow!("URL has no host: {}", url)),
+    };
+
+    let mut headers = vec![("host", host), ("x-amz-date", amz_date.clone())];
+    if let Some(token) = &params.credentials.session_token {
+        headers.push(("x-amz-security-token", token.clone()));
+    }
+    let signed_headers = headers.iter().map(|(name, _)| *name).collect::<Vec<_>>().join(";");
+    let canonical_headers: String = headers
+        .iter()
+        .map(|(name, value)| format!("{}:{}\n", name, value.trim()))
+        .collect();
+
+    let canonical_request = [
+        method,
+        &canonical_uri(url),
+        &canonical_query(url),
+        &canonical_headers,
+        &signed_headers,
+        &hex_sha256(body),
+    ]
+    .join("\n");
+
+    let scope = format!("{}/{}/{}/aws4_request", date, params.region, params.service);
+    let string_to_sign = format!(
+        "{}\n{}\n{}\n{}",
+        ALGORITHM,
+        amz_date,
+        scope,
+        hex_sha256(canonical_request.as_bytes())
+    );
+
+    let secret = format!("AWS4{}", params.credentials.secret_access_key);
+    let key = [date.as_str(), params.region, params.service, "aws4_request"]
+        .iter()
+        .fold(secret.into_bytes(), |key, data| hmac_sha256(&key, data.as_bytes()));
+    let signature = hex(&hmac_sha256(&key, string_to_sign.as_bytes()));
+
+    let authorization = format!(
+        "{} Credential={}/{}, SignedHeaders={}, Signature={}",
+        ALGORITHM, params.credentials.access_key_id, scope, signed_headers, signature
+    );
+
+    // `host` is set by the HTTP client
+    headers.remove(0);
+    headers.push(("authorization", authorization));
+    Ok(headers)
+}
+
+/// Percent-encode everything except unreserved characters, as SigV4 requires
+pub fn uri_encode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// Path with each segment encoded again (non-S3 services sign the encoded path)
+fn canonical_uri(url: &Url) -> String {
+    url.path().split('/').map(uri_encode).collect::<Vec<_>>().join("/")
+}
+
+fn canonical_query(url: &Url) -> String {
+    let mut pairs: Vec<(String, String)> = url
+        .query_pairs()
+        .map(|(name, value)| (uri_encode(&name), uri_encode(&value)))
+        .collect();
+    pairs.sort();
+    pairs
+        .iter()
+        .map(|(name, value)| format!("{}={}", name, value))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let key = hmac::Key::new(hmac::HMAC_SHA256, key);
+    hmac::sign(&key, data).as_ref().to_vec()
+}
+
+fn hex_sha256(data: &[u8]) -> String {
+    hex(digest::digest(&digest::SHA256, data).as_ref())
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Format a time as the SigV4 date (`YYYYMMDD`) and timestamp (`YYYYMMDDTHHMMSSZ`) in UTC
+fn format_time(time: SystemTime) -> Result<(String, String)> {
+    let secs = time.duration_since(UNIX_EPOCH)?.as_secs();
+    let (days, seconds) = (secs / 86_400, secs % 86_400);
+    let (year, month, day) = civil_date(days as i64);
+
+    let date = format!("{:04}{:02}{:02}", year, month, day);
+    let timestamp = format!(
+        "{}T{:02}{:02}{:02}Z",
+        date,
+        seconds / 3_600,
+        seconds % 3_600 / 60,
+        seconds % 60
+    );
+    Ok((date, timestamp))
+}
+
+/// Civil date (year, month, day) from days since 1970-01-01 (Howard Hinnant's algorithm)
+pub(crate) fn civil_date(days: i64) -> (i64, i64, i64) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn example_credentials() -> AwsCredentials {
+        AwsCredentials {
+            access_key_id: "AKIDEXAMPLE".to_string(),
+            secret_access_key: "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY".to_string(),
+            session_token: None,
+        }
+    }
+
+    // 2015-08-30T12:36:00Z
+    fn example_time() -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(1_440_938_160)
+    }
+
+    #[test]
+    fn test_format_time() {
+        let (date, timestamp) = format_time(example_time()).unwrap();
+        assert_eq!(date, "20150830");
+        assert_eq!(timestamp, "20150830T123600Z");
+    }
+
+    #[test]
+    fn test_sign_get_vanilla() {
+        // "get-vanilla" from the AWS Signature Version 4 test suite
+        let credentials = example_credentials();
+        let params = SigningParams {
+            credentials: &credentials,
+            region: "us-east-1",
+            service: "service",
+            time: example_time(),
+        };
+        let url = Url::parse("https://example.amazonaws.com/").unwrap();
+
+        let headers = sign("GET", &url, b"", &params).unwrap();
+
+        assert_eq!(headers[0], ("x-amz-date", "20150830T123600Z".to_string()));
+        assert_eq!(
+            headers[1],
+            (
+                "authorization",
+                "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, \
+                 SignedHeaders=host;x-amz-date, \
+                 Signature=5fa00fa31553b73ebf1942676e86291e8372ff2a2260956d9b8aae1d763fbf31"
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_sign_with_session_token() {
+        let credentials = AwsCredentials {
+            session_token: Some("token".to_string()),
+            ..example_credentials()
+        };
+        let params = SigningParams {
+            credentials: &credentials,
+            region: "us-east-1",
+            service: "bedrock",
+            time: example_time(),
+        };
+        let url = Url::parse("https://bedrock-runtime.us-east-1.amazonaws.com/model/a%3A0/invoke").unwrap();
+
+        let headers = sign("POST", &url, b"{}", &params).unwrap();
+
+        assert_eq!(headers[1], ("x-amz-security-token", "token".to_string()));
+        assert!(headers[2].1.contains("SignedHeaders=host;x-amz-date;x-amz-security-token,"));
+    }
+
+    #[test]
+    fn test_canonical_uri_double_encodes() {
+        let url = Url::parse("https://example.com/model/anthropic.claude-v2%3A1/invoke").unwrap();
+        assert_eq!(canonical_uri(&url), "/model/anthropic.claude-v2%253A1/invoke");
+        assert_eq!(uri_encode("a:b c"), "a%3Ab%20c");
+    }
+}
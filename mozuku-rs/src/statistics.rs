@@ -0,0 +1,269 @@
+//! Text statistics for documents and sections
+//!
+//! Computes character counts and sentence lengths shown in code lenses and
+//! statistics commands.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use tower_lsp::lsp_types::{Diagnostic, NumberOrString};
+
+use crate::analyzer::split_sentences;
+use crate::dictionary::CharKind;
+use crate::rules;
+
+/// Statistics for a piece of text
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextStatistics {
+    /// Number of characters (excluding whitespace)
+    pub char_count: usize,
+    /// Number of sentences
+    pub sentence_count: usize,
+    /// Average sentence length in characters
+    pub average_sentence_length: f64,
+    /// Length of the longest sentence in characters (excluding whitespace)
+    pub max_sentence_length: usize,
+    /// Number of kanji characters
+    pub kanji_count: usize,
+    /// Ratio of kanji to all characters (0.0 - 1.0)
+    pub kanji_ratio: f64,
+}
+
+impl TextStatistics {
+    /// Compute statistics for the given text
+    pub fn from_text(text: &str) -> Self {
+        let char_count = text.chars().filter(|c| !c.is_whitespace()).count();
+        let sentences = split_sentences(text);
+        let sentence_count = sentences.len();
+        let average_sentence_length = if sentence_count > 0 {
+            char_count as f64 / sentence_count as f64
+        } else {
+            0.0
+        };
+        let max_sentence_length = sentences
+            .iter()
+            .map(|s| s.text.chars().filter(|c| !c.is_whitespace()).count())
+            .max()
+            .unwrap_or(0);
+        let kanji_count = text.chars().filter(|&c| CharKind::of(c) == CharKind::Kanji).count();
+        let kanji_ratio = if char_count > 0 {
+            kanji_count as f64 / char_count as f64
+        } else {
+            0.0
+        };
+
+        Self {
+            char_count,
+            sentence_count,
+            average_sentence_length,
+            max_sentence_length,
+            kanji_count,
+            kanji_ratio,
+        }
+    }
+}
+
+/// Readability grade, from A (easy to read) to D (hard to read)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Readability {
+    A,
+    B,
+    C,
+    D,
+}
+
+impl TextStatistics {
+    /// Grade readability from the average sentence length and kanji ratio.
+    ///
+    /// Sentences of up to about 40 characters and a kanji ratio of up to
+    /// about 35% are generally considered easy to read.
+    pub fn readability(&self) -> Readability {
+        let length = self.average_sentence_length;
+        let kanji = self.kanji_ratio;
+        if length <= 40.0 && kanji <= 0.35 {
+            Readability::A
+        } else if length <= 60.0 && kanji <= 0.45 {
+            Readability::B
+        } else if length <= 80.0 && kanji <= 0.55 {
+            Readability::C
+        } else {
+            Readability::D
+        }
+    }
+}
+
+/// Number of diagnostics reported by a rule
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RuleCount {
+    /// Rule ID (diagnostic code)
+    pub rule_id: String,
+    /// Display name of the rule
+    pub name: String,
+    /// Number of diagnostics
+    pub count: usize,
+}
+
+/// Statistics report returned by the statistics command
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatisticsReport {
+    #[serde(flatten)]
+    pub statistics: TextStatistics,
+    /// Total number of diagnostics
+    pub diagnostic_count: usize,
+    /// Diagnostics per rule, most frequent first
+    pub rule_counts: Vec<RuleCount>,
+}
+
+/// Count diagnostics per rule, most frequent first
+pub fn rule_counts(diagnostics: &[&Diagnostic]) -> Vec<RuleCount> {
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+    for diag in diagnostics {
+        let rule_id = match &diag.code {
+            Some(NumberOrString::String(code)) => code.clone(),
+            Some(NumberOrString::Number(code)) => code.to_string(),
+            None => "unknown".to_string(),
+        };
+        *counts.entry(rule_id).or_default() += 1;
+    }
+
+    let mut rule_counts: Vec<RuleCount> = counts
+        .into_iter()
+        .map(|(rule_id, count)| RuleCount {
+            name: rules::find_rule(&rule_id)
+                .map(|rule| rule.name.to_string())
+                .unwrap_or_else(|| rule_id.clone()),
+            rule_id,
+            count,
+        })
+        .collect();
+    rule_counts.sort_by_key(|rule| std::cmp::Reverse(rule.count));
+    rule_counts
+}
+
+impl StatisticsReport {
+    /// Render the report as a Markdown document
+    pub fn to_markdown(&self, title: &str) -> String {
+        let stats = &self.statistics;
+        let mut markdown = format!("# 統計: {}\n\n", title);
+
+        markdown.push_str("| 項目 | 値 |\n|------|-----|\n");
+        markdown.push_str(&format!("| 文字数 | {} |\n", stats.char_count));
+        markdown.push_str(&format!("| 文数 | {} |\n", stats.sentence_count));
+        markdown.push_str(&format!("| 平均文長 | {:.1}文字 |\n", stats.average_sentence_length));
+        markdown.push_str(&format!("| 最長文 | {}文字 |\n", stats.max_sentence_length));
+        markdown.push_str(&format!("| 漢字率 | {:.1}% |\n", stats.kanji_ratio * 100.0));
+        markdown.push_str(&format!("| 指摘数 | {}件 |\n", self.diagnostic_count));
+
+        markdown.push_str("\n## ルール別指摘数\n\n");
+        if self.rule_counts.is_empty() {
+            markdown.push_str("指摘はありません。\n");
+        } else {
+            markdown.push_str("| ルール | ID | 件数 |\n|--------|----|------|\n");
+            for rule in &self.rule_counts {
+                markdown.push_str(&format!("| {} | `{}` | {} |\n", rule.name, rule.rule_id, rule.count));
+            }
+        }
+
+        markdown
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_statistics_from_text() {
+        let stats = TextStatistics::from_text("今日は晴れ。\n明日は 雨です。");
+
+        assert_eq!(stats.char_count, 13);
+        assert_eq!(stats.sentence_count, 2);
+        assert!((stats.average_sentence_length - 6.5).abs() < f64::EPSILON);
+        assert_eq!(stats.max_sentence_length, 7);
+        assert_eq!(stats.kanji_count, 6);
+        assert!((stats.kanji_ratio - 6.0 / 13.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_readability() {
+        let stats = |average_sentence_length, kanji_ratio| TextStatistics {
+            average_sentence_length,
+            kanji_ratio,
+            ..Default::default()
+        };
+
+        assert_eq!(stats(30.0, 0.3).readability(), Readability::A);
+        assert_eq!(stats(30.0, 0.4).readability(), Readability::B);
+        assert_eq!(stats(70.0, 0.2).readability(), Readability::C);
+        assert_eq!(stats(120.0, 0.2).readability(), Readability::D);
+        assert_eq!(TextStatistics::from_text("").readability(), Readability::A);
+    }
+
+    #[test]
+    fn test_report_markdown() {
+        let report = StatisticsReport {
+            statistics: TextStatistics::from_text("今日は晴れ。"),
+            diagnostic_count: 2,
+            rule_counts: vec![RuleCount {
+                rule_id: "ra-nuki".to_string(),
+                name: "ら抜き言葉".to_string(),
+                count: 2,
+            }],
+        };
+        let markdown = report.to_markdown("README.md");
+
+        assert!(markdown.starts_with("# 統計: README.md\n"));
+        assert!(markdown.contains("| 文字数 | 6 |"));
+        assert!(markdown.contains("| 漢字率 | 50.0% |"));
+        assert!(markdown.contains("| ら抜き言葉 | `ra-nuki` | 2 |"));
+    }
+
+    #[test]
+    fn test_report_serializes_flat() {
+        let report = StatisticsReport {
+            statistics: TextStatistics::default(),
+            diagnostic_count: 0,
+            rule_counts: Vec::new(),
+        };
+        let value = serde_json::to_value(&report).unwrap();
+
+        assert_eq!(value["charCount"], 0);
+        assert_eq!(value["diagnosticCount"], 0);
+        assert!(report.to_markdown("x").contains("指摘はありません"));
+    }
+
+    #[test]
+    fn test_rule_counts() {
+        let diagnostic = |code: &str| Diagnostic {
+            code: Some(NumberOrString::String(code.to_string())),
+            ..Default::default()
+        };
+        let diagnostics = [
+            diagnostic("i-nuki"),
+            diagnostic("ra-nuki"),
+            diagnostic("ra-nuki"),
+            diagnostic("file-too-large"),
+        ];
+        let refs: Vec<&Diagnostic> = diagnostics.iter().collect();
+
+        let counts = rule_counts(&refs);
+        assert_eq!(counts[0].rule_id, "ra-nuki");
+        assert_eq!(counts[0].name, "ら抜き言葉");
+        assert_eq!(counts[0].count, 2);
+        assert_eq!(counts.len(), 3);
+        assert_eq!(
+            counts.iter().find(|c| c.rule_id == "file-too-large").unwrap().name,
+            "file-too-large"
+        );
+    }
+
+    #[test]
+    fn test_statistics_empty() {
+        let stats = TextStatistics::from_text("");
+
+        assert_eq!(stats, TextStatistics::default());
+    }
+}
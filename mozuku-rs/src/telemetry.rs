@@ -0,0 +1,373 @@
+//! Opt-in local rule statistics
+//!
+//! With `[telemetry] enabled = true`, the server counts the diagnostics each
+//! rule reports, the fixes applied from them and the time spent in each rule.
+//! Only rule IDs, counts and durations are recorded, never text or file
+//! names, and nothing is sent anywhere. Counts are added up in memory and
+//! merged into a JSON file in the user data directory at most once a minute
+//! and on shutdown. The file is shared by all server instances and read by
+//! `mozuku-rs stats` and the `mozuku/stats` request.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use tower_lsp::lsp_types::{Diagnostic, NumberOrString};
+
+use crate::checker::CheckProfile;
+use crate::config::TelemetryConfig;
+use crate::rules;
+use crate::sigv4::civil_date;
+
+/// Pending counts are written to the statistics file at most this often
+const FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Statistics of a rule
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct RuleStats {
+    /// Diagnostics reported
+    pub diagnostics: u64,
+    /// Fixes applied from its diagnostics
+    pub fixes: u64,
+    /// Time spent checking, in milliseconds
+    pub time_ms: f64,
+}
+
+impl RuleStats {
+    fn add(&mut self, other: &RuleStats) {
+        self.diagnostics += other.diagnostics;
+        self.fixes += other.fixes;
+        self.time_ms += other.time_ms;
+    }
+
+    /// Share of the reported diagnostics that were fixed with a code action
+    pub fn fix_rate(&self) -> Option<f64> {
+        (self.diagnostics > 0).then(|| self.fixes as f64 / self.diagnostics as f64)
+    }
+}
+
+/// Accumulated statistics, as stored in the statistics file and returned by
+/// `mozuku/stats`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Stats {
+    /// Day the first statistics were recorded (`YYYY-MM-DD`, UTC)
+    pub since: Option<String>,
+    /// Number of document analyses
+    pub analyses: u64,
+    /// Time spent analyzing documents, in milliseconds
+    pub analysis_ms: f64,
+    /// Statistics by rule ID
+    pub rules: BTreeMap<String, RuleStats>,
+}
+
+impl Stats {
+    /// Whether nothing has been recorded
+    pub fn is_empty(&self) -> bool {
+        self.analyses == 0 && self.rules.is_empty()
+    }
+
+    fn add(&mut self, other: &Stats) {
+        self.since = match (self.since.take(), other.since.clone()) {
+            (Some(since), Some(other)) => Some(since.min(other)),
+            (since, other) => since.or(other),
+        };
+        self.analyses += other.analyses;
+        self.analysis_ms += other.analysis_ms;
+        for (rule_id, stats) in &other.rules {
+            self.rules.entry(rule_id.clone()).or_default().add(stats);
+        }
+    }
+
+    /// Human-readable summary shown to the user, with the rules reporting
+    /// the most diagnostics first
+    pub fn message(&self) -> String {
+        if self.is_empty() {
+            return "利用統計はまだ記録されていません".to_string();
+        }
+        let average = self.analysis_ms / self.analyses.max(1) as f64;
+        let mut message = format!(
+            "{} からの利用統計: 解析 {} 回（平均 {:.1} ms）",
+            self.since.as_deref().unwrap_or("-"),
+            self.analyses,
+            average
+        );
+
+        let mut sorted: Vec<(&String, &RuleStats)> = self.rules.iter().collect();
+        sorted.sort_by_key(|(_, stats)| std::cmp::Reverse(stats.diagnostics));
+        for (rule_id, stats) in sorted {
+            let name = rules::find_rule(rule_id).map_or(rule_id.as_str(), |rule| rule.name);
+            let fix_rate = stats
+                .fix_rate()
+                .map_or("-".to_string(), |rate| format!("{:.0}%", rate * 100.0));
+            message.push_str(&format!(
+                "\n- {}（{}）: 指摘 {} 件、修正 {} 件（{}）、処理時間 {:.1} ms",
+                name, rule_id, stats.diagnostics, stats.fixes, fix_rate, stats.time_ms
+            ));
+        }
+        message
+    }
+}
+
+/// Counts not written to the statistics file yet
+#[derive(Debug)]
+struct Pending {
+    stats: Stats,
+    last_flush: Instant,
+}
+
+/// Records rule statistics when enabled by the configuration
+#[derive(Debug)]
+pub struct Telemetry {
+    enabled: bool,
+    /// Statistics file; `None` keeps the statistics in memory only
+    path: Option<PathBuf>,
+    pending: Mutex<Pending>,
+}
+
+impl Telemetry {
+    /// Create a recorder from the configuration
+    pub fn new(config: &TelemetryConfig) -> Self {
+        Self {
+            enabled: config.enabled,
+            path: config.enabled.then(|| config.stats_file()).flatten(),
+            pending: Mutex::new(Pending {
+                stats: Stats::default(),
+                last_flush: Instant::now(),
+            }),
+        }
+    }
+
+    /// Whether statistics are recorded
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Record an analysis of a document.
+    ///
+    /// A diagnostic counts once when it appears: only the increase in the
+    /// number of diagnostics of each rule since the previous analysis of the
+    /// document is added, so re-analyzing a document while it is edited does
+    /// not count the same diagnostics again.
+    pub fn record_analysis(
+        &self,
+        previous: &[Diagnostic],
+        diagnostics: &[Diagnostic],
+        profile: &CheckProfile,
+        elapsed: Duration,
+    ) {
+        if !self.enabled {
+            return;
+        }
+        let before = rule_counts(previous);
+
+        let mut pending = self.lock();
+        let stats = &mut pending.stats;
+        stats.since.get_or_insert_with(today);
+        stats.analyses += 1;
+        stats.analysis_ms += millis(elapsed);
+        for (rule_id, count) in rule_counts(diagnostics) {
+            let new = count.saturating_sub(before.get(&rule_id).copied().unwrap_or(0));
+            if new > 0 {
+                stats.rules.entry(rule_id).or_default().diagnostics += new;
+            }
+        }
+        for (rule_id, time) in &profile.rules {
+            stats.rules.entry(rule_id.to_string()).or_default().time_ms += millis(*time);
+        }
+        self.flush_if_due(&mut pending);
+    }
+
+    /// Record a fix applied from a diagnostic of a rule
+    pub fn record_fix(&self, rule_id: &str) {
+        if !self.enabled {
+            return;
+        }
+        let mut pending = self.lock();
+        pending.stats.since.get_or_insert_with(today);
+        pending.stats.rules.entry(rule_id.to_string()).or_default().fixes += 1;
+        self.flush_if_due(&mut pending);
+    }
+
+    /// Statistics recorded so far, including those not written to the file yet
+    pub fn stats(&self) -> Stats {
+        let pending = self.lock();
+        let mut stats = self.path.as_deref().map(load).unwrap_or_default();
+        stats.add(&pending.stats);
+        stats
+    }
+
+    /// Write the pending counts to the statistics file
+    pub fn flush(&self) {
+        let mut pending = self.lock();
+        self.write(&mut pending);
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Pending> {
+        self.pending.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn flush_if_due(&self, pending: &mut Pending) {
+        if pending.last_flush.elapsed() >= FLUSH_INTERVAL {
+            self.write(pending);
+        }
+    }
+
+    fn write(&self, pending: &mut Pending) {
+        pending.last_flush = Instant::now();
+        let Some(path) = &self.path else {
+            return;
+        };
+        if pending.stats.is_empty() {
+            return;
+        }
+
+        // Other server instances may have written their counts since
+        let mut stats = load(path);
+        stats.add(&pending.stats);
+        let written = path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|_| std::fs::write(path, serde_json::to_string_pretty(&stats).unwrap_or_default()));
+        match written {
+            Ok(()) => pending.stats = Stats::default(),
+            Err(e) => tracing::warn!("Failed to write rule statistics {}: {}", path.display(), e),
+        }
+    }
+}
+
+/// Statistics stored in a file; a missing or unreadable file counts as empty
+pub fn load(path: &Path) -> Stats {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Number of diagnostics by rule ID
+fn rule_counts(diagnostics: &[Diagnostic]) -> BTreeMap<String, u64> {
+    let mut counts = BTreeMap::new();
+    for diag in diagnostics {
+        let rule_id = match &diag.code {
+            Some(NumberOrString::String(code)) => code.clone(),
+            Some(NumberOrString::Number(code)) => code.to_string(),
+            None => continue,
+        };
+        *counts.entry(rule_id).or_default() += 1;
+    }
+    counts
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// Current day (`YYYY-MM-DD`, UTC)
+fn today() -> String {
+    let days = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() / 86_400)
+        .unwrap_or(0);
+    let (year, month, day) = civil_date(days as i64);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn in_memory() -> Telemetry {
+        let config = TelemetryConfig {
+            enabled: true,
+            file: None,
+        };
+        Telemetry {
+            path: None,
+            ..Telemetry::new(&config)
+        }
+    }
+
+    fn diagnostic(code: &str) -> Diagnostic {
+        Diagnostic {
+            code: Some(NumberOrString::String(code.to_string())),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_record_analysis_counts_new_diagnostics() {
+        let telemetry = in_memory();
+        let mut profile = CheckProfile::default();
+        profile.rules.insert("ra-nuki", Duration::from_millis(2));
+
+        let first = vec![
+            diagnostic("ra-nuki"),
+            diagnostic("ra-nuki"),
+            diagnostic("double-particle"),
+        ];
+        telemetry.record_analysis(&[], &first, &profile, Duration::from_millis(10));
+        // The same diagnostics again, plus one more
+        let second = vec![diagnostic("ra-nuki"), diagnostic("ra-nuki"), diagnostic("ra-nuki")];
+        telemetry.record_analysis(&first, &second, &profile, Duration::from_millis(20));
+
+        let stats = telemetry.stats();
+        assert_eq!(stats.analyses, 2);
+        assert!((stats.analysis_ms - 30.0).abs() < 1e-9);
+        assert_eq!(stats.rules["ra-nuki"].diagnostics, 3);
+        assert!((stats.rules["ra-nuki"].time_ms - 4.0).abs() < 1e-9);
+        assert_eq!(stats.rules["double-particle"].diagnostics, 1);
+        assert_eq!(stats.since, Some(today()));
+    }
+
+    #[test]
+    fn test_record_fix() {
+        let telemetry = in_memory();
+        let diagnostics = vec![diagnostic("ra-nuki"), diagnostic("ra-nuki")];
+        telemetry.record_analysis(&[], &diagnostics, &CheckProfile::default(), Duration::ZERO);
+        telemetry.record_fix("ra-nuki");
+
+        let stats = telemetry.stats();
+        assert_eq!(stats.rules["ra-nuki"].fixes, 1);
+        assert_eq!(stats.rules["ra-nuki"].fix_rate(), Some(0.5));
+        assert!(stats.message().contains("（ra-nuki）: 指摘 2 件、修正 1 件（50%）"));
+    }
+
+    #[test]
+    fn test_disabled() {
+        let telemetry = Telemetry::new(&TelemetryConfig::default());
+        telemetry.record_analysis(&[], &[diagnostic("ra-nuki")], &CheckProfile::default(), Duration::ZERO);
+        telemetry.record_fix("ra-nuki");
+
+        assert!(!telemetry.is_enabled());
+        assert!(telemetry.stats().is_empty());
+        assert_eq!(telemetry.stats().message(), "利用統計はまだ記録されていません");
+    }
+
+    #[test]
+    fn test_flushed_stats_are_shared() {
+        let dir = std::env::temp_dir().join(format!("mozuku-telemetry-test-{}", std::process::id()));
+        let config = TelemetryConfig {
+            enabled: true,
+            file: Some(dir.join("stats.json")),
+        };
+
+        let telemetry = Telemetry::new(&config);
+        telemetry.record_analysis(&[], &[diagnostic("ra-nuki")], &CheckProfile::default(), Duration::ZERO);
+        telemetry.flush();
+        let other = Telemetry::new(&config);
+        other.record_fix("ra-nuki");
+        other.flush();
+
+        let stats = load(&dir.join("stats.json"));
+        assert_eq!(stats.analyses, 1);
+        assert_eq!(stats.rules["ra-nuki"].diagnostics, 1);
+        assert_eq!(stats.rules["ra-nuki"].fixes, 1);
+        // Flushed counts are not added twice
+        assert_eq!(telemetry.stats(), stats);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,271 @@
+//! Transports for serving the language server
+//!
+//! Besides stdio, the server can listen on TCP (LSP base protocol framing) or
+//! WebSocket (one JSON-RPC message per text frame, as used by browser-based
+//! editors).
+//!
+//! Neither listener authenticates clients, and a connected client can read
+//! and edit files in its workspace. WebSocket handshakes carrying an `Origin`
+//! header (sent by every browser) are rejected unless the origin is allowed,
+//! so that web pages the user opens cannot connect to a local server.
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use anyhow::Result;
+use futures::{SinkExt, StreamExt};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_tungstenite::tungstenite::handshake::server::{ErrorResponse, Request, Response};
+use tokio_tungstenite::tungstenite::http::{header, StatusCode};
+use tokio_tungstenite::tungstenite::Message;
+use tower_lsp::lsp_types::request::Request;
+use tower_lsp::{LspService, Server};
+
+use crate::protocol::RuleStatistics;
+use crate::server::MozukuServer;
+
+/// Buffer size of the in-memory pipe between a WebSocket and the server
+const WEBSOCKET_PIPE_SIZE: usize = 64 * 1024;
+
+/// How the server communicates with clients
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Transport {
+    /// Standard input/output (single client)
+    Stdio,
+    /// TCP listener; each connection gets its own server instance
+    Tcp(SocketAddr),
+    /// WebSocket listener; each connection gets its own server instance
+    WebSocket {
+        addr: SocketAddr,
+        /// Origins of browser pages allowed to connect
+        allowed_origins: Vec<String>,
+    },
+}
+
+/// Serve the language server over the given transport
+pub async fn serve(transport: Transport) -> Result<()> {
+    match transport {
+        Transport::Stdio => {
+            serve_connection(tokio::io::stdin(), tokio::io::stdout()).await;
+            Ok(())
+        }
+        Transport::Tcp(addr) => {
+            let listener = TcpListener::bind(addr).await?;
+            tracing::info!("Listening for TCP connections on {}", listener.local_addr()?);
+            warn_if_not_loopback(addr);
+            loop {
+                let (stream, peer) = listener.accept().await?;
+                tracing::info!("Client connected: {}", peer);
+                tokio::spawn(async move {
+                    let (read, write) = stream.into_split();
+                    serve_connection(read, write).await;
+                    tracing::info!("Client disconnected: {}", peer);
+                });
+            }
+        }
+        Transport::WebSocket { addr, allowed_origins } => {
+            let listener = TcpListener::bind(addr).await?;
+            tracing::info!("Listening for WebSocket connections on {}", listener.local_addr()?);
+            warn_if_not_loopback(addr);
+            let allowed_origins = Arc::new(allowed_origins);
+            loop {
+                let (stream, peer) = listener.accept().await?;
+                tracing::info!("Client connected: {}", peer);
+                let allowed_origins = Arc::clone(&allowed_origins);
+                tokio::spawn(async move {
+                    if let Err(e) = serve_websocket(stream, &allowed_origins).await {
+                        tracing::warn!("WebSocket connection {} failed: {}", peer, e);
+                    }
+                    tracing::info!("Client disconnected: {}", peer);
+                });
+            }
+        }
+    }
+}
+
+/// Warn when a listener is reachable from other machines, as clients are not
+/// authenticated
+fn warn_if_not_loopback(addr: SocketAddr) {
+    if !addr.ip().is_loopback() {
+        tracing::warn!(
+            "{} is not a loopback address: any client that can reach it can read and edit files in the workspace",
+            addr
+        );
+    }
+}
+
+/// Whether a WebSocket handshake with the given `Origin` header is accepted.
+///
+/// Editors and other non-browser clients send no `Origin`; browser pages
+/// always do, and are only accepted from the allowed origins.
+fn is_origin_allowed(origin: Option<&str>, allowed_origins: &[String]) -> bool {
+    match origin {
+        None => true,
+        Some(origin) => allowed_origins
+            .iter()
+            .any(|allowed| allowed.trim_end_matches('/').eq_ignore_ascii_case(origin)),
+    }
+}
+
+/// Run a server instance over a byte stream using LSP base protocol framing
+async fn serve_connection<I, O>(input: I, output: O)
+where
+    I: AsyncRead + Unpin,
+    O: AsyncWrite,
+{
+    let (service, socket) = LspService::build(MozukuServer::new)
+        .custom_method(RuleStatistics::METHOD, MozukuServer::rule_statistics)
+        .finish();
+    Server::new(input, output, socket).serve(service).await;
+}
+
+/// Run a server instance over a WebSocket connection.
+///
+/// Incoming text frames are framed with `Content-Length` headers and piped to
+/// the server; the server's framed output is unwrapped into text frames.
+/// Handshakes from origins that are not allowed are refused with 403.
+async fn serve_websocket(stream: TcpStream, allowed_origins: &[String]) -> Result<()> {
+    let check_origin = |request: &Request, response: Response| -> Result<Response, ErrorResponse> {
+        let origin = request
+            .headers()
+            .get(header::ORIGIN)
+            .map(|value| value.to_str().unwrap_or_default());
+        if is_origin_allowed(origin, allowed_origins) {
+            return Ok(response);
+        }
+        tracing::warn!(
+            "Rejected WebSocket connection from origin {:?}",
+            origin.unwrap_or_default()
+        );
+        let mut response = ErrorResponse::new(Some("origin not allowed".to_string()));
+        *response.status_mut() = StatusCode::FORBIDDEN;
+        Err(response)
+    };
+    let websocket = tokio_tungstenite::accept_hdr_async(stream, check_origin).await?;
+    let (mut ws_sink, mut ws_stream) = websocket.split();
+
+    let (pipe, server_end) = tokio::io::duplex(WEBSOCKET_PIPE_SIZE);
+    let (pipe_read, mut pipe_write) = tokio::io::split(pipe);
+    let (server_read, server_write) = tokio::io::split(server_end);
+
+    let server = tokio::spawn(serve_connection(server_read, server_write));
+
+    // Server output → WebSocket
+    let outgoing = tokio::spawn(async move {
+        let mut reader = BufReader::new(pipe_read);
+        while let Ok(Some(body)) = read_message(&mut reader).await {
+            if ws_sink.send(Message::Text(body)).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    // WebSocket → server input
+    while let Some(message) = ws_stream.next().await {
+        let body = match message? {
+            Message::Text(text) => text,
+            Message::Binary(bytes) => String::from_utf8(bytes)?,
+            Message::Close(_) => break,
+            _ => continue,
+        };
+        pipe_write.write_all(frame_message(&body).as_bytes()).await?;
+    }
+
+    // Closing the pipe ends the server's input and lets it shut down
+    drop(pipe_write);
+    server.abort();
+    outgoing.abort();
+    Ok(())
+}
+
+/// Frame a JSON-RPC message body with a `Content-Length` header
+fn frame_message(body: &str) -> String {
+    format!("Content-Length: {}\r\n\r\n{}", body.len(), body)
+}
+
+/// Read one `Content-Length` framed message body, or `None` at end of stream
+async fn read_message<R>(reader: &mut R) -> std::io::Result<Option<String>>
+where
+    R: AsyncBufRead + Unpin,
+{
+    let mut content_length = None;
+    let mut line = String::new();
+
+    loop {
+        line.clear();
+        if reader.read_line(&mut line).await? == 0 {
+            return Ok(None);
+        }
+
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                content_length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+
+    let length = content_length.ok_or_else(|| {
+        std::io::Error::new(std::io::ErrorKind::InvalidData, "missing Content-Length header")
+    })?;
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).await?;
+
+    String::from_utf8(body)
+        .map(Some)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_origin_allowed() {
+        let allowed = vec!["http://localhost:3000/".to_string()];
+
+        assert!(is_origin_allowed(None, &allowed));
+        assert!(is_origin_allowed(Some("http://localhost:3000"), &allowed));
+        assert!(!is_origin_allowed(Some("https://example.com"), &allowed));
+        assert!(!is_origin_allowed(Some("null"), &allowed));
+        assert!(!is_origin_allowed(Some("http://localhost:3000"), &[]));
+    }
+
+    #[test]
+    fn test_frame_message() {
+        let body = r#"{"jsonrpc":"2.0","method":"日本語"}"#;
+        let framed = frame_message(body);
+
+        assert!(framed.starts_with(&format!("Content-Length: {}\r\n\r\n", body.len())));
+        assert!(framed.ends_with(body));
+    }
+
+    #[tokio::test]
+    async fn test_read_message_with_extra_headers() {
+        let input = "Content-Length: 8\r\nContent-Type: application/vscode-jsonrpc; charset=utf-8\r\n\r\n{\"id\":1}";
+        let mut reader = BufReader::new(input.as_bytes());
+
+        assert_eq!(read_message(&mut reader).await.unwrap().as_deref(), Some("{\"id\":1}"));
+        assert_eq!(read_message(&mut reader).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_read_message_multiple() {
+        let input = format!("{}{}", frame_message("あ"), frame_message("{}"));
+        let mut reader = BufReader::new(input.as_bytes());
+
+        assert_eq!(read_message(&mut reader).await.unwrap().as_deref(), Some("あ"));
+        assert_eq!(read_message(&mut reader).await.unwrap().as_deref(), Some("{}"));
+    }
+
+    #[tokio::test]
+    async fn test_read_message_missing_length() {
+        let mut reader = BufReader::new("Content-Type: text\r\n\r\n{}".as_bytes());
+
+        assert!(read_message(&mut reader).await.is_err());
+    }
+}
@@ -0,0 +1,462 @@
+//! LLM usage and cost tracking
+//!
+//! Token counts of each request are added up per month and model, with the
+//! estimated cost, in a JSON file in the user data directory. The file is
+//! shared by all server instances, so totals are re-read before each update,
+//! which holds a lock file and replaces the file atomically.
+//! A monthly budget stops the AI features once the estimated cost reaches it,
+//! and refuses models whose price is unknown, since their cost cannot count.
+
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::LlmUsageConfig;
+use crate::sigv4::civil_date;
+
+/// Prices in USD per million tokens (input, output) of well-known models,
+/// matched by model name prefix
+const MODEL_PRICES: &[(&str, f64, f64)] = &[
+    ("claude-3-5-haiku", 0.8, 4.0),
+    ("claude-3-5-sonnet", 3.0, 15.0),
+    ("claude-3-haiku", 0.25, 1.25),
+    ("claude-3-opus", 15.0, 75.0),
+    ("anthropic.claude-3-5-sonnet", 3.0, 15.0),
+    ("anthropic.claude-3-haiku", 0.25, 1.25),
+    ("gpt-4o-mini", 0.15, 0.6),
+    ("gpt-4o", 2.5, 10.0),
+    ("gpt-4.1-mini", 0.4, 1.6),
+    ("gpt-4.1", 2.0, 8.0),
+];
+
+/// Token counts of a request
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TokenUsage {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+}
+
+impl TokenUsage {
+    /// Combine counts reported in parts (e.g. by different stream events)
+    pub fn merge(&mut self, other: TokenUsage) {
+        self.input_tokens = self.input_tokens.max(other.input_tokens);
+        self.output_tokens = self.output_tokens.max(other.output_tokens);
+    }
+}
+
+/// Accumulated usage
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageTotals {
+    pub requests: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    /// Estimated cost in USD
+    pub cost: f64,
+}
+
+impl UsageTotals {
+    fn add(&mut self, usage: TokenUsage, cost: f64) {
+        self.requests += 1;
+        self.input_tokens += usage.input_tokens;
+        self.output_tokens += usage.output_tokens;
+        self.cost += cost;
+    }
+}
+
+/// Usage of a month, as reported by `mozuku.showLlmUsage`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageSummary {
+    /// Month (`YYYY-MM`, UTC)
+    pub month: String,
+    #[serde(flatten)]
+    pub totals: UsageTotals,
+    /// Monthly budget in USD
+    pub budget: Option<f64>,
+    /// Usage per model
+    pub models: BTreeMap<String, UsageTotals>,
+}
+
+impl UsageSummary {
+    /// Human-readable summary shown to the user
+    pub fn message(&self) -> String {
+        let mut message = format!(
+            "{} のLLM使用量: {}リクエスト、入力 {} / 出力 {} トークン、推定 ${:.4}",
+            self.month, self.totals.requests, self.totals.input_tokens, self.totals.output_tokens, self.totals.cost
+        );
+        if let Some(budget) = self.budget {
+            message.push_str(&format!("（上限 ${:.2}）", budget));
+        }
+        for (model, totals) in &self.models {
+            message.push_str(&format!(
+                "\n- {}: {}リクエスト、${:.4}",
+                model, totals.requests, totals.cost
+            ));
+        }
+        message
+    }
+}
+
+/// Usage per month and model
+type UsageLog = BTreeMap<String, BTreeMap<String, UsageTotals>>;
+
+/// Records LLM usage and enforces the monthly budget
+#[derive(Debug)]
+pub struct UsageTracker {
+    enabled: bool,
+    /// Usage file; `None` keeps the usage in memory only
+    path: Option<PathBuf>,
+    budget: Option<f64>,
+    input_price: Option<f64>,
+    output_price: Option<f64>,
+    log: Mutex<UsageLog>,
+}
+
+impl UsageTracker {
+    /// Create a tracker from the configuration
+    pub fn new(config: &LlmUsageConfig) -> Self {
+        let path = (config.enabled && config.persist).then(|| config.usage_file()).flatten();
+        let log = path.as_deref().map(load_or_warn).unwrap_or_default();
+        Self {
+            enabled: config.enabled,
+            path,
+            budget: config.monthly_budget,
+            input_price: config.input_price,
+            output_price: config.output_price,
+            log: Mutex::new(log),
+        }
+    }
+
+    /// Check whether the cost of requests to a model can be estimated:
+    /// local models, models in the price table, or both prices configured
+    pub fn is_priced(&self, provider: &str, model: &str) -> bool {
+        provider == "ollama"
+            || model_price(model).is_some()
+            || (self.input_price.is_some() && self.output_price.is_some())
+    }
+
+    /// Check whether a monthly budget is set but requests to a model would not count towards it
+    pub fn budget_unenforceable(&self, provider: &str, model: &str) -> bool {
+        self.enabled && self.budget.is_some() && !self.is_priced(provider, model)
+    }
+
+    /// Estimated cost in USD of a request.
+    /// Local models are free; unknown models count as free unless prices are configured.
+    pub fn cost(&self, provider: &str, model: &str, usage: TokenUsage) -> f64 {
+        let (default_input, default_output) = if provider == "ollama" {
+            (0.0, 0.0)
+        } else {
+            model_price(model).unwrap_or((0.0, 0.0))
+        };
+        let input_price = self.input_price.unwrap_or(default_input);
+        let output_price = self.output_price.unwrap_or(default_output);
+        (usage.input_tokens as f64 * input_price + usage.output_tokens as f64 * output_price) / 1_000_000.0
+    }
+
+    /// Record a request made now
+    pub fn record(&self, provider: &str, model: &str, usage: TokenUsage) {
+        if !self.enabled {
+            return;
+        }
+        let cost = self.cost(provider, model, usage);
+        let month = month_of(SystemTime::now());
+
+        let add = |log: &mut UsageLog| {
+            log.entry(month.clone())
+                .or_default()
+                .entry(model.to_string())
+                .or_default()
+                .add(usage, cost);
+        };
+
+        let mut log = self.log.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(path) = &self.path else {
+            add(&mut log);
+            return;
+        };
+        // Other server instances may have recorded usage since the last update
+        match update(path, add) {
+            Ok(updated) => *log = updated,
+            Err(e) => {
+                // Keep counting in memory so that the budget still applies here
+                tracing::warn!("Failed to record LLM usage in {}: {}", path.display(), e);
+                add(&mut log);
+            }
+        }
+    }
+
+    /// Usage of the current month
+    pub fn summary(&self) -> UsageSummary {
+        self.summary_of(&month_of(SystemTime::now()))
+    }
+
+    fn summary_of(&self, month: &str) -> UsageSummary {
+        let mut log = self.log.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(path) = &self.path {
+            reload(&mut log, path);
+        }
+        let models = log.get(month).cloned().unwrap_or_default();
+
+        let mut totals = UsageTotals::default();
+        for model in models.values() {
+            totals.requests += model.requests;
+            totals.input_tokens += model.input_tokens;
+            totals.output_tokens += model.output_tokens;
+            totals.cost += model.cost;
+        }
+
+        UsageSummary {
+            month: month.to_string(),
+            totals,
+            budget: self.budget,
+            models,
+        }
+    }
+
+    /// Check whether the estimated cost of the current month has reached the budget
+    pub fn budget_exceeded(&self) -> bool {
+        self.budget_exceeded_in(&month_of(SystemTime::now()))
+    }
+
+    fn budget_exceeded_in(&self, month: &str) -> bool {
+        if !self.enabled {
+            return false;
+        }
+        let Some(budget) = self.budget else {
+            return false;
+        };
+        let mut log = self.log.lock().unwrap_or_else(|e| e.into_inner());
+        // Spending of other server and CLI instances counts too
+        if let Some(path) = &self.path {
+            reload(&mut log, path);
+        }
+        let cost: f64 = log.get(month).into_iter().flat_map(|m| m.values()).map(|t| t.cost).sum();
+        cost >= budget
+    }
+}
+
+/// Price in USD per million tokens (input, output) of a well-known model
+pub fn model_price(model: &str) -> Option<(f64, f64)> {
+    MODEL_PRICES
+        .iter()
+        .find(|(prefix, _, _)| model.starts_with(prefix))
+        .map(|&(_, input, output)| (input, output))
+}
+
+/// Month of a time (`YYYY-MM`, UTC)
+fn month_of(time: SystemTime) -> String {
+    let days = time.duration_since(UNIX_EPOCH).map(|d| d.as_secs() / 86_400).unwrap_or(0);
+    let (year, month, _) = civil_date(days as i64);
+    format!("{:04}-{:02}", year, month)
+}
+
+/// Read the usage file; a missing file is empty
+fn load(path: &Path) -> io::Result<UsageLog> {
+    match std::fs::read_to_string(path) {
+        Ok(content) => Ok(serde_json::from_str(&content)?),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(UsageLog::default()),
+        Err(e) => Err(e),
+    }
+}
+
+fn load_or_warn(path: &Path) -> UsageLog {
+    load(path).unwrap_or_else(|e| {
+        tracing::warn!("Failed to read LLM usage {}: {}", path.display(), e);
+        UsageLog::default()
+    })
+}
+
+/// Replace the usage in memory with the file, keeping it if the file cannot be read
+fn reload(log: &mut UsageLog, path: &Path) {
+    match load(path) {
+        Ok(loaded) => *log = loaded,
+        Err(e) => tracing::warn!("Failed to read LLM usage {}: {}", path.display(), e),
+    }
+}
+
+/// Path next to the usage file with a suffix appended (`usage.json.lock`)
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// Update the usage file while holding a lock shared with other instances,
+/// and return the updated usage.
+///
+/// The new contents are written to a temporary file renamed over the usage
+/// file, so readers never see a partial file. A file that cannot be read or
+/// parsed is left untouched rather than replaced with the new usage alone.
+fn update(path: &Path, change: impl FnOnce(&mut UsageLog)) -> io::Result<UsageLog> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let lock = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(sibling(path, ".lock"))?;
+    lock.lock()?;
+
+    let mut log = load(path)?;
+    change(&mut log);
+
+    let temp = sibling(path, ".tmp");
+    let mut file = File::create(&temp)?;
+    serde_json::to_writer_pretty(&mut file, &log)?;
+    file.sync_all()?;
+    std::fs::rename(&temp, path)?;
+    Ok(log)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn memory_only(monthly_budget: Option<f64>) -> UsageTracker {
+        UsageTracker::new(&LlmUsageConfig {
+            monthly_budget,
+            persist: false,
+            ..Default::default()
+        })
+    }
+
+    fn usage(input_tokens: u64, output_tokens: u64) -> TokenUsage {
+        TokenUsage {
+            input_tokens,
+            output_tokens,
+        }
+    }
+
+    #[test]
+    fn test_month_of() {
+        // 2024-02-29T23:59:59Z
+        assert_eq!(month_of(UNIX_EPOCH + Duration::from_secs(1_709_251_199)), "2024-02");
+        assert_eq!(month_of(UNIX_EPOCH + Duration::from_secs(1_709_251_200)), "2024-03");
+    }
+
+    #[test]
+    fn test_cost() {
+        let tracker = memory_only(None);
+
+        let cost = tracker.cost("claude", "claude-3-5-sonnet-20241022", usage(1_000_000, 100_000));
+        assert!((cost - 4.5).abs() < 1e-9);
+        assert_eq!(tracker.cost("ollama", "llama3.1", usage(1_000_000, 1_000_000)), 0.0);
+        assert_eq!(tracker.cost("openai-compatible", "unknown", usage(1_000, 1_000)), 0.0);
+
+        let priced = UsageTracker::new(&LlmUsageConfig {
+            input_price: Some(1.0),
+            output_price: Some(2.0),
+            persist: false,
+            ..Default::default()
+        });
+        assert!((priced.cost("openai-compatible", "unknown", usage(500_000, 500_000)) - 1.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_record_and_summary() {
+        let tracker = memory_only(Some(10.0));
+        tracker.record("claude", "claude-3-5-sonnet-20241022", usage(1_000, 200));
+        tracker.record("claude", "claude-3-5-sonnet-20241022", usage(2_000, 300));
+        tracker.record("openai", "gpt-4o-mini", usage(1_000, 100));
+
+        let summary = tracker.summary();
+        assert_eq!(summary.totals.requests, 3);
+        assert_eq!(summary.totals.input_tokens, 4_000);
+        assert_eq!(summary.totals.output_tokens, 600);
+        assert_eq!(summary.models["claude-3-5-sonnet-20241022"].requests, 2);
+        assert_eq!(summary.budget, Some(10.0));
+        assert!(summary.message().contains("3リクエスト"));
+        assert!(!tracker.budget_exceeded());
+    }
+
+    #[test]
+    fn test_budget_exceeded() {
+        let tracker = memory_only(Some(1.0));
+        tracker.record("claude", "claude-3-opus-20240229", usage(0, 20_000));
+        assert!(tracker.budget_exceeded());
+
+        // Usage of other months does not count
+        assert!(!tracker.budget_exceeded_in("1999-01"));
+        assert!(!memory_only(None).budget_exceeded());
+    }
+
+    #[test]
+    fn test_budget_unenforceable() {
+        let tracker = memory_only(Some(10.0));
+        assert!(tracker.budget_unenforceable("openai-compatible", "unknown"));
+        assert!(!tracker.budget_unenforceable("openai", "gpt-4o-mini"));
+        assert!(!tracker.budget_unenforceable("ollama", "llama3.1"));
+        assert!(!memory_only(None).budget_unenforceable("openai-compatible", "unknown"));
+
+        let priced = UsageTracker::new(&LlmUsageConfig {
+            monthly_budget: Some(10.0),
+            input_price: Some(1.0),
+            output_price: Some(2.0),
+            persist: false,
+            ..Default::default()
+        });
+        assert!(!priced.budget_unenforceable("openai-compatible", "unknown"));
+    }
+
+    #[test]
+    fn test_token_usage_merge() {
+        let mut total = usage(120, 0);
+        total.merge(usage(0, 45));
+        assert_eq!(total, usage(120, 45));
+    }
+
+    #[test]
+    fn test_persisted_usage_is_shared() {
+        let dir = std::env::temp_dir().join(format!("mozuku-usage-test-{}", std::process::id()));
+        let config = LlmUsageConfig {
+            file: Some(dir.join("usage.json")),
+            ..Default::default()
+        };
+
+        UsageTracker::new(&config).record("openai", "gpt-4o", usage(1_000, 1_000));
+        let other = UsageTracker::new(&config);
+        other.record("openai", "gpt-4o", usage(1_000, 1_000));
+        assert_eq!(other.summary().totals.requests, 2);
+
+        // The budget check sees usage recorded by other instances after creation
+        let limited = UsageTracker::new(&LlmUsageConfig {
+            monthly_budget: Some(1.0),
+            ..config.clone()
+        });
+        assert!(!limited.budget_exceeded());
+        UsageTracker::new(&config).record("openai", "gpt-4o", usage(0, 2_000_000));
+        assert!(limited.budget_exceeded());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_unreadable_usage_is_kept() {
+        let dir = std::env::temp_dir().join(format!("mozuku-usage-corrupt-test-{}", std::process::id()));
+        let path = dir.join("usage.json");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(&path, "{\"2024-01\": {\"gpt-4o\"").unwrap();
+        let tracker = UsageTracker::new(&LlmUsageConfig {
+            file: Some(path.clone()),
+            monthly_budget: Some(1.0),
+            ..Default::default()
+        });
+
+        // The partial file is not replaced, and the usage counts in memory
+        tracker.record("openai", "gpt-4o", usage(0, 200_000));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "{\"2024-01\": {\"gpt-4o\"");
+        assert_eq!(tracker.summary().totals.requests, 1);
+        assert!(tracker.budget_exceeded());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}